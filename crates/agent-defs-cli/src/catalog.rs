@@ -0,0 +1,479 @@
+//! Locating the local cache and wiring configured sources to their stores.
+//!
+//! Shared by the CLI binary and anything else (such as the FFI bindings)
+//! that wants to read or refresh the same catalog.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use agent_defs::demo::DemoProvider;
+use agent_defs::{
+    CategoryMap, CompositeSource, DefinitionId, FileCheck, FilteredProvider, PathFilter, Policy,
+    RawDefinitionFile, Source, SyncError, SyncProvider, UsageKind,
+};
+use agent_defs_github::{ArchiveCache, ArchiveMode, HttpOptions, TarballLimits};
+use agent_defs_store::DefinitionStore;
+use anyhow::{Context, Result};
+
+use crate::config::{self, AppConfig, SourceEntry, SourceType};
+use crate::presets;
+use crate::signing::{SIGNATURE_SUFFIX, SignaturePolicy};
+use crate::sources::{
+    AwesomeSubagentsProvider, CatalogSnapshotProvider, ClaudeCodeTemplatesProvider,
+    GenericGistProvider, GenericRepoProvider, PresetProvider,
+};
+
+/// A paired store and provider for a single configured source.
+pub type SourcePair = (Arc<DefinitionStore>, Box<dyn SyncProvider>);
+
+pub fn cache_dir() -> Result<PathBuf> {
+    let base = dirs::cache_dir().context("could not determine cache directory")?;
+    let dir = base.join("agent-def-fetcher");
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create cache directory: {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// The catalog database: `definitions.db` in the cache directory, or
+/// `demo.db` beside it after [`use_demo_catalog`].
+pub fn db_path() -> Result<PathBuf> {
+    let name = if DEMO.load(Ordering::Relaxed) {
+        "demo.db"
+    } else {
+        "definitions.db"
+    };
+    Ok(cache_dir()?.join(name))
+}
+
+pub fn build_store(
+    db_path: &Path,
+    label: &str,
+    max_file_bytes: u64,
+    categories: CategoryMap,
+) -> Result<DefinitionStore> {
+    DefinitionStore::open(db_path, label)
+        .map(|store| {
+            store
+                .with_max_file_bytes(max_file_bytes)
+                .with_categories(categories)
+        })
+        .map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+/// The token used for GitHub API calls, read from `GITHUB_TOKEN`.
+pub fn github_token() -> Option<String> {
+    std::env::var("GITHUB_TOKEN").ok()
+}
+
+static INSECURE_TLS: AtomicBool = AtomicBool::new(false);
+
+/// Stop verifying TLS certificates in every HTTP client built from here
+/// on, for `--insecure`.
+pub fn allow_insecure_tls() {
+    INSECURE_TLS.store(true, Ordering::Relaxed);
+}
+
+static METERED: AtomicBool = AtomicBool::new(false);
+
+/// Treat the connection as metered in every catalog built from here on,
+/// for `--metered`; see [`ProviderOptions::metered`].
+pub fn use_metered_connection() {
+    METERED.store(true, Ordering::Relaxed);
+}
+
+static REPLAY: AtomicBool = AtomicBool::new(false);
+
+/// Sync repositories from the archives kept by earlier syncs in every
+/// catalog built from here on, for `sync --offline-replay`; see
+/// [`ProviderOptions::archives`].
+pub fn replay_cached_archives() {
+    REPLAY.store(true, Ordering::Relaxed);
+}
+
+static DEMO: AtomicBool = AtomicBool::new(false);
+
+/// Build every catalog from here on out of the built-in demo source alone,
+/// cached in its own database, for `--demo`.
+pub fn use_demo_catalog() {
+    DEMO.store(true, Ordering::Relaxed);
+}
+
+/// An HTTP client for `options`, with certificate checks off if
+/// [`allow_insecure_tls`] has been called.
+pub fn http_client(options: &HttpOptions) -> Result<reqwest::Client> {
+    let mut options = options.clone();
+    if INSECURE_TLS.load(Ordering::Relaxed) {
+        options.insecure = true;
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| {
+            eprintln!(
+                "WARNING: --insecure turns off TLS certificate checks. Anyone between you \
+                 and GitHub can read your token and change what gets synced."
+            );
+        });
+    }
+    options.client().map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+/// How the providers [`build_provider_for`] makes reach GitHub.
+pub struct ProviderOptions<'a> {
+    /// Size caps on repository downloads.
+    pub limits: TarballLimits,
+    /// The client every request goes through.
+    pub http: &'a reqwest::Client,
+    /// The GitHub API root, when it isn't api.github.com.
+    pub api_url: Option<&'a str>,
+    /// Bytes per second to hold repository downloads to.
+    pub rate_limit: Option<u64>,
+    /// The source's cache, on a metered connection. Repositories then sync
+    /// file by file, downloading only what the cache lacks, and sources
+    /// that can only be synced as a whole archive don't sync at all.
+    pub metered: Option<Arc<DefinitionStore>>,
+    /// Whether repository archives are kept after download or replayed
+    /// instead of downloaded. Replaying, nothing syncs over the network:
+    /// gists, which have no archive, don't sync at all.
+    pub archives: ArchiveMode,
+    /// The `[signing]` policy, when there is one. Snapshots are checked as
+    /// a whole by their provider; other sources file by file by their
+    /// store, which needs every signature file in the source.
+    pub signatures: Option<Arc<SignaturePolicy>>,
+}
+
+/// The provider for `entry`, narrowed to its `include`/`exclude` globs when
+/// it has any. Under a signing policy, the signature beside each file the
+/// globs allow is passed on with it.
+pub fn build_provider_for(entry: &SourceEntry, options: ProviderOptions) -> Box<dyn SyncProvider> {
+    let filter = PathFilter::new(entry.include.clone(), entry.exclude.clone());
+    let signed = options.signatures.is_some();
+    let provider = build_unfiltered_provider(entry, options, &filter);
+    if filter.is_empty() {
+        provider
+    } else if signed {
+        Box::new(FilteredProvider::new(provider, filter).with_companions(SIGNATURE_SUFFIX))
+    } else {
+        Box::new(FilteredProvider::new(provider, filter))
+    }
+}
+
+fn build_unfiltered_provider(
+    entry: &SourceEntry,
+    options: ProviderOptions,
+    filter: &PathFilter,
+) -> Box<dyn SyncProvider> {
+    let token = entry.token.clone().or_else(github_token);
+    let api_url = options.api_url.unwrap_or(agent_defs_github::GITHUB_API_URL);
+    let http = options.http.clone();
+    let replay = matches!(options.archives, ArchiveMode::Replay(_));
+    let unavailable = |reason| {
+        Box::new(Unavailable {
+            label: entry.label.clone(),
+            reason,
+        })
+    };
+    match entry.source_type {
+        SourceType::GitHubGist { .. } if replay => {
+            return unavailable("skipped by --offline-replay: gists have no cached archive");
+        }
+        SourceType::CatalogSnapshot { .. } if replay => {
+            return unavailable("skipped by --offline-replay: snapshots have no cached archive");
+        }
+        SourceType::GitHubRepo { .. }
+            if options.metered.is_some() && options.signatures.is_some() && !replay =>
+        {
+            return unavailable(
+                "skipped on a metered connection: checking signatures needs the whole \
+                 repository",
+            );
+        }
+        SourceType::ClaudeCodeTemplates
+        | SourceType::AwesomeSubagents
+        | SourceType::Preset { .. }
+            if options.metered.is_some() && !replay =>
+        {
+            return unavailable(
+                "skipped on a metered connection: this source only syncs by downloading \
+                 its whole repository",
+            );
+        }
+        _ => {}
+    }
+    match &entry.source_type {
+        SourceType::ClaudeCodeTemplates => Box::new(
+            ClaudeCodeTemplatesProvider::new(&entry.label, token)
+                .with_limits(options.limits)
+                .with_archives(options.archives)
+                .with_rate_limit(options.rate_limit)
+                .with_http_client(http)
+                .with_api_base_url(api_url),
+        ),
+        SourceType::AwesomeSubagents => Box::new(
+            AwesomeSubagentsProvider::new(&entry.label, token)
+                .with_limits(options.limits)
+                .with_archives(options.archives)
+                .with_rate_limit(options.rate_limit)
+                .with_http_client(http)
+                .with_api_base_url(api_url),
+        ),
+        SourceType::GitHubRepo {
+            owner,
+            repo,
+            branch,
+            branches,
+            base_path,
+        } => {
+            let provider = GenericRepoProvider::new(
+                owner,
+                repo,
+                branch,
+                base_path,
+                token,
+                &entry.label,
+            )
+            .with_limits(options.limits)
+            .with_rate_limit(options.rate_limit)
+            .with_http_client(http)
+            .with_api_base_url(api_url);
+            // Replaying reads the kept archive whatever the connection.
+            let provider = match options.metered {
+                Some(cache) if !replay => provider.metered(cache, filter.clone()),
+                _ => provider.with_archives(options.archives),
+            };
+            if branches.is_empty() {
+                Box::new(provider)
+            } else {
+                Box::new(provider.with_branches(branches.clone()))
+            }
+        }
+        SourceType::GitHubGist {
+            gist_id,
+            path_prefix,
+            dir_separator,
+        } => {
+            let provider =
+                GenericGistProvider::new(gist_id, path_prefix.as_deref(), token, &entry.label)
+                    .with_http_client(http)
+                    .with_api_base_url(api_url);
+            match dir_separator {
+                Some(separator) => Box::new(provider.with_dir_separator(separator)),
+                None => Box::new(provider),
+            }
+        }
+        SourceType::Preset { name } => match presets::find(name) {
+            Some(preset) => Box::new(
+                PresetProvider::new(preset, &entry.label, token)
+                    .with_limits(options.limits)
+                    .with_archives(options.archives)
+                    .with_rate_limit(options.rate_limit)
+                    .with_http_client(http)
+                    .with_api_base_url(api_url),
+            ),
+            None => unavailable("no preset by that name; `sources discover` lists the known ones"),
+        },
+        SourceType::Demo => Box::new(DemoProvider::new(&entry.label)),
+        SourceType::CatalogSnapshot { url, source } => {
+            // Only the source's own token: GITHUB_TOKEN isn't for this host.
+            let bearer = entry.token.clone();
+            let provider =
+                CatalogSnapshotProvider::new(url, source.as_deref(), bearer, &entry.label)
+                    .with_http_client(http);
+            match options.signatures {
+                Some(policy) => Box::new(provider.with_signatures(policy)),
+                None => Box::new(provider),
+            }
+        }
+    }
+}
+
+/// Stands in for a source that can't sync the way this run asks, such as
+/// one that only syncs by downloading its whole repository on a metered
+/// connection. Its syncs fail with `reason`, so the cache keeps what it has
+/// and the source shows as needing another try.
+struct Unavailable {
+    label: String,
+    reason: &'static str,
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for Unavailable {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        Err(SyncError::Other(self.reason.to_owned()))
+    }
+}
+
+/// Where a source's definitions come from, for showing their provenance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// The repository or gist, such as `github.com/acme/agents`.
+    pub location: String,
+    /// The branch synced, for a repository on a single branch. Multi-branch
+    /// sources carry the branch in each ID instead.
+    pub branch: Option<String>,
+}
+
+/// The repository or gist `entry` syncs from.
+pub fn origin(entry: &SourceEntry) -> Origin {
+    let repo = |owner: &str, repo: &str, branch: Option<&str>| Origin {
+        location: format!("github.com/{owner}/{repo}"),
+        branch: branch.map(str::to_owned),
+    };
+    match &entry.source_type {
+        SourceType::ClaudeCodeTemplates => repo(
+            ClaudeCodeTemplatesProvider::OWNER,
+            ClaudeCodeTemplatesProvider::REPO,
+            Some(ClaudeCodeTemplatesProvider::BRANCH),
+        ),
+        SourceType::AwesomeSubagents => repo(
+            AwesomeSubagentsProvider::OWNER,
+            AwesomeSubagentsProvider::REPO,
+            Some(AwesomeSubagentsProvider::BRANCH),
+        ),
+        SourceType::GitHubRepo {
+            owner,
+            repo: name,
+            branch,
+            branches,
+            ..
+        } => repo(owner, name, branches.is_empty().then_some(branch.as_str())),
+        SourceType::GitHubGist { gist_id, .. } => Origin {
+            location: format!("gist.github.com/{gist_id}"),
+            branch: None,
+        },
+        SourceType::CatalogSnapshot { url, .. } => Origin {
+            location: url.clone(),
+            branch: None,
+        },
+        SourceType::Preset { name } => match presets::find(name) {
+            Some(preset) => repo(&preset.owner, &preset.repo, Some(&preset.branch)),
+            None => Origin {
+                location: format!("preset:{name}"),
+                branch: None,
+            },
+        },
+        SourceType::Demo => Origin {
+            location: "demo".into(),
+            branch: None,
+        },
+    }
+}
+
+/// Open a store for every enabled source in `app_config` that `policy`
+/// allows, all backed by the database at `db_path`. Sources the policy
+/// refuses are left out with a warning.
+pub fn build_pairs(
+    app_config: &AppConfig,
+    policy: &Policy,
+    db_path: &Path,
+) -> Result<Vec<SourcePair>> {
+    let mut pairs = Vec::new();
+    let http = http_client(&app_config.network)?;
+    let categories = CategoryMap::new(app_config.categories.clone());
+    let signatures = SignaturePolicy::from_config(&app_config.signing)
+        .map_err(|e| anyhow::anyhow!("{e}"))?
+        .map(Arc::new);
+    // Archives live next to the database they fill.
+    let archive_dir = db_path.with_file_name("archives");
+    let archives = ArchiveCache::new(archive_dir, app_config.archive_cache_bytes);
+    let archives = if REPLAY.load(Ordering::Relaxed) {
+        ArchiveMode::Replay(archives)
+    } else if app_config.archive_cache_bytes > 0 {
+        ArchiveMode::Keep(archives)
+    } else {
+        ArchiveMode::Discard
+    };
+
+    for entry in &app_config.sources {
+        if !entry.enabled {
+            continue;
+        }
+        if let Err(e) = policy.check_source(&origin(entry).location) {
+            eprintln!("warning: skipping source {}: {e}", entry.label);
+            continue;
+        }
+        let mut store = build_store(
+            db_path,
+            &entry.label,
+            app_config.max_definition_bytes,
+            categories.clone(),
+        )?;
+        if let Some(fingerprint) = app_config.signing.fingerprint() {
+            store = store.with_signing_policy(fingerprint);
+        }
+        if let Some(policy) = &signatures
+            && !matches!(entry.source_type, SourceType::CatalogSnapshot { .. })
+        {
+            store = store.with_file_check(Arc::clone(policy) as Arc<dyn FileCheck>);
+        }
+        let store = Arc::new(store);
+        let metered = METERED.load(Ordering::Relaxed);
+        let provider = build_provider_for(
+            entry,
+            ProviderOptions {
+                limits: app_config.archive_limits,
+                http: &http,
+                api_url: app_config.network.api_url.as_deref(),
+                rate_limit: app_config.network.max_bytes_per_sec(),
+                metered: metered.then(|| Arc::clone(&store)),
+                archives: archives.clone(),
+                signatures: signatures.clone(),
+            },
+        );
+        pairs.push((store, provider));
+    }
+
+    Ok(pairs)
+}
+
+/// [`build_pairs`] for the user's config file, the org policy, and the
+/// default cache location.
+pub fn build_from_config() -> Result<Vec<SourcePair>> {
+    let mut app_config = config::load_config();
+    if DEMO.load(Ordering::Relaxed) {
+        app_config.sources = vec![config::demo_source()];
+    }
+    build_pairs(&app_config, &Policy::load()?, &db_path()?)
+}
+
+/// The stores of `pairs`, for commands that take any [`Source`].
+pub fn stores_as_sources(pairs: &[SourcePair]) -> Vec<&DefinitionStore> {
+    pairs.iter().map(|(store, _)| store.as_ref()).collect()
+}
+
+/// The revision each source's latest sync got, by label, for sources that
+/// report one.
+pub fn revisions(pairs: &[SourcePair]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .filter_map(|(store, _)| Some((store.label().to_owned(), store.revision().ok()??)))
+        .collect()
+}
+
+pub fn composite_source(pairs: &[SourcePair]) -> Arc<dyn Source> {
+    Arc::new(CompositeSource::from_sources(
+        pairs.iter().map(|(store, _)| Arc::clone(store)),
+    ))
+}
+
+/// Count a view or install of `id` against the first source that has it,
+/// the same source `show` and `install` would pick. Counting is best effort:
+/// a database error here shouldn't fail the command that triggered it.
+pub fn record_usage<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    id: &DefinitionId,
+    source_filter: Option<&str>,
+    kind: UsageKind,
+) {
+    for store in stores {
+        if source_filter.is_some_and(|filter| filter != store.label()) {
+            continue;
+        }
+        if store.record_usage(id, kind).unwrap_or(false) {
+            return;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+
+use agent_defs_store::schema;
+use anyhow::Result;
+
+/// Apply pending schema migrations to the cache database, optionally
+/// snapshotting it next to the original first.
+pub fn migrate(db_path: &Path, backup: bool) -> Result<()> {
+    if !db_path.exists() {
+        println!("No cache at {}; nothing to migrate.", db_path.display());
+        return Ok(());
+    }
+
+    let backup_path = backup.then(|| backup_path_for(db_path));
+    let report = schema::migrate_file(db_path, backup_path.as_deref())
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    if let Some(path) = &report.backup {
+        println!("Backed up cache to {}", path.display());
+    }
+    if report.migrated() {
+        println!("Migrated cache schema v{} -> v{}.", report.from, report.to);
+    } else {
+        println!("Cache schema is up to date (v{}).", report.to);
+    }
+
+    Ok(())
+}
+
+/// `definitions.db` -> `definitions.db.<epoch-secs>.bak`
+fn backup_path_for(db_path: &Path) -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{stamp}.bak"));
+    db_path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_path_sits_next_to_database() {
+        let path = backup_path_for(Path::new("/cache/definitions.db"));
+        assert_eq!(path.parent(), Some(Path::new("/cache")));
+
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("definitions.db."));
+        assert!(name.ends_with(".bak"));
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use agent_defs::DefinitionChange;
+use agent_defs::changelog::{format_date, parse_date};
+use agent_defs_store::DefinitionStore;
+use anyhow::Result;
+
+use super::span;
+
+/// How far back `changes` looks when no `--since` is given.
+pub const DEFAULT_WINDOW_DAYS: u64 = 7;
+
+/// Units a `--since` span can be given in, with their length in seconds.
+const SINCE_UNITS: &[(char, u64)] = &[('d', 86_400), ('w', 7 * 86_400)];
+const SINCE_FORMS: &str = "YYYY-MM-DD, Nd, or Nw";
+
+/// Parse a `--since` argument: a `YYYY-MM-DD` date, or a span back from now
+/// such as `3d` or `2w`.
+pub fn parse_since(arg: &str) -> Result<u64, String> {
+    if let Some(secs) = parse_date(arg) {
+        return Ok(secs);
+    }
+    span::parse(arg, SINCE_UNITS, SINCE_FORMS).map(secs_ago)
+}
+
+/// Seconds since the Unix epoch, `days` days before now.
+pub fn days_ago(days: u64) -> u64 {
+    secs_ago(days.saturating_mul(86_400))
+}
+
+fn secs_ago(secs: u64) -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    now.saturating_sub(secs)
+}
+
+/// Changes recorded by any of `stores` since `since`, newest first.
+pub fn collect<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    since: u64,
+) -> Result<Vec<DefinitionChange>> {
+    let mut changes = Vec::new();
+    for store in stores {
+        changes.extend(
+            store
+                .changes_since(since)
+                .map_err(|e| anyhow::anyhow!("{e}"))?,
+        );
+    }
+    changes.sort_by_key(|change| std::cmp::Reverse(change.synced_at));
+    Ok(changes)
+}
+
+/// Print changes grouped under the date of the sync that found them.
+pub fn print(changes: &[DefinitionChange], since: u64, output: &mut impl Write) -> Result<()> {
+    if changes.is_empty() {
+        writeln!(output, "No changes since {}.", format_date(since))?;
+        return Ok(());
+    }
+
+    let mut current_date = None;
+    for change in changes {
+        let date = format_date(change.synced_at);
+        if current_date.as_ref() != Some(&date) {
+            writeln!(output, "{date}")?;
+            current_date = Some(date);
+        }
+        writeln!(
+            output,
+            "  {} {} [{}] {} ({})",
+            change.change.marker(),
+            change.id,
+            change.source_label,
+            change.name,
+            change.kind
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::{ChangeKind, DefinitionId, DefinitionKind};
+
+    use super::*;
+
+    fn change(id: &str, change: ChangeKind, synced_at: u64) -> DefinitionChange {
+        DefinitionChange {
+            id: DefinitionId::new(id),
+            name: id.to_uppercase(),
+            kind: DefinitionKind::Agent,
+            source_label: "acme".into(),
+            change,
+            synced_at,
+        }
+    }
+
+    #[test]
+    fn parse_since_accepts_dates_and_spans() {
+        assert_eq!(parse_since("2026-10-12"), Ok(1_791_763_200));
+        assert_eq!(
+            span::parse("1w", SINCE_UNITS, SINCE_FORMS),
+            span::parse("7d", SINCE_UNITS, SINCE_FORMS)
+        );
+        assert!(parse_since("1w").is_ok());
+        assert!(parse_since("soon").is_err());
+        assert!(parse_since("xd").is_err());
+        assert!(parse_since("3é").is_err());
+        assert!(parse_since("99999999999999w").is_err());
+    }
+
+    #[test]
+    fn print_groups_by_sync_date() {
+        let day = 1_791_763_200;
+        let changes = [
+            change("b", ChangeKind::Added, day + 86_400),
+            change("a", ChangeKind::Removed, day),
+            change("c", ChangeKind::Changed, day),
+        ];
+        let mut output = Vec::new();
+        print(&changes, day, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2026-10-13\n  + b [acme] B (agent)\n2026-10-12\n  - a [acme] A (agent)\n  ~ c [acme] C (agent)\n"
+        );
+    }
+
+    #[test]
+    fn print_reports_nothing_new() {
+        let mut output = Vec::new();
+        print(&[], 1_791_763_200, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "No changes since 2026-10-12.\n"
+        );
+    }
+}
@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use agent_defs::changelog::format_date;
+use agent_defs::{
+    BatchId, Collection, Definition, DefinitionId, DefinitionKind, DefinitionSummary,
+    InstallRules, JournalState, Source,
+};
+use agent_defs_store::DefinitionStore;
+use anyhow::{Context, Result, bail};
+
+/// Look up a collection by name, listing the known names on failure.
+pub fn find<'a>(collections: &'a [Collection], name: &str) -> Result<&'a Collection> {
+    if let Some(collection) = collections.iter().find(|c| c.name == name) {
+        return Ok(collection);
+    }
+    if collections.is_empty() {
+        bail!("No collection named {name}. Define one under [[collections]] in the config, or import one.");
+    }
+    let names: Vec<&str> = collections.iter().map(|c| c.name.as_str()).collect();
+    bail!("No collection named {name}. Known collections: {}", names.join(", "));
+}
+
+pub fn list(collections: &[Collection], output: &mut impl Write) -> Result<()> {
+    if collections.is_empty() {
+        writeln!(output, "No collections.")?;
+        return Ok(());
+    }
+    for collection in collections {
+        let count = collection.items.len();
+        let noun = if count == 1 { "definition" } else { "definitions" };
+        match &collection.description {
+            Some(description) => writeln!(
+                output,
+                "{} ({count} {noun}): {description}",
+                collection.name
+            )?,
+            None => writeln!(output, "{} ({count} {noun})", collection.name)?,
+        }
+    }
+    Ok(())
+}
+
+/// Print each item with the source it resolves to, or a note that it is
+/// missing from every synced source.
+pub async fn show(
+    sources: &[impl Source],
+    collection: &Collection,
+    output: &mut impl Write,
+) -> Result<()> {
+    let summaries = all_summaries(sources).await?;
+    if let Some(description) = &collection.description {
+        writeln!(output, "{description}")?;
+    }
+    for item in &collection.items {
+        match summaries.iter().find(|summary| item.matches(summary)) {
+            Some(summary) => writeln!(
+                output,
+                "  {} [{}] {}",
+                summary.id, summary.source_label, summary.name
+            )?,
+            None => writeln!(output, "  {} (not found)", item.id)?,
+        }
+    }
+    Ok(())
+}
+
+/// Install every definition in the collection, returning each one that
+/// was installed and where it went. Template variables take their
+/// defaults; items that can't be installed are reported and skipped, as are
+/// hooks unless `allow_hooks` and anything the policy in `rules` refuses.
+///
+/// With a `journal`, the plan and each write are recorded there as they
+/// happen, so [`recover`] can finish or undo the install if this run dies.
+pub async fn install(
+    sources: &[impl Source],
+    collection: &Collection,
+    target: &Path,
+    rules: &InstallRules,
+    allow_hooks: bool,
+    journal: Option<&DefinitionStore>,
+    output: &mut impl Write,
+) -> Result<Vec<(Definition, PathBuf)>> {
+    let summaries = all_summaries(sources).await?;
+    let (found, missing) = collection.resolve(&summaries);
+    let mut failed = missing.len();
+    let mut installed = Vec::new();
+
+    for item in missing {
+        writeln!(output, "Skipped {}: not found in any source", item.id)?;
+    }
+    let mut planned = Vec::new();
+    for summary in found {
+        if summary.kind == DefinitionKind::Hook && !allow_hooks {
+            failed += 1;
+            writeln!(
+                output,
+                "Skipped {}: hooks run shell commands (pass --yes to install them)",
+                summary.id
+            )?;
+            continue;
+        }
+        planned.push(summary);
+    }
+
+    let batch = match journal {
+        Some(store) => {
+            let items = planned.iter().map(|s| (s.source_label.as_str(), &s.id));
+            let batch = store
+                .begin_install_batch(&collection.name, target, items)
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            Some((store, batch))
+        }
+        None => None,
+    };
+    for (position, summary) in planned.into_iter().enumerate() {
+        let step = batch.map(|(store, batch)| (store, batch, position));
+        let (label, id) = (&summary.source_label, &summary.id);
+        match install_one(sources, label, id, target, rules, step).await {
+            Ok((def, path)) => {
+                writeln!(output, "Installed {} to {}", summary.id, path.display())?;
+                installed.push((def, path));
+            }
+            Err(e) => {
+                failed += 1;
+                writeln!(output, "Skipped {}: {e}", summary.id)?;
+            }
+        }
+    }
+
+    if let Some((store, batch)) = batch {
+        store
+            .end_install_batch(batch)
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+    }
+    let total = collection.items.len();
+    writeln!(
+        output,
+        "Installed {} of {total} definitions from {}",
+        total - failed,
+        collection.name
+    )?;
+    if failed == total && total > 0 {
+        bail!("nothing from {} could be installed", collection.name);
+    }
+    Ok(installed)
+}
+
+/// Write the collection as pretty-printed JSON to `path`, or stdout.
+pub fn export(collection: &Collection, path: Option<&Path>) -> Result<()> {
+    let json = serde_json::to_string_pretty(collection)?;
+    match path {
+        Some(path) => std::fs::write(path, json + "\n")
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+/// Copy a collection file into `dir` as `<name>.json`, returning the new
+/// path. Refuses to replace an existing collection unless `force` is set.
+pub fn import(file: &Path, dir: &Path, force: bool) -> Result<PathBuf> {
+    let text = std::fs::read_to_string(file)
+        .with_context(|| format!("failed to read {}", file.display()))?;
+    let collection: Collection = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a collection file", file.display()))?;
+    let file_name: String = collection
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    if file_name.is_empty() {
+        bail!("collection in {} has no name", file.display());
+    }
+
+    let path = dir.join(format!("{file_name}.json"));
+    if path.exists() && !force {
+        bail!(
+            "a collection is already imported at {} (use --force to replace it)",
+            path.display()
+        );
+    }
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    std::fs::write(&path, serde_json::to_string_pretty(&collection)? + "\n")
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+async fn all_summaries(sources: &[impl Source]) -> Result<Vec<DefinitionSummary>> {
+    let mut all = Vec::new();
+    for source in sources {
+        all.extend(source.list().await?);
+    }
+    Ok(all)
+}
+
+/// What to do with the installs [`install`] journaled and never finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reconcile {
+    /// Only say what each one got done.
+    Report,
+    /// Install what each one didn't get to.
+    Resume,
+    /// Put back every file each one wrote.
+    Rollback,
+}
+
+/// Report every interrupted install in `journal`, then resume or roll it
+/// back as `action` says. Returns the definitions resuming installed, as
+/// source label and ID pairs.
+pub async fn recover(
+    journal: &DefinitionStore,
+    sources: &[impl Source],
+    rules: &InstallRules,
+    action: Reconcile,
+    output: &mut impl Write,
+) -> Result<Vec<(String, DefinitionId)>> {
+    let batches = journal
+        .install_batches()
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    if batches.is_empty() {
+        writeln!(output, "No interrupted installs.")?;
+        return Ok(Vec::new());
+    }
+
+    let mut installed = Vec::new();
+    let mut undone = true;
+    for batch in &batches {
+        writeln!(
+            output,
+            "Install of {} into {} on {} stopped after {} of {} definitions:",
+            batch.label,
+            batch.target.display(),
+            format_date(batch.started_at),
+            batch.done(),
+            batch.entries.len()
+        )?;
+        for entry in &batch.entries {
+            let state = match &entry.state {
+                JournalState::Planned => "not started".to_owned(),
+                JournalState::Started => "interrupted".to_owned(),
+                JournalState::Done => "installed".to_owned(),
+                JournalState::Failed(error) => format!("failed: {error}"),
+            };
+            writeln!(output, "  {} [{}] {state}", entry.id, entry.source_label)?;
+        }
+
+        match action {
+            Reconcile::Report => {}
+            Reconcile::Resume => {
+                for (position, entry) in batch.entries.iter().enumerate() {
+                    if !matches!(entry.state, JournalState::Planned | JournalState::Started) {
+                        continue;
+                    }
+                    let step = Some((journal, batch.id, position));
+                    let target = &batch.target;
+                    let result =
+                        install_one(sources, &entry.source_label, &entry.id, target, rules, step)
+                            .await;
+                    match result {
+                        Ok((_, path)) => {
+                            writeln!(output, "Installed {} to {}", entry.id, path.display())?;
+                            installed.push((entry.source_label.clone(), entry.id.clone()));
+                        }
+                        Err(e) => writeln!(output, "Skipped {}: {e}", entry.id)?,
+                    }
+                }
+                journal
+                    .end_install_batch(batch.id)
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            }
+            Reconcile::Rollback => {
+                let mut failed = false;
+                for entry in batch.written() {
+                    let Some(path) = &entry.path else { continue };
+                    match undo_write(path, entry.previous.as_deref()) {
+                        Ok(verb) => writeln!(output, "{verb} {}", path.display())?,
+                        Err(e) => {
+                            failed = true;
+                            writeln!(output, "Could not undo {}: {e}", path.display())?;
+                        }
+                    }
+                }
+                // Keep the journal of a partial rollback so it can be retried.
+                if failed {
+                    undone = false;
+                } else {
+                    journal
+                        .end_install_batch(batch.id)
+                        .map_err(|e| anyhow::anyhow!("{e}"))?;
+                }
+            }
+        }
+    }
+
+    match action {
+        Reconcile::Report => writeln!(
+            output,
+            "Run `agent-def-fetcher collection recover --resume` to finish, or --rollback to undo."
+        )?,
+        Reconcile::Rollback if !undone => bail!("some files could not be put back"),
+        _ => {}
+    }
+    Ok(installed)
+}
+
+/// Put `path` back the way it was before an install: holding `previous`,
+/// or gone if it didn't exist. Returns what was done, for the report.
+fn undo_write(path: &Path, previous: Option<&str>) -> std::io::Result<&'static str> {
+    match previous {
+        Some(previous) => std::fs::write(path, previous).map(|()| "Restored"),
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok("Removed"),
+        },
+    }
+}
+
+/// Fetch and install one definition as `rules` say. With a journal
+/// `step` (store, batch, and position), the file's old content is recorded
+/// before it is written and the outcome after.
+async fn install_one(
+    sources: &[impl Source],
+    source_label: &str,
+    id: &DefinitionId,
+    target: &Path,
+    rules: &InstallRules,
+    step: Option<(&DefinitionStore, BatchId, usize)>,
+) -> Result<(Definition, PathBuf)> {
+    let result = fetch_and_install(sources, source_label, id, target, rules, step).await;
+    if let Some((store, batch, position)) = step {
+        let error = result.as_ref().err().map(ToString::to_string);
+        store
+            .journal_finished(batch, position, error.as_deref())
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+    }
+    result
+}
+
+async fn fetch_and_install(
+    sources: &[impl Source],
+    source_label: &str,
+    id: &DefinitionId,
+    target: &Path,
+    rules: &InstallRules,
+    step: Option<(&DefinitionStore, BatchId, usize)>,
+) -> Result<(Definition, PathBuf)> {
+    let Some(source) = sources.iter().find(|s| s.label() == source_label) else {
+        bail!("source {source_label} is not configured");
+    };
+    let mut def = source.fetch(id).await?;
+    if def.raw.is_empty() {
+        def.raw = source.fetch_raw(id).await?;
+    }
+    rules.policy.check_install(&def)?;
+    if let Some((store, batch, position)) = step {
+        let path = rules.layout.path(target, &def);
+        let previous = std::fs::read_to_string(&path).ok();
+        store
+            .journal_started(batch, position, &path, previous.as_deref())
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+    }
+    let path = rules.layout.install(target, &def, &HashMap::new())?;
+    Ok((def, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::InMemorySource;
+    use agent_defs::{CollectionItem, Definition, DefinitionId, Policy};
+
+    use super::*;
+
+    fn definition(id: &str, raw: &str) -> Definition {
+        Definition {
+            kind: DefinitionKind::Agent,
+            source_label: "acme".into(),
+            raw: raw.to_owned(),
+            ..agent_defs::test_support::definition(id)
+        }
+    }
+
+    fn sources() -> Vec<InMemorySource> {
+        let mut source = InMemorySource::new("acme");
+        source.add(definition("api", "api agent"));
+        source.add(definition("db", "db agent"));
+        source.add(definition("templated", "---\nvars: [X]\n---\n{{X}}"));
+        source.add(Definition {
+            kind: DefinitionKind::Hook,
+            ..definition("fmt", "cargo fmt")
+        });
+        vec![source]
+    }
+
+    fn starter(ids: &[&str]) -> Collection {
+        Collection {
+            name: "starter".into(),
+            description: Some("Backend basics".into()),
+            items: ids.iter().map(|id| CollectionItem::new(*id)).collect(),
+        }
+    }
+
+    #[test]
+    fn find_lists_known_names() {
+        let collections = [starter(&[])];
+        assert!(find(&collections, "starter").is_ok());
+        let err = find(&collections, "other").unwrap_err().to_string();
+        assert!(err.contains("Known collections: starter"));
+    }
+
+    #[test]
+    fn list_shows_counts_and_descriptions() {
+        let mut output = Vec::new();
+        list(&[starter(&["api"])], &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "starter (1 definition): Backend basics\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn show_marks_missing_items() {
+        let mut output = Vec::new();
+        show(&sources(), &starter(&["api", "gone"]), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("  api [acme] api"));
+        assert!(out.contains("  gone (not found)"));
+    }
+
+    #[tokio::test]
+    async fn install_writes_found_items_and_reports_the_rest() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-install");
+        let _ = std::fs::remove_dir_all(&target);
+
+        let mut output = Vec::new();
+        let collection = starter(&["api", "db", "templated", "fmt", "gone"]);
+        let rules = InstallRules::default();
+        let installed = install(&sources(), &collection, &target, &rules, false, None, &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        let ids: Vec<&str> = installed.iter().map(|(def, _)| def.id.as_str()).collect();
+        assert_eq!(ids, ["api", "db"]);
+
+        assert!(target.join(".claude/agents/api.md").exists());
+        assert!(target.join(".claude/agents/db.md").exists());
+        assert!(!target.join(".claude/agents/templated.md").exists());
+        assert!(out.contains("Skipped gone: not found in any source"));
+        assert!(out.contains("Skipped templated: missing values for template variables: X"));
+        assert!(out.contains("Skipped fmt: hooks run shell commands (pass --yes to install them)"));
+        assert!(out.ends_with("Installed 2 of 5 definitions from starter\n"));
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn install_fails_when_nothing_installs() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-none");
+        let collection = starter(&["gone"]);
+        let rules = InstallRules::default();
+        let result =
+            install(&sources(), &collection, &target, &rules, true, None, &mut Vec::new()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn install_skips_what_the_policy_refuses() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-policy");
+        let _ = std::fs::remove_dir_all(&target);
+        let rules = InstallRules {
+            policy: Policy::parse("allowed_kinds = [\"skill\"]").unwrap(),
+            ..InstallRules::default()
+        };
+
+        let mut output = Vec::new();
+        let (sources, collection) = (sources(), starter(&["api"]));
+        let result = install(&sources, &collection, &target, &rules, false, None, &mut output);
+        assert!(result.await.is_err());
+        let out = String::from_utf8(output).unwrap();
+        assert!(
+            out.contains("Skipped api: agent definitions may not be installed under the org"),
+            "{out}"
+        );
+        assert!(!target.join(".claude/agents/api.md").exists());
+    }
+
+    /// A journal as a run killed partway through installing api then db
+    /// would leave it: api written over an older file, db not started.
+    fn interrupted(target: &Path) -> DefinitionStore {
+        let journal = DefinitionStore::open_in_memory("acme").unwrap();
+        let (api, db) = (DefinitionId::new("api"), DefinitionId::new("db"));
+        let batch = journal
+            .begin_install_batch("starter", target, [("acme", &api), ("acme", &db)])
+            .unwrap();
+        let path = target.join(".claude/agents/api.md");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        journal
+            .journal_started(batch, 0, &path, Some("my api"))
+            .unwrap();
+        std::fs::write(&path, "api agent").unwrap();
+        journal
+    }
+
+    #[tokio::test]
+    async fn install_leaves_no_journal_behind() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-journal");
+        let _ = std::fs::remove_dir_all(&target);
+        let journal = DefinitionStore::open_in_memory("acme").unwrap();
+
+        let collection = starter(&["api", "db"]);
+        let (rules, journaled) = (InstallRules::default(), Some(&journal));
+        install(&sources(), &collection, &target, &rules, false, journaled, &mut Vec::new())
+            .await
+            .unwrap();
+        assert!(journal.install_batches().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn recover_resumes_what_an_interrupted_install_missed() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-resume");
+        let _ = std::fs::remove_dir_all(&target);
+        let journal = interrupted(&target);
+
+        let mut output = Vec::new();
+        recover(&journal, &sources(), &InstallRules::default(), Reconcile::Report, &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("stopped after 0 of 2 definitions"), "{out}");
+        assert!(out.contains("  api [acme] interrupted\n  db [acme] not started\n"), "{out}");
+        assert_eq!(journal.install_batches().unwrap().len(), 1);
+
+        let rules = InstallRules::default();
+        let installed = recover(&journal, &sources(), &rules, Reconcile::Resume, &mut Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(installed.len(), 2);
+        assert!(target.join(".claude/agents/db.md").exists());
+        assert!(journal.install_batches().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn recover_rolls_back_what_an_interrupted_install_wrote() {
+        let target = std::env::temp_dir().join("agent-defs-test-collection-rollback");
+        let _ = std::fs::remove_dir_all(&target);
+        let journal = interrupted(&target);
+        let batch = journal.install_batches().unwrap()[0].id;
+        let db = target.join(".claude/agents/db.md");
+        journal.journal_started(batch, 1, &db, None).unwrap();
+        std::fs::write(&db, "db agent").unwrap();
+
+        let mut output = Vec::new();
+        recover(&journal, &sources(), &InstallRules::default(), Reconcile::Rollback, &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains(&format!("Removed {}", db.display())), "{out}");
+        assert!(!db.exists());
+        let api = std::fs::read_to_string(target.join(".claude/agents/api.md")).unwrap();
+        assert_eq!(api, "my api");
+        assert!(journal.install_batches().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let dir = std::env::temp_dir().join("agent-defs-test-collection-import");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("shared.json");
+        let collection = starter(&["api", "db"]);
+
+        export(&collection, Some(&file)).unwrap();
+        let imported = import(&file, &dir.join("collections"), false).unwrap();
+        assert_eq!(imported, dir.join("collections/starter.json"));
+        assert_eq!(
+            agent_defs_cli::config::read_collections_dir(&dir.join("collections")),
+            [collection]
+        );
+
+        assert!(import(&file, &dir.join("collections"), false).is_err());
+        assert!(import(&file, &dir.join("collections"), true).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
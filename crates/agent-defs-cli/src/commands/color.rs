@@ -0,0 +1,96 @@
+//! Whether output is colored, decided in one place: `--color` first, then
+//! `NO_COLOR`, `--plain` and `TERM=dumb`, then whether the stream is a
+//! terminal. Commands ask [`stdout`] or [`stderr`] before styling anything.
+
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+pub(super) const MATCH_STYLE: &str = "\x1b[1;31m";
+pub(super) const DIM_STYLE: &str = "\x1b[2m";
+pub(super) const BOLD_STYLE: &str = "\x1b[1m";
+pub(super) const WARNING_STYLE: &str = "\x1b[33m";
+pub(super) const ERROR_STYLE: &str = "\x1b[31m";
+pub(super) const RESET: &str = "\x1b[0m";
+
+/// The `--color` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color terminals, unless `NO_COLOR`, `--plain`, or `TERM=dumb` says not to.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Parse a `--color` argument.
+pub fn parse_color(arg: &str) -> Result<ColorChoice, String> {
+    match arg {
+        "auto" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => Err(format!("expected auto, always, or never, got `{arg}`")),
+    }
+}
+
+static SETTINGS: OnceLock<(ColorChoice, bool)> = OnceLock::new();
+
+/// Set the color choice for the rest of the run, along with `--plain`.
+/// Until this is called, output is colored as for [`ColorChoice::Auto`].
+pub fn init(choice: ColorChoice, plain: bool) {
+    let _ = SETTINGS.set((choice, plain));
+}
+
+/// Whether to color what's printed to stdout.
+pub fn stdout() -> bool {
+    enabled(std::io::stdout().is_terminal())
+}
+
+/// Whether to color what's printed to stderr.
+pub fn stderr() -> bool {
+    enabled(std::io::stderr().is_terminal())
+}
+
+/// `text` in `style` when `color` is on, otherwise as it is.
+pub(super) fn paint(color: bool, style: &str, text: &str) -> String {
+    if color {
+        format!("{style}{text}{RESET}")
+    } else {
+        text.to_owned()
+    }
+}
+
+fn enabled(terminal: bool) -> bool {
+    let (choice, plain) = SETTINGS.get().copied().unwrap_or_default();
+    // Any value but an empty one turns color off; see no-color.org.
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    resolve(choice, plain || no_color || dumb, terminal)
+}
+
+fn resolve(choice: ColorChoice, opted_out: bool, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && !opted_out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_overrides_the_environment_and_terminal() {
+        assert!(resolve(ColorChoice::Always, true, false));
+        assert!(!resolve(ColorChoice::Never, false, true));
+        assert!(resolve(ColorChoice::Auto, false, true));
+        assert!(!resolve(ColorChoice::Auto, true, true));
+        assert!(!resolve(ColorChoice::Auto, false, false));
+    }
+
+    #[test]
+    fn parses_color_choices() {
+        assert_eq!(parse_color("never"), Ok(ColorChoice::Never));
+        assert!(parse_color("yes").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::Write;
+use std::path::Path;
+
+use agent_defs::Policy;
+use agent_defs_cli::catalog;
+use agent_defs_cli::config::{self, AppConfig};
+use agent_defs_cli::validate::{self, Issue};
+use anyhow::{Result, bail};
+
+/// Check the config file at `path` and the `workspace` overlay, if any, and
+/// print what's wrong with them, then the configuration that actually takes
+/// effect, with `${VAR}` references expanded and tokens hidden, noting
+/// sources the org `policy` blocks. Fails if either file has errors.
+pub fn check(
+    path: Option<&Path>,
+    workspace: Option<&Path>,
+    policy: &Policy,
+    output: &mut impl Write,
+) -> Result<()> {
+    let contents = path.and_then(|path| std::fs::read_to_string(path).ok());
+    let (config, issues) = match (&contents, path) {
+        (Some(contents), Some(path)) => {
+            writeln!(output, "Config: {}", path.display())?;
+            validate::check(contents)
+        }
+        (_, path) => {
+            match path {
+                Some(path) => writeln!(
+                    output,
+                    "No config file at {}; using the built-in defaults.",
+                    path.display()
+                )?,
+                None => writeln!(output, "No config directory; using the built-in defaults.")?,
+            }
+            (Some(config::default_config()), Vec::new())
+        }
+    };
+
+    let mut errors = issues.iter().filter(|issue| issue.is_error()).count();
+    print_issues(&issues, output)?;
+
+    let mut overlay = None;
+    if let Some(workspace) = workspace {
+        writeln!(output, "\nWorkspace config: {}", workspace.display())?;
+        let contents = std::fs::read_to_string(workspace)?;
+        let issues = validate::check_workspace(&contents).1;
+        let workspace_errors = issues.iter().filter(|issue| issue.is_error()).count();
+        print_issues(&issues, output)?;
+        if workspace_errors == 0 {
+            overlay = Some(config::read_workspace_config(workspace)?);
+        } else {
+            writeln!(output, "\nThe workspace config is ignored until the errors are fixed.")?;
+        }
+        errors += workspace_errors;
+    }
+
+    let mut effective = match config {
+        Some(config) if errors == 0 => config,
+        _ => {
+            writeln!(
+                output,
+                "\nThe built-in defaults are used until the errors are fixed."
+            )?;
+            config::default_config()
+        }
+    };
+    if let Some(overlay) = &overlay {
+        effective.overlay(overlay);
+    }
+    print_policy(&effective, policy, output)?;
+    effective.glyphs = config::glyph_set(&effective);
+    effective.list_density = config::list_density(&effective);
+    for source in &mut effective.sources {
+        if let Some(token) = &mut source.token {
+            *token = "<redacted>".into();
+        }
+    }
+    writeln!(output, "\n# Effective configuration\n")?;
+    write!(output, "{}", render(&effective)?)?;
+
+    if errors > 0 {
+        bail!(
+            "config has {errors} {}",
+            if errors == 1 { "error" } else { "errors" }
+        );
+    }
+    Ok(())
+}
+
+fn print_issues(issues: &[Issue], output: &mut impl Write) -> Result<()> {
+    if issues.is_empty() {
+        writeln!(output, "No problems found.")?;
+    }
+    for issue in issues {
+        writeln!(output, "{issue}")?;
+    }
+    Ok(())
+}
+
+/// Where the org policy came from and which enabled sources it blocks.
+fn print_policy(config: &AppConfig, policy: &Policy, output: &mut impl Write) -> Result<()> {
+    let Some(path) = &policy.path else {
+        return Ok(());
+    };
+    writeln!(output, "\nOrg policy: {}", path.display())?;
+    for source in config.sources.iter().filter(|source| source.enabled) {
+        if let Err(e) = policy.check_source(&catalog::origin(source).location) {
+            writeln!(output, "warning: source `{}` is skipped: {e}", source.label)?;
+        }
+    }
+    Ok(())
+}
+
+fn render(config: &AppConfig) -> Result<String> {
+    toml::to_string(config).map_err(|e| anyhow::anyhow!("failed to render config: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, contents: Option<&str>) -> (Result<()>, String) {
+        run_under(&Policy::default(), name, contents)
+    }
+
+    fn run_under(policy: &Policy, name: &str, contents: Option<&str>) -> (Result<()>, String) {
+        let dir = std::env::temp_dir().join(format!("agent-defs-test-config-check-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sources.toml");
+        if let Some(contents) = contents {
+            std::fs::write(&path, contents).unwrap();
+        }
+        let mut output = Vec::new();
+        let result = check(Some(&path), None, policy, &mut output);
+        let _ = std::fs::remove_dir_all(&dir);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn prints_the_effective_config() {
+        let (result, out) = run(
+            "valid",
+            Some(
+                "[[sources]]\nlabel = \"mine\"\ntype = \"github-repo\"\nowner = \"acme\"\nrepo = \"agents\"\ntoken = \"ghp_secret\"\n",
+            ),
+        );
+        result.unwrap();
+        assert!(out.contains("token = \"<redacted>\""), "{out}");
+        assert!(!out.contains("ghp_secret"), "{out}");
+        assert!(out.contains("No problems found."), "{out}");
+        assert!(out.contains("label = \"mine\""), "{out}");
+        // Defaults are filled in.
+        assert!(out.contains("branch = \"main\""), "{out}");
+        assert!(out.contains("max_definition_bytes = "), "{out}");
+    }
+
+    #[test]
+    fn errors_fail_and_show_the_fallback() {
+        let (result, out) = run(
+            "invalid",
+            Some("[[sources]]\nlabel = \"mine\"\ntype = \"github\"\n"),
+        );
+        assert_eq!(result.unwrap_err().to_string(), "config has 1 error");
+        assert!(
+            out.contains("error: line 3: unknown source type `github`"),
+            "{out}"
+        );
+        assert!(out.contains("label = \"claude-code-templates\""), "{out}");
+    }
+
+    #[test]
+    fn names_sources_the_policy_blocks() {
+        let policy = Policy {
+            path: Some("/etc/agent-def-fetcher/policy.toml".into()),
+            ..Policy::parse("allowed_sources = [\"github.com/acme/*\"]").unwrap()
+        };
+        let (result, out) = run_under(
+            &policy,
+            "policy",
+            Some(
+                "[[sources]]\nlabel = \"mine\"\ntype = \"github-repo\"\nowner = \"acme\"\n\
+                 repo = \"agents\"\n\
+                 [[sources]]\nlabel = \"theirs\"\ntype = \"github-repo\"\nowner = \"other\"\n\
+                 repo = \"agents\"\n",
+            ),
+        );
+        result.unwrap();
+        assert!(out.contains("Org policy: /etc/agent-def-fetcher/policy.toml"), "{out}");
+        assert!(
+            out.contains("source `theirs` is skipped: github.com/other/agents is not an allowed"),
+            "{out}"
+        );
+        assert!(!out.contains("source `mine`"), "{out}");
+    }
+
+    #[test]
+    fn workspace_config_is_checked_and_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join(config::WORKSPACE_CONFIG_NAME);
+        std::fs::write(&workspace, "install_layout = \"flat\"\n").unwrap();
+        let mut output = Vec::new();
+        check(None, Some(&workspace), &Policy::default(), &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("Workspace config: "), "{out}");
+        assert!(out.contains("install_layout = \"flat\""), "{out}");
+
+        std::fs::write(&workspace, "update_checks = false\n").unwrap();
+        let mut output = Vec::new();
+        let result = check(None, Some(&workspace), &Policy::default(), &mut output);
+        assert_eq!(result.unwrap_err().to_string(), "config has 1 error");
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("The workspace config is ignored"), "{out}");
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let (result, out) = run("missing", None);
+        result.unwrap();
+        assert!(out.starts_with("No config file at "), "{out}");
+        assert!(out.contains("label = \"awesome-subagents\""), "{out}");
+    }
+}
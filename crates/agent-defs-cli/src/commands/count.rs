@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use agent_defs::Source;
+use agent_defs_store::{CountBy, DefinitionStore};
+use anyhow::Result;
+
+/// Parse a `--by` argument.
+pub fn parse_by(arg: &str) -> Result<CountBy, String> {
+    match arg {
+        "kind" => Ok(CountBy::Kind),
+        "category" => Ok(CountBy::Category),
+        "source" => Ok(CountBy::Source),
+        _ => Err(format!("expected kind, category, or source, got `{arg}`")),
+    }
+}
+
+/// Print how many definitions share each kind, category, or source across
+/// `stores` (or just the one labelled `source`), most first.
+pub fn run<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    by: CountBy,
+    source: Option<&str>,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut totals: HashMap<Option<String>, u64> = HashMap::new();
+    for store in stores {
+        if source.is_some_and(|label| label != store.label()) {
+            continue;
+        }
+        for (key, count) in store.count_by(by).map_err(|e| anyhow::anyhow!("{e}"))? {
+            *totals.entry(key).or_default() += count;
+        }
+    }
+
+    let mut totals: Vec<(Option<String>, u64)> = totals.into_iter().collect();
+    // Most first; the uncategorized bucket goes last among equals.
+    totals.sort_by(|(a, m), (b, n)| {
+        n.cmp(m)
+            .then_with(|| a.is_none().cmp(&b.is_none()))
+            .then(a.cmp(b))
+    });
+
+    let total: u64 = totals.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        writeln!(output, "No definitions cached yet.")?;
+        return Ok(());
+    }
+    let width = total.to_string().len();
+    for (key, count) in &totals {
+        let key = key.as_deref().unwrap_or("(none)");
+        writeln!(output, "  {count:>width$}  {key}")?;
+    }
+    writeln!(output, "  {total:>width$}  total")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs::{Definition, DefinitionId, DefinitionKind};
+
+    use super::*;
+
+    fn store(label: &str, defs: &[(&str, DefinitionKind, Option<&str>)]) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        for (id, kind, category) in defs {
+            store
+                .upsert_definition(&Definition {
+                    id: DefinitionId::new(*id),
+                    name: id.to_string(),
+                    description: None,
+                    kind: kind.clone(),
+                    category: category.map(str::to_owned),
+                    source_label: label.into(),
+                    body: String::new(),
+                    tools: vec![],
+                    model: None,
+                    metadata: HashMap::new(),
+                    raw: String::new(),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn count(stores: &[DefinitionStore], by: CountBy, source: Option<&str>) -> String {
+        let mut output = Vec::new();
+        run(stores, by, source, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn counts_add_up_across_sources() {
+        let stores = [
+            store(
+                "acme",
+                &[
+                    ("a.md", DefinitionKind::Agent, Some("backend")),
+                    ("b.md", DefinitionKind::Agent, None),
+                ],
+            ),
+            store("other", &[("c.md", DefinitionKind::Hook, Some("backend"))]),
+        ];
+
+        assert_eq!(
+            count(&stores, CountBy::Kind, None),
+            "  2  agent\n  1  hook\n  3  total\n"
+        );
+        assert_eq!(
+            count(&stores, CountBy::Category, None),
+            "  2  backend\n  1  (none)\n  3  total\n"
+        );
+        assert_eq!(
+            count(&stores, CountBy::Source, Some("other")),
+            "  1  other\n  1  total\n"
+        );
+        assert_eq!(parse_by("category"), Ok(CountBy::Category));
+        assert!(parse_by("model").is_err());
+    }
+
+    #[test]
+    fn empty_cache_says_so() {
+        let stores = [store("acme", &[])];
+        assert_eq!(
+            count(&stores, CountBy::Kind, None),
+            "No definitions cached yet.\n"
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use agent_defs::Definition;
+use anyhow::{Context, Result};
+
+/// File format `show --export` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Parse an `--export` argument.
+pub fn parse_format(arg: &str) -> Result<ExportFormat, String> {
+    match arg {
+        "html" => Ok(ExportFormat::Html),
+        "pdf" => Ok(ExportFormat::Pdf),
+        _ => Err(format!("expected html or pdf, got `{arg}`")),
+    }
+}
+
+/// Where an export of `def` goes without `--output`: its file name, with
+/// the format's extension, in the current directory.
+pub fn default_path(def: &Definition, format: ExportFormat) -> PathBuf {
+    let stem = Path::new(def.id.as_str())
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(&def.name);
+    PathBuf::from(format!("{stem}.{}", format.extension()))
+}
+
+/// Write `def` to `path` as `format`. `fields` are extra rows for the
+/// metadata table, such as where the definition was synced from.
+pub fn write(
+    def: &Definition,
+    fields: &[(&str, String)],
+    format: ExportFormat,
+    path: &Path,
+) -> Result<()> {
+    let page = html(def, fields);
+    match format {
+        ExportFormat::Html => {
+            std::fs::write(path, page).with_context(|| format!("writing {}", path.display()))
+        }
+        ExportFormat::Pdf => pdf::print(&page, path),
+    }
+}
+
+/// A standalone HTML page: the frontmatter as a table, then the body.
+pub fn html(def: &Definition, fields: &[(&str, String)]) -> String {
+    document(&def.name, &definition_html(def, fields))
+}
+
+/// A complete HTML document titled `title`, with the shared stylesheet.
+pub fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         {body}</body>\n</html>\n",
+        title = escape(title),
+    )
+}
+
+/// The heading, frontmatter table, and rendered body of `def`.
+pub fn definition_html(def: &Definition, fields: &[(&str, String)]) -> String {
+    let mut rows: Vec<(String, String)> = vec![
+        ("Name".into(), def.name.clone()),
+        ("Kind".into(), def.kind.to_string()),
+    ];
+    if let Some(desc) = &def.description {
+        rows.push(("Description".into(), desc.clone()));
+    }
+    if let Some(category) = &def.category {
+        rows.push(("Category".into(), category.clone()));
+    }
+    if let Some(model) = &def.model {
+        rows.push(("Model".into(), model.clone()));
+    }
+    if !def.tools.is_empty() {
+        rows.push(("Tools".into(), def.tools.join(", ")));
+    }
+    let mut metadata: Vec<_> = def.metadata.iter().collect();
+    metadata.sort();
+    rows.extend(metadata.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+    rows.push(("Source".into(), def.source_label.clone()));
+    rows.extend(fields.iter().map(|(k, v)| ((*k).to_owned(), v.clone())));
+    rows.push(("ID".into(), def.id.to_string()));
+
+    let mut table = String::new();
+    for (key, value) in rows {
+        let _ = writeln!(
+            table,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(&key),
+            escape(&value)
+        );
+    }
+
+    format!(
+        "<h1>{title}</h1>\n<table class=\"frontmatter\">\n{table}</table>\n\
+         <main>\n{body}</main>\n",
+        title = escape(&def.name),
+        body = markdown(&def.body),
+    )
+}
+
+const STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; \
+max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; color: #1f2328; }
+table.frontmatter { border-collapse: collapse; margin-bottom: 2rem; }
+table.frontmatter th { text-align: left; padding: 0.25rem 1rem 0.25rem 0; \
+vertical-align: top; white-space: nowrap; }
+table.frontmatter td { padding: 0.25rem 0; white-space: pre-wrap; }
+table.frontmatter tr { border-bottom: 1px solid #d0d7de; }
+pre { background: #f6f8fa; padding: 0.75rem; overflow-x: auto; border-radius: 6px; }
+code { font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 0.9em; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid #d0d7de; color: #59636e; }
+input#search { width: 100%; padding: 0.5rem; font-size: 1rem; margin-bottom: 1rem; }
+ul.definitions { list-style: none; padding-left: 0; }
+ul.definitions li { margin-bottom: 0.5rem; }
+.meta, .description { color: #59636e; font-size: 0.9em; }
+";
+
+/// Escape text for HTML element content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render the Markdown that definition bodies use: headings, paragraphs,
+/// fenced code, lists, block quotes, rules, and inline code, emphasis, and
+/// links. Anything else comes through as text, escaped.
+fn markdown(source: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<&str> = None;
+    let mut lines = source.lines().peekable();
+
+    let flush_paragraph = |out: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            let _ = writeln!(out, "<p>{}</p>", inline(&paragraph.join("\n")));
+            paragraph.clear();
+        }
+    };
+    let close_list = |out: &mut String, list: &mut Option<&str>| {
+        if let Some(tag) = list.take() {
+            let _ = writeln!(out, "</{tag}>");
+        }
+    };
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+
+        if let Some(info) = trimmed.strip_prefix("```") {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            let language = info.split_whitespace().next().unwrap_or("");
+            if language.is_empty() {
+                out.push_str("<pre><code>");
+            } else {
+                let _ = write!(out, "<pre><code class=\"language-{}\">", escape(language));
+            }
+            for code in lines.by_ref() {
+                if code.trim_start().starts_with("```") {
+                    break;
+                }
+                out.push_str(&escape(code));
+                out.push('\n');
+            }
+            out.push_str("</code></pre>\n");
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            let _ = writeln!(out, "<h{level}>{}</h{level}>", inline(text));
+            continue;
+        }
+
+        if is_rule(trimmed) {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            out.push_str("<hr>\n");
+            continue;
+        }
+
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            let mut quoted = vec![quote.trim_start()];
+            while let Some(next) = lines.peek().and_then(|l| l.trim_start().strip_prefix('>')) {
+                quoted.push(next.trim_start());
+                lines.next();
+            }
+            let _ = writeln!(
+                out,
+                "<blockquote>\n{}</blockquote>",
+                markdown(&quoted.join("\n"))
+            );
+            continue;
+        }
+
+        if let Some((tag, item)) = list_item(trimmed) {
+            flush_paragraph(&mut out, &mut paragraph);
+            if list != Some(tag) {
+                close_list(&mut out, &mut list);
+                let _ = writeln!(out, "<{tag}>");
+                list = Some(tag);
+            }
+            let _ = writeln!(out, "<li>{}</li>", inline(item));
+            continue;
+        }
+
+        close_list(&mut out, &mut list);
+        paragraph.push(trimmed);
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    close_list(&mut out, &mut list);
+    out
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| (level, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&mark| compact.chars().all(|c| c == mark))
+}
+
+/// The list tag and text of a `- item` or `1. item` line.
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+    {
+        return Some(("ul", item));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(|item| ("ol", item))
+}
+
+/// Inline code, links, strong and emphasized text; everything else escaped.
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        let rendered = match c {
+            '`' => after
+                .find('`')
+                .map(|end| (format!("<code>{}</code>", escape(&after[..end])), end + 2)),
+            '*' | '_' if after.starts_with(c) => {
+                let marker = &rest[..2];
+                rest[2..].find(marker).filter(|&end| end > 0).map(|end| {
+                    let inner = &rest[2..2 + end];
+                    (format!("<strong>{}</strong>", inline(inner)), end + 4)
+                })
+            }
+            '*' | '_' => {
+                // `_` inside a word, as in snake_case, isn't emphasis.
+                let in_word = c == '_'
+                    && out
+                        .chars()
+                        .last()
+                        .is_some_and(|prev: char| prev.is_alphanumeric());
+                after
+                    .find(c)
+                    .filter(|&end| end > 0 && !in_word)
+                    .map(|end| (format!("<em>{}</em>", inline(&after[..end])), end + 2))
+            }
+            '[' => link(after),
+            _ => None,
+        };
+        match rendered {
+            Some((html, consumed)) => {
+                out.push_str(&html);
+                rest = &rest[consumed..];
+            }
+            None => {
+                out.push_str(&escape(&rest[..c.len_utf8()]));
+                rest = after;
+            }
+        }
+    }
+    out
+}
+
+/// A `[text](url)` link, given what follows the `[`, and the bytes it
+/// spans counting the `[`.
+fn link(after: &str) -> Option<(String, usize)> {
+    let close = after.find("](")?;
+    let url_len = after[close + 2..].find(')')?;
+    let text = &after[..close];
+    let url = &after[close + 2..close + 2 + url_len];
+    let html = format!("<a href=\"{}\">{}</a>", escape(url), inline(text));
+    Some((html, 1 + close + 2 + url_len + 1))
+}
+
+#[cfg(feature = "pdf-export")]
+mod pdf {
+    use std::path::Path;
+    use std::process::Command;
+
+    use anyhow::{Context, Result, bail};
+
+    /// Browsers tried in order when `AGENT_DEFS_PDF_BROWSER` isn't set.
+    const BROWSERS: &[&str] = &[
+        "chromium",
+        "chromium-browser",
+        "google-chrome",
+        "google-chrome-stable",
+        "microsoft-edge",
+        "msedge",
+    ];
+
+    /// Print `page` to `path` with a headless Chromium-based browser.
+    pub fn print(page: &str, path: &Path) -> Result<()> {
+        let html =
+            std::env::temp_dir().join(format!("agent-defs-export-{}.html", std::process::id()));
+        std::fs::write(&html, page).with_context(|| format!("writing {}", html.display()))?;
+        let result = run_browser(&html, path);
+        let _ = std::fs::remove_file(&html);
+        result
+    }
+
+    fn run_browser(html: &Path, pdf: &Path) -> Result<()> {
+        let pdf = std::path::absolute(pdf)?;
+        let candidates: Vec<String> = match std::env::var("AGENT_DEFS_PDF_BROWSER") {
+            Ok(browser) if !browser.trim().is_empty() => vec![browser],
+            _ => BROWSERS.iter().map(|b| (*b).to_owned()).collect(),
+        };
+        for browser in &candidates {
+            let status = Command::new(browser)
+                .arg("--headless")
+                .arg("--disable-gpu")
+                .arg("--no-pdf-header-footer")
+                .arg(format!("--print-to-pdf={}", pdf.display()))
+                .arg(format!("file://{}", html.display()))
+                .output();
+            match status {
+                Ok(output) if output.status.success() => return Ok(()),
+                Ok(output) => bail!(
+                    "{browser} couldn't print the PDF: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).with_context(|| format!("running {browser}")),
+            }
+        }
+        bail!(
+            "no headless browser found to print the PDF (tried {}); \
+             set AGENT_DEFS_PDF_BROWSER or export --export html instead",
+            candidates.join(", ")
+        )
+    }
+}
+
+#[cfg(not(feature = "pdf-export"))]
+mod pdf {
+    use std::path::Path;
+
+    use anyhow::{Result, bail};
+
+    pub fn print(_page: &str, _path: &Path) -> Result<()> {
+        bail!(
+            "this build can't export PDF; rebuild with `--features pdf-export`, \
+             or use --export html"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs::{DefinitionId, DefinitionKind};
+
+    use super::*;
+
+    #[test]
+    fn markdown_covers_what_definition_bodies_use() {
+        let body = "# Reviewer\n\
+                    Checks **every** change for `unsafe_code` and *style*.\n\
+                    See [the guide](https://example.com/?a=1&b=2).\n\
+                    \n\
+                    - one\n\
+                    - two <b>\n\
+                    \n\
+                    1. first\n\
+                    \n\
+                    > quoted\n\
+                    \n\
+                    ```rust\n\
+                    let x = a < b;\n\
+                    ```\n\
+                    ---\n\
+                    keep snake_case_names";
+        assert_eq!(
+            markdown(body),
+            "<h1>Reviewer</h1>\n\
+             <p>Checks <strong>every</strong> change for <code>unsafe_code</code> and \
+             <em>style</em>.\n\
+             See <a href=\"https://example.com/?a=1&amp;b=2\">the guide</a>.</p>\n\
+             <ul>\n<li>one</li>\n<li>two &lt;b&gt;</li>\n</ul>\n\
+             <ol>\n<li>first</li>\n</ol>\n\
+             <blockquote>\n<p>quoted</p>\n</blockquote>\n\
+             <pre><code class=\"language-rust\">let x = a &lt; b;\n</code></pre>\n\
+             <hr>\n\
+             <p>keep snake_case_names</p>\n"
+        );
+    }
+
+    #[test]
+    fn unclosed_markers_stay_literal() {
+        assert_eq!(inline("2 * 3 and `tick"), "2 * 3 and `tick");
+        assert_eq!(inline("[not a link]"), "[not a link]");
+    }
+
+    #[test]
+    fn page_tables_the_frontmatter() {
+        let def = Definition {
+            id: DefinitionId::new("agents/review/reviewer.md"),
+            name: "reviewer".into(),
+            description: Some("Reviews <diffs>".into()),
+            kind: DefinitionKind::Agent,
+            category: None,
+            source_label: "acme".into(),
+            body: "Review code.\n".into(),
+            tools: vec!["Read".into(), "Grep".into()],
+            model: None,
+            metadata: HashMap::from([("color".to_owned(), "blue".to_owned())]),
+            raw: String::new(),
+        };
+        let page = html(&def, &[("Origin", "github.com/acme/agents".into())]);
+
+        assert!(page.starts_with("<!DOCTYPE html>"), "{page}");
+        assert!(page.contains("<title>reviewer</title>"), "{page}");
+        assert!(
+            page.contains("<tr><th>Description</th><td>Reviews &lt;diffs&gt;</td></tr>"),
+            "{page}"
+        );
+        assert!(
+            page.contains("<tr><th>Tools</th><td>Read, Grep</td></tr>"),
+            "{page}"
+        );
+        assert!(
+            page.contains("<tr><th>color</th><td>blue</td></tr>"),
+            "{page}"
+        );
+        assert!(page.contains("<tr><th>Origin</th><td>github.com/acme/agents</td></tr>"));
+        assert!(
+            page.contains("<main>\n<p>Review code.</p>\n</main>"),
+            "{page}"
+        );
+        assert_eq!(
+            default_path(&def, ExportFormat::Pdf),
+            PathBuf::from("reviewer.pdf")
+        );
+    }
+}
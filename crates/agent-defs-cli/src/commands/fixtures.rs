@@ -0,0 +1,83 @@
+//! `bench-fixtures`: write the synthetic catalog the benchmarks use to
+//! disk, so a sync can be timed end to end against a repo or local server
+//! holding it.
+
+use std::io::Write;
+use std::path::Path;
+
+use agent_defs::fixtures;
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+/// Write `count` synthetic definitions under `dir/definitions/`, plus
+/// `dir/definitions.tar.gz` packed the way GitHub serves a repository
+/// tarball.
+pub fn write(dir: &Path, count: usize, output: &mut impl Write) -> Result<()> {
+    let files = fixtures::corpus(count);
+    let tree = dir.join("definitions");
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+
+    for file in &files {
+        let path = tree.join(&file.relative_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, &file.content)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.content.len() as u64);
+        header.set_mode(0o644);
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_cksum();
+        archive.append_data(
+            &mut header,
+            format!("bench-fixtures/{}", file.relative_path),
+            file.content.as_bytes(),
+        )?;
+    }
+
+    let tarball = dir.join("definitions.tar.gz");
+    std::fs::write(&tarball, archive.into_inner()?.finish()?)
+        .with_context(|| format!("failed to write {}", tarball.display()))?;
+    writeln!(
+        output,
+        "Wrote {count} definitions to {} and {}",
+        tree.display(),
+        tarball.display()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs_github::{TarballClient, TarballLimits};
+
+    use super::*;
+
+    #[test]
+    fn tree_and_tarball_hold_the_same_files() {
+        let dir = std::env::temp_dir().join("agent-defs-test-bench-fixtures");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut output = Vec::new();
+        write(&dir, 60, &mut output).unwrap();
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .starts_with("Wrote 60 definitions")
+        );
+
+        let tarball = std::fs::read(dir.join("definitions.tar.gz")).unwrap();
+        let files = TarballClient::extract_files(&tarball, &TarballLimits::default()).unwrap();
+        assert_eq!(files.len(), 60);
+        for file in files {
+            let on_disk = std::fs::read_to_string(dir.join("definitions").join(&file.path));
+            assert_eq!(on_disk.unwrap(), file.content);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,46 +1,441 @@
-use agent_defs::{DefinitionKind, DefinitionSummary};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use agent_defs::{
+    Definition, DefinitionId, DefinitionKind, DefinitionSummary, DisplayNames, Rating, TextQuery,
+    section,
+};
+
+use super::color::{self, BOLD_STYLE, DIM_STYLE, MATCH_STYLE, RESET};
 
 const MAX_NAME_WIDTH: usize = 35;
 const LINE_BUDGET: usize = 90;
+/// Widest the category and source columns of [`list_table`] get.
+const MAX_COLUMN_WIDTH: usize = 20;
+/// Characters of a body line kept around a search match.
+const SNIPPET_WIDTH: usize = 70;
+
+
+/// A body line that matched a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// 1-based line number in the body.
+    pub line: usize,
+    /// Title of the body section the line is in, if it's under a heading.
+    pub section: Option<String>,
+    pub text: String,
+}
+
+impl Snippet {
+    /// Where in the body the snippet is, as `12` or `12 (Usage)`.
+    fn location(&self) -> String {
+        match &self.section {
+            Some(section) => format!("{} ({section})", self.line),
+            None => self.line.to_string(),
+        }
+    }
+}
 
-pub fn print_summary_table(summaries: &[DefinitionSummary]) {
+/// What a search matched, so its results can show why they matched.
+#[derive(Debug, Clone, Default)]
+pub struct Matches {
+    pub query: String,
+    /// Highlight matches with ANSI colors rather than leaving them plain.
+    pub color: bool,
+    /// Body lines that matched, by source label and ID.
+    pub snippets: HashMap<(String, DefinitionId), Snippet>,
+}
+
+impl Matches {
+    fn snippet(&self, summary: &DefinitionSummary) -> Option<&Snippet> {
+        self.snippets
+            .get(&(summary.source_label.clone(), summary.id.clone()))
+    }
+}
+
+/// Print search results with their matches highlighted and, under each
+/// one whose body matched, the line that did.
+pub fn print_search_table(summaries: &[DefinitionSummary], matches: &Matches) {
+    print!("{}", summary_table(summaries, matches));
+}
+
+fn summary_table(summaries: &[DefinitionSummary], matches: &Matches) -> String {
+    let mut out = String::new();
     if summaries.is_empty() {
-        return;
+        return out;
     }
 
+    let names = DisplayNames::new(summaries);
     let groups = group_by_kind(summaries);
     let mut total = 0usize;
     let mut first = true;
 
     for (kind, entries) in &groups {
         if !first {
-            println!();
+            out.push('\n');
         }
         first = false;
 
         let name_width = entries
             .iter()
-            .map(|s| s.name.chars().count())
+            .map(|s| names.get(s).chars().count())
             .max()
             .unwrap_or(0)
             .min(MAX_NAME_WIDTH);
 
         let desc_budget = LINE_BUDGET.saturating_sub(2 + name_width + 2);
 
-        println!("{} ({})", kind_label(kind), entries.len());
+        let _ = writeln!(out, "{} ({})", kind_label(kind), entries.len());
 
         for entry in entries {
-            let name = truncate(&entry.name, name_width);
+            let name = truncate(names.get(entry), name_width);
+            let padding = " ".repeat(name_width.saturating_sub(name.chars().count()));
             let desc = entry.description.as_deref().unwrap_or("");
             let desc = truncate(desc, desc_budget);
+            let (name, desc) = (
+                highlight(&name, &matches.query, matches.color),
+                highlight(&desc, &matches.query, matches.color),
+            );
 
-            println!("  {:<width$}  {}", name, desc, width = name_width);
+            let _ = writeln!(out, "  {name}{padding}  {desc}");
+            if let Some(snippet) = matches.snippet(entry) {
+                let line = color::paint(
+                    matches.color,
+                    DIM_STYLE,
+                    &format!("{}:", snippet.location()),
+                );
+                let text = highlight(&snippet.text, &matches.query, matches.color);
+                let _ = writeln!(out, "    {line} {text}");
+            }
         }
 
         total += entries.len();
     }
 
-    println!("\n{total} definitions");
+    let _ = writeln!(out, "\n{total} definitions");
+    out
+}
+
+/// An aligned table with a row per definition, in the order given: name,
+/// kind, category, source, and description. With a `width`, long cells are
+/// truncated to fit it, giving up the description and then the category
+/// column when too narrow; without one nothing is cut.
+pub fn list_table(
+    summaries: &[DefinitionSummary],
+    ratings: &[Rating],
+    width: Option<usize>,
+    color: bool,
+) -> String {
+    let names = DisplayNames::new(summaries);
+    let rows: Vec<[String; 6]> = summaries
+        .iter()
+        .map(|s| {
+            [
+                names.get(s).to_owned(),
+                s.kind.to_string(),
+                s.category.clone().unwrap_or_default(),
+                s.source_label.clone(),
+                rating_badge(ratings, s),
+                s.description.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    const HEADERS: [&str; 6] = ["NAME", "KIND", "CATEGORY", "SOURCE", "RATING", "DESCRIPTION"];
+    const CAPS: [usize; 5] = [
+        MAX_NAME_WIDTH,
+        usize::MAX,
+        MAX_COLUMN_WIDTH,
+        MAX_COLUMN_WIDTH,
+        usize::MAX,
+    ];
+    const MIN_DESCRIPTION: usize = 10;
+
+    // Natural width of each column, capped when the table has to fit.
+    let mut widths = [0usize; 6];
+    for (i, header) in HEADERS.iter().enumerate() {
+        let longest = rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0);
+        widths[i] = longest.max(header.len());
+        if width.is_some() && i < CAPS.len() {
+            widths[i] = widths[i].min(CAPS[i]);
+        }
+    }
+
+    // Without a community index there is nothing to rate by.
+    let mut columns = vec![0, 1, 2, 3, 4, 5];
+    if rows.iter().all(|row| row[4].is_empty()) {
+        columns.retain(|&i| i != 4);
+    }
+    if let Some(width) = width {
+        let used = |columns: &[usize]| -> usize {
+            columns[..columns.len() - 1]
+                .iter()
+                .map(|&i| widths[i] + 2)
+                .sum()
+        };
+        if width.saturating_sub(used(&columns)) < MIN_DESCRIPTION {
+            columns.retain(|&i| i != 2);
+        }
+        if width.saturating_sub(used(&columns)) < MIN_DESCRIPTION {
+            columns.retain(|&i| i != 5);
+        } else {
+            widths[5] = widths[5].min(width - used(&columns));
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: [&str; 6], style: Option<&str>| {
+        let mut line = String::new();
+        for (n, &i) in columns.iter().enumerate() {
+            let cell = truncate(cells[i], widths[i]);
+            if n + 1 == columns.len() {
+                line.push_str(&cell);
+            } else {
+                let _ = write!(line, "{cell:<w$}  ", w = widths[i]);
+            }
+        }
+        let line = line.trim_end();
+        let _ = match style {
+            Some(style) => writeln!(out, "{}", color::paint(color, style, line)),
+            None => writeln!(out, "{line}"),
+        };
+    };
+    push_row(HEADERS, Some(BOLD_STYLE));
+    for row in &rows {
+        push_row([&row[0], &row[1], &row[2], &row[3], &row[4], &row[5]], None);
+    }
+    let noun = if rows.len() == 1 { "definition" } else { "definitions" };
+    let footer = format!("{} {noun}", rows.len());
+    let _ = writeln!(out, "\n{}", color::paint(color, DIM_STYLE, &footer));
+    out
+}
+
+/// The community rating badge for `summary`, or an empty string if it has
+/// none.
+pub fn rating_badge(ratings: &[Rating], summary: &DefinitionSummary) -> String {
+    ratings
+        .iter()
+        .find(|rating| rating.matches(summary))
+        .map(Rating::badge)
+        .unwrap_or_default()
+}
+
+/// Print summaries for screen readers and dumb terminals, with a line
+/// spelling out the rating after each rated one.
+pub fn print_summary_plain(summaries: &[DefinitionSummary], ratings: &[Rating]) {
+    if !summaries.is_empty() {
+        print!(
+            "{}",
+            plain_listing(summaries, false, |entry| ratings
+                .iter()
+                .find(|rating| rating.matches(entry))
+                .map(|rating| format!("Rating: {}", rating.describe())))
+        );
+    }
+}
+
+/// Print search results for screen readers and dumb terminals, with the
+/// body line that matched after each result whose body did.
+pub fn print_search_plain(summaries: &[DefinitionSummary], matches: &Matches) {
+    if !summaries.is_empty() {
+        print!(
+            "{}",
+            plain_listing(summaries, false, |entry| matches
+                .snippet(entry)
+                .map(|s| format!("Matched line {}: {}", s.location(), s.text)))
+        );
+    }
+}
+
+/// Byte ranges in `text`, in order, where any word or phrase `query` looks
+/// for occurs, ignoring ASCII case as the store's search does.
+pub fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = TextQuery::parse(query)
+        .include
+        .iter()
+        .flat_map(|term| term_ranges(text, term))
+        .collect();
+    ranges.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    // Drop ranges inside or overlapping an earlier one.
+    let mut end = 0;
+    ranges.retain(|range| {
+        let keep = range.start >= end;
+        end = end.max(range.end);
+        keep
+    });
+    ranges
+}
+
+fn term_ranges(text: &str, term: &str) -> Vec<Range<usize>> {
+    let (haystack, needle) = (text.as_bytes(), term.as_bytes());
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+    let mut start = 0;
+    while start + needle.len() <= haystack.len() {
+        if text.is_char_boundary(start)
+            && haystack[start..start + needle.len()].eq_ignore_ascii_case(needle)
+        {
+            ranges.push(start..start + needle.len());
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    ranges
+}
+
+/// `text` with every match of `query` in bold red when `color`; unchanged
+/// otherwise.
+pub fn highlight(text: &str, query: &str, color: bool) -> String {
+    if !color {
+        return text.to_owned();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for range in match_ranges(text, query) {
+        out.push_str(&text[last..range.start]);
+        let _ = write!(out, "{MATCH_STYLE}{}{RESET}", &text[range.clone()]);
+        last = range.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// The first line of `body` containing `query`, trimmed and cut down to
+/// about [`SNIPPET_WIDTH`] characters around the match, with the section
+/// it's in.
+pub fn snippet(body: &str, query: &str) -> Option<Snippet> {
+    let sections = section::sections(body);
+    let section = |i| section::section_at(&sections, i).map(|s| s.title.clone());
+    body.lines().enumerate().find_map(|(i, line)| {
+        let line = line.trim();
+        let found = match_ranges(line, query).into_iter().next()?;
+        let len = line.chars().count();
+        if len <= SNIPPET_WIDTH {
+            return Some(Snippet {
+                line: i + 1,
+                section: section(i),
+                text: line.to_owned(),
+            });
+        }
+
+        let match_start = line[..found.start].chars().count();
+        let match_len = line[found].chars().count();
+        let lead = SNIPPET_WIDTH.saturating_sub(match_len) / 2;
+        let start = match_start.saturating_sub(lead).min(len - SNIPPET_WIDTH);
+        let end = start + SNIPPET_WIDTH;
+        let mut text: String = line.chars().skip(start).take(SNIPPET_WIDTH).collect();
+        if start > 0 {
+            text.insert(0, '…');
+        }
+        if end < len {
+            text.push('…');
+        }
+        Some(Snippet {
+            line: i + 1,
+            section: section(i),
+            text,
+        })
+    })
+}
+
+/// Summaries in the order both listings show them: grouped by kind.
+pub fn display_order(summaries: &[DefinitionSummary]) -> Vec<&DefinitionSummary> {
+    group_by_kind(summaries)
+        .into_iter()
+        .flat_map(|(_, entries)| entries)
+        .collect()
+}
+
+/// Plain listing: one definition per line, no column padding, truncation,
+/// or ellipses. With `numbered`, entries are numbered in [`display_order`].
+pub fn plain_summaries(summaries: &[DefinitionSummary], numbered: bool) -> String {
+    plain_listing(summaries, numbered, |_| None)
+}
+
+/// [`plain_summaries`], with an optional extra line after each entry.
+fn plain_listing(
+    summaries: &[DefinitionSummary],
+    numbered: bool,
+    extra: impl Fn(&DefinitionSummary) -> Option<String>,
+) -> String {
+    let names = DisplayNames::new(summaries);
+    let mut out = String::new();
+    let mut number = 0usize;
+
+    for (kind, entries) in group_by_kind(summaries) {
+        let noun = if entries.len() == 1 {
+            "definition"
+        } else {
+            "definitions"
+        };
+        let _ = writeln!(out, "{}, {} {noun}", kind_label(kind), entries.len());
+
+        for entry in entries {
+            number += 1;
+            if numbered {
+                let _ = write!(out, "{number}. ");
+            }
+            let name = names.get(entry);
+            match &entry.description {
+                Some(desc) => {
+                    let _ = writeln!(out, "{name}: {desc}");
+                }
+                None => {
+                    let _ = writeln!(out, "{name}");
+                }
+            }
+            if let Some(line) = extra(entry) {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+        out.push('\n');
+    }
+
+    let noun = if number == 1 {
+        "definition"
+    } else {
+        "definitions"
+    };
+    let _ = writeln!(out, "{number} {noun} total");
+    out
+}
+
+/// Plain definition details: one labelled field per line, then the body.
+pub fn plain_definition(def: &Definition) -> String {
+    plain_definition_with(def, &[])
+}
+
+/// [`plain_definition`] with `extra` fields listed after the source.
+pub fn plain_definition_with(def: &Definition, extra: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Name: {}", def.name);
+    let _ = writeln!(out, "Kind: {}", def.kind);
+    if let Some(desc) = &def.description {
+        let _ = writeln!(out, "Description: {desc}");
+    }
+    if let Some(category) = &def.category {
+        let _ = writeln!(out, "Category: {category}");
+    }
+    if let Some(model) = &def.model {
+        let _ = writeln!(out, "Model: {model}");
+    }
+    if !def.tools.is_empty() {
+        let _ = writeln!(out, "Tools: {}", def.tools.join(", "));
+    }
+    let _ = writeln!(out, "Source: {}", def.source_label);
+    for (label, value) in extra {
+        let _ = writeln!(out, "{label}: {value}");
+    }
+    let _ = writeln!(out, "ID: {}", def.id);
+    out.push('\n');
+    out.push_str(&def.body);
+    if !def.body.ends_with('\n') {
+        out.push('\n');
+    }
+    out
 }
 
 fn kind_label(kind: &DefinitionKind) -> &str {
@@ -55,7 +450,7 @@ fn kind_label(kind: &DefinitionKind) -> &str {
     }
 }
 
-fn kind_sort_key(kind: &DefinitionKind) -> u8 {
+pub(super) fn kind_sort_key(kind: &DefinitionKind) -> u8 {
     match kind {
         DefinitionKind::Agent => 0,
         DefinitionKind::Command => 1,
@@ -151,14 +546,195 @@ mod tests {
         assert_eq!(groups[1].1.len(), 1);
     }
 
-    fn summary(name: &str, kind: DefinitionKind) -> DefinitionSummary {
-        DefinitionSummary {
-            id: agent_defs::DefinitionId::new(name),
-            name: name.to_owned(),
+    #[test]
+    fn plain_summaries_have_no_padding_or_truncation() {
+        let long = "x".repeat(200);
+        let mut reviewer = summary("reviewer", DefinitionKind::Agent);
+        reviewer.description = Some(long.clone());
+        let summaries = vec![reviewer, summary("fmt", DefinitionKind::Hook)];
+
+        let out = plain_summaries(&summaries, false);
+        assert_eq!(
+            out,
+            format!(
+                "Agents, 1 definition\nreviewer: {long}\n\nHooks, 1 definition\nfmt\n\n2 definitions total\n"
+            )
+        );
+        assert!(!out.contains('…'));
+    }
+
+    #[test]
+    fn numbered_plain_summaries_follow_display_order() {
+        let summaries = vec![
+            summary("s", DefinitionKind::Skill),
+            summary("a", DefinitionKind::Agent),
+        ];
+
+        let out = plain_summaries(&summaries, true);
+        assert!(out.contains("1. a\n"));
+        assert!(out.contains("2. s\n"));
+
+        let names: Vec<&str> = display_order(&summaries)
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a", "s"]);
+    }
+
+    #[test]
+    fn plain_definition_lists_fields_then_body() {
+        let def = Definition {
+            id: agent_defs::DefinitionId::new("agents/r.md"),
+            name: "r".into(),
             description: None,
-            kind,
+            kind: DefinitionKind::Agent,
             category: None,
             source_label: "test".into(),
+            body: "Body".into(),
+            tools: vec!["Read".into(), "Grep".into()],
+            model: None,
+            metadata: Default::default(),
+            raw: String::new(),
+        };
+
+        assert_eq!(
+            plain_definition(&def),
+            "Name: r\nKind: agent\nTools: Read, Grep\nSource: test\nID: agents/r.md\n\nBody\n"
+        );
+    }
+
+    #[test]
+    fn plain_summaries_qualify_shared_names() {
+        let mut other = summary("reviewer", DefinitionKind::Agent);
+        other.source_label = "other".into();
+        let summaries = vec![summary("reviewer", DefinitionKind::Agent), other];
+
+        let out = plain_summaries(&summaries, false);
+        assert!(out.contains("reviewer (test)\n"));
+        assert!(out.contains("reviewer (other)\n"));
+    }
+
+    #[test]
+    fn matches_ignore_ascii_case_and_stay_on_char_boundaries() {
+        assert_eq!(match_ranges("GitHub and github", "github"), [0..6, 11..17]);
+        assert_eq!(match_ranges("café Café", "caf"), [0..3, 6..9]);
+        assert!(match_ranges("anything", "").is_empty());
+        assert_eq!(
+            match_ranges("code review of reviewed code", "review \"code review\" !of"),
+            [0..11, 15..21]
+        );
+        assert_eq!(highlight("Use GitHub", "github", false), "Use GitHub");
+        assert_eq!(
+            highlight("Use GitHub", "github", true),
+            "Use \x1b[1;31mGitHub\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn snippets_center_long_lines_on_the_match() {
+        let (before, after) = ("a".repeat(80), "b".repeat(80));
+        let body = format!("# Title\n\n{before} calls the Stripe API {after}\n");
+        let snippet = snippet(&body, "stripe").unwrap();
+        assert_eq!(snippet.line, 3);
+        assert_eq!(snippet.section.as_deref(), Some("Title"));
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert!(snippet.text.contains("calls the Stripe API"));
+        assert_eq!(snippet.text.chars().count(), SNIPPET_WIDTH + 2);
+
+        assert_eq!(snippet_text("short line with stripe"), "short line with stripe");
+        assert!(snippet_text("nothing here").is_empty());
+    }
+
+    fn snippet_text(body: &str) -> String {
+        snippet(body, "stripe").map(|s| s.text).unwrap_or_default()
+    }
+
+    #[test]
+    fn search_table_highlights_and_shows_snippets() {
+        let mut reviewer = summary("reviewer", DefinitionKind::Agent);
+        reviewer.description = Some("Reviews Rust code".into());
+        let mut matches = Matches {
+            query: "rust".into(),
+            color: false,
+            ..Matches::default()
+        };
+        matches.snippets.insert(
+            ("test".into(), reviewer.id.clone()),
+            Snippet {
+                line: 4,
+                section: Some("Style".into()),
+                text: "Prefer idiomatic Rust.".into(),
+            },
+        );
+
+        let out = summary_table(std::slice::from_ref(&reviewer), &matches);
+        assert_eq!(
+            out,
+            "Agents (1)\n  reviewer  Reviews Rust code\n    4 (Style): Prefer idiomatic Rust.\n\n1 definitions\n"
+        );
+
+        matches.color = true;
+        let out = summary_table(&[reviewer], &matches);
+        assert!(out.contains("Reviews \x1b[1;31mRust\x1b[0m code"), "{out}");
+    }
+
+    #[test]
+    fn list_table_aligns_columns_and_fits_the_width() {
+        let mut reviewer = summary("reviewer", DefinitionKind::Agent);
+        reviewer.category = Some("quality".into());
+        reviewer.description = Some("Reviews code for bugs and style".into());
+        let summaries = vec![reviewer, summary("fmt", DefinitionKind::Hook)];
+
+        assert_eq!(
+            list_table(&summaries, &[], None, false),
+            "NAME      KIND   CATEGORY  SOURCE  DESCRIPTION\n\
+             reviewer  agent  quality   test    Reviews code for bugs and style\n\
+             fmt       hook             test\n\
+             \n2 definitions\n"
+        );
+
+        let narrow = list_table(&summaries, &[], Some(50), false);
+        assert!(narrow.contains("reviewer  agent  quality   test    Reviews code f…\n"), "{narrow}");
+
+        let narrower = list_table(&summaries, &[], Some(40), false);
+        assert!(narrower.starts_with("NAME      KIND   SOURCE  DESCRIPTION\n"), "{narrower}");
+
+        let tiny = list_table(&summaries, &[], Some(20), false);
+        assert!(tiny.ends_with("\n2 definitions\n"), "{tiny}");
+
+        let colored = list_table(&summaries, &[], None, true);
+        assert!(colored.starts_with("\x1b[1mNAME "), "{colored}");
+        assert!(colored.ends_with("\n\x1b[2m2 definitions\x1b[0m\n"), "{colored}");
+        assert!(tiny.starts_with("NAME      KIND   SOURCE\n"), "{tiny}");
+    }
+
+    #[test]
+    fn list_table_shows_ratings_when_there_are_any() {
+        let summaries = vec![
+            summary("reviewer", DefinitionKind::Agent),
+            summary("fmt", DefinitionKind::Hook),
+        ];
+        let ratings = [Rating {
+            id: summaries[0].id.clone(),
+            source_label: "test".into(),
+            score: Some(4.62),
+            votes: 212,
+            verified: true,
+        }];
+
+        assert_eq!(
+            list_table(&summaries, &ratings, None, false),
+            "NAME      KIND   CATEGORY  SOURCE  RATING  DESCRIPTION\n\
+             reviewer  agent            test    \u{2605}4.6 \u{2713}\n\
+             fmt       hook             test\n\
+             \n2 definitions\n"
+        );
+    }
+
+    fn summary(name: &str, kind: DefinitionKind) -> DefinitionSummary {
+        DefinitionSummary {
+            kind,
+            ..agent_defs::test_support::definition(name).summary()
         }
     }
 }
@@ -0,0 +1,176 @@
+use std::io::{ErrorKind, Write};
+
+use agent_defs::Source;
+use agent_defs_store::DefinitionStore;
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+
+use super::color::{MATCH_STYLE, RESET};
+
+const ID_STYLE: &str = "\x1b[35m";
+const LINE_STYLE: &str = "\x1b[32m";
+
+/// How `grep` matches and prints.
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Only search the source with this label.
+    pub source: Option<String>,
+    pub ignore_case: bool,
+    /// Color IDs, line numbers, and matches with ANSI escapes.
+    pub color: bool,
+}
+
+/// Print every line of a cached definition body that matches `pattern` as
+/// `id:line: text`, a definition at a time as the stores are read. Returns
+/// how many lines matched.
+pub fn run<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    pattern: &str,
+    options: &GrepOptions,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(options.ignore_case)
+        .build()?;
+
+    let mut matched = 0;
+    let mut write_error = None;
+    for store in stores {
+        if options
+            .source
+            .as_deref()
+            .is_some_and(|source| source != store.label())
+        {
+            continue;
+        }
+        store.for_each_body(|id, body| {
+            for (i, line) in body.lines().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                matched += 1;
+                let result = if options.color {
+                    writeln!(
+                        output,
+                        "{ID_STYLE}{id}{RESET}:{LINE_STYLE}{}{RESET}: {}",
+                        i + 1,
+                        highlight(&regex, line)
+                    )
+                } else {
+                    writeln!(output, "{id}:{}: {line}", i + 1)
+                };
+                if let Err(e) = result {
+                    write_error = Some(e);
+                    return false;
+                }
+            }
+            true
+        })?;
+        match write_error.take() {
+            // The reader went away, as with `| head`; that's not a failure.
+            Some(e) if e.kind() == ErrorKind::BrokenPipe => return Ok(matched),
+            Some(e) => return Err(e.into()),
+            None => {}
+        }
+    }
+    Ok(matched)
+}
+
+fn highlight(regex: &Regex, line: &str) -> String {
+    regex
+        .replace_all(line, |caps: &regex::Captures| {
+            format!("{MATCH_STYLE}{}{RESET}", &caps[0])
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs::{Definition, DefinitionId, DefinitionKind};
+
+    use super::*;
+
+    fn store(label: &str, bodies: &[(&str, &str)]) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        for (id, body) in bodies {
+            store
+                .upsert_definition(&Definition {
+                    id: DefinitionId::new(*id),
+                    name: id.to_string(),
+                    description: None,
+                    kind: DefinitionKind::Agent,
+                    category: None,
+                    source_label: label.into(),
+                    body: body.to_string(),
+                    tools: vec![],
+                    model: None,
+                    metadata: HashMap::new(),
+                    raw: body.to_string(),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn grep(stores: &[DefinitionStore], pattern: &str, options: &GrepOptions) -> (usize, String) {
+        let mut output = Vec::new();
+        let matched = run(stores, pattern, options, &mut output).unwrap();
+        (matched, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn prints_matching_lines_with_ids_and_line_numbers() {
+        let stores = [
+            store(
+                "acme",
+                &[("agents/api.md", "Design APIs.\nUse the GitHub API.\n")],
+            ),
+            store(
+                "other",
+                &[("agents/ci.md", "Runs CI.\ngithub actions only\n")],
+            ),
+        ];
+
+        let (matched, out) = grep(&stores, r"GitHub\s+API", &GrepOptions::default());
+        assert_eq!(matched, 1);
+        assert_eq!(out, "agents/api.md:2: Use the GitHub API.\n");
+
+        let options = GrepOptions {
+            ignore_case: true,
+            ..GrepOptions::default()
+        };
+        let (matched, out) = grep(&stores, "github", &options);
+        assert_eq!(matched, 2);
+        assert!(
+            out.ends_with("agents/ci.md:2: github actions only\n"),
+            "{out}"
+        );
+
+        let options = GrepOptions {
+            source: Some("other".into()),
+            color: true,
+            ..options
+        };
+        let (_, out) = grep(&stores, "github", &options);
+        assert_eq!(
+            out,
+            "\x1b[35magents/ci.md\x1b[0m:\x1b[32m2\x1b[0m: \x1b[1;31mgithub\x1b[0m actions only\n"
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_are_errors() {
+        let stores = [store("acme", &[])];
+        assert!(
+            run(
+                &stores,
+                "(unclosed",
+                &GrepOptions::default(),
+                &mut Vec::new()
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use std::path::Path;
+
+use agent_defs::{HideRule, HideRules};
+use agent_defs_cli::config;
+use anyhow::Result;
+
+/// Add `rule` to the hide rules stored at `path`.
+pub fn hide(path: &Path, rule: HideRule, output: &mut impl Write) -> Result<()> {
+    let mut rules = read(path);
+    let description = describe(&rule);
+    if rules.add(rule) {
+        config::write_hidden(path, &rules)?;
+        writeln!(output, "Hiding {description}")?;
+    } else {
+        writeln!(output, "Already hiding {description}")?;
+    }
+    Ok(())
+}
+
+/// Remove `rule` from the hide rules stored at `path`.
+pub fn unhide(path: &Path, rule: &HideRule, output: &mut impl Write) -> Result<()> {
+    let mut rules = read(path);
+    if rules.remove(rule) {
+        config::write_hidden(path, &rules)?;
+        writeln!(output, "No longer hiding {}", describe(rule))?;
+    } else {
+        writeln!(output, "Not hiding {}; `hide` lists the rules", describe(rule))?;
+    }
+    Ok(())
+}
+
+/// Print the rules in the form `unhide` accepts.
+pub fn list(rules: &HideRules, output: &mut impl Write) -> Result<()> {
+    if rules.is_empty() {
+        writeln!(output, "Nothing is hidden.")?;
+        return Ok(());
+    }
+    for rule in rules.rules() {
+        let args = match rule {
+            HideRule::Id(id) => id.to_string(),
+            HideRule::Category(category) => format!("--category {category}"),
+            HideRule::Glob(pattern) => format!("--glob '{pattern}'"),
+        };
+        writeln!(output, "{args}")?;
+    }
+    Ok(())
+}
+
+/// Tell the user, on stderr, that some results were left out.
+pub fn note_hidden(count: usize) {
+    if count > 0 {
+        eprintln!(
+            "({count} hidden {}; pass --all to include them)",
+            if count == 1 { "definition" } else { "definitions" }
+        );
+    }
+}
+
+fn read(path: &Path) -> HideRules {
+    std::fs::read_to_string(path)
+        .map(|text| HideRules::parse(&text))
+        .unwrap_or_default()
+}
+
+fn describe(rule: &HideRule) -> String {
+    match rule {
+        HideRule::Id(id) => id.to_string(),
+        HideRule::Category(category) => format!("category {category}"),
+        HideRule::Glob(pattern) => format!("IDs matching {pattern}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::DefinitionId;
+
+    use super::*;
+
+    #[test]
+    fn hide_and_unhide_update_the_file() {
+        let dir = std::env::temp_dir().join("agent-defs-test-hide-command");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("hidden");
+        let rule = HideRule::Category("blockchain".into());
+
+        let mut output = Vec::new();
+        hide(&path, rule.clone(), &mut output).unwrap();
+        hide(&path, rule.clone(), &mut output).unwrap();
+        hide(&path, HideRule::Id(DefinitionId::new("agents/a.md")), &mut output).unwrap();
+        assert_eq!(read(&path).rules().len(), 2);
+
+        unhide(&path, &rule, &mut output).unwrap();
+        unhide(&path, &rule, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Hiding category blockchain\n\
+             Already hiding category blockchain\n\
+             Hiding agents/a.md\n\
+             No longer hiding category blockchain\n\
+             Not hiding category blockchain; `hide` lists the rules\n"
+        );
+        assert_eq!(read(&path).rules(), [HideRule::Id(DefinitionId::new("agents/a.md"))]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn list_prints_unhide_arguments() {
+        let rules = HideRules::parse("id:agents/a.md\ncategory:web3\nglob:skills/*");
+        let mut output = Vec::new();
+        list(&rules, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "agents/a.md\n--category web3\n--glob 'skills/*'\n"
+        );
+    }
+}
@@ -1,33 +1,239 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-use agent_defs::{DefinitionId, Source, install};
+use agent_defs::merge::MergeConflict;
+use agent_defs::template::{self, TemplateVar};
+use agent_defs::{Definition, DefinitionKind, InstallError, InstallRules, Source, install, merge};
 use anyhow::{Result, bail};
 
+/// Install the definition `id` names into `target`, returning it, the
+/// file it went to, and the template variable values it was filled in
+/// with.
 pub async fn run(
-    sources: &[Box<dyn Source>],
+    sources: &[impl Source],
     id: &str,
     target: &Path,
+    rules: &InstallRules,
     source_filter: Option<&str>,
-) -> Result<()> {
-    let def_id = DefinitionId::new(id);
+    vars: Vec<(String, String)>,
+    yes: bool,
+) -> Result<(Definition, PathBuf, HashMap<String, String>)> {
+    let def = super::lookup::fetch_one(sources, id, source_filter).await?;
+    rules.policy.check_install(&def)?;
+    let interactive = std::io::stdin().is_terminal();
+    if def.kind == DefinitionKind::Hook && !yes {
+        if !interactive {
+            bail!("{id} is a hook, which runs shell commands; pass --yes to install it");
+        }
+        if !confirm_hook(&def, &mut std::io::stdin().lock(), &mut std::io::stderr())? {
+            bail!("install cancelled");
+        }
+    }
+    let mut values: HashMap<String, String> = vars.into_iter().collect();
+    let declared = template::declared_vars(&def.raw);
+    if interactive {
+        prompt_for_vars(
+            &declared,
+            &mut values,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stderr(),
+        )?;
+    }
+    if interactive && let Some(config) = install::merge_target(target, &def) {
+        let content = template::render(&def.raw, &values)
+            .map_err(|e| anyhow::anyhow!("{e} (pass --var NAME=VALUE for each)"))?;
+        let plan = merge::plan(&config, &content)?;
+        let keep = prompt_for_conflicts(
+            &plan.conflicts,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stderr(),
+        )?;
+        let outcome = plan.apply(|c| keep.contains(&c.path))?;
+        println!("Merged into {}", outcome.path.display());
+        if let Some(backup) = outcome.backup {
+            println!("Previous version saved to {}", backup.display());
+        }
+        return Ok((def, outcome.path, values));
+    }
+    let path = match rules.layout.install(target, &def, &values) {
+        Err(InstallError::Template(e)) => bail!("{e} (pass --var NAME=VALUE for each)"),
+        result => result?,
+    };
+    println!("Installed to {}", path.display());
+    Ok((def, path, values))
+}
+
+/// Parse a `--var NAME=VALUE` argument.
+pub fn parse_var(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_owned(), value.to_owned()))
+        }
+        _ => Err(format!("expected NAME=VALUE, got `{arg}`")),
+    }
+}
 
-    for source in sources {
-        if let Some(filter) = source_filter
-            && source.label() != filter
-        {
+/// Ask for each declared variable that wasn't given on the command line.
+/// An empty answer keeps the variable's default, if it has one.
+pub fn prompt_for_vars(
+    declared: &[TemplateVar],
+    values: &mut HashMap<String, String>,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<()> {
+    for var in declared {
+        if values.contains_key(&var.name) {
             continue;
         }
+        match &var.default {
+            Some(default) => write!(output, "{} [{default}]: ", var.name)?,
+            None => write!(output, "{}: ", var.name)?,
+        }
+        output.flush()?;
 
-        match source.fetch(&def_id).await {
-            Ok(def) => {
-                let path = install::install_definition(target, &def)?;
-                println!("Installed to {}", path.display());
-                return Ok(());
-            }
-            Err(agent_defs::SourceError::NotFound(_)) => continue,
-            Err(e) => return Err(e.into()),
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            break;
+        }
+        let answer = answer.trim_end_matches(['\r', '\n']);
+        if !answer.is_empty() {
+            values.insert(var.name.clone(), answer.to_owned());
         }
     }
+    Ok(())
+}
 
-    bail!("Definition not found: {id}");
+/// Show the shell commands a hook runs and ask before installing it.
+/// Anything but `y`/`yes` declines.
+pub fn confirm_hook(
+    def: &Definition,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<bool> {
+    let commands = def.hook_commands();
+    if commands.is_empty() {
+        writeln!(output, "{} is a hook and may run shell commands.", def.name)?;
+    } else {
+        writeln!(output, "{} is a hook that runs these shell commands:", def.name)?;
+        for command in &commands {
+            writeln!(output, "  $ {command}")?;
+        }
+    }
+    write!(output, "Install it? [y/N]: ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Ask which side wins for each key the config file already sets
+/// differently. Returns the paths of the keys where the file's value is
+/// kept; an empty answer takes the definition's.
+pub fn prompt_for_conflicts(
+    conflicts: &[MergeConflict],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<HashSet<Vec<String>>> {
+    let mut keep = HashSet::new();
+    for conflict in conflicts {
+        writeln!(output, "{} is already set:", conflict.key())?;
+        writeln!(output, "  current:    {}", conflict.existing)?;
+        writeln!(output, "  definition: {}", conflict.incoming)?;
+        write!(output, "Keep the current value? [y/N]: ")?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            bail!("install cancelled");
+        }
+        if matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+            keep.insert(conflict.path.clone());
+        }
+    }
+    Ok(keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::DefinitionId;
+
+    use super::*;
+
+    fn var(name: &str, default: Option<&str>) -> TemplateVar {
+        TemplateVar {
+            name: name.to_owned(),
+            default: default.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn parse_var_splits_on_first_equals() {
+        assert_eq!(parse_var("A=b=c"), Ok(("A".into(), "b=c".into())));
+        assert_eq!(parse_var("A="), Ok(("A".into(), String::new())));
+        assert!(parse_var("A").is_err());
+        assert!(parse_var("=b").is_err());
+    }
+
+    #[test]
+    fn prompts_only_for_vars_not_given() {
+        let declared = [var("PROJECT", None), var("LANGUAGE", Some("Rust"))];
+        let mut values = HashMap::from([("PROJECT".to_owned(), "crate".to_owned())]);
+        let mut output = Vec::new();
+        prompt_for_vars(&declared, &mut values, &mut "Go\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "LANGUAGE [Rust]: ");
+        assert_eq!(values["LANGUAGE"], "Go");
+    }
+
+    #[test]
+    fn empty_answer_keeps_default() {
+        let declared = [var("LANGUAGE", Some("Rust")), var("PROJECT", None)];
+        let mut values = HashMap::new();
+        prompt_for_vars(&declared, &mut values, &mut "\n".as_bytes(), &mut Vec::new()).unwrap();
+
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn conflict_prompts_collect_the_keys_to_keep() {
+        let conflict = |key: &str| MergeConflict {
+            path: vec![key.to_owned(), "command".to_owned()],
+            existing: "docker".into(),
+            incoming: "npx".into(),
+        };
+        let conflicts = [conflict("a"), conflict("b")];
+        let mut output = Vec::new();
+        let keep = prompt_for_conflicts(&conflicts, &mut "y\n\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(keep, HashSet::from([vec!["a".to_owned(), "command".to_owned()]]));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("current:    \"docker\""), "{output}");
+
+        let cancelled = prompt_for_conflicts(&conflicts, &mut "y\n".as_bytes(), &mut Vec::new());
+        assert!(cancelled.is_err());
+    }
+
+    #[test]
+    fn hook_confirmation_lists_commands_and_defaults_to_no() {
+        let def = Definition {
+            id: DefinitionId::new("hooks/format.json"),
+            name: "format".into(),
+            description: None,
+            kind: DefinitionKind::Hook,
+            category: None,
+            source_label: "acme".into(),
+            body: r#"{"hooks": {"Stop": [{"hooks": [{"command": "cargo fmt"}]}]}}"#.into(),
+            tools: vec![],
+            model: None,
+            metadata: Default::default(),
+            raw: String::new(),
+        };
+        let mut output = Vec::new();
+        assert!(confirm_hook(&def, &mut "yes\n".as_bytes(), &mut output).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("  $ cargo fmt\n"), "{output}");
+
+        assert!(!confirm_hook(&def, &mut "\n".as_bytes(), &mut Vec::new()).unwrap());
+    }
 }
@@ -1,18 +1,94 @@
-use agent_defs::{DefinitionKind, Source};
+use std::io::IsTerminal;
+
+use agent_defs::{DefinitionKind, DefinitionSummary, HideRules, Rating, Source};
 use anyhow::Result;
 
 use super::format;
 
-pub async fn run(
-    sources: &[Box<dyn Source>],
-    kind_filter: Option<&str>,
-    source_filter: Option<&str>,
-) -> Result<()> {
-    let kind_predicate = kind_filter.map(DefinitionKind::parse);
+/// Column `list` sorts its table by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    /// Kinds in their usual order (agents first), then by name.
+    Kind,
+    Source,
+    /// Most recently added first; undated definitions last.
+    Added,
+    /// Most recently updated first; undated definitions last.
+    Updated,
+}
+
+/// Parse a `--sort` argument.
+pub fn parse_sort(arg: &str) -> Result<SortKey, String> {
+    match arg {
+        "name" => Ok(SortKey::Name),
+        "kind" => Ok(SortKey::Kind),
+        "source" => Ok(SortKey::Source),
+        "added" => Ok(SortKey::Added),
+        "updated" => Ok(SortKey::Updated),
+        _ => Err(format!(
+            "expected name, kind, source, added, or updated, got `{arg}`"
+        )),
+    }
+}
+
+/// Order `summaries` by `key`, breaking ties by name and then source, so
+/// the order is the same every run.
+pub fn sort(summaries: &mut [DefinitionSummary], key: SortKey, reverse: bool) {
+    summaries.sort_by(|a, b| {
+        let by_name = a
+            .name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.source_label.cmp(&b.source_label));
+        match key {
+            SortKey::Name => by_name,
+            SortKey::Kind => format::kind_sort_key(&a.kind)
+                .cmp(&format::kind_sort_key(&b.kind))
+                .then(by_name),
+            SortKey::Source => a.source_label.cmp(&b.source_label).then(by_name),
+            SortKey::Added => b.first_seen_at.cmp(&a.first_seen_at).then(by_name),
+            SortKey::Updated => b.last_updated_at.cmp(&a.last_updated_at).then(by_name),
+        }
+    });
+    if reverse {
+        summaries.reverse();
+    }
+}
+
+/// Columns available to the table: the terminal's width, or `COLUMNS`
+/// when it can't be asked. `None` (no truncation) when output is piped.
+fn table_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(cols, _)| cols as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+}
+
+/// Which definitions `list` prints, and how.
+pub struct ListOptions<'a> {
+    /// Only definitions of this kind.
+    pub kind: Option<&'a str>,
+    /// Only the source with this label.
+    pub source: Option<&'a str>,
+    pub hidden: &'a HideRules,
+    /// Community ratings, shown next to the definitions they rate.
+    pub ratings: &'a [Rating],
+    pub plain: bool,
+    pub sort: SortKey,
+    pub reverse: bool,
+}
+
+pub async fn run(sources: &[impl Source], options: &ListOptions<'_>) -> Result<()> {
+    let kind_predicate = options.kind.map(DefinitionKind::parse);
     let mut all = Vec::new();
+    let mut hidden_count = 0;
 
     for source in sources {
-        if let Some(filter) = source_filter
+        if let Some(filter) = options.source
             && source.label() != filter
         {
             continue;
@@ -26,12 +102,67 @@ pub async fn run(
             {
                 continue;
             }
+            if options.hidden.hides(&summary) {
+                hidden_count += 1;
+                continue;
+            }
 
             all.push(summary);
         }
     }
 
-    format::print_summary_table(&all);
+    if options.plain {
+        format::print_summary_plain(&all, options.ratings);
+    } else if !all.is_empty() {
+        sort(&mut all, options.sort, options.reverse);
+        let color = super::color::stdout();
+        print!("{}", format::list_table(&all, options.ratings, table_width(), color));
+    }
+    super::hide::note_hidden(hidden_count);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(name: &str, kind: DefinitionKind, source: &str) -> DefinitionSummary {
+        DefinitionSummary {
+            kind,
+            source_label: source.to_owned(),
+            ..agent_defs::test_support::definition(name).summary()
+        }
+    }
+
+    fn names(summaries: &[DefinitionSummary]) -> Vec<&str> {
+        summaries.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_each_key_with_name_as_tie_breaker() {
+        let mut summaries = vec![
+            summary("zeta", DefinitionKind::Agent, "b"),
+            summary("fmt", DefinitionKind::Hook, "a"),
+            summary("Alpha", DefinitionKind::Agent, "c"),
+        ];
+        sort(&mut summaries, SortKey::Kind, false);
+        assert_eq!(names(&summaries), ["Alpha", "zeta", "fmt"]);
+        sort(&mut summaries, SortKey::Name, false);
+        assert_eq!(names(&summaries), ["Alpha", "fmt", "zeta"]);
+        sort(&mut summaries, SortKey::Source, true);
+        assert_eq!(names(&summaries), ["Alpha", "zeta", "fmt"]);
+
+        summaries[0].first_seen_at = Some(5);
+        summaries[2].first_seen_at = Some(9);
+        sort(&mut summaries, SortKey::Added, false);
+        assert_eq!(names(&summaries), ["fmt", "Alpha", "zeta"]);
+    }
+
+    #[test]
+    fn parses_sort_keys() {
+        assert_eq!(parse_sort("source"), Ok(SortKey::Source));
+        assert_eq!(parse_sort("updated"), Ok(SortKey::Updated));
+        assert!(parse_sort("date").is_err());
+    }
+}
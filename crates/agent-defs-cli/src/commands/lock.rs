@@ -0,0 +1,806 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use agent_defs::diff::{self, DiffStat};
+use agent_defs::template;
+use agent_defs::{
+    Definition, DefinitionId, DefinitionKind, InstallRules, LockedDefinition, Lockfile, Source,
+    install, merge,
+};
+use agent_defs_store::DefinitionStore;
+use anyhow::{Result, bail};
+
+/// Pin each installed definition in `target`'s lockfile at the revision
+/// its source last synced, along with the `values` given for the template
+/// variables it declares. With `bases`, each file as written is kept there
+/// for merging local edits on a later update.
+pub fn pin<'a>(
+    target: &Path,
+    installed: impl IntoIterator<Item = (&'a Definition, &'a Path)>,
+    revisions: &HashMap<String, String>,
+    values: &HashMap<String, String>,
+    bases: Option<&DefinitionStore>,
+) -> Result<()> {
+    let mut lockfile = Lockfile::load(target)?;
+    let before = lockfile.clone();
+    for (def, path) in installed {
+        let revision = revisions.get(&def.source_label).cloned();
+        let vars = template::declared_vars(&def.raw)
+            .into_iter()
+            .filter_map(|var| Some((var.name.clone(), values.get(&var.name)?.clone())))
+            .collect();
+        let mut locked = LockedDefinition::new(def, target, path, revision).with_vars(vars);
+        if let Ok(content) = std::fs::read_to_string(path) {
+            locked = locked.with_installed(&content);
+            if let Some(bases) = bases
+                && locked.installed_sha256.is_some()
+            {
+                bases.keep_content(&content)?;
+            }
+        }
+        lockfile.pin(locked);
+    }
+    if lockfile != before {
+        lockfile.save(target)?;
+    }
+    Ok(())
+}
+
+/// Install what `target`'s lockfile pins, each to the path it was locked
+/// at: everything, or only `id` when given. Template variables take the
+/// pinned values unless `values` overrides them. Nothing is written if any
+/// of them has changed since it was locked.
+pub async fn install_locked(
+    sources: &[impl Source],
+    target: &Path,
+    rules: &InstallRules,
+    id: Option<&str>,
+    values: &HashMap<String, String>,
+    allow_hooks: bool,
+    output: &mut impl Write,
+) -> Result<Vec<Definition>> {
+    let lockfile = load_pins(target)?;
+    let pinned: Vec<&LockedDefinition> = match id {
+        Some(id) => vec![find_pin(&lockfile, target, id)?],
+        None => lockfile.definitions.iter().collect(),
+    };
+
+    let mut defs = Vec::new();
+    let mut changed = Vec::new();
+    for locked in &pinned {
+        let def = fetch(sources, locked).await?;
+        rules.policy.check_install(&def)?;
+        if def.kind == DefinitionKind::Hook && !allow_hooks {
+            bail!(
+                "{} is a hook, which runs shell commands; pass --yes to install it",
+                locked.qualified_id()
+            );
+        }
+        if !locked.matches(&def) {
+            changed.push(locked.qualified_id().as_str().to_owned());
+        }
+        defs.push(def);
+    }
+    if !changed.is_empty() {
+        bail!(
+            "changed since {} was written:\n  {}\nRun `agent-def-fetcher update` to take the new \
+             versions.",
+            Lockfile::path(target).display(),
+            changed.join("\n  ")
+        );
+    }
+
+    for (locked, def) in pinned.iter().zip(&defs) {
+        let path = target.join(&locked.path);
+        let mut pinned_values = locked.values();
+        pinned_values.extend(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+        agent_defs::install::install_content(&path, &render(def, &pinned_values)?)?;
+        writeln!(
+            output,
+            "Installed {} to {}",
+            locked.qualified_id(),
+            path.display()
+        )?;
+    }
+    Ok(defs)
+}
+
+/// What `update` would change in a target: each pinned definition whose
+/// source now has different content, ready to be written.
+pub struct UpdatePlan {
+    lockfile: Lockfile,
+    pub pending: Vec<PendingUpdate>,
+    /// How many pins already match their sources.
+    pub current: usize,
+    /// How many pins are marked keep-local and weren't checked.
+    pub kept: usize,
+    /// Pins that couldn't be checked or aren't allowed, with why.
+    pub skipped: Vec<(DefinitionId, String)>,
+}
+
+/// What [`UpdatePlan::apply`] wrote.
+pub struct Applied {
+    pub updated: Vec<Definition>,
+    /// Pending changes that couldn't be written, with why. The rest are
+    /// still re-pinned.
+    pub failed: Vec<(DefinitionId, String)>,
+}
+
+/// One pinned definition whose source has changed.
+pub struct PendingUpdate {
+    pub locked: LockedDefinition,
+    pub def: Definition,
+    pub path: PathBuf,
+    /// The new file, with template variables filled in as they were at
+    /// install.
+    content: String,
+    /// What will be written: `content`, or it merged with local edits.
+    written: String,
+    /// Lines changed against the installed file, or `None` when it's gone.
+    pub stat: Option<DiffStat>,
+    /// Whether the installed file was edited after it was written.
+    pub edited: bool,
+    /// When local edits were merged with the new version, how many places
+    /// conflict.
+    pub conflicts: Option<usize>,
+}
+
+impl PendingUpdate {
+    fn new(locked: LockedDefinition, def: Definition, path: PathBuf, content: String) -> Self {
+        Self {
+            written: content.clone(),
+            locked,
+            def,
+            path,
+            content,
+            stat: None,
+            edited: false,
+            conflicts: None,
+        }
+    }
+
+    /// Compare with `installed`, the file at the pinned path now, merging
+    /// in local edits when `base`, the file as it was written, is known.
+    fn against(mut self, installed: &str, base: Option<String>) -> Self {
+        self.edited = self.locked.edited(installed);
+        if self.edited
+            && let Some(base) = base
+        {
+            let merged = diff::merge(&base, installed, &self.content);
+            self.conflicts = Some(merged.conflicts);
+            self.written = merged.text;
+        }
+        self.stat = Some(DiffStat::between(installed, &self.written));
+        self
+    }
+
+    /// Write the update, and keep the new version in `bases`. A JSON
+    /// config file other installs share is merged into, taking the new
+    /// version's value for every key it changes.
+    fn write(&self, bases: Option<&DefinitionStore>) -> Result<()> {
+        if self.path.extension().is_some_and(|ext| ext == "json") {
+            merge::plan(&self.path, &self.content)?.apply(|_| false)?;
+        } else {
+            install::install_content(&self.path, &self.written)?;
+        }
+        if let Some(bases) = bases {
+            bases.keep_content(&self.content)?;
+        }
+        Ok(())
+    }
+}
+
+/// Work out what updating would change: for every pin in `target`'s
+/// lockfile, or only `ids`, compare the pinned content with what its
+/// source has now. Where the installed file was edited and `bases` has it
+/// as it was written, the edits are merged into the new version. Nothing
+/// is written.
+pub async fn plan_update(
+    sources: &[impl Source],
+    target: &Path,
+    rules: &InstallRules,
+    ids: &[String],
+    bases: Option<&DefinitionStore>,
+) -> Result<UpdatePlan> {
+    let lockfile = load_pins(target)?;
+    let pinned: Vec<LockedDefinition> = if ids.is_empty() {
+        lockfile.definitions.clone()
+    } else {
+        ids.iter()
+            .map(|id| find_pin(&lockfile, target, id).cloned())
+            .collect::<Result<_>>()?
+    };
+
+    let mut plan = UpdatePlan {
+        lockfile,
+        pending: Vec::new(),
+        current: 0,
+        kept: 0,
+        skipped: Vec::new(),
+    };
+    for locked in pinned {
+        if locked.keep_local {
+            plan.kept += 1;
+            continue;
+        }
+        let checked = async {
+            let def = fetch(sources, &locked).await?;
+            if locked.matches(&def) {
+                return Ok(None);
+            }
+            rules.policy.check_install(&def)?;
+            let content = render(&def, &locked.values())?;
+            let path = target.join(&locked.path);
+            let installed = std::fs::read_to_string(&path).ok();
+            Ok::<_, anyhow::Error>(Some((def, path, content, installed)))
+        };
+        match checked.await {
+            Ok(Some((def, path, content, installed))) => {
+                let base = match (bases, &locked.installed_sha256) {
+                    (Some(bases), Some(hash)) => bases.content(hash)?,
+                    _ => None,
+                };
+                let pending = PendingUpdate::new(locked, def, path, content);
+                plan.pending.push(match installed {
+                    Some(installed) => pending.against(&installed, base),
+                    None => pending,
+                });
+            }
+            Ok(None) => plan.current += 1,
+            Err(e) => plan.skipped.push((locked.qualified_id(), e.to_string())),
+        }
+    }
+    Ok(plan)
+}
+
+impl UpdatePlan {
+    /// Whether nothing would change.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// List the pending changes, with the revisions in `revisions` each
+    /// would move to, and what was skipped.
+    pub fn print(
+        &self,
+        revisions: &HashMap<String, String>,
+        output: &mut impl Write,
+    ) -> Result<()> {
+        for (id, reason) in &self.skipped {
+            writeln!(output, "Skipped {id}: {reason}")?;
+        }
+        if !self.pending.is_empty() {
+            let noun = if self.pending.len() == 1 {
+                "definition has"
+            } else {
+                "definitions have"
+            };
+            writeln!(output, "{} pinned {noun} changed:", self.pending.len())?;
+        }
+        for pending in &self.pending {
+            let mut line = format!("  {}", pending.locked.qualified_id());
+            if let Some(change) = revision_change(&pending.locked, revisions) {
+                line.push_str(&format!("  {change}"));
+            }
+            match (pending.stat, pending.conflicts) {
+                (Some(stat), Some(0)) => {
+                    line.push_str(&format!("  ({stat} lines, merged with local edits)"))
+                }
+                (Some(stat), Some(conflicts)) => line.push_str(&format!(
+                    "  ({stat} lines, {conflicts} {} with local edits)",
+                    if conflicts == 1 { "conflict" } else { "conflicts" }
+                )),
+                (Some(stat), None) if pending.edited => {
+                    line.push_str(&format!("  ({stat} lines, edited locally)"))
+                }
+                (Some(stat), None) => line.push_str(&format!("  ({stat} lines)")),
+                (None, _) => line.push_str("  (not installed)"),
+            }
+            writeln!(output, "{line}")?;
+        }
+        if self.pending.iter().any(|p| p.conflicts.is_some_and(|n| n > 0)) {
+            writeln!(
+                output,
+                "Conflicting changes are written between <<<<<<< local and >>>>>>> upstream \
+                 markers for you to resolve."
+            )?;
+        }
+        if self
+            .pending
+            .iter()
+            .any(|pending| pending.edited && pending.conflicts.is_none())
+        {
+            writeln!(
+                output,
+                "Updating overwrites local edits; run `agent-def-fetcher keep-local ID` to keep \
+                 them and skip that definition from now on."
+            )?;
+        }
+        let mut totals = format!("{} already up to date", self.current);
+        if self.kept > 0 {
+            totals.push_str(&format!(", {} kept local", self.kept));
+        }
+        writeln!(output, "{totals}")?;
+        Ok(())
+    }
+
+    /// Write every pending change where its pin says it was installed and
+    /// re-pin it at its source's revision in `revisions`, keeping the new
+    /// version in `bases` to merge against next time. One that fails to
+    /// write is reported and left pinned as it was; the lockfile is still
+    /// saved for the rest.
+    pub fn apply(
+        mut self,
+        target: &Path,
+        revisions: &HashMap<String, String>,
+        bases: Option<&DefinitionStore>,
+        output: &mut impl Write,
+    ) -> Result<Applied> {
+        let mut updated = Vec::new();
+        let mut failed = Vec::new();
+        let mut conflicted = 0;
+        for pending in self.pending {
+            let id = pending.locked.qualified_id();
+            if let Err(e) = pending.write(bases) {
+                writeln!(output, "Failed to update {id}: {e}")?;
+                failed.push((id, e.to_string()));
+                continue;
+            }
+            let change = revision_change(&pending.locked, revisions)
+                .map(|change| format!(" ({change})"))
+                .unwrap_or_default();
+            match pending.conflicts {
+                Some(0) => writeln!(output, "Updated {id}{change}, keeping local edits")?,
+                Some(conflicts) => {
+                    conflicted += 1;
+                    writeln!(
+                        output,
+                        "Updated {id}{change} with {conflicts} conflicting {}; resolve the \
+                         markers in {}",
+                        if conflicts == 1 { "change" } else { "changes" },
+                        pending.path.display()
+                    )?
+                }
+                None => writeln!(output, "Updated {id}{change}")?,
+            }
+            let revision = revisions.get(&pending.locked.source).cloned();
+            let locked = LockedDefinition::new(&pending.def, target, &pending.path, revision)
+                .with_vars(pending.locked.vars.clone())
+                .with_installed(&pending.content);
+            self.lockfile.pin(locked);
+            updated.push(pending.def);
+        }
+        if !updated.is_empty() {
+            self.lockfile.save(target)?;
+        }
+        let mut totals = format!("{} updated", updated.len());
+        if conflicted > 0 {
+            totals.push_str(&format!(", {conflicted} with conflicts to resolve"));
+        }
+        if !failed.is_empty() {
+            totals.push_str(&format!(", {} failed", failed.len()));
+        }
+        writeln!(output, "{totals}")?;
+        Ok(Applied { updated, failed })
+    }
+}
+
+/// Mark the pins `ids` name as customized, so `update` skips them, or
+/// with `keep` false, let it update them again. With no IDs, list the pins
+/// marked.
+pub fn keep_local(
+    target: &Path,
+    ids: &[String],
+    keep: bool,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut lockfile = load_pins(target)?;
+    if ids.is_empty() {
+        let kept: Vec<&LockedDefinition> = lockfile
+            .definitions
+            .iter()
+            .filter(|locked| locked.keep_local)
+            .collect();
+        if kept.is_empty() {
+            writeln!(
+                output,
+                "Nothing in {} is kept local.",
+                Lockfile::path(target).display()
+            )?;
+        }
+        for locked in kept {
+            writeln!(output, "{}", locked.qualified_id())?;
+        }
+        return Ok(());
+    }
+
+    for id in ids {
+        let locked = find_pin(&lockfile, target, id)?;
+        let qualified = locked.qualified_id();
+        let locked = LockedDefinition {
+            keep_local: keep,
+            ..locked.clone()
+        };
+        lockfile.pin(locked);
+        if keep {
+            writeln!(output, "Keeping local edits to {qualified}; `update` will skip it")?;
+        } else {
+            writeln!(output, "{qualified} will be updated again")?;
+        }
+    }
+    lockfile.save(target)?;
+    Ok(())
+}
+
+/// Ask before an update rewrites files. Anything but `y`/`yes` declines.
+pub fn confirm_update(input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    write!(output, "Update them? [y/N]: ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// `old -> new` when `locked`'s source has moved to a different revision.
+fn revision_change(
+    locked: &LockedDefinition,
+    revisions: &HashMap<String, String>,
+) -> Option<String> {
+    match (&locked.revision, revisions.get(&locked.source)) {
+        (Some(old), Some(new)) if old != new => Some(format!("{old} -> {new}")),
+        _ => None,
+    }
+}
+
+/// `target`'s lockfile, which must pin something.
+fn load_pins(target: &Path) -> Result<Lockfile> {
+    let lockfile = Lockfile::load(target)?;
+    if lockfile.definitions.is_empty() {
+        bail!(
+            "{} pins nothing yet; install definitions there first",
+            Lockfile::path(target).display()
+        );
+    }
+    Ok(lockfile)
+}
+
+/// The pin `id` names, given as `source:id` or, when only one source has
+/// it pinned, as a bare ID.
+fn find_pin<'a>(lockfile: &'a Lockfile, target: &Path, id: &str) -> Result<&'a LockedDefinition> {
+    let matches: Vec<&LockedDefinition> = lockfile
+        .definitions
+        .iter()
+        .filter(|locked| locked.qualified_id().as_str() == id || locked.id == id)
+        .collect();
+    match matches[..] {
+        [locked] => Ok(locked),
+        [] => bail!("{id} isn't pinned in {}", Lockfile::path(target).display()),
+        _ => bail!("{id} is pinned from more than one source; qualify it as source:id"),
+    }
+}
+
+async fn fetch(sources: &[impl Source], locked: &LockedDefinition) -> Result<Definition> {
+    super::lookup::fetch_one(sources, &locked.id, Some(&locked.source)).await
+}
+
+/// `def`'s file with its template variables filled from `values` or their
+/// defaults.
+fn render(def: &Definition, values: &HashMap<String, String>) -> Result<String> {
+    match template::render(&def.raw, values) {
+        Ok(content) => Ok(content),
+        Err(e) => bail!("{e} (pass --var NAME=VALUE for each)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::InMemorySource;
+
+    use super::*;
+
+    fn reviewer(raw: &str) -> Definition {
+        Definition {
+            source_label: "acme".into(),
+            raw: raw.into(),
+            ..agent_defs::test_support::definition("agents/reviewer.md")
+        }
+    }
+
+    fn source(raw: &str) -> Vec<InMemorySource> {
+        vec![InMemorySource::new("acme").with(reviewer(raw))]
+    }
+
+    fn pin_reviewer(target: &Path, raw: &str, revision: &str) {
+        let def = reviewer(raw);
+        let path = target.join(".claude/agents/reviewer.md");
+        let revisions = HashMap::from([("acme".to_owned(), revision.to_owned())]);
+        pin(target, [(&def, path.as_path())], &revisions, &HashMap::new(), None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn locked_installs_refuse_changed_content() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-install");
+        let _ = std::fs::remove_dir_all(&target);
+        pin_reviewer(&target, "Reviews code.", "1111111");
+
+        let rules = InstallRules::default();
+        let mut output = Vec::new();
+        let (sources, values) = (source("Reviews code."), HashMap::new());
+        install_locked(&sources, &target, &rules, None, &values, false, &mut output)
+            .await
+            .unwrap();
+        let path = target.join(".claude/agents/reviewer.md");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Reviews code.");
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .starts_with("Installed acme:agents/reviewer.md")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        let sources = source("Reviews code, differently.");
+        let err = install_locked(
+            &sources,
+            &target,
+            &rules,
+            None,
+            &values,
+            false,
+            &mut Vec::new(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("  acme:agents/reviewer.md\n"), "{err}");
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_shows_changes_before_repinning_them() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-update");
+        let _ = std::fs::remove_dir_all(&target);
+        pin_reviewer(&target, "Reviews code.", "1111111");
+        let path = target.join(".claude/agents/reviewer.md");
+        agent_defs::install::install_content(&path, "Reviews code.").unwrap();
+
+        let rules = InstallRules::default();
+        let revisions = HashMap::from([("acme".to_owned(), "2222222".to_owned())]);
+        let sources = source("Reviews code, differently.");
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        let mut output = Vec::new();
+        plan.print(&revisions, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1 pinned definition has changed:\n  \
+             acme:agents/reviewer.md  1111111 -> 2222222  (+1 -1 lines)\n\
+             0 already up to date\n"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Reviews code.");
+
+        let mut output = Vec::new();
+        let updated = plan.apply(&target, &revisions, None, &mut output).unwrap().updated;
+        assert_eq!(updated.len(), 1);
+        let out = String::from_utf8(output).unwrap();
+        assert!(
+            out.starts_with("Updated acme:agents/reviewer.md (1111111 -> 2222222)\n"),
+            "{out}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Reviews code, differently."
+        );
+
+        let lockfile = Lockfile::load(&target).unwrap();
+        assert_eq!(lockfile.definitions[0].revision.as_deref(), Some("2222222"));
+        assert!(lockfile.definitions[0].matches(&updated[0]));
+
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        assert!(plan.is_empty());
+        assert_eq!(plan.current, 1);
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_fills_in_the_pinned_vars() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-vars");
+        let _ = std::fs::remove_dir_all(&target);
+        let raw = "---\nvars: [PROJECT]\n---\nReview {{PROJECT}}.";
+        let def = reviewer(raw);
+        let path = target.join(".claude/agents/reviewer.md");
+        let values = HashMap::from([
+            ("PROJECT".to_owned(), "crate".to_owned()),
+            ("UNUSED".to_owned(), "x".to_owned()),
+        ]);
+        pin(&target, [(&def, path.as_path())], &HashMap::new(), &values, None).unwrap();
+        let pinned = Lockfile::load(&target).unwrap();
+        let expected = HashMap::from([("PROJECT".to_owned(), "crate".to_owned())]);
+        assert_eq!(pinned.definitions[0].values(), expected);
+
+        let rules = InstallRules::default();
+        let sources = source("---\nvars: [PROJECT]\n---\nReview {{PROJECT}} closely.");
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        plan.apply(&target, &HashMap::new(), None, &mut Vec::new())
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "---\nvars: [PROJECT]\n---\nReview crate closely."
+        );
+        let repinned = Lockfile::load(&target).unwrap();
+        assert_eq!(repinned.definitions[0].vars, pinned.definitions[0].vars);
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_merges_into_shared_config_files() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-json");
+        let _ = std::fs::remove_dir_all(&target);
+        let server = |command: &str| Definition {
+            source_label: "acme".into(),
+            raw: format!(r#"{{"mcpServers": {{"github": {{"command": "{command}"}}}}}}"#),
+            ..agent_defs::test_support::definition("mcp/github.json")
+        };
+        let path = target.join(".mcp.json");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(&path, r#"{"mcpServers": {"github": {"command": "gh-mcp"}}, "x": 1}"#)
+            .unwrap();
+        let def = server("gh-mcp");
+        let none = HashMap::new();
+        pin(&target, [(&def, path.as_path())], &none, &none, None).unwrap();
+
+        let sources = vec![InMemorySource::new("acme").with(server("gh-mcp-v2"))];
+        let plan = plan_update(&sources, &target, &InstallRules::default(), &[], None)
+            .await
+            .unwrap();
+        let applied = plan.apply(&target, &none, None, &mut Vec::new()).unwrap();
+        assert!(applied.failed.is_empty(), "{:?}", applied.failed);
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["mcpServers"]["github"]["command"], "gh-mcp-v2");
+        assert_eq!(written["x"], 1);
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_repins_what_it_could_write() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-partial");
+        let _ = std::fs::remove_dir_all(&target);
+        let def = |id: &str, raw: &str| Definition {
+            source_label: "acme".into(),
+            raw: raw.into(),
+            ..agent_defs::test_support::definition(id)
+        };
+        let reviewer_path = target.join(".claude/agents/reviewer.md");
+        let deploy_path = target.join(".claude/commands/deploy.md");
+        let none = HashMap::new();
+        let reviewer = def("agents/reviewer.md", "Reviews.");
+        let deploy = def("commands/deploy.md", "Deploys.");
+        let pins = [(&reviewer, reviewer_path.as_path()), (&deploy, deploy_path.as_path())];
+        pin(&target, pins, &none, &none, None).unwrap();
+        std::fs::create_dir_all(target.join(".claude")).unwrap();
+        // A file where the commands directory should be can't be written under.
+        std::fs::write(target.join(".claude/commands"), "").unwrap();
+
+        let sources = vec![
+            InMemorySource::new("acme")
+                .with(def("agents/reviewer.md", "Reviews closely."))
+                .with(def("commands/deploy.md", "Deploys safely.")),
+        ];
+        let plan = plan_update(&sources, &target, &InstallRules::default(), &[], None)
+            .await
+            .unwrap();
+        let mut output = Vec::new();
+        let applied = plan.apply(&target, &none, None, &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("Failed to update acme:commands/deploy.md: "), "{out}");
+        assert!(out.ends_with("1 updated, 1 failed\n"), "{out}");
+        assert_eq!(applied.failed.len(), 1);
+
+        let lockfile = Lockfile::load(&target).unwrap();
+        assert!(lockfile.definitions[0].matches(&def("agents/reviewer.md", "Reviews closely.")));
+        assert!(lockfile.definitions[1].matches(&def("commands/deploy.md", "Deploys.")));
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_flags_local_edits_and_skips_kept_ones() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-keep-local");
+        let _ = std::fs::remove_dir_all(&target);
+        let path = target.join(".claude/agents/reviewer.md");
+        agent_defs::install::install_content(&path, "Reviews code.").unwrap();
+        pin_reviewer(&target, "Reviews code.", "1111111");
+        std::fs::write(&path, "Reviews code, my way.").unwrap();
+
+        let rules = InstallRules::default();
+        let sources = source("Reviews code, differently.");
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        assert!(plan.pending[0].edited);
+        let mut output = Vec::new();
+        plan.print(&HashMap::new(), &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("(+1 -1 lines, edited locally)"), "{out}");
+
+        let ids = ["agents/reviewer.md".to_owned()];
+        keep_local(&target, &ids, true, &mut Vec::new()).unwrap();
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        assert!(plan.is_empty());
+        assert_eq!(plan.kept, 1);
+        let mut output = Vec::new();
+        keep_local(&target, &[], true, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "acme:agents/reviewer.md\n");
+
+        keep_local(&target, &ids, false, &mut Vec::new()).unwrap();
+        let plan = plan_update(&sources, &target, &rules, &[], None).await.unwrap();
+        assert_eq!(plan.pending.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn update_merges_local_edits_into_the_new_version() {
+        let target = std::env::temp_dir().join("agent-defs-test-lock-merge");
+        let _ = std::fs::remove_dir_all(&target);
+        let bases = DefinitionStore::open_in_memory("acme").unwrap();
+        let path = target.join(".claude/agents/reviewer.md");
+        let original = "# Reviewer\nReview the diff.\nBe kind.\n";
+        agent_defs::install::install_content(&path, original).unwrap();
+        let def = reviewer(original);
+        let none = HashMap::new();
+        pin(&target, [(&def, path.as_path())], &none, &none, Some(&bases)).unwrap();
+        std::fs::write(&path, "# Reviewer\nReview the diff closely.\nBe kind.\n").unwrap();
+
+        let rules = InstallRules::default();
+        let sources = source("# Reviewer\nReview the diff.\nBe kind.\nCite lines.\n");
+        let plan = plan_update(&sources, &target, &rules, &[], Some(&bases))
+            .await
+            .unwrap();
+        assert_eq!(plan.pending[0].conflicts, Some(0));
+        let mut output = Vec::new();
+        plan.apply(&target, &HashMap::new(), Some(&bases), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Updated acme:agents/reviewer.md, keeping local edits\n1 updated\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# Reviewer\nReview the diff closely.\nBe kind.\nCite lines.\n"
+        );
+
+        let sources = source("# Reviewer\nReview the diff briefly.\nBe kind.\nCite lines.\n");
+        let plan = plan_update(&sources, &target, &rules, &[], Some(&bases))
+            .await
+            .unwrap();
+        assert_eq!(plan.pending[0].conflicts, Some(1));
+        let mut output = Vec::new();
+        plan.apply(&target, &HashMap::new(), Some(&bases), &mut output)
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.ends_with("1 updated, 1 with conflicts to resolve\n"), "{out}");
+        let merged = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            merged.contains(
+                "<<<<<<< local\nReview the diff closely.\n=======\n\
+                 Review the diff briefly.\n>>>>>>> upstream\n"
+            ),
+            "{merged}"
+        );
+
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn confirming_an_update_needs_a_yes() {
+        let mut output = Vec::new();
+        assert!(confirm_update(&mut "y\n".as_bytes(), &mut output).unwrap());
+        assert_eq!(String::from_utf8(output).unwrap(), "Update them? [y/N]: ");
+        assert!(!confirm_update(&mut "\n".as_bytes(), &mut Vec::new()).unwrap());
+    }
+}
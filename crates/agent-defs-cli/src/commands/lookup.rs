@@ -0,0 +1,127 @@
+use agent_defs::{Definition, DefinitionId, Source, SourceError};
+use anyhow::{Result, bail};
+
+/// Split an ID argument qualified as `label:path`, where `label` is one of
+/// `labels`, into the path and that label. A `--source` given alongside
+/// must name the same source. Bare IDs come back with `source` unchanged.
+pub fn split_id<'a>(
+    labels: impl IntoIterator<Item = &'a str>,
+    id: &str,
+    source: Option<String>,
+) -> Result<(String, Option<String>)> {
+    let Some((label, path)) = DefinitionId::new(id).split_source(labels) else {
+        return Ok((id.to_owned(), source));
+    };
+    if let Some(source) = source
+        && source != label
+    {
+        bail!("{id} is qualified with [{label}], but --source names [{source}]");
+    }
+    Ok((path.as_str().to_owned(), Some(label.to_owned())))
+}
+
+/// The one definition `id` names, with its raw file. Only the source
+/// labelled `source_filter` is searched when given; otherwise an ID more
+/// than one source has is an error asking for a qualified one.
+pub async fn fetch_one(
+    sources: &[impl Source],
+    id: &str,
+    source_filter: Option<&str>,
+) -> Result<Definition> {
+    let def_id = DefinitionId::new(id);
+    let mut found = Vec::new();
+    for source in sources {
+        if source_filter.is_some_and(|filter| filter != source.label()) {
+            continue;
+        }
+        match source.fetch(&def_id).await {
+            Ok(mut def) => {
+                if def.raw.is_empty() {
+                    def.raw = source.fetch_raw(&def_id).await?;
+                }
+                found.push(def);
+            }
+            Err(SourceError::NotFound(_)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if found.len() > 1 {
+        let sources = found.into_iter().map(|def| def.source_label).collect();
+        return Err(SourceError::Ambiguous {
+            id: def_id,
+            sources,
+        }
+        .into());
+    }
+    match found.pop() {
+        Some(def) => Ok(def),
+        None => bail!("Definition not found: {id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::{InMemorySource, definition};
+
+    use super::*;
+
+    fn source(label: &str, ids: &[&str]) -> InMemorySource {
+        let mut source = InMemorySource::new(label);
+        for id in ids {
+            source.add(Definition {
+                source_label: label.to_owned(),
+                ..definition(id)
+            });
+        }
+        source
+    }
+
+    #[test]
+    fn qualified_ids_name_their_source() {
+        let labels = ["acme", "other"];
+        let split = |id: &str, source: Option<&str>| {
+            split_id(labels, id, source.map(str::to_owned)).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            split("acme:agents/a.md", None),
+            Ok(("agents/a.md".into(), Some("acme".into())))
+        );
+        assert_eq!(
+            split("agents/a.md", Some("other")),
+            Ok(("agents/a.md".into(), Some("other".into())))
+        );
+        assert_eq!(
+            split("main:agents/a.md", None),
+            Ok(("main:agents/a.md".into(), None))
+        );
+        assert_eq!(
+            split("acme:agents/a.md", Some("other")),
+            Err("acme:agents/a.md is qualified with [acme], but --source names [other]".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn bare_ids_must_be_unambiguous() {
+        let sources = vec![
+            source("acme", &["agents/a.md", "agents/b.md"]),
+            source("other", &["agents/a.md"]),
+        ];
+
+        let def = fetch_one(&sources, "agents/b.md", None).await.unwrap();
+        assert_eq!(def.source_label, "acme");
+        let def = fetch_one(&sources, "agents/a.md", Some("other"))
+            .await
+            .unwrap();
+        assert_eq!(def.source_label, "other");
+
+        let err = fetch_one(&sources, "agents/a.md", None).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("qualify it, as in `acme:agents/a.md`"),
+            "{err}"
+        );
+        let err = fetch_one(&sources, "agents/c.md", None).await.unwrap_err();
+        assert_eq!(err.to_string(), "Definition not found: agents/c.md");
+    }
+}
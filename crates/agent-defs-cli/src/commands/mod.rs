@@ -1,6 +1,31 @@
+pub mod cache;
+pub mod changes;
+pub mod collection;
+pub mod color;
+pub mod config;
+pub mod count;
+pub mod export;
+pub mod fixtures;
 mod format;
+pub mod grep;
+pub mod hide;
 pub mod install;
 pub mod list;
+pub mod lock;
+pub mod lookup;
+pub mod note;
+pub mod pager;
+pub mod rating;
+pub mod reparse;
+pub mod schedule;
 pub mod search;
+pub mod self_update;
+pub mod share;
 pub mod show;
+pub mod site;
+pub mod snapshot;
+pub mod sources;
+mod span;
+pub mod stats;
 pub mod sync;
+pub mod watch;
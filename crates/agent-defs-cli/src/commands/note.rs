@@ -0,0 +1,145 @@
+use std::io::Write;
+
+use agent_defs::{DefinitionId, DefinitionNote, Source};
+use agent_defs_store::DefinitionStore;
+use anyhow::{Result, bail};
+
+/// Attach `text` to `id` in the first source that has it, replacing any
+/// earlier note there. Returns the label of that source.
+pub fn set<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    id: &DefinitionId,
+    source_filter: Option<&str>,
+    text: &str,
+) -> Result<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("note is empty; use --clear to remove a note");
+    }
+    for store in stores {
+        if source_filter.is_some_and(|filter| filter != store.label()) {
+            continue;
+        }
+        if store
+            .set_note(id, text)
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+        {
+            return Ok(store.label().to_owned());
+        }
+    }
+    bail!("Definition not found: {id}");
+}
+
+/// Remove the notes on `id`, returning how many there were.
+pub fn clear<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    id: &DefinitionId,
+    source_filter: Option<&str>,
+) -> Result<usize> {
+    let mut removed = 0;
+    for store in stores {
+        if source_filter.is_some_and(|filter| filter != store.label()) {
+            continue;
+        }
+        if store.remove_note(id).map_err(|e| anyhow::anyhow!("{e}"))? {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Every note, across all sources.
+pub fn collect<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+) -> Result<Vec<DefinitionNote>> {
+    let mut notes = Vec::new();
+    for store in stores {
+        notes.extend(store.notes().map_err(|e| anyhow::anyhow!("{e}"))?);
+    }
+    Ok(notes)
+}
+
+/// Print notes under the ID and source they belong to.
+pub fn print(notes: &[DefinitionNote], output: &mut impl Write) -> Result<()> {
+    if notes.is_empty() {
+        writeln!(output, "No notes yet. Add one with `note <id> \"text\"`.")?;
+        return Ok(());
+    }
+    for note in notes {
+        writeln!(output, "{} [{}]", note.id, note.source_label)?;
+        for line in note.text.lines() {
+            writeln!(output, "  {line}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::{Definition, DefinitionKind};
+
+    use super::*;
+
+    fn store(label: &str, ids: &[&str]) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        for id in ids {
+            store
+                .upsert_definition(&Definition {
+                    id: DefinitionId::new(*id),
+                    name: id.to_string(),
+                    description: None,
+                    kind: DefinitionKind::Agent,
+                    category: None,
+                    source_label: label.into(),
+                    body: String::new(),
+                    tools: vec![],
+                    model: None,
+                    metadata: Default::default(),
+                    raw: String::new(),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn set_picks_the_first_source_with_the_definition() {
+        let acme = store("acme", &["a"]);
+        let other = store("other", &["a", "x"]);
+        let id = DefinitionId::new("a");
+
+        assert_eq!(set([&acme, &other], &id, None, " pairs well ").unwrap(), "acme");
+        assert_eq!(set([&acme, &other], &id, Some("other"), "too").unwrap(), "other");
+        assert_eq!(acme.note(&id).unwrap().unwrap().text, "pairs well");
+
+        let missing = set([&acme, &other], &DefinitionId::new("nope"), None, "text");
+        assert!(missing.unwrap_err().to_string().contains("not found"));
+        assert!(set([&acme], &id, None, "   ").is_err());
+    }
+
+    #[test]
+    fn clear_removes_from_every_matching_source() {
+        let acme = store("acme", &["a"]);
+        let other = store("other", &["a"]);
+        let id = DefinitionId::new("a");
+        acme.set_note(&id, "one").unwrap();
+        other.set_note(&id, "two").unwrap();
+
+        assert_eq!(clear([&acme, &other], &id, Some("acme")).unwrap(), 1);
+        assert_eq!(clear([&acme, &other], &id, None).unwrap(), 1);
+        assert!(collect([&acme, &other]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn print_indents_each_line_under_its_definition() {
+        let acme = store("acme", &["a"]);
+        acme.set_note(&DefinitionId::new("a"), "first\nsecond").unwrap();
+
+        let mut output = Vec::new();
+        print(&collect([&acme]).unwrap(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a [acme]\n  first\n  second\n"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Line-oriented alternative to the TUI for screen readers and dumb
+//! terminals: plain text in, plain text out, no raw mode or box drawing.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use agent_defs::{
+    Definition, DefinitionKind, DefinitionSummary, HideRules, InstallError, InstallRules,
+    Source, SourceError, template,
+};
+use anyhow::Result;
+
+use super::format;
+
+const HELP: &str = "\
+Commands:
+  list [kind]       list definitions, optionally of one kind
+  search <query>    search by name or description (or /<query>)
+  <number>          show a definition from the last list
+  raw <number>      show its raw file content
+  install <number>  install it to the target directory, asking for any
+                    template variables it declares
+  help              show this help
+  quit              leave (or end of input)
+";
+
+/// Run the pager until `quit` or end of input, reading commands from
+/// `input` and writing results to `output`. Installs follow `rules`, and
+/// ones its policy refuses are reported and skipped. Definitions `hidden`
+/// matches are left out of lists and search results.
+pub async fn run(
+    source: &dyn Source,
+    install_target: &Path,
+    rules: &InstallRules,
+    hidden: &HideRules,
+    mut input: impl BufRead,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut shown: Vec<DefinitionSummary> = Vec::new();
+
+    writeln!(
+        output,
+        "Agent definitions, plain mode. Type help for commands."
+    )?;
+    prompt(output)?;
+
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if input.read_line(&mut buf)? == 0 {
+            break;
+        }
+        let line = buf.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "" => {}
+            "q" | "quit" | "exit" => break,
+            "h" | "help" | "?" => write!(output, "{HELP}")?,
+            "l" | "list" => {
+                if let Some(mut summaries) = report(source.list().await, output)? {
+                    summaries.retain(|s| !hidden.hides(s));
+                    if !arg.is_empty() {
+                        let kind = DefinitionKind::parse(arg);
+                        summaries.retain(|s| s.kind == kind);
+                    }
+                    shown = show_list(&summaries, output)?;
+                }
+            }
+            "s" | "search" => shown = search(source, arg, hidden, output).await?,
+            _ if command.starts_with('/') => {
+                shown = search(source, line.trim_start_matches('/').trim(), hidden, output).await?;
+            }
+            "raw" => {
+                if let Some(summary) = pick(&shown, arg, output)?
+                    && let Some(raw) = report(source.fetch_raw(&summary.id).await, output)?
+                {
+                    write!(output, "{raw}")?;
+                    if !raw.ends_with('\n') {
+                        writeln!(output)?;
+                    }
+                }
+            }
+            "i" | "install" => {
+                if let Some(summary) = pick(&shown, arg, output)?
+                    && let Some(def) = report(fetch_with_raw(source, summary).await, output)?
+                {
+                    if let Err(e) = rules.policy.check_install(&def) {
+                        writeln!(output, "Not installed: {e}")?;
+                    } else if def.kind == DefinitionKind::Hook
+                        && !super::install::confirm_hook(&def, &mut input, output)?
+                    {
+                        writeln!(output, "Not installed.")?;
+                    } else {
+                        let mut values = HashMap::new();
+                        let declared = template::declared_vars(&def.raw);
+                        super::install::prompt_for_vars(
+                            &declared,
+                            &mut values,
+                            &mut input,
+                            output,
+                        )?;
+                        match rules.layout.install(install_target, &def, &values) {
+                            Ok(path) => writeln!(output, "Installed to {}", path.display())?,
+                            Err(InstallError::Template(e)) => {
+                                writeln!(output, "Not installed: {e}")?
+                            }
+                            Err(e) => writeln!(output, "Error: {e}")?,
+                        }
+                    }
+                }
+            }
+            _ if command.parse::<usize>().is_ok() => {
+                if let Some(summary) = pick(&shown, command, output)?
+                    && let Some(def) = report(source.fetch(&summary.id).await, output)?
+                {
+                    write!(output, "{}", format::plain_definition(&def))?;
+                }
+            }
+            _ => writeln!(
+                output,
+                "Unknown command: {command}. Type help for commands."
+            )?,
+        }
+
+        prompt(output)?;
+    }
+
+    Ok(())
+}
+
+fn prompt(output: &mut impl Write) -> std::io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()
+}
+
+async fn search(
+    source: &dyn Source,
+    query: &str,
+    hidden: &HideRules,
+    output: &mut impl Write,
+) -> Result<Vec<DefinitionSummary>> {
+    if query.is_empty() {
+        writeln!(output, "Search needs a query, for example: search review")?;
+        return Ok(Vec::new());
+    }
+    let Some(mut results) = report(source.search(query).await, output)? else {
+        return Ok(Vec::new());
+    };
+    results.retain(|s| !hidden.hides(s));
+    if results.is_empty() {
+        writeln!(output, "No results found for \"{query}\".")?;
+        return Ok(Vec::new());
+    }
+    show_list(&results, output)
+}
+
+/// Fetch a definition for installing, filling in its raw file when the
+/// source leaves it out.
+async fn fetch_with_raw(
+    source: &dyn Source,
+    summary: &DefinitionSummary,
+) -> Result<Definition, SourceError> {
+    let mut def = source.fetch(&summary.id).await?;
+    if def.raw.is_empty() {
+        def.raw = source.fetch_raw(&summary.id).await?;
+    }
+    Ok(def)
+}
+
+/// Tell the reader a command failed and carry on, so one unreachable
+/// definition doesn't end the session.
+fn report<T>(
+    result: std::result::Result<T, impl std::fmt::Display>,
+    output: &mut impl Write,
+) -> std::io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            writeln!(output, "Error: {e}")?;
+            Ok(None)
+        }
+    }
+}
+
+/// Print a numbered listing and return the summaries in numbered order.
+fn show_list(
+    summaries: &[DefinitionSummary],
+    output: &mut impl Write,
+) -> Result<Vec<DefinitionSummary>> {
+    if summaries.is_empty() {
+        writeln!(output, "No definitions.")?;
+        return Ok(Vec::new());
+    }
+    write!(output, "{}", format::plain_summaries(summaries, true))?;
+    Ok(format::display_order(summaries)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Resolve a 1-based number from the last listing, explaining failures.
+fn pick<'a>(
+    shown: &'a [DefinitionSummary],
+    arg: &str,
+    output: &mut impl Write,
+) -> Result<Option<&'a DefinitionSummary>> {
+    let picked = arg
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| shown.get(i));
+    if picked.is_none() {
+        if shown.is_empty() {
+            writeln!(output, "Nothing listed yet. Use list or search first.")?;
+        } else {
+            writeln!(output, "Pick a number from 1 to {}.", shown.len())?;
+        }
+    }
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::InMemorySource;
+    use agent_defs::Policy;
+
+    use super::*;
+
+    fn definition(id: &str, kind: DefinitionKind) -> Definition {
+        Definition {
+            description: Some(format!("About {id}")),
+            kind,
+            body: format!("Body of {id}"),
+            raw: format!("raw {id}"),
+            ..agent_defs::test_support::definition(id)
+        }
+    }
+
+    fn source() -> InMemorySource {
+        let mut source = InMemorySource::new("test");
+        source.add(definition("reviewer", DefinitionKind::Agent));
+        source.add(definition("fmt", DefinitionKind::Hook));
+        source
+    }
+
+    async fn session(input: &str) -> String {
+        let mut output = Vec::new();
+        let target = std::env::temp_dir();
+        run(&source(), &target, &InstallRules::default(), &HideRules::default(), input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn list_then_show_by_number() {
+        let out = session("list\n2\nquit\n").await;
+        assert!(out.contains("1. reviewer: About reviewer"));
+        assert!(out.contains("2. fmt: About fmt"));
+        assert!(out.contains("Name: fmt\nKind: hook"));
+        assert!(out.contains("Body of fmt"));
+    }
+
+    #[tokio::test]
+    async fn output_avoids_box_drawing() {
+        let out = session("list\n1\nhelp\n").await;
+        assert!(!out.chars().any(|c| ('\u{2500}'..='\u{257f}').contains(&c)));
+        assert!(!out.contains('\u{1b}'));
+    }
+
+    #[tokio::test]
+    async fn slash_searches() {
+        let out = session("/review\n1\n").await;
+        assert!(out.contains("1. reviewer"));
+        assert!(!out.contains("fmt"));
+        assert!(out.contains("Name: reviewer"));
+    }
+
+    #[tokio::test]
+    async fn list_filters_by_kind() {
+        let out = session("list hooks\n").await;
+        assert!(out.contains("1. fmt"));
+        assert!(!out.contains("reviewer"));
+    }
+
+    #[tokio::test]
+    async fn hidden_definitions_are_left_out() {
+        let hidden = HideRules::parse("id:fmt");
+        let mut output = Vec::new();
+        let input = "list\n/fmt\n";
+        run(&source(), &std::env::temp_dir(), &InstallRules::default(), &hidden, input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert!(out.contains("1. reviewer"), "{out}");
+        assert!(!out.contains("fmt:"), "{out}");
+        assert!(out.contains("No results found for \"fmt\"."), "{out}");
+    }
+
+    #[tokio::test]
+    async fn raw_prints_file_content() {
+        let out = session("list\nraw 1\n").await;
+        assert!(out.contains("raw reviewer\n"));
+    }
+
+    #[tokio::test]
+    async fn bad_numbers_are_explained() {
+        let out = session("3\nlist\n9\n").await;
+        assert!(out.contains("Nothing listed yet"));
+        assert!(out.contains("Pick a number from 1 to 2."));
+    }
+
+    #[tokio::test]
+    async fn failed_fetches_are_reported_and_the_session_goes_on() {
+        let source = source().failing_fetch("fmt", SourceError::Network("down".into()));
+        let mut output = Vec::new();
+        let input = "list\n2\ninstall 2\n1\n";
+        run(&source, &std::env::temp_dir(), &InstallRules::default(), &HideRules::default(), input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert_eq!(out.matches("Error: ").count(), 2, "{out}");
+        assert!(out.contains("Name: reviewer"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn unknown_command_is_reported() {
+        let out = session("frobnicate\n").await;
+        assert!(out.contains("Unknown command: frobnicate"));
+    }
+
+    #[tokio::test]
+    async fn install_prompts_for_template_vars() {
+        let target = std::env::temp_dir().join("agent-defs-pager-template");
+        let _ = std::fs::remove_dir_all(&target);
+        let mut def = definition("templated", DefinitionKind::Agent);
+        def.raw = "---\nvars: [PROJECT]\n---\nFor {{PROJECT}}.".into();
+        let mut source = InMemorySource::new("test");
+        source.add(def);
+
+        let mut output = Vec::new();
+        let input = "list\ninstall 1\ncrate\n";
+        run(&source, &target, &InstallRules::default(), &HideRules::default(), input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert!(out.contains("PROJECT: "));
+        assert!(out.contains("Installed to"));
+        let installed = target.join(".claude/agents/templated.md");
+        assert!(std::fs::read_to_string(installed).unwrap().ends_with("For crate."));
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn hooks_are_confirmed_before_installing() {
+        let target = std::env::temp_dir().join("agent-defs-pager-hook");
+        let _ = std::fs::remove_dir_all(&target);
+        let mut output = Vec::new();
+        let input = "list hooks\ninstall 1\nn\n";
+        run(&source(), &target, &InstallRules::default(), &HideRules::default(), input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert!(out.contains("fmt is a hook and may run shell commands."), "{out}");
+        assert!(out.contains("Not installed."), "{out}");
+        assert!(!target.exists());
+    }
+
+    #[tokio::test]
+    async fn installs_the_policy_refuses_are_reported() {
+        let target = std::env::temp_dir().join("agent-defs-pager-policy");
+        let _ = std::fs::remove_dir_all(&target);
+        let rules = InstallRules {
+            policy: Policy::parse("allowed_kinds = [\"skill\"]").unwrap(),
+            ..InstallRules::default()
+        };
+        let mut output = Vec::new();
+        let input = "list hooks\ninstall 1\n";
+        run(&source(), &target, &rules, &HideRules::default(), input.as_bytes(), &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert!(
+            out.contains("Not installed: hook definitions may not be installed under the org"),
+            "{out}"
+        );
+        assert!(!out.contains("may run shell commands"), "{out}");
+        assert!(!target.exists());
+    }
+}
@@ -0,0 +1,120 @@
+use agent_defs::{Rating, Source};
+use agent_defs_cli::config::AppConfig;
+use agent_defs_store::DefinitionStore;
+use anyhow::{Context, Result, bail};
+
+/// Client for the community index configured as `community_index`.
+pub struct CommunityIndex {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl CommunityIndex {
+    pub fn new(base_url: &str, client: reqwest::Client) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// The index `config` names, if any, reached through its network settings.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.community_index else {
+            return Ok(None);
+        };
+        let client = agent_defs_cli::catalog::http_client(&config.network)?;
+        Ok(Some(Self::new(url, client)))
+    }
+
+    /// Where the index keeps the ratings for the source labelled `label`.
+    pub fn url(&self, label: &str) -> String {
+        format!("{}/{label}.json", self.base_url)
+    }
+
+    /// The ratings for the source labelled `label`. A source the index
+    /// doesn't cover has none.
+    pub async fn fetch(&self, label: &str) -> Result<Vec<Rating>> {
+        let url = self.url(label);
+        let response = self
+            .client
+            .get(&url)
+            .header("User-Agent", "agent-def-fetcher")
+            .send()
+            .await
+            .with_context(|| format!("failed to reach {url}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !response.status().is_success() {
+            bail!("{url} returned HTTP {}", response.status());
+        }
+        let text = response.text().await?;
+        agent_defs::rating::parse_index(label, &text)
+            .with_context(|| format!("failed to parse {url}"))
+    }
+
+    /// Fetch the ratings for `store`'s source and keep them in its cache,
+    /// replacing the previous ones. Returns how many there were.
+    pub async fn refresh(&self, store: &DefinitionStore) -> Result<usize> {
+        let ratings = self.fetch(store.label()).await?;
+        store
+            .set_ratings(&ratings)
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(ratings.len())
+    }
+}
+
+/// Every community rating, across all sources.
+pub fn collect<'a>(stores: impl IntoIterator<Item = &'a DefinitionStore>) -> Result<Vec<Rating>> {
+    let mut ratings = Vec::new();
+    for store in stores {
+        ratings.extend(store.ratings().map_err(|e| anyhow::anyhow!("{e}"))?);
+    }
+    Ok(ratings)
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn index(server: &MockServer) -> CommunityIndex {
+        CommunityIndex::new(
+            &format!("{}/ratings/", server.uri()),
+            reqwest::Client::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_the_cached_ratings() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ratings/acme.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"definitions": {"agents/a.md": {"rating": 4.2, "ratings": 9}}}"#,
+            ))
+            .mount(&server)
+            .await;
+        let store = DefinitionStore::open_in_memory("acme").unwrap();
+
+        assert_eq!(index(&server).refresh(&store).await.unwrap(), 1);
+        let ratings = collect([&store]).unwrap();
+        assert_eq!(ratings[0].badge(), "\u{2605}4.2");
+    }
+
+    #[tokio::test]
+    async fn sources_the_index_lacks_have_no_ratings() {
+        let server = MockServer::start().await;
+        let ratings = index(&server).fetch("acme").await.unwrap();
+        assert!(ratings.is_empty());
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+            .mount(&server)
+            .await;
+        let err = index(&server).fetch("acme").await.unwrap_err();
+        assert!(err.to_string().contains("failed to parse"), "{err}");
+    }
+}
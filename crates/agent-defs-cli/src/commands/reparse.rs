@@ -0,0 +1,89 @@
+use std::io::Write;
+
+use agent_defs::Source;
+use agent_defs_store::DefinitionStore;
+use anyhow::Result;
+
+/// Parse every cached definition again with this version's parser and
+/// print the ones that come out different. Unless `dry_run`, the new
+/// parses replace the cached ones.
+pub fn run<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    dry_run: bool,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut checked = 0;
+    let mut changed = 0;
+    let mut failed = 0;
+    for store in stores {
+        let report = store.reparse(dry_run).map_err(|e| anyhow::anyhow!("{e}"))?;
+        for reparsed in &report.changed {
+            writeln!(
+                output,
+                "[{}] {}: {}",
+                store.label(),
+                reparsed.id,
+                reparsed.fields.join(", ")
+            )?;
+        }
+        for (id, reason) in &report.failed {
+            writeln!(
+                output,
+                "[{}] {id}: no longer parses: {reason}",
+                store.label()
+            )?;
+        }
+        checked += report.checked;
+        changed += report.changed.len();
+        failed += report.failed.len();
+    }
+
+    let verb = if dry_run { "would change" } else { "changed" };
+    write!(output, "Reparsed {checked} definitions: {changed} {verb}")?;
+    if failed > 0 {
+        write!(output, ", {failed} kept their old parse")?;
+    }
+    writeln!(output, ".")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::{Definition, DefinitionId, DefinitionKind};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn dry_runs_report_without_saving() {
+        let store = DefinitionStore::open_in_memory("acme").unwrap();
+        store
+            .upsert_definition(&Definition {
+                id: DefinitionId::new("agents/a.md"),
+                name: "a".to_owned(),
+                description: None,
+                kind: DefinitionKind::Agent,
+                category: None,
+                source_label: "acme".to_owned(),
+                body: "Body.".to_owned(),
+                tools: Vec::new(),
+                model: None,
+                metadata: Default::default(),
+                raw: "---\nname: a\ndescription: Does A\n---\nBody.".to_owned(),
+            })
+            .unwrap();
+
+        let mut output = Vec::new();
+        run([&store], true, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.starts_with("[acme] agents/a.md: description"),
+            "{output}"
+        );
+        assert!(
+            output.ends_with("Reparsed 1 definitions: 1 would change.\n"),
+            "{output}"
+        );
+        let def = store.fetch(&DefinitionId::new("agents/a.md")).await;
+        assert_eq!(def.unwrap().description, None);
+    }
+}
@@ -0,0 +1,377 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use super::span;
+
+/// Name shared by the systemd units and the launchd job label.
+const SYSTEMD_NAME: &str = "agent-def-fetcher-sync";
+const LAUNCHD_LABEL: &str = "com.esmevane.agent-def-fetcher.sync";
+
+/// The smallest interval `schedule install` accepts, to stay well inside
+/// GitHub's rate limits.
+const MIN_INTERVAL_SECS: u64 = 15 * 60;
+
+/// Parse an `--every` interval such as `30m`, `6h`, `1d`, or `1w` into
+/// seconds.
+pub fn parse_every(arg: &str) -> Result<u64, String> {
+    let units = [
+        ('m', 60),
+        ('h', 60 * 60),
+        ('d', 24 * 60 * 60),
+        ('w', 7 * 24 * 60 * 60),
+    ];
+    let secs = span::parse(arg, &units, "a number followed by m, h, d, or w")?;
+    if secs < MIN_INTERVAL_SECS {
+        return Err(format!("`{arg}` is too often; the minimum is 15m"));
+    }
+    Ok(secs)
+}
+
+/// The service manager that runs scheduled syncs on this platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduler {
+    /// A systemd user timer and oneshot service (Linux).
+    Systemd,
+    /// A launchd agent (macOS).
+    Launchd,
+}
+
+impl Scheduler {
+    pub fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else {
+            bail!(
+                "scheduled sync needs systemd (Linux) or launchd (macOS); on this platform, \
+                 run `agent-def-fetcher sync --quiet` from the system scheduler"
+            )
+        }
+    }
+
+    /// Where the unit files live.
+    pub fn unit_dir(self) -> Result<PathBuf> {
+        let dir = match self {
+            Self::Systemd => dirs::config_dir().map(|d| d.join("systemd").join("user")),
+            Self::Launchd => dirs::home_dir().map(|d| d.join("Library").join("LaunchAgents")),
+        };
+        dir.context("could not determine the user service directory")
+    }
+
+    /// File names and contents that schedule `exe sync --quiet` every
+    /// `every_secs` seconds. launchd writes the job's stderr to `log`.
+    pub fn files(self, exe: &Path, every_secs: u64, log: &Path) -> Vec<(String, String)> {
+        match self {
+            Self::Systemd => vec![
+                (
+                    format!("{SYSTEMD_NAME}.service"),
+                    format!(
+                        "[Unit]\n\
+                         Description=Sync agent-def-fetcher sources\n\
+                         \n\
+                         [Service]\n\
+                         Type=oneshot\n\
+                         ExecStart=\"{}\" sync --quiet\n",
+                        exe.display().to_string().replace('%', "%%")
+                    ),
+                ),
+                (
+                    format!("{SYSTEMD_NAME}.timer"),
+                    format!(
+                        "[Unit]\n\
+                         Description=Refresh the agent-def-fetcher cache every {}\n\
+                         \n\
+                         [Timer]\n\
+                         OnBootSec=5min\n\
+                         OnUnitActiveSec={every_secs}s\n\
+                         \n\
+                         [Install]\n\
+                         WantedBy=timers.target\n",
+                        describe(every_secs)
+                    ),
+                ),
+            ],
+            Self::Launchd => vec![(
+                format!("{LAUNCHD_LABEL}.plist"),
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>sync</string>
+        <string>--quiet</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{every_secs}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardErrorPath</key>
+    <string>{}</string>
+</dict>
+</plist>
+"#,
+                    xml_escape(&exe.display().to_string()),
+                    xml_escape(&log.display().to_string())
+                ),
+            )],
+        }
+    }
+
+    /// The file the service manager is told to load or unload.
+    fn primary_file(self) -> String {
+        match self {
+            Self::Systemd => format!("{SYSTEMD_NAME}.timer"),
+            Self::Launchd => format!("{LAUNCHD_LABEL}.plist"),
+        }
+    }
+
+    fn load(self, dir: &Path) -> Result<()> {
+        match self {
+            Self::Systemd => {
+                run("systemctl", &["--user", "daemon-reload"])?;
+                run(
+                    "systemctl",
+                    &["--user", "enable", "--now", &self.primary_file()],
+                )
+            }
+            Self::Launchd => {
+                let plist = dir.join(self.primary_file());
+                let plist = plist.to_string_lossy();
+                // Unloading first makes a reinstall pick up the new interval.
+                let _ = run("launchctl", &["unload", &plist]);
+                run("launchctl", &["load", "-w", &plist])
+            }
+        }
+    }
+
+    fn unload(self, dir: &Path) -> Result<()> {
+        match self {
+            Self::Systemd => run(
+                "systemctl",
+                &["--user", "disable", "--now", &self.primary_file()],
+            ),
+            Self::Launchd => {
+                let plist = dir.join(self.primary_file());
+                run("launchctl", &["unload", "-w", &plist.to_string_lossy()])
+            }
+        }
+    }
+}
+
+/// Write the unit files for `every_secs` and load them.
+pub fn install(every_secs: u64, log: &Path, output: &mut impl Write) -> Result<()> {
+    let scheduler = Scheduler::current()?;
+    let dir = scheduler.unit_dir()?;
+    let exe = std::env::current_exe().context("could not locate the agent-def-fetcher binary")?;
+
+    for path in write_files(scheduler, &dir, &exe, every_secs, log)? {
+        writeln!(output, "Wrote {}", path.display())?;
+    }
+    scheduler.load(&dir)?;
+    writeln!(
+        output,
+        "Scheduled `sync --quiet` every {}",
+        describe(every_secs)
+    )?;
+    Ok(())
+}
+
+/// Unload the scheduled sync and delete its unit files.
+pub fn remove(output: &mut impl Write) -> Result<()> {
+    let scheduler = Scheduler::current()?;
+    let dir = scheduler.unit_dir()?;
+    if !dir.join(scheduler.primary_file()).exists() {
+        writeln!(output, "No scheduled sync is installed.")?;
+        return Ok(());
+    }
+
+    if let Err(e) = scheduler.unload(&dir) {
+        eprintln!("warning: {e}");
+    }
+    for path in installed_files(scheduler, &dir) {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("failed to remove {}", path.display()))?;
+        writeln!(output, "Removed {}", path.display())?;
+    }
+    if scheduler == Scheduler::Systemd {
+        let _ = run("systemctl", &["--user", "daemon-reload"]);
+    }
+    Ok(())
+}
+
+/// Report whether a scheduled sync is installed and what the service
+/// manager says about it.
+pub fn status(output: &mut impl Write) -> Result<()> {
+    let scheduler = Scheduler::current()?;
+    let dir = scheduler.unit_dir()?;
+    let files = installed_files(scheduler, &dir);
+    if files.is_empty() {
+        writeln!(output, "No scheduled sync is installed.")?;
+        return Ok(());
+    }
+
+    for path in &files {
+        writeln!(output, "Installed: {}", path.display())?;
+    }
+    let report = match scheduler {
+        Scheduler::Systemd => Command::new("systemctl")
+            .args(["--user", "list-timers", "--all", "--no-pager"])
+            .arg(scheduler.primary_file())
+            .output(),
+        Scheduler::Launchd => Command::new("launchctl")
+            .args(["list", LAUNCHD_LABEL])
+            .output(),
+    };
+    match report {
+        Ok(report) => output.write_all(&report.stdout)?,
+        Err(e) => writeln!(output, "Could not query the service manager: {e}")?,
+    }
+    Ok(())
+}
+
+/// Write the scheduler's files into `dir`, returning their paths.
+pub fn write_files(
+    scheduler: Scheduler,
+    dir: &Path,
+    exe: &Path,
+    every_secs: u64,
+    log: &Path,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut written = Vec::new();
+    for (name, contents) in scheduler.files(exe, every_secs, log) {
+        let path = dir.join(name);
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn installed_files(scheduler: Scheduler, dir: &Path) -> Vec<PathBuf> {
+    scheduler
+        .files(Path::new(""), MIN_INTERVAL_SECS, Path::new(""))
+        .into_iter()
+        .map(|(name, _)| dir.join(name))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("failed to run {program}"))?;
+    if !status.success() {
+        bail!("`{program} {}` exited with {status}", args.join(" "));
+    }
+    Ok(())
+}
+
+/// `86400` -> `1d`, `5400` -> `90m`.
+fn describe(secs: u64) -> String {
+    for (unit, size) in [("w", 7 * 86_400), ("d", 86_400), ("h", 3_600)] {
+        if secs.is_multiple_of(size) {
+            return format!("{}{unit}", secs / size);
+        }
+    }
+    format!("{}m", secs / 60)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_every_accepts_units() {
+        assert_eq!(parse_every("30m"), Ok(1_800));
+        assert_eq!(parse_every("6h"), Ok(21_600));
+        assert_eq!(parse_every("1d"), Ok(86_400));
+        assert_eq!(parse_every("2w"), Ok(1_209_600));
+        assert!(parse_every("5m").is_err());
+        assert!(parse_every("daily").is_err());
+        assert!(parse_every("").is_err());
+        assert!(parse_every("1é").is_err());
+        assert!(parse_every("99999999999999w").is_err());
+    }
+
+    #[test]
+    fn describe_picks_the_largest_whole_unit() {
+        assert_eq!(describe(86_400), "1d");
+        assert_eq!(describe(5_400), "90m");
+        assert_eq!(describe(1_209_600), "2w");
+    }
+
+    #[test]
+    fn systemd_timer_runs_sync_quietly() {
+        let files = Scheduler::Systemd.files(
+            Path::new("/opt/bin/agent-def-fetcher"),
+            86_400,
+            Path::new("/tmp/log"),
+        );
+        let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "agent-def-fetcher-sync.service",
+                "agent-def-fetcher-sync.timer"
+            ]
+        );
+        assert!(
+            files[0]
+                .1
+                .contains("ExecStart=\"/opt/bin/agent-def-fetcher\" sync --quiet\n")
+        );
+        assert!(files[1].1.contains("OnUnitActiveSec=86400s\n"));
+        assert!(files[1].1.contains("every 1d"));
+    }
+
+    #[test]
+    fn launchd_plist_escapes_paths() {
+        let files = Scheduler::Launchd.files(
+            Path::new("/Users/a&b/bin/agent-def-fetcher"),
+            3_600,
+            Path::new("/tmp/sync.log"),
+        );
+        let (name, plist) = &files[0];
+        assert_eq!(name, "com.esmevane.agent-def-fetcher.sync.plist");
+        assert!(plist.contains("<string>/Users/a&amp;b/bin/agent-def-fetcher</string>"));
+        assert!(plist.contains("<integer>3600</integer>"));
+        assert!(plist.contains("<string>/tmp/sync.log</string>"));
+    }
+
+    #[test]
+    fn write_files_creates_the_unit_dir() {
+        let dir = std::env::temp_dir().join("agent-defs-test-schedule");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let written = write_files(
+            Scheduler::Systemd,
+            &dir,
+            Path::new("/bin/agent-def-fetcher"),
+            3_600,
+            Path::new("/tmp/log"),
+        )
+        .unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(installed_files(Scheduler::Systemd, &dir), written);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,16 +1,24 @@
-use agent_defs::{DefinitionKind, Source};
+use agent_defs::{DefinitionKind, HideRules, Source};
 use anyhow::Result;
 
-use super::format;
+use super::format::{self, Matches};
 
 pub async fn run(
-    sources: &[Box<dyn Source>],
+    sources: &[impl Source],
     query: &str,
     kind_filter: Option<&str>,
     source_filter: Option<&str>,
+    hidden: &HideRules,
+    plain: bool,
 ) -> Result<()> {
     let kind_predicate = kind_filter.map(DefinitionKind::parse);
     let mut all = Vec::new();
+    let mut hidden_count = 0;
+    let mut matches = Matches {
+        query: query.to_owned(),
+        color: super::color::stdout(),
+        ..Matches::default()
+    };
 
     for source in sources {
         if let Some(filter) = source_filter
@@ -27,16 +35,31 @@ pub async fn run(
             {
                 continue;
             }
+            if hidden.hides(&summary) {
+                hidden_count += 1;
+                continue;
+            }
 
+            // The body line that matched, if it was the body that did.
+            if let Ok(def) = source.fetch(&summary.id).await
+                && let Some(snippet) = format::snippet(&def.body, query)
+            {
+                matches
+                    .snippets
+                    .insert((summary.source_label.clone(), summary.id.clone()), snippet);
+            }
             all.push(summary);
         }
     }
 
     if all.is_empty() {
         println!("No results found for \"{query}\".");
+    } else if plain {
+        format::print_search_plain(&all, &matches);
     } else {
-        format::print_summary_table(&all);
+        format::print_search_table(&all, &matches);
     }
+    super::hide::note_hidden(hidden_count);
 
     Ok(())
 }
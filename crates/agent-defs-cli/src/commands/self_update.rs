@@ -0,0 +1,572 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use agent_defs_cli::signing::{self, PublicKey, SIGNATURE_SUFFIX};
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Name of the binary inside release archives.
+const BIN_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// The minisign public key release archives are signed with, baked in at
+/// build time. A build without one can't check a download, so it won't
+/// replace itself.
+const RELEASE_KEY: Option<&str> = option_env!("AGENT_DEFS_RELEASE_KEY");
+
+/// How long to wait between the update notices printed after `sync`.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A GitHub release, as returned by `/repos/{owner}/{repo}/releases/latest`.
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    /// The release's version, without the tag's leading `v`.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .with_context(|| format!("release {} has no {name}", self.tag_name))
+    }
+}
+
+/// Client for this project's GitHub releases.
+pub struct Updater {
+    client: reqwest::Client,
+    api_base: String,
+    repo: String,
+    release_keys: Vec<PublicKey>,
+}
+
+impl Updater {
+    /// Check the repository named in the crate manifest, optionally through
+    /// a different API host.
+    pub fn new(api_base: Option<String>) -> Self {
+        let repo = env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/");
+        Self {
+            client: reqwest::Client::new(),
+            api_base: api_base.unwrap_or_else(|| "https://api.github.com".into()),
+            repo: repo.trim_end_matches('/').to_owned(),
+            release_keys: RELEASE_KEY
+                .and_then(|key| PublicKey::parse(key).ok())
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Trust archives signed by `keys` rather than the built-in release key.
+    #[cfg(test)]
+    pub fn with_release_keys(mut self, keys: Vec<PublicKey>) -> Self {
+        self.release_keys = keys;
+        self
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub async fn latest(&self) -> Result<Release> {
+        let url = format!("{}/repos/{}/releases/latest", self.api_base, self.repo);
+        let response = self
+            .client
+            .get(&url)
+            .header("User-Agent", "agent-def-fetcher")
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .context("failed to reach GitHub releases")?;
+        if !response.status().is_success() {
+            bail!("GitHub releases returned HTTP {}", response.status());
+        }
+        response
+            .json()
+            .await
+            .context("failed to parse the GitHub release")
+    }
+
+    /// Download the archive for `target`, check it against the release's
+    /// published SHA-256 and minisign signature, and return the binary
+    /// inside it.
+    pub async fn download(&self, release: &Release, target: &str) -> Result<Vec<u8>> {
+        if self.release_keys.is_empty() {
+            bail!(
+                "this build has no release signing key to check a download with; \
+                 update with cargo or from the release page instead"
+            );
+        }
+        let archive_name = archive_name(target);
+        let archive = self.get(release.asset(&archive_name)?).await?;
+        let checksum = self
+            .get(release.asset(&format!("{archive_name}.sha256"))?)
+            .await?;
+        let signature = self
+            .get(release.asset(&format!("{archive_name}{SIGNATURE_SUFFIX}"))?)
+            .await?;
+
+        let expected = parse_checksum(&String::from_utf8_lossy(&checksum))?;
+        let actual = sha256_hex(&archive);
+        if actual != expected {
+            bail!("checksum mismatch for {archive_name}: expected {expected}, got {actual}");
+        }
+        signing::verify(
+            &self.release_keys,
+            &archive,
+            &String::from_utf8_lossy(&signature),
+        )
+        .map_err(|e| anyhow::anyhow!("bad signature on {archive_name}: {e}"))?;
+        extract_binary(&archive)
+    }
+
+    async fn get(&self, asset: &Asset) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(&asset.browser_download_url)
+            .header("User-Agent", "agent-def-fetcher")
+            .send()
+            .await
+            .with_context(|| format!("failed to download {}", asset.name))?;
+        if !response.status().is_success() {
+            bail!(
+                "downloading {} returned HTTP {}",
+                asset.name,
+                response.status()
+            );
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Report whether a newer release exists and, unless `check_only`, install
+/// it over the running binary.
+pub async fn run(updater: &Updater, check_only: bool, output: &mut impl Write) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = updater.latest().await?;
+    if !is_newer(release.version(), current) {
+        writeln!(output, "agent-def-fetcher {current} is up to date")?;
+        return Ok(());
+    }
+    if check_only {
+        writeln!(
+            output,
+            "agent-def-fetcher {} is available (you have {current})",
+            release.version()
+        )?;
+        return Ok(());
+    }
+
+    let Some(target) = current_target() else {
+        bail!("no release binaries are published for this platform; update with cargo instead");
+    };
+    let binary = updater.download(&release, target).await?;
+    let exe = std::env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .context("failed to locate the running binary")?;
+    replace_exe(&exe, &binary)?;
+    writeln!(
+        output,
+        "Updated agent-def-fetcher {current} -> {}",
+        release.version()
+    )?;
+    Ok(())
+}
+
+/// Print a one-line notice when a newer release exists, at most once a day.
+/// `stamp` records when the last check happened; failures are silent. A
+/// build without a release key says nothing, since it can't update itself.
+pub async fn notify_if_outdated(updater: &Updater, stamp: &Path) {
+    if updater.release_keys.is_empty() {
+        return;
+    }
+    let checked_recently = std::fs::metadata(stamp)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < CHECK_INTERVAL);
+    if checked_recently || std::fs::write(stamp, b"").is_err() {
+        return;
+    }
+    if let Ok(release) = updater.latest().await
+        && is_newer(release.version(), env!("CARGO_PKG_VERSION"))
+    {
+        eprintln!(
+            "agent-def-fetcher {} is available; run `agent-def-fetcher self-update`",
+            release.version()
+        );
+    }
+}
+
+/// Compare `major.minor.patch` versions. Pre-releases and anything
+/// unparseable never count as newer.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((major, minor, patch))
+}
+
+/// The release target triple for this build, if binaries are published
+/// for it.
+pub fn current_target() -> Option<&'static str> {
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("aarch64-apple-darwin")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("x86_64-apple-darwin")
+    } else if cfg!(all(
+        target_os = "linux",
+        target_arch = "x86_64",
+        target_env = "gnu"
+    )) {
+        Some("x86_64-unknown-linux-gnu")
+    } else if cfg!(all(
+        target_os = "windows",
+        target_arch = "x86_64",
+        target_env = "msvc"
+    )) {
+        Some("x86_64-pc-windows-msvc")
+    } else {
+        None
+    }
+}
+
+fn archive_name(target: &str) -> String {
+    format!("{BIN_NAME}-{target}.tar.gz")
+}
+
+/// Read the digest from a `sha256sum`-style line: `<hex>  <file name>`.
+fn parse_checksum(text: &str) -> Result<String> {
+    match text.split_whitespace().next() {
+        Some(hex) if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Ok(hex.to_ascii_lowercase())
+        }
+        _ => bail!("release checksum file is malformed"),
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Pull the binary out of a release `.tar.gz`, wherever it sits in the
+/// archive's top-level directory.
+fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in tar.entries().context("release archive is unreadable")? {
+        let mut entry = entry.context("release archive is unreadable")?;
+        let path = entry.path()?;
+        let name = path.file_name().and_then(|n| n.to_str());
+        if entry.header().entry_type().is_file()
+            && (name == Some(BIN_NAME) || name == Some(&format!("{BIN_NAME}.exe")))
+        {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary)?;
+            return Ok(binary);
+        }
+    }
+    bail!("release archive does not contain {BIN_NAME}")
+}
+
+/// Swap `binary` in for the executable at `exe`. The new file is written
+/// alongside and renamed into place, so a failed write leaves the old binary
+/// untouched. Windows won't replace a running executable, so there the old
+/// one is moved aside to `<name>.old` first.
+fn replace_exe(exe: &Path, binary: &[u8]) -> Result<()> {
+    let staged = exe.with_extension("new");
+    std::fs::write(&staged, binary)
+        .with_context(|| format!("failed to write {}", staged.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)
+            .with_context(|| format!("failed to move {} aside", exe.display()))?;
+    }
+    std::fs::rename(&staged, exe).with_context(|| format!("failed to replace {}", exe.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    const KEY_ID: [u8; 8] = [7; 8];
+
+    fn key_pair(seed: u8) -> ring::signature::Ed25519KeyPair {
+        ring::signature::Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap()
+    }
+
+    fn release_key(seed: u8) -> PublicKey {
+        use base64::Engine;
+        use ring::signature::KeyPair;
+
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&KEY_ID);
+        bytes.extend_from_slice(key_pair(seed).public_key().as_ref());
+        PublicKey::parse(&base64::engine::general_purpose::STANDARD.encode(bytes)).unwrap()
+    }
+
+    /// A legacy (not prehashed) `.minisig` over `message`.
+    fn sign(seed: u8, message: &[u8]) -> String {
+        use base64::Engine;
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let pair = key_pair(seed);
+        let signature = pair.sign(message);
+        let mut line = b"Ed".to_vec();
+        line.extend_from_slice(&KEY_ID);
+        line.extend_from_slice(signature.as_ref());
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(b"release");
+        format!(
+            "untrusted comment: test\n{}\ntrusted comment: release\n{}\n",
+            engine.encode(line),
+            engine.encode(pair.sign(&global))
+        )
+    }
+
+    fn updater(server: &wiremock::MockServer) -> Updater {
+        Updater::new(Some(server.uri())).with_release_keys(vec![release_key(1)])
+    }
+
+    async fn serve_release(archive: &[u8], checksum: &str) -> wiremock::MockServer {
+        serve_signed_release(archive, checksum, &sign(1, archive)).await
+    }
+
+    async fn serve_signed_release(
+        archive: &[u8],
+        checksum: &str,
+        signature: &str,
+    ) -> wiremock::MockServer {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = wiremock::MockServer::start().await;
+        let name = archive_name("x86_64-unknown-linux-gnu");
+        let release = serde_json::json!({
+            "tag_name": "v9.0.0",
+            "assets": [
+                { "name": name, "browser_download_url": format!("{}/dl/archive", server.uri()) },
+                { "name": format!("{name}.sha256"), "browser_download_url": format!("{}/dl/sum", server.uri()) },
+                { "name": format!("{name}.minisig"), "browser_download_url": format!("{}/dl/sig", server.uri()) },
+            ]
+        });
+        Mock::given(method("GET"))
+            .and(path("/repos/esmevane/agent-def-fetcher/releases/latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release))
+            .mount(&server)
+            .await;
+        Mock::given(path("/dl/archive"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(archive.to_vec()))
+            .mount(&server)
+            .await;
+        Mock::given(path("/dl/sum"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(format!("{checksum}  {name}\n")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/dl/sig"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(signature))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(is_newer("1.0.0", "0.9.9"));
+        assert!(!is_newer("0.1.3", "0.1.3"));
+        assert!(!is_newer("0.1.2", "0.1.3"));
+        assert!(!is_newer("0.2.0-beta.1", "0.1.3"));
+        assert!(!is_newer("latest", "0.1.3"));
+    }
+
+    #[test]
+    fn parses_sha256sum_lines() {
+        let digest = "A".repeat(64);
+        assert_eq!(
+            parse_checksum(&format!("{digest}  x.tar.gz\n")).unwrap(),
+            "a".repeat(64)
+        );
+        assert!(parse_checksum("not-a-digest  x.tar.gz").is_err());
+        assert!(parse_checksum("").is_err());
+    }
+
+    #[test]
+    fn extracts_binary_from_release_layout() {
+        let archive = archive(&[
+            (
+                "agent-defs-cli-x86_64-unknown-linux-gnu/README.md",
+                b"readme",
+            ),
+            (
+                "agent-defs-cli-x86_64-unknown-linux-gnu/agent-defs-cli",
+                b"new binary",
+            ),
+        ]);
+        assert_eq!(extract_binary(&archive).unwrap(), b"new binary");
+        assert!(extract_binary(&self::archive(&[("README.md", b"readme")])).is_err());
+    }
+
+    #[test]
+    fn replaces_executable_in_place() {
+        let dir = std::env::temp_dir().join("agent-defs-test-self-update-replace");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("agent-defs-cli");
+        std::fs::write(&exe, b"old").unwrap();
+
+        replace_exe(&exe, b"new").unwrap();
+        assert_eq!(std::fs::read(&exe).unwrap(), b"new");
+        assert!(!exe.with_extension("new").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn downloads_and_verifies_release_binary() {
+        let archive = archive(&[(
+            "agent-defs-cli-x86_64-unknown-linux-gnu/agent-defs-cli",
+            b"v9",
+        )]);
+        let server = serve_release(&archive, &sha256_hex(&archive)).await;
+        let updater = updater(&server);
+
+        let release = updater.latest().await.unwrap();
+        assert_eq!(release.version(), "9.0.0");
+        let binary = updater
+            .download(&release, "x86_64-unknown-linux-gnu")
+            .await
+            .unwrap();
+        assert_eq!(binary, b"v9");
+    }
+
+    #[tokio::test]
+    async fn rejects_checksum_mismatch() {
+        let archive = archive(&[(
+            "agent-defs-cli-x86_64-unknown-linux-gnu/agent-defs-cli",
+            b"v9",
+        )]);
+        let server = serve_release(&archive, &"0".repeat(64)).await;
+        let updater = updater(&server);
+
+        let release = updater.latest().await.unwrap();
+        let err = updater
+            .download(&release, "x86_64-unknown-linux-gnu")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+
+    #[tokio::test]
+    async fn rejects_archives_signed_by_another_key() {
+        let archive = archive(&[(
+            "agent-defs-cli-x86_64-unknown-linux-gnu/agent-defs-cli",
+            b"v9",
+        )]);
+        let server =
+            serve_signed_release(&archive, &sha256_hex(&archive), &sign(2, &archive)).await;
+        let updater = Updater::new(Some(server.uri())).with_release_keys(vec![release_key(3)]);
+
+        let release = updater.latest().await.unwrap();
+        let err = updater
+            .download(&release, "x86_64-unknown-linux-gnu")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("bad signature on"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn builds_without_a_release_key_refuse_to_download() {
+        let server = serve_release(&[], &"0".repeat(64)).await;
+        let updater = Updater::new(Some(server.uri())).with_release_keys(Vec::new());
+        let release = updater.latest().await.unwrap();
+        let err = updater
+            .download(&release, "x86_64-unknown-linux-gnu")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no release signing key"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn builds_without_a_release_key_skip_the_update_notice() {
+        let server = serve_release(&[], &"0".repeat(64)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let stamp = dir.path().join("last-update-check");
+        let updater = Updater::new(Some(server.uri())).with_release_keys(Vec::new());
+        notify_if_outdated(&updater, &stamp).await;
+        assert!(!stamp.exists());
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        notify_if_outdated(&updater.with_release_keys(vec![release_key(1)]), &stamp).await;
+        assert!(stamp.exists());
+    }
+
+    #[tokio::test]
+    async fn check_only_reports_available_version() {
+        let server = serve_release(&[], &"0".repeat(64)).await;
+        let mut output = Vec::new();
+        run(&Updater::new(Some(server.uri())), true, &mut output)
+            .await
+            .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.starts_with("agent-def-fetcher 9.0.0 is available"));
+    }
+
+    #[tokio::test]
+    async fn missing_platform_asset_is_an_error() {
+        let server = serve_release(&[], &"0".repeat(64)).await;
+        let updater = updater(&server);
+        let release = updater.latest().await.unwrap();
+        let err = updater
+            .download(&release, "riscv64gc-unknown-linux-gnu")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("has no agent-defs-cli-riscv64gc"));
+    }
+}
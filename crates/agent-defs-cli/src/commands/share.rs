@@ -0,0 +1,87 @@
+use agent_defs::path::is_skill_directory_id;
+use agent_defs::Source;
+use agent_defs_github::{GistClient, GistFile};
+use anyhow::Result;
+
+/// Upload a definition's raw file to a secret gist and return its URL.
+pub async fn run(
+    sources: &[impl Source],
+    id: &str,
+    source_filter: Option<&str>,
+    client: &GistClient,
+) -> Result<String> {
+    let def = super::lookup::fetch_one(sources, id, source_filter).await?;
+    let file = GistFile {
+        filename: gist_filename(id),
+        content: def.raw,
+    };
+    let description = format!("{}, shared from {}", def.name, def.source_label);
+    Ok(client.create(&description, &[file]).await?)
+}
+
+/// The file name a definition gets inside the gist: its own file name, or
+/// `SKILL.md` for a skill stored as a directory.
+fn gist_filename(id: &str) -> String {
+    let (_, id) = agent_defs::path::split_branch(id);
+    let id = agent_defs::path::normalize_separators(id);
+    if is_skill_directory_id(&id) {
+        return "SKILL.md".to_owned();
+    }
+    match id.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name.to_owned(),
+        _ => "definition.md".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::InMemorySource;
+    use agent_defs::Definition;
+
+    use super::*;
+
+    #[test]
+    fn gist_filename_uses_the_file_name() {
+        assert_eq!(gist_filename("agents/review/reviewer.md"), "reviewer.md");
+        assert_eq!(gist_filename("commands\\deploy.md"), "deploy.md");
+        assert_eq!(gist_filename("skills/ai/crewai"), "SKILL.md");
+        assert_eq!(gist_filename("next:skills/ai/crewai"), "SKILL.md");
+    }
+
+    #[tokio::test]
+    async fn run_uploads_the_raw_definition() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/gists"))
+            .and(wiremock::matchers::body_json(serde_json::json!({
+                "description": "Reviewer, shared from acme",
+                "public": false,
+                "files": { "reviewer.md": { "content": "---\nname: Reviewer\n---\n" } }
+            })))
+            .respond_with(
+                wiremock::ResponseTemplate::new(201)
+                    .set_body_string(r#"{"html_url": "https://gist.github.com/xyz"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut source = InMemorySource::new("acme");
+        source.add(Definition {
+            name: "Reviewer".into(),
+            source_label: "acme".into(),
+            raw: "---\nname: Reviewer\n---\n".into(),
+            ..agent_defs::test_support::definition("agents/reviewer.md")
+        });
+        let sources = [source];
+        let client = GistClient::new(Some("token".into()), Some(server.uri()));
+
+        let url = run(&sources, "agents/reviewer.md", None, &client)
+            .await
+            .unwrap();
+        assert_eq!(url, "https://gist.github.com/xyz");
+
+        let missing = run(&sources, "agents/gone.md", None, &client).await;
+        assert!(missing.is_err());
+    }
+}
@@ -1,54 +1,284 @@
-use agent_defs::{DefinitionId, Source};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use agent_defs::changelog::format_date;
+use agent_defs::{Definition, DefinitionId, Source, SourceError};
+use agent_defs_cli::catalog::Origin;
+use agent_defs_store::{DefinitionStore, SyncStatus};
 use anyhow::{Result, bail};
 
-pub async fn run(
-    sources: &[Box<dyn Source>],
+use super::color::{self, BOLD_STYLE};
+use super::export::{self, ExportFormat};
+use super::format;
+
+/// How `show` presents the definition it finds.
+pub enum View {
+    /// The raw file, frontmatter and all.
+    Raw,
+    /// Labelled fields, then the body; `plain` turns off color.
+    Formatted { plain: bool },
+    /// Written to a file, by default named after the ID, in the current
+    /// directory.
+    Export {
+        format: ExportFormat,
+        path: Option<PathBuf>,
+    },
+}
+
+/// Print the definition `id`. When more than one source has it and
+/// `source_filter` doesn't pick one, list those sources and how fresh each
+/// is instead. `origins` maps source labels to where they sync from.
+pub async fn run<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    origins: &HashMap<String, Origin>,
     id: &str,
     source_filter: Option<&str>,
-    raw: bool,
+    view: View,
+    output: &mut impl Write,
 ) -> Result<()> {
     let def_id = DefinitionId::new(id);
 
-    for source in sources {
-        if let Some(filter) = source_filter
-            && source.label() != filter
-        {
+    let mut candidates = Vec::new();
+    for store in stores {
+        if source_filter.is_some_and(|filter| filter != store.label()) {
             continue;
         }
+        match store.fetch(&def_id).await {
+            Ok(def) => candidates.push((store, def)),
+            Err(SourceError::NotFound(_)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
 
-        match source.fetch(&def_id).await {
-            Ok(def) => {
-                if raw {
-                    print!("{}", def.raw);
-                    return Ok(());
-                }
-
-                println!("Name:        {}", def.name);
-                println!("Kind:        {}", def.kind);
-
-                if let Some(desc) = &def.description {
-                    println!("Description: {desc}");
-                }
-                if let Some(category) = &def.category {
-                    println!("Category:    {category}");
-                }
-                if let Some(model) = &def.model {
-                    println!("Model:       {model}");
-                }
-                if !def.tools.is_empty() {
-                    println!("Tools:       {}", def.tools.join(", "));
-                }
-                println!("Source:      {}", def.source_label);
-                println!("ID:          {}", def.id);
-                println!();
-                print!("{}", def.body);
-
-                return Ok(());
+    let (store, def) = match candidates.len() {
+        0 => bail!("Definition not found: {id}"),
+        1 => candidates.remove(0),
+        count => {
+            writeln!(output, "{id} is in {count} sources:")?;
+            let width = candidates
+                .iter()
+                .map(|(store, _)| store.label().len())
+                .max()
+                .unwrap_or(0);
+            for (store, _) in &candidates {
+                writeln!(output, "  {:<width$}  {}", store.label(), last_synced(store)?)?;
             }
-            Err(agent_defs::SourceError::NotFound(_)) => continue,
-            Err(e) => return Err(e.into()),
+            let first = candidates[0].0.label();
+            bail!(
+                "{id} is in {count} sources; pass --source to pick one, \
+                 or qualify the ID, as in `{first}:{id}`"
+            );
+        }
+    };
+
+    if let View::Raw = view {
+        write!(output, "{}", store.fetch_raw(&def_id).await?)?;
+        return Ok(());
+    }
+
+    let origin = origins.get(store.label());
+    let mut provenance = Vec::new();
+    if let Some(origin) = origin {
+        provenance.push(("Origin", origin.location.clone()));
+    }
+    let branch = agent_defs::path::split_branch(id)
+        .0
+        .or(origin.and_then(|origin| origin.branch.as_deref()));
+    if let Some(branch) = branch {
+        provenance.push(("Branch", branch.to_owned()));
+    }
+    provenance.push(("Synced", last_synced(store)?));
+    let ratings = store.ratings().map_err(|e| anyhow::anyhow!("{e}"))?;
+    if let Some(rating) = ratings.iter().find(|rating| rating.id == def_id) {
+        provenance.push(("Rating", rating.describe()));
+    }
+
+    match view {
+        View::Raw => unreachable!("raw output returns early"),
+        View::Formatted { plain: true } => {
+            write!(output, "{}", format::plain_definition_with(&def, &provenance))?;
+        }
+        View::Formatted { plain: false } => {
+            print_definition(&def, &provenance, color::stdout(), output)?;
+        }
+        View::Export { format, path } => {
+            let path = path.unwrap_or_else(|| export::default_path(&def, format));
+            export::write(&def, &provenance, format, &path)?;
+            writeln!(output, "Exported {} to {}", def.name, path.display())?;
+        }
+    }
+    Ok(())
+}
+
+fn print_definition(
+    def: &Definition,
+    provenance: &[(&str, String)],
+    color: bool,
+    output: &mut impl Write,
+) -> Result<()> {
+    // Labels are padded outside the color codes so values still line up.
+    let mut field = |label: &str, value: &str| {
+        let label = format!("{label}:");
+        let padding = " ".repeat(13usize.saturating_sub(label.len()));
+        let label = color::paint(color, BOLD_STYLE, &label);
+        writeln!(output, "{label}{padding}{value}")
+    };
+    field("Name", &def.name)?;
+    field("Kind", &def.kind.to_string())?;
+
+    if let Some(desc) = &def.description {
+        field("Description", desc)?;
+    }
+    if let Some(category) = &def.category {
+        field("Category", category)?;
+    }
+    if let Some(model) = &def.model {
+        field("Model", model)?;
+    }
+    if !def.tools.is_empty() {
+        field("Tools", &def.tools.join(", "))?;
+    }
+    field("Source", &def.source_label)?;
+    for (label, value) in provenance {
+        field(label, value)?;
+    }
+    field("ID", def.id.as_str())?;
+    writeln!(output)?;
+    write!(output, "{}", def.body)?;
+    Ok(())
+}
+
+/// When `store` last synced, such as "2026-03-02 (4 days ago)".
+fn last_synced(store: &DefinitionStore) -> Result<String> {
+    let store_err = |e| anyhow::anyhow!("{e}");
+    let Some(at) = store.last_synced_at().map_err(store_err)? else {
+        return Ok("never synced".to_owned());
+    };
+    let age = match store.sync_status().map_err(store_err)? {
+        SyncStatus::Fresh { days_old: 0 } => "today".to_owned(),
+        SyncStatus::Fresh { days_old: 1 } => "1 day ago".to_owned(),
+        SyncStatus::Fresh { days_old } => format!("{days_old} days ago"),
+        SyncStatus::Stale { days_old } => format!("{days_old} days ago, stale"),
+        SyncStatus::NeverSynced => return Ok("never synced".to_owned()),
+    };
+    Ok(format!("{} ({age})", format_date(at)))
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::DefinitionKind;
+
+    use super::*;
+
+    fn store(label: &str, synced: bool) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        store
+            .upsert_definition(&Definition {
+                id: DefinitionId::new("agents/reviewer.md"),
+                name: "reviewer".into(),
+                description: None,
+                kind: DefinitionKind::Agent,
+                category: None,
+                source_label: label.into(),
+                body: "Review code.\n".into(),
+                tools: vec![],
+                model: None,
+                metadata: HashMap::new(),
+                raw: String::new(),
+            })
+            .unwrap();
+        if synced {
+            store.record_sync().unwrap();
         }
+        store
     }
 
-    bail!("Definition not found: {id}");
+    fn origins() -> HashMap<String, Origin> {
+        HashMap::from([(
+            "acme".to_owned(),
+            Origin {
+                location: "github.com/acme/agents".into(),
+                branch: Some("main".into()),
+            },
+        )])
+    }
+
+    async fn show(stores: &[DefinitionStore], source: Option<&str>) -> (Result<()>, String) {
+        let mut output = Vec::new();
+        let result = run(
+            stores,
+            &origins(),
+            "agents/reviewer.md",
+            source,
+            View::Formatted { plain: false },
+            &mut output,
+        )
+        .await;
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[tokio::test]
+    async fn ambiguous_ids_list_their_sources() {
+        let stores = [store("acme", true), store("other", false)];
+        let (result, out) = show(&stores, None).await;
+
+        assert!(result.unwrap_err().to_string().contains("pass --source"));
+        let today = format_date(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        );
+        assert_eq!(
+            out,
+            format!(
+                "agents/reviewer.md is in 2 sources:\n  acme   {today} (today)\n  other  never synced\n"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn picked_source_shows_provenance() {
+        let stores = [store("acme", true), store("other", false)];
+        let (result, out) = show(&stores, Some("acme")).await;
+
+        result.unwrap();
+        assert!(out.contains("Source:      acme\n"), "{out}");
+        assert!(out.contains("Origin:      github.com/acme/agents\n"), "{out}");
+        assert!(out.contains("Branch:      main\n"), "{out}");
+        assert!(out.contains("(today)\n"), "{out}");
+        assert!(out.ends_with("\nReview code.\n"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn export_writes_a_page_instead_of_printing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.html");
+        let stores = [store("acme", true)];
+        let mut output = Vec::new();
+        let view = View::Export {
+            format: ExportFormat::Html,
+            path: Some(path.clone()),
+        };
+        run(&stores, &origins(), "agents/reviewer.md", None, view, &mut output)
+            .await
+            .unwrap();
+
+        let out = String::from_utf8(output).unwrap();
+        assert_eq!(out, format!("Exported reviewer to {}\n", path.display()));
+        let page = std::fs::read_to_string(&path).unwrap();
+        assert!(page.contains("<tr><th>Origin</th><td>github.com/acme/agents</td></tr>"), "{page}");
+        assert!(page.contains("<p>Review code.</p>"), "{page}");
+    }
+
+    #[tokio::test]
+    async fn single_source_needs_no_filter() {
+        let stores = [store("other", false)];
+        let (result, out) = show(&stores, None).await;
+
+        result.unwrap();
+        assert!(out.contains("Synced:      never synced\n"), "{out}");
+        assert!(!out.contains("Origin:"), "{out}");
+    }
 }
@@ -0,0 +1,283 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+
+use agent_defs::tree::{self, TreeExpansion, TreeNode, TreeRow};
+use agent_defs::{Definition, DefinitionSummary, Source};
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use super::export::{self, escape};
+
+/// Filters the index page against `SEARCH_INDEX` as the reader types.
+const SEARCH_JS: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var catalog = document.getElementById("catalog");
+  var results = document.getElementById("results");
+  input.addEventListener("input", function () {
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = "";
+    catalog.hidden = terms.length > 0;
+    results.hidden = terms.length === 0;
+    window.SEARCH_INDEX.filter(function (entry) {
+      return terms.every(function (term) { return entry.text.indexOf(term) !== -1; });
+    }).forEach(function (entry) {
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = entry.url;
+      link.textContent = entry.name;
+      item.appendChild(link);
+      var meta = document.createElement("span");
+      meta.className = "meta";
+      meta.textContent = " " + entry.kind + (entry.category ? " / " + entry.category : "") +
+        " [" + entry.source + "]";
+      item.appendChild(meta);
+      if (entry.description) {
+        var description = document.createElement("div");
+        description.className = "description";
+        description.textContent = entry.description;
+        item.appendChild(description);
+      }
+      results.appendChild(item);
+    });
+  });
+})();
+"#;
+
+/// One definition in the client-side search index.
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    name: &'a str,
+    description: Option<&'a str>,
+    kind: String,
+    category: Option<&'a str>,
+    source: &'a str,
+    url: String,
+    /// Everything searchable, lowercased, so the page matches with a
+    /// plain substring test.
+    text: String,
+}
+
+/// Render every cached definition in `sources` into a static site under
+/// `out`: an index by kind and category with search, and one page per
+/// definition. The search index is a script rather than JSON so the site
+/// works straight from disk, where browsers won't fetch local files.
+pub async fn build(sources: &[impl Source], out: &Path, output: &mut impl Write) -> Result<()> {
+    let mut summaries = Vec::new();
+    let mut definitions = Vec::new();
+    for source in sources {
+        for summary in source.list().await? {
+            match source.fetch(&summary.id).await {
+                Ok(def) => {
+                    summaries.push(summary);
+                    definitions.push(def);
+                }
+                Err(e) => eprintln!("warning: skipping [{}] {}: {e}", source.label(), summary.id),
+            }
+        }
+    }
+
+    let pages = out.join("defs");
+    std::fs::create_dir_all(&pages).with_context(|| format!("creating {}", pages.display()))?;
+    for (summary, def) in summaries.iter().zip(&definitions) {
+        let path = pages.join(page_name(summary));
+        std::fs::write(&path, definition_page(def))
+            .with_context(|| format!("writing {}", path.display()))?;
+    }
+
+    let index: Vec<IndexEntry> = summaries.iter().map(index_entry).collect();
+    let script = format!(
+        "window.SEARCH_INDEX = {};\n",
+        serde_json::to_string(&index)?
+    );
+    let files = [
+        ("index.html", index_page(&summaries)),
+        ("search-index.js", script),
+        ("search.js", SEARCH_JS.to_owned()),
+    ];
+    for (name, content) in files {
+        let path = out.join(name);
+        std::fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
+    }
+
+    writeln!(
+        output,
+        "Built {} definition pages; open {}",
+        definitions.len(),
+        out.join("index.html").display()
+    )?;
+    Ok(())
+}
+
+/// File name of a definition's page: its source and ID, with anything
+/// that isn't alphanumeric turned into `-`.
+fn page_name(summary: &DefinitionSummary) -> String {
+    let slug: String = summary
+        .qualified_id()
+        .as_str()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{slug}.html")
+}
+
+fn index_entry(summary: &DefinitionSummary) -> IndexEntry<'_> {
+    let text = [
+        summary.name.as_str(),
+        summary.description.as_deref().unwrap_or(""),
+        summary.kind.display_label(),
+        summary.category.as_deref().unwrap_or(""),
+        &summary.source_label,
+        summary.id.as_str(),
+    ]
+    .join(" ")
+    .to_lowercase();
+    IndexEntry {
+        name: &summary.name,
+        description: summary.description.as_deref(),
+        kind: summary.kind.to_string(),
+        category: summary.category.as_deref(),
+        source: &summary.source_label,
+        url: format!("defs/{}", page_name(summary)),
+        text,
+    }
+}
+
+fn definition_page(def: &Definition) -> String {
+    let body = format!(
+        "<nav><a href=\"../index.html\">&larr; Catalog</a></nav>\n{}",
+        export::definition_html(def, &[])
+    );
+    export::document(&def.name, &body)
+}
+
+/// The catalog grouped by kind, then category, every node open.
+fn index_page(summaries: &[DefinitionSummary]) -> String {
+    let mut body = format!(
+        "<h1>Agent definitions</h1>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search {} definitions\" autofocus>\n\
+         <ul id=\"results\" class=\"definitions\" hidden></ul>\n<div id=\"catalog\">\n",
+        summaries.len()
+    );
+    let mut list_open = false;
+    for row in tree::build_tree(summaries, &TreeExpansion::default(), true) {
+        match row {
+            TreeRow::Node {
+                node, label, count, ..
+            } => {
+                if std::mem::take(&mut list_open) {
+                    body.push_str("</ul>\n");
+                }
+                let level = match node {
+                    TreeNode::Kind(_) => 2,
+                    TreeNode::Category(..) => 3,
+                };
+                let _ = writeln!(body, "<h{level}>{} ({count})</h{level}>", escape(&label));
+            }
+            TreeRow::Item { summary_index, .. } => {
+                if !std::mem::replace(&mut list_open, true) {
+                    body.push_str("<ul class=\"definitions\">\n");
+                }
+                let summary = &summaries[summary_index];
+                let _ = write!(
+                    body,
+                    "<li><a href=\"defs/{}\">{}</a> <span class=\"meta\">[{}]</span>",
+                    page_name(summary),
+                    escape(&summary.name),
+                    escape(&summary.source_label)
+                );
+                if let Some(description) = &summary.description {
+                    let _ = write!(
+                        body,
+                        "<div class=\"description\">{}</div>",
+                        escape(description)
+                    );
+                }
+                body.push_str("</li>\n");
+            }
+        }
+    }
+    if list_open {
+        body.push_str("</ul>\n");
+    }
+    body.push_str(
+        "</div>\n<script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>\n",
+    );
+    export::document("Agent definitions", &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs::{DefinitionId, DefinitionKind};
+    use agent_defs_store::DefinitionStore;
+
+    use super::*;
+
+    fn store(label: &str, defs: &[(&str, Option<&str>)]) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        for (id, category) in defs {
+            let name = Path::new(id).file_stem().unwrap().to_str().unwrap();
+            store
+                .upsert_definition(&Definition {
+                    id: DefinitionId::new(*id),
+                    name: name.into(),
+                    description: Some(format!("The {name} <agent>")),
+                    kind: DefinitionKind::Agent,
+                    category: category.map(str::to_owned),
+                    source_label: label.into(),
+                    body: format!("# {name}\n\nDoes things.\n"),
+                    tools: vec![],
+                    model: None,
+                    metadata: HashMap::new(),
+                    raw: String::new(),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn build_writes_an_index_pages_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = [
+            store(
+                "acme",
+                &[
+                    ("agents/reviewer.md", Some("review")),
+                    ("agents/helper.md", None),
+                ],
+            ),
+            store("other", &[("agents/reviewer.md", Some("review"))]),
+        ];
+        let mut output = Vec::new();
+        build(&sources, dir.path(), &mut output).await.unwrap();
+
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.starts_with("Built 3 definition pages; open "), "{out}");
+
+        let index = std::fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert!(index.contains("<h2>Agents (3)</h2>"), "{index}");
+        assert!(index.contains("<h3>review (2)</h3>"), "{index}");
+        assert!(
+            index.contains("<a href=\"defs/other-agents-reviewer-md.html\">reviewer</a>"),
+            "{index}"
+        );
+        assert!(index.contains("The helper &lt;agent&gt;"), "{index}");
+
+        let page = dir.path().join("defs/acme-agents-reviewer-md.html");
+        let page = std::fs::read_to_string(page).unwrap();
+        assert!(page.contains("<a href=\"../index.html\">"), "{page}");
+        assert!(page.contains("<h1>reviewer</h1>"), "{page}");
+        assert!(page.contains("<p>Does things.</p>"), "{page}");
+
+        let search = std::fs::read_to_string(dir.path().join("search-index.js")).unwrap();
+        assert!(search.starts_with("window.SEARCH_INDEX = [{"), "{search}");
+        assert!(
+            search.contains("\"url\":\"defs/acme-agents-helper-md.html\""),
+            "{search}"
+        );
+        assert!(dir.path().join("search.js").exists());
+    }
+}
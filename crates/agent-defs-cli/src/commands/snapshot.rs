@@ -0,0 +1,163 @@
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agent_defs::Source;
+use agent_defs_cli::sources::catalog_snapshot::{
+    CatalogSnapshot, SNAPSHOT_VERSION, SnapshotFile, SnapshotSource,
+};
+use agent_defs_store::DefinitionStore;
+use anyhow::{Context, Result, bail};
+
+/// Every store's cached files, as they were synced.
+pub fn collect<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+) -> Result<CatalogSnapshot> {
+    let mut sources = Vec::new();
+    for store in stores {
+        let files = store.cached_files().map_err(|e| anyhow::anyhow!("{e}"))?;
+        sources.push(SnapshotSource {
+            label: store.label().to_owned(),
+            files: files.into_iter().map(SnapshotFile::from).collect(),
+        });
+    }
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(CatalogSnapshot {
+        version: SNAPSHOT_VERSION,
+        created_at,
+        sources,
+    })
+}
+
+/// Publish `snapshot` to `dest`. An HTTP(S) URL gets a `PUT`, which is
+/// also how presigned S3 and GCS upload URLs take an object; anything else
+/// is a file path to write.
+pub async fn push(
+    snapshot: &CatalogSnapshot,
+    dest: &str,
+    token: Option<&str>,
+    client: &reqwest::Client,
+    output: &mut impl Write,
+) -> Result<()> {
+    let body = serde_json::to_vec(snapshot)?;
+    let size = body.len();
+    if dest.starts_with("http://") || dest.starts_with("https://") {
+        let mut request = client
+            .put(dest)
+            .header("User-Agent", "agent-def-fetcher")
+            .header("Content-Type", "application/json")
+            .body(body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("failed to reach {dest}"))?;
+        if !response.status().is_success() {
+            bail!("{dest} returned HTTP {}", response.status());
+        }
+    } else {
+        std::fs::write(dest, &body).with_context(|| format!("writing {dest}"))?;
+    }
+
+    let files: usize = snapshot.sources.iter().map(|s| s.files.len()).sum();
+    writeln!(
+        output,
+        "Pushed {files} files from {} sources ({} KiB) to {dest}",
+        snapshot.sources.len(),
+        size.div_ceil(1024)
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs::{Definition, DefinitionId, DefinitionKind};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn store() -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory("acme").unwrap();
+        store
+            .upsert_definition(&Definition {
+                id: DefinitionId::new("agents/reviewer.md"),
+                name: "reviewer".into(),
+                description: None,
+                kind: DefinitionKind::Agent,
+                category: None,
+                source_label: "acme".into(),
+                body: "Reviews.".into(),
+                tools: vec![],
+                model: None,
+                metadata: HashMap::new(),
+                raw: "---\nname: reviewer\n---\nReviews.".into(),
+            })
+            .unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn push_puts_the_snapshot_with_the_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/catalog.json"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let snapshot = collect([&store()]).unwrap();
+        assert_eq!(snapshot.sources[0].label, "acme");
+        assert_eq!(snapshot.sources[0].files[0].path, "agents/reviewer.md");
+
+        let url = format!("{}/catalog.json", server.uri());
+        let mut output = Vec::new();
+        push(
+            &snapshot,
+            &url,
+            Some("secret"),
+            &reqwest::Client::new(),
+            &mut output,
+        )
+        .await
+        .unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.starts_with("Pushed 1 files from 1 sources"), "{out}");
+
+        let body = &server.received_requests().await.unwrap()[0].body;
+        let sent = CatalogSnapshot::parse(std::str::from_utf8(body).unwrap()).unwrap();
+        assert_eq!(
+            sent.sources[0].files[0].content,
+            "---\nname: reviewer\n---\nReviews."
+        );
+    }
+
+    #[tokio::test]
+    async fn push_reports_a_rejected_upload() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let snapshot = collect([&store()]).unwrap();
+        let err = push(
+            &snapshot,
+            &server.uri(),
+            None,
+            &reqwest::Client::new(),
+            &mut Vec::new(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("HTTP 403"), "{err}");
+    }
+}
@@ -0,0 +1,281 @@
+use std::io::Write;
+use std::path::Path;
+
+use agent_defs::{Policy, Source};
+use agent_defs::changelog::format_date;
+use agent_defs_cli::catalog;
+use agent_defs_cli::config::{self, SourceEntry, SourceType};
+use agent_defs_cli::presets::Preset;
+use agent_defs_cli::validate;
+use agent_defs_store::DefinitionStore;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+/// List `presets`, marking those a source in `configured` already uses.
+pub fn discover(
+    presets: &[Preset],
+    configured: &[SourceEntry],
+    output: &mut impl Write,
+) -> Result<()> {
+    if presets.is_empty() {
+        writeln!(output, "No presets.")?;
+        return Ok(());
+    }
+    let width = presets.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    for preset in presets {
+        let added = configured.iter().any(|source| {
+            matches!(&source.source_type, SourceType::Preset { name } if *name == preset.name)
+        });
+        writeln!(
+            output,
+            "{:width$}  {}/{}{}\n{:width$}  {}",
+            preset.name,
+            preset.owner,
+            preset.repo,
+            if added { " (added)" } else { "" },
+            "",
+            preset.description,
+        )?;
+    }
+    writeln!(output, "\nAdd one with `sources add --preset <name>`.")?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Sources<'a> {
+    sources: &'a [SourceEntry],
+}
+
+/// Add a source for `preset`, labelled `label` or the preset's name, to the
+/// config file at `path`. The file's contents are kept as they are and the
+/// source appended; a missing file is started with the default sources, so
+/// they aren't lost. Nothing is written if the org `policy` doesn't allow
+/// the preset's repository, or if the result has errors, such as a label
+/// already in use.
+pub fn add(
+    path: &Path,
+    preset: &Preset,
+    label: Option<&str>,
+    policy: &Policy,
+    output: &mut impl Write,
+) -> Result<()> {
+    let label = label.unwrap_or(&preset.name).to_owned();
+    let source = SourceEntry {
+        label: label.clone(),
+        enabled: true,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        token: None,
+        source_type: SourceType::Preset {
+            name: preset.name.clone(),
+        },
+    };
+    if let Err(e) = policy.check_source(&catalog::origin(&source).location) {
+        bail!("not adding `{label}`: {e}");
+    }
+
+    let mut contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => render(&config::default_sources())?,
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    if !contents.is_empty() {
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents.push('\n');
+    }
+    contents.push_str(&render(std::slice::from_ref(&source))?);
+
+    let errors: Vec<String> = validate::check(&contents)
+        .1
+        .iter()
+        .filter(|issue| issue.is_error())
+        .map(ToString::to_string)
+        .collect();
+    if !errors.is_empty() {
+        bail!(
+            "not adding `{label}`; the config would have errors:\n{}",
+            errors.join("\n")
+        );
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, contents)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    writeln!(
+        output,
+        "Added source `{label}` ({}/{}) to {}. Run `sync` to fetch it.",
+        preset.owner,
+        preset.repo,
+        path.display()
+    )?;
+    Ok(())
+}
+
+/// Each source's failure streak and latest error, noting the ones that
+/// have failed `degraded_after` times in a row.
+pub fn health<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    degraded_after: u32,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut rows = Vec::new();
+    for store in stores {
+        let health = store.health().map_err(|e| anyhow::anyhow!("{e}"))?;
+        rows.push((store.label().to_owned(), health));
+    }
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+
+    let mut degraded = 0;
+    for (label, health) in &rows {
+        let streak = health.failure_streak;
+        let state = match streak {
+            0 => "ok".to_owned(),
+            1 => "1 failed sync".to_owned(),
+            _ => format!("{streak} failed syncs in a row"),
+        };
+        if health.is_degraded(degraded_after) {
+            degraded += 1;
+            writeln!(output, "{label:width$}  {state}, degraded")?;
+        } else {
+            writeln!(output, "{label:width$}  {state}")?;
+        }
+        if let (Some(error), Some(at)) = (&health.last_error, health.last_failed_at) {
+            writeln!(output, "{:width$}  last failed {}: {error}", "", format_date(at))?;
+        }
+    }
+
+    if degraded > 0 {
+        writeln!(
+            output,
+            "\nDegraded sources are only synced by `sync`, which ends the streak when it \
+             succeeds; other commands use what they have cached."
+        )?;
+    }
+    Ok(())
+}
+
+fn render(sources: &[SourceEntry]) -> Result<String> {
+    toml::to_string(&Sources { sources })
+        .map_err(|e| anyhow::anyhow!("failed to render sources: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::SyncError;
+    use agent_defs::test_support::FakeSyncProvider;
+
+    use super::*;
+
+    fn preset() -> Preset {
+        agent_defs_cli::presets::find("wshobson-agents").unwrap()
+    }
+
+    #[test]
+    fn add_appends_to_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sources.toml");
+        std::fs::write(&path, "# my sources\nupdate_checks = false\n").unwrap();
+
+        let mut output = Vec::new();
+        add(&path, &preset(), None, &Policy::default(), &mut output).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# my sources\n"), "{contents}");
+        let config = config::read_config(&path).unwrap();
+        assert_eq!(config.sources.len(), 1);
+        assert_eq!(config.sources[0].label, "wshobson-agents");
+        assert!(matches!(
+            &config.sources[0].source_type,
+            SourceType::Preset { name } if name == "wshobson-agents"
+        ));
+
+        let err = add(&path, &preset(), None, &Policy::default(), &mut output).unwrap_err();
+        assert!(err.to_string().contains("more than one source"), "{err}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+        add(&path, &preset(), Some("plugins"), &Policy::default(), &mut output).unwrap();
+        assert_eq!(config::read_config(&path).unwrap().sources.len(), 2);
+    }
+
+    #[test]
+    fn add_refuses_sources_the_policy_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sources.toml");
+        let policy = Policy::parse("allowed_sources = [\"github.com/acme/*\"]").unwrap();
+
+        let err = add(&path, &preset(), None, &policy, &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("not an allowed source"), "{err}");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn add_keeps_the_default_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent-def-fetcher").join("sources.toml");
+
+        add(&path, &preset(), None, &Policy::default(), &mut Vec::new()).unwrap();
+        let labels: Vec<String> = config::read_config(&path)
+            .unwrap()
+            .sources
+            .into_iter()
+            .map(|source| source.label)
+            .collect();
+        let mut expected: Vec<String> = config::default_sources()
+            .into_iter()
+            .map(|source| source.label)
+            .collect();
+        expected.push("wshobson-agents".into());
+        assert_eq!(labels, expected);
+    }
+
+    #[tokio::test]
+    async fn health_shows_streaks_and_degraded_sources() {
+        let ok = DefinitionStore::open_in_memory("ok").unwrap();
+        let flaky = DefinitionStore::open_in_memory("flaky").unwrap();
+        let dead = DefinitionStore::open_in_memory("dead").unwrap();
+        let provider = |label: &str, failures: usize| {
+            (0..failures).fold(FakeSyncProvider::new(label), |provider, _| {
+                provider.failing_next(SyncError::NotFound("acme/gone".into()))
+            })
+        };
+        ok.sync(&provider("ok", 0)).await.unwrap();
+        flaky.sync(&provider("flaky", 1)).await.unwrap_err();
+        let gone = provider("dead", 3);
+        for _ in 0..3 {
+            dead.sync(&gone).await.unwrap_err();
+        }
+
+        let mut output = Vec::new();
+        health([&ok, &flaky, &dead], 3, &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.starts_with("ok     ok\nflaky  1 failed sync\n"), "{out}");
+        assert!(out.contains("dead   3 failed syncs in a row, degraded\n"), "{out}");
+        assert!(out.contains("last failed "), "{out}");
+        assert!(out.contains("acme/gone"), "{out}");
+        assert!(out.contains("Degraded sources"), "{out}");
+    }
+
+    #[test]
+    fn discover_marks_added_presets() {
+        let presets = agent_defs_cli::presets::builtin();
+        let configured = [SourceEntry {
+            label: "w".into(),
+            enabled: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            token: None,
+            source_type: SourceType::Preset {
+                name: "wshobson-agents".into(),
+            },
+        }];
+        let mut output = Vec::new();
+        discover(&presets, &configured, &mut output).unwrap();
+        let out = String::from_utf8(output).unwrap();
+        assert!(out.contains("wshobson/agents (added)"), "{out}");
+        assert!(!out.contains("wshobson/commands (added)"), "{out}");
+    }
+}
@@ -0,0 +1,49 @@
+//! Spans of time written as a count and a one-letter unit, such as `3d`.
+
+/// Seconds in the span `arg`, where `units` gives the length in seconds of
+/// each unit letter accepted. `expected` names the accepted forms when
+/// `arg` is none of them.
+pub fn parse(arg: &str, units: &[(char, u64)], expected: &str) -> Result<u64, String> {
+    let invalid = || format!("expected {expected}, got `{arg}`");
+    let unit = arg.chars().next_back().ok_or_else(invalid)?;
+    let &(_, unit_secs) = units
+        .iter()
+        .find(|(letter, _)| *letter == unit)
+        .ok_or_else(invalid)?;
+    let count: u64 = arg[..arg.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    count
+        .checked_mul(unit_secs)
+        .ok_or_else(|| format!("`{arg}` is too long a span"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNITS: &[(char, u64)] = &[('m', 60), ('h', 3_600)];
+
+    #[test]
+    fn multiplies_the_count_by_the_unit() {
+        assert_eq!(parse("90m", UNITS, "Nm or Nh"), Ok(5_400));
+        assert_eq!(parse("2h", UNITS, "Nm or Nh"), Ok(7_200));
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_read() {
+        assert_eq!(parse("", UNITS, "Nm or Nh"), Err("expected Nm or Nh, got ``".into()));
+        assert!(parse("3d", UNITS, "Nm or Nh").is_err());
+        assert!(parse("h", UNITS, "Nm or Nh").is_err());
+        assert!(parse("3é", UNITS, "Nm or Nh").is_err());
+        assert!(parse("é", UNITS, "Nm or Nh").is_err());
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert_eq!(
+            parse("18446744073709551615h", UNITS, "Nm or Nh"),
+            Err("`18446744073709551615h` is too long a span".into())
+        );
+    }
+}
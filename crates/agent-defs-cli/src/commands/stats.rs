@@ -0,0 +1,211 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use agent_defs::usage::sort_most_used;
+use agent_defs::{DefinitionUsage, Source};
+use agent_defs_store::DefinitionStore;
+use anyhow::Result;
+
+/// How many definitions `stats --personal` lists.
+const MOST_USED_LIMIT: usize = 10;
+
+/// Definition counts for each source, broken down by kind.
+pub async fn catalog<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    output: &mut impl Write,
+) -> Result<()> {
+    for store in stores {
+        let summaries = store.list().await?;
+        let mut by_kind: BTreeMap<String, usize> = BTreeMap::new();
+        for summary in &summaries {
+            *by_kind.entry(summary.kind.to_string()).or_default() += 1;
+        }
+        writeln!(
+            output,
+            "{} ({} definitions)",
+            store.label(),
+            summaries.len()
+        )?;
+        for (kind, count) in by_kind {
+            writeln!(output, "  {count:>5}  {kind}")?;
+        }
+    }
+    Ok(())
+}
+
+/// The definitions viewed and installed most, then how much of each source
+/// has been used at all, so sources that never get touched stand out.
+pub async fn personal<'a>(
+    stores: impl IntoIterator<Item = &'a DefinitionStore>,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut all: Vec<DefinitionUsage> = Vec::new();
+    let mut by_source = Vec::new();
+    for store in stores {
+        let usage = store.usage().map_err(|e| anyhow::anyhow!("{e}"))?;
+        let listed = store.list().await?;
+        // Usage outlives definitions a sync removed, so only what's still
+        // listed counts toward the share used.
+        let ids: HashSet<_> = listed.iter().map(|s| &s.id).collect();
+        let live: Vec<_> = usage.iter().filter(|u| ids.contains(&u.id)).cloned().collect();
+        by_source.push((store.label().to_owned(), listed.len(), summarize(&live)));
+        all.extend(usage);
+    }
+
+    if all.is_empty() {
+        writeln!(
+            output,
+            "No usage recorded yet. Definitions you show or install are counted here."
+        )?;
+        return Ok(());
+    }
+
+    sort_most_used(&mut all);
+    writeln!(output, "Most used")?;
+    for usage in all.iter().take(MOST_USED_LIMIT) {
+        writeln!(
+            output,
+            "  {:>4} {:<7} {:>4} {:<9} {} [{}] {}",
+            usage.views,
+            plural(usage.views, "view", "views"),
+            usage.installs,
+            plural(usage.installs, "install", "installs"),
+            usage.id,
+            usage.source_label,
+            usage.name
+        )?;
+    }
+
+    writeln!(output, "\nBy source")?;
+    for (label, total, (used, views, installs)) in by_source {
+        if used == 0 {
+            writeln!(
+                output,
+                "  {label}: never used ({total} {})",
+                plural(total as u64, "definition", "definitions")
+            )?;
+        } else {
+            writeln!(
+                output,
+                "  {label}: {used} of {total} definitions used ({views} {}, {installs} {})",
+                plural(views, "view", "views"),
+                plural(installs, "install", "installs")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete every recorded view and install, returning how many definitions
+/// had any.
+pub fn wipe<'a>(stores: impl IntoIterator<Item = &'a DefinitionStore>) -> Result<usize> {
+    let mut cleared = 0;
+    for store in stores {
+        cleared += store.clear_usage().map_err(|e| anyhow::anyhow!("{e}"))?;
+    }
+    Ok(cleared)
+}
+
+/// Definitions used, total views, and total installs.
+fn summarize(usage: &[DefinitionUsage]) -> (usize, u64, u64) {
+    let views = usage.iter().map(|u| u.views).sum();
+    let installs = usage.iter().map(|u| u.installs).sum();
+    (usage.len(), views, installs)
+}
+
+fn plural<'a>(count: u64, one: &'a str, many: &'a str) -> &'a str {
+    if count == 1 { one } else { many }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::{Definition, DefinitionId, DefinitionKind, UsageKind};
+
+    use super::*;
+
+    fn store(label: &str, ids: &[&str]) -> DefinitionStore {
+        let store = DefinitionStore::open_in_memory(label).unwrap();
+        for id in ids {
+            store
+                .upsert_definition(&Definition {
+                    kind: DefinitionKind::Agent,
+                    source_label: label.into(),
+                    ..agent_defs::test_support::definition(id)
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn catalog_counts_kinds_per_source() {
+        let acme = store("acme", &["a", "b"]);
+        let mut output = Vec::new();
+        catalog([&acme], &mut output).await.unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "acme (2 definitions)\n      2  agent\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn personal_ranks_usage_and_flags_unused_sources() {
+        let acme = store("acme", &["a", "b", "c"]);
+        let other = store("other", &["x"]);
+        acme.record_usage(&DefinitionId::new("a"), UsageKind::View)
+            .unwrap();
+        acme.record_usage(&DefinitionId::new("b"), UsageKind::Install)
+            .unwrap();
+
+        let mut output = Vec::new();
+        personal([&acme, &other], &mut output).await.unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        let b = out.find("b [acme] b").unwrap();
+        let a = out.find("a [acme] a").unwrap();
+        assert!(b < a, "installed definition should rank first:\n{out}");
+        assert!(out.contains("acme: 2 of 3 definitions used (1 view, 1 install)"));
+        assert!(out.contains("other: never used (1 definition)"));
+    }
+
+    #[tokio::test]
+    async fn personal_leaves_removed_definitions_out_of_the_share_used() {
+        let acme = store("acme", &["a", "b"]);
+        acme.record_usage(&DefinitionId::new("a"), UsageKind::View)
+            .unwrap();
+        acme.record_usage(&DefinitionId::new("gone"), UsageKind::View)
+            .unwrap();
+
+        let mut output = Vec::new();
+        personal([&acme], &mut output).await.unwrap();
+        let out = String::from_utf8(output).unwrap();
+
+        assert!(out.contains("acme: 1 of 2 definitions used (1 view, 0 installs)"), "{out}");
+    }
+
+    #[tokio::test]
+    async fn personal_without_usage_says_so() {
+        let acme = store("acme", &["a"]);
+        let mut output = Vec::new();
+        personal([&acme], &mut output).await.unwrap();
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .starts_with("No usage recorded yet.")
+        );
+    }
+
+    #[test]
+    fn wipe_clears_every_store() {
+        let acme = store("acme", &["a"]);
+        let other = store("other", &["x"]);
+        acme.record_usage(&DefinitionId::new("a"), UsageKind::View)
+            .unwrap();
+        other
+            .record_usage(&DefinitionId::new("x"), UsageKind::View)
+            .unwrap();
+
+        assert_eq!(wipe([&acme, &other]).unwrap(), 2);
+        assert!(acme.usage().unwrap().is_empty());
+    }
+}
@@ -1,22 +1,64 @@
+use std::sync::Arc;
+
 use agent_defs::{Feedback, SyncProvider};
-use agent_defs_store::DefinitionStore;
-use anyhow::Result;
+use agent_defs_store::{DefinitionStore, SyncReport, SyncStatus};
+use agent_defs_cli::catalog::SourcePair;
+use agent_defs_tui::{Freshness, SourceReport, SyncController, SyncSelection};
+use anyhow::{Result, bail};
+
+use super::color::{self, BOLD_STYLE, ERROR_STYLE, WARNING_STYLE};
+use super::rating::CommunityIndex;
 
-/// Print feedback items to stderr.
+/// Print feedback items to stderr, warnings and errors in color.
 pub fn print_feedback(feedback: &[Feedback]) {
+    let color = color::stderr();
     for item in feedback {
-        eprintln!("{item}");
+        let style = match item {
+            Feedback::Info(_) => None,
+            Feedback::Warning(_) => Some(WARNING_STYLE),
+            Feedback::Error(_) => Some(ERROR_STYLE),
+        };
+        match style {
+            Some(style) => eprintln!("{}", color::paint(color, style, &item.to_string())),
+            None => eprintln!("{item}"),
+        }
+    }
+}
+
+/// Print what to do about a sync that failed with `error`, when the remote
+/// refused it for a reason the user can fix, such as a missing token.
+pub fn print_guidance(error: &anyhow::Error) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    if let Some(guidance) = agent_defs::guidance(error.as_ref(), now) {
+        eprintln!("hint: {guidance}");
     }
 }
 
 /// Run sync and print progress/results to stdout, warnings to stderr.
-pub async fn run(store: &DefinitionStore, provider: &dyn SyncProvider) -> Result<()> {
-    println!("Syncing definitions from {}...", provider.label());
+/// With `quiet`, nothing is printed; failures still come back as errors
+/// for the caller to report. Ratings from `index` are merged after a
+/// successful sync; failing to fetch them only warns.
+pub async fn run(
+    store: &DefinitionStore,
+    provider: &dyn SyncProvider,
+    index: Option<&CommunityIndex>,
+    quiet: bool,
+) -> Result<SyncReport> {
+    if !quiet {
+        let label = color::paint(color::stdout(), BOLD_STYLE, provider.label());
+        println!("Syncing definitions from {label}...");
+    }
 
-    let report = store
-        .sync(provider)
-        .await
-        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    let report = store.sync(provider).await?;
+    let ratings = match index {
+        Some(index) => Some(index.refresh(store).await),
+        None => None,
+    };
+    if quiet {
+        return Ok(report);
+    }
 
     print_feedback(&report.feedback);
 
@@ -24,6 +66,163 @@ pub async fn run(store: &DefinitionStore, provider: &dyn SyncProvider) -> Result
         "Synced {} definitions ({} skipped).",
         report.synced, report.skipped
     );
+    if !report.changes.is_empty() {
+        println!(
+            "{} change(s) since the last sync; run `agent-def-fetcher changes` to see them.",
+            report.changes.len()
+        );
+    }
+    match ratings {
+        Some(Ok(0)) | None => {}
+        Some(Ok(count)) => println!("Merged {count} community rating(s)."),
+        Some(Err(e)) => eprintln!(
+            "warning: could not fetch community ratings for [{}]: {e:#}",
+            provider.label()
+        ),
+    }
 
-    Ok(())
+    Ok(report)
+}
+
+/// How fresh `store`'s cache is, as the TUI and `--only-stale` see it.
+pub fn freshness(store: &DefinitionStore) -> Freshness {
+    match store.sync_status() {
+        Ok(SyncStatus::Fresh { days_old }) => Freshness::Fresh { days_old },
+        Ok(SyncStatus::Stale { days_old }) => Freshness::Stale { days_old },
+        Ok(SyncStatus::NeverSynced) | Err(_) => Freshness::NeverSynced,
+    }
+}
+
+/// The pairs `selection` covers. Naming a source that isn't configured is
+/// an error, so a typo doesn't quietly sync nothing.
+pub fn select(pairs: Vec<SourcePair>, selection: &SyncSelection) -> Result<Vec<SourcePair>> {
+    let known: Vec<&str> = pairs.iter().map(|(_, provider)| provider.label()).collect();
+    if let Some(label) = selection.unknown(&known) {
+        bail!("no source named [{label}]; configured: {}", known.join(", "));
+    }
+    Ok(pairs
+        .into_iter()
+        .filter(|(store, provider)| selection.includes(provider.label(), freshness(store)))
+        .collect())
+}
+
+/// Each configured store with the provider that fills it, as the TUI sees
+/// them when syncing.
+pub struct StoreSyncController {
+    pairs: Vec<(Arc<DefinitionStore>, Arc<dyn SyncProvider>)>,
+    index: Option<CommunityIndex>,
+}
+
+impl StoreSyncController {
+    pub fn new(pairs: Vec<(Arc<DefinitionStore>, Arc<dyn SyncProvider>)>) -> Self {
+        Self { pairs, index: None }
+    }
+
+    /// Merge ratings from `index` after each sync.
+    pub fn with_community_index(mut self, index: Option<CommunityIndex>) -> Self {
+        self.index = index;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncController for StoreSyncController {
+    fn sources(&self) -> Vec<String> {
+        self.pairs
+            .iter()
+            .map(|(_, provider)| provider.label().to_owned())
+            .collect()
+    }
+
+    fn status(&self, label: &str) -> Freshness {
+        self.pairs
+            .iter()
+            .find(|(_, provider)| provider.label() == label)
+            .map_or(Freshness::NeverSynced, |(store, _)| freshness(store))
+    }
+
+    async fn sync_source(&self, label: &str) -> Result<SourceReport> {
+        let (store, provider) = self
+            .pairs
+            .iter()
+            .find(|(_, provider)| provider.label() == label)
+            .ok_or_else(|| anyhow::anyhow!("no source named [{label}]"))?;
+        let report = store.sync(provider.as_ref()).await?;
+        let mut warnings: Vec<String> = report
+            .feedback
+            .iter()
+            .filter(|fb| fb.is_warning())
+            .map(|fb| fb.message().to_owned())
+            .collect();
+        if let Some(index) = &self.index
+            && let Err(e) = index.refresh(store).await
+        {
+            warnings.push(format!("could not fetch community ratings: {e:#}"));
+        }
+        Ok(SourceReport {
+            synced: report.synced,
+            skipped: report.skipped,
+            warnings,
+            changes: report.changes,
+            skipped_files: store.skipped_files().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::FakeSyncProvider;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn controller_syncs_sources_by_label() {
+        let store = Arc::new(DefinitionStore::open_in_memory("acme").unwrap());
+        let provider = FakeSyncProvider::new("acme")
+            .with_file("agents/a.md", "---\nname: a\ndescription: A\n---\nBody\n");
+        let controller = StoreSyncController::new(vec![(store, Arc::new(provider))]);
+        assert_eq!(controller.sources(), ["acme"]);
+        assert_eq!(controller.status("acme"), Freshness::NeverSynced);
+
+        let report = controller.sync_source("acme").await.unwrap();
+        assert_eq!(report.synced, 1);
+        assert_eq!(controller.status("acme"), Freshness::Fresh { days_old: 0 });
+
+        let missing = controller.sync_source("other").await.unwrap_err();
+        assert_eq!(missing.to_string(), "no source named [other]");
+    }
+
+    fn pair(label: &str) -> SourcePair {
+        let store = Arc::new(DefinitionStore::open_in_memory(label).unwrap());
+        (store, Box::new(FakeSyncProvider::new(label)))
+    }
+
+    fn labels(pairs: &[SourcePair]) -> Vec<&str> {
+        pairs.iter().map(|(_, provider)| provider.label()).collect()
+    }
+
+    #[tokio::test]
+    async fn selection_picks_named_and_stale_sources() {
+        let pairs = vec![pair("acme"), pair("other")];
+        pairs[0].0.sync(pairs[0].1.as_ref()).await.unwrap();
+
+        let only_stale = SyncSelection {
+            only_stale: true,
+            ..SyncSelection::default()
+        };
+        assert_eq!(labels(&select(pairs, &only_stale).unwrap()), ["other"]);
+
+        let named = SyncSelection {
+            labels: vec!["acme".into()],
+            only_stale: false,
+        };
+        assert_eq!(labels(&select(vec![pair("acme"), pair("other")], &named).unwrap()), ["acme"]);
+
+        let typo = SyncSelection {
+            labels: vec!["acne".into()],
+            only_stale: false,
+        };
+        let err = select(vec![pair("acme")], &typo).err().unwrap();
+        assert_eq!(err.to_string(), "no source named [acne]; configured: acme");
+    }
 }
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use agent_defs::{ChangeKind, DefinitionSummary, Query, Source};
+use agent_defs_cli::catalog::SourcePair;
+use agent_defs_cli::config::Alert;
+use anyhow::{Result, bail};
+
+use super::rating::CommunityIndex;
+
+/// Sync every source now and then once per `interval`, firing `alerts` for
+/// definitions each sync adds. Runs until interrupted.
+pub async fn run(
+    pairs: &[SourcePair],
+    alerts: &[Alert],
+    index: Option<&CommunityIndex>,
+    interval: Duration,
+) -> Result<()> {
+    if alerts.is_empty() {
+        eprintln!("note: no [[alerts]] in the config, so new definitions are only logged");
+    }
+
+    loop {
+        let added = sync_all(pairs, index).await;
+        for summary in &added {
+            println!(
+                "New: {} [{}] {}",
+                summary.id, summary.source_label, summary.name
+            );
+        }
+        for alert in alerts {
+            let matches = matching(alert, &added);
+            if !matches.is_empty() {
+                fire(alert, &matches).await;
+            }
+        }
+
+        println!(
+            "Next sync in {} minute(s); press Ctrl-C to stop.",
+            interval.as_secs() / 60
+        );
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Sync each source, returning summaries of the definitions that were added.
+async fn sync_all(
+    pairs: &[SourcePair],
+    index: Option<&CommunityIndex>,
+) -> Vec<DefinitionSummary> {
+    let mut added = Vec::new();
+    for (store, provider) in pairs {
+        let report = match super::sync::run(store, provider.as_ref(), index, false).await {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("warning: sync failed for [{}]: {e}", provider.label());
+                continue;
+            }
+        };
+
+        let new_ids: Vec<_> = report
+            .changes
+            .iter()
+            .filter(|change| change.change == ChangeKind::Added)
+            .map(|change| &change.id)
+            .collect();
+        if new_ids.is_empty() {
+            continue;
+        }
+        match store.list().await {
+            Ok(summaries) => added.extend(
+                summaries
+                    .into_iter()
+                    .filter(|summary| new_ids.contains(&&summary.id)),
+            ),
+            Err(e) => eprintln!("warning: could not list [{}]: {e}", provider.label()),
+        }
+    }
+    added
+}
+
+/// The newly added definitions that match the alert's query.
+pub fn matching<'a>(alert: &Alert, added: &'a [DefinitionSummary]) -> Vec<&'a DefinitionSummary> {
+    let query = Query::parse(&alert.query);
+    added
+        .iter()
+        .filter(|summary| query.matches(summary))
+        .collect()
+}
+
+/// Carry out an alert. Failures are reported, not fatal, so one broken
+/// alert doesn't stop the watch.
+async fn fire(alert: &Alert, matches: &[&DefinitionSummary]) {
+    if alert.notify
+        && let Err(e) = notify(alert, matches)
+    {
+        eprintln!("warning: desktop notification failed: {e}");
+    }
+    if let Some(command) = &alert.command {
+        let command = command.clone();
+        let query = alert.query.clone();
+        let matches: Vec<DefinitionSummary> = matches.iter().map(|s| (*s).clone()).collect();
+        let result = tokio::task::spawn_blocking(move || {
+            let matches: Vec<&DefinitionSummary> = matches.iter().collect();
+            run_command(&command, &query, &matches)
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("warning: alert command failed: {e}"),
+            Err(e) => eprintln!("warning: alert command panicked: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "desktop-notifications")]
+fn notify(alert: &Alert, matches: &[&DefinitionSummary]) -> Result<()> {
+    let names: Vec<&str> = matches.iter().map(|s| s.name.as_str()).collect();
+    notify_rust::Notification::new()
+        .appname("agent-def-fetcher")
+        .summary(&format!(
+            "{} new definition(s) for \"{}\"",
+            matches.len(),
+            alert.query
+        ))
+        .body(&names.join("\n"))
+        .show()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn notify(_alert: &Alert, _matches: &[&DefinitionSummary]) -> Result<()> {
+    bail!("this build was compiled without the desktop-notifications feature")
+}
+
+/// Run an alert's shell command. The matches are passed as
+/// `AGENT_DEFS_QUERY`, `AGENT_DEFS_COUNT`, and newline-separated
+/// `AGENT_DEFS_IDS` / `AGENT_DEFS_NAMES`.
+pub fn run_command(command: &str, query: &str, matches: &[&DefinitionSummary]) -> Result<()> {
+    let ids: Vec<&str> = matches.iter().map(|s| s.id.as_str()).collect();
+    let names: Vec<&str> = matches.iter().map(|s| s.name.as_str()).collect();
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let status = shell
+        .arg(command)
+        .env("AGENT_DEFS_QUERY", query)
+        .env("AGENT_DEFS_COUNT", matches.len().to_string())
+        .env("AGENT_DEFS_IDS", ids.join("\n"))
+        .env("AGENT_DEFS_NAMES", names.join("\n"))
+        .status()?;
+    if !status.success() {
+        bail!("`{command}` exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::DefinitionKind;
+
+    use super::*;
+
+    fn summary(id: &str, kind: DefinitionKind, category: &str) -> DefinitionSummary {
+        DefinitionSummary {
+            name: id.to_uppercase(),
+            kind,
+            category: Some(category.to_owned()),
+            source_label: "acme".into(),
+            ..agent_defs::test_support::definition(id).summary()
+        }
+    }
+
+    fn alert(query: &str) -> Alert {
+        Alert {
+            query: query.into(),
+            notify: false,
+            command: None,
+        }
+    }
+
+    #[test]
+    fn matching_applies_the_saved_query() {
+        let added = [
+            summary("lint", DefinitionKind::Skill, "code-quality"),
+            summary("deploy", DefinitionKind::Skill, "ops"),
+            summary("review", DefinitionKind::Agent, "code-quality"),
+        ];
+        let matches = matching(&alert("kind:skill category:code-quality"), &added);
+        let ids: Vec<_> = matches.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["lint"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_exposes_matches_in_env() {
+        let out = std::env::temp_dir().join("agent-defs-test-alert-command.txt");
+        let _ = std::fs::remove_file(&out);
+        let lint = summary("lint", DefinitionKind::Skill, "code-quality");
+        let review = summary("review", DefinitionKind::Agent, "code-quality");
+
+        let command = format!(
+            "printf '%s|%s|%s' \"$AGENT_DEFS_COUNT\" \"$AGENT_DEFS_IDS\" \"$AGENT_DEFS_QUERY\" > {}",
+            out.display()
+        );
+        run_command(&command, "category:code-quality", &[&lint, &review]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "2|lint\nreview|category:code-quality"
+        );
+        let _ = std::fs::remove_file(&out);
+
+        assert!(run_command("exit 3", "q", &[&lint]).is_err());
+    }
+}
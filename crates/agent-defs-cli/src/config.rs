@@ -1,12 +1,144 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
 
-use serde::{Deserialize, Serialize};
+use agent_defs::{Collection, GlyphSet, HideRules, InstallLayout, ListDensity};
+use agent_defs_github::{HttpOptions, TarballLimits};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::validate;
 
 /// Top-level application configuration.
+///
+/// String values may refer to environment variables as `${NAME}`, or
+/// `${NAME:-fallback}` to use `fallback` when `NAME` is unset, so a shared
+/// config can leave out secrets and machine-specific paths. `$${` writes a
+/// literal `${`. Alert commands are left alone for the shell to expand.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
     #[serde(default)]
     pub sources: Vec<SourceEntry>,
+    /// Files larger than this many bytes are skipped during sync.
+    #[serde(default = "default_max_definition_bytes")]
+    pub max_definition_bytes: u64,
+    /// Glyphs shown next to kinds in the TUI (`unicode`, `nerd-font`, `ascii`, `none`).
+    #[serde(default)]
+    pub glyphs: GlyphSet,
+    /// How much each list row shows (`compact`: the name; `comfortable`: the
+    /// description under it). Unset, the TUI is compact and the GUI
+    /// comfortable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list_density: Option<ListDensity>,
+    /// Milliseconds between the TUI's ticks while a status message or
+    /// countdown is showing. Unset, 250.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_rate_ms: Option<u64>,
+    /// Named lists of definitions, browsable and installable as a unit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<Collection>,
+    /// What `sync --watch` does when it finds new definitions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<Alert>,
+    /// Whether `self-update` and the daily notice after `sync` may contact
+    /// GitHub releases.
+    #[serde(default = "default_true")]
+    pub update_checks: bool,
+    /// Where `install` puts definitions when `--target` isn't given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_target: Option<PathBuf>,
+    /// How installed definitions are arranged under `.claude`: `nested`
+    /// in category directories, or `flat`.
+    #[serde(default)]
+    pub install_layout: InstallLayout,
+    /// Whether `I` in the TUI, which installs to `install_target` without
+    /// asking for a directory, still asks before installing.
+    #[serde(default = "default_true")]
+    pub confirm_quick_install: bool,
+    /// Size caps on the repository archives downloaded during sync, so an
+    /// untrusted source can't fill memory.
+    #[serde(default)]
+    pub archive_limits: TarballLimits,
+    /// Bytes of downloaded repository archives to keep for
+    /// `sync --offline-replay`, dropping the oldest past that. 0 keeps none.
+    #[serde(default = "default_archive_cache_bytes")]
+    pub archive_cache_bytes: u64,
+    /// After this many failed syncs in a row, a source is degraded: other
+    /// commands stop syncing it on their own until `sync` succeeds. 0 never
+    /// degrades one.
+    #[serde(default = "default_degraded_after")]
+    pub degraded_after: u32,
+    /// Proxy and CA certificate settings for networks that need them.
+    #[serde(default)]
+    pub network: HttpOptions,
+    /// Categories to rename during sync, on top of the built-in synonyms,
+    /// such as `quality = "testing"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<String, String>,
+    /// Base URL of a community index of ratings. Each sync fetches
+    /// `<url>/<source label>.json` for the source it synced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub community_index: Option<String>,
+    /// URL of the preset registry `sources discover` fetches, in place of
+    /// the one in this project's repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset_registry: Option<String>,
+    /// Minisign keys that synced definitions and snapshots are checked
+    /// against, and whether unsigned ones are refused.
+    #[serde(default)]
+    pub signing: SigningConfig,
+}
+
+/// Signature checks on synced content.
+///
+/// ```toml
+/// [signing]
+/// require_signed = true
+/// public_keys = ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SigningConfig {
+    /// Drop definitions without a valid signature from a trusted key,
+    /// rather than only those whose signature is bad.
+    #[serde(default)]
+    pub require_signed: bool,
+    /// Trusted minisign public keys, as the base64 line of a `.pub` file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub public_keys: Vec<String>,
+}
+
+impl SigningConfig {
+    /// What stores record the policy as, so definitions cached under
+    /// other keys, or before signing was turned on, aren't installed
+    /// without a fresh sync. `None` when there's no policy.
+    pub fn fingerprint(&self) -> Option<String> {
+        if self.public_keys.is_empty() && !self.require_signed {
+            return None;
+        }
+        let mut keys: Vec<&str> = self.public_keys.iter().map(|key| key.trim()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        Some(format!("{};require_signed={}", keys.join(","), self.require_signed))
+    }
+}
+
+/// A saved query checked against newly added definitions during
+/// `sync --watch`, and what to do when something matches.
+///
+/// ```toml
+/// [[alerts]]
+/// query = "kind:skill category:code-quality"
+/// notify = true
+/// command = "echo \"$AGENT_DEFS_NAMES\" >> ~/new-skills.txt"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Alert {
+    pub query: String,
+    /// Show a desktop notification.
+    #[serde(default)]
+    pub notify: bool,
+    /// Shell command to run, with the matches described in `AGENT_DEFS_*`
+    /// environment variables.
+    pub command: Option<String>,
 }
 
 /// A single source definition.
@@ -15,6 +147,18 @@ pub struct SourceEntry {
     pub label: String,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Only sync files whose paths (relative to the source root) match one
+    /// of these globs, e.g. `agents/**`. Empty keeps everything.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Skip files whose paths match any of these globs, e.g.
+    /// `**/deprecated/**`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// GitHub token for this source, usually `"${SOME_VAR}"`. Falls back to
+    /// `GITHUB_TOKEN`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
     #[serde(flatten)]
     pub source_type: SourceType,
 }
@@ -38,7 +182,18 @@ pub enum SourceType {
         repo: String,
         #[serde(default = "default_branch")]
         branch: String,
-        base_path: Option<String>,
+        /// Several branches to sync side by side, in place of `branch`.
+        /// Their IDs are prefixed with the branch, as in `next:agents/a.md`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        branches: Vec<String>,
+        /// Directory holding the definitions, or a list of them for a repo
+        /// that keeps definitions in several places.
+        #[serde(
+            default,
+            deserialize_with = "one_or_many",
+            skip_serializing_if = "Vec::is_empty"
+        )]
+        base_path: Vec<String>,
     },
 
     /// User-defined GitHub Gist source.
@@ -46,28 +201,99 @@ pub enum SourceType {
     GitHubGist {
         gist_id: String,
         path_prefix: Option<String>,
+        /// Stands in for `/` in file names, since gists can't hold
+        /// directories: with `"__"`, `agents__review__reviewer.md` syncs as
+        /// `agents/review/reviewer.md`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dir_separator: Option<String>,
+    },
+
+    /// A source published in a team's catalog snapshot (see `snapshot
+    /// push`), fetched from an HTTP(S) URL or a local path. `token`, if
+    /// set, is sent as a bearer token; `GITHUB_TOKEN` never is.
+    #[serde(rename = "catalog-snapshot")]
+    CatalogSnapshot {
+        url: String,
+        /// The source to take from the snapshot, when it's published under
+        /// a different label than this one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
     },
+
+    /// A community repository this binary knows the layout of, by the
+    /// preset's name (see `crate::presets`).
+    #[serde(rename = "preset")]
+    Preset { name: String },
+
+    /// Built-in: a handful of sample definitions bundled with the binary,
+    /// for trying things out without a repository.
+    #[serde(rename = "demo")]
+    Demo,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// Read either a single string or a list of them.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
 fn default_branch() -> String {
     "main".into()
 }
 
+fn default_max_definition_bytes() -> u64 {
+    agent_defs_store::DEFAULT_MAX_FILE_BYTES
+}
+
+fn default_archive_cache_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+
+fn default_degraded_after() -> u32 {
+    3
+}
+
+/// The one source `--demo` browses in place of the configured ones.
+pub fn demo_source() -> SourceEntry {
+    SourceEntry {
+        label: agent_defs::demo::LABEL.into(),
+        enabled: true,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        token: None,
+        source_type: SourceType::Demo,
+    }
+}
+
 /// Built-in registry of default sources.
 pub fn default_sources() -> Vec<SourceEntry> {
     vec![
         SourceEntry {
             label: "claude-code-templates".into(),
             enabled: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            token: None,
             source_type: SourceType::ClaudeCodeTemplates,
         },
         SourceEntry {
             label: "awesome-subagents".into(),
             enabled: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            token: None,
             source_type: SourceType::AwesomeSubagents,
         },
     ]
@@ -78,23 +304,310 @@ pub fn config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join("agent-def-fetcher").join("sources.toml"))
 }
 
-/// Load config from file, falling back to defaults if missing.
+/// Parse the config file at `path`, without falling back to defaults.
+/// Fails if [`validate::check`] finds any errors; warnings are ignored.
+pub fn read_config(path: &Path) -> anyhow::Result<AppConfig> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read config at {}: {e}", path.display()))?;
+    without_errors(path, validate::check(&contents))
+}
+
+fn without_errors(
+    path: &Path,
+    (config, issues): (Option<AppConfig>, Vec<validate::Issue>),
+) -> anyhow::Result<AppConfig> {
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|issue| issue.is_error())
+        .map(ToString::to_string)
+        .collect();
+    match config {
+        Some(config) if errors.is_empty() => Ok(config),
+        _ => anyhow::bail!("invalid config at {}:\n{}", path.display(), errors.join("\n")),
+    }
+}
+
+/// File name of a project's config overlay, looked for in the working
+/// directory and each directory above it.
+pub const WORKSPACE_CONFIG_NAME: &str = ".agent-def-fetcher.toml";
+
+/// A project's overlay on the user's config, committed alongside the
+/// project so everyone working in it gets the same sources and install
+/// settings.
+///
+/// ```toml
+/// install_target = "."
+/// install_layout = "flat"
+///
+/// [[sources]]
+/// label = "team"
+/// type = "github-repo"
+/// owner = "acme"
+/// repo = "agents"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceConfig {
+    /// The overlay file.
+    pub path: PathBuf,
+    /// Added to the user's sources, replacing any with the same label.
+    pub sources: Vec<SourceEntry>,
+    /// Resolved against the overlay's directory.
+    pub install_target: Option<PathBuf>,
+    pub install_layout: Option<InstallLayout>,
+}
+
+/// The nearest workspace overlay in `dir` or a directory above it.
+pub fn find_workspace_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(WORKSPACE_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Parse the workspace overlay at `path`. Fails if
+/// [`validate::check_workspace`] finds any errors.
+pub fn read_workspace_config(path: &Path) -> anyhow::Result<WorkspaceConfig> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read config at {}: {e}", path.display()))?;
+    let config = without_errors(path, validate::check_workspace(&contents))?;
+    // Leaving the layout out shouldn't reset the user's choice to the default.
+    let sets_layout = toml::from_str::<toml::Table>(&contents)
+        .is_ok_and(|table| table.contains_key("install_layout"));
+    let dir = path.parent().unwrap_or(Path::new("."));
+    Ok(WorkspaceConfig {
+        path: path.to_owned(),
+        sources: config.sources,
+        install_target: config.install_target.map(|target| dir.join(target)),
+        install_layout: sets_layout.then_some(config.install_layout),
+    })
+}
+
+impl AppConfig {
+    /// Lay `workspace` over this config.
+    pub fn overlay(&mut self, workspace: &WorkspaceConfig) {
+        for source in &workspace.sources {
+            match self.sources.iter_mut().find(|s| s.label == source.label) {
+                Some(existing) => *existing = source.clone(),
+                None => self.sources.push(source.clone()),
+            }
+        }
+        if let Some(target) = &workspace.install_target {
+            self.install_target = Some(target.clone());
+        }
+        if let Some(layout) = workspace.install_layout {
+            self.install_layout = layout;
+        }
+    }
+}
+
+/// The workspace overlay for the working directory, if there is one and it
+/// has no errors. Errors are reported on stderr the first time.
+pub fn load_workspace_config() -> Option<WorkspaceConfig> {
+    static REPORTED: Once = Once::new();
+
+    let path = find_workspace_config(&std::env::current_dir().ok()?)?;
+    match read_workspace_config(&path) {
+        Ok(workspace) => Some(workspace),
+        Err(e) => {
+            REPORTED.call_once(|| eprintln!("warning: ignoring the workspace config: {e}"));
+            None
+        }
+    }
+}
+
+/// Load config from file, falling back to defaults if it's missing or has
+/// errors, with the working directory's workspace overlay on top. Problems
+/// are reported on stderr the first time this is called.
 pub fn load_config() -> AppConfig {
+    let mut config = load_user_config();
+    if let Some(workspace) = load_workspace_config() {
+        config.overlay(&workspace);
+    }
+    config
+}
+
+fn load_user_config() -> AppConfig {
+    static REPORTED: Once = Once::new();
+
     if let Some(path) = config_path()
         && let Ok(contents) = std::fs::read_to_string(&path)
     {
-        if let Ok(config) = toml::from_str::<AppConfig>(&contents) {
+        let (config, issues) = validate::check(&contents);
+        let usable = !issues.iter().any(validate::Issue::is_error);
+        REPORTED.call_once(|| {
+            for issue in &issues {
+                eprintln!("{}: {issue}", path.display());
+            }
+            if !usable {
+                eprintln!(
+                    "warning: using the default config instead; \
+                     `config check` shows the effective configuration"
+                );
+            }
+        });
+        if let Some(config) = config
+            && usable
+        {
             return config;
         }
-        eprintln!(
-            "warning: failed to parse config at {}, using defaults",
-            path.display()
-        );
     }
 
+    default_config()
+}
+
+/// The configuration used when there is no config file.
+pub fn default_config() -> AppConfig {
     AppConfig {
         sources: default_sources(),
+        max_definition_bytes: default_max_definition_bytes(),
+        glyphs: GlyphSet::default(),
+        list_density: None,
+        tick_rate_ms: None,
+        collections: Vec::new(),
+        alerts: Vec::new(),
+        update_checks: true,
+        install_target: None,
+        install_layout: InstallLayout::default(),
+        confirm_quick_install: true,
+        archive_limits: TarballLimits::default(),
+        archive_cache_bytes: default_archive_cache_bytes(),
+        degraded_after: default_degraded_after(),
+        network: HttpOptions::default(),
+        categories: BTreeMap::new(),
+        community_index: None,
+        preset_registry: None,
+        signing: SigningConfig::default(),
+    }
+}
+
+/// Replace `${NAME}` and `${NAME:-fallback}` in `value` with what `lookup`
+/// returns for `NAME`. Fails with a message naming the first variable that
+/// is unset and has no fallback.
+pub fn expand_env(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            expanded.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let Some(end) = reference.find('}') else {
+                return Err(format!("`${{` without a closing `}}` in `{value}`"));
+            };
+            let (name, fallback) = match reference[..end].split_once(":-") {
+                Some((name, fallback)) => (name, Some(fallback)),
+                None => (&reference[..end], None),
+            };
+            // As in the shell, a fallback also covers a variable set to "".
+            let found = lookup(name).filter(|v| fallback.is_none() || !v.is_empty());
+            match found.or(fallback.map(str::to_owned)) {
+                Some(v) => expanded.push_str(&v),
+                None => return Err(format!("environment variable `{name}` is not set")),
+            }
+            rest = &reference[end + 1..];
+        } else {
+            expanded.push('$');
+            rest = after;
+        }
     }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Directory of imported collections, one JSON file each:
+/// `~/.config/agent-def-fetcher/collections/`
+pub fn collections_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("agent-def-fetcher").join("collections"))
+}
+
+/// Collections from the config file followed by imported ones. When two
+/// share a name, the first wins.
+pub fn load_collections(config: &AppConfig) -> Vec<Collection> {
+    let mut collections = config.collections.clone();
+    if let Some(dir) = collections_dir() {
+        for collection in read_collections_dir(&dir) {
+            if !collections.iter().any(|c| c.name == collection.name) {
+                collections.push(collection);
+            }
+        }
+    }
+    collections
+}
+
+/// Read every `*.json` collection in `dir`, in file name order. Files that
+/// fail to parse are skipped with a warning; a missing directory is empty.
+pub fn read_collections_dir(dir: &Path) -> Vec<Collection> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(collection) => Some(collection),
+                Err(e) => {
+                    eprintln!("warning: skipping collection {}: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Hide rules, one per line: `~/.config/agent-def-fetcher/hidden`
+pub fn hidden_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("agent-def-fetcher").join("hidden"))
+}
+
+/// The user's hide rules; a missing file hides nothing.
+pub fn load_hidden() -> HideRules {
+    hidden_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| HideRules::parse(&text))
+        .unwrap_or_default()
+}
+
+/// Write hide rules to `path`, creating its directory if needed.
+pub fn write_hidden(path: &Path, rules: &HideRules) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let text = format!(
+        "# Definitions hidden from list, search, and the TUI, written by\n\
+         # `agent-def-fetcher hide`. One rule per line: id:, category:, or glob:.\n{rules}"
+    );
+    std::fs::write(path, text)
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))
+}
+
+/// Glyph set to use: `AGENT_DEFS_GLYPHS` if set to a known value, else the config.
+pub fn glyph_set(config: &AppConfig) -> GlyphSet {
+    std::env::var("AGENT_DEFS_GLYPHS")
+        .ok()
+        .and_then(|v| GlyphSet::parse(&v))
+        .unwrap_or(config.glyphs)
+}
+
+/// List density to use: `AGENT_DEFS_LIST_DENSITY` if set to a known value,
+/// else the config's, if it sets one.
+pub fn list_density(config: &AppConfig) -> Option<ListDensity> {
+    std::env::var("AGENT_DEFS_LIST_DENSITY")
+        .ok()
+        .and_then(|v| ListDensity::parse(&v))
+        .or(config.list_density)
 }
 
 #[cfg(test)]
@@ -171,11 +684,12 @@ base_path = "src/defs"
                 repo,
                 branch,
                 base_path,
+                ..
             } => {
                 assert_eq!(owner, "user");
                 assert_eq!(repo, "repo");
                 assert_eq!(branch, "develop");
-                assert_eq!(base_path.as_deref(), Some("src/defs"));
+                assert_eq!(base_path, &["src/defs"]);
             }
             _ => panic!("expected GitHubRepo"),
         }
@@ -196,6 +710,7 @@ path_prefix = "skills/rust"
             SourceType::GitHubGist {
                 gist_id,
                 path_prefix,
+                ..
             } => {
                 assert_eq!(gist_id, "abc123");
                 assert_eq!(path_prefix.as_deref(), Some("skills/rust"));
@@ -204,6 +719,32 @@ path_prefix = "skills/rust"
         }
     }
 
+    #[test]
+    fn parse_catalog_snapshot_from_toml() {
+        let toml_str = r#"
+[[sources]]
+label = "acme"
+type = "catalog-snapshot"
+url = "https://cdn.example.com/catalog.json"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        match &config.sources[0].source_type {
+            SourceType::CatalogSnapshot { url, source } => {
+                assert_eq!(url, "https://cdn.example.com/catalog.json");
+                assert_eq!(source, &None);
+            }
+            _ => panic!("expected CatalogSnapshot"),
+        }
+    }
+
+    #[test]
+    fn parse_demo_from_toml() {
+        let toml_str = "[[sources]]\nlabel = \"samples\"\ntype = \"demo\"\n";
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert!(matches!(config.sources[0].source_type, SourceType::Demo));
+        assert_eq!(config.sources[0].label, "samples");
+    }
+
     #[test]
     fn missing_config_uses_defaults() {
         // load_config falls back to defaults when no file exists.
@@ -211,6 +752,24 @@ path_prefix = "skills/rust"
         // that the default construction path works.
         let config = AppConfig {
             sources: default_sources(),
+            max_definition_bytes: default_max_definition_bytes(),
+            glyphs: GlyphSet::default(),
+            list_density: None,
+            tick_rate_ms: None,
+            collections: Vec::new(),
+            alerts: Vec::new(),
+            update_checks: true,
+            install_target: None,
+            install_layout: InstallLayout::default(),
+            confirm_quick_install: true,
+            archive_limits: TarballLimits::default(),
+            archive_cache_bytes: default_archive_cache_bytes(),
+            degraded_after: default_degraded_after(),
+            network: HttpOptions::default(),
+            categories: BTreeMap::new(),
+            community_index: None,
+            preset_registry: None,
+            signing: SigningConfig::default(),
         };
         assert_eq!(config.sources.len(), 2);
     }
@@ -244,4 +803,335 @@ repo = "repo"
             _ => panic!("expected GitHubRepo"),
         }
     }
+
+    #[test]
+    fn max_definition_bytes_defaults_to_store_limit() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(
+            config.max_definition_bytes,
+            agent_defs_store::DEFAULT_MAX_FILE_BYTES
+        );
+    }
+
+    #[test]
+    fn parse_max_definition_bytes_from_toml() {
+        let toml_str = r#"
+max_definition_bytes = 4096
+
+[[sources]]
+label = "cct"
+type = "claude-code-templates"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.max_definition_bytes, 4096);
+        assert_eq!(config.sources.len(), 1);
+    }
+
+    #[test]
+    fn glyphs_default_to_unicode() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(config.glyphs, GlyphSet::Unicode);
+    }
+
+    #[test]
+    fn parse_tick_rate_from_toml() {
+        let config: AppConfig = toml::from_str("tick_rate_ms = 100").unwrap();
+        assert_eq!(config.tick_rate_ms, Some(100));
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(config.tick_rate_ms, None);
+    }
+
+    #[test]
+    fn parse_list_density_from_toml() {
+        let config: AppConfig = toml::from_str(r#"list_density = "comfortable""#).unwrap();
+        assert_eq!(config.list_density, Some(ListDensity::Comfortable));
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(config.list_density, None);
+    }
+
+    #[test]
+    fn parse_glyphs_from_toml() {
+        let config: AppConfig = toml::from_str(r#"glyphs = "nerd-font""#).unwrap();
+        assert_eq!(config.glyphs, GlyphSet::NerdFont);
+
+        let config: AppConfig = toml::from_str(r#"glyphs = "ascii""#).unwrap();
+        assert_eq!(config.glyphs, GlyphSet::Ascii);
+    }
+
+    #[test]
+    fn parse_collections_from_toml() {
+        let toml_str = r#"
+[[collections]]
+name = "backend-starter"
+description = "APIs and databases"
+items = ["agents/api.md", { id = "agents/db.md", source = "acme" }]
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.collections.len(), 1);
+        let collection = &config.collections[0];
+        assert_eq!(collection.name, "backend-starter");
+        assert_eq!(collection.items.len(), 2);
+        assert_eq!(collection.items[1].source.as_deref(), Some("acme"));
+    }
+
+    #[test]
+    fn parse_alerts_from_toml() {
+        let toml_str = r#"
+[[alerts]]
+query = "kind:skill category:code-quality"
+notify = true
+
+[[alerts]]
+query = "review"
+command = "echo hi"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.alerts,
+            [
+                Alert {
+                    query: "kind:skill category:code-quality".into(),
+                    notify: true,
+                    command: None,
+                },
+                Alert {
+                    query: "review".into(),
+                    notify: false,
+                    command: Some("echo hi".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn update_checks_default_on() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert!(config.update_checks);
+        let config: AppConfig = toml::from_str("update_checks = false").unwrap();
+        assert!(!config.update_checks);
+    }
+
+    #[test]
+    fn collections_default_to_empty() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert!(config.collections.is_empty());
+    }
+
+    #[test]
+    fn read_collections_dir_skips_bad_files() {
+        let dir = std::env::temp_dir().join("agent-defs-test-collections");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.json"), r#"{"name":"second","items":[]}"#).unwrap();
+        std::fs::write(dir.join("a.json"), r#"{"name":"first","items":["x.md"]}"#).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let names: Vec<_> = read_collections_dir(&dir).into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["first", "second"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_collections_dir_is_empty() {
+        assert!(read_collections_dir(Path::new("/nonexistent/agent-defs-collections")).is_empty());
+    }
+
+    #[test]
+    fn written_hide_rules_read_back() {
+        let dir = std::env::temp_dir().join("agent-defs-test-hidden");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("hidden");
+        let rules = HideRules::parse("category:blockchain\nglob:agents/crypto-*");
+
+        write_hidden(&path, &rules).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with('#'));
+        assert_eq!(HideRules::parse(&text), rules);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_include_and_exclude_globs() {
+        let toml_str = r#"
+[[sources]]
+label = "big"
+type = "github-repo"
+owner = "acme"
+repo = "everything"
+include = ["agents/**"]
+exclude = ["**/deprecated/**"]
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.sources[0].include, ["agents/**"]);
+        assert_eq!(config.sources[0].exclude, ["**/deprecated/**"]);
+
+        let toml_str = r#"
+[[sources]]
+label = "x"
+type = "awesome-subagents"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.sources[0].include.is_empty());
+    }
+
+    #[test]
+    fn expand_env_substitutes_variables() {
+        let lookup = |name: &str| match name {
+            "TEAM" => Some("acme".to_owned()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(expand_env("${TEAM}/agents", lookup).unwrap(), "acme/agents");
+        assert_eq!(expand_env("${MISSING:-~/agents}", lookup).unwrap(), "~/agents");
+        assert_eq!(expand_env("${EMPTY:-fallback}", lookup).unwrap(), "fallback");
+        assert_eq!(expand_env("[${EMPTY}]", lookup).unwrap(), "[]");
+        assert_eq!(expand_env("$HOME and $${TEAM}", lookup).unwrap(), "$HOME and ${TEAM}");
+        assert_eq!(
+            expand_env("${MISSING}", lookup).unwrap_err(),
+            "environment variable `MISSING` is not set"
+        );
+        assert!(expand_env("${TEAM", lookup).is_err());
+    }
+
+    #[test]
+    fn parse_several_base_paths() {
+        let toml_str = r#"
+[[sources]]
+label = "monorepo"
+type = "github-repo"
+owner = "acme"
+repo = "platform"
+base_path = ["claude", "tools/agents"]
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        match &config.sources[0].source_type {
+            SourceType::GitHubRepo { base_path, .. } => {
+                assert_eq!(base_path, &["claude", "tools/agents"]);
+            }
+            _ => panic!("expected GitHubRepo"),
+        }
+    }
+
+    #[test]
+    fn parse_several_branches() {
+        let toml_str = r#"
+[[sources]]
+label = "compare"
+type = "github-repo"
+owner = "acme"
+repo = "agents"
+branches = ["main", "next"]
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        match &config.sources[0].source_type {
+            SourceType::GitHubRepo { branches, .. } => assert_eq!(branches, &["main", "next"]),
+            _ => panic!("expected GitHubRepo"),
+        }
+    }
+
+    #[test]
+    fn parse_token_and_install_target() {
+        let toml_str = r#"
+install_target = "/work/.claude"
+
+[[sources]]
+label = "private"
+type = "github-repo"
+owner = "acme"
+repo = "agents"
+token = "ghp_example"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.install_target, Some(PathBuf::from("/work/.claude")));
+        assert_eq!(config.sources[0].token.as_deref(), Some("ghp_example"));
+    }
+
+    #[test]
+    fn workspace_config_is_found_above_and_overlays() {
+        let project = tempfile::tempdir().unwrap();
+        let nested = project.path().join("src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        let path = project.path().join(WORKSPACE_CONFIG_NAME);
+        std::fs::write(
+            &path,
+            r#"
+install_target = "tools"
+
+[[sources]]
+label = "awesome-subagents"
+type = "github-repo"
+owner = "acme"
+repo = "agents"
+
+[[sources]]
+label = "team"
+type = "github-gist"
+gist_id = "abc123"
+"#,
+        )
+        .unwrap();
+        assert_eq!(find_workspace_config(&nested), Some(path.clone()));
+
+        let workspace = read_workspace_config(&path).unwrap();
+        let mut config = default_config();
+        config.install_layout = InstallLayout::Flat;
+        config.overlay(&workspace);
+
+        let labels: Vec<&str> = config.sources.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, ["claude-code-templates", "awesome-subagents", "team"]);
+        assert!(matches!(
+            &config.sources[1].source_type,
+            SourceType::GitHubRepo { owner, .. } if owner == "acme"
+        ));
+        assert_eq!(config.install_target, Some(project.path().join("tools")));
+        // Left unset in the overlay, so the user's layout stands.
+        assert_eq!(config.install_layout, InstallLayout::Flat);
+    }
+
+    #[test]
+    fn parse_network_settings() {
+        let config: AppConfig = toml::from_str(
+            "[network]\nproxy = \"http://proxy.corp:3128\"\nca_bundle = \"/etc/corp-ca.pem\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.network.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(config.network.ca_bundle, Some(PathBuf::from("/etc/corp-ca.pem")));
+        assert!(!config.network.insecure);
+    }
+
+    #[test]
+    fn archive_limits_fill_in_defaults() {
+        let config: AppConfig =
+            toml::from_str("[archive_limits]\nmax_download_bytes = 1048576\n").unwrap();
+        assert_eq!(config.archive_limits.max_download_bytes, 1_048_576);
+        assert_eq!(
+            config.archive_limits.max_file_bytes,
+            TarballLimits::default().max_file_bytes
+        );
+        assert_eq!(default_config().archive_limits, TarballLimits::default());
+    }
+
+    #[test]
+    fn signing_fingerprint_changes_with_the_policy_only() {
+        let signing = |require_signed, keys: &[&str]| SigningConfig {
+            require_signed,
+            public_keys: keys.iter().map(|&key| key.to_owned()).collect(),
+        };
+        assert_eq!(signing(false, &[]).fingerprint(), None);
+        assert_eq!(
+            signing(false, &["b", " a"]).fingerprint(),
+            signing(false, &["a", "b"]).fingerprint()
+        );
+        assert_ne!(
+            signing(false, &["a"]).fingerprint(),
+            signing(true, &["a"]).fingerprint()
+        );
+        assert_ne!(
+            signing(false, &["a"]).fingerprint(),
+            signing(false, &["a", "b"]).fingerprint()
+        );
+    }
 }
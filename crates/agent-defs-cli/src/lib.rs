@@ -0,0 +1,9 @@
+//! Library half of the CLI: configuration, source providers, and the
+//! helpers that connect them to the local cache.
+
+pub mod catalog;
+pub mod config;
+pub mod presets;
+pub mod signing;
+pub mod sources;
+pub mod validate;
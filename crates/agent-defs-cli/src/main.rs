@@ -1,29 +1,60 @@
 mod commands;
-mod config;
-mod sources;
 
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use agent_defs::{CompositeSource, Source, SyncProvider};
-use agent_defs_store::{DefinitionStore, SyncStatus};
-use agent_defs_tui::{SyncFn, SyncResult};
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-
-use crate::config::{SourceEntry, SourceType};
-use crate::sources::{
-    AwesomeSubagentsProvider, ClaudeCodeTemplatesProvider, GenericGistProvider,
-    GenericRepoProvider,
+use agent_defs::{
+    DefinitionId, HideRule, HideRules, InstallRules, Lockfile, Policy, SkippedFile, UsageKind,
 };
-
-/// A paired store and provider for a single configured source.
-type SourcePair = (Arc<DefinitionStore>, Box<dyn SyncProvider>);
+use agent_defs_cli::catalog::{
+    SourcePair, build_from_config, composite_source, db_path, github_token, record_usage,
+    revisions, stores_as_sources,
+};
+use agent_defs_cli::{catalog, config, presets};
+use agent_defs_store::{DefinitionStore, SyncStatus};
+use agent_defs_tui::{SyncSelection, TuiOptions};
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use commands::rating::CommunityIndex;
+use commands::show::View;
 
 #[derive(Parser)]
 #[command(name = "agent-def-fetcher")]
 #[command(about = "Fetch and browse agent definitions from curated sources")]
 struct Cli {
+    /// Plain output for screen readers and dumb terminals: no column
+    /// alignment or truncation, and `tui` starts a line-based pager instead
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// When to color output: auto (terminals only, unless NO_COLOR is set),
+    /// always, or never
+    #[arg(
+        long,
+        global = true,
+        default_value = "auto",
+        value_parser = commands::color::parse_color
+    )]
+    color: commands::color::ColorChoice,
+
+    /// Skip TLS certificate verification. A last resort for networks whose
+    /// proxy re-signs traffic; prefer `ca_bundle` under [network] in sources.toml
+    #[arg(long, global = true)]
+    insecure: bool,
+
+    /// Go easy on a metered connection: sync repositories file by file,
+    /// downloading only new definitions, and skip sources that can only
+    /// be synced as a whole archive
+    #[arg(long, global = true)]
+    metered: bool,
+
+    /// Browse a built-in set of sample definitions instead of the configured
+    /// sources, cached apart from them. No repository or token needed
+    #[arg(long, global = true)]
+    demo: bool,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -31,7 +62,66 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Sync definitions from remote sources into the local cache
-    Sync,
+    Sync {
+        /// Keep running, syncing again every --interval minutes and firing
+        /// the config's [[alerts]] for new definitions that match
+        #[arg(long)]
+        watch: bool,
+        /// Minutes between syncs in --watch mode
+        #[arg(long, default_value_t = 60, requires = "watch", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Only report failures (for scheduled runs)
+        #[arg(long, conflicts_with = "watch")]
+        quiet: bool,
+        /// Only sync the source with this label (repeatable)
+        #[arg(long = "source", value_name = "LABEL")]
+        sources: Vec<String>,
+        /// Skip sources synced within the last week
+        #[arg(long, conflicts_with = "watch")]
+        only_stale: bool,
+        /// Rebuild the cache from the repository archives kept by earlier
+        /// syncs, without touching the network
+        #[arg(long, conflicts_with_all = ["watch", "only_stale"])]
+        offline_replay: bool,
+    },
+    /// Keep the cache fresh with a systemd timer or launchd job
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Show definitions that syncs added, removed, or changed
+    Changes {
+        /// Only changes found on or after this date (YYYY-MM-DD) or span
+        /// back from now (e.g. 3d, 2w); defaults to the last week
+        #[arg(long, value_parser = commands::changes::parse_since)]
+        since: Option<u64>,
+    },
+    /// Parse cached definitions again after upgrading, and list what the
+    /// new parser reads differently
+    Reparse {
+        /// Only list the differences; keep the cached definitions as they are
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show how many definitions each source has
+    Stats {
+        /// Show which definitions you view and install most, and which
+        /// sources you never use (counted locally, never sent anywhere)
+        #[arg(long)]
+        personal: bool,
+        /// Delete the recorded views and installs
+        #[arg(long, requires = "personal")]
+        wipe: bool,
+    },
+    /// Count definitions by kind, category, or source
+    Count {
+        /// What to group by: kind, category, or source
+        #[arg(long, default_value = "kind", value_parser = commands::count::parse_by)]
+        by: agent_defs_store::CountBy,
+        /// Only count this source's definitions
+        #[arg(long)]
+        source: Option<String>,
+    },
     /// List available definitions
     List {
         /// Filter by kind (agent, command, hook, mcp, setting, skill)
@@ -40,6 +130,16 @@ enum Command {
         /// Filter by source label
         #[arg(long)]
         source: Option<String>,
+        /// Include definitions hidden with `hide`
+        #[arg(long)]
+        all: bool,
+        /// Sort by name, kind, or source, or newest first by when
+        /// definitions were added or last updated
+        #[arg(long, default_value = "kind", value_parser = commands::list::parse_sort)]
+        sort: commands::list::SortKey,
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
     },
     /// Search definitions by name or description
     Search {
@@ -51,25 +151,125 @@ enum Command {
         /// Filter by source label
         #[arg(long)]
         source: Option<String>,
+        /// Include definitions hidden with `hide`
+        #[arg(long)]
+        all: bool,
+    },
+    /// Search cached definition bodies with a regular expression, printing
+    /// each matching line as `id:line: text`
+    Grep {
+        /// Regular expression to match against each line
+        pattern: String,
+        /// Filter by source label
+        #[arg(long)]
+        source: Option<String>,
+        /// Match regardless of case
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
     },
     /// Show full definition details
     Show {
-        /// Definition ID (file path within the source)
+        /// Definition ID: its path within the source, or `source:path` to pick a source
         id: String,
         /// Filter by source label
         #[arg(long)]
         source: Option<String>,
         /// Show raw content instead of formatted output
-        #[arg(long)]
+        #[arg(long, conflicts_with = "export")]
         raw: bool,
+        /// Write the definition to a standalone file, `html` or `pdf`, for
+        /// sharing (PDF needs a build with the `pdf-export` feature)
+        #[arg(long, value_parser = commands::export::parse_format)]
+        export: Option<commands::export::ExportFormat>,
+        /// File to export to (defaults to the ID's file name in the current
+        /// directory)
+        #[arg(long, requires = "export")]
+        output: Option<PathBuf>,
+    },
+    /// Write, show, or remove your own note on a definition (kept locally
+    /// and searchable); with no ID, list every note
+    Note {
+        /// Definition ID: its path within the source, or `source:path` to pick a source
+        id: Option<String>,
+        /// Note text, replacing any existing note; omit to print the note
+        text: Option<String>,
+        /// Filter by source label
+        #[arg(long)]
+        source: Option<String>,
+        /// Remove the note
+        #[arg(long, requires = "id", conflicts_with = "text")]
+        clear: bool,
     },
-    /// Install a definition to a target directory
+    /// Hide definitions from list, search, and the TUI; with no arguments,
+    /// list what is hidden
+    Hide {
+        #[command(flatten)]
+        target: HideTarget,
+    },
+    /// Stop hiding definitions hidden with `hide`
+    Unhide {
+        #[command(flatten)]
+        target: HideTarget,
+    },
+    /// Install a definition to a target directory, pinning it in the
+    /// target's agent-defs.lock
     Install {
-        /// Definition ID (file path within the source)
+        /// Definition ID: its path within the source, or `source:path` to pick a source
+        #[arg(required_unless_present = "locked")]
+        id: Option<String>,
+        /// Target directory (defaults to `install_target` in the config, else
+        /// the current directory)
+        #[arg(long)]
+        target: Option<PathBuf>,
+        /// Filter by source label
+        #[arg(long)]
+        source: Option<String>,
+        /// Value for a template variable the definition declares (repeatable);
+        /// any left out are prompted for when stdin is a terminal
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = commands::install::parse_var)]
+        vars: Vec<(String, String)>,
+        /// Install a hook without asking first; hooks run shell commands
+        #[arg(long)]
+        yes: bool,
+        /// Install exactly what agent-defs.lock pins (all of it, without an
+        /// ID), failing if anything changed since it was locked
+        #[arg(long)]
+        locked: bool,
+    },
+    /// Reinstall definitions pinned in agent-defs.lock whose sources have
+    /// changed, and pin the new versions
+    Update {
+        /// Definition IDs to update (defaults to everything pinned)
+        ids: Vec<String>,
+        /// Target directory holding the lockfile (defaults to `install_target`
+        /// in the config, else the current directory)
+        #[arg(long)]
+        target: Option<PathBuf>,
+        /// Rewrite the changed files without asking first
+        #[arg(long)]
+        yes: bool,
+        /// Compare against the local cache without syncing the pinned
+        /// sources first
+        #[arg(long)]
+        no_sync: bool,
+    },
+    /// Keep local edits to pinned definitions, so `update` skips them; with
+    /// no IDs, list the definitions kept
+    KeepLocal {
+        /// Definition IDs pinned in agent-defs.lock
+        ids: Vec<String>,
+        /// Target directory holding the lockfile (defaults to `install_target`
+        /// in the config, else the current directory)
+        #[arg(long)]
+        target: Option<PathBuf>,
+        /// Let `update` update them again
+        #[arg(long, requires = "ids")]
+        undo: bool,
+    },
+    /// Upload a definition to a secret GitHub gist and print its URL
+    Share {
+        /// Definition ID: its path within the source, or `source:path` to pick a source
         id: String,
-        /// Target directory (defaults to current directory)
-        #[arg(long, default_value = ".")]
-        target: PathBuf,
         /// Filter by source label
         #[arg(long)]
         source: Option<String>,
@@ -79,62 +279,204 @@ enum Command {
         /// Target directory for installing definitions
         #[arg(long)]
         target: Option<PathBuf>,
+        /// Play the keys in a script instead of reading them, then quit
+        /// (for recordings and smoke tests)
+        #[arg(long, value_name = "FILE")]
+        demo_script: Option<PathBuf>,
+    },
+    /// Update this binary to the latest GitHub release
+    SelfUpdate {
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
+    /// Manage the local definition cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Browse, install, and share named collections of definitions
+    Collection {
+        #[command(subcommand)]
+        command: CollectionCommand,
+    },
+    /// Find and add community sources from the preset registry
+    Sources {
+        #[command(subcommand)]
+        command: SourcesCommand,
+    },
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Publish the cached catalog as a static website
+    Site {
+        #[command(subcommand)]
+        command: SiteCommand,
+    },
+    /// Share the cached catalog with a team as one snapshot file
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Write the synthetic catalog the benchmarks use into DIR, as a tree
+    /// of definition files and a GitHub-style tarball
+    #[command(hide = true)]
+    BenchFixtures {
+        dir: PathBuf,
+        /// How many definitions to write
+        #[arg(long, default_value_t = agent_defs::fixtures::BENCH_DEFINITIONS)]
+        count: usize,
     },
 }
 
-fn cache_dir() -> Result<PathBuf> {
-    let base = dirs::cache_dir().context("could not determine cache directory")?;
-    let dir = base.join("agent-def-fetcher");
-    std::fs::create_dir_all(&dir)
-        .with_context(|| format!("failed to create cache directory: {}", dir.display()))?;
-    Ok(dir)
+/// What `hide` and `unhide` apply to: one definition, a category, or a
+/// glob over IDs.
+#[derive(Args)]
+#[group(multiple = false)]
+struct HideTarget {
+    /// Definition ID: its path within the source, or `source:path` to pick a source
+    id: Option<String>,
+    /// Every definition in this category
+    #[arg(long)]
+    category: Option<String>,
+    /// Definitions whose ID matches this pattern (`*` and `?` wildcards)
+    #[arg(long)]
+    glob: Option<String>,
 }
 
-fn db_path() -> Result<PathBuf> {
-    Ok(cache_dir()?.join("definitions.db"))
+impl HideTarget {
+    fn rule(self) -> Option<HideRule> {
+        if let Some(id) = self.id {
+            Some(HideRule::Id(DefinitionId::new(id)))
+        } else if let Some(category) = self.category {
+            Some(HideRule::Category(category))
+        } else {
+            self.glob.map(HideRule::Glob)
+        }
+    }
 }
 
-fn build_store(label: &str) -> Result<DefinitionStore> {
-    let path = db_path()?;
-    DefinitionStore::open(&path, label).map_err(|e| anyhow::anyhow!("{e}"))
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Apply pending schema migrations to the cache database
+    Migrate {
+        /// Copy the database file aside before migrating
+        #[arg(long)]
+        backup: bool,
+    },
 }
 
-fn github_token() -> Option<String> {
-    std::env::var("GITHUB_TOKEN").ok()
+#[derive(Subcommand)]
+enum SiteCommand {
+    /// Render every cached definition into a searchable static HTML site
+    Build {
+        /// Directory to write the site into
+        #[arg(long, default_value = "site")]
+        out: PathBuf,
+    },
 }
 
-fn build_provider_for(entry: &SourceEntry) -> Box<dyn SyncProvider> {
-    let token = github_token();
-    match &entry.source_type {
-        SourceType::ClaudeCodeTemplates => {
-            Box::new(ClaudeCodeTemplatesProvider::new(&entry.label, token))
-        }
-        SourceType::AwesomeSubagents => {
-            Box::new(AwesomeSubagentsProvider::new(&entry.label, token))
-        }
-        SourceType::GitHubRepo {
-            owner,
-            repo,
-            branch,
-            base_path,
-        } => Box::new(GenericRepoProvider::new(
-            owner,
-            repo,
-            branch,
-            base_path.as_deref(),
-            token,
-            &entry.label,
-        )),
-        SourceType::GitHubGist {
-            gist_id,
-            path_prefix,
-        } => Box::new(GenericGistProvider::new(
-            gist_id,
-            path_prefix.as_deref(),
-            token,
-            &entry.label,
-        )),
-    }
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Upload every source's cached files as a JSON snapshot that
+    /// `catalog-snapshot` sources can sync from
+    Push {
+        /// HTTP(S) URL to PUT the snapshot to, such as a presigned S3 or
+        /// GCS upload URL, or a file path to write it to
+        to: String,
+        /// Bearer token for the upload; defaults to AGENT_DEFS_SNAPSHOT_TOKEN
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollectionCommand {
+    /// List collections from the config and imported files
+    List,
+    /// Show a collection's definitions and the source each resolves to
+    Show {
+        /// Collection name
+        name: String,
+    },
+    /// Install every definition in a collection
+    Install {
+        /// Collection name
+        name: String,
+        /// Target directory (defaults to `install_target` in the config, else
+        /// the current directory)
+        #[arg(long)]
+        target: Option<PathBuf>,
+        /// Install the collection's hooks too; they run shell commands, so
+        /// they're skipped otherwise
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Report installs that were interrupted before they finished, and
+    /// finish or undo them
+    Recover {
+        /// Install what each interrupted install didn't get to
+        #[arg(long)]
+        resume: bool,
+        /// Put back every file each interrupted install wrote
+        #[arg(long, conflicts_with = "resume")]
+        rollback: bool,
+    },
+    /// Write a collection as JSON for sharing
+    Export {
+        /// Collection name
+        name: String,
+        /// File to write instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Import a shared collection file
+    Import {
+        /// JSON file written by `collection export`
+        file: PathBuf,
+        /// Replace an imported collection with the same name
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Report mistakes in sources.toml and print the effective configuration
+    Check,
+}
+
+#[derive(Subcommand)]
+enum SourcesCommand {
+    /// Fetch the preset registry and list the community sources in it
+    Discover,
+    /// Show each source's failed syncs in a row and its latest error
+    Health,
+    /// Add a source to the config file
+    Add {
+        /// Name of the preset to add, as `sources discover` lists it
+        #[arg(long)]
+        preset: String,
+        /// Label for the source (defaults to the preset's name)
+        #[arg(long)]
+        label: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Write and load a user timer that runs `sync --quiet`
+    Install {
+        /// How often to sync: 30m, 6h, 1d, 1w, ...
+        #[arg(long, default_value = "1d", value_parser = commands::schedule::parse_every)]
+        every: u64,
+    },
+    /// Unload the timer and delete its files
+    Remove,
+    /// Show whether a timer is installed and when it next runs
+    Status,
 }
 
 /// Ensure every store has data. Auto-syncs if never synced, warns if stale.
@@ -143,6 +485,9 @@ fn build_provider_for(entry: &SourceEntry) -> Box<dyn SyncProvider> {
 /// initial sync are dropped with a warning. Returns an error only when
 /// *every* source is unusable.
 async fn ensure_synced(pairs: Vec<SourcePair>) -> Result<Vec<SourcePair>> {
+    warn_interrupted_installs(&pairs);
+    let config = config::load_config();
+    let index = CommunityIndex::from_config(&config)?;
     let mut usable = Vec::with_capacity(pairs.len());
 
     for (store, provider) in pairs {
@@ -157,19 +502,43 @@ async fn ensure_synced(pairs: Vec<SourcePair>) -> Result<Vec<SourcePair>> {
             }
         };
 
+        // A source that keeps failing isn't retried on every command; only
+        // `sync` does that.
+        let health = store.health().unwrap_or_default();
+        if health.is_degraded(config.degraded_after) {
+            let cached = status != SyncStatus::NeverSynced;
+            eprintln!(
+                "warning: [{}] is degraded after {} failed syncs in a row; {}. \
+                 `sources health` shows the last error, and `sync` tries it again.",
+                provider.label(),
+                health.failure_streak,
+                if cached {
+                    "using its cached definitions"
+                } else {
+                    "skipping it"
+                }
+            );
+            if cached {
+                usable.push((store, provider));
+            }
+            continue;
+        }
+
         match status {
             SyncStatus::NeverSynced => {
                 eprintln!(
                     "No local cache for [{}]. Running initial sync...",
                     provider.label()
                 );
-                match commands::sync::run(&store, provider.as_ref()).await {
-                    Ok(()) => usable.push((store, provider)),
+                let synced = commands::sync::run(&store, provider.as_ref(), index.as_ref(), false);
+                match synced.await {
+                    Ok(_) => usable.push((store, provider)),
                     Err(e) => {
                         eprintln!(
                             "warning: initial sync failed for [{}]: {e}",
                             provider.label()
                         );
+                        commands::sync::print_guidance(&e);
                     }
                 }
             }
@@ -193,50 +562,90 @@ async fn ensure_synced(pairs: Vec<SourcePair>) -> Result<Vec<SourcePair>> {
     Ok(usable)
 }
 
-fn build_from_config() -> Result<Vec<SourcePair>> {
-    let app_config = config::load_config();
-    let mut pairs = Vec::new();
-
-    for entry in &app_config.sources {
-        if !entry.enabled {
-            continue;
-        }
-        let store = Arc::new(build_store(&entry.label)?);
-        let provider = build_provider_for(entry);
-        pairs.push((store, provider));
+/// Point out collection installs that a crash or kill left half done. The
+/// journal is shared by every store, so any one of them can read it.
+fn warn_interrupted_installs(pairs: &[SourcePair]) {
+    let Some((store, _)) = pairs.first() else {
+        return;
+    };
+    for batch in store.install_batches().unwrap_or_default() {
+        eprintln!(
+            "warning: installing {} into {} stopped after {} of {} definitions. Run `agent-def-fetcher collection recover` to finish or undo it.",
+            batch.label,
+            batch.target.display(),
+            batch.done(),
+            batch.entries.len()
+        );
     }
-
-    Ok(pairs)
-}
-
-fn stores_as_sources(pairs: &[SourcePair]) -> Vec<Box<dyn Source>> {
-    pairs
-        .iter()
-        .map(|(s, _)| Box::new(Arc::clone(s)) as Box<dyn Source>)
-        .collect()
-}
-
-fn composite_source(pairs: &[SourcePair]) -> Arc<dyn Source> {
-    let sources: Vec<Arc<dyn Source>> = pairs
-        .iter()
-        .map(|(s, _)| Arc::clone(s) as Arc<dyn Source>)
-        .collect();
-    Arc::new(CompositeSource::new(sources))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    commands::color::init(cli.color, cli.plain);
+    if cli.insecure {
+        agent_defs_cli::catalog::allow_insecure_tls();
+    }
+    if cli.metered {
+        agent_defs_cli::catalog::use_metered_connection();
+    }
+    if cli.demo {
+        agent_defs_cli::catalog::use_demo_catalog();
+    }
 
     match cli.command {
-        Command::Sync => {
-            let pairs = build_from_config()?;
+        Command::Sync {
+            watch: true,
+            interval,
+            sources,
+            ..
+        } => {
+            let selection = SyncSelection {
+                labels: sources,
+                only_stale: false,
+            };
+            let pairs = commands::sync::select(build_from_config()?, &selection)?;
+            let config = config::load_config();
+            let index = CommunityIndex::from_config(&config)?;
+            let interval = Duration::from_secs(interval * 60);
+            commands::watch::run(&pairs, &config.alerts, index.as_ref(), interval).await
+        }
+        Command::Sync {
+            watch: false,
+            quiet,
+            sources,
+            only_stale,
+            offline_replay,
+            ..
+        } => {
+            if offline_replay {
+                agent_defs_cli::catalog::replay_cached_archives();
+            }
+            let selection = SyncSelection {
+                labels: sources,
+                only_stale,
+            };
+            let pairs = commands::sync::select(build_from_config()?, &selection)?;
+            if pairs.is_empty() {
+                if !quiet {
+                    println!("Nothing to sync: every selected source synced within the last week.");
+                }
+                return Ok(());
+            }
+            // Ratings come from the network, so a replay keeps the old ones.
+            let index = if offline_replay {
+                None
+            } else {
+                CommunityIndex::from_config(&config::load_config())?
+            };
             let total = pairs.len();
             let mut failed = 0usize;
 
             for (store, provider) in &pairs {
-                if let Err(e) = commands::sync::run(store, provider.as_ref()).await {
+                let synced = commands::sync::run(store, provider.as_ref(), index.as_ref(), quiet);
+                if let Err(e) = synced.await {
                     eprintln!("warning: sync failed for [{}]: {e}", provider.label());
+                    commands::sync::print_guidance(&e);
                     failed += 1;
                 }
             }
@@ -248,97 +657,649 @@ async fn main() -> Result<()> {
             if failed > 0 {
                 eprintln!("Synced {succeeded} sources ({failed} failed)");
             }
+            if !quiet && !offline_replay && config::load_config().update_checks {
+                let stamp = agent_defs_cli::catalog::cache_dir()?.join("last-update-check");
+                let http = agent_defs_cli::catalog::http_client(&config::load_config().network)?;
+                let updater = commands::self_update::Updater::new(None).with_http_client(http);
+                commands::self_update::notify_if_outdated(&updater, &stamp).await;
+            }
             Ok(())
         }
-        Command::List { kind, source } => {
+        Command::Changes { since } => {
+            let pairs = build_from_config()?;
+            let since = since.unwrap_or_else(|| {
+                commands::changes::days_ago(commands::changes::DEFAULT_WINDOW_DAYS)
+            });
+            let changes =
+                commands::changes::collect(pairs.iter().map(|(store, _)| store.as_ref()), since)?;
+            commands::changes::print(&changes, since, &mut std::io::stdout())
+        }
+        Command::Reparse { dry_run } => {
+            let pairs = build_from_config()?;
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            commands::reparse::run(stores, dry_run, &mut std::io::stdout())
+        }
+        Command::Stats { personal, wipe } => {
+            let pairs = build_from_config()?;
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            let mut stdout = std::io::stdout();
+            if wipe {
+                let cleared = commands::stats::wipe(stores)?;
+                println!("Forgot usage for {cleared} definitions");
+                Ok(())
+            } else if personal {
+                commands::stats::personal(stores, &mut stdout).await
+            } else {
+                commands::stats::catalog(stores, &mut stdout).await
+            }
+        }
+        Command::Count { by, source } => {
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            commands::count::run(stores, by, source.as_deref(), &mut std::io::stdout())
+        }
+        Command::List {
+            kind,
+            source,
+            all,
+            sort,
+            reverse,
+        } => {
             let pairs = ensure_synced(build_from_config()?).await?;
             let sources = stores_as_sources(&pairs);
-            commands::list::run(&sources, kind.as_deref(), source.as_deref()).await
+            let hidden = if all { HideRules::default() } else { config::load_hidden() };
+            let ratings =
+                commands::rating::collect(pairs.iter().map(|(store, _)| store.as_ref()))?;
+            let options = commands::list::ListOptions {
+                kind: kind.as_deref(),
+                source: source.as_deref(),
+                hidden: &hidden,
+                ratings: &ratings,
+                plain: cli.plain,
+                sort,
+                reverse,
+            };
+            commands::list::run(&sources, &options).await
         }
         Command::Search {
             query,
             kind,
             source,
+            all,
         } => {
             let pairs = ensure_synced(build_from_config()?).await?;
             let sources = stores_as_sources(&pairs);
-            commands::search::run(&sources, &query, kind.as_deref(), source.as_deref()).await
+            let hidden = if all { HideRules::default() } else { config::load_hidden() };
+            commands::search::run(
+                &sources,
+                &query,
+                kind.as_deref(),
+                source.as_deref(),
+                &hidden,
+                cli.plain,
+            )
+            .await
+        }
+        Command::Grep {
+            pattern,
+            source,
+            ignore_case,
+        } => {
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let options = commands::grep::GrepOptions {
+                source,
+                ignore_case,
+                color: commands::color::stdout(),
+            };
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            let matched = commands::grep::run(stores, &pattern, &options, &mut std::io::stdout())?;
+            if matched == 0 {
+                eprintln!("No lines match `{pattern}`.");
+            }
+            Ok(())
         }
-        Command::Show { id, source, raw } => {
+        Command::Show {
+            id,
+            source,
+            raw,
+            export,
+            output,
+        } => {
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let (id, source) = commands::lookup::split_id(labels(&pairs), &id, source)?;
+            let origins = config::load_config()
+                .sources
+                .iter()
+                .map(|entry| (entry.label.clone(), catalog::origin(entry)))
+                .collect();
+            commands::show::run(
+                pairs.iter().map(|(store, _)| store.as_ref()),
+                &origins,
+                &id,
+                source.as_deref(),
+                match export {
+                    Some(format) => View::Export {
+                        format,
+                        path: output,
+                    },
+                    None if raw => View::Raw,
+                    None => View::Formatted { plain: cli.plain },
+                },
+                &mut std::io::stdout(),
+            )
+            .await?;
+            record_usage(
+                pairs.iter().map(|(store, _)| store.as_ref()),
+                &DefinitionId::new(&id),
+                source.as_deref(),
+                UsageKind::View,
+            );
+            Ok(())
+        }
+        Command::Note {
+            id,
+            text,
+            source,
+            clear,
+        } => {
+            let pairs = build_from_config()?;
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            let mut stdout = std::io::stdout();
+            let Some(id) = id else {
+                return commands::note::print(&commands::note::collect(stores)?, &mut stdout);
+            };
+            let (id, source) = commands::lookup::split_id(labels(&pairs), &id, source)?;
+            let def_id = DefinitionId::new(&id);
+            if clear {
+                match commands::note::clear(stores, &def_id, source.as_deref())? {
+                    0 => println!("No note on {id}"),
+                    _ => println!("Removed note on {id}"),
+                }
+            } else if let Some(text) = text {
+                let label = commands::note::set(stores, &def_id, source.as_deref(), &text)?;
+                println!("Saved note on {id} [{label}]");
+            } else {
+                let notes: Vec<_> = commands::note::collect(stores)?
+                    .into_iter()
+                    .filter(|note| note.id == def_id)
+                    .filter(|note| source.as_ref().is_none_or(|s| *s == note.source_label))
+                    .collect();
+                if notes.is_empty() {
+                    println!("No note on {id}");
+                } else {
+                    commands::note::print(&notes, &mut stdout)?;
+                }
+            }
+            Ok(())
+        }
+        Command::Hide { target } => {
+            let mut stdout = std::io::stdout();
+            match target.rule() {
+                Some(rule) => commands::hide::hide(&hidden_path()?, rule, &mut stdout),
+                None => commands::hide::list(&config::load_hidden(), &mut stdout),
+            }
+        }
+        Command::Unhide { target } => {
+            let Some(rule) = target.rule() else {
+                anyhow::bail!("name a definition ID, --category, or --glob to unhide");
+            };
+            commands::hide::unhide(&hidden_path()?, &rule, &mut std::io::stdout())
+        }
+        Command::Install {
+            id,
+            target,
+            source,
+            vars,
+            yes,
+            locked,
+        } => {
             let pairs = ensure_synced(build_from_config()?).await?;
             let sources = stores_as_sources(&pairs);
-            commands::show::run(&sources, &id, source.as_deref(), raw).await
+            let target = install_target(target);
+            let rules = install_rules()?;
+            let stores = || pairs.iter().map(|(store, _)| store.as_ref());
+            if locked {
+                let id = match (id, source) {
+                    (Some(id), Some(source)) => Some(format!("{source}:{id}")),
+                    (id, _) => id,
+                };
+                let values = vars.into_iter().collect();
+                let mut stdout = std::io::stdout();
+                let installed = commands::lock::install_locked(
+                    &sources,
+                    &target,
+                    &rules,
+                    id.as_deref(),
+                    &values,
+                    yes,
+                    &mut stdout,
+                )
+                .await?;
+                for def in installed {
+                    let label = Some(def.source_label.as_str());
+                    record_usage(stores(), &def.id, label, UsageKind::Install);
+                }
+                return Ok(());
+            }
+            let id = id.expect("clap requires an ID without --locked");
+            let (id, source) = commands::lookup::split_id(labels(&pairs), &id, source)?;
+            let filter = source.as_deref();
+            let (def, path, values) =
+                commands::install::run(&sources, &id, &target, &rules, filter, vars, yes).await?;
+            let bases = pairs.first().map(|(store, _)| store.as_ref());
+            commands::lock::pin(
+                &target,
+                [(&def, path.as_path())],
+                &revisions(&pairs),
+                &values,
+                bases,
+            )?;
+            record_usage(stores(), &def.id, source.as_deref(), UsageKind::Install);
+            Ok(())
         }
-        Command::Install { id, target, source } => {
+        Command::Update {
+            ids,
+            target,
+            yes,
+            no_sync,
+        } => {
+            let target = install_target(target);
+            let pairs = build_from_config()?;
+            if !no_sync {
+                let pinned = Lockfile::load(&target)?;
+                for (store, provider) in &pairs {
+                    let label = provider.label();
+                    if !pinned.definitions.iter().any(|locked| locked.source == label) {
+                        continue;
+                    }
+                    let synced = commands::sync::run(store, provider.as_ref(), None, true);
+                    if let Err(e) = synced.await {
+                        eprintln!("warning: sync failed for [{label}]: {e}");
+                        commands::sync::print_guidance(&e);
+                    }
+                }
+            }
+            let pairs = ensure_synced(pairs).await?;
+            let sources = stores_as_sources(&pairs);
+            let rules = install_rules()?;
+            let revisions = revisions(&pairs);
+            let bases = pairs.first().map(|(store, _)| store.as_ref());
+            let plan = commands::lock::plan_update(&sources, &target, &rules, &ids, bases).await?;
+            let mut stdout = std::io::stdout();
+            plan.print(&revisions, &mut stdout)?;
+            if plan.is_empty() {
+                return Ok(());
+            }
+            if !yes {
+                if !std::io::stdin().is_terminal() {
+                    anyhow::bail!("not asking without a terminal; pass --yes to update");
+                }
+                let confirmed = commands::lock::confirm_update(
+                    &mut std::io::stdin().lock(),
+                    &mut std::io::stderr(),
+                )?;
+                if !confirmed {
+                    anyhow::bail!("update cancelled");
+                }
+            }
+            let applied = plan.apply(&target, &revisions, bases, &mut stdout)?;
+            for def in applied.updated {
+                record_usage(
+                    pairs.iter().map(|(store, _)| store.as_ref()),
+                    &def.id,
+                    Some(&def.source_label),
+                    UsageKind::Install,
+                );
+            }
+            if !applied.failed.is_empty() {
+                anyhow::bail!("{} of the updates couldn't be written", applied.failed.len());
+            }
+            Ok(())
+        }
+        Command::KeepLocal { ids, target, undo } => commands::lock::keep_local(
+            &install_target(target),
+            &ids,
+            !undo,
+            &mut std::io::stdout(),
+        ),
+        Command::Share { id, source } => {
+            let Some(token) = github_token() else {
+                anyhow::bail!("sharing creates a gist on your account; set GITHUB_TOKEN first");
+            };
             let pairs = ensure_synced(build_from_config()?).await?;
+            let (id, source) = commands::lookup::split_id(labels(&pairs), &id, source)?;
             let sources = stores_as_sources(&pairs);
-            commands::install::run(&sources, &id, &target, source.as_deref()).await
+            let http = agent_defs_cli::catalog::http_client(&config::load_config().network)?;
+            let client =
+                agent_defs_github::GistClient::new(Some(token), None).with_http_client(http);
+            let url = commands::share::run(&sources, &id, source.as_deref(), &client).await?;
+            println!("{url}");
+            Ok(())
+        }
+        Command::SelfUpdate { check } => {
+            if !config::load_config().update_checks {
+                anyhow::bail!("update checks are disabled (update_checks = false in sources.toml)");
+            }
+            let http = agent_defs_cli::catalog::http_client(&config::load_config().network)?;
+            let updater = commands::self_update::Updater::new(None).with_http_client(http);
+            commands::self_update::run(&updater, check, &mut std::io::stdout()).await
+        }
+        Command::Cache {
+            command: CacheCommand::Migrate { backup },
+        } => commands::cache::migrate(&db_path()?, backup),
+        Command::Collection { command } => run_collection(command).await,
+        Command::Sources { command } => run_sources(command).await,
+        Command::Site {
+            command: SiteCommand::Build { out },
+        } => {
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let sources = stores_as_sources(&pairs);
+            commands::site::build(&sources, &out, &mut std::io::stdout()).await
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::Push { to, token },
+        } => {
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let snapshot =
+                commands::snapshot::collect(pairs.iter().map(|(store, _)| store.as_ref()))?;
+            let token = token.or_else(|| std::env::var("AGENT_DEFS_SNAPSHOT_TOKEN").ok());
+            let client = agent_defs_cli::catalog::http_client(&config::load_config().network)?;
+            let mut stdout = std::io::stdout();
+            commands::snapshot::push(&snapshot, &to, token.as_deref(), &client, &mut stdout).await
+        }
+        Command::Config {
+            command: ConfigCommand::Check,
+        } => commands::config::check(
+            config::config_path().as_deref(),
+            std::env::current_dir()
+                .ok()
+                .and_then(|dir| config::find_workspace_config(&dir))
+                .as_deref(),
+            &Policy::load()?,
+            &mut std::io::stdout(),
+        ),
+        Command::Schedule { command } => {
+            let mut stdout = std::io::stdout();
+            match command {
+                ScheduleCommand::Install { every } => {
+                    let log = agent_defs_cli::catalog::cache_dir()?.join("scheduled-sync.log");
+                    commands::schedule::install(every, &log, &mut stdout)
+                }
+                ScheduleCommand::Remove => commands::schedule::remove(&mut stdout),
+                ScheduleCommand::Status => commands::schedule::status(&mut stdout),
+            }
         }
-        Command::Tui { target } => {
+        Command::Tui {
+            target,
+            demo_script,
+        } => {
+            // A scripted session is read up front, so a typo in it fails
+            // before the screen is taken over.
+            let demo_script = demo_script
+                .map(|path| agent_defs_tui::demo::DemoScript::load(&path))
+                .transpose()?;
+            let target = match (target, &demo_script) {
+                (Some(target), _) => Some(target),
+                // Demos install somewhere new each run, so they replay the
+                // same way and leave the project alone.
+                (None, Some(_)) => {
+                    let name = format!("agent-def-fetcher-demo-{}", std::process::id());
+                    Some(std::env::temp_dir().join(name))
+                }
+                (None, None) => config::load_config().install_target,
+            };
             let pairs = ensure_synced(build_from_config()?).await?;
 
             let source = composite_source(&pairs);
 
-            // Build sync closures that iterate all store/provider pairs.
-            let sync_pairs: Vec<(Arc<DefinitionStore>, Arc<dyn SyncProvider>)> = pairs
-                .into_iter()
-                .map(|(s, p)| (s, Arc::from(p)))
-                .collect();
-            let sync_pairs = Arc::new(sync_pairs);
-
-            let on_sync: SyncFn = Box::new(move || {
-                let pairs = Arc::clone(&sync_pairs);
-                Box::pin(async move {
-                    let mut total_synced = 0u64;
-                    let mut total_skipped = 0u64;
-                    let mut all_warnings: Vec<String> = Vec::new();
-                    let mut failed = 0usize;
-
-                    for (store, provider) in pairs.iter() {
-                        match store.sync(provider.as_ref()).await {
-                            Ok(report) => {
-                                total_synced += report.synced;
-                                total_skipped += report.skipped;
-                                // Collect warning messages
-                                for fb in &report.feedback {
-                                    if fb.is_warning() {
-                                        all_warnings.push(fb.message().to_owned());
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                all_warnings.push(format!(
-                                    "sync failed for [{}]: {}",
-                                    provider.label(),
-                                    e
-                                ));
-                                failed += 1;
-                            }
-                        }
-                    }
+            if cli.plain || std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+                let target = match target {
+                    Some(target) => target,
+                    None => std::env::current_dir()?,
+                };
+                let rules = install_rules()?;
+                let stdin = std::io::stdin().lock();
+                let mut stdout = std::io::stdout();
+                let hidden = config::load_hidden();
+                return commands::pager::run(
+                    source.as_ref(),
+                    &target,
+                    &rules,
+                    &hidden,
+                    stdin,
+                    &mut stdout,
+                )
+                .await;
+            }
 
-                    if total_synced == 0 && failed == pairs.len() {
-                        return Err(anyhow::anyhow!("all sources failed to sync"));
-                    }
+            let mut usage = Vec::new();
+            for (store, _) in &pairs {
+                usage.extend(store.usage().unwrap_or_default());
+            }
+            agent_defs::usage::sort_most_used(&mut usage);
+            let usage_stores: Vec<Arc<DefinitionStore>> =
+                pairs.iter().map(|(store, _)| Arc::clone(store)).collect();
+            let on_usage: agent_defs_tui::UsageFn = Arc::new(move |id, kind| {
+                record_usage(usage_stores.iter().map(Arc::as_ref), id, None, kind);
+            });
 
-                    let mut msg = format!(
-                        "Synced {} definitions ({} skipped)",
-                        total_synced, total_skipped
-                    );
-                    if !all_warnings.is_empty() {
-                        msg.push_str(&format!(", {} warning(s)", all_warnings.len()));
-                    }
-                    if failed > 0 {
-                        msg.push_str(&format!(", {} source(s) failed", failed));
-                    }
-                    Ok(SyncResult {
-                        message: msg,
-                        warnings: all_warnings,
-                    })
-                })
+            let notes = commands::note::collect(pairs.iter().map(|(store, _)| store.as_ref()))?;
+            let ratings =
+                commands::rating::collect(pairs.iter().map(|(store, _)| store.as_ref()))?;
+            let note_stores: Vec<Arc<DefinitionStore>> =
+                pairs.iter().map(|(store, _)| Arc::clone(store)).collect();
+            let on_note: agent_defs_tui::NoteFn = Arc::new(move |note| {
+                let stores = note_stores.iter().map(Arc::as_ref);
+                let source = Some(note.source_label.as_str());
+                // Best effort, like usage: a failed save only loses the note.
+                let _ = if note.text.is_empty() {
+                    commands::note::clear(stores, &note.id, source).map(drop)
+                } else {
+                    commands::note::set(stores, &note.id, source, &note.text).map(drop)
+                };
+            });
+
+            let on_hide: agent_defs_tui::HideFn = Arc::new(|rule| {
+                commands::hide::hide(&hidden_path()?, rule.clone(), &mut std::io::sink())
             });
 
-            agent_defs_tui::run(source, on_sync, target).await
+            let recent_changes = commands::changes::collect(
+                pairs.iter().map(|(store, _)| store.as_ref()),
+                commands::changes::days_ago(commands::changes::DEFAULT_WINDOW_DAYS),
+            )?;
+            let skipped = skipped_files(pairs.iter().map(|(store, _)| store.as_ref()));
+
+            let sync_pairs = pairs.into_iter().map(|(s, p)| (s, Arc::from(p))).collect();
+            let config = config::load_config();
+            let sync = Arc::new(
+                commands::sync::StoreSyncController::new(sync_pairs)
+                    .with_community_index(CommunityIndex::from_config(&config)?),
+            );
+
+            // A demo leaves usage, notes and hidden definitions as they were.
+            let demo = demo_script.is_some();
+            let options = TuiOptions {
+                install_target: target,
+                skip_quick_install_confirm: !config.confirm_quick_install,
+                glyphs: config::glyph_set(&config),
+                list_density: config::list_density(&config),
+                collections: config::load_collections(&config),
+                changes: recent_changes,
+                usage,
+                on_usage: (!demo).then_some(on_usage),
+                notes,
+                on_note: (!demo).then_some(on_note),
+                ratings,
+                hidden: config::load_hidden(),
+                on_hide: (!demo).then_some(on_hide),
+                skipped,
+                rules: install_rules()?,
+                demo_script,
+                tick_rate: config.tick_rate_ms.map(Duration::from_millis),
+            };
+            agent_defs_tui::run(source, sync, options).await
+        }
+        Command::BenchFixtures { dir, count } => {
+            commands::fixtures::write(&dir, count, &mut std::io::stdout())
+        }
+    }
+}
+
+/// `--target` if given, else the configured `install_target`, else the
+/// current directory.
+fn install_target(target: Option<PathBuf>) -> PathBuf {
+    target
+        .or(config::load_config().install_target)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The configured install layout, under the org policy.
+fn install_rules() -> Result<InstallRules> {
+    Ok(InstallRules {
+        layout: config::load_config().install_layout,
+        policy: Policy::load()?,
+    })
+}
+
+/// Labels of the configured sources, which qualified IDs start with.
+fn labels(pairs: &[SourcePair]) -> impl Iterator<Item = &str> {
+    pairs.iter().map(|(_, provider)| provider.label())
+}
+
+/// What the latest sync of each store skipped. A store that can't be read
+/// just contributes nothing.
+fn skipped_files<'a>(stores: impl Iterator<Item = &'a DefinitionStore>) -> Vec<SkippedFile> {
+    stores
+        .flat_map(|store| store.skipped_files().unwrap_or_default())
+        .collect()
+}
+
+fn hidden_path() -> Result<PathBuf> {
+    config::hidden_path().ok_or_else(|| anyhow::anyhow!("could not determine config directory"))
+}
+
+async fn run_sources(command: SourcesCommand) -> Result<()> {
+    let config = config::load_config();
+    let url = config
+        .preset_registry
+        .as_deref()
+        .unwrap_or(presets::REGISTRY_URL);
+    let mut stdout = std::io::stdout();
+
+    match command {
+        SourcesCommand::Discover => {
+            let client = catalog::http_client(&config.network)?;
+            if let Err(e) = presets::refresh(url, &client).await {
+                eprintln!("warning: {e:#}; listing the presets already known");
+            }
+            commands::sources::discover(&presets::known(), &config.sources, &mut stdout)
+        }
+        SourcesCommand::Health => {
+            let pairs = build_from_config()?;
+            let stores = pairs.iter().map(|(store, _)| store.as_ref());
+            commands::sources::health(stores, config.degraded_after, &mut stdout)
+        }
+        SourcesCommand::Add { preset, label } => {
+            let found = match presets::find(&preset) {
+                Some(found) => Some(found),
+                None => {
+                    let client = catalog::http_client(&config.network)?;
+                    let registry = presets::refresh(url, &client).await?;
+                    registry.into_iter().find(|known| known.name == preset)
+                }
+            };
+            let Some(found) = found else {
+                anyhow::bail!("No preset named {preset}; `sources discover` lists them.");
+            };
+            let path = config::config_path()
+                .ok_or_else(|| anyhow::anyhow!("could not determine config directory"))?;
+            commands::sources::add(
+                &path,
+                &found,
+                label.as_deref(),
+                &Policy::load()?,
+                &mut stdout,
+            )
+        }
+    }
+}
+
+async fn run_collection(command: CollectionCommand) -> Result<()> {
+    let collections = config::load_collections(&config::load_config());
+    let mut stdout = std::io::stdout();
+
+    match command {
+        CollectionCommand::List => commands::collection::list(&collections, &mut stdout),
+        CollectionCommand::Show { name } => {
+            let collection = commands::collection::find(&collections, &name)?;
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let sources = stores_as_sources(&pairs);
+            commands::collection::show(&sources, collection, &mut stdout).await
+        }
+        CollectionCommand::Install { name, target, yes } => {
+            let collection = commands::collection::find(&collections, &name)?;
+            let pairs = ensure_synced(build_from_config()?).await?;
+            let sources = stores_as_sources(&pairs);
+            let target = install_target(target);
+            let journal = pairs.first().map(|(store, _)| store.as_ref());
+            let installed = commands::collection::install(
+                &sources,
+                collection,
+                &target,
+                &install_rules()?,
+                yes,
+                journal,
+                &mut stdout,
+            )
+            .await?;
+            let pins = installed.iter().map(|(def, path)| (def, path.as_path()));
+            let values = Default::default();
+            commands::lock::pin(&target, pins, &revisions(&pairs), &values, journal)?;
+            for (def, _) in installed {
+                record_usage(
+                    pairs.iter().map(|(store, _)| store.as_ref()),
+                    &def.id,
+                    Some(&def.source_label),
+                    UsageKind::Install,
+                );
+            }
+            Ok(())
+        }
+        CollectionCommand::Recover { resume, rollback } => {
+            let pairs = build_from_config()?;
+            let Some((journal, _)) = pairs.first() else {
+                anyhow::bail!("no sources are configured");
+            };
+            let action = match (resume, rollback) {
+                (true, _) => commands::collection::Reconcile::Resume,
+                (_, true) => commands::collection::Reconcile::Rollback,
+                _ => commands::collection::Reconcile::Report,
+            };
+            let sources = stores_as_sources(&pairs);
+            let rules = install_rules()?;
+            let installed =
+                commands::collection::recover(journal, &sources, &rules, action, &mut stdout)
+                    .await?;
+            for (source_label, id) in installed {
+                record_usage(
+                    pairs.iter().map(|(store, _)| store.as_ref()),
+                    &id,
+                    Some(&source_label),
+                    UsageKind::Install,
+                );
+            }
+            Ok(())
+        }
+        CollectionCommand::Export { name, output } => {
+            let collection = commands::collection::find(&collections, &name)?;
+            commands::collection::export(collection, output.as_deref())
+        }
+        CollectionCommand::Import { file, force } => {
+            let dir = config::collections_dir()
+                .ok_or_else(|| anyhow::anyhow!("could not determine config directory"))?;
+            let path = commands::collection::import(&file, &dir, force)?;
+            println!("Imported to {}", path.display());
+            Ok(())
         }
     }
 }
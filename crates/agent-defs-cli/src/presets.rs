@@ -0,0 +1,255 @@
+//! Presets: community repositories we know the layout of, so a source can
+//! name one (`type = "preset"`, `name = "wshobson-agents"`) instead of
+//! spelling out where the repository keeps its definitions.
+//!
+//! Each preset says which repository to sync and how its paths map onto
+//! the canonical `kind/category/name.md` layout. The built-in ones are
+//! bundled from `presets.json` beside this crate's manifest. The same file
+//! on the main branch is the registry: `sources discover` fetches it and
+//! keeps a copy in the cache, so presets added since a release can be used
+//! without a new binary.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+const BUILTIN: &str = include_str!("../presets.json");
+
+/// Where the registry is fetched from unless `preset_registry` says
+/// otherwise.
+pub const REGISTRY_URL: &str = "https://raw.githubusercontent.com/esmevane/agent-def-fetcher/main/\
+                                crates/agent-defs-cli/presets.json";
+
+/// A known repository and where its definitions go.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub owner: String,
+    pub repo: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Tried in order; a file matching none of them isn't synced.
+    pub paths: Vec<PathMapping>,
+}
+
+fn default_branch() -> String {
+    "main".into()
+}
+
+impl Preset {
+    /// Where the repository's file at `path` is synced to, if anywhere.
+    /// READMEs are left out wherever they are.
+    pub fn map(&self, path: &str) -> Option<String> {
+        if path.rsplit('/').next() == Some("README.md") {
+            return None;
+        }
+        self.paths.iter().find_map(|mapping| mapping.apply(path))
+    }
+}
+
+/// Moves files matching `from` to `to`. In `from`, `{name}` matches one
+/// path segment and `{name..}`, last, matches the rest of the path; `to`
+/// puts what they matched wherever it uses the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PathMapping {
+    pub from: String,
+    pub to: String,
+}
+
+impl PathMapping {
+    /// `path` rewritten by this mapping, or `None` if it doesn't match.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let mut segments = path.split('/');
+        let mut captures: Vec<(String, String)> = Vec::new();
+        let mut parts = self.from.split('/').peekable();
+        while let Some(part) = parts.next() {
+            if let Some(name) = placeholder(part).and_then(|name| name.strip_suffix("..")) {
+                let rest: Vec<&str> = segments.by_ref().collect();
+                if rest.is_empty() || parts.peek().is_some() {
+                    return None;
+                }
+                captures.push((format!("{{{name}..}}"), rest.join("/")));
+                break;
+            }
+            let segment = segments.next().filter(|segment| !segment.is_empty())?;
+            match placeholder(part) {
+                Some(name) => captures.push((format!("{{{name}}}"), segment.to_owned())),
+                None if part == segment => {}
+                None => return None,
+            }
+        }
+        if segments.next().is_some() {
+            return None;
+        }
+        Some(
+            captures
+                .iter()
+                .fold(self.to.clone(), |to, (placeholder, value)| {
+                    to.replace(placeholder, value)
+                }),
+        )
+    }
+}
+
+/// `name` for a `{name}` segment.
+fn placeholder(part: &str) -> Option<&str> {
+    part.strip_prefix('{')?.strip_suffix('}')
+}
+
+/// The presets that ship with this binary.
+pub fn builtin() -> Vec<Preset> {
+    serde_json::from_str(BUILTIN).expect("bundled presets.json is valid")
+}
+
+/// The built-in presets and those from the last registry fetched, which
+/// replace built-in ones of the same name.
+pub fn known() -> Vec<Preset> {
+    let cached = registry_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    merge(builtin(), cached)
+}
+
+/// The known preset called `name`.
+pub fn find(name: &str) -> Option<Preset> {
+    known().into_iter().find(|preset| preset.name == name)
+}
+
+fn merge(mut presets: Vec<Preset>, registry: Vec<Preset>) -> Vec<Preset> {
+    for preset in registry {
+        match presets.iter_mut().find(|known| known.name == preset.name) {
+            Some(known) => *known = preset,
+            None => presets.push(preset),
+        }
+    }
+    presets
+}
+
+/// Where the last registry fetched is kept:
+/// `~/.cache/agent-def-fetcher/presets.json`.
+pub fn registry_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("agent-def-fetcher").join("presets.json"))
+}
+
+/// Fetch the registry at `url` and keep it for [`known`], returning its
+/// presets.
+pub async fn refresh(url: &str, client: &reqwest::Client) -> Result<Vec<Preset>> {
+    let response = client
+        .get(url)
+        .header("User-Agent", "agent-def-fetcher")
+        .send()
+        .await
+        .with_context(|| format!("failed to reach {url}"))?;
+    if !response.status().is_success() {
+        bail!("{url} returned HTTP {}", response.status());
+    }
+    let text = response.text().await?;
+    let registry: Vec<Preset> =
+        serde_json::from_str(&text).with_context(|| format!("failed to parse {url}"))?;
+
+    if let Some(path) = registry_path() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, text)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(from: &str, to: &str) -> PathMapping {
+        PathMapping {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    #[test]
+    fn mappings_move_captured_segments() {
+        let agents = mapping("plugins/{plugin}/agents/{file}", "agents/{plugin}/{file}");
+        assert_eq!(
+            agents
+                .apply("plugins/python-development/agents/django-pro.md")
+                .as_deref(),
+            Some("agents/python-development/django-pro.md")
+        );
+        assert_eq!(
+            agents.apply("plugins/python-development/commands/x.md"),
+            None
+        );
+        assert_eq!(
+            agents.apply("plugins/python-development/agents/nested/x.md"),
+            None
+        );
+        assert_eq!(agents.apply("plugins//agents/x.md"), None);
+
+        let skills = mapping(
+            "plugins/{plugin}/skills/{rest..}",
+            "skills/{plugin}/{rest..}",
+        );
+        assert_eq!(
+            skills.apply("plugins/k8s/skills/helm/SKILL.md").as_deref(),
+            Some("skills/k8s/helm/SKILL.md")
+        );
+        assert_eq!(
+            skills
+                .apply("plugins/k8s/skills/helm/references/values.md")
+                .as_deref(),
+            Some("skills/k8s/helm/references/values.md")
+        );
+        assert_eq!(skills.apply("plugins/k8s/skills"), None);
+    }
+
+    #[test]
+    fn builtin_presets_map_their_layouts() {
+        let presets = builtin();
+        let mut names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), presets.len(), "preset names are unique");
+
+        let wshobson = find("wshobson-agents").unwrap();
+        assert_eq!(
+            (wshobson.owner.as_str(), wshobson.repo.as_str()),
+            ("wshobson", "agents")
+        );
+        assert_eq!(
+            wshobson
+                .map("plugins/security-scanning/commands/security-sast.md")
+                .as_deref(),
+            Some("commands/security-scanning/security-sast.md")
+        );
+        assert_eq!(wshobson.map("plugins/security-scanning/README.md"), None);
+        assert_eq!(wshobson.map("docs/agents.md"), None);
+        assert!(find("no-such-preset").is_none());
+    }
+
+    #[test]
+    fn registry_presets_replace_builtin_ones() {
+        let preset = |name: &str, repo: &str| Preset {
+            name: name.into(),
+            description: String::new(),
+            owner: "acme".into(),
+            repo: repo.into(),
+            branch: default_branch(),
+            paths: Vec::new(),
+        };
+        let merged = merge(
+            vec![preset("a", "old"), preset("b", "b")],
+            vec![preset("c", "c"), preset("a", "new")],
+        );
+        let repos: Vec<(&str, &str)> = merged
+            .iter()
+            .map(|p| (p.name.as_str(), p.repo.as_str()))
+            .collect();
+        assert_eq!(repos, [("a", "new"), ("b", "b"), ("c", "c")]);
+    }
+}
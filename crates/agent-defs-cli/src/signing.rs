@@ -0,0 +1,382 @@
+//! Minisign signature checks on synced content, for the `[signing]` config.
+//!
+//! A definition is signed by a detached `<file>.minisig` kept beside it in
+//! its source, and a catalog snapshot by a `.minisig` beside the snapshot.
+//! Both the legacy and the prehashed signatures minisign writes are
+//! accepted; sigstore bundles are not.
+
+use agent_defs::{FileCheck, RawDefinitionFile};
+use base64::Engine;
+use ring::signature::{ED25519, UnparsedPublicKey};
+
+use crate::config::SigningConfig;
+
+/// Appended to a file's path to find its detached signature.
+pub const SIGNATURE_SUFFIX: &str = ".minisig";
+
+/// A minisign public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl PublicKey {
+    /// Parse the base64 line of a minisign `.pub` file, with or without the
+    /// `untrusted comment:` line above it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or("the public key is empty")?;
+        let bytes = decode(line)?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err(format!("`{line}` isn't a minisign public key"));
+        }
+        Ok(Self {
+            id: bytes[2..10].try_into().expect("8 bytes"),
+            key: bytes[10..].try_into().expect("32 bytes"),
+        })
+    }
+}
+
+/// Check `signature`, the text of a `.minisig` file, over `message`. It
+/// must be made by one of `keys`, and its trusted comment must be intact.
+pub fn verify(keys: &[PublicKey], message: &[u8], signature: &str) -> Result<(), String> {
+    let malformed = || "not a minisign signature".to_owned();
+    let mut lines = signature.lines().skip(1);
+    let (Some(signature_line), Some(trusted_line), Some(global_line)) =
+        (lines.next(), lines.next(), lines.next())
+    else {
+        return Err(malformed());
+    };
+    let trusted_comment = trusted_line
+        .strip_prefix("trusted comment: ")
+        .ok_or_else(malformed)?;
+    let signature = decode(signature_line.trim())?;
+    if signature.len() != 74 {
+        return Err(malformed());
+    }
+    let (algorithm, id, signature) = (&signature[..2], &signature[2..10], &signature[10..]);
+
+    let key = keys.iter().find(|key| key.id == id).ok_or_else(|| {
+        let id = u64::from_le_bytes(id.try_into().expect("8 bytes"));
+        format!("signed by key {id:016X}, which isn't one of signing.public_keys")
+    })?;
+    let digest;
+    let signed = match algorithm {
+        b"Ed" => message,
+        b"ED" => {
+            digest = blake2b_512(message);
+            &digest[..]
+        }
+        _ => return Err("unsupported signature algorithm".into()),
+    };
+    let public_key = UnparsedPublicKey::new(&ED25519, &key.key);
+    public_key
+        .verify(signed, signature)
+        .map_err(|_| "the signature doesn't match the content".to_owned())?;
+
+    let global_signature = decode(global_line.trim())?;
+    let mut global_message = signature.to_vec();
+    global_message.extend_from_slice(trusted_comment.as_bytes());
+    public_key
+        .verify(&global_message, &global_signature)
+        .map_err(|_| "the trusted comment has been altered".to_owned())
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, String> {
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(|e| format!("bad base64: {e}"))
+}
+
+/// The `[signing]` config, ready to check content with.
+#[derive(Debug, Clone)]
+pub struct SignaturePolicy {
+    keys: Vec<PublicKey>,
+    require_signed: bool,
+}
+
+impl SignaturePolicy {
+    /// The policy `config` sets, or `None` when it lists no keys and
+    /// doesn't require signatures, leaving nothing to check.
+    pub fn from_config(config: &SigningConfig) -> Result<Option<Self>, String> {
+        if config.public_keys.is_empty() {
+            return if config.require_signed {
+                Err("signing.require_signed is set, but signing.public_keys is empty".into())
+            } else {
+                Ok(None)
+            };
+        }
+        let keys = config
+            .public_keys
+            .iter()
+            .map(|key| PublicKey::parse(key))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self {
+            keys,
+            require_signed: config.require_signed,
+        }))
+    }
+
+    /// Check `message` against its detached `signature`, if it has one.
+    /// Unsigned content passes unless signatures are required.
+    pub fn check(&self, message: &[u8], signature: Option<&str>) -> Result<(), String> {
+        match signature {
+            Some(signature) => {
+                verify(&self.keys, message, signature).map_err(|e| format!("bad signature: {e}"))
+            }
+            None if self.require_signed => {
+                Err("unsigned, and signing.require_signed is set".into())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl FileCheck for SignaturePolicy {
+    fn reject<'a>(
+        &self,
+        file: &RawDefinitionFile,
+        companion: &dyn Fn(&str) -> Option<&'a str>,
+    ) -> Option<String> {
+        let signature = companion(&format!("{}{SIGNATURE_SUFFIX}", file.relative_path));
+        self.check(file.content.as_bytes(), signature).err()
+    }
+}
+
+/// BLAKE2b with a 64-byte digest (RFC 7693), which prehashed minisign
+/// signatures sign in place of the content.
+fn blake2b_512(message: &[u8]) -> [u8; 64] {
+    const IV: [u64; 8] = [
+        0x6a09e667f3bcc908,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+    const SIGMA: [[usize; 16]; 10] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+        [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+        [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+        [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+        [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+        [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+        [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+        [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+        [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    ];
+
+    fn compress(h: &mut [u64; 8], block: &[u8; 128], counter: u128, last: bool) {
+        let mut m = [0u64; 16];
+        for (word, bytes) in m.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().expect("8 bytes"));
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= counter as u64;
+        v[13] ^= (counter >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+        let mut mix = |a: usize, b: usize, c: usize, d: usize, x: u64, y: u64| {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        };
+        for round in 0..12 {
+            let s = &SIGMA[round % 10];
+            mix(0, 4, 8, 12, m[s[0]], m[s[1]]);
+            mix(1, 5, 9, 13, m[s[2]], m[s[3]]);
+            mix(2, 6, 10, 14, m[s[4]], m[s[5]]);
+            mix(3, 7, 11, 15, m[s[6]], m[s[7]]);
+            mix(0, 5, 10, 15, m[s[8]], m[s[9]]);
+            mix(1, 6, 11, 12, m[s[10]], m[s[11]]);
+            mix(2, 7, 8, 13, m[s[12]], m[s[13]]);
+            mix(3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+        for i in 0..8 {
+            h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+
+    let mut h = IV;
+    // Parameter block: no key, 64-byte digest.
+    h[0] ^= 0x0101_0040;
+    let mut counter = 0u128;
+    let mut rest = message;
+    while rest.len() > 128 {
+        let (block, tail) = rest.split_at(128);
+        counter += 128;
+        compress(&mut h, block.try_into().expect("128 bytes"), counter, false);
+        rest = tail;
+    }
+    let mut last = [0u8; 128];
+    last[..rest.len()].copy_from_slice(rest);
+    counter += rest.len() as u128;
+    compress(&mut h, &last, counter, true);
+
+    let mut digest = [0u8; 64];
+    for (bytes, word) in digest.chunks_exact_mut(8).zip(h) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+
+    const ENGINE: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
+    const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn key_pair(seed: u8) -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap()
+    }
+
+    /// The public key line minisign would write for the key made from
+    /// `seed`.
+    pub(crate) fn public_key(seed: u8) -> String {
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&KEY_ID);
+        bytes.extend_from_slice(key_pair(seed).public_key().as_ref());
+        ENGINE.encode(bytes)
+    }
+
+    /// A `.minisig` file over `message`, prehashed as minisign does by
+    /// default.
+    pub(crate) fn sign(seed: u8, message: &[u8]) -> String {
+        sign_with(seed, b"ED", &blake2b_512(message), "timestamp:0")
+    }
+
+    fn sign_with(seed: u8, algorithm: &[u8], signed: &[u8], trusted_comment: &str) -> String {
+        let pair = key_pair(seed);
+        let signature = pair.sign(signed);
+        let mut line = algorithm.to_vec();
+        line.extend_from_slice(&KEY_ID);
+        line.extend_from_slice(signature.as_ref());
+        let mut global = signature.as_ref().to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        format!(
+            "untrusted comment: test\n{}\ntrusted comment: {trusted_comment}\n{}\n",
+            ENGINE.encode(line),
+            ENGINE.encode(pair.sign(&global))
+        )
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn blake2b_matches_the_rfc_test_vector() {
+        assert_eq!(
+            hex(&blake2b_512(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            hex(&blake2b_512(b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        // Exactly one block, which must be compressed as the last one.
+        assert_ne!(blake2b_512(&[0; 128]), blake2b_512(&[0; 129]));
+    }
+
+    #[test]
+    fn verifies_prehashed_and_legacy_signatures() {
+        let keys = [PublicKey::parse(&public_key(7)).unwrap()];
+        let message = b"---\nname: reviewer\n---\n";
+        assert_eq!(verify(&keys, message, &sign(7, message)), Ok(()));
+
+        let legacy = sign_with(7, b"Ed", message, "legacy");
+        assert_eq!(verify(&keys, message, &legacy), Ok(()));
+    }
+
+    #[test]
+    fn rejects_altered_content_and_unknown_keys() {
+        let keys = [PublicKey::parse(&format!("untrusted comment: k\n{}", public_key(7))).unwrap()];
+        let signature = sign(7, b"original");
+        assert_eq!(
+            verify(&keys, b"altered", &signature),
+            Err("the signature doesn't match the content".into())
+        );
+
+        let altered = signature.replace("timestamp:0", "timestamp:1");
+        assert_eq!(
+            verify(&keys, b"original", &altered),
+            Err("the trusted comment has been altered".into())
+        );
+
+        let other = [PublicKey::parse(&public_key(9)).unwrap()];
+        let other_id = PublicKey {
+            id: [9; 8],
+            ..other[0].clone()
+        };
+        let err = verify(&[other_id], b"original", &signature).unwrap_err();
+        assert!(err.contains("0807060504030201"), "{err}");
+        assert!(verify(&other, b"original", &signature).is_err());
+        assert!(verify(&keys, b"original", "junk").is_err());
+    }
+
+    #[test]
+    fn policy_rejects_unsigned_files_only_when_required() {
+        let config = |require_signed| SigningConfig {
+            require_signed,
+            public_keys: vec![public_key(7)],
+        };
+        let signed = RawDefinitionFile {
+            relative_path: "agents/a.md".into(),
+            content: "signed".into(),
+        };
+        let signature = sign(7, b"signed");
+        let companions = |path: &str| (path == "agents/a.md.minisig").then_some(signature.as_str());
+        let unsigned = RawDefinitionFile {
+            relative_path: "agents/b.md".into(),
+            content: "unsigned".into(),
+        };
+
+        let lenient = SignaturePolicy::from_config(&config(false))
+            .unwrap()
+            .unwrap();
+        assert_eq!(lenient.reject(&signed, &companions), None);
+        assert_eq!(lenient.reject(&unsigned, &companions), None);
+
+        let strict = SignaturePolicy::from_config(&config(true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(strict.reject(&signed, &companions), None);
+        assert_eq!(
+            strict.reject(&unsigned, &companions),
+            Some("unsigned, and signing.require_signed is set".into())
+        );
+
+        assert!(
+            SignaturePolicy::from_config(&SigningConfig::default())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            SignaturePolicy::from_config(&SigningConfig {
+                require_signed: true,
+                public_keys: vec![],
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use agent_defs::{RawDefinitionFile, SyncError, SyncProvider};
+use serde::{Deserialize, Serialize};
+
+use crate::signing::{SIGNATURE_SUFFIX, SignaturePolicy};
+
+/// Format version written into every snapshot. Readers reject newer ones
+/// rather than guess at fields they don't know.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A team's catalog frozen into one JSON document: every source's files as
+/// they were synced, so others can sync from it instead of from GitHub.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogSnapshot {
+    pub version: u32,
+    /// When the snapshot was taken, in seconds since the Unix epoch.
+    pub created_at: u64,
+    pub sources: Vec<SnapshotSource>,
+}
+
+/// One source's files within a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotSource {
+    pub label: String,
+    pub files: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub path: String,
+    pub content: String,
+}
+
+impl From<RawDefinitionFile> for SnapshotFile {
+    fn from(file: RawDefinitionFile) -> Self {
+        Self {
+            path: file.relative_path,
+            content: file.content,
+        }
+    }
+}
+
+impl CatalogSnapshot {
+    /// Parse a snapshot, refusing one written by a newer version.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let snapshot: Self =
+            serde_json::from_str(text).map_err(|e| format!("not a catalog snapshot: {e}"))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(format!(
+                "snapshot format {} is newer than this build reads ({SNAPSHOT_VERSION}); \
+                 upgrade agent-def-fetcher",
+                snapshot.version
+            ));
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Provider for a source published as part of a catalog snapshot, read
+/// from an HTTP(S) URL or a local path.
+pub struct CatalogSnapshotProvider {
+    label: String,
+    url: String,
+    /// The source to take from the snapshot, when it isn't `label`.
+    source: Option<String>,
+    token: Option<String>,
+    client: reqwest::Client,
+    signatures: Option<Arc<SignaturePolicy>>,
+}
+
+impl CatalogSnapshotProvider {
+    pub fn new(url: &str, source: Option<&str>, token: Option<String>, label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            url: url.to_owned(),
+            source: source.map(str::to_owned),
+            token,
+            client: reqwest::Client::new(),
+            signatures: None,
+        }
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check the snapshot against the `.minisig` beside it under `policy`.
+    pub fn with_signatures(mut self, policy: Arc<SignaturePolicy>) -> Self {
+        self.signatures = Some(policy);
+        self
+    }
+
+    /// What's at `url`, or `None` if there's nothing there.
+    async fn download(&self, url: &str) -> Result<Option<String>, SyncError> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            let path = url.strip_prefix("file://").unwrap_or(url);
+            return match std::fs::read_to_string(path) {
+                Ok(text) => Ok(Some(text)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(SyncError::Io(format!("reading {path}: {e}"))),
+            };
+        }
+        let mut request = self
+            .client
+            .get(url)
+            .header("User-Agent", "agent-def-fetcher");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let network = |e: reqwest::Error| {
+            if e.is_timeout() {
+                SyncError::Timeout(format!("{url}: {e}"))
+            } else {
+                SyncError::Network(format!("{url}: {e}"))
+            }
+        };
+        let response = request.send().await.map_err(network)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let message = format!("{url} returned HTTP {}", response.status());
+            return Err(match response.status().as_u16() {
+                401 | 403 => SyncError::Unauthorized(message),
+                _ => SyncError::Network(message),
+            });
+        }
+        response.text().await.map(Some).map_err(network)
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for CatalogSnapshotProvider {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        let text = self
+            .download(&self.url)
+            .await?
+            .ok_or_else(|| SyncError::Network(format!("no snapshot at {}", self.url)))?;
+        if let Some(policy) = &self.signatures {
+            let signature = self
+                .download(&format!("{}{SIGNATURE_SUFFIX}", self.url))
+                .await?;
+            policy
+                .check(text.as_bytes(), signature.as_deref())
+                .map_err(|e| SyncError::Other(format!("snapshot at {}: {e}", self.url)))?;
+        }
+        let snapshot = CatalogSnapshot::parse(&text)
+            .map_err(|e| SyncError::Extraction(format!("{}: {e}", self.url)))?;
+        let wanted = self.source.as_deref().unwrap_or(&self.label);
+        let labels: Vec<String> = snapshot.sources.iter().map(|s| s.label.clone()).collect();
+        let Some(source) = snapshot.sources.into_iter().find(|s| s.label == wanted) else {
+            return Err(SyncError::Other(format!(
+                "the snapshot at {} has no source `{wanted}`; it holds: {}",
+                self.url,
+                labels.join(", ")
+            )));
+        };
+        Ok(source
+            .files
+            .into_iter()
+            .map(|f| RawDefinitionFile {
+                relative_path: f.path,
+                content: f.content,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SigningConfig;
+    use crate::signing::tests::{public_key, sign};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn snapshot_json() -> String {
+        let snapshot = CatalogSnapshot {
+            version: SNAPSHOT_VERSION,
+            created_at: 1_700_000_000,
+            sources: vec![
+                SnapshotSource {
+                    label: "acme".into(),
+                    files: vec![SnapshotFile {
+                        path: "agents/reviewer.md".into(),
+                        content: "# Reviewer".into(),
+                    }],
+                },
+                SnapshotSource {
+                    label: "other".into(),
+                    files: vec![],
+                },
+            ],
+        };
+        serde_json::to_string(&snapshot).unwrap()
+    }
+
+    #[tokio::test]
+    async fn takes_the_source_matching_its_label() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/catalog.json"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(snapshot_json()))
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/catalog.json", server.uri());
+        let provider = CatalogSnapshotProvider::new(&url, None, Some("secret".into()), "acme");
+        let files = provider.fetch_all().await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, "agents/reviewer.md");
+        assert_eq!(files[0].content, "# Reviewer");
+
+        // Without the token the mock doesn't match.
+        let provider = CatalogSnapshotProvider::new(&url, None, None, "acme");
+        let err = provider.fetch_all().await.unwrap_err().to_string();
+        assert!(err.contains("no snapshot at"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn names_the_sources_it_holds_when_one_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("catalog.json");
+        std::fs::write(&file, snapshot_json()).unwrap();
+
+        let provider =
+            CatalogSnapshotProvider::new(file.to_str().unwrap(), Some("missing"), None, "acme");
+        let err = provider.fetch_all().await.unwrap_err().to_string();
+        assert!(
+            err.contains("no source `missing`; it holds: acme, other"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_the_signature_beside_the_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("catalog.json");
+        let text = snapshot_json();
+        std::fs::write(&file, &text).unwrap();
+        let policy = |require_signed| {
+            let config = SigningConfig {
+                require_signed,
+                public_keys: vec![public_key(7)],
+            };
+            Arc::new(SignaturePolicy::from_config(&config).unwrap().unwrap())
+        };
+        let provider = |require_signed| {
+            CatalogSnapshotProvider::new(file.to_str().unwrap(), None, None, "acme")
+                .with_signatures(policy(require_signed))
+        };
+
+        assert!(provider(false).fetch_all().await.is_ok());
+        let err = provider(true).fetch_all().await.unwrap_err().to_string();
+        assert!(err.ends_with("unsigned, and signing.require_signed is set"), "{err}");
+
+        let signature = dir.path().join("catalog.json.minisig");
+        std::fs::write(&signature, sign(7, text.as_bytes())).unwrap();
+        assert_eq!(provider(true).fetch_all().await.unwrap().len(), 1);
+
+        std::fs::write(&signature, sign(7, b"something else")).unwrap();
+        let err = provider(false).fetch_all().await.unwrap_err().to_string();
+        assert!(err.contains("bad signature"), "{err}");
+    }
+
+    #[test]
+    fn refuses_snapshots_from_a_newer_format() {
+        let text = snapshot_json().replace("\"version\":1", "\"version\":99");
+        let err = CatalogSnapshot::parse(&text).unwrap_err();
+        assert!(err.contains("newer"), "{err}");
+    }
+}
@@ -1,5 +1,5 @@
 use agent_defs::{RawDefinitionFile, SyncError, SyncProvider};
-use agent_defs_github::TarballClient;
+use agent_defs_github::{ArchiveMode, TarballClient, TarballLimits};
 
 /// Provider for the davila7/claude-code-templates repository.
 ///
@@ -12,9 +12,9 @@ pub struct ClaudeCodeTemplatesProvider {
 }
 
 impl ClaudeCodeTemplatesProvider {
-    const OWNER: &'static str = "davila7";
-    const REPO: &'static str = "claude-code-templates";
-    const BRANCH: &'static str = "main";
+    pub const OWNER: &'static str = "davila7";
+    pub const REPO: &'static str = "claude-code-templates";
+    pub const BRANCH: &'static str = "main";
     const BASE_PATH: &'static str = "cli-tool/components/";
 
     pub fn new(label: &str, token: Option<String>) -> Self {
@@ -31,6 +31,36 @@ impl ClaudeCodeTemplatesProvider {
             client: TarballClient::new(token, Some(api_base_url)),
         }
     }
+
+    /// Download with `limits` instead of the default size caps.
+    pub fn with_limits(mut self, limits: TarballLimits) -> Self {
+        self.client = self.client.with_limits(limits);
+        self
+    }
+
+    /// Keep or replay downloaded archives as `mode` says.
+    pub fn with_archives(mut self, mode: ArchiveMode) -> Self {
+        self.client = self.client.with_archives(mode);
+        self
+    }
+
+    /// Download no faster than `bytes_per_sec`, when given.
+    pub fn with_rate_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.client = self.client.with_rate_limit(bytes_per_sec);
+        self
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(client);
+        self
+    }
+
+    /// Talk to the GitHub API at `url`, as for GitHub Enterprise Server.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.client = self.client.with_api_base_url(url);
+        self
+    }
 }
 
 #[async_trait::async_trait]
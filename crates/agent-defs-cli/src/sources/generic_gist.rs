@@ -5,11 +5,14 @@ use agent_defs_github::GistClient;
 ///
 /// This provider applies the same logic as the old `GitHubGistProvider`:
 /// optionally prepends a path_prefix to each file to map flat gist files
-/// into the expected directory structure.
+/// into the expected directory structure. A directory separator, set with
+/// [`with_dir_separator`](Self::with_dir_separator), lets file names carry
+/// their own directories.
 pub struct GenericGistProvider {
     label: String,
     gist_id: String,
     path_prefix: Option<String>,
+    dir_separator: Option<String>,
     client: GistClient,
 }
 
@@ -24,6 +27,7 @@ impl GenericGistProvider {
             label: label.to_owned(),
             gist_id: gist_id.to_owned(),
             path_prefix: path_prefix.map(|s| s.to_owned()),
+            dir_separator: None,
             client: GistClient::new(token, None),
         }
     }
@@ -40,9 +44,29 @@ impl GenericGistProvider {
             label: label.to_owned(),
             gist_id: gist_id.to_owned(),
             path_prefix: path_prefix.map(|s| s.to_owned()),
+            dir_separator: None,
             client: GistClient::new(token, Some(api_base_url)),
         }
     }
+
+    /// Read `separator` in file names as `/`, so `agents__a.md` becomes
+    /// `agents/a.md` with a separator of `__`.
+    pub fn with_dir_separator(mut self, separator: &str) -> Self {
+        self.dir_separator = Some(separator.to_owned());
+        self
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(client);
+        self
+    }
+
+    /// Talk to the GitHub API at `url`, as for GitHub Enterprise Server.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.client = self.client.with_api_base_url(url);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -57,10 +81,14 @@ impl SyncProvider for GenericGistProvider {
         Ok(files
             .into_iter()
             .map(|f| {
-                let path = match &self.path_prefix {
-                    Some(prefix) => format!("{}/{}", prefix, f.filename),
+                let filename = match &self.dir_separator {
+                    Some(separator) => f.filename.replace(separator.as_str(), "/"),
                     None => f.filename,
                 };
+                let path = match &self.path_prefix {
+                    Some(prefix) => format!("{}/{}", prefix, filename),
+                    None => filename,
+                };
                 RawDefinitionFile {
                     relative_path: path,
                     content: f.content,
@@ -132,6 +160,27 @@ mod tests {
         assert_eq!(files[0].relative_path, "my-agent.md");
     }
 
+    #[tokio::test]
+    async fn dir_separator_becomes_directories() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/gists/abc123"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(gist_json(&[
+                ("agents__review__reviewer.md", "agent content"),
+            ])))
+            .mount(&server)
+            .await;
+
+        let provider =
+            GenericGistProvider::with_api_base("abc123", Some("shared"), None, "test", server.uri())
+                .with_dir_separator("__");
+        let files = provider.fetch_all().await.unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].relative_path, "shared/agents/review/reviewer.md");
+    }
+
     #[tokio::test]
     async fn label_from_constructor() {
         let provider = GenericGistProvider::new("abc", None, None, "my-gist");
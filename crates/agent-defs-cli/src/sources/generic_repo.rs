@@ -1,18 +1,46 @@
-use agent_defs::{RawDefinitionFile, SyncError, SyncProvider};
-use agent_defs_github::TarballClient;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use agent_defs::{PathFilter, RawDefinitionFile, SyncError, SyncProvider};
+use agent_defs_github::{ArchiveMode, TarballClient, TarballLimits, TreeClient};
+use agent_defs_store::DefinitionStore;
 
 /// Generic provider for user-defined GitHub repository sources.
 ///
 /// This provider applies the same logic as the old `GitHubTarballProvider`:
-/// filters to files under `base_path` (if specified) and assumes the
-/// canonical `kind/category/name.md` layout.
+/// filters to files under the base paths (if any) and assumes the
+/// canonical `kind/category/name.md` layout. With several base paths, a
+/// file is relative to the deepest one it sits under, and when two roots
+/// hold the same relative path the one listed first wins.
+///
+/// Given several branches with [`with_branches`](Self::with_branches), it
+/// fetches each and prefixes every path with its branch (`next:agents/…`),
+/// so the same file on two branches becomes two definitions.
+///
+/// Given a cache with [`metered`](Self::metered), it lists the repository
+/// through the trees API instead and downloads only the definition files
+/// the cache lacks, one request each. Files already cached keep their
+/// cached content, so upstream edits to them wait for an unmetered sync.
 pub struct GenericRepoProvider {
     label: String,
     owner: String,
     repo: String,
-    branch: String,
-    base_path: Option<String>,
+    branches: Vec<String>,
+    /// Whether paths carry their branch, as they do once `with_branches`
+    /// has been used.
+    namespaced: bool,
+    base_paths: Vec<String>,
     client: TarballClient,
+    tree: TreeClient,
+    metered: Option<Metered>,
+    /// The commit the latest tarball sync got, per branch when namespaced.
+    revision: Mutex<Option<String>>,
+}
+
+/// What a metered sync needs to decide which files to download.
+struct Metered {
+    cache: Arc<DefinitionStore>,
+    filter: PathFilter,
 }
 
 impl GenericRepoProvider {
@@ -20,7 +48,7 @@ impl GenericRepoProvider {
         owner: &str,
         repo: &str,
         branch: &str,
-        base_path: Option<&str>,
+        base_paths: &[String],
         token: Option<String>,
         label: &str,
     ) -> Self {
@@ -28,9 +56,13 @@ impl GenericRepoProvider {
             label: label.to_owned(),
             owner: owner.to_owned(),
             repo: repo.to_owned(),
-            branch: branch.to_owned(),
-            base_path: base_path.map(|s| s.to_owned()),
-            client: TarballClient::new(token, None),
+            branches: vec![branch.to_owned()],
+            namespaced: false,
+            base_paths: base_paths.to_vec(),
+            client: TarballClient::new(token.clone(), None),
+            tree: TreeClient::new(token, None),
+            metered: None,
+            revision: Mutex::new(None),
         }
     }
 
@@ -39,7 +71,7 @@ impl GenericRepoProvider {
         owner: &str,
         repo: &str,
         branch: &str,
-        base_path: Option<&str>,
+        base_paths: &[String],
         token: Option<String>,
         label: &str,
         api_base_url: String,
@@ -48,20 +80,108 @@ impl GenericRepoProvider {
             label: label.to_owned(),
             owner: owner.to_owned(),
             repo: repo.to_owned(),
-            branch: branch.to_owned(),
-            base_path: base_path.map(|s| s.to_owned()),
-            client: TarballClient::new(token, Some(api_base_url)),
+            branches: vec![branch.to_owned()],
+            namespaced: false,
+            base_paths: base_paths.to_vec(),
+            client: TarballClient::new(token.clone(), Some(api_base_url.clone())),
+            tree: TreeClient::new(token, Some(api_base_url)),
+            metered: None,
+            revision: Mutex::new(None),
         }
     }
 
-    fn base_path_prefix(&self) -> Option<String> {
-        self.base_path.as_ref().map(|bp| {
-            if bp.ends_with('/') {
-                bp.clone()
-            } else {
-                format!("{bp}/")
+    /// Fetch each of `branches` instead of the single branch, namespacing
+    /// paths with the branch they came from.
+    pub fn with_branches(mut self, branches: Vec<String>) -> Self {
+        self.branches = branches;
+        self.namespaced = true;
+        self
+    }
+
+    /// Download with `limits` instead of the default size caps.
+    pub fn with_limits(mut self, limits: TarballLimits) -> Self {
+        self.client = self.client.with_limits(limits);
+        self
+    }
+
+    /// Keep or replay downloaded archives as `mode` says.
+    pub fn with_archives(mut self, mode: ArchiveMode) -> Self {
+        self.client = self.client.with_archives(mode);
+        self
+    }
+
+    /// Download no faster than `bytes_per_sec`, when given.
+    pub fn with_rate_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.client = self.client.with_rate_limit(bytes_per_sec);
+        self
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(client.clone());
+        self.tree = self.tree.with_http_client(client);
+        self
+    }
+
+    /// Talk to the GitHub API at `url`, as for GitHub Enterprise Server.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.client = self.client.with_api_base_url(url);
+        self.tree = self.tree.with_api_base_url(url);
+        self
+    }
+
+    /// Sync file by file for a metered connection, reusing what `cache`
+    /// already has. Files `filter` leaves out aren't downloaded at all.
+    pub fn metered(mut self, cache: Arc<DefinitionStore>, filter: PathFilter) -> Self {
+        self.metered = Some(Metered { cache, filter });
+        self
+    }
+
+    /// [`fetch_all`](SyncProvider::fetch_all) over the trees and contents
+    /// APIs, downloading only files missing from `metered.cache`.
+    async fn fetch_new(&self, metered: &Metered) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        let cached: HashMap<String, String> = metered
+            .cache
+            .cached_files()
+            .map_err(|e| SyncError::Storage(e.to_string()))?
+            .into_iter()
+            .map(|file| (file.relative_path, file.content))
+            .collect();
+        let mut all = Vec::new();
+
+        for branch in &self.branches {
+            let paths = self.tree.paths(&self.owner, &self.repo, branch).await?;
+            let tagged = paths
+                .iter()
+                .filter_map(|path| {
+                    let (root, relative) = agent_defs::path::strip_root(path, &self.base_paths)?;
+                    // Each file costs a request, so leave out any that
+                    // syncing would skip anyway.
+                    let wanted = agent_defs::path::is_definition_file(relative)
+                        && !agent_defs::path::is_skill_reference(relative)
+                        && metered.filter.allows(relative);
+                    wanted.then_some((root, (path.as_str(), relative)))
+                })
+                .collect();
+
+            for (path, relative) in agent_defs::path::first_root_wins(tagged, |(_, rel)| rel) {
+                let relative_path = if self.namespaced {
+                    agent_defs::path::with_branch(branch, relative)
+                } else {
+                    relative.to_owned()
+                };
+                let content = match cached.get(&relative_path) {
+                    Some(content) => content.clone(),
+                    None => self.tree.file(&self.owner, &self.repo, branch, path).await?,
+                };
+                all.push(RawDefinitionFile {
+                    relative_path,
+                    content,
+                });
             }
-        })
+        }
+
+        Ok(all)
     }
 }
 
@@ -72,31 +192,62 @@ impl SyncProvider for GenericRepoProvider {
     }
 
     async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
-        let files = self
-            .client
-            .fetch(&self.owner, &self.repo, &self.branch)
-            .await?;
+        if let Some(metered) = &self.metered {
+            *self.revision.lock().unwrap() = None;
+            return self.fetch_new(metered).await;
+        }
+        let mut all = Vec::new();
+        let mut revisions = Vec::new();
 
-        let base_path_prefix = self.base_path_prefix();
+        for branch in &self.branches {
+            let tarball = self
+                .client
+                .fetch_tarball(&self.owner, &self.repo, branch)
+                .await?;
+            let files = tarball.files;
+            if let Some(revision) = tarball.revision {
+                revisions.push(if self.namespaced {
+                    format!("{branch}@{revision}")
+                } else {
+                    revision
+                });
+            }
 
-        Ok(files
-            .into_iter()
-            .filter_map(|f| {
-                let relative = match &base_path_prefix {
-                    Some(prefix) => f.path.strip_prefix(prefix)?.to_owned(),
-                    None => f.path,
-                };
+            let tagged = files
+                .into_iter()
+                .filter_map(|f| {
+                    let (root, relative) =
+                        agent_defs::path::strip_root(&f.path, &self.base_paths)?;
+                    if relative.is_empty() {
+                        return None;
+                    }
+                    let file = RawDefinitionFile {
+                        relative_path: relative.to_owned(),
+                        content: f.content,
+                    };
+                    Some((root, file))
+                })
+                .collect();
 
-                if relative.is_empty() {
-                    return None;
-                }
+            all.extend(
+                agent_defs::path::first_root_wins(tagged, |f| f.relative_path.as_str())
+                    .into_iter()
+                    .map(|mut file| {
+                        if self.namespaced {
+                            file.relative_path =
+                                agent_defs::path::with_branch(branch, &file.relative_path);
+                        }
+                        file
+                    }),
+            );
+        }
 
-                Some(RawDefinitionFile {
-                    relative_path: relative,
-                    content: f.content,
-                })
-            })
-            .collect())
+        *self.revision.lock().unwrap() = (!revisions.is_empty()).then(|| revisions.join(", "));
+        Ok(all)
+    }
+
+    fn revision(&self) -> Option<String> {
+        self.revision.lock().unwrap().clone()
     }
 }
 
@@ -146,7 +297,7 @@ mod tests {
             "user",
             "repo",
             "main",
-            Some("src/defs"),
+            &["src/defs".into()],
             None,
             "test",
             server.uri(),
@@ -175,7 +326,7 @@ mod tests {
             "user",
             "repo",
             "main",
-            None,
+            &[],
             None,
             "test",
             server.uri(),
@@ -206,7 +357,7 @@ mod tests {
             "user",
             "repo",
             "main",
-            Some("defs/"),
+            &["defs/".into()],
             None,
             "test",
             server.uri(),
@@ -217,9 +368,142 @@ mod tests {
         assert_eq!(files[0].relative_path, "agents/agent.md");
     }
 
+    #[tokio::test]
+    async fn several_base_paths_keep_the_first_root_on_collision() {
+        let tarball = build_tarball(&[
+            ("owner-repo-sha/tools/agents/agents/a.md", "from tools"),
+            ("owner-repo-sha/claude/agents/a.md", "from claude"),
+            ("owner-repo-sha/tools/agents/hooks/h.md", "hook"),
+            ("owner-repo-sha/docs/agents/b.md", "outside"),
+        ]);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/user/repo/tarball/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(tarball, "application/gzip"))
+            .mount(&server)
+            .await;
+
+        let provider = GenericRepoProvider::with_api_base(
+            "user",
+            "repo",
+            "main",
+            &["claude".into(), "tools/agents".into()],
+            None,
+            "test",
+            server.uri(),
+        );
+        let files = provider.fetch_all().await.unwrap();
+
+        let found: Vec<(&str, &str)> = files
+            .iter()
+            .map(|f| (f.relative_path.as_str(), f.content.as_str()))
+            .collect();
+        assert_eq!(found, [("agents/a.md", "from claude"), ("hooks/h.md", "hook")]);
+    }
+
     #[tokio::test]
     async fn label_from_constructor() {
-        let provider = GenericRepoProvider::new("owner", "repo", "main", None, None, "my-label");
+        let provider = GenericRepoProvider::new("owner", "repo", "main", &[], None, "my-label");
         assert_eq!(provider.label(), "my-label");
     }
+
+    #[tokio::test]
+    async fn several_branches_are_namespaced() {
+        let server = MockServer::start().await;
+        let branches = [
+            ("main", "agents/a.md", "1111111"),
+            ("next", "agents/b.md", "2222222"),
+        ];
+        for (branch, file, sha) in branches {
+            let entry = format!("owner-repo-{sha}/{file}");
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/user/repo/tarball/{branch}")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_raw(build_tarball(&[(&entry, "x")]), "application/gzip"),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let provider = GenericRepoProvider::with_api_base(
+            "user",
+            "repo",
+            "main",
+            &[],
+            None,
+            "test",
+            server.uri(),
+        )
+        .with_branches(vec!["main".into(), "next".into()]);
+        let files = provider.fetch_all().await.unwrap();
+
+        let paths: Vec<&str> = files.iter().map(|f| f.relative_path.as_str()).collect();
+        assert_eq!(paths, ["main:agents/a.md", "next:agents/b.md"]);
+        assert_eq!(
+            provider.revision().as_deref(),
+            Some("main@1111111, next@2222222")
+        );
+    }
+
+    #[tokio::test]
+    async fn metered_syncs_download_only_new_files() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/user/repo/git/trees/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "sha": "abc",
+                "tree": [
+                    {"path": "defs/agents/a.md", "type": "blob"},
+                    {"path": "defs/agents/b.md", "type": "blob"},
+                    {"path": "defs/agents/skip.md", "type": "blob"},
+                    {"path": "defs/LICENSE", "type": "blob"},
+                    {"path": "defs/agents", "type": "tree"},
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/user/repo/contents/defs/agents/b.md"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "b.md",
+                "path": "defs/agents/b.md",
+                "encoding": "base64",
+                "content": "LS0tCm5hbWU6IGIKLS0tCk5ldy4K",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(DefinitionStore::open_in_memory("test").unwrap());
+        let cached = agent_defs::test_support::FakeSyncProvider::new("test")
+            .with_file("agents/a.md", "---\nname: a\ndescription: A\n---\nCached.\n");
+        cache.sync(&cached).await.unwrap();
+
+        let filter = PathFilter::new(Vec::new(), vec!["agents/skip.md".into()]);
+        let provider = GenericRepoProvider::with_api_base(
+            "user",
+            "repo",
+            "main",
+            &["defs".into()],
+            None,
+            "test",
+            server.uri(),
+        )
+        .metered(cache, filter);
+        let files = provider.fetch_all().await.unwrap();
+
+        let found: Vec<(&str, &str)> = files
+            .iter()
+            .map(|f| (f.relative_path.as_str(), f.content.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("agents/a.md", "---\nname: a\ndescription: A\n---\nCached.\n"),
+                ("agents/b.md", "---\nname: b\n---\nNew.\n"),
+            ]
+        );
+    }
 }
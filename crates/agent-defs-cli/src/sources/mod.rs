@@ -1,9 +1,13 @@
 pub mod awesome_subagents;
+pub mod catalog_snapshot;
 pub mod claude_code_templates;
 pub mod generic_gist;
 pub mod generic_repo;
+pub mod preset;
 
 pub use awesome_subagents::AwesomeSubagentsProvider;
+pub use catalog_snapshot::CatalogSnapshotProvider;
 pub use claude_code_templates::ClaudeCodeTemplatesProvider;
 pub use generic_gist::GenericGistProvider;
 pub use generic_repo::GenericRepoProvider;
+pub use preset::PresetProvider;
@@ -0,0 +1,166 @@
+use agent_defs::{RawDefinitionFile, SyncError, SyncProvider};
+use agent_defs_github::{ArchiveMode, TarballClient, TarballLimits};
+
+use crate::presets::Preset;
+
+/// Provider for a repository described by a [`Preset`].
+///
+/// Downloads the preset's repository and branch, and keeps the files its
+/// path mappings place, at the paths they place them.
+pub struct PresetProvider {
+    label: String,
+    preset: Preset,
+    client: TarballClient,
+}
+
+impl PresetProvider {
+    pub fn new(preset: Preset, label: &str, token: Option<String>) -> Self {
+        Self {
+            label: label.to_owned(),
+            preset,
+            client: TarballClient::new(token, None),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_api_base(preset: Preset, label: &str, api_base_url: String) -> Self {
+        Self {
+            label: label.to_owned(),
+            preset,
+            client: TarballClient::new(None, Some(api_base_url)),
+        }
+    }
+
+    /// Download with `limits` instead of the default size caps.
+    pub fn with_limits(mut self, limits: TarballLimits) -> Self {
+        self.client = self.client.with_limits(limits);
+        self
+    }
+
+    /// Keep or replay downloaded archives as `mode` says.
+    pub fn with_archives(mut self, mode: ArchiveMode) -> Self {
+        self.client = self.client.with_archives(mode);
+        self
+    }
+
+    /// Download no faster than `bytes_per_sec`, when given.
+    pub fn with_rate_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.client = self.client.with_rate_limit(bytes_per_sec);
+        self
+    }
+
+    /// Send requests through `client` rather than a default one.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = self.client.with_http_client(client);
+        self
+    }
+
+    /// Talk to the GitHub API at `url`, as for GitHub Enterprise Server.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.client = self.client.with_api_base_url(url);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for PresetProvider {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        let preset = &self.preset;
+        let files = self
+            .client
+            .fetch(&preset.owner, &preset.repo, &preset.branch)
+            .await?;
+
+        Ok(files
+            .into_iter()
+            .filter_map(|f| {
+                Some(RawDefinitionFile {
+                    relative_path: preset.map(&f.path)?,
+                    content: f.content,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn build_tarball(entries: &[(&str, &str)]) -> Vec<u8> {
+        let encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        for (file_path, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_path(file_path).unwrap();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_cksum();
+            archive.append(&header, content.as_bytes()).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn maps_the_preset_layout() {
+        let tarball = build_tarball(&[
+            (
+                "wshobson-agents-abc123/plugins/python-development/agents/django-pro.md",
+                "---\nname: django-pro\n---\nYou write Django.",
+            ),
+            (
+                "wshobson-agents-abc123/plugins/python-development/commands/python-scaffold.md",
+                "Scaffold a Python project.",
+            ),
+            (
+                "wshobson-agents-abc123/plugins/kubernetes-operations/skills/helm-chart-scaffolding/SKILL.md",
+                "---\nname: helm-chart-scaffolding\n---\nScaffold a chart.",
+            ),
+            (
+                "wshobson-agents-abc123/plugins/python-development/README.md",
+                "# Python",
+            ),
+            (
+                "wshobson-agents-abc123/docs/architecture.md",
+                "# Architecture",
+            ),
+        ]);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/wshobson/agents/tarball/main"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(tarball, "application/gzip"))
+            .mount(&server)
+            .await;
+
+        let preset = crate::presets::find("wshobson-agents").unwrap();
+        let provider = PresetProvider::with_api_base(preset, "wshobson", server.uri());
+        let mut paths: Vec<String> = provider
+            .fetch_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            [
+                "agents/python-development/django-pro.md",
+                "commands/python-development/python-scaffold.md",
+                "skills/kubernetes-operations/helm-chart-scaffolding/SKILL.md",
+            ]
+        );
+        assert_eq!(provider.label(), "wshobson");
+    }
+}
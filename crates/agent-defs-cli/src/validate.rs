@@ -0,0 +1,1078 @@
+//! Checks on `sources.toml` beyond what deserializing it catches: unknown
+//! keys (usually typos), unknown source types, unset `${VAR}` references,
+//! duplicate labels, and values that look like a URL where a bare name
+//! belongs.
+//!
+//! Each problem carries the line it was found on, when that can be worked
+//! out, so the messages point somewhere useful.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use agent_defs_github::HttpOptions;
+
+use crate::config::{self, AppConfig, SourceType};
+use crate::signing::PublicKey;
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "sources",
+    "max_definition_bytes",
+    "glyphs",
+    "list_density",
+    "tick_rate_ms",
+    "collections",
+    "alerts",
+    "update_checks",
+    "install_target",
+    "install_layout",
+    "confirm_quick_install",
+    "archive_limits",
+    "archive_cache_bytes",
+    "degraded_after",
+    "network",
+    "categories",
+    "community_index",
+    "preset_registry",
+    "signing",
+];
+/// Top-level keys a project's workspace config may set. The rest describe
+/// the machine or the user rather than the project.
+const WORKSPACE_KEYS: &[&str] = &["sources", "install_target", "install_layout"];
+const SOURCE_KEYS: &[&str] = &["label", "enabled", "include", "exclude", "token", "type"];
+const ALERT_KEYS: &[&str] = &["query", "notify", "command"];
+const COLLECTION_KEYS: &[&str] = &["name", "description", "items"];
+const ARCHIVE_LIMIT_KEYS: &[&str] =
+    &["max_download_bytes", "max_file_bytes", "max_extracted_bytes"];
+const SIGNING_KEYS: &[&str] = &["require_signed", "public_keys"];
+const NETWORK_KEYS: &[&str] = &[
+    "proxy",
+    "ca_bundle",
+    "connect_timeout_secs",
+    "read_timeout_secs",
+    "api_url",
+    "max_kib_per_sec",
+];
+
+/// The `type` values a source may have, with the extra keys each accepts.
+const SOURCE_TYPES: &[(&str, &[&str])] = &[
+    ("claude-code-templates", &[]),
+    ("awesome-subagents", &[]),
+    (
+        "github-repo",
+        &["owner", "repo", "branch", "branches", "base_path"],
+    ),
+    ("github-gist", &["gist_id", "path_prefix", "dir_separator"]),
+    ("catalog-snapshot", &["url", "source"]),
+    ("preset", &["name"]),
+    ("demo", &[]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The config can't be used as written.
+    Error,
+    /// Probably a mistake, but the rest of the config still works.
+    Warning,
+}
+
+/// One problem found in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 1-based line the problem was found on.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            line,
+            message: message.into(),
+        }
+    }
+
+    fn warning(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "{severity}: line {line}: {}", self.message),
+            None => write!(f, "{severity}: {}", self.message),
+        }
+    }
+}
+
+/// Parse and check config text, expanding `${VAR}` references from the
+/// environment. The config is returned whenever it could be deserialized,
+/// even if there are errors, so callers decide what to do with it.
+pub fn check(text: &str) -> (Option<AppConfig>, Vec<Issue>) {
+    check_with_env(text, |name| std::env::var(name).ok())
+}
+
+/// [`check`] for a project's `.agent-def-fetcher.toml`, which may only set
+/// [`WORKSPACE_KEYS`]. It can't give a source a token or refer to
+/// environment variables either, since anyone who can commit to the project
+/// could then send your secrets wherever they like.
+pub fn check_workspace(text: &str) -> (Option<AppConfig>, Vec<Issue>) {
+    let (config, mut issues) = check_with_env(text, |_| Some(String::new()));
+    let Ok(table) = toml::from_str::<toml::Table>(text) else {
+        return (config, issues);
+    };
+    for key in table.keys() {
+        if TOP_LEVEL_KEYS.contains(&key.as_str()) && !WORKSPACE_KEYS.contains(&key.as_str()) {
+            issues.push(Issue::error(
+                top_level_line(text, key),
+                format!("{key} can't be set in a workspace config, only in sources.toml"),
+            ));
+        }
+    }
+    for (index, source) in tables(&table, "sources") {
+        if source.contains_key("token") {
+            issues.push(Issue::error(
+                key_line(text, "sources", index, "token"),
+                format!(
+                    "sources[{index}].token can't be set in a workspace config; \
+                     GitHub sources fall back to GITHUB_TOKEN"
+                ),
+            ));
+        }
+    }
+    for (key, value) in &table {
+        env_references(text, value, key, &mut issues);
+    }
+    (config, issues)
+}
+
+/// Report every string under `value` that refers to an environment variable.
+fn env_references(text: &str, value: &toml::Value, path: &str, issues: &mut Vec<Issue>) {
+    match value {
+        toml::Value::String(s) if s.replace("$${", "").contains("${") => {
+            let line = text
+                .lines()
+                .position(|line| line.contains(s.as_str()))
+                .map(|index| index + 1);
+            issues.push(Issue::error(
+                line,
+                format!("{path} refers to an environment variable, which a workspace config can't"),
+            ));
+        }
+        toml::Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                env_references(text, item, &format!("{path}[{index}]"), issues);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table {
+                env_references(text, item, &format!("{path}.{key}"), issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_with_env(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String> + Copy,
+) -> (Option<AppConfig>, Vec<Issue>) {
+    let mut table = match toml::from_str::<toml::Table>(text) {
+        Ok(table) => table,
+        Err(e) => return (None, vec![deserialize_issue(text, &e)]),
+    };
+
+    let mut issues = Vec::new();
+    check_keys(text, &table, &mut issues);
+
+    // Deserializing the text as written, before expansion, is what gives
+    // type errors a location.
+    if let Err(e) = toml::from_str::<AppConfig>(text) {
+        // An unknown source type already has a clearer message than
+        // serde's "unknown variant".
+        if !issues.iter().any(Issue::is_error) {
+            issues.push(deserialize_issue(text, &e));
+        }
+        return (None, issues);
+    }
+
+    let before = issues.len();
+    for (key, value) in table.iter_mut() {
+        expand_strings(text, value, key, lookup, &mut issues);
+    }
+    if issues[before..].iter().any(Issue::is_error) {
+        return (None, issues);
+    }
+    let config = match toml::Value::Table(table).try_into::<AppConfig>() {
+        Ok(config) => config,
+        Err(e) => {
+            issues.push(Issue::error(None, e.message().trim_end()));
+            return (None, issues);
+        }
+    };
+
+    check_values(text, &config, &mut issues);
+    (Some(config), issues)
+}
+
+fn deserialize_issue(text: &str, error: &toml::de::Error) -> Issue {
+    let line = error.span().map(|span| line_of_offset(text, span.start));
+    Issue::error(line, error.message().trim_end())
+}
+
+fn check_keys(text: &str, table: &toml::Table, issues: &mut Vec<Issue>) {
+    for key in table.keys() {
+        if !TOP_LEVEL_KEYS.contains(&key.as_str()) {
+            issues.push(unknown_key(
+                top_level_line(text, key),
+                key,
+                "at the top level",
+                TOP_LEVEL_KEYS,
+            ));
+        }
+    }
+
+    for (name, known) in [
+        ("archive_limits", ARCHIVE_LIMIT_KEYS),
+        ("network", NETWORK_KEYS),
+        ("signing", SIGNING_KEYS),
+    ] {
+        let Some(toml::Value::Table(section)) = table.get(name) else {
+            continue;
+        };
+        for key in section.keys() {
+            if !known.contains(&key.as_str()) {
+                issues.push(unknown_key(
+                    table_key_line(text, name, key),
+                    key,
+                    &format!("in {name}"),
+                    known,
+                ));
+            }
+        }
+    }
+
+    for (index, source) in tables(table, "sources") {
+        let place = format!("in sources[{index}]");
+        let known = match source.get("type") {
+            None => {
+                issues.push(Issue::error(
+                    header_line(text, "sources", index),
+                    format!(
+                        "sources[{index}] has no `type`; expected one of {}",
+                        type_names()
+                    ),
+                ));
+                continue;
+            }
+            Some(toml::Value::String(name)) => {
+                match SOURCE_TYPES.iter().find(|(known, _)| known == name) {
+                    Some((_, keys)) => *keys,
+                    None => {
+                        let names: Vec<&str> = SOURCE_TYPES.iter().map(|(n, _)| *n).collect();
+                        let hint = suggestion(name, &names)
+                            .map(|s| format!("did you mean `{s}`? "))
+                            .unwrap_or_default();
+                        issues.push(Issue::error(
+                            key_line(text, "sources", index, "type"),
+                            format!(
+                                "unknown source type `{name}` {place}; {hint}expected one of {}",
+                                type_names()
+                            ),
+                        ));
+                        continue;
+                    }
+                }
+            }
+            Some(_) => continue,
+        };
+        let all: Vec<&str> = SOURCE_KEYS.iter().chain(known).copied().collect();
+        for key in source.keys() {
+            if !all.contains(&key.as_str()) {
+                let line = key_line(text, "sources", index, key);
+                issues.push(unknown_key(line, key, &place, &all));
+            }
+        }
+        if source.contains_key("branch") && source.contains_key("branches") {
+            issues.push(Issue::warning(
+                key_line(text, "sources", index, "branch"),
+                format!("sources[{index}] sets both `branch` and `branches`; `branch` is ignored"),
+            ));
+        }
+    }
+
+    for (array, known) in [("alerts", ALERT_KEYS), ("collections", COLLECTION_KEYS)] {
+        for (index, entry) in tables(table, array) {
+            for key in entry.keys() {
+                if !known.contains(&key.as_str()) {
+                    let line = key_line(text, array, index, key);
+                    issues.push(unknown_key(
+                        line,
+                        key,
+                        &format!("in {array}[{index}]"),
+                        known,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Expand `${VAR}` in every string under `value`, except alert commands,
+/// which the shell expands when it runs them.
+fn expand_strings(
+    text: &str,
+    value: &mut toml::Value,
+    path: &str,
+    lookup: impl Fn(&str) -> Option<String> + Copy,
+    issues: &mut Vec<Issue>,
+) {
+    match value {
+        toml::Value::String(s) => match config::expand_env(s, lookup) {
+            Ok(expanded) => *s = expanded,
+            Err(message) => {
+                let line = text
+                    .lines()
+                    .position(|line| line.contains(s.as_str()))
+                    .map(|index| index + 1);
+                issues.push(Issue::error(line, format!("{message} (in {path})")));
+            }
+        },
+        toml::Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                expand_strings(text, item, &format!("{path}[{index}]"), lookup, issues);
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                if path.starts_with("alerts[") && key == "command" {
+                    continue;
+                }
+                expand_strings(text, item, &format!("{path}.{key}"), lookup, issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_values(text: &str, config: &AppConfig, issues: &mut Vec<Issue>) {
+    if config.max_definition_bytes == 0 {
+        issues.push(Issue::error(
+            top_level_line(text, "max_definition_bytes"),
+            "max_definition_bytes is 0, so every file would be skipped",
+        ));
+    }
+    let limits = &config.archive_limits;
+    for (key, value) in [
+        ("max_download_bytes", limits.max_download_bytes),
+        ("max_file_bytes", limits.max_file_bytes),
+        ("max_extracted_bytes", limits.max_extracted_bytes),
+    ] {
+        if value == 0 {
+            issues.push(Issue::error(
+                table_key_line(text, "archive_limits", key),
+                format!("archive_limits.{key} is 0, so every repository sync would fail"),
+            ));
+        }
+    }
+
+    // Build a client from each setting alone so a problem points at its key.
+    let network = &config.network;
+    for (key, value) in [
+        ("connect_timeout_secs", network.connect_timeout_secs),
+        ("read_timeout_secs", network.read_timeout_secs),
+    ] {
+        if value == 0 {
+            issues.push(Issue::error(
+                table_key_line(text, "network", key),
+                format!("network.{key} is 0, so every request would time out at once"),
+            ));
+        }
+    }
+    if network.max_kib_per_sec == Some(0) {
+        issues.push(Issue::error(
+            table_key_line(text, "network", "max_kib_per_sec"),
+            "network.max_kib_per_sec is 0, so no download could ever finish".to_owned(),
+        ));
+    }
+    let settings = [
+        (
+            "proxy",
+            HttpOptions {
+                proxy: network.proxy.clone(),
+                ..HttpOptions::default()
+            },
+        ),
+        (
+            "ca_bundle",
+            HttpOptions {
+                ca_bundle: network.ca_bundle.clone(),
+                ..HttpOptions::default()
+            },
+        ),
+    ];
+    for (key, options) in settings {
+        if options != HttpOptions::default()
+            && let Err(e) = options.client()
+        {
+            issues.push(Issue::error(
+                table_key_line(text, "network", key),
+                format!("network.{key}: {e}"),
+            ));
+        }
+    }
+    if let Some(url) = &network.api_url
+        && reqwest::Url::parse(url).is_err()
+    {
+        issues.push(Issue::error(
+            table_key_line(text, "network", "api_url"),
+            format!("network.api_url `{url}` is not a URL"),
+        ));
+    }
+
+    if let Some(url) = &config.community_index
+        && reqwest::Url::parse(url).is_err()
+    {
+        issues.push(Issue::error(
+            top_level_line(text, "community_index"),
+            format!("community_index `{url}` is not a URL"),
+        ));
+    }
+
+    if let Some(url) = &config.preset_registry
+        && reqwest::Url::parse(url).is_err()
+    {
+        issues.push(Issue::error(
+            top_level_line(text, "preset_registry"),
+            format!("preset_registry `{url}` is not a URL"),
+        ));
+    }
+
+    let signing = &config.signing;
+    if signing.require_signed && signing.public_keys.is_empty() {
+        issues.push(Issue::error(
+            table_key_line(text, "signing", "require_signed"),
+            "signing.require_signed is set, but there are no signing.public_keys to check \
+             signatures with"
+                .to_owned(),
+        ));
+    }
+    for key in &signing.public_keys {
+        if let Err(e) = PublicKey::parse(key) {
+            issues.push(Issue::error(
+                table_key_line(text, "signing", "public_keys"),
+                format!("signing.public_keys: {e}"),
+            ));
+        }
+    }
+
+    let mut labels = HashSet::new();
+    for (index, source) in config.sources.iter().enumerate() {
+        let line = |key| key_line(text, "sources", index, key);
+        if source.label.trim().is_empty() {
+            issues.push(Issue::error(
+                line("label"),
+                format!("sources[{index}] has an empty label"),
+            ));
+        } else if !labels.insert(source.label.as_str()) {
+            issues.push(Issue::error(
+                line("label"),
+                format!(
+                    "label `{}` is used by more than one source; labels must be unique",
+                    source.label
+                ),
+            ));
+        }
+
+        match &source.source_type {
+            SourceType::GitHubRepo {
+                owner,
+                repo,
+                branches,
+                ..
+            } => {
+                for (key, value) in [("owner", owner), ("repo", repo)] {
+                    if let Some(problem) = github_name_problem(value) {
+                        issues.push(Issue::error(
+                            line(key),
+                            format!("{key} `{value}` in sources[{index}] {problem}"),
+                        ));
+                    }
+                }
+                let mut seen = HashSet::new();
+                for branch in branches {
+                    if branch.trim().is_empty() || branch.contains(':') {
+                        issues.push(Issue::error(
+                            line("branches"),
+                            format!(
+                                "branch `{branch}` in sources[{index}] isn't a usable branch name"
+                            ),
+                        ));
+                    } else if !seen.insert(branch) {
+                        issues.push(Issue::warning(
+                            line("branches"),
+                            format!("branch `{branch}` is listed twice in sources[{index}]"),
+                        ));
+                    }
+                }
+            }
+            SourceType::GitHubGist {
+                gist_id,
+                dir_separator,
+                ..
+            } => {
+                if !gist_id.chars().all(|c| c.is_ascii_hexdigit()) || gist_id.is_empty() {
+                    issues.push(Issue::error(
+                        line("gist_id"),
+                        format!(
+                            "gist_id `{gist_id}` in sources[{index}] isn't a gist ID; \
+                             use the hex string at the end of the gist's URL"
+                        ),
+                    ));
+                }
+                if let Some(separator) = dir_separator
+                    && (separator.is_empty() || separator.contains('/'))
+                {
+                    issues.push(Issue::error(
+                        line("dir_separator"),
+                        format!(
+                            "dir_separator in sources[{index}] must be non-empty text \
+                             without `/`, such as \"__\""
+                        ),
+                    ));
+                }
+            }
+            SourceType::CatalogSnapshot { url, .. } => {
+                if url.trim().is_empty() {
+                    issues.push(Issue::error(
+                        line("url"),
+                        format!("url in sources[{index}] is empty"),
+                    ));
+                } else if url.contains("://")
+                    && !["http://", "https://", "file://"].iter().any(|s| url.starts_with(s))
+                {
+                    issues.push(Issue::error(
+                        line("url"),
+                        format!(
+                            "url `{url}` in sources[{index}] must be http(s):// or a local path; \
+                             for S3 or GCS, use the bucket's HTTPS URL"
+                        ),
+                    ));
+                }
+            }
+            SourceType::Preset { name } => {
+                if crate::presets::find(name).is_none() {
+                    let known: Vec<String> =
+                        crate::presets::known().into_iter().map(|p| p.name).collect();
+                    issues.push(Issue::error(
+                        line("name"),
+                        format!(
+                            "preset `{name}` in sources[{index}] isn't a known one; \
+                             known presets: {}; `sources discover` fetches the latest",
+                            known.join(", ")
+                        ),
+                    ));
+                }
+            }
+            SourceType::ClaudeCodeTemplates
+            | SourceType::AwesomeSubagents
+            | SourceType::Demo => {}
+        }
+
+        for (key, patterns) in [("include", &source.include), ("exclude", &source.exclude)] {
+            if patterns.iter().any(|p| p.trim().is_empty()) {
+                issues.push(Issue::warning(
+                    line(key),
+                    format!("{key} in sources[{index}] has an empty pattern"),
+                ));
+            }
+        }
+    }
+
+    for (index, alert) in config.alerts.iter().enumerate() {
+        if !alert.notify && alert.command.is_none() {
+            issues.push(Issue::warning(
+                header_line(text, "alerts", index),
+                format!("alerts[{index}] sets neither `notify` nor `command`, so it does nothing"),
+            ));
+        }
+    }
+}
+
+/// Why a GitHub owner or repo name won't work, if it won't.
+fn github_name_problem(value: &str) -> Option<&'static str> {
+    if value.contains("://") || value.starts_with("github.com") {
+        Some("is a URL; give just the name, e.g. owner = \"acme\" and repo = \"agents\"")
+    } else if value.contains('/') {
+        Some("contains `/`; put the owner and the repo in separate keys")
+    } else if value.is_empty()
+        || !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        Some("isn't a valid GitHub name")
+    } else {
+        None
+    }
+}
+
+fn unknown_key(line: Option<usize>, key: &str, place: &str, known: &[&str]) -> Issue {
+    let hint = suggestion(key, known)
+        .map(|s| format!("; did you mean `{s}`?"))
+        .unwrap_or_default();
+    Issue::warning(line, format!("unknown key `{key}` {place}{hint}"))
+}
+
+fn type_names() -> String {
+    SOURCE_TYPES
+        .iter()
+        .map(|(name, _)| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The entries of an array of tables, with their indices.
+fn tables<'a>(table: &'a toml::Table, key: &str) -> impl Iterator<Item = (usize, &'a toml::Table)> {
+    table
+        .get(key)
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, value)| value.as_table().map(|t| (index, t)))
+}
+
+/// The closest known name to a misspelt one, if any is close enough to be
+/// a likely typo.
+fn suggestion<'a>(input: &str, known: &[&'a str]) -> Option<&'a str> {
+    let normalized = input.to_lowercase().replace('_', "-");
+    known
+        .iter()
+        .map(|&candidate| {
+            let distance = edit_distance(&normalized, &candidate.replace('_', "-"));
+            (distance, candidate)
+        })
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn line_of_offset(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+// Line lookups scan the text rather than relying on spans, which `toml::Table`
+// doesn't keep. They understand the `[[array]]` layout the README documents;
+// inline tables just go without a line number.
+
+fn top_level_line(text: &str, key: &str) -> Option<usize> {
+    text.lines()
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .position(|line| assigns(line, key))
+        .map(|index| index + 1)
+}
+
+fn header_line(text: &str, array: &str, index: usize) -> Option<usize> {
+    let header = format!("[[{array}]]");
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == header)
+        .nth(index)
+        .map(|(number, _)| number + 1)
+}
+
+fn key_line(text: &str, array: &str, index: usize, key: &str) -> Option<usize> {
+    let start = header_line(text, array, index)?;
+    text.lines()
+        .enumerate()
+        .skip(start)
+        .take_while(|(_, line)| !line.trim_start().starts_with('['))
+        .find(|(_, line)| assigns(line, key))
+        .map(|(number, _)| number + 1)
+        .or(Some(start))
+}
+
+fn table_key_line(text: &str, table: &str, key: &str) -> Option<usize> {
+    let header = format!("[{table}]");
+    let start = text.lines().position(|line| line.trim() == header)? + 1;
+    text.lines()
+        .enumerate()
+        .skip(start)
+        .take_while(|(_, line)| !line.trim_start().starts_with('['))
+        .find(|(_, line)| assigns(line, key))
+        .map(|(number, _)| number + 1)
+        .or(Some(start))
+}
+
+fn assigns(line: &str, key: &str) -> bool {
+    let line = line.trim_start();
+    let key_part = line.split('=').next().unwrap_or_default().trim();
+    line.contains('=') && key_part.trim_matches('"') == key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(text: &str) -> Vec<String> {
+        check(text).1.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let text = r#"
+max_definition_bytes = 1000
+
+[[sources]]
+label = "mine"
+type = "github-repo"
+owner = "acme"
+repo = "agents.rs"
+include = ["agents/**"]
+
+[[sources]]
+label = "gist"
+type = "github-gist"
+gist_id = "abc123def456"
+"#;
+        let (config, issues) = check(text);
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(config.unwrap().sources.len(), 2);
+    }
+
+    #[test]
+    fn workspace_config_is_limited() {
+        let text = r#"
+install_layout = "flat"
+update_checks = false
+
+[[sources]]
+label = "team"
+type = "catalog-snapshot"
+url = "https://catalog.example/${TEAM}.json"
+token = "secret"
+"#;
+        let (_, issues) = check_workspace(text);
+        let messages: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "error: line 3: update_checks can't be set in a workspace config, only in sources.toml",
+                "error: line 9: sources[0].token can't be set in a workspace config; \
+                 GitHub sources fall back to GITHUB_TOKEN",
+                "error: line 8: sources[0].url refers to an environment variable, \
+                 which a workspace config can't",
+            ]
+        );
+
+        let (config, issues) = check_workspace("install_layout = \"flat\"\n");
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(config.unwrap().install_layout, agent_defs::InstallLayout::Flat);
+    }
+
+    #[test]
+    fn unknown_type_suggests_the_closest() {
+        let text =
+            "[[sources]]\nlabel = \"x\"\ntype = \"github_repo\"\nowner = \"a\"\nrepo = \"b\"\n";
+        let (config, issues) = check(text);
+        assert!(config.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(3));
+        assert!(
+            issues[0].message.starts_with(
+                "unknown source type `github_repo` in sources[0]; did you mean `github-repo`?"
+            ),
+            "{}",
+            issues[0].message
+        );
+    }
+
+    #[test]
+    fn missing_type_is_an_error() {
+        let issues = messages("[[sources]]\nlabel = \"x\"\n");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].starts_with("error: line 1: sources[0] has no `type`"));
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings_with_lines() {
+        let text = "glyph = \"ascii\"\n\n[[sources]]\nlabel = \"x\"\ntype = \"github-repo\"\nowner = \"a\"\nrepo = \"b\"\nbrnach = \"dev\"\n\n[[alerts]]\nquery = \"rust\"\nnotify = true\nshell = \"echo\"\n";
+        let (config, issues) = check(text);
+        assert!(config.is_some());
+        assert_eq!(
+            issues.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "warning: line 1: unknown key `glyph` at the top level; did you mean `glyphs`?",
+                "warning: line 8: unknown key `brnach` in sources[0]; did you mean `branch`?",
+                "warning: line 13: unknown key `shell` in alerts[0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_from_another_source_type_are_unknown() {
+        let issues = messages(
+            "[[sources]]\nlabel = \"x\"\ntype = \"github-gist\"\ngist_id = \"abc\"\nbranch = \"dev\"\n",
+        );
+        assert_eq!(
+            issues,
+            ["warning: line 5: unknown key `branch` in sources[0]"]
+        );
+    }
+
+    #[test]
+    fn duplicate_labels_point_at_the_second() {
+        let text = "[[sources]]\nlabel = \"x\"\ntype = \"awesome-subagents\"\n\n[[sources]]\nlabel = \"x\"\ntype = \"claude-code-templates\"\n";
+        let (config, issues) = check(text);
+        assert!(config.is_some());
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_error());
+        assert_eq!(issues[0].line, Some(6));
+    }
+
+    #[test]
+    fn urls_in_name_fields_are_errors() {
+        let text = "[[sources]]\nlabel = \"x\"\ntype = \"github-repo\"\nowner = \"https://github.com/acme\"\nrepo = \"acme/agents\"\n\n[[sources]]\nlabel = \"g\"\ntype = \"github-gist\"\ngist_id = \"https://gist.github.com/u/abc\"\n";
+        let issues = messages(text);
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(
+            issues[0].starts_with(
+                "error: line 4: owner `https://github.com/acme` in sources[0] is a URL"
+            )
+        );
+        assert!(
+            issues[1].starts_with("error: line 5: repo `acme/agents` in sources[0] contains `/`")
+        );
+        assert!(issues[2].starts_with("error: line 10: gist_id"));
+    }
+
+    #[test]
+    fn syntax_and_type_errors_carry_lines() {
+        let (config, issues) = check("[[sources]]\nlabel = \"x\"\ntype = \n");
+        assert!(config.is_none());
+        assert_eq!(issues[0].line, Some(3));
+
+        let (config, issues) = check("update_checks = \"yes\"\n");
+        assert!(config.is_none());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(1));
+    }
+
+    #[test]
+    fn environment_variables_are_expanded() {
+        let text = "install_target = \"${WORK}/.claude\"\n\n[[sources]]\nlabel = \"x\"\ntype = \"github-repo\"\nowner = \"${OWNER:-acme}\"\nrepo = \"agents\"\ntoken = \"${TOKEN}\"\n\n[[alerts]]\nquery = \"rust\"\ncommand = \"echo ${AGENT_DEFS_NAMES}\"\n";
+        let lookup = |name: &str| match name {
+            "WORK" => Some("/work".to_owned()),
+            "TOKEN" => Some("secret".to_owned()),
+            _ => None,
+        };
+        let (config, issues) = check_with_env(text, lookup);
+        assert!(issues.is_empty(), "{issues:?}");
+        let config = config.unwrap();
+        assert_eq!(
+            config.install_target.unwrap().to_str(),
+            Some("/work/.claude")
+        );
+        assert_eq!(config.sources[0].token.as_deref(), Some("secret"));
+        assert!(matches!(
+            &config.sources[0].source_type,
+            SourceType::GitHubRepo { owner, .. } if owner == "acme"
+        ));
+        assert_eq!(
+            config.alerts[0].command.as_deref(),
+            Some("echo ${AGENT_DEFS_NAMES}")
+        );
+    }
+
+    #[test]
+    fn unset_variables_are_errors() {
+        let text = "[[sources]]\nlabel = \"x\"\ntype = \"github-repo\"\nowner = \"acme\"\nrepo = \"agents\"\ntoken = \"${ACME_TOKEN}\"\n";
+        let (config, issues) = check_with_env(text, |_| None);
+        assert!(config.is_none());
+        assert_eq!(
+            issues.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["error: line 6: environment variable `ACME_TOKEN` is not set (in sources[0].token)"]
+        );
+    }
+
+    #[test]
+    fn branch_lists_are_checked() {
+        let issues = messages(
+            "[[sources]]\nlabel = \"x\"\ntype = \"github-repo\"\nowner = \"a\"\nrepo = \"b\"\nbranch = \"main\"\nbranches = [\"main\", \"main\", \"a:b\"]\n",
+        );
+        assert_eq!(
+            issues,
+            [
+                "warning: line 6: sources[0] sets both `branch` and `branches`; `branch` is ignored",
+                "warning: line 7: branch `main` is listed twice in sources[0]",
+                "error: line 7: branch `a:b` in sources[0] isn't a usable branch name",
+            ]
+        );
+    }
+
+    #[test]
+    fn inert_alerts_and_zero_size_limit_are_flagged() {
+        let issues = messages("max_definition_bytes = 0\n\n[[alerts]]\nquery = \"rust\"\n");
+        assert_eq!(
+            issues,
+            [
+                "error: line 1: max_definition_bytes is 0, so every file would be skipped",
+                "warning: line 3: alerts[0] sets neither `notify` nor `command`, so it does nothing",
+            ]
+        );
+    }
+
+    #[test]
+    fn gist_dir_separator_is_checked() {
+        let text = "[[sources]]\nlabel = \"g\"\ntype = \"github-gist\"\ngist_id = \"abc\"\n\
+                    dir_separator = \"\"\n";
+        assert_eq!(
+            messages(text),
+            [
+                "error: line 5: dir_separator in sources[0] must be non-empty text without `/`, \
+                 such as \"__\""
+            ]
+        );
+    }
+
+    #[test]
+    fn catalog_snapshot_url_is_checked() {
+        let source = "[[sources]]\nlabel = \"s\"\ntype = \"catalog-snapshot\"\n";
+        let https = format!("{source}url = \"https://cdn.example.com/c.json\"\n");
+        assert!(messages(&https).is_empty());
+        assert!(messages(&format!("{source}url = \"/srv/catalog.json\"\n")).is_empty());
+        assert_eq!(
+            messages(&format!("{source}url = \"s3://bucket/c.json\"\n")),
+            [
+                "error: line 4: url `s3://bucket/c.json` in sources[0] must be http(s):// or a \
+                 local path; for S3 or GCS, use the bucket's HTTPS URL"
+            ]
+        );
+    }
+
+    #[test]
+    fn preset_names_are_checked() {
+        let source = "[[sources]]\nlabel = \"p\"\ntype = \"preset\"\n";
+        assert!(messages(&format!("{source}name = \"wshobson-agents\"\n")).is_empty());
+        let issues = messages(&format!("{source}name = \"wshobson\"\n"));
+        assert_eq!(issues.len(), 1);
+        assert!(
+            issues[0].starts_with("error: line 4: preset `wshobson` in sources[0] isn't a known"),
+            "{issues:?}"
+        );
+        assert!(issues[0].contains("wshobson-agents"), "{issues:?}");
+    }
+
+    #[test]
+    fn signing_keys_are_checked() {
+        assert_eq!(
+            messages("[signing]\nrequire_signed = true\n"),
+            [
+                "error: line 2: signing.require_signed is set, but there are no \
+                 signing.public_keys to check signatures with"
+            ]
+        );
+        assert_eq!(
+            messages("[signing]\npublic_keys = [\"bm90IGEga2V5\"]\n"),
+            ["error: line 2: signing.public_keys: `bm90IGEga2V5` isn't a minisign public key"]
+        );
+    }
+
+    #[test]
+    fn network_settings_are_checked() {
+        let issues = messages(
+            "[network]\nproxy = \"http://proxy.corp:3128\"\nca_bundle = \"/nonexistent/ca.pem\"\n",
+        );
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(
+            issues[0].starts_with("error: line 3: network.ca_bundle: I/O error: failed to read"),
+            "{issues:?}"
+        );
+    }
+
+    #[test]
+    fn api_url_must_be_a_url() {
+        let enterprise = "[network]\napi_url = \"https://github.example.com/api/v3\"\n";
+        assert!(messages(enterprise).is_empty());
+        assert_eq!(
+            messages("[network]\napi_url = \"github.example.com\"\n"),
+            ["error: line 2: network.api_url `github.example.com` is not a URL"]
+        );
+    }
+
+    #[test]
+    fn community_index_must_be_a_url() {
+        assert!(messages("community_index = \"https://example.com/ratings\"\n").is_empty());
+        assert_eq!(
+            messages("community_index = \"ratings\"\n"),
+            ["error: line 1: community_index `ratings` is not a URL"]
+        );
+    }
+
+    #[test]
+    fn zero_timeouts_are_errors() {
+        assert_eq!(
+            messages("[network]\nread_timeout_secs = 0\n"),
+            [
+                "error: line 2: network.read_timeout_secs is 0, so every request would time out \
+                 at once"
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_rate_limit_is_an_error() {
+        assert!(messages("[network]\nmax_kib_per_sec = 512\n").is_empty());
+        assert_eq!(
+            messages("[network]\nmax_kib_per_sec = 0\n"),
+            ["error: line 2: network.max_kib_per_sec is 0, so no download could ever finish"]
+        );
+    }
+
+    #[test]
+    fn archive_limits_are_checked() {
+        let issues = messages("[archive_limits]\nmax_file_bytes = 0\nmax_downlod_bytes = 10\n");
+        assert_eq!(
+            issues,
+            [
+                "warning: line 3: unknown key `max_downlod_bytes` in archive_limits; \
+                 did you mean `max_download_bytes`?",
+                "error: line 2: archive_limits.max_file_bytes is 0, so every repository sync \
+                 would fail",
+            ]
+        );
+    }
+}
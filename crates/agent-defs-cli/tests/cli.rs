@@ -0,0 +1,458 @@
+//! End-to-end runs of the real binary against a mocked GitHub API, with
+//! the config and cache in a throwaway home directory.
+
+// `dirs` finds the config and cache under $HOME only on Unix.
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use predicates::prelude::*;
+use tempfile::TempDir;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TARBALL: &str = "/repos/acme/agents/tarball/main";
+
+const REVIEWER: &str = "---\nname: reviewer\ndescription: Reviews pull requests\n\
+                        tools: Read, Grep\n---\nYou review pull requests.\n";
+const DEPLOY: &str = "---\nname: deploy\ndescription: Deploys the current branch\n---\n\
+                      Deploy with care.\n";
+const LINT_HOOK: &str = "---\nname: lint\ndescription: Lints on save\n---\n```json\n\
+                         {\"hooks\": {\"PostToolUse\": [{\"command\": \"make lint\"}]}}\n```\n";
+
+/// A home directory holding a config with one repository source, served
+/// by a mock GitHub.
+struct Harness {
+    home: TempDir,
+    github: MockServer,
+}
+
+impl Harness {
+    async fn new() -> Self {
+        let harness = Self {
+            home: TempDir::new().unwrap(),
+            github: MockServer::start().await,
+        };
+        harness.write_config(&harness.config(""));
+        harness
+    }
+
+    /// The config pointing at the mock, with `top_level` settings added.
+    fn config(&self, top_level: &str) -> String {
+        format!(
+            "update_checks = false\n{top_level}\n\
+             [network]\napi_url = \"{}\"\n\n\
+             [[sources]]\nlabel = \"acme\"\ntype = \"github-repo\"\n\
+             owner = \"acme\"\nrepo = \"agents\"\n",
+            self.github.uri()
+        )
+    }
+
+    /// Serve a repository tarball holding `files`, laid out the way GitHub
+    /// lays them out: under a directory named for the commit.
+    async fn serve_repo(&self, files: &[(&str, &str)]) {
+        Mock::given(method("GET"))
+            .and(path(TARBALL))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(tarball(files), "application/gzip"),
+            )
+            .mount(&self.github)
+            .await;
+    }
+
+    async fn serve_status(&self, status: u16) {
+        Mock::given(method("GET"))
+            .and(path(TARBALL))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&self.github)
+            .await;
+    }
+
+    fn write_config(&self, toml: &str) {
+        let dir = self.config_dir().join("agent-def-fetcher");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("sources.toml"), toml).unwrap();
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        if cfg!(target_os = "macos") {
+            self.home.path().join("Library/Application Support")
+        } else {
+            self.home.path().join(".config")
+        }
+    }
+
+    /// The binary, run from the home directory with nothing inherited that
+    /// would point it at the real user's files or tokens.
+    fn cli(&self) -> Command {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_agent-defs-cli"));
+        cmd.current_dir(self.home.path())
+            .env("HOME", self.home.path())
+            .env("NO_COLOR", "1")
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .env_remove("GITHUB_TOKEN");
+        cmd
+    }
+
+    fn project(&self) -> PathBuf {
+        let dir = self.home.path().join("project");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
+
+fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (file_path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header
+            .set_path(format!("acme-agents-0abc123/{file_path}"))
+            .unwrap();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append(&header, content.as_bytes()).unwrap();
+    }
+    archive.into_inner().unwrap().finish().unwrap()
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_list_show_install() {
+    let harness = Harness::new().await;
+    harness
+        .serve_repo(&[
+            ("agents/reviewer.md", REVIEWER),
+            ("commands/deploy.md", DEPLOY),
+            ("LICENSE", "MIT\n"),
+        ])
+        .await;
+
+    harness
+        .cli()
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Synced 2 definitions"));
+
+    harness
+        .cli()
+        .args(["list", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("reviewer: Reviews pull requests"))
+        .stdout(predicate::str::contains(
+            "deploy: Deploys the current branch",
+        ))
+        .stdout(predicate::str::contains("2 definitions total"));
+
+    harness
+        .cli()
+        .args(["show", "agents/reviewer.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reviews pull requests"))
+        .stdout(predicate::str::contains("You review pull requests."));
+
+    let project = harness.project();
+    harness
+        .cli()
+        .args(["install", "agents/reviewer.md", "--target"])
+        .arg(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed to"));
+    assert_eq!(read(&project.join(".claude/agents/reviewer.md")), REVIEWER);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_merges_community_ratings() {
+    let harness = Harness::new().await;
+    harness.write_config(&harness.config(&format!(
+        "community_index = \"{}/ratings\"\n",
+        harness.github.uri()
+    )));
+    harness
+        .serve_repo(&[
+            ("agents/reviewer.md", REVIEWER),
+            ("commands/deploy.md", DEPLOY),
+        ])
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/ratings/acme.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"{"definitions": {
+                "agents/reviewer.md": {"rating": 4.6, "ratings": 212, "verified": true}
+            }}"#,
+        ))
+        .mount(&harness.github)
+        .await;
+
+    harness
+        .cli()
+        .arg("sync")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merged 1 community rating(s)."));
+
+    harness
+        .cli()
+        .args(["list", "--plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "reviewer: Reviews pull requests\nRating: \u{2605}4.6 from 212 ratings, verified\n",
+        ));
+
+    harness
+        .cli()
+        .args(["show", "agents/reviewer.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\u{2605}4.6 from 212 ratings, verified",
+        ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn first_list_syncs_on_its_own() {
+    let harness = Harness::new().await;
+    harness
+        .serve_repo(&[("agents/reviewer.md", REVIEWER)])
+        .await;
+
+    harness
+        .cli()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("reviewer"));
+    assert_eq!(harness.github.received_requests().await.unwrap().len(), 1);
+
+    harness.cli().arg("list").assert().success();
+    assert_eq!(harness.github.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn demo_needs_no_repository() {
+    let harness = Harness::new().await;
+
+    harness
+        .cli()
+        .args(["--demo", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("code-reviewer"))
+        .stdout(predicate::str::contains("pdf-forms"));
+    harness
+        .cli()
+        .args(["--demo", "show", "agents/review/code-reviewer.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Group findings by severity"));
+    assert!(harness.github.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn presets_from_the_registry_can_be_added() {
+    let harness = Harness::new().await;
+    harness.write_config(&harness.config(&format!(
+        "preset_registry = \"{}/presets.json\"",
+        harness.github.uri()
+    )));
+    Mock::given(method("GET"))
+        .and(path("/presets.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"[{"name": "acme-plugins", "description": "Acme's plugin agents",
+                 "owner": "acme", "repo": "agents",
+                 "paths": [{"from": "plugins/{plugin}/agents/{file}",
+                            "to": "agents/{plugin}/{file}"}]}]"#,
+        ))
+        .mount(&harness.github)
+        .await;
+    harness
+        .serve_repo(&[("plugins/review/agents/reviewer.md", REVIEWER)])
+        .await;
+
+    harness
+        .cli()
+        .args(["sources", "discover"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("acme-plugins"))
+        .stdout(predicate::str::contains("wshobson-agents"));
+    harness
+        .cli()
+        .args(["sources", "add", "--preset", "acme-plugins", "--label", "plugins"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added source `plugins`"));
+    harness.cli().args(["config", "check"]).assert().success();
+    harness
+        .cli()
+        .args(["list", "--source", "plugins"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reviews pull requests"))
+        .stdout(predicate::str::contains("1 definition"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sync_fails_when_github_does() {
+    let harness = Harness::new().await;
+    harness.serve_status(500).await;
+
+    harness
+        .cli()
+        .arg("sync")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("sync failed for [acme]"))
+        .stderr(predicate::str::contains("all 1 sources failed to sync"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sources_that_keep_failing_stop_being_retried() {
+    let harness = Harness::new().await;
+    harness.write_config(&harness.config("degraded_after = 2"));
+    harness.serve_status(404).await;
+
+    for _ in 0..2 {
+        harness
+            .cli()
+            .arg("list")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("initial sync failed for [acme]"));
+    }
+    let requests = harness.github.received_requests().await.unwrap().len();
+    harness
+        .cli()
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "[acme] is degraded after 2 failed syncs in a row; skipping it",
+        ));
+    assert_eq!(
+        harness.github.received_requests().await.unwrap().len(),
+        requests
+    );
+    harness
+        .cli()
+        .args(["sources", "health"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("acme  2 failed syncs in a row, degraded"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rate_limited_sync_says_when_to_retry() {
+    let harness = Harness::new().await;
+    let reset_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 20 * 60;
+    Mock::given(method("GET"))
+        .and(path(TARBALL))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .insert_header("x-ratelimit-remaining", "0")
+                .insert_header("x-ratelimit-reset", reset_at.to_string().as_str()),
+        )
+        .mount(&harness.github)
+        .await;
+
+    harness
+        .cli()
+        .arg("sync")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("rate limited"))
+        .stderr(predicate::str::contains("hint: rate limit resets in 20m"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn missing_repository_is_reported() {
+    let harness = Harness::new().await;
+    harness.serve_status(404).await;
+
+    harness
+        .cli()
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("initial sync failed for [acme]"))
+        .stderr(predicate::str::contains("nothing to display"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unknown_definitions_are_errors() {
+    let harness = Harness::new().await;
+    harness
+        .serve_repo(&[("agents/reviewer.md", REVIEWER)])
+        .await;
+    harness.cli().arg("sync").assert().success();
+
+    harness
+        .cli()
+        .args(["show", "agents/missing.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("agents/missing.md"));
+
+    let project = harness.project();
+    harness
+        .cli()
+        .args(["install", "agents/missing.md", "--target"])
+        .arg(&project)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("agents/missing.md"));
+    assert!(!project.join(".claude").exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hooks_need_confirmation_without_a_terminal() {
+    let harness = Harness::new().await;
+    harness.serve_repo(&[("hooks/lint.md", LINT_HOOK)]).await;
+    harness.cli().arg("sync").assert().success();
+    let project = harness.project();
+
+    harness
+        .cli()
+        .args(["install", "hooks/lint.md", "--target"])
+        .arg(&project)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --yes to install it"));
+
+    harness
+        .cli()
+        .args(["install", "hooks/lint.md", "--yes", "--target"])
+        .arg(&project)
+        .assert()
+        .success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invalid_config_is_rejected_before_any_request() {
+    let harness = Harness::new().await;
+    harness.write_config("[network]\napi_url = \"not a url\"\n");
+
+    harness
+        .cli()
+        .arg("sync")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("network.api_url"));
+    assert!(harness.github.received_requests().await.unwrap().is_empty());
+}
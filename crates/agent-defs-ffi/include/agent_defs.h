@@ -0,0 +1,43 @@
+/*
+ * C interface to the agent-def-fetcher local catalog.
+ *
+ * Strings returned as `char *` are UTF-8 JSON and must be released with
+ * agent_defs_string_free(). A NULL return means the call failed; see
+ * agent_defs_last_error(). Optional `const char *` arguments accept NULL.
+ */
+#ifndef AGENT_DEFS_H
+#define AGENT_DEFS_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct AgentDefs AgentDefs;
+
+/* NULL db_path / config_path select the default cache and user config. */
+AgentDefs *agent_defs_open(const char *db_path, const char *config_path);
+void agent_defs_close(AgentDefs *handle);
+
+char *agent_defs_list(const AgentDefs *handle, const char *kind, const char *source);
+char *agent_defs_search(const AgentDefs *handle, const char *query, const char *kind,
+                        const char *source);
+char *agent_defs_fetch(const AgentDefs *handle, const char *id, const char *source);
+/* Syncs the sources in the handle's config; not safe alongside other calls
+ * on the same handle. */
+char *agent_defs_sync(AgentDefs *handle, const char *source);
+/* vars: JSON object of template variable values, or NULL for the defaults.
+ * Hooks run shell commands and are refused unless allow_hooks is true. */
+char *agent_defs_install(const AgentDefs *handle, const char *id, const char *target,
+                         const char *source, const char *vars, bool allow_hooks);
+
+/* Valid until the next call into the library on the same thread. */
+const char *agent_defs_last_error(void);
+void agent_defs_string_free(char *ptr);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AGENT_DEFS_H */
@@ -0,0 +1,124 @@
+"""ctypes wrapper around the agent-defs-ffi shared library.
+
+Build the library with ``cargo build --release -p agent-defs-ffi`` and either
+point ``AGENT_DEFS_LIB`` at it or leave it in ``target/release``.
+
+    from agent_defs import Catalog
+
+    with Catalog() as catalog:
+        for summary in catalog.search("review", kind="agent"):
+            print(summary["id"], summary["name"])
+"""
+
+import ctypes
+import json
+import os
+import sys
+from pathlib import Path
+
+_LIB_NAMES = {
+    "darwin": "libagent_defs_ffi.dylib",
+    "win32": "agent_defs_ffi.dll",
+}
+
+
+class AgentDefsError(RuntimeError):
+    """Raised when a library call fails."""
+
+
+def _default_library_path():
+    name = _LIB_NAMES.get(sys.platform, "libagent_defs_ffi.so")
+    repo_root = Path(__file__).resolve().parents[3]
+    return repo_root / "target" / "release" / name
+
+
+def _load(path=None):
+    lib = ctypes.CDLL(str(path or os.environ.get("AGENT_DEFS_LIB") or _default_library_path()))
+
+    # Returned strings are declared as void pointers so they can be freed.
+    lib.agent_defs_open.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+    lib.agent_defs_open.restype = ctypes.c_void_p
+    lib.agent_defs_close.argtypes = [ctypes.c_void_p]
+    lib.agent_defs_close.restype = None
+    lib.agent_defs_list.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p]
+    lib.agent_defs_search.argtypes = [ctypes.c_void_p] + [ctypes.c_char_p] * 3
+    lib.agent_defs_fetch.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p]
+    lib.agent_defs_sync.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+    lib.agent_defs_install.argtypes = [ctypes.c_void_p] + [ctypes.c_char_p] * 4 + [ctypes.c_bool]
+    for fn in ("list", "search", "fetch", "sync", "install"):
+        getattr(lib, f"agent_defs_{fn}").restype = ctypes.c_void_p
+    lib.agent_defs_last_error.argtypes = []
+    lib.agent_defs_last_error.restype = ctypes.c_char_p
+    lib.agent_defs_string_free.argtypes = [ctypes.c_void_p]
+    lib.agent_defs_string_free.restype = None
+    return lib
+
+
+def _encode(value):
+    if value is None or isinstance(value, bool):
+        return value
+    return os.fspath(value).encode("utf-8")
+
+
+class Catalog:
+    """The local definition catalog shared with the ``agent-def-fetcher`` CLI."""
+
+    def __init__(self, db_path=None, config_path=None, library=None):
+        self._lib = _load(library)
+        self._handle = self._lib.agent_defs_open(_encode(db_path), _encode(config_path))
+        if not self._handle:
+            raise AgentDefsError(self._last_error())
+
+    def _last_error(self):
+        message = self._lib.agent_defs_last_error()
+        return message.decode("utf-8") if message else "unknown error"
+
+    def _call(self, name, *args):
+        if not self._handle:
+            raise AgentDefsError("catalog is closed")
+        ptr = getattr(self._lib, f"agent_defs_{name}")(self._handle, *map(_encode, args))
+        if not ptr:
+            raise AgentDefsError(self._last_error())
+        try:
+            return json.loads(ctypes.string_at(ptr).decode("utf-8"))
+        finally:
+            self._lib.agent_defs_string_free(ptr)
+
+    def list(self, kind=None, source=None):
+        return self._call("list", kind, source)
+
+    def search(self, query, kind=None, source=None):
+        return self._call("search", query, kind, source)
+
+    def fetch(self, definition_id, source=None):
+        return self._call("fetch", definition_id, source)
+
+    def sync(self, source=None):
+        return self._call("sync", source)
+
+    def install(self, definition_id, target=".", source=None, values=None, allow_hooks=False):
+        """Install a definition, filling its template variables from ``values``.
+
+        Hooks run shell commands, so they're refused unless ``allow_hooks``
+        is true; show the user ``fetch(...)``'s body before passing it.
+        """
+        vars_json = json.dumps(values) if values is not None else None
+        return self._call(
+            "install", definition_id, target, source, vars_json, allow_hooks
+        )["path"]
+
+    def close(self):
+        if self._handle:
+            self._lib.agent_defs_close(self._handle)
+            self._handle = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        # __init__ may have failed before the handle existed.
+        if getattr(self, "_handle", None):
+            self.close()
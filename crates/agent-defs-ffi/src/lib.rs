@@ -0,0 +1,790 @@
+//! C-compatible bindings over the local definition cache.
+//!
+//! The bindings read the same cache the CLI fills, so scripts in other
+//! languages can list, search, fetch, and install definitions without
+//! shelling out, and sync them with the CLI's source providers. See
+//! `include/agent_defs.h` for the C declarations and
+//! `python/agent_defs.py` for a ctypes wrapper.
+//!
+//! Conventions:
+//! - Functions returning `*mut c_char` hand back a NUL-terminated UTF-8 JSON
+//!   document that must be released with [`agent_defs_string_free`].
+//! - On failure they return NULL; [`agent_defs_last_error`] then describes
+//!   the problem until the next call on the same thread.
+//! - Optional string arguments accept NULL.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+
+use agent_defs::{
+    Definition, DefinitionId, DefinitionKind, DefinitionSummary, HideRules, InstallLayout,
+    InstallRules, Policy, Source, SourceError,
+};
+use agent_defs_cli::catalog;
+use agent_defs_cli::config::{self, AppConfig, SigningConfig};
+use agent_defs_store::DefinitionStore;
+use serde_json::{Value, json};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An open catalog: one store per source cached in the database plus a
+/// runtime to drive their async APIs.
+pub struct AgentDefs {
+    runtime: tokio::runtime::Runtime,
+    stores: Vec<DefinitionStore>,
+    rules: InstallRules,
+    /// The user's `hide` rules, applied to lists and searches as the CLI does.
+    hidden: HideRules,
+    /// The config the handle was opened with, whose sources a sync fills.
+    config: AppConfig,
+    /// The database the stores read and a sync writes.
+    db_path: PathBuf,
+}
+
+/// The CLI's cache database, `definitions.db` in the user cache directory.
+fn default_db_path() -> Result<PathBuf, String> {
+    dirs::cache_dir()
+        .map(|dir| dir.join("agent-def-fetcher").join("definitions.db"))
+        .ok_or_else(|| "could not determine cache directory".to_owned())
+}
+
+/// The CLI's config file, `sources.toml` in the user config directory.
+fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("agent-def-fetcher").join("sources.toml"))
+}
+
+/// A store for every source cached in the database at `db_path`, refusing
+/// definitions synced under a policy other than `signing`.
+fn open_stores(db_path: &Path, signing: &SigningConfig) -> Result<Vec<DefinitionStore>, String> {
+    let labels = DefinitionStore::labels_in(db_path).map_err(|e| e.to_string())?;
+    labels
+        .into_iter()
+        .map(|label| {
+            let store = DefinitionStore::open(db_path, label).map_err(|e| e.to_string())?;
+            Ok(match signing.fingerprint() {
+                Some(fingerprint) => store.with_signing_policy(fingerprint),
+                None => store,
+            })
+        })
+        .collect()
+}
+
+impl AgentDefs {
+    /// Open the catalog at `db_path` (the CLI's cache when `None`). Installs
+    /// follow the `install_layout` in the config at `config_path` (the
+    /// user's config when `None`) and the org policy. Lists and searches
+    /// leave out what the `hidden` file beside that config hides, and
+    /// definitions synced under another `[signing]` policy can't be fetched.
+    pub fn open(db_path: Option<&Path>, config_path: Option<&Path>) -> Result<Self, String> {
+        let hidden = config_path
+            .map(Path::to_path_buf)
+            .or_else(default_config_path)
+            .and_then(|path| std::fs::read_to_string(path.with_file_name("hidden")).ok())
+            .map(|text| HideRules::parse(&text))
+            .unwrap_or_default();
+        let layout = match config_path {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).map_err(|e| {
+                    format!("failed to read config at {}: {e}", path.display())
+                })?;
+                InstallLayout::from_config(&text).unwrap_or_default()
+            }
+            None => default_config_path()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|text| InstallLayout::from_config(&text))
+                .unwrap_or_default(),
+        };
+        let db_path = match db_path {
+            Some(path) => path.to_path_buf(),
+            None => default_db_path()?,
+        };
+        if let Some(dir) = db_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        }
+        let config = match config_path {
+            Some(path) => config::read_config(path).map_err(|e| e.to_string())?,
+            None => config::load_config(),
+        };
+        let stores = open_stores(&db_path, &config.signing)?;
+        let policy = Policy::load().map_err(|e| e.to_string())?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("failed to start runtime: {e}"))?;
+
+        Ok(Self {
+            runtime,
+            stores,
+            rules: InstallRules { layout, policy },
+            hidden,
+            config,
+            db_path,
+        })
+    }
+
+    /// Labels of the cached sources, in alphabetical order.
+    pub fn labels(&self) -> Vec<String> {
+        self.stores
+            .iter()
+            .map(|store| store.label().to_owned())
+            .collect()
+    }
+
+    fn stores(&self, source: Option<&str>) -> impl Iterator<Item = &DefinitionStore> {
+        self.stores
+            .iter()
+            .filter(move |store| source.is_none_or(|label| store.label() == label))
+    }
+
+    /// Summaries across sources, optionally narrowed by kind and source label.
+    pub fn list(&self, kind: Option<&str>, source: Option<&str>) -> Result<Value, String> {
+        self.collect_summaries(kind, source, |store| self.runtime.block_on(store.list()))
+    }
+
+    /// Summaries whose name, description, or body match `query`.
+    pub fn search(
+        &self,
+        query: &str,
+        kind: Option<&str>,
+        source: Option<&str>,
+    ) -> Result<Value, String> {
+        self.collect_summaries(kind, source, |store| {
+            self.runtime.block_on(store.search(query))
+        })
+    }
+
+    fn collect_summaries(
+        &self,
+        kind: Option<&str>,
+        source: Option<&str>,
+        query: impl Fn(&dyn Source) -> Result<Vec<DefinitionSummary>, SourceError>,
+    ) -> Result<Value, String> {
+        let kind = kind.map(DefinitionKind::parse);
+        let mut summaries = Vec::new();
+        for store in self.stores(source) {
+            let found = query(store).map_err(|e| e.to_string())?;
+            summaries.extend(
+                found
+                    .iter()
+                    .filter(|s| kind.as_ref().is_none_or(|k| &s.kind == k))
+                    .filter(|s| !self.hidden.hides(s))
+                    .map(summary_json),
+            );
+        }
+        Ok(Value::Array(summaries))
+    }
+
+    /// The full definition, including its raw file content.
+    pub fn fetch(&self, id: &str, source: Option<&str>) -> Result<Value, String> {
+        self.fetch_definition(id, source)
+            .map(|def| definition_json(&def))
+    }
+
+    fn fetch_definition(&self, id: &str, source: Option<&str>) -> Result<Definition, String> {
+        let def_id = DefinitionId::new(id);
+        for store in self.stores(source) {
+            let result = self.runtime.block_on(async {
+                let mut def = store.fetch(&def_id).await?;
+                if def.raw.is_empty() {
+                    def.raw = store.fetch_raw(&def_id).await?;
+                }
+                Ok::<_, SourceError>(def)
+            });
+            match result {
+                Ok(def) => return Ok(def),
+                Err(SourceError::NotFound(_)) => continue,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        Err(format!("definition not found: {id}"))
+    }
+
+    /// Sync every enabled source in the config (or just `source`) into the
+    /// handle's database, then report when each one last synced and the
+    /// error from this sync, if it failed. Sources cached for the first
+    /// time are listed and searched from then on.
+    pub fn sync(&mut self, source: Option<&str>) -> Result<Value, String> {
+        let pairs = catalog::build_pairs(&self.config, &self.rules.policy, &self.db_path)
+            .map_err(|e| e.to_string())?;
+        let known: Vec<&str> = pairs.iter().map(|(store, _)| store.label()).collect();
+        if let Some(label) = source
+            && !known.contains(&label)
+        {
+            return Err(format!(
+                "no source named [{label}]; configured: {}",
+                known.join(", ")
+            ));
+        }
+
+        let mut results = Vec::new();
+        for (store, provider) in &pairs {
+            if source.is_some_and(|label| store.label() != label) {
+                continue;
+            }
+            let error = self
+                .runtime
+                .block_on(store.sync(provider.as_ref()))
+                .err()
+                .map(|e| e.to_string());
+            results.push(json!({
+                "label": store.label(),
+                "last_synced_at": store.last_synced_at().map_err(|e| e.to_string())?,
+                "error": error,
+            }));
+        }
+        self.stores = open_stores(&self.db_path, &self.config.signing)?;
+        Ok(json!({ "sources": results }))
+    }
+
+    /// Install a definition under `target` in the configured layout, if the
+    /// org policy allows it, filling in its template variables from
+    /// `values`, and report where it was written. Hooks run shell commands,
+    /// so they're refused unless `allow_hooks` says the caller has shown
+    /// them to the user.
+    pub fn install(
+        &self,
+        id: &str,
+        target: &Path,
+        source: Option<&str>,
+        values: &HashMap<String, String>,
+        allow_hooks: bool,
+    ) -> Result<Value, String> {
+        let def = self.fetch_definition(id, source)?;
+        if def.kind == DefinitionKind::Hook && !allow_hooks {
+            let commands = def.hook_commands();
+            let runs = if commands.is_empty() {
+                "may run shell commands".to_owned()
+            } else {
+                format!("runs shell commands: {}", commands.join("; "))
+            };
+            return Err(format!(
+                "{} is a hook that {runs}; pass allow_hooks to install it",
+                def.name
+            ));
+        }
+        self.rules.policy.check_install(&def).map_err(|e| e.to_string())?;
+        let path = self
+            .rules
+            .layout
+            .install(target, &def, values)
+            .map_err(|e| e.to_string())?;
+        Ok(json!({ "path": path.display().to_string() }))
+    }
+}
+
+fn summary_json(summary: &DefinitionSummary) -> Value {
+    json!({
+        "id": summary.id.as_str(),
+        "name": summary.name,
+        "description": summary.description,
+        "kind": summary.kind.to_string(),
+        "category": summary.category,
+        "source": summary.source_label,
+    })
+}
+
+fn definition_json(def: &Definition) -> Value {
+    json!({
+        "id": def.id.as_str(),
+        "name": def.name,
+        "description": def.description,
+        "kind": def.kind.to_string(),
+        "category": def.category,
+        "source": def.source_label,
+        "tools": def.tools,
+        "model": def.model,
+        "metadata": def.metadata,
+        "body": def.body,
+        "raw": def.raw,
+    })
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Run `f`, converting its JSON result into an owned C string and any error
+/// (or panic) into NULL plus a last-error message.
+fn json_call(f: impl FnOnce() -> Result<Value, String>) -> *mut c_char {
+    clear_last_error();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => CString::new(value.to_string())
+            .map(CString::into_raw)
+            .unwrap_or_else(|e| {
+                set_last_error(format!("result contained a NUL byte: {e}"));
+                std::ptr::null_mut()
+            }),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            std::ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("internal panic".to_owned());
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Borrow an optional C string argument.
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string that outlives the call.
+unsafe fn optional_str<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>, String> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| format!("{name} is not valid UTF-8"))
+}
+
+/// Borrow a required C string argument.
+///
+/// # Safety
+/// Same as [`optional_str`].
+unsafe fn required_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { optional_str(ptr, name) }?.ok_or_else(|| format!("{name} must not be NULL"))
+}
+
+/// Borrow the handle behind a pointer.
+///
+/// # Safety
+/// `handle` must be NULL or a live pointer from [`agent_defs_open`].
+unsafe fn handle_ref<'a>(handle: *const AgentDefs) -> Result<&'a AgentDefs, String> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle.as_ref() }.ok_or_else(|| "handle must not be NULL".to_owned())
+}
+
+/// Borrow the handle behind a pointer mutably.
+///
+/// # Safety
+/// As for [`handle_ref`], and no other reference to it may be live.
+unsafe fn handle_mut<'a>(handle: *mut AgentDefs) -> Result<&'a mut AgentDefs, String> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle.as_mut() }.ok_or_else(|| "handle must not be NULL".to_owned())
+}
+
+/// Open the catalog. Returns NULL on failure.
+///
+/// # Safety
+/// `db_path` and `config_path` must each be NULL or a valid NUL-terminated
+/// string. NULL selects the default cache database / user config.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_open(
+    db_path: *const c_char,
+    config_path: *const c_char,
+) -> *mut AgentDefs {
+    clear_last_error();
+    let result = catch_unwind(|| {
+        // SAFETY: guaranteed by the caller.
+        let db_path = unsafe { optional_str(db_path, "db_path") }?.map(PathBuf::from);
+        let config_path = unsafe { optional_str(config_path, "config_path") }?.map(PathBuf::from);
+        AgentDefs::open(db_path.as_deref(), config_path.as_deref())
+    });
+    match result {
+        Ok(Ok(handle)) => Box::into_raw(Box::new(handle)),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            std::ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("internal panic".to_owned());
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Close a handle returned by [`agent_defs_open`]. NULL is ignored.
+///
+/// # Safety
+/// `handle` must be NULL or a pointer from [`agent_defs_open`] that has not
+/// already been closed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_close(handle: *mut AgentDefs) {
+    if !handle.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// JSON array of summaries, optionally filtered by kind and source label.
+///
+/// # Safety
+/// `handle` must come from [`agent_defs_open`]; string arguments must be NULL
+/// or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_list(
+    handle: *const AgentDefs,
+    kind: *const c_char,
+    source: *const c_char,
+) -> *mut c_char {
+    json_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let defs = unsafe { handle_ref(handle) }?;
+        let kind = unsafe { optional_str(kind, "kind") }?;
+        let source = unsafe { optional_str(source, "source") }?;
+        defs.list(kind, source)
+    })
+}
+
+/// JSON array of summaries matching `query`.
+///
+/// # Safety
+/// As for [`agent_defs_list`]; `query` must not be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_search(
+    handle: *const AgentDefs,
+    query: *const c_char,
+    kind: *const c_char,
+    source: *const c_char,
+) -> *mut c_char {
+    json_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let defs = unsafe { handle_ref(handle) }?;
+        let query = unsafe { required_str(query, "query") }?;
+        let kind = unsafe { optional_str(kind, "kind") }?;
+        let source = unsafe { optional_str(source, "source") }?;
+        defs.search(query, kind, source)
+    })
+}
+
+/// JSON object for one definition, including `body` and `raw`.
+///
+/// # Safety
+/// As for [`agent_defs_list`]; `id` must not be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_fetch(
+    handle: *const AgentDefs,
+    id: *const c_char,
+    source: *const c_char,
+) -> *mut c_char {
+    json_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let defs = unsafe { handle_ref(handle) }?;
+        let id = unsafe { required_str(id, "id") }?;
+        let source = unsafe { optional_str(source, "source") }?;
+        defs.fetch(id, source)
+    })
+}
+
+/// Sync all sources, or only `source`, from the config the handle was
+/// opened with. Returns a JSON report with one entry per source synced;
+/// per-source failures appear in its `error` field.
+///
+/// # Safety
+/// As for [`agent_defs_list`], and no other call may be using the handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_sync(
+    handle: *mut AgentDefs,
+    source: *const c_char,
+) -> *mut c_char {
+    json_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let defs = unsafe { handle_mut(handle) }?;
+        let source = unsafe { optional_str(source, "source") }?;
+        defs.sync(source)
+    })
+}
+
+/// Install a definition under `target`. `vars` is a JSON object of
+/// template variable values, or NULL to use the defaults. Hooks are refused
+/// unless `allow_hooks` is true. Returns `{"path": ...}`.
+///
+/// # Safety
+/// As for [`agent_defs_list`]; `id` and `target` must not be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_install(
+    handle: *const AgentDefs,
+    id: *const c_char,
+    target: *const c_char,
+    source: *const c_char,
+    vars: *const c_char,
+    allow_hooks: bool,
+) -> *mut c_char {
+    json_call(|| {
+        // SAFETY: guaranteed by the caller.
+        let defs = unsafe { handle_ref(handle) }?;
+        let id = unsafe { required_str(id, "id") }?;
+        let target = unsafe { required_str(target, "target") }?;
+        let source = unsafe { optional_str(source, "source") }?;
+        let values = match unsafe { optional_str(vars, "vars") }? {
+            Some(json) => serde_json::from_str(json)
+                .map_err(|e| format!("vars must be a JSON object of strings: {e}"))?,
+            None => HashMap::new(),
+        };
+        defs.install(id, Path::new(target), source, &values, allow_hooks)
+    })
+}
+
+/// The error from the most recent failed call on this thread, or NULL.
+/// The pointer stays valid until the next call into this library.
+#[unsafe(no_mangle)]
+pub extern "C" fn agent_defs_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by this library. NULL is ignored.
+///
+/// # Safety
+/// `ptr` must be NULL or a string returned by this library that has not
+/// already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn agent_defs_string_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { CString::from_raw(ptr) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use agent_defs_store::DefinitionStore;
+
+    use super::*;
+
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("agent-defs-ffi-test-{name}"));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("sources.toml"), "install_layout = \"flat\"\n").unwrap();
+
+            let store = DefinitionStore::open(&dir.join("definitions.db"), "local").unwrap();
+            store
+                .upsert_definition(&definition(
+                    "agents/reviewer.md",
+                    "Reviewer",
+                    DefinitionKind::Agent,
+                ))
+                .unwrap();
+            store
+                .upsert_definition(&definition(
+                    "commands/deploy.md",
+                    "Deploy",
+                    DefinitionKind::Command,
+                ))
+                .unwrap();
+
+            Self { dir }
+        }
+
+        fn open(&self) -> AgentDefs {
+            AgentDefs::open(
+                Some(&self.dir.join("definitions.db")),
+                Some(&self.dir.join("sources.toml")),
+            )
+            .unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn definition(id: &str, name: &str, kind: DefinitionKind) -> Definition {
+        Definition {
+            name: name.to_owned(),
+            description: Some(format!("{name} description")),
+            kind,
+            source_label: "local".to_owned(),
+            body: format!("You are {name}."),
+            tools: vec!["Read".to_owned()],
+            raw: format!("---\nname: {name}\n---\nYou are {name}."),
+            ..agent_defs::test_support::definition(id)
+        }
+    }
+
+    #[test]
+    fn list_filters_by_kind() {
+        let fixture = Fixture::new("list");
+        let defs = fixture.open();
+
+        assert_eq!(defs.labels(), vec!["local"]);
+        assert_eq!(defs.list(None, None).unwrap().as_array().unwrap().len(), 2);
+
+        let agents = defs.list(Some("agent"), None).unwrap();
+        assert_eq!(agents.as_array().unwrap().len(), 1);
+        assert_eq!(agents[0]["name"], "Reviewer");
+        assert_eq!(agents[0]["source"], "local");
+
+        let none = defs.list(None, Some("elsewhere")).unwrap();
+        assert!(none.as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn hidden_definitions_are_left_out() {
+        let fixture = Fixture::new("hidden");
+        std::fs::write(fixture.dir.join("hidden"), "id:commands/deploy.md\n").unwrap();
+        let defs = fixture.open();
+
+        let listed = defs.list(None, None).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["name"], "Reviewer");
+        assert!(defs.search("Deploy", None, None).unwrap().as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fetch_includes_raw_content() {
+        let fixture = Fixture::new("fetch");
+        let defs = fixture.open();
+
+        let def = defs.fetch("agents/reviewer.md", None).unwrap();
+        assert_eq!(def["body"], "You are Reviewer.");
+        assert!(def["raw"].as_str().unwrap().starts_with("---"));
+        assert_eq!(def["tools"][0], "Read");
+
+        let err = defs.fetch("agents/missing.md", None).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn install_writes_definition() {
+        let fixture = Fixture::new("install");
+        let defs = fixture.open();
+        let target = fixture.dir.join("project");
+
+        let result = defs
+            .install("agents/reviewer.md", &target, None, &HashMap::new(), false)
+            .unwrap();
+        let path = PathBuf::from(result["path"].as_str().unwrap());
+        assert_eq!(path, target.join(".claude/agents/Reviewer.md"));
+        assert!(
+            std::fs::read_to_string(path)
+                .unwrap()
+                .contains("name: Reviewer")
+        );
+    }
+
+    #[test]
+    fn install_fills_in_template_variables() {
+        let fixture = Fixture::new("install-vars");
+        let mut templated = definition("agents/templated.md", "Templated", DefinitionKind::Agent);
+        templated.raw = "---\nname: Templated\nvars: [PROJECT]\n---\nWork on {{PROJECT}}.".into();
+        DefinitionStore::open(&fixture.dir.join("definitions.db"), "local")
+            .unwrap()
+            .upsert_definition(&templated)
+            .unwrap();
+        let defs = fixture.open();
+        let target = fixture.dir.join("project");
+
+        let err = defs
+            .install("agents/templated.md", &target, None, &HashMap::new(), false)
+            .unwrap_err();
+        assert!(err.contains("PROJECT"), "{err}");
+
+        let values = HashMap::from([("PROJECT".to_owned(), "crate".to_owned())]);
+        let result = defs
+            .install("agents/templated.md", &target, None, &values, false)
+            .unwrap();
+        let written = std::fs::read_to_string(result["path"].as_str().unwrap()).unwrap();
+        assert!(written.ends_with("Work on crate."), "{written}");
+    }
+
+    #[test]
+    fn hooks_need_allow_hooks() {
+        let fixture = Fixture::new("install-hook");
+        let mut hook = definition("hooks/format.md", "Format", DefinitionKind::Hook);
+        hook.body = "```sh\ncargo fmt\n```".into();
+        DefinitionStore::open(&fixture.dir.join("definitions.db"), "local")
+            .unwrap()
+            .upsert_definition(&hook)
+            .unwrap();
+        let defs = fixture.open();
+        let target = fixture.dir.join("project");
+
+        let err = defs
+            .install("hooks/format.md", &target, None, &HashMap::new(), false)
+            .unwrap_err();
+        assert!(err.contains("cargo fmt"), "{err}");
+        assert!(!target.exists());
+
+        defs.install("hooks/format.md", &target, None, &HashMap::new(), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn sync_fills_the_handle_database_and_reports_each_source() {
+        let fixture = Fixture::new("sync");
+        let missing = fixture.dir.join("missing.json");
+        std::fs::write(
+            fixture.dir.join("sources.toml"),
+            format!(
+                "[[sources]]\nlabel = \"demo\"\ntype = \"demo\"\n\n\
+                 [[sources]]\nlabel = \"team\"\ntype = \"catalog-snapshot\"\nurl = {:?}\n",
+                missing.display().to_string()
+            ),
+        )
+        .unwrap();
+        let mut defs = fixture.open();
+        assert_eq!(defs.labels(), vec!["local"]);
+
+        let report = defs.sync(None).unwrap();
+        let sources = report["sources"].as_array().unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0]["label"], "demo");
+        assert!(sources[0]["error"].is_null());
+        assert!(sources[0]["last_synced_at"].is_u64());
+        assert_eq!(sources[1]["label"], "team");
+        assert!(sources[1]["error"].is_string());
+
+        // The newly cached source is read without reopening the handle.
+        assert_eq!(defs.labels(), vec!["demo", "local"]);
+        assert!(!defs.list(None, Some("demo")).unwrap().as_array().unwrap().is_empty());
+
+        let err = defs.sync(Some("elsewhere")).unwrap_err();
+        assert!(err.starts_with("no source named [elsewhere]"), "{err}");
+    }
+
+    #[test]
+    fn extern_calls_round_trip_json_and_errors() {
+        let fixture = Fixture::new("extern");
+        let db = CString::new(fixture.dir.join("definitions.db").to_str().unwrap()).unwrap();
+        let cfg = CString::new(fixture.dir.join("sources.toml").to_str().unwrap()).unwrap();
+        let query = CString::new("deploy").unwrap();
+
+        unsafe {
+            let handle = agent_defs_open(db.as_ptr(), cfg.as_ptr());
+            assert!(!handle.is_null());
+
+            let out = agent_defs_search(handle, query.as_ptr(), std::ptr::null(), std::ptr::null());
+            assert!(!out.is_null());
+            let parsed: Value =
+                serde_json::from_str(CStr::from_ptr(out).to_str().unwrap()).unwrap();
+            assert_eq!(parsed[0]["id"], "commands/deploy.md");
+            agent_defs_string_free(out);
+
+            let out = agent_defs_fetch(handle, std::ptr::null(), std::ptr::null());
+            assert!(out.is_null());
+            let err = CStr::from_ptr(agent_defs_last_error()).to_str().unwrap();
+            assert_eq!(err, "id must not be NULL");
+
+            agent_defs_close(handle);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Unpacking a repository tarball the size of the synthetic corpus.
+//!
+//! Run with `cargo bench -p agent-defs-github --bench tarball`.
+
+use agent_defs::fixtures::{self, BENCH_DEFINITIONS};
+use agent_defs_github::{TarballClient, TarballLimits};
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+/// The corpus packed the way GitHub serves a repo: gzipped, under one
+/// `owner-repo-sha/` root directory.
+fn tarball() -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for file in fixtures::corpus(BENCH_DEFINITIONS) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                format!("acme-agents-abc123/{}", file.relative_path),
+                file.content.as_bytes(),
+            )
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+fn extract(c: &mut Criterion) {
+    let bytes = tarball();
+    let limits = TarballLimits::default();
+
+    let mut group = c.benchmark_group("tarball");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("extract", |b| {
+        b.iter(|| TarballClient::extract_files(&bytes, &limits).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, extract);
+criterion_main!(benches);
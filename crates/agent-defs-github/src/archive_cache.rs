@@ -0,0 +1,128 @@
+//! Repository archives kept after they are downloaded, so a later sync can
+//! rebuild the local cache from them without the network: after a schema
+//! change, say, or to apply new parsing rules to what was already fetched.
+
+use std::path::PathBuf;
+
+use agent_defs::SyncError;
+
+/// A directory of downloaded archives, one per repository branch, kept
+/// under a total size.
+#[derive(Debug, Clone)]
+pub struct ArchiveCache {
+    dir: PathBuf,
+    budget_bytes: u64,
+}
+
+impl ArchiveCache {
+    /// Keep archives in `dir`, dropping the oldest once together they
+    /// pass `budget_bytes`.
+    pub fn new(dir: impl Into<PathBuf>, budget_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            budget_bytes,
+        }
+    }
+
+    fn path(&self, owner: &str, repo: &str, branch: &str) -> PathBuf {
+        let flat = |part: &str| -> String {
+            part.chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        };
+        self.dir.join(format!(
+            "{}--{}--{}.tar.gz",
+            flat(owner),
+            flat(repo),
+            flat(branch)
+        ))
+    }
+
+    /// The archive last kept for `owner/repo` at `branch`.
+    pub fn load(&self, owner: &str, repo: &str, branch: &str) -> Result<Vec<u8>, SyncError> {
+        let path = self.path(owner, repo, branch);
+        std::fs::read(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => SyncError::Other(format!(
+                "no archive of {owner}/{repo}@{branch} is cached; sync it online first"
+            )),
+            _ => SyncError::Io(format!("failed to read {}: {e}", path.display())),
+        })
+    }
+
+    /// Keep `bytes` as the archive of `owner/repo` at `branch`, replacing
+    /// the one before, then drop the oldest archives until the rest fit
+    /// the budget. An archive bigger than the whole budget isn't kept.
+    pub fn save(&self, owner: &str, repo: &str, branch: &str, bytes: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(owner, repo, branch), bytes)?;
+        self.prune()
+    }
+
+    fn prune(&self) -> std::io::Result<()> {
+        let mut archives = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                archives.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        // Newest first, so the oldest are the ones past the budget.
+        archives.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+        let mut total = 0u64;
+        for (_, len, path) in archives {
+            total += len;
+            if total > self.budget_bytes {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str, budget_bytes: u64) -> ArchiveCache {
+        let dir = std::env::temp_dir().join(format!("agent-defs-archives-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        ArchiveCache::new(dir, budget_bytes)
+    }
+
+    #[test]
+    fn saved_archives_load_back() {
+        let cache = cache("round-trip", 1024);
+        cache
+            .save("acme", "agents", "feature/x", b"archive")
+            .unwrap();
+
+        assert_eq!(
+            cache.load("acme", "agents", "feature/x").unwrap(),
+            b"archive"
+        );
+        let missing = cache.load("acme", "agents", "main").unwrap_err();
+        assert!(matches!(missing, SyncError::Other(msg) if msg.contains("sync it online first")));
+    }
+
+    #[test]
+    fn the_oldest_archives_go_first() {
+        let cache = cache("budget", 10);
+        cache.save("acme", "old", "main", b"123456").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.save("acme", "new", "main", b"123456").unwrap();
+
+        assert!(cache.load("acme", "old", "main").is_err());
+        assert!(cache.load("acme", "new", "main").is_ok());
+
+        cache.save("acme", "huge", "main", &[0; 11]).unwrap();
+        assert!(cache.load("acme", "huge", "main").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use base64::Engine;
 use serde::Deserialize;
 
 /// Response from GitHub's Contents API.
@@ -9,3 +10,22 @@ pub struct ContentResponse {
     pub content: Option<String>,
     pub encoding: Option<String>,
 }
+
+impl ContentResponse {
+    /// The file's content, decoded from the base64 GitHub sends.
+    pub fn text(&self) -> Result<String, String> {
+        let encoded = self
+            .content
+            .as_deref()
+            .ok_or_else(|| "no content in response".to_owned())?;
+
+        // GitHub returns base64 with newlines embedded
+        let cleaned: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&cleaned)
+            .map_err(|e| format!("base64 decode failed: {e}"))?;
+
+        String::from_utf8(bytes).map_err(|e| format!("invalid UTF-8: {e}"))
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use agent_defs::SyncError;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::http;
 
 /// A file from a GitHub Gist.
 #[derive(Debug, Clone)]
@@ -12,7 +14,7 @@ pub struct GistFile {
     pub content: String,
 }
 
-/// HTTP client for fetching GitHub Gists.
+/// HTTP client for fetching and creating GitHub Gists.
 ///
 /// This is a pure transport utility — it fetches gist files without
 /// applying any path transformation or layout interpretation.
@@ -25,74 +27,228 @@ pub struct GistClient {
 impl GistClient {
     pub fn new(token: Option<String>, api_base_url: Option<String>) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: http::default_client(),
             token,
             api_base_url,
         }
     }
 
+    /// Send requests through `client`, as built by
+    /// [`HttpOptions::client`](crate::HttpOptions::client).
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Call the API at `url` rather than api.github.com.
+    pub fn with_api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.api_base_url = Some(url.into().trim_end_matches('/').to_owned());
+        self
+    }
+
     fn api_base(&self) -> &str {
         self.api_base_url
             .as_deref()
-            .unwrap_or("https://api.github.com")
+            .unwrap_or(http::GITHUB_API_URL)
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let req = self
+            .client
+            .get(url)
+            .header("User-Agent", "agent-def-fetcher");
+        match &self.token {
+            Some(token) => req.header("Authorization", format!("Bearer {token}")),
+            None => req,
+        }
     }
 
     /// Fetch all files from a GitHub Gist.
     ///
-    /// Returns all files that have content. Files without content (truncated
-    /// large files) are silently skipped.
+    /// Follows the `Link` header across pages of the response, and
+    /// downloads from `raw_url` any file the API marks as truncated, so
+    /// large gists come back whole. Files with neither content nor a raw
+    /// URL are silently skipped.
     pub async fn fetch(&self, gist_id: &str) -> Result<Vec<GistFile>, SyncError> {
-        let url = format!("{}/gists/{}", self.api_base(), gist_id);
+        let mut next = Some(format!("{}/gists/{}", self.api_base(), gist_id));
+        let mut entries: Vec<GistFileEntry> = Vec::new();
+        let mut truncated = false;
+        let mut pages = 0;
+
+        while let Some(url) = next.take() {
+            pages += 1;
+            if pages > MAX_PAGES {
+                return Err(SyncError::Network(format!(
+                    "gist {gist_id} has more than {MAX_PAGES} pages of files"
+                )));
+            }
+
+            let response = self
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| http::sync_error("gist fetch failed", e))?;
+
+            if !response.status().is_success() {
+                return Err(http::sync_status_error("gist fetch", &response));
+            }
+
+            next = next_page(response.headers());
+            let gist: GistResponse = response
+                .json()
+                .await
+                .map_err(|e| SyncError::Extraction(format!("failed to parse gist JSON: {e}")))?;
+
+            truncated = gist.truncated;
+            for entry in gist.files.into_values() {
+                if !entries.iter().any(|e| e.filename == entry.filename) {
+                    entries.push(entry);
+                }
+            }
+        }
 
-        let mut req = self
-            .client
-            .get(&url)
-            .header("User-Agent", "agent-def-fetcher");
+        // The last page still being truncated means GitHub has files it
+        // won't list; a partial sync would drop them from the catalog.
+        if truncated {
+            return Err(SyncError::Extraction(format!(
+                "gist {gist_id} has more files than the GitHub API lists"
+            )));
+        }
 
-        if let Some(token) = &self.token {
-            req = req.header("Authorization", format!("Bearer {token}"));
+        let mut files = Vec::new();
+        for entry in entries {
+            let content = match (entry.content, entry.raw_url) {
+                (Some(content), _) if !entry.truncated => content,
+                (_, Some(raw_url)) => self.fetch_raw(&entry.filename, &raw_url).await?,
+                (Some(_), None) => {
+                    return Err(SyncError::Extraction(format!(
+                        "gist file {} is truncated and has no raw URL",
+                        entry.filename
+                    )));
+                }
+                (None, None) => continue,
+            };
+            files.push(GistFile {
+                filename: entry.filename,
+                content,
+            });
         }
+        Ok(files)
+    }
 
-        let response = req
+    async fn fetch_raw(&self, filename: &str, raw_url: &str) -> Result<String, SyncError> {
+        let response = self
+            .get(raw_url)
             .send()
             .await
-            .map_err(|e| SyncError::Network(format!("gist fetch failed: {e}")))?;
+            .map_err(|e| http::sync_error(&format!("gist file {filename} fetch failed"), e))?;
 
         if !response.status().is_success() {
-            return Err(SyncError::Network(format!(
-                "gist fetch returned HTTP {}",
-                response.status()
-            )));
+            let context = format!("gist file {filename}");
+            return Err(http::sync_status_error(&context, &response));
+        }
+
+        response
+            .text()
+            .await
+            .map_err(|e| http::sync_error(&format!("failed to read gist file {filename}"), e))
+    }
+
+    /// Create a secret gist holding `files` and return its web URL.
+    ///
+    /// GitHub only lets authenticated users create gists, so this fails
+    /// up front when the client has no token.
+    pub async fn create(
+        &self,
+        description: &str,
+        files: &[GistFile],
+    ) -> Result<String, SyncError> {
+        let Some(token) = &self.token else {
+            return Err(SyncError::Other(
+                "creating a gist needs a GitHub token (set GITHUB_TOKEN)".into(),
+            ));
+        };
+
+        let body = CreateGistRequest {
+            description,
+            public: false,
+            files: files
+                .iter()
+                .map(|f| (f.filename.as_str(), CreateGistFile { content: &f.content }))
+                .collect(),
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/gists", self.api_base()))
+            .header("User-Agent", "agent-def-fetcher")
+            .header("Authorization", format!("Bearer {token}"))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| http::sync_error("gist create failed", e))?;
+
+        if !response.status().is_success() {
+            return Err(http::sync_status_error("gist create", &response));
         }
 
-        let gist: GistResponse = response
+        let created: CreatedGist = response
             .json()
             .await
             .map_err(|e| SyncError::Extraction(format!("failed to parse gist JSON: {e}")))?;
-
-        Ok(gist
-            .files
-            .into_values()
-            .filter_map(|f| {
-                let content = f.content?;
-                Some(GistFile {
-                    filename: f.filename,
-                    content,
-                })
-            })
-            .collect())
+        Ok(created.html_url)
     }
 }
 
+/// How many pages of one gist to follow before giving up, as a guard
+/// against a server that keeps linking onward.
+const MAX_PAGES: usize = 100;
+
+/// The `rel="next"` URL from a `Link` header, if there is one.
+fn next_page(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_owned())
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct CreateGistRequest<'a> {
+    description: &'a str,
+    public: bool,
+    files: HashMap<&'a str, CreateGistFile<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateGistFile<'a> {
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedGist {
+    html_url: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GistResponse {
     files: HashMap<String, GistFileEntry>,
+    /// Set when the gist has more files than the response includes.
+    #[serde(default)]
+    truncated: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct GistFileEntry {
     filename: String,
     content: Option<String>,
+    /// Set when `content` stops short of the whole file.
+    #[serde(default)]
+    truncated: bool,
+    raw_url: Option<String>,
 }
 
 #[cfg(test)]
@@ -195,4 +351,165 @@ mod tests {
         assert!(filenames.contains(&"file1.md"));
         assert!(filenames.contains(&"file2.md"));
     }
+
+    #[tokio::test]
+    async fn gist_downloads_truncated_files_from_raw_url() {
+        let server = start_mock_server().await;
+        let body = serde_json::json!({
+            "files": {
+                "big.md": {
+                    "filename": "big.md",
+                    "content": "the first meg",
+                    "truncated": true,
+                    "raw_url": format!("{}/raw/big.md", server.uri()),
+                },
+                "small.md": { "filename": "small.md", "content": "small", "truncated": false },
+            }
+        });
+
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/gists/abc123"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/raw/big.md"))
+            .and(wiremock::matchers::header("Authorization", "Bearer secret"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_string("the whole file"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GistClient::new(Some("secret".into()), Some(server.uri()));
+
+        let mut files = client.fetch("abc123").await.unwrap();
+        files.sort_by(|a, b| a.filename.cmp(&b.filename));
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].content, "the whole file");
+        assert_eq!(files[1].content, "small");
+    }
+
+    #[tokio::test]
+    async fn gist_follows_next_page_links() {
+        let server = start_mock_server().await;
+        let next = format!("<{}/gists/abc123?page=2>; rel=\"next\"", server.uri());
+
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/gists/abc123"))
+            .and(wiremock::matchers::query_param("page", "2"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_string(gist_json(&[("second.md", Some("two"))])),
+            )
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/gists/abc123"))
+            .and(wiremock::matchers::query_param_is_missing("page"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .insert_header("Link", next.as_str())
+                    .set_body_string(gist_json(&[("first.md", Some("one"))])),
+            )
+            .mount(&server)
+            .await;
+
+        let client = GistClient::new(None, Some(server.uri()));
+
+        let files = client.fetch("abc123").await.unwrap();
+        let filenames: Vec<&str> = files.iter().map(|f| f.filename.as_str()).collect();
+        assert_eq!(files.len(), 2);
+        assert!(filenames.contains(&"first.md"));
+        assert!(filenames.contains(&"second.md"));
+    }
+
+    #[tokio::test]
+    async fn gist_with_unlisted_files_is_an_error() {
+        let server = start_mock_server().await;
+        let body = serde_json::json!({
+            "truncated": true,
+            "files": { "a.md": { "filename": "a.md", "content": "a" } }
+        });
+
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/gists/abc123"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let client = GistClient::new(None, Some(server.uri()));
+
+        let result = client.fetch("abc123").await;
+        assert!(
+            matches!(result, Err(SyncError::Extraction(msg)) if msg.contains("more files"))
+        );
+    }
+
+    #[test]
+    fn next_page_reads_the_link_header() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::LINK,
+            concat!(
+                "<https://api.github.com/x?page=3>; rel=\"next\", ",
+                "<https://api.github.com/x?page=9>; rel=\"last\"",
+            )
+            .parse()
+            .unwrap(),
+        );
+        assert_eq!(next_page(&headers).as_deref(), Some("https://api.github.com/x?page=3"));
+        assert_eq!(next_page(&reqwest::header::HeaderMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn create_posts_secret_gist_and_returns_url() {
+        let server = start_mock_server().await;
+
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/gists"))
+            .and(wiremock::matchers::header("Authorization", "Bearer secret"))
+            .and(wiremock::matchers::body_json(serde_json::json!({
+                "description": "reviewer agent",
+                "public": false,
+                "files": { "reviewer.md": { "content": "---\nname: reviewer\n---\n" } }
+            })))
+            .respond_with(
+                wiremock::ResponseTemplate::new(201)
+                    .set_body_string(r#"{"html_url": "https://gist.github.com/abc123"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GistClient::new(Some("secret".into()), Some(server.uri()));
+        let files = [GistFile {
+            filename: "reviewer.md".into(),
+            content: "---\nname: reviewer\n---\n".into(),
+        }];
+
+        let url = client.create("reviewer agent", &files).await.unwrap();
+        assert_eq!(url, "https://gist.github.com/abc123");
+    }
+
+    #[tokio::test]
+    async fn create_requires_token() {
+        let client = GistClient::new(None, Some("http://127.0.0.1:9".into()));
+        let result = client.create("x", &[]).await;
+        assert!(matches!(result, Err(SyncError::Other(msg)) if msg.contains("GITHUB_TOKEN")));
+    }
+
+    #[tokio::test]
+    async fn create_reports_http_errors() {
+        let server = start_mock_server().await;
+
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/gists"))
+            .respond_with(wiremock::ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = GistClient::new(Some("bad".into()), Some(server.uri()));
+        let result = client.create("x", &[]).await;
+        assert!(matches!(result, Err(SyncError::Unauthorized(msg)) if msg.contains("401")));
+    }
 }
@@ -0,0 +1,334 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use agent_defs::{SourceError, SyncError};
+use serde::{Deserialize, Serialize};
+
+/// The public GitHub REST API, used unless a client is given another root.
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Network settings shared by every client, for networks that route
+/// through a proxy or intercept TLS with their own certificate authority.
+///
+/// Without an explicit `proxy`, the standard `HTTPS_PROXY`, `HTTP_PROXY`,
+/// `ALL_PROXY` and `NO_PROXY` environment variables apply.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpOptions {
+    /// Proxy URL for every request, such as `http://proxy.corp:3128`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM file of extra CA certificates to trust alongside the system's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    /// Seconds to wait for a connection to open.
+    pub connect_timeout_secs: u64,
+    /// Seconds to wait for each read from an open connection, so a stalled
+    /// download fails instead of hanging.
+    pub read_timeout_secs: u64,
+    /// Root of the GitHub REST API, such as
+    /// `https://github.example.com/api/v3` for GitHub Enterprise Server.
+    /// Defaults to `https://api.github.com`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// Fastest to download repository archives, in KiB per second, to
+    /// leave room for everything else on a slow or shared link. Unlimited
+    /// when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_kib_per_sec: Option<u64>,
+    /// Accept any certificate. Only ever set from the command line.
+    #[serde(skip)]
+    pub insecure: bool,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            ca_bundle: None,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            api_url: None,
+            max_kib_per_sec: None,
+            insecure: false,
+        }
+    }
+}
+
+impl HttpOptions {
+    /// [`max_kib_per_sec`](Self::max_kib_per_sec) in bytes.
+    pub fn max_bytes_per_sec(&self) -> Option<u64> {
+        self.max_kib_per_sec.map(|kib| kib.saturating_mul(1024))
+    }
+
+    /// Build a client with these settings. Fails if the proxy URL doesn't
+    /// parse or the CA bundle can't be read.
+    pub fn client(&self) -> Result<reqwest::Client, SyncError> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(self.read_timeout_secs));
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| SyncError::Other(format!("invalid proxy `{proxy}`: {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path).map_err(|e| {
+                SyncError::Io(format!("failed to read CA bundle {}: {e}", path.display()))
+            })?;
+            let certs = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+                SyncError::Other(format!("invalid CA bundle {}: {e}", path.display()))
+            })?;
+            if certs.is_empty() {
+                return Err(SyncError::Other(format!(
+                    "CA bundle {} holds no certificates",
+                    path.display()
+                )));
+            }
+            builder = builder.tls_certs_merge(certs);
+        }
+
+        if self.insecure {
+            builder = builder.tls_danger_accept_invalid_certs(true);
+        }
+
+        builder
+            .build()
+            .map_err(|e| SyncError::Other(format!("failed to set up HTTP client: {e}")))
+    }
+}
+
+/// Paces a download to an average byte rate, sleeping whenever it gets
+/// ahead.
+pub(crate) struct Throttle {
+    bytes_per_sec: u64,
+    started: Instant,
+    read: u64,
+}
+
+impl Throttle {
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: Instant::now(),
+            read: 0,
+        }
+    }
+
+    /// Count `bytes` more as read, and wait until reading them fits the rate.
+    pub(crate) async fn take(&mut self, bytes: usize) {
+        self.read += bytes as u64;
+        let due = Duration::from_secs_f64(self.read as f64 / self.bytes_per_sec as f64);
+        let elapsed = self.started.elapsed();
+        if due > elapsed {
+            tokio::time::sleep(due - elapsed).await;
+        }
+    }
+}
+
+/// The client the GitHub clients start with: default timeouts and nothing
+/// else, which can't fail to build.
+pub(crate) fn default_client() -> reqwest::Client {
+    let defaults = HttpOptions::default();
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(defaults.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(defaults.read_timeout_secs))
+        .build()
+        .unwrap_or_default()
+}
+
+/// A failed request as a [`SourceError`], keeping timeouts apart.
+pub(crate) fn source_error(e: reqwest::Error) -> SourceError {
+    if e.is_timeout() {
+        SourceError::Timeout(e.to_string())
+    } else {
+        SourceError::Network(e.to_string())
+    }
+}
+
+/// A failed request as a [`SyncError`], keeping timeouts apart.
+pub(crate) fn sync_error(context: &str, e: reqwest::Error) -> SyncError {
+    if e.is_timeout() {
+        SyncError::Timeout(format!("{context}: {e}"))
+    } else {
+        SyncError::Network(format!("{context}: {e}"))
+    }
+}
+
+/// Why GitHub turned a request down, going by its status and rate-limit
+/// headers.
+pub(crate) enum Refusal {
+    NotFound,
+    Unauthorized,
+    RateLimited { reset_at: Option<u64> },
+    Other,
+}
+
+pub(crate) fn refusal(response: &reqwest::Response) -> Refusal {
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    let retry_after = header("retry-after");
+    // GitHub answers an exhausted rate limit with 403, or 429 for the
+    // secondary limits, and says when it resets.
+    let limited = response.status().as_u16() == 429
+        || header("x-ratelimit-remaining") == Some(0)
+        || retry_after.is_some();
+    match response.status().as_u16() {
+        403 | 429 if limited => Refusal::RateLimited {
+            reset_at: header("x-ratelimit-reset").or_else(|| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .ok()?;
+                Some(now.as_secs() + retry_after?)
+            }),
+        },
+        401 | 403 => Refusal::Unauthorized,
+        404 => Refusal::NotFound,
+        _ => Refusal::Other,
+    }
+}
+
+impl Refusal {
+    pub(crate) fn sync_error(self, message: String) -> SyncError {
+        match self {
+            Self::NotFound => SyncError::NotFound(message),
+            Self::Unauthorized => SyncError::Unauthorized(message),
+            Self::RateLimited { reset_at } => SyncError::RateLimited { message, reset_at },
+            Self::Other => SyncError::Network(message),
+        }
+    }
+
+    pub(crate) fn source_error(self, message: String) -> SourceError {
+        match self {
+            Self::Unauthorized => SourceError::Unauthorized(message),
+            Self::RateLimited { reset_at } => SourceError::RateLimited { message, reset_at },
+            Self::NotFound | Self::Other => SourceError::Network(message),
+        }
+    }
+}
+
+/// An unsuccessful response as a [`SyncError`], telling a missing
+/// resource, refused credentials, and a rate limit apart from the rest.
+pub(crate) fn sync_status_error(context: &str, response: &reqwest::Response) -> SyncError {
+    refusal(response).sync_error(format!("{context} returned HTTP {}", response.status()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_build_a_client() {
+        assert!(HttpOptions::default().client().is_ok());
+    }
+
+    #[test]
+    fn bad_proxy_and_missing_bundle_are_errors() {
+        let proxy = HttpOptions {
+            proxy: Some("not a url".into()),
+            ..HttpOptions::default()
+        };
+        assert!(matches!(proxy.client(), Err(SyncError::Other(msg)) if msg.contains("proxy")));
+
+        let bundle = HttpOptions {
+            ca_bundle: Some(PathBuf::from("/nonexistent/agent-defs-ca.pem")),
+            ..HttpOptions::default()
+        };
+        assert!(matches!(bundle.client(), Err(SyncError::Io(msg)) if msg.contains("CA bundle")));
+    }
+
+    #[tokio::test]
+    async fn slow_responses_time_out() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_delay(Duration::from_secs(3)),
+            )
+            .mount(&server)
+            .await;
+
+        let options = HttpOptions {
+            read_timeout_secs: 1,
+            ..HttpOptions::default()
+        };
+        let error = options.client().unwrap().get(server.uri()).send().await.unwrap_err();
+        assert!(matches!(source_error(error), SourceError::Timeout(_)));
+    }
+
+    #[tokio::test]
+    async fn refusals_are_told_apart() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = wiremock::MockServer::start().await;
+        let limited = ResponseTemplate::new(403)
+            .insert_header("x-ratelimit-remaining", "0")
+            .insert_header("x-ratelimit-reset", "1700000000");
+        Mock::given(path("/limited")).respond_with(limited).mount(&server).await;
+        Mock::given(path("/forbidden"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+        Mock::given(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = default_client();
+        let get = |route: &'static str| {
+            let request = client.get(format!("{}{route}", server.uri()));
+            async move { request.send().await.unwrap() }
+        };
+        assert!(matches!(
+            sync_status_error("fetch", &get("/limited").await),
+            SyncError::RateLimited { reset_at: Some(1_700_000_000), .. }
+        ));
+        assert!(matches!(
+            sync_status_error("fetch", &get("/forbidden").await),
+            SyncError::Unauthorized(msg) if msg == "fetch returned HTTP 403 Forbidden"
+        ));
+        assert!(matches!(
+            sync_status_error("fetch", &get("/missing").await),
+            SyncError::NotFound(_)
+        ));
+        assert!(matches!(
+            refusal(&get("/missing").await).source_error("gone".into()),
+            SourceError::Network(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn throttles_hold_reads_to_their_rate() {
+        let started = Instant::now();
+        let mut throttle = Throttle::new(1000);
+        throttle.take(100).await;
+        throttle.take(100).await;
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        let options = HttpOptions {
+            max_kib_per_sec: Some(64),
+            ..HttpOptions::default()
+        };
+        assert_eq!(options.max_bytes_per_sec(), Some(65_536));
+    }
+
+    #[test]
+    fn bundle_without_certificates_is_an_error() {
+        let path = std::env::temp_dir().join("agent-defs-test-empty-ca.pem");
+        std::fs::write(&path, "not a certificate\n").unwrap();
+        let options = HttpOptions {
+            ca_bundle: Some(path.clone()),
+            ..HttpOptions::default()
+        };
+        let result = options.client();
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+}
@@ -1,9 +1,14 @@
+pub mod archive_cache;
 pub mod content;
 pub mod gist;
+pub mod http;
 pub mod repo_source;
 pub mod tarball;
 pub mod tree;
 
+pub use archive_cache::ArchiveCache;
 pub use gist::{GistClient, GistFile};
+pub use http::{GITHUB_API_URL, HttpOptions};
 pub use repo_source::{GitHubRepoSource, GitHubRepoSourceConfig};
-pub use tarball::{RepoFile, TarballClient};
+pub use tarball::{ArchiveMode, RepoFile, Tarball, TarballClient, TarballLimits};
+pub use tree::TreeClient;
@@ -1,10 +1,9 @@
-use base64::Engine;
-
 use agent_defs::{
     Definition, DefinitionId, DefinitionKind, DefinitionSummary, Source, SourceError,
 };
 
 use crate::content::ContentResponse;
+use crate::http;
 use crate::tree::TreeResponse;
 
 /// Configuration for a GitHub repository source.
@@ -13,7 +12,10 @@ pub struct GitHubRepoSourceConfig {
     pub owner: String,
     pub repo: String,
     pub branch: String,
-    pub base_path: Option<String>,
+    /// Directories within the repo that hold definitions, e.g. `claude` and
+    /// `tools/agents`. Empty means the whole repo. When two roots hold the
+    /// same relative path, the one listed first wins.
+    pub base_paths: Vec<String>,
     pub token: Option<String>,
     pub api_base_url: Option<String>,
 }
@@ -28,15 +30,22 @@ impl GitHubRepoSource {
     pub fn new(config: GitHubRepoSourceConfig) -> Self {
         Self {
             config,
-            client: reqwest::Client::new(),
+            client: http::default_client(),
         }
     }
 
+    /// Send requests through `client`, as built by
+    /// [`HttpOptions::client`](crate::HttpOptions::client).
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
     fn api_base(&self) -> &str {
         self.config
             .api_base_url
             .as_deref()
-            .unwrap_or("https://api.github.com")
+            .unwrap_or(http::GITHUB_API_URL)
     }
 
     fn build_request(&self, url: &str) -> reqwest::RequestBuilder {
@@ -49,23 +58,6 @@ impl GitHubRepoSource {
         req
     }
 
-    fn decode_content(&self, response: &ContentResponse) -> Result<String, SourceError> {
-        let encoded = response
-            .content
-            .as_deref()
-            .ok_or_else(|| SourceError::Parse("no content in response".into()))?;
-
-        // GitHub returns base64 with newlines embedded
-        let cleaned: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
-
-        let bytes = base64::engine::general_purpose::STANDARD
-            .decode(&cleaned)
-            .map_err(|e| SourceError::Parse(format!("base64 decode failed: {e}")))?;
-
-        String::from_utf8(bytes)
-            .map_err(|e| SourceError::Parse(format!("invalid UTF-8: {e}")))
-    }
-
     fn build_definition(
         &self,
         id: &DefinitionId,
@@ -96,35 +88,66 @@ impl GitHubRepoSource {
         }
     }
 
-    /// Strip the configured base_path prefix from a full repo path.
-    /// Returns the relative path, or the original path if no base_path is set.
-    fn strip_base_path<'a>(&self, full_path: &'a str) -> Option<&'a str> {
-        match &self.config.base_path {
-            Some(bp) => {
-                let prefix = if bp.ends_with('/') {
-                    bp.clone()
-                } else {
-                    format!("{bp}/")
-                };
-                full_path.strip_prefix(&prefix)
-            }
-            None => Some(full_path),
-        }
-    }
-
-    /// Build the full content API path from a relative ID.
-    /// Prepends base_path and appends `/SKILL.md` for skill directory IDs.
-    fn resolve_content_path(&self, relative_id: &str) -> String {
+    /// Build the candidate content API paths for a relative ID, one per
+    /// base path in config order. Appends `/SKILL.md` for skill directory IDs.
+    fn resolve_content_paths(&self, relative_id: &str) -> Vec<String> {
         let file_path = if agent_defs::path::is_skill_directory_id(relative_id) {
             format!("{relative_id}/SKILL.md")
         } else {
             relative_id.to_owned()
         };
 
-        match &self.config.base_path {
-            Some(bp) => format!("{bp}/{file_path}"),
-            None => file_path,
+        if self.config.base_paths.is_empty() {
+            return vec![file_path];
         }
+        self.config
+            .base_paths
+            .iter()
+            .map(|root| agent_defs::path::join_root(root, &file_path))
+            .collect()
+    }
+
+    /// Fetch a file through the contents API, or `None` if it isn't there.
+    async fn fetch_content(
+        &self,
+        content_path: &str,
+    ) -> Result<Option<ContentResponse>, SourceError> {
+        let url = format!(
+            "{}/repos/{}/{}/contents/{}",
+            self.api_base(),
+            self.config.owner,
+            self.config.repo,
+            content_path,
+        );
+
+        let response = self
+            .build_request(&url)
+            .send()
+            .await
+            .map_err(http::source_error)?;
+
+        if response.status().as_u16() == 404 {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            let refusal = http::refusal(&response);
+            let message = format!(
+                "HTTP {}: {}",
+                response.status(),
+                response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "unknown".into())
+            );
+            return Err(refusal.source_error(message));
+        }
+
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| SourceError::parse(e.to_string()))
     }
 }
 
@@ -147,10 +170,10 @@ impl Source for GitHubRepoSource {
             .build_request(&url)
             .send()
             .await
-            .map_err(|e| SourceError::Network(e.to_string()))?
+            .map_err(http::source_error)?
             .json()
             .await
-            .map_err(|e| SourceError::Parse(e.to_string()))?;
+            .map_err(|e| SourceError::parse(e.to_string()))?;
 
         if response.truncated {
             eprintln!(
@@ -161,12 +184,13 @@ impl Source for GitHubRepoSource {
 
         let label = self.label().to_owned();
 
-        let summaries = response
+        let tagged = response
             .tree
             .iter()
             .filter(|entry| entry.entry_type == "blob")
             .filter_map(|entry| {
-                let relative = self.strip_base_path(&entry.path)?;
+                let (root, relative) =
+                    agent_defs::path::strip_root(&entry.path, &self.config.base_paths)?;
 
                 if !agent_defs::path::is_definition_file(relative) {
                     return None;
@@ -182,71 +206,60 @@ impl Source for GitHubRepoSource {
                     // Skill ID is the directory path (without /SKILL.md)
                     let dir_path = relative.strip_suffix("/SKILL.md").unwrap_or(relative);
 
-                    return Some(DefinitionSummary {
-                        id: DefinitionId::new(dir_path),
-                        name,
-                        description: None,
-                        kind,
-                        category,
-                        source_label: label.clone(),
-                    });
+                    return Some((
+                        root,
+                        DefinitionSummary {
+                            id: DefinitionId::new(dir_path),
+                            name,
+                            description: None,
+                            kind,
+                            category,
+                            source_label: label.clone(),
+                            first_seen_at: None,
+                            last_updated_at: None,
+                        },
+                    ));
                 }
 
                 // Flat kind: ID is the relative path (with extension)
                 let (name, kind, category) = agent_defs::path::parse_relative_path(relative);
 
-                Some(DefinitionSummary {
-                    id: DefinitionId::new(relative),
-                    name,
-                    description: None,
-                    kind,
-                    category,
-                    source_label: label.clone(),
-                })
+                Some((
+                    root,
+                    DefinitionSummary {
+                        id: DefinitionId::new(relative),
+                        name,
+                        description: None,
+                        kind,
+                        category,
+                        source_label: label.clone(),
+                        first_seen_at: None,
+                        last_updated_at: None,
+                    },
+                ))
             })
             .collect();
 
-        Ok(summaries)
+        Ok(agent_defs::path::first_root_wins(tagged, |summary| {
+            summary.id.as_str()
+        }))
     }
 
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
-        let content_path = self.resolve_content_path(id.as_str());
-
-        let url = format!(
-            "{}/repos/{}/{}/contents/{}",
-            self.api_base(),
-            self.config.owner,
-            self.config.repo,
-            content_path,
-        );
-
-        let response = self
-            .build_request(&url)
-            .send()
-            .await
-            .map_err(|e| SourceError::Network(e.to_string()))?;
-
-        if response.status().as_u16() == 404 {
-            return Err(SourceError::NotFound(id.clone()));
-        }
-
-        if !response.status().is_success() {
-            return Err(SourceError::Network(format!(
-                "HTTP {}: {}",
-                response.status(),
-                response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "unknown".into())
-            )));
+        // Roots are tried in order, matching which one `list` keeps when
+        // several hold the same ID.
+        let mut found = None;
+        for content_path in self.resolve_content_paths(id.as_str()) {
+            found = self.fetch_content(&content_path).await?;
+            if found.is_some() {
+                break;
+            }
         }
+        let Some(content_response) = found else {
+            return Err(SourceError::NotFound(id.clone()));
+        };
 
-        let content_response: ContentResponse = response
-            .json()
-            .await
-            .map_err(|e| SourceError::Parse(e.to_string()))?;
-
-        let raw_content = self.decode_content(&content_response)?;
+        let raw_content = content_response.text().map_err(SourceError::parse)?;
 
         // Route skill directory IDs through parse_skill_path
         let relative_id = id.as_str();
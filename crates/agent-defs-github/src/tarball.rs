@@ -1,7 +1,13 @@
+use std::cell::Cell;
 use std::io::Read;
+use std::rc::Rc;
 
 use agent_defs::SyncError;
 use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::archive_cache::ArchiveCache;
+use crate::http::{self, Throttle};
 
 /// A file extracted from a GitHub repository tarball.
 #[derive(Debug, Clone)]
@@ -12,6 +18,39 @@ pub struct RepoFile {
     pub content: String,
 }
 
+/// A repository tarball, unpacked.
+#[derive(Debug, Clone)]
+pub struct Tarball {
+    /// The commit the archive was cut from, as GitHub names it in the
+    /// `owner-repo-<sha>/` root directory.
+    pub revision: Option<String>,
+    pub files: Vec<RepoFile>,
+}
+
+/// Size caps applied while downloading and unpacking a tarball, so a
+/// hostile or runaway repository can't exhaust memory. Exceeding any of
+/// them fails the fetch rather than returning a partial file list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TarballLimits {
+    /// Largest compressed archive to download, in bytes.
+    pub max_download_bytes: u64,
+    /// Largest single file inside the archive, in bytes.
+    pub max_file_bytes: u64,
+    /// Most bytes to decompress from the archive as a whole.
+    pub max_extracted_bytes: u64,
+}
+
+impl Default for TarballLimits {
+    fn default() -> Self {
+        Self {
+            max_download_bytes: 256 * 1024 * 1024,
+            max_file_bytes: 64 * 1024 * 1024,
+            max_extracted_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
 /// HTTP client for downloading GitHub repository tarballs.
 ///
 /// This is a pure transport utility — it downloads and extracts files
@@ -20,21 +59,72 @@ pub struct TarballClient {
     client: reqwest::Client,
     token: Option<String>,
     api_base_url: Option<String>,
+    limits: TarballLimits,
+    /// Bytes per second to hold downloads to, if any.
+    rate_limit: Option<u64>,
+    archives: ArchiveMode,
+}
+
+/// What a [`TarballClient`] does about the archives it fetches.
+#[derive(Debug, Clone, Default)]
+pub enum ArchiveMode {
+    /// Download each archive and let it go once unpacked.
+    #[default]
+    Discard,
+    /// Download each archive and keep it in the cache.
+    Keep(ArchiveCache),
+    /// Read archives the cache kept earlier instead of downloading them.
+    Replay(ArchiveCache),
 }
 
 impl TarballClient {
     pub fn new(token: Option<String>, api_base_url: Option<String>) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: http::default_client(),
             token,
             api_base_url,
+            limits: TarballLimits::default(),
+            rate_limit: None,
+            archives: ArchiveMode::Discard,
         }
     }
 
+    /// Send requests through `client`, as built by
+    /// [`HttpOptions::client`](crate::HttpOptions::client).
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Use `limits` instead of the defaults.
+    pub fn with_limits(mut self, limits: TarballLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Download no faster than `bytes_per_sec`, or as fast as possible
+    /// with `None`.
+    pub fn with_rate_limit(mut self, bytes_per_sec: Option<u64>) -> Self {
+        self.rate_limit = bytes_per_sec;
+        self
+    }
+
+    /// Keep or replay archives as `mode` says, rather than discard them.
+    pub fn with_archives(mut self, mode: ArchiveMode) -> Self {
+        self.archives = mode;
+        self
+    }
+
+    /// Call the API at `url` rather than api.github.com.
+    pub fn with_api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.api_base_url = Some(url.into().trim_end_matches('/').to_owned());
+        self
+    }
+
     fn api_base(&self) -> &str {
         self.api_base_url
             .as_deref()
-            .unwrap_or("https://api.github.com")
+            .unwrap_or(http::GITHUB_API_URL)
     }
 
     fn tarball_url(&self, owner: &str, repo: &str, branch: &str) -> String {
@@ -51,13 +141,36 @@ impl TarballClient {
     ///
     /// Downloads the tarball for the specified owner/repo/branch, extracts it,
     /// and returns all text files with their paths relative to the repo root.
-    /// Binary and non-UTF-8 files are silently skipped.
+    /// Binary and non-UTF-8 files are silently skipped. Fails with a
+    /// [`SyncError`] naming the limit when the archive is bigger than its
+    /// [`TarballLimits`] allow.
+    ///
+    /// Replaying, the archive comes from the cache and the network isn't
+    /// touched; otherwise a successfully unpacked archive is kept there.
     pub async fn fetch(
         &self,
         owner: &str,
         repo: &str,
         branch: &str,
     ) -> Result<Vec<RepoFile>, SyncError> {
+        self.fetch_tarball(owner, repo, branch)
+            .await
+            .map(|tarball| tarball.files)
+    }
+
+    /// Like [`fetch`](Self::fetch), but also says which commit the files
+    /// came from.
+    pub async fn fetch_tarball(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<Tarball, SyncError> {
+        if let ArchiveMode::Replay(cache) = &self.archives {
+            let bytes = cache.load(owner, repo, branch)?;
+            return Self::unpack(&bytes, &self.limits);
+        }
+
         let url = self.tarball_url(owner, repo, branch);
 
         let mut req = self
@@ -69,39 +182,94 @@ impl TarballClient {
             req = req.header("Authorization", format!("Bearer {token}"));
         }
 
-        let response = req
+        let mut response = req
             .send()
             .await
-            .map_err(|e| SyncError::Network(format!("tarball download failed: {e}")))?;
+            .map_err(|e| http::sync_error("tarball download failed", e))?;
 
         if !response.status().is_success() {
-            return Err(SyncError::Network(format!(
-                "tarball download returned HTTP {}",
-                response.status()
-            )));
+            return Err(http::sync_status_error("tarball download", &response));
+        }
+
+        let max = self.limits.max_download_bytes;
+        let too_large = || {
+            SyncError::Network(format!(
+                "tarball is larger than max_download_bytes ({max} bytes)"
+            ))
+        };
+        if response.content_length().is_some_and(|length| length > max) {
+            return Err(too_large());
         }
 
-        let bytes = response
-            .bytes()
+        // Read in chunks so a missing or lying Content-Length can't get
+        // more than the limit into memory.
+        let mut bytes = Vec::new();
+        let mut throttle = self.rate_limit.map(Throttle::new);
+        while let Some(chunk) = response
+            .chunk()
             .await
-            .map_err(|e| SyncError::Network(format!("failed to read tarball body: {e}")))?;
+            .map_err(|e| http::sync_error("failed to read tarball body", e))?
+        {
+            if (bytes.len() + chunk.len()) as u64 > max {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+            if let Some(throttle) = &mut throttle {
+                throttle.take(chunk.len()).await;
+            }
+        }
 
-        Self::extract_files(&bytes)
+        let tarball = Self::unpack(&bytes, &self.limits)?;
+        if let ArchiveMode::Keep(cache) = &self.archives {
+            // Keeping a copy is a convenience; failing to shouldn't fail
+            // the sync that downloaded it.
+            let _ = cache.save(owner, repo, branch, &bytes);
+        }
+        Ok(tarball)
+    }
+
+    /// Unpack a gzipped tarball already in memory, as [`fetch`](Self::fetch)
+    /// does once the download finishes.
+    pub fn extract_files(
+        tarball_bytes: &[u8],
+        limits: &TarballLimits,
+    ) -> Result<Vec<RepoFile>, SyncError> {
+        Self::unpack(tarball_bytes, limits).map(|tarball| tarball.files)
     }
 
-    fn extract_files(tarball_bytes: &[u8]) -> Result<Vec<RepoFile>, SyncError> {
-        let decoder = GzDecoder::new(tarball_bytes);
+    /// Unpack a gzipped tarball, noting the commit its root directory names.
+    fn unpack(tarball_bytes: &[u8], limits: &TarballLimits) -> Result<Tarball, SyncError> {
+        let extracted = Rc::new(Cell::new(0));
+        let decoder = CountingReader {
+            inner: GzDecoder::new(tarball_bytes),
+            count: Rc::clone(&extracted),
+            limit: limits.max_extracted_bytes,
+        };
         let mut archive = tar::Archive::new(decoder);
 
+        // Hitting the extraction cap surfaces from `tar` as a read error;
+        // report it as the limit instead.
+        let max_extracted = limits.max_extracted_bytes;
+        let extraction_error = |context: &str, e: std::io::Error| {
+            if extracted.get() > max_extracted {
+                SyncError::Extraction(format!(
+                    "tarball expands to more than max_extracted_bytes ({max_extracted} bytes)"
+                ))
+            } else {
+                SyncError::Extraction(format!("{context}: {e}"))
+            }
+        };
+
         let entries = archive
             .entries()
-            .map_err(|e| SyncError::Extraction(format!("failed to read tar entries: {e}")))?;
+            .map_err(|e| extraction_error("failed to read tar entries", e))?;
 
         let mut files = Vec::new();
+        let mut revision = None;
 
         for entry_result in entries {
-            let mut entry = entry_result
-                .map_err(|e| SyncError::Extraction(format!("failed to read tar entry: {e}")))?;
+            let mut entry =
+                entry_result.map_err(|e| extraction_error("failed to read tar entry", e))?;
 
             // Skip directories
             if entry.header().entry_type() != tar::EntryType::Regular {
@@ -116,18 +284,32 @@ impl TarballClient {
 
             // GitHub tarballs have a root directory like "owner-repo-sha/"
             // Strip the first path component.
-            let without_root = match entry_path.find('/') {
-                Some(idx) => &entry_path[idx + 1..],
+            let (root, without_root) = match entry_path.split_once('/') {
+                Some(split) => split,
                 None => continue,
             };
+            if revision.is_none() {
+                revision = root_revision(root);
+            }
 
             if without_root.is_empty() {
                 continue;
             }
 
+            let size = entry.size();
+            if size > limits.max_file_bytes {
+                return Err(SyncError::Extraction(format!(
+                    "{without_root} is {size} bytes, more than max_file_bytes ({} bytes)",
+                    limits.max_file_bytes
+                )));
+            }
+
             // Read file content
             let mut content = String::new();
-            if entry.read_to_string(&mut content).is_err() {
+            if let Err(e) = entry.read_to_string(&mut content) {
+                if extracted.get() > max_extracted {
+                    return Err(extraction_error("failed to read tar entry", e));
+                }
                 // Skip binary or non-UTF-8 files silently
                 continue;
             }
@@ -138,6 +320,31 @@ impl TarballClient {
             });
         }
 
-        Ok(files)
+        Ok(Tarball { revision, files })
+    }
+}
+
+/// The commit SHA at the end of a root directory like `owner-repo-1a2b3c4`.
+fn root_revision(root: &str) -> Option<String> {
+    let (_, sha) = root.rsplit_once('-')?;
+    (sha.len() >= 7 && sha.chars().all(|c| c.is_ascii_hexdigit())).then(|| sha.to_owned())
+}
+
+/// Counts the bytes read through it into `count`, and fails once they pass
+/// `limit`.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+    limit: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.set(self.count.get() + read as u64);
+        if self.count.get() > self.limit {
+            return Err(std::io::Error::other("extraction limit exceeded"));
+        }
+        Ok(read)
     }
 }
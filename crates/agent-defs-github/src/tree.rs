@@ -1,5 +1,9 @@
+use agent_defs::SyncError;
 use serde::Deserialize;
 
+use crate::content::ContentResponse;
+use crate::http;
+
 /// Response from GitHub's Git Trees API.
 /// `GET /repos/{owner}/{repo}/git/trees/{branch}?recursive=1`
 #[derive(Debug, Deserialize)]
@@ -17,3 +21,115 @@ pub struct TreeEntry {
     #[serde(rename = "type")]
     pub entry_type: String,
 }
+
+/// HTTP client for reading a repository a file at a time: its paths from
+/// the Git Trees API and each file from the Contents API. Costs a request
+/// per file, but only for the files asked for, where a tarball brings the
+/// whole repository every time.
+pub struct TreeClient {
+    client: reqwest::Client,
+    token: Option<String>,
+    api_base_url: Option<String>,
+}
+
+impl TreeClient {
+    pub fn new(token: Option<String>, api_base_url: Option<String>) -> Self {
+        Self {
+            client: http::default_client(),
+            token,
+            api_base_url,
+        }
+    }
+
+    /// Send requests through `client`, as built by
+    /// [`HttpOptions::client`](crate::HttpOptions::client).
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Call the API at `url` rather than api.github.com.
+    pub fn with_api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.api_base_url = Some(url.into().trim_end_matches('/').to_owned());
+        self
+    }
+
+    fn api_base(&self) -> &str {
+        self.api_base_url.as_deref().unwrap_or(http::GITHUB_API_URL)
+    }
+
+    async fn get(&self, url: &str, context: &str) -> Result<reqwest::Response, SyncError> {
+        let mut req = self
+            .client
+            .get(url)
+            .header("User-Agent", "agent-def-fetcher");
+
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", format!("Bearer {token}"));
+        }
+
+        let response = req.send().await.map_err(|e| http::sync_error(context, e))?;
+
+        if !response.status().is_success() {
+            return Err(http::sync_status_error(context, &response));
+        }
+        Ok(response)
+    }
+
+    /// Path of every file on `branch`, relative to the repository root.
+    /// Fails on a tree GitHub truncated, since the paths it left out would
+    /// look deleted.
+    pub async fn paths(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<Vec<String>, SyncError> {
+        let url = format!(
+            "{}/repos/{owner}/{repo}/git/trees/{branch}?recursive=1",
+            self.api_base()
+        );
+        let response: TreeResponse = self
+            .get(&url, "tree request")
+            .await?
+            .json()
+            .await
+            .map_err(|e| http::sync_error("failed to read tree", e))?;
+
+        if response.truncated {
+            return Err(SyncError::Other(format!(
+                "the tree for {owner}/{repo} is too large for GitHub to list in one response"
+            )));
+        }
+
+        Ok(response
+            .tree
+            .into_iter()
+            .filter(|entry| entry.entry_type == "blob")
+            .map(|entry| entry.path)
+            .collect())
+    }
+
+    /// Content of the file at `path` on `branch`.
+    pub async fn file(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+        path: &str,
+    ) -> Result<String, SyncError> {
+        let url = format!(
+            "{}/repos/{owner}/{repo}/contents/{path}?ref={branch}",
+            self.api_base()
+        );
+        let response: ContentResponse = self
+            .get(&url, "contents request")
+            .await?
+            .json()
+            .await
+            .map_err(|e| http::sync_error("failed to read contents", e))?;
+        response
+            .text()
+            .map_err(|e| SyncError::Other(format!("{path}: {e}")))
+    }
+}
@@ -8,7 +8,7 @@ fn config_for(server: &MockServer) -> GitHubRepoSourceConfig {
         owner: "test-owner".into(),
         repo: "test-repo".into(),
         branch: "main".into(),
-        base_path: Some("cli-tool/components".into()),
+        base_paths: vec!["cli-tool/components".into()],
         token: None,
         api_base_url: Some(server.uri()),
     }
@@ -166,3 +166,30 @@ async fn fetch_skill_by_directory_id() {
     assert!(def.body.contains("CrewAI"));
     assert_eq!(def.source_label, "test-repo");
 }
+
+#[tokio::test]
+async fn fetch_tries_base_paths_in_order() {
+    let server = MockServer::start().await;
+    let fixture = include_str!("fixtures/content_no_frontmatter.json");
+
+    Mock::given(method("GET"))
+        .and(path("/repos/test-owner/test-repo/contents/claude/agents/misc/plain.md"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/test-owner/test-repo/contents/tools/agents/agents/misc/plain.md"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(fixture, "application/json"))
+        .mount(&server)
+        .await;
+
+    let mut config = config_for(&server);
+    config.base_paths = vec!["claude".into(), "tools/agents".into()];
+    let source = GitHubRepoSource::new(config);
+
+    let def = source.fetch(&DefinitionId::new("agents/misc/plain.md")).await.unwrap();
+    assert_eq!(def.category.as_deref(), Some("misc"));
+
+    let missing = source.fetch(&DefinitionId::new("agents/misc/gone.md")).await;
+    assert!(matches!(missing, Err(SourceError::NotFound(_))));
+}
@@ -8,7 +8,7 @@ fn config_for(server: &MockServer, base_path: Option<&str>) -> GitHubRepoSourceC
         owner: "test-owner".into(),
         repo: "test-repo".into(),
         branch: "main".into(),
-        base_path: base_path.map(|s| s.into()),
+        base_paths: base_path.into_iter().map(str::to_owned).collect(),
         token: None,
         api_base_url: Some(server.uri()),
     }
@@ -249,7 +249,7 @@ async fn list_handles_network_error() {
         owner: "test-owner".into(),
         repo: "test-repo".into(),
         branch: "main".into(),
-        base_path: None,
+        base_paths: Vec::new(),
         token: None,
         api_base_url: Some("http://127.0.0.1:1".into()),
     };
@@ -271,3 +271,36 @@ async fn list_sets_source_label() {
         assert_eq!(summary.source_label, "test-repo");
     }
 }
+
+#[tokio::test]
+async fn list_merges_several_base_paths() {
+    let server = MockServer::start().await;
+    let blob = |path: &str| {
+        serde_json::json!({ "path": path, "mode": "100644", "type": "blob", "sha": "0" })
+    };
+    let tree = serde_json::json!({
+        "sha": "abc",
+        "url": "",
+        "truncated": false,
+        "tree": [
+            blob("claude/agents/team/lead.md"),
+            blob("tools/agents/hooks/fmt.md"),
+            blob("tools/agents/agents/team/lead.md"),
+            blob("docs/agents/team/other.md"),
+        ]
+    });
+    Mock::given(method("GET"))
+        .and(path("/repos/test-owner/test-repo/git/trees/main"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(tree))
+        .mount(&server)
+        .await;
+
+    let mut config = config_for(&server, None);
+    config.base_paths = vec!["claude".into(), "tools/agents/".into()];
+    let summaries = GitHubRepoSource::new(config).list().await.unwrap();
+
+    // `agents/team/lead.md` is under both roots; the first one listed wins.
+    let ids: Vec<&str> = summaries.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["agents/team/lead.md", "hooks/fmt.md"]);
+    assert_eq!(summaries[1].kind, DefinitionKind::Hook);
+}
@@ -1,4 +1,5 @@
-use agent_defs_github::TarballClient;
+use agent_defs::SyncError;
+use agent_defs_github::{ArchiveCache, ArchiveMode, TarballClient, TarballLimits};
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use wiremock::matchers::{method, path};
@@ -79,6 +80,23 @@ async fn strips_github_root_prefix() {
     assert!(paths.contains(&"README.md"));
 }
 
+#[tokio::test]
+async fn reads_the_commit_from_the_root_directory() {
+    let tarball = build_tarball(&[("my-org-my-repo-1a2b3c4/agents/agent.md", "agent content")]);
+
+    let server = MockServer::start().await;
+    mount_tarball(&server, tarball).await;
+
+    let client = TarballClient::new(None, Some(server.uri()));
+    let tarball = client
+        .fetch_tarball("test-owner", "test-repo", "main")
+        .await
+        .unwrap();
+
+    assert_eq!(tarball.revision.as_deref(), Some("1a2b3c4"));
+    assert_eq!(tarball.files[0].path, "agents/agent.md");
+}
+
 #[tokio::test]
 async fn handles_network_error() {
     let client = TarballClient::new(None, Some("http://127.0.0.1:1".into()));
@@ -146,3 +164,99 @@ async fn preserves_file_content() {
     assert_eq!(files.len(), 1);
     assert_eq!(files[0].content, content);
 }
+
+async fn fetch_with_limits(
+    entries: &[(&str, &str)],
+    limits: TarballLimits,
+) -> Result<Vec<agent_defs_github::RepoFile>, SyncError> {
+    let server = MockServer::start().await;
+    mount_tarball(&server, build_tarball(entries)).await;
+
+    let client = TarballClient::new(None, Some(server.uri())).with_limits(limits);
+    client.fetch("test-owner", "test-repo", "main").await
+}
+
+#[tokio::test]
+async fn rejects_downloads_over_the_limit() {
+    let limits = TarballLimits {
+        max_download_bytes: 16,
+        ..TarballLimits::default()
+    };
+    let err = fetch_with_limits(&[("owner-repo-sha/agents/a.md", "content")], limits)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, SyncError::Network(_)), "{err}");
+    assert!(err.to_string().contains("max_download_bytes (16 bytes)"), "{err}");
+}
+
+#[tokio::test]
+async fn rejects_files_over_the_limit() {
+    let limits = TarballLimits {
+        max_file_bytes: 10,
+        ..TarballLimits::default()
+    };
+    let big = "x".repeat(11);
+    let err = fetch_with_limits(
+        &[("owner-repo-sha/agents/a.md", "small"), ("owner-repo-sha/agents/big.md", &big)],
+        limits,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, SyncError::Extraction(_)), "{err}");
+    assert!(
+        err.to_string().contains("agents/big.md is 11 bytes, more than max_file_bytes (10 bytes)"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn rejects_archives_that_expand_past_the_limit() {
+    // Compresses to a few hundred bytes but expands to a megabyte.
+    let bomb = "a".repeat(1024 * 1024);
+    let limits = TarballLimits {
+        max_extracted_bytes: 64 * 1024,
+        ..TarballLimits::default()
+    };
+    let err = fetch_with_limits(&[("owner-repo-sha/agents/bomb.md", &bomb)], limits)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, SyncError::Extraction(_)), "{err}");
+    assert!(err.to_string().contains("max_extracted_bytes (65536 bytes)"), "{err}");
+}
+
+#[tokio::test]
+async fn archives_within_the_limits_are_extracted() {
+    let limits = TarballLimits {
+        max_download_bytes: 64 * 1024,
+        max_file_bytes: 7,
+        max_extracted_bytes: 64 * 1024,
+    };
+    let files = fetch_with_limits(&[("owner-repo-sha/agents/a.md", "content")], limits)
+        .await
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].content, "content");
+}
+
+#[tokio::test]
+async fn kept_archives_replay_without_the_network() {
+    let dir = std::env::temp_dir().join("agent-defs-tarball-replay");
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = ArchiveCache::new(&dir, 1024 * 1024);
+    let tarball = build_tarball(&[("owner-repo-sha/agents/a.md", "---\nname: A\n---\nBody")]);
+
+    let server = MockServer::start().await;
+    mount_tarball(&server, tarball).await;
+    let client = TarballClient::new(None, Some(server.uri()))
+        .with_archives(ArchiveMode::Keep(cache.clone()));
+    client.fetch("test-owner", "test-repo", "main").await.unwrap();
+    drop(server);
+
+    let offline = TarballClient::new(None, Some("http://127.0.0.1:9".into()))
+        .with_archives(ArchiveMode::Replay(cache));
+    let files = offline.fetch("test-owner", "test-repo", "main").await.unwrap();
+    assert_eq!(files[0].path, "agents/a.md");
+
+    let missing = offline.fetch("test-owner", "other-repo", "main").await;
+    assert!(matches!(missing, Err(SyncError::Other(_))));
+}
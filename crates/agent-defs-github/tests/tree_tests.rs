@@ -0,0 +1,86 @@
+use agent_defs::SyncError;
+use agent_defs_github::TreeClient;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mount_tree(server: &MockServer, fixture: &'static str) {
+    Mock::given(method("GET"))
+        .and(path("/repos/test-owner/test-repo/git/trees/main"))
+        .and(query_param("recursive", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(fixture, "application/json"))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn paths_lists_files_only() {
+    let server = MockServer::start().await;
+    mount_tree(&server, include_str!("fixtures/tree_response.json")).await;
+
+    let client = TreeClient::new(None, Some(server.uri()));
+    let paths = client
+        .paths("test-owner", "test-repo", "main")
+        .await
+        .unwrap();
+
+    assert!(
+        paths.contains(&"cli-tool/components/agents/development-team/code-architect.md".into())
+    );
+    assert!(!paths.contains(&"cli-tool/components".into()));
+}
+
+#[tokio::test]
+async fn truncated_trees_fail() {
+    let server = MockServer::start().await;
+    mount_tree(&server, include_str!("fixtures/tree_truncated.json")).await;
+
+    let client = TreeClient::new(None, Some(server.uri()));
+    let err = client
+        .paths("test-owner", "test-repo", "main")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SyncError::Other(msg) if msg.contains("too large")));
+}
+
+#[tokio::test]
+async fn file_reads_one_file_on_the_branch() {
+    let server = MockServer::start().await;
+    let path_in_repo = "cli-tool/components/agents/development-team/code-architect.md";
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/repos/test-owner/test-repo/contents/{path_in_repo}"
+        )))
+        .and(query_param("ref", "main"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            include_str!("fixtures/content_markdown.json"),
+            "application/json",
+        ))
+        .mount(&server)
+        .await;
+
+    let client = TreeClient::new(None, Some(server.uri()));
+    let content = client
+        .file("test-owner", "test-repo", "main", path_in_repo)
+        .await
+        .unwrap();
+
+    assert!(content.starts_with("---\nname: code-architect\n"));
+}
+
+#[tokio::test]
+async fn file_reports_http_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+
+    let client = TreeClient::new(None, Some(server.uri()));
+    let err = client
+        .file("test-owner", "test-repo", "main", "agents/a.md")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, SyncError::Unauthorized(msg) if msg.contains("HTTP 403")));
+}
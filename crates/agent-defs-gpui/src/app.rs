@@ -1,18 +1,26 @@
 //! Main application state and rendering for the GPUI agent definition browser.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use agent_defs::{Definition, DefinitionId, DefinitionKind, DefinitionSummary, Source};
+use agent_defs::grouping;
+use agent_defs::tree::{self, TreeExpansion, TreeNode, TreeRow};
+use agent_defs::{
+    Definition, DefinitionId, DefinitionKind, DefinitionSummary, DisplayNames, GlyphSet,
+    InstallLayout, ListDensity, Policy, Source, SourceError, TaskQueue, TaskState, TemplateVar,
+};
 use gpui::{
-    App, AsyncApp, Context, CursorStyle, Entity, FocusHandle, Focusable, IntoElement,
-    ListAlignment, ListState, ParentElement, Render, Styled, WeakEntity, Window, div, list,
-    prelude::*, px,
+    App, AsyncApp, Bounds, ClipboardItem, Context, CursorStyle, Entity, ExternalPaths,
+    FocusHandle, Focusable, IntoElement, ListAlignment, ListState, ParentElement, Render, Styled,
+    TitlebarOptions, WeakEntity, Window, WindowBounds, WindowOptions, div, list, point, prelude::*,
+    px, size,
 };
 
-use crate::grouping::{self, Group, ListRow};
 use crate::{
-    ClearFilters, EnterKindFilter, EnterSearch, EnterSourceFilter, ExitSearch, Install, MoveDown,
-    MoveUp, Quit, SelectItem, Sync as SyncAction, ToggleCommandPalette,
+    CancelTask, ClearFilters, CollapseNode, CopyBody, CopyId, EnterKindFilter, EnterSearch,
+    EnterSourceFilter, ExitSearch, ExpandNode, Install, MoveDown, MoveUp, OpenInNewWindow, Quit,
+    SelectItem, Sync as SyncAction, ToggleCommandPalette, ToggleDensity, ToggleTasks,
 };
 
 /// Drag data for resize handle.
@@ -33,6 +41,28 @@ impl Render for EmptyDragView {
     }
 }
 
+/// Drag data for a definition row, dropped onto the detail pane to install it.
+#[derive(Clone)]
+struct DefinitionDrag {
+    id: DefinitionId,
+    name: String,
+}
+
+impl Render for DefinitionDrag {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px(px(10.0))
+            .py(px(4.0))
+            .rounded(px(4.0))
+            .bg(colors::surface1())
+            .border_1()
+            .border_color(colors::blue())
+            .text_color(colors::text())
+            .text_size(px(12.0))
+            .child(self.name.clone())
+    }
+}
+
 /// Colors - Catppuccin Mocha theme
 mod colors {
     use gpui::Rgba;
@@ -69,7 +99,6 @@ mod colors {
     pub fn green() -> Rgba {
         rgb(0xa6e3a1)
     }
-    #[allow(dead_code)]
     pub fn yellow() -> Rgba {
         rgb(0xf9e2af)
     }
@@ -120,6 +149,67 @@ pub enum Mode {
     SourceFilter,
     /// Command palette mode.
     CommandPalette,
+    /// Background task list.
+    Tasks,
+    /// Answering an install's questions - keystrokes go to the form.
+    InstallForm,
+}
+
+/// A background operation waiting in, or running from, the task queue.
+pub enum Job {
+    /// Reload the definition list from the database.
+    Refresh,
+    /// Write a definition into the directory the user picked, filling in
+    /// its template variables from `values`.
+    Install {
+        def: Box<Definition>,
+        target: PathBuf,
+        values: HashMap<String, String>,
+    },
+}
+
+/// A definition waiting on answers before it installs: a value for each of
+/// its template variables, asked one at a time, then for a hook, a look at
+/// the shell commands it runs.
+pub struct InstallForm {
+    pub def: Box<Definition>,
+    pub vars: Vec<TemplateVar>,
+    pub values: HashMap<String, String>,
+    /// Whether the hook's commands are shown for confirmation once the
+    /// variables are answered.
+    pub is_hook: bool,
+    /// Index into `vars` of the variable being asked.
+    pub field: usize,
+    /// What has been typed for it so far.
+    pub input: String,
+}
+
+impl InstallForm {
+    fn new(def: Definition, vars: Vec<TemplateVar>) -> Self {
+        Self {
+            is_hook: def.kind == DefinitionKind::Hook,
+            def: Box::new(def),
+            vars,
+            values: HashMap::new(),
+            field: 0,
+            input: String::new(),
+        }
+    }
+
+    /// The variable being asked, or `None` once every one is answered and
+    /// only a hook's confirmation is left.
+    pub fn current(&self) -> Option<&TemplateVar> {
+        self.vars.get(self.field)
+    }
+}
+
+/// An entry in the source filter: every source, one source, or one branch
+/// of a source that syncs several.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceChoice {
+    All,
+    Source(String),
+    Branch(String),
 }
 
 /// A command available in the command palette.
@@ -146,7 +236,12 @@ const PALETTE_COMMANDS: &[PaletteCommand] = &[
     PaletteCommand::new("filter_kind", "Filter by kind", "f"),
     PaletteCommand::new("filter_source", "Filter by source/provider", "p"),
     PaletteCommand::new("install", "Install selected definition", "i"),
+    PaletteCommand::new("copy_body", "Copy selected definition's body", "c"),
+    PaletteCommand::new("copy_id", "Copy selected definition's ID", "Y"),
+    PaletteCommand::new("new_window", "Open selected definition in a new window", "n"),
+    PaletteCommand::new("density", "Show or hide descriptions in the list", "D"),
     PaletteCommand::new("sync", "Sync/refresh definitions", "s"),
+    PaletteCommand::new("tasks", "Show background tasks", "t"),
     PaletteCommand::new("quit", "Quit application", "q"),
 ];
 
@@ -158,10 +253,12 @@ pub struct AppState {
     pub summaries: Vec<DefinitionSummary>,
     /// Current view summaries (filtered).
     pub view_summaries: Vec<DefinitionSummary>,
-    /// Computed groups from current view.
-    pub groups: Vec<Group>,
-    /// Flattened rows for cursor navigation.
-    pub flat_items: Vec<ListRow>,
+    /// Names for the view, qualified where two of them would read the same.
+    pub display_names: DisplayNames,
+    /// The view as a kind → category tree, flattened for cursor navigation.
+    pub flat_items: Vec<TreeRow>,
+    /// Which tree nodes are open, saved across runs.
+    pub expansion: TreeExpansion,
     /// Current cursor position in flat_items.
     pub cursor: usize,
     /// Full definition for the currently selected item.
@@ -174,6 +271,8 @@ pub struct AppState {
     pub kind_filter: Option<DefinitionKind>,
     /// Source filter.
     pub source_filter: Option<String>,
+    /// Branch filter, for sources that sync several branches.
+    pub branch_filter: Option<String>,
     /// Loading state.
     pub loading: LoadingState,
     /// Status message.
@@ -190,6 +289,18 @@ pub struct AppState {
     pub palette_query: String,
     /// Command palette cursor.
     pub palette_cursor: usize,
+    /// Glyphs prefixed to kinds so they are distinguishable without color.
+    pub glyphs: GlyphSet,
+    /// Whether list rows show descriptions under the names.
+    pub list_density: ListDensity,
+    /// Refreshes and installs, run one at a time.
+    pub tasks: TaskQueue<Job>,
+    /// Selected row in the task list.
+    pub task_cursor: usize,
+    /// Folder dropped onto the detail pane; installs go there without asking.
+    pub install_target: Option<PathBuf>,
+    /// The install whose questions are being answered, in `Mode::InstallForm`.
+    pub install_form: Option<InstallForm>,
 }
 
 impl AppState {
@@ -198,14 +309,16 @@ impl AppState {
             source,
             summaries: Vec::new(),
             view_summaries: Vec::new(),
-            groups: Vec::new(),
+            display_names: DisplayNames::default(),
             flat_items: Vec::new(),
+            expansion: load_expansion(),
             cursor: 0,
             selected_definition: None,
             pending_fetch: None,
             search_query: String::new(),
             kind_filter: None,
             source_filter: None,
+            branch_filter: None,
             loading: LoadingState::Loading,
             status_message: Some("Loading definitions...".into()),
             list_scroll_offset: 0,
@@ -214,6 +327,136 @@ impl AppState {
             filter_cursor: 0,
             palette_query: String::new(),
             palette_cursor: 0,
+            glyphs: GlyphSet::default(),
+            list_density: ListDensity::Comfortable,
+            tasks: TaskQueue::new(),
+            task_cursor: 0,
+            install_target: None,
+            install_form: None,
+        }
+    }
+
+    /// Take the install target from paths dropped onto the window. A dropped
+    /// file stands in for the folder it lives in.
+    pub fn set_install_target(&mut self, paths: &[PathBuf]) {
+        let target = paths.iter().find_map(|path| {
+            if path.is_dir() {
+                Some(path.clone())
+            } else {
+                path.parent().map(PathBuf::from)
+            }
+        });
+        self.status_message = Some(match &target {
+            Some(dir) => format!("Installing into {}", dir.display()),
+            None => "Drop a folder to set where definitions install".into(),
+        });
+        if target.is_some() {
+            self.install_target = target;
+        }
+    }
+
+    /// Cancel the selected task if it hasn't started yet.
+    pub fn cancel_selected_task(&mut self) {
+        let Some(task) = self.tasks.tasks().get(self.task_cursor) else {
+            return;
+        };
+        let (id, label) = (task.id, task.label.clone());
+        self.status_message = Some(match self.tasks.cancel(id) {
+            Some(job) => {
+                if let Job::Refresh = job {
+                    self.loading = LoadingState::Idle;
+                }
+                format!("Cancelled {label}")
+            }
+            None => "Only tasks that haven't started can be cancelled".into(),
+        });
+    }
+
+    /// Move the cursor onto the definition with `id`, if it's in the view,
+    /// opening the nodes above it.
+    pub fn select_id(&mut self, id: &DefinitionId) -> bool {
+        let Some(summary) = self.view_summaries.iter().find(|s| &s.id == id) else {
+            return false;
+        };
+        let mut nodes = vec![TreeNode::Kind(summary.kind.clone())];
+        if let Some(category) = &summary.category {
+            nodes.push(TreeNode::Category(summary.kind.clone(), category.clone()));
+        }
+        if nodes.iter().any(|node| !self.expansion.is_expanded(node)) {
+            for node in &nodes {
+                self.expansion.set_expanded(node, true);
+            }
+            self.rebuild_rows();
+            save_expansion(&self.expansion);
+        }
+        let position = self.flat_items.iter().position(|row| match row {
+            TreeRow::Item { summary_index, .. } => &self.view_summaries[*summary_index].id == id,
+            TreeRow::Node { .. } => false,
+        });
+        if let Some(index) = position {
+            self.cursor = index;
+        }
+        position.is_some()
+    }
+
+    /// Rebuild the tree rows from the current view, keeping the cursor on
+    /// the same row where it's still shown. A search opens every node so no
+    /// match is hidden.
+    fn rebuild_rows(&mut self) {
+        let current = self.flat_items.get(self.cursor).cloned();
+        self.flat_items = tree::build_tree(
+            &self.view_summaries,
+            &self.expansion,
+            !self.search_query.is_empty(),
+        );
+        let same_row = |row: &TreeRow| match (row, &current) {
+            (TreeRow::Node { node, .. }, Some(TreeRow::Node { node: old, .. })) => node == old,
+            (
+                TreeRow::Item { summary_index, .. },
+                Some(TreeRow::Item {
+                    summary_index: old, ..
+                }),
+            ) => summary_index == old,
+            _ => false,
+        };
+        self.cursor = self
+            .flat_items
+            .iter()
+            .position(same_row)
+            .unwrap_or(self.cursor.min(self.flat_items.len().saturating_sub(1)));
+    }
+
+    /// Open or close the node under the cursor, remembering the choice.
+    pub fn toggle_node(&mut self) {
+        if let Some(TreeRow::Node { node, .. }) = self.flat_items.get(self.cursor) {
+            let node = node.clone();
+            self.expansion.toggle(&node);
+            self.rebuild_rows();
+            save_expansion(&self.expansion);
+        }
+    }
+
+    /// Close the node under the cursor, or move up to the node containing
+    /// the row.
+    pub fn collapse_node(&mut self) {
+        match self.flat_items.get(self.cursor) {
+            Some(TreeRow::Node { expanded: true, .. }) => self.toggle_node(),
+            Some(_) => {
+                if let Some(parent) = tree::parent_index(&self.flat_items, self.cursor) {
+                    self.cursor = parent;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Open the node under the cursor.
+    pub fn expand_node(&mut self) {
+        if let Some(TreeRow::Node {
+            expanded: false, ..
+        }) = self.flat_items.get(self.cursor)
+        {
+            self.toggle_node();
         }
     }
 
@@ -241,6 +484,11 @@ impl AppState {
                 {
                     return false;
                 }
+                if let Some(ref branch) = self.branch_filter
+                    && agent_defs::path::split_branch(s.id.as_str()).0 != Some(branch.as_str())
+                {
+                    return false;
+                }
                 if !self.search_query.is_empty() {
                     let q = self.search_query.to_lowercase();
                     if !s.name.to_lowercase().contains(&q)
@@ -258,36 +506,42 @@ impl AppState {
             .collect();
 
         self.view_summaries = view;
-        let (groups, flat_items) = grouping::build_groups(&self.view_summaries);
-        self.groups = groups;
-        self.flat_items = flat_items;
-        self.cursor = grouping::first_item_index(&self.flat_items).unwrap_or(0);
+        self.display_names = DisplayNames::new(&self.view_summaries);
+        self.flat_items =
+            tree::build_tree(&self.view_summaries, &self.expansion, !self.search_query.is_empty());
+        self.cursor = self
+            .flat_items
+            .iter()
+            .position(|row| matches!(row, TreeRow::Item { .. }))
+            .unwrap_or(0);
         self.list_scroll_offset = 0;
     }
 
     /// Get the currently selected summary.
     pub fn selected_summary(&self) -> Option<&DefinitionSummary> {
         self.flat_items.get(self.cursor).and_then(|row| match row {
-            ListRow::Item { summary_index } => self.view_summaries.get(*summary_index),
-            ListRow::Header { .. } => None,
+            TreeRow::Item { summary_index, .. } => self.view_summaries.get(*summary_index),
+            TreeRow::Node { .. } => None,
         })
     }
 
     /// Move cursor down.
     pub fn move_cursor_down(&mut self) {
-        self.cursor = grouping::next_item_index(&self.flat_items, self.cursor);
+        if self.cursor + 1 < self.flat_items.len() {
+            self.cursor += 1;
+        }
     }
 
     /// Move cursor up.
     pub fn move_cursor_up(&mut self) {
-        self.cursor = grouping::prev_item_index(&self.flat_items, self.cursor);
+        self.cursor = self.cursor.saturating_sub(1);
     }
 
     /// Set the selected definition.
     pub fn set_selected_definition(
         &mut self,
         id: DefinitionId,
-        result: Result<Definition, String>,
+        result: Result<Definition, SourceError>,
     ) {
         if self.pending_fetch.as_ref() != Some(&id) {
             return; // Stale fetch
@@ -300,9 +554,9 @@ impl AppState {
                 self.selected_definition = Some(def);
                 self.detail_scroll = 0;
             }
-            Err(msg) => {
+            Err(e) => {
                 self.selected_definition = None;
-                self.status_message = Some(format!("Error: {msg}"));
+                self.status_message = Some(error_status(&e));
             }
         }
     }
@@ -319,6 +573,45 @@ impl AppState {
         self.recompute_view();
     }
 
+    /// Ask for `def`'s template variables, and to confirm it if it's a hook,
+    /// before installing it.
+    pub fn start_install_form(&mut self, def: Definition, vars: Vec<TemplateVar>) {
+        self.install_form = Some(InstallForm::new(def, vars));
+        self.mode = Mode::InstallForm;
+    }
+
+    /// Take the typed answer for the variable being asked. An empty answer
+    /// takes its default, or is refused when it has none. Once every
+    /// variable is answered, and a hook's commands confirmed, the form
+    /// closes and hands back the definition with its values.
+    pub fn submit_install_field(&mut self) -> Option<(Box<Definition>, HashMap<String, String>)> {
+        let form = self.install_form.as_mut()?;
+        if let Some(var) = form.current().cloned() {
+            let answer = std::mem::take(&mut form.input);
+            if !answer.is_empty() {
+                form.values.insert(var.name, answer);
+            } else if var.default.is_none() {
+                self.status_message = Some(format!("{} needs a value", var.name));
+                return None;
+            }
+            form.field += 1;
+            if form.current().is_some() || form.is_hook {
+                return None;
+            }
+        }
+        let form = self.install_form.take()?;
+        self.mode = Mode::Normal;
+        Some((form.def, form.values))
+    }
+
+    /// Drop the install being asked about.
+    pub fn cancel_install_form(&mut self) {
+        if let Some(form) = self.install_form.take() {
+            self.status_message = Some(format!("Cancelled installing {}", form.def.name));
+        }
+        self.mode = Mode::Normal;
+    }
+
     /// Get all unique source labels from summaries.
     pub fn unique_sources(&self) -> Vec<String> {
         let mut sources: Vec<String> = self
@@ -337,17 +630,33 @@ impl AppState {
         self.recompute_view();
     }
 
-    /// Set source filter.
-    pub fn set_source_filter(&mut self, source: Option<String>) {
-        self.source_filter = source;
+    /// Apply a source filter entry, replacing any source or branch filter.
+    pub fn set_source_choice(&mut self, choice: &SourceChoice) {
+        self.source_filter = None;
+        self.branch_filter = None;
+        match choice {
+            SourceChoice::All => {}
+            SourceChoice::Source(source) => self.source_filter = Some(source.clone()),
+            SourceChoice::Branch(branch) => self.branch_filter = Some(branch.clone()),
+        }
         self.recompute_view();
     }
 
+    /// The source filter entry currently in effect.
+    pub fn current_source_choice(&self) -> SourceChoice {
+        match (&self.source_filter, &self.branch_filter) {
+            (Some(source), _) => SourceChoice::Source(source.clone()),
+            (None, Some(branch)) => SourceChoice::Branch(branch.clone()),
+            (None, None) => SourceChoice::All,
+        }
+    }
+
     /// Clear all filters.
     pub fn clear_filters(&mut self) {
         self.search_query.clear();
         self.kind_filter = None;
         self.source_filter = None;
+        self.branch_filter = None;
         self.recompute_view();
     }
 
@@ -378,18 +687,33 @@ impl AppState {
         }
     }
 
-    /// Get source filter options (None = All, Some = specific source).
-    pub fn source_options(&self) -> Vec<Option<String>> {
-        let mut opts = vec![None]; // "All" option
-        opts.extend(self.unique_sources().into_iter().map(Some));
+    /// Get all unique branches of multi-branch sources, from definition IDs.
+    pub fn unique_branches(&self) -> Vec<String> {
+        let mut branches: Vec<String> = self
+            .summaries
+            .iter()
+            .filter_map(|s| agent_defs::path::split_branch(s.id.as_str()).0)
+            .map(str::to_owned)
+            .collect();
+        branches.sort();
+        branches.dedup();
+        branches
+    }
+
+    /// Get source filter options: all, each source, then each branch.
+    pub fn source_options(&self) -> Vec<SourceChoice> {
+        let mut opts = vec![SourceChoice::All];
+        opts.extend(self.unique_sources().into_iter().map(SourceChoice::Source));
+        opts.extend(self.unique_branches().into_iter().map(SourceChoice::Branch));
         opts
     }
 
     /// Get the label for a source filter option.
-    pub fn source_option_label(source: &Option<String>) -> String {
+    pub fn source_option_label(source: &SourceChoice) -> String {
         match source {
-            None => "All Sources".to_string(),
-            Some(s) => s.clone(),
+            SourceChoice::All => "All Sources".to_string(),
+            SourceChoice::Source(s) => s.clone(),
+            SourceChoice::Branch(b) => format!("\u{2387} {b}"),
         }
     }
 
@@ -430,9 +754,93 @@ impl Focusable for AgentDefsApp {
     }
 }
 
+/// The `install_layout` from the CLI's `sources.toml`, read at each install
+/// so a change applies without restarting. Nested when it isn't set.
+fn config_install_layout() -> InstallLayout {
+    dirs::config_dir()
+        .map(|dir| dir.join("agent-def-fetcher").join("sources.toml"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| InstallLayout::from_config(&text))
+        .unwrap_or_default()
+}
+
+/// `e` for the status bar, with what to do about it when a remote refused
+/// the request, such as setting a token or waiting out a rate limit.
+fn error_status(e: &SourceError) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    match agent_defs::guidance(e, now) {
+        Some(guidance) => format!("Error: {e} ({guidance})"),
+        None => format!("Error: {e}"),
+    }
+}
+
+fn expansion_path() -> Option<PathBuf> {
+    crate::cache_dir().ok().map(|dir| dir.join("gui-tree.txt"))
+}
+
+/// The sidebar tree's expansion from the last run, or the defaults.
+fn load_expansion() -> TreeExpansion {
+    expansion_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|text| TreeExpansion::from_lines(&text))
+        .unwrap_or_default()
+}
+
+/// Losing the expansion only costs a few clicks, so a failed write is ignored.
+fn save_expansion(expansion: &TreeExpansion) {
+    if let Some(path) = expansion_path() {
+        let _ = std::fs::write(path, expansion.to_lines());
+    }
+}
+
+/// Open a browser window over `source`, with its own filters and selection.
+/// `select` starts it on that definition rather than the first in the list.
+pub fn open_window(
+    source: Arc<dyn Source>,
+    select: Option<DefinitionId>,
+    cx: &mut App,
+) -> anyhow::Result<()> {
+    // Cascade each new window so it doesn't land squarely on the last one.
+    let offset = px(32.0 * cx.windows().len() as f32);
+    let mut bounds = Bounds::centered(None, size(px(1200.0), px(800.0)), cx);
+    bounds.origin = point(bounds.origin.x + offset, bounds.origin.y + offset);
+
+    cx.open_window(
+        WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            titlebar: Some(TitlebarOptions {
+                title: Some("Agent Defs Browser".into()),
+                appears_transparent: false,
+                traffic_light_position: Some(point(px(9.0), px(9.0))),
+            }),
+            focus: true,
+            show: true,
+            ..Default::default()
+        },
+        |_window, cx| cx.new(|cx| AgentDefsApp::new(source, select, cx)),
+    )?;
+    Ok(())
+}
+
 impl AgentDefsApp {
-    pub fn new(source: Arc<dyn Source>, cx: &mut Context<Self>) -> Self {
-        let state = AppState::new(Arc::clone(&source));
+    pub fn new(
+        source: Arc<dyn Source>,
+        select: Option<DefinitionId>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut state = AppState::new(Arc::clone(&source));
+        state.glyphs = std::env::var("AGENT_DEFS_GLYPHS")
+            .ok()
+            .and_then(|v| GlyphSet::parse(&v))
+            .unwrap_or_default();
+        if let Some(density) = std::env::var("AGENT_DEFS_LIST_DENSITY")
+            .ok()
+            .and_then(|v| ListDensity::parse(&v))
+        {
+            state.list_density = density;
+        }
         let focus_handle = cx.focus_handle();
         // Initialize list state with 0 items; will be updated when data loads.
         // Overdraw of 100px ensures smooth scrolling by pre-rendering items just outside view.
@@ -441,13 +849,24 @@ impl AgentDefsApp {
         // Spawn async task to load definitions
         cx.spawn(
             async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
-                let summaries = source.list().await.unwrap_or_default();
+                let listed = source.list().await;
                 let _ = this.update(
                     cx,
                     |app: &mut AgentDefsApp, cx: &mut Context<AgentDefsApp>| {
-                        app.state.load_summaries(summaries);
+                        match listed {
+                            Ok(summaries) => app.state.load_summaries(summaries),
+                            Err(e) => {
+                                app.state.load_summaries(Vec::new());
+                                app.state.status_message = Some(error_status(&e));
+                            }
+                        }
                         // Update list state with new item count
                         app.list_state.reset(app.state.flat_items.len());
+                        if let Some(id) = &select
+                            && app.state.select_id(id)
+                        {
+                            app.list_state.scroll_to_reveal_item(app.state.cursor);
+                        }
                         // Fetch the first definition if available
                         if let Some(summary) = app.state.selected_summary() {
                             let id = summary.id.clone();
@@ -457,7 +876,7 @@ impl AgentDefsApp {
                             let source = Arc::clone(&app.state.source);
                             cx.spawn(
                                 async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
-                                    let result = source.fetch(&id).await.map_err(|e| e.to_string());
+                                    let result = source.fetch(&id).await;
                                     let _ = this.update(
                                     cx,
                                     |app: &mut AgentDefsApp, _cx: &mut Context<AgentDefsApp>| {
@@ -515,7 +934,7 @@ impl AgentDefsApp {
             let source = Arc::clone(&self.state.source);
             cx.spawn(
                 async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
-                    let result = source.fetch(&id).await.map_err(|e| e.to_string());
+                    let result = source.fetch(&id).await;
                     let _ = this.update(
                         cx,
                         |app: &mut AgentDefsApp, cx: &mut Context<AgentDefsApp>| {
@@ -532,99 +951,274 @@ impl AgentDefsApp {
     pub fn do_sync(&mut self, cx: &mut Context<Self>) {
         if self.state.loading == LoadingState::Idle {
             self.state.loading = LoadingState::Syncing;
-            self.state.status_message = Some("Refreshing definitions from database...".into());
-            cx.notify();
+            self.queue_job("Refresh definitions".into(), Job::Refresh, cx);
+        }
+    }
 
-            let source = Arc::clone(&self.state.source);
-            cx.spawn(
-                async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
-                    // Update status to show we're fetching
-                    let _ = this.update(cx, |app, cx| {
-                        app.state.status_message = Some("Loading definitions...".into());
-                        cx.notify();
-                    });
+    /// Run `job` now if no other task is running, or queue it behind the
+    /// ones that are.
+    fn queue_job(&mut self, label: String, job: Job, cx: &mut Context<Self>) {
+        match self.state.tasks.push(label.clone(), job) {
+            Some(job) => self.run_job(job, cx),
+            None => {
+                let waiting = self.state.tasks.pending();
+                self.state.status_message =
+                    Some(format!("Queued {label} ({waiting} waiting; t for tasks)"));
+                cx.notify();
+            }
+        }
+    }
 
-                    let summaries = source.list().await.unwrap_or_default();
-                    let count = summaries.len();
+    /// Record how the running task went and start the next one.
+    fn finish_job(&mut self, result: Result<String, String>, cx: &mut Context<Self>) {
+        if let Some(job) = self.state.tasks.finish(result) {
+            self.run_job(job, cx);
+        }
+        cx.notify();
+    }
 
-                    let _ = this.update(
-                        cx,
-                        |app: &mut AgentDefsApp, cx: &mut Context<AgentDefsApp>| {
-                            let previous_count = app.state.summaries.len();
-                            app.state.load_summaries(summaries);
-
-                            // Show informative message about what changed
-                            let message = if count == previous_count {
-                                format!("Refreshed: {} definitions (no changes)", count)
-                            } else if count > previous_count {
-                                format!(
-                                    "Refreshed: {} definitions (+{} new)",
-                                    count,
-                                    count - previous_count
-                                )
-                            } else {
-                                format!(
-                                    "Refreshed: {} definitions (-{} removed)",
-                                    count,
-                                    previous_count - count
-                                )
-                            };
-                            app.state.status_message = Some(message);
-                            cx.notify();
-                        },
-                    );
-                },
-            )
-            .detach();
+    fn run_job(&mut self, job: Job, cx: &mut Context<Self>) {
+        match job {
+            Job::Refresh => self.refresh(cx),
+            Job::Install {
+                def,
+                target,
+                values,
+            } => {
+                cx.spawn(
+                    async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
+                        let result = config_install_layout()
+                            .install(&target, &def, &values)
+                            .map(|path| format!("Installed to {}", path.display()))
+                            .map_err(|e| format!("Install failed: {}", e));
+                        let _ = this.update(cx, |app, cx| {
+                            let (Ok(message) | Err(message)) = &result;
+                            app.state.status_message = Some(message.clone());
+                            app.finish_job(result, cx);
+                        });
+                    },
+                )
+                .detach();
+            }
         }
     }
 
+    /// Reload the definition list, reporting what changed.
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        self.state.status_message = Some("Refreshing definitions from database...".into());
+        cx.notify();
+
+        let source = Arc::clone(&self.state.source);
+        cx.spawn(
+            async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
+                // Update status to show we're fetching
+                let _ = this.update(cx, |app, cx| {
+                    app.state.status_message = Some("Loading definitions...".into());
+                    cx.notify();
+                });
+
+                let summaries = match source.list().await {
+                    Ok(summaries) => summaries,
+                    Err(e) => {
+                        let _ = this.update(cx, |app, cx| {
+                            let message = error_status(&e);
+                            app.state.loading = LoadingState::Idle;
+                            app.state.status_message = Some(message.clone());
+                            app.finish_job(Err(message), cx);
+                        });
+                        return;
+                    }
+                };
+                let count = summaries.len();
+
+                let _ = this.update(
+                    cx,
+                    |app: &mut AgentDefsApp, cx: &mut Context<AgentDefsApp>| {
+                        let previous_count = app.state.summaries.len();
+                        app.state.load_summaries(summaries);
+
+                        // Show informative message about what changed
+                        let message = if count == previous_count {
+                            format!("Refreshed: {} definitions (no changes)", count)
+                        } else if count > previous_count {
+                            format!(
+                                "Refreshed: {} definitions (+{} new)",
+                                count,
+                                count - previous_count
+                            )
+                        } else {
+                            format!(
+                                "Refreshed: {} definitions (-{} removed)",
+                                count,
+                                previous_count - count
+                            )
+                        };
+                        app.state.status_message = Some(message.clone());
+                        app.finish_job(Ok(message), cx);
+                    },
+                );
+            },
+        )
+        .detach();
+    }
+
     pub fn do_install(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
-        // Must have a selected definition with content
+        // Must have a selected definition; its raw content is loaded on demand.
         let Some(def) = &self.state.selected_definition else {
             self.state.status_message = Some("No definition selected".into());
             cx.notify();
             return;
         };
 
-        self.install_definition(def.clone(), cx);
+        self.install_by_id(def.id.clone(), cx);
+    }
+
+    /// Open the selected definition in a window of its own, sharing this
+    /// window's source, so two definitions can sit side by side.
+    pub fn open_selected_in_new_window(&mut self, cx: &mut Context<Self>) {
+        let Some(summary) = self.state.selected_summary() else {
+            self.state.status_message = Some("No definition selected".into());
+            cx.notify();
+            return;
+        };
+        let id = summary.id.clone();
+        let source = Arc::clone(&self.state.source);
+        let this = cx.entity().downgrade();
+        cx.defer(move |cx| {
+            if let Err(e) = open_window(source, Some(id), cx) {
+                let _ = this.update(cx, |app, cx| {
+                    app.state.status_message = Some(format!("Failed to open window: {e}"));
+                    cx.notify();
+                });
+            }
+        });
+    }
+
+    /// Copy the selected definition's body, once it has loaded.
+    pub fn copy_body(&mut self, cx: &mut Context<Self>) {
+        let body = self
+            .state
+            .selected_summary()
+            .zip(self.state.selected_definition.as_ref())
+            .filter(|(summary, def)| summary.id == def.id)
+            .map(|(_, def)| def.body.clone());
+        match body {
+            Some(body) => {
+                cx.write_to_clipboard(ClipboardItem::new_string(body));
+                self.state.status_message = Some("Copied body to clipboard".into());
+            }
+            None => self.state.status_message = Some("No definition loaded to copy".into()),
+        }
+        cx.notify();
+    }
+
+    /// Switch between list rows with descriptions and rows of names alone.
+    pub fn toggle_density(&mut self, cx: &mut Context<Self>) {
+        self.state.list_density = self.state.list_density.toggled();
+        // Rows change height, so the list measures them again.
+        self.list_state.reset(self.state.flat_items.len());
+        self.state.status_message = Some(match self.state.list_density {
+            ListDensity::Compact => "Showing names only".into(),
+            ListDensity::Comfortable => "Showing descriptions".into(),
+        });
+        cx.notify();
     }
 
-    /// Install a specific definition by fetching it first if needed, then prompting for directory.
+    /// Copy the selected definition's source-qualified ID.
+    pub fn copy_id(&mut self, cx: &mut Context<Self>) {
+        match self.state.selected_summary() {
+            Some(summary) => {
+                let id = summary.qualified_id().to_string();
+                cx.write_to_clipboard(ClipboardItem::new_string(id.clone()));
+                self.state.status_message = Some(format!("Copied {id} to clipboard"));
+            }
+            None => self.state.status_message = Some("No definition selected".into()),
+        }
+        cx.notify();
+    }
+
+    /// Install a specific definition by fetching it (and its raw content) first, then prompting for directory.
     pub fn install_by_id(&mut self, id: DefinitionId, cx: &mut Context<Self>) {
         self.state.status_message = Some("Fetching definition for install...".into());
         cx.notify();
 
         let source = Arc::clone(&self.state.source);
         cx.spawn(
-            async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| match source
-                .fetch(&id)
-                .await
-            {
-                Ok(def) => {
-                    let _ = this.update(cx, |app, cx| {
-                        app.install_definition(def, cx);
-                    });
-                }
-                Err(e) => {
-                    let _ = this.update(cx, |app, cx| {
-                        app.state.status_message =
-                            Some(format!("Failed to fetch definition: {}", e));
-                        cx.notify();
-                    });
+            async move |this: WeakEntity<AgentDefsApp>, cx: &mut AsyncApp| {
+                let result = match source.fetch(&id).await {
+                    Ok(mut def) if def.raw.is_empty() => {
+                        source.fetch_raw(&id).await.map(|raw| {
+                            def.raw = raw;
+                            def
+                        })
+                    }
+                    other => other,
+                };
+                match result {
+                    Ok(def) => {
+                        let _ = this.update(cx, |app, cx| {
+                            app.install_definition(def, cx);
+                        });
+                    }
+                    Err(e) => {
+                        let _ = this.update(cx, |app, cx| {
+                            app.state.status_message =
+                                Some(format!("Failed to fetch definition: {}", e));
+                            cx.notify();
+                        });
+                    }
                 }
             },
         )
         .detach();
     }
 
-    /// Install a definition - prompts for directory and writes file.
+    /// Install a definition - asks for its template variables, then
+    /// prompts for directory and writes file.
     fn install_definition(&mut self, def: Definition, cx: &mut Context<Self>) {
         if def.raw.is_empty() {
             self.state.status_message = Some("Definition has no raw content to install".into());
             cx.notify();
             return;
         }
+        // Read the org policy at each install so a changed file applies
+        // without restarting, and an unreadable one blocks installs.
+        let allowed = Policy::load()
+            .map_err(|e| e.to_string())
+            .and_then(|policy| policy.check_install(&def).map_err(|e| e.to_string()));
+        if let Err(message) = allowed {
+            self.state.status_message = Some(format!("Install blocked: {message}"));
+            cx.notify();
+            return;
+        }
+
+        // Hooks run shell commands, so they're shown before installing.
+        let vars = agent_defs::template::declared_vars(&def.raw);
+        if vars.is_empty() && def.kind != DefinitionKind::Hook {
+            self.choose_install_target(Box::new(def), HashMap::new(), cx);
+        } else {
+            self.state.start_install_form(def, vars);
+            cx.notify();
+        }
+    }
+
+    /// Queue the install into the dropped folder, or ask for a directory.
+    fn choose_install_target(
+        &mut self,
+        def: Box<Definition>,
+        values: HashMap<String, String>,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(target) = self.state.install_target.clone() {
+            let label = format!("Install {}", def.name);
+            let job = Job::Install {
+                def,
+                target,
+                values,
+            };
+            self.queue_job(label, job, cx);
+            return;
+        }
 
         // Open native directory picker
         let paths_receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
@@ -641,26 +1235,19 @@ impl AgentDefsApp {
                 let Ok(Ok(Some(paths))) = paths_receiver.await else {
                     return;
                 };
-                let Some(target_dir) = paths.first() else {
+                let Some(target) = paths.into_iter().next() else {
                     return;
                 };
 
-                // Install the definition
-                match agent_defs::install::install_definition(target_dir, &def) {
-                    Ok(installed_path) => {
-                        let _ = this.update(cx, |app, cx| {
-                            app.state.status_message =
-                                Some(format!("Installed to {}", installed_path.display()));
-                            cx.notify();
-                        });
-                    }
-                    Err(e) => {
-                        let _ = this.update(cx, |app, cx| {
-                            app.state.status_message = Some(format!("Install failed: {}", e));
-                            cx.notify();
-                        });
-                    }
-                }
+                let _ = this.update(cx, |app, cx| {
+                    let label = format!("Install {}", def.name);
+                    let job = Job::Install {
+                        def,
+                        target,
+                        values,
+                    };
+                    app.queue_job(label, job, cx);
+                });
             },
         )
         .detach();
@@ -671,7 +1258,10 @@ impl AgentDefsApp {
         // This allows virtual scrolling - only visible items are rendered.
         let flat_items = self.state.flat_items.clone();
         let view_summaries = self.state.view_summaries.clone();
+        let display_names = self.state.display_names.clone();
         let cursor = self.state.cursor;
+        let glyphs = self.state.glyphs;
+        let show_descriptions = self.state.list_density == ListDensity::Comfortable;
         let list_state = self.list_state.clone();
         let width = self.list_pane_width;
 
@@ -802,30 +1392,74 @@ impl AgentDefsApp {
                     let is_selected = idx == cursor;
 
                     match &flat_items[idx] {
-                        ListRow::Header { label, count } => div()
-                            .h(px(28.0))
-                            .px(px(12.0))
-                            .flex()
-                            .items_center()
-                            .justify_between()
-                            .bg(colors::base())
-                            .child(
-                                div()
-                                    .text_color(colors::subtext0())
-                                    .text_size(px(11.0))
-                                    .font_weight(gpui::FontWeight::SEMIBOLD)
-                                    .child(label.clone()),
-                            )
-                            .child(
-                                div()
-                                    .text_color(colors::overlay0())
-                                    .text_size(px(10.0))
-                                    .child(format!("{count}")),
-                            )
-                            .into_any_element(),
-                        ListRow::Item { summary_index } => {
+                        TreeRow::Node {
+                            node,
+                            label,
+                            count,
+                            expanded,
+                            depth,
+                        } => {
+                            let label = match node {
+                                TreeNode::Kind(kind) => glyphs.prefix(kind, label),
+                                TreeNode::Category(..) => label.clone(),
+                            };
+                            let entity_for_toggle = entity.clone();
+
+                            div()
+                                .id(gpui::ElementId::Integer(idx as u64))
+                                .h(px(28.0))
+                                .pl(px(12.0 + 14.0 * *depth as f32))
+                                .pr(px(12.0))
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .bg(if is_selected {
+                                    colors::surface1()
+                                } else {
+                                    colors::base()
+                                })
+                                .cursor_pointer()
+                                .hover(|style| style.bg(colors::surface1()))
+                                .on_click(move |_event, _window, cx| {
+                                    entity_for_toggle.update(cx, |app, cx| {
+                                        app.state.cursor = idx;
+                                        app.state.toggle_node();
+                                        app.sync_list_state();
+                                        cx.notify();
+                                    });
+                                })
+                                .child(
+                                    div()
+                                        .flex_1()
+                                        .min_w(px(0.0))
+                                        .truncate()
+                                        .text_color(colors::subtext0())
+                                        .text_size(px(11.0))
+                                        .font_weight(gpui::FontWeight::SEMIBOLD)
+                                        .child(format!(
+                                            "{} {label}",
+                                            if *expanded { "▾" } else { "▸" }
+                                        )),
+                                )
+                                .child(
+                                    div()
+                                        .flex_shrink_0()
+                                        .text_color(colors::overlay0())
+                                        .text_size(px(10.0))
+                                        .child(format!("{count}")),
+                                )
+                                .into_any_element()
+                        }
+                        TreeRow::Item {
+                            summary_index,
+                            depth,
+                        } => {
                             let summary = &view_summaries[*summary_index];
                             let summary_id = summary.id.clone();
+                            let drag = DefinitionDrag {
+                                id: summary.id.clone(),
+                                name: display_names.get(summary).to_string(),
+                            };
                             let bg = if is_selected {
                                 colors::surface1()
                             } else {
@@ -845,7 +1479,8 @@ impl AgentDefsApp {
                                 .id(gpui::ElementId::Integer(idx as u64))
                                 .w_full()
                                 .h(px(36.0))
-                                .px(px(12.0))
+                                .pl(px(12.0 + 14.0 * *depth as f32))
+                                .pr(px(12.0))
                                 .flex()
                                 .items_center()
                                 .justify_between()
@@ -854,6 +1489,9 @@ impl AgentDefsApp {
                                 .bg(bg)
                                 .cursor_pointer()
                                 .hover(|style| style.bg(colors::surface1()))
+                                .on_drag(drag, |drag, _offset, _window, cx| {
+                                    cx.new(|_| drag.clone())
+                                })
                                 .on_click(move |event, _window, cx| {
                                     // Check for double-click to install
                                     let click_count = match event {
@@ -867,10 +1505,10 @@ impl AgentDefsApp {
                                         app.fetch_current(cx);
 
                                         // Double-click triggers install
-                                        if click_count >= 2 {
-                                            if let Some(summary) = app.state.selected_summary() {
-                                                app.install_by_id(summary.id.clone(), cx);
-                                            }
+                                        if click_count >= 2
+                                            && let Some(summary) = app.state.selected_summary()
+                                        {
+                                            app.install_by_id(summary.id.clone(), cx);
                                         }
                                         cx.notify();
                                     });
@@ -889,9 +1527,9 @@ impl AgentDefsApp {
                                                 .truncate()
                                                 .text_color(name_color)
                                                 .text_size(px(13.0))
-                                                .child(summary.name.clone()),
+                                                .child(glyphs.prefix(&summary.kind, display_names.get(summary))),
                                         )
-                                        .children(summary.description.as_ref().map(|desc| {
+                                        .children(summary.description.as_ref().filter(|_| show_descriptions).map(|desc| {
                                             div()
                                                 .w_full()
                                                 .truncate()
@@ -979,6 +1617,14 @@ impl AgentDefsApp {
             .selected_definition
             .as_ref()
             .map(|d| d.id.clone());
+        let install_target = self
+            .state
+            .install_target
+            .as_ref()
+            .map(|dir| dir.display().to_string());
+        let entity_for_paths = entity.clone();
+        let entity_for_definition = entity.clone();
+        let entity_for_clear = entity.clone();
 
         div()
             .flex()
@@ -986,6 +1632,19 @@ impl AgentDefsApp {
             .flex_1()
             .h_full()
             .bg(colors::base())
+            .drag_over::<ExternalPaths>(|style, _, _, _| style.bg(colors::surface0()))
+            .drag_over::<DefinitionDrag>(|style, _, _, _| style.bg(colors::green_bg()))
+            .on_drop(move |paths: &ExternalPaths, _window, cx| {
+                entity_for_paths.update(cx, |app, cx| {
+                    app.state.set_install_target(paths.paths());
+                    cx.notify();
+                });
+            })
+            .on_drop(move |drag: &DefinitionDrag, _window, cx| {
+                entity_for_definition.update(cx, |app, cx| {
+                    app.install_by_id(drag.id.clone(), cx);
+                });
+            })
             .child(
                 // Detail header
                 div()
@@ -1003,6 +1662,28 @@ impl AgentDefsApp {
                             .font_weight(gpui::FontWeight::SEMIBOLD)
                             .child("DETAILS"),
                     )
+                    // Install target set by dropping a folder; click to go back to asking
+                    .children(install_target.map(|dir| {
+                        div()
+                            .id("install-target")
+                            .flex_1()
+                            .min_w(px(0.0))
+                            .mx(px(12.0))
+                            .truncate()
+                            .text_color(colors::overlay0())
+                            .text_size(px(11.0))
+                            .cursor_pointer()
+                            .hover(|style| style.text_color(colors::text()))
+                            .on_click(move |_event, _window, cx| {
+                                entity_for_clear.update(cx, |app, cx| {
+                                    app.state.install_target = None;
+                                    app.state.status_message =
+                                        Some("Installs will ask for a folder".into());
+                                    cx.notify();
+                                });
+                            })
+                            .child(format!("→ {dir}  ✕"))
+                    }))
                     // Install button in header (when definition is selected)
                     .when(def_id_for_install.is_some(), |el| {
                         let id = def_id_for_install.clone().unwrap();
@@ -1060,7 +1741,10 @@ impl AgentDefsApp {
                                     .flex_wrap()
                                     .gap(px(12.0))
                                     .child(render_badge(
-                                        grouping::kind_label(&def.kind),
+                                        &self
+                                            .state
+                                            .glyphs
+                                            .prefix(&def.kind, grouping::kind_label(&def.kind)),
                                         colors::blue(),
                                         colors::blue_bg(),
                                     ))
@@ -1174,11 +1858,18 @@ impl AgentDefsApp {
 
         let key_hints = match self.state.mode {
             Mode::Normal => {
-                "j/k: navigate | /: search | f: kind | p: source | i: install | s: sync | ⌘K: commands"
+                "j/k: navigate | ←/→: fold | /: search | f: kind | p: source | i: install | s: sync | t: tasks | ⌘K: commands"
             }
             Mode::Search => "type to filter | enter: confirm | esc: cancel",
             Mode::KindFilter | Mode::SourceFilter => "j/k: navigate | enter: select | esc: cancel",
             Mode::CommandPalette => "↑↓: navigate | enter: select | esc: close",
+            Mode::Tasks => "j/k: navigate | x: cancel waiting | t/esc: close",
+            Mode::InstallForm
+                if self.state.install_form.as_ref().is_some_and(|f| f.current().is_none()) =>
+            {
+                "enter: install | esc: cancel"
+            }
+            Mode::InstallForm => "type a value | enter: next | esc: cancel",
         };
 
         let mode_indicator = match self.state.mode {
@@ -1187,6 +1878,8 @@ impl AgentDefsApp {
             Mode::KindFilter => Some("KIND FILTER"),
             Mode::SourceFilter => Some("SOURCE FILTER"),
             Mode::CommandPalette => Some("COMMANDS"),
+            Mode::Tasks => Some("TASKS"),
+            Mode::InstallForm => Some("INSTALL"),
         };
 
         div()
@@ -1276,6 +1969,10 @@ impl AgentDefsApp {
             .children(kinds.iter().enumerate().map(|(idx, kind)| {
                 let is_selected = idx == self.state.filter_cursor;
                 let label = AppState::kind_option_label(kind);
+                let label = match kind {
+                    Some(kind) => self.state.glyphs.prefix(kind, label),
+                    None => label.to_owned(),
+                };
                 let bg = if is_selected {
                     colors::surface1()
                 } else {
@@ -1386,6 +2083,201 @@ impl AgentDefsApp {
             )
     }
 
+    fn render_task_list_overlay(&self) -> impl IntoElement {
+        let tasks = self.state.tasks.tasks();
+
+        div()
+            .absolute()
+            .top(px(120.0))
+            .left(px(50.0))
+            .w(px(420.0))
+            .bg(colors::surface0())
+            .border_1()
+            .border_color(colors::surface1())
+            .rounded(px(8.0))
+            .shadow_lg()
+            .p(px(8.0))
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_color(colors::subtext0())
+                    .text_size(px(11.0))
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .pb(px(4.0))
+                    .child("Background Tasks"),
+            )
+            .when(tasks.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(px(8.0))
+                        .text_color(colors::overlay0())
+                        .text_size(px(13.0))
+                        .child("No refreshes or installs yet"),
+                )
+            })
+            .children(tasks.iter().enumerate().map(|(idx, task)| {
+                let is_selected = idx == self.state.task_cursor;
+                let (detail, color) = match &task.state {
+                    TaskState::Pending => ("waiting".to_owned(), colors::overlay0()),
+                    TaskState::Running => ("running".to_owned(), colors::yellow()),
+                    TaskState::Done(summary) => (summary.clone(), colors::green()),
+                    TaskState::Failed(error) => (error.clone(), colors::peach()),
+                    TaskState::Cancelled => ("cancelled".to_owned(), colors::overlay0()),
+                };
+                let bg = if is_selected {
+                    colors::surface1()
+                } else {
+                    colors::surface0()
+                };
+
+                div()
+                    .id(gpui::ElementId::Name(format!("task-{}", task.id).into()))
+                    .h(px(28.0))
+                    .px(px(8.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .bg(bg)
+                    .rounded(px(4.0))
+                    .child(
+                        div()
+                            .text_color(colors::text())
+                            .text_size(px(13.0))
+                            .child(task.label.clone()),
+                    )
+                    .child(div().text_color(color).text_size(px(11.0)).child(detail))
+            }))
+            .child(
+                div()
+                    .pt(px(8.0))
+                    .border_t_1()
+                    .border_color(colors::surface1())
+                    .mt(px(4.0))
+                    .child(
+                        div()
+                            .text_color(colors::overlay0())
+                            .text_size(px(10.0))
+                            .child("j/k: navigate | x: cancel waiting | t/esc: close"),
+                    ),
+            )
+    }
+
+    fn render_install_form_overlay(&self) -> impl IntoElement {
+        let Some(form) = &self.state.install_form else {
+            return div();
+        };
+        let hint = if form.current().is_some() {
+            "type a value | enter: next | esc: cancel"
+        } else {
+            "enter: install | esc: cancel"
+        };
+
+        div()
+            .absolute()
+            .top(px(120.0))
+            .left(px(50.0))
+            .w(px(420.0))
+            .bg(colors::surface0())
+            .border_1()
+            .border_color(colors::surface1())
+            .rounded(px(8.0))
+            .shadow_lg()
+            .p(px(8.0))
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_color(colors::subtext0())
+                    .text_size(px(11.0))
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .pb(px(4.0))
+                    .child(format!("Install {}", form.def.name)),
+            )
+            .when(form.current().is_none(), |el| {
+                let commands = form.def.hook_commands();
+                let heading = if commands.is_empty() {
+                    "A hook, which may run shell commands".to_owned()
+                } else {
+                    "A hook that runs these shell commands:".to_owned()
+                };
+                el.child(
+                    div()
+                        .px(px(8.0))
+                        .text_color(colors::peach())
+                        .text_size(px(13.0))
+                        .child(heading),
+                )
+                .children(commands.into_iter().map(|command| {
+                    div()
+                        .px(px(8.0))
+                        .text_color(colors::text())
+                        .text_size(px(13.0))
+                        .child(format!("$ {command}"))
+                }))
+            })
+            .children(form.vars.iter().enumerate().map(|(idx, var)| {
+                let is_current = idx == form.field;
+                // Answered fields show what they'll be filled in with.
+                let value = if is_current {
+                    format!("{}▏", form.input)
+                } else if idx < form.field {
+                    let answer = form.values.get(&var.name).or(var.default.as_ref());
+                    answer.cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                let default = var
+                    .default
+                    .as_ref()
+                    .filter(|_| is_current && form.input.is_empty())
+                    .map(|default| format!("default: {default}"));
+                let bg = if is_current {
+                    colors::surface1()
+                } else {
+                    colors::surface0()
+                };
+
+                div()
+                    .h(px(28.0))
+                    .px(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .bg(bg)
+                    .rounded(px(4.0))
+                    .child(
+                        div()
+                            .text_color(colors::mauve())
+                            .text_size(px(13.0))
+                            .child(var.name.clone()),
+                    )
+                    .child(div().text_color(colors::text()).text_size(px(13.0)).child(value))
+                    .children(default.map(|default| {
+                        div()
+                            .text_color(colors::overlay0())
+                            .text_size(px(11.0))
+                            .child(default)
+                    }))
+            }))
+            .child(
+                div()
+                    .pt(px(8.0))
+                    .border_t_1()
+                    .border_color(colors::surface1())
+                    .mt(px(4.0))
+                    .child(
+                        div()
+                            .text_color(colors::overlay0())
+                            .text_size(px(10.0))
+                            .child(hint),
+                    ),
+            )
+    }
+
     fn render_command_palette(&self, entity: Entity<Self>) -> impl IntoElement {
         let commands = self.state.filtered_palette_commands();
         let query = self.state.palette_query.clone();
@@ -1548,9 +2440,10 @@ impl AgentDefsApp {
             "filter_source" => {
                 self.state.mode = Mode::SourceFilter;
                 let sources = self.state.source_options();
+                let current = self.state.current_source_choice();
                 self.state.filter_cursor = sources
                     .iter()
-                    .position(|s| s == &self.state.source_filter)
+                    .position(|s| s == &current)
                     .unwrap_or(0);
             }
             "install" => {
@@ -1559,6 +2452,22 @@ impl AgentDefsApp {
             "sync" => {
                 self.do_sync(cx);
             }
+            "copy_body" => {
+                self.copy_body(cx);
+            }
+            "copy_id" => {
+                self.copy_id(cx);
+            }
+            "new_window" => {
+                self.open_selected_in_new_window(cx);
+            }
+            "density" => {
+                self.toggle_density(cx);
+            }
+            "tasks" => {
+                self.state.mode = Mode::Tasks;
+                self.state.task_cursor = self.state.tasks.tasks().len().saturating_sub(1);
+            }
             "quit" => {
                 cx.quit();
             }
@@ -1610,6 +2519,13 @@ impl Render for AgentDefsApp {
                         this.state.palette_cursor += 1;
                     }
                 }
+                Mode::Tasks => {
+                    let max = this.state.tasks.tasks().len();
+                    if this.state.task_cursor + 1 < max {
+                        this.state.task_cursor += 1;
+                    }
+                }
+                Mode::InstallForm => {}
             }
             cx.notify();
         });
@@ -1636,6 +2552,10 @@ impl Render for AgentDefsApp {
                         this.state.palette_cursor -= 1;
                     }
                 }
+                Mode::Tasks => {
+                    this.state.task_cursor = this.state.task_cursor.saturating_sub(1);
+                }
+                Mode::InstallForm => {}
             }
             cx.notify();
         });
@@ -1663,7 +2583,7 @@ impl Render for AgentDefsApp {
 
         let on_exit_search = cx.listener(|this: &mut Self, _: &ExitSearch, _window, cx| {
             // Reset to normal mode from any overlay
-            this.state.mode = Mode::Normal;
+            this.state.cancel_install_form();
             this.state.filter_cursor = 0;
             this.state.palette_query.clear();
             this.state.palette_cursor = 0;
@@ -1687,6 +2607,8 @@ impl Render for AgentDefsApp {
                 // In normal mode, clear all filters
                 this.state.clear_filters();
                 this.sync_list_state();
+            } else if let Some(form) = &mut this.state.install_form {
+                form.input.pop();
             }
             cx.notify();
         });
@@ -1711,7 +2633,7 @@ impl Render for AgentDefsApp {
                     // Apply the selected source filter
                     let sources = this.state.source_options();
                     if let Some(source) = sources.get(this.state.filter_cursor) {
-                        this.state.set_source_filter(source.clone());
+                        this.state.set_source_choice(source);
                         this.sync_list_state();
                     }
                     this.state.mode = Mode::Normal;
@@ -1725,11 +2647,38 @@ impl Render for AgentDefsApp {
                         this.execute_palette_command(cmd_id, window, cx);
                     }
                 }
-                Mode::Normal => {}
+                Mode::Normal => {
+                    this.state.toggle_node();
+                    this.sync_list_state();
+                }
+                Mode::Tasks => {}
+                Mode::InstallForm => {
+                    if let Some((def, values)) = this.state.submit_install_field() {
+                        this.choose_install_target(def, values, cx);
+                    }
+                }
             }
             cx.notify();
         });
 
+        // Arrow keys only; in search and the palette they do nothing.
+        let on_collapse_node = cx.listener(|this: &mut Self, _: &CollapseNode, _window, cx| {
+            if this.state.mode == Mode::Normal {
+                this.state.collapse_node();
+                this.sync_list_state();
+                this.list_state.scroll_to_reveal_item(this.state.cursor);
+                cx.notify();
+            }
+        });
+
+        let on_expand_node = cx.listener(|this: &mut Self, _: &ExpandNode, _window, cx| {
+            if this.state.mode == Mode::Normal {
+                this.state.expand_node();
+                this.sync_list_state();
+                cx.notify();
+            }
+        });
+
         let on_enter_kind_filter =
             cx.listener(|this: &mut Self, _: &EnterKindFilter, _window, cx| {
                 if this.state.mode == Mode::Normal {
@@ -1760,9 +2709,10 @@ impl Render for AgentDefsApp {
                     this.state.mode = Mode::SourceFilter;
                     // Set cursor to current selection if any
                     let sources = this.state.source_options();
+                    let current = this.state.current_source_choice();
                     this.state.filter_cursor = sources
                         .iter()
-                        .position(|s| s == &this.state.source_filter)
+                        .position(|s| s == &current)
                         .unwrap_or(0);
                     cx.notify();
                 } else if this.state.mode == Mode::Search {
@@ -1794,6 +2744,107 @@ impl Render for AgentDefsApp {
             }
         });
 
+        let on_toggle_tasks = cx.listener(|this: &mut Self, _: &ToggleTasks, _window, cx| {
+            match this.state.mode {
+                Mode::Normal => {
+                    this.state.mode = Mode::Tasks;
+                    this.state.task_cursor = this.state.tasks.tasks().len().saturating_sub(1);
+                }
+                Mode::Tasks => this.state.mode = Mode::Normal,
+                Mode::Search => {
+                    this.state.search_append('t');
+                    this.sync_list_state();
+                }
+                Mode::CommandPalette => {
+                    this.state.palette_query.push('t');
+                    this.state.palette_cursor = 0;
+                }
+                Mode::KindFilter | Mode::SourceFilter | Mode::InstallForm => {}
+            }
+            cx.notify();
+        });
+
+        let on_cancel_task = cx.listener(|this: &mut Self, _: &CancelTask, _window, cx| {
+            match this.state.mode {
+                Mode::Tasks => this.state.cancel_selected_task(),
+                Mode::Search => {
+                    this.state.search_append('x');
+                    this.sync_list_state();
+                }
+                Mode::CommandPalette => {
+                    this.state.palette_query.push('x');
+                    this.state.palette_cursor = 0;
+                }
+                Mode::Normal | Mode::KindFilter | Mode::SourceFilter | Mode::InstallForm => {}
+            }
+            cx.notify();
+        });
+
+        let on_copy_body = cx.listener(|this: &mut Self, _: &CopyBody, _window, cx| {
+            match this.state.mode {
+                Mode::Normal => this.copy_body(cx),
+                Mode::Search => {
+                    this.state.search_append('c');
+                    this.sync_list_state();
+                }
+                Mode::CommandPalette => {
+                    this.state.palette_query.push('c');
+                    this.state.palette_cursor = 0;
+                }
+                Mode::KindFilter | Mode::SourceFilter | Mode::Tasks | Mode::InstallForm => {}
+            }
+            cx.notify();
+        });
+
+        let on_copy_id = cx.listener(|this: &mut Self, _: &CopyId, _window, cx| {
+            match this.state.mode {
+                Mode::Normal => this.copy_id(cx),
+                Mode::Search => {
+                    this.state.search_append('Y');
+                    this.sync_list_state();
+                }
+                Mode::CommandPalette => {
+                    this.state.palette_query.push('Y');
+                    this.state.palette_cursor = 0;
+                }
+                Mode::KindFilter | Mode::SourceFilter | Mode::Tasks | Mode::InstallForm => {}
+            }
+            cx.notify();
+        });
+
+        let on_toggle_density = cx.listener(|this: &mut Self, _: &ToggleDensity, _window, cx| {
+            match this.state.mode {
+                Mode::Normal => this.toggle_density(cx),
+                Mode::Search => {
+                    this.state.search_append('D');
+                    this.sync_list_state();
+                }
+                Mode::CommandPalette => {
+                    this.state.palette_query.push('D');
+                    this.state.palette_cursor = 0;
+                }
+                Mode::KindFilter | Mode::SourceFilter | Mode::Tasks | Mode::InstallForm => {}
+            }
+            cx.notify();
+        });
+
+        let on_open_in_new_window =
+            cx.listener(|this: &mut Self, _: &OpenInNewWindow, _window, cx| {
+                match this.state.mode {
+                    Mode::Normal => this.open_selected_in_new_window(cx),
+                    Mode::Search => {
+                        this.state.search_append('n');
+                        this.sync_list_state();
+                    }
+                    Mode::CommandPalette => {
+                        this.state.palette_query.push('n');
+                        this.state.palette_cursor = 0;
+                    }
+                    Mode::KindFilter | Mode::SourceFilter | Mode::Tasks | Mode::InstallForm => {}
+                }
+                cx.notify();
+            });
+
         let on_quit = cx.listener(|this: &mut Self, _: &Quit, _window, cx| {
             if this.state.mode == Mode::Search {
                 // 'q' key in search mode - type it instead of quitting
@@ -1855,6 +2906,15 @@ impl Render for AgentDefsApp {
                             this.state.palette_cursor = 0;
                             cx.notify();
                         }
+                        Mode::InstallForm => {
+                            if let Some(form) = &mut this.state.install_form
+                                && form.current().is_some()
+                            {
+                                form.input
+                                    .extend(key_char.chars().filter(|ch| !ch.is_control()));
+                            }
+                            cx.notify();
+                        }
                         _ => {}
                     }
                 }
@@ -1864,6 +2924,14 @@ impl Render for AgentDefsApp {
         let show_kind_filter = self.state.mode == Mode::KindFilter;
         let show_source_filter = self.state.mode == Mode::SourceFilter;
         let show_command_palette = self.state.mode == Mode::CommandPalette;
+        let show_tasks = self.state.mode == Mode::Tasks;
+        let show_install_form = self.state.mode == Mode::InstallForm;
+        // Letter bindings would eat the form's typing, so it has keys of its own.
+        let key_context = if show_install_form {
+            "InstallForm"
+        } else {
+            "AgentDefsApp"
+        };
 
         // Show resize cursor when dragging
         let is_dragging = self.is_dragging_divider;
@@ -1872,7 +2940,7 @@ impl Render for AgentDefsApp {
 
         div()
             .id("root")
-            .key_context("AgentDefsApp")
+            .key_context(key_context)
             .track_focus(&self.focus_handle)
             .flex()
             .flex_col()
@@ -1921,6 +2989,14 @@ impl Render for AgentDefsApp {
             .on_action(on_enter_source_filter)
             .on_action(on_install)
             .on_action(on_toggle_palette)
+            .on_action(on_toggle_tasks)
+            .on_action(on_cancel_task)
+            .on_action(on_copy_body)
+            .on_action(on_copy_id)
+            .on_action(on_toggle_density)
+            .on_action(on_open_in_new_window)
+            .on_action(on_collapse_node)
+            .on_action(on_expand_node)
             .on_key_down(on_key_down)
             .child(
                 div()
@@ -1939,6 +3015,10 @@ impl Render for AgentDefsApp {
             .when(show_source_filter, |el| {
                 el.child(self.render_source_filter_overlay())
             })
+            .when(show_tasks, |el| el.child(self.render_task_list_overlay()))
+            .when(show_install_form, |el| {
+                el.child(self.render_install_form_overlay())
+            })
             // Command palette overlay
             .when(show_command_palette, |el| {
                 el.child(self.render_command_palette(entity))
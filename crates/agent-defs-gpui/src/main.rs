@@ -1,18 +1,13 @@
 mod app;
-mod grouping;
 
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use agent_defs::demo::DemoProvider;
 use agent_defs::{CompositeSource, Source};
 use agent_defs_store::DefinitionStore;
 use anyhow::{Context, Result};
-use gpui::{
-    App, Application, Bounds, Menu, MenuItem, TitlebarOptions, WindowBounds, WindowOptions,
-    actions, point, prelude::*, px, size,
-};
-
-use crate::app::AgentDefsApp;
+use gpui::{App, Application, Menu, MenuItem, actions};
 
 actions!(
     agent_defs_gui,
@@ -29,6 +24,14 @@ actions!(
         EnterSourceFilter,
         Install,
         ToggleCommandPalette,
+        ToggleTasks,
+        CancelTask,
+        CopyBody,
+        CopyId,
+        OpenInNewWindow,
+        ToggleDensity,
+        CollapseNode,
+        ExpandNode,
     ]
 );
 
@@ -47,24 +50,42 @@ fn db_path() -> Result<PathBuf> {
     Ok(cache_dir()?.join("definitions.db"))
 }
 
+/// Open the store for `label`, refusing what was synced under a `[signing]`
+/// policy other than the config's.
 fn build_store(label: &str) -> Result<DefinitionStore> {
     let path = db_path()?;
-    DefinitionStore::open(&path, label).map_err(|e| anyhow::anyhow!("{e}"))
+    let store = DefinitionStore::open(&path, label).map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(match agent_defs_cli::config::load_config().signing.fingerprint() {
+        Some(fingerprint) => store.with_signing_policy(fingerprint),
+        None => store,
+    })
+}
+
+/// The built-in sample definitions, synced into a store that lives as long
+/// as the app, for `--demo`.
+fn build_demo_source() -> Result<Arc<dyn Source>> {
+    let store = DefinitionStore::open_in_memory(agent_defs::demo::LABEL)
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    // The window opens once this returns, so there's no progress to watch.
+    let provider = DemoProvider::default().with_latency(std::time::Duration::ZERO);
+    tokio::runtime::Builder::new_current_thread()
+        .build()?
+        .block_on(store.sync(&provider))?;
+    Ok(Arc::new(store))
 }
 
 /// Build a composite source from all known source labels.
 fn build_composite_source() -> Result<Arc<dyn Source>> {
-    let stores: Vec<Arc<dyn Source>> = SOURCE_LABELS
+    let stores: Vec<DefinitionStore> = SOURCE_LABELS
         .iter()
         .filter_map(|label| build_store(label).ok())
-        .map(|s| Arc::new(s) as Arc<dyn Source>)
         .collect();
 
     if stores.is_empty() {
         anyhow::bail!("No stores could be opened");
     }
 
-    Ok(Arc::new(CompositeSource::new(stores)))
+    Ok(Arc::new(CompositeSource::from_sources(stores)))
 }
 
 fn main() {
@@ -84,6 +105,9 @@ fn main() {
                 items: vec![
                     MenuItem::action("Sync Definitions", Sync),
                     MenuItem::action("Install Selected", Install),
+                    MenuItem::separator(),
+                    MenuItem::action("Copy Body", CopyBody),
+                    MenuItem::action("Copy ID", CopyId),
                 ],
             },
             Menu {
@@ -94,6 +118,10 @@ fn main() {
                     MenuItem::action("Filter by Source", EnterSourceFilter),
                     MenuItem::separator(),
                     MenuItem::action("Clear Filters", ClearFilters),
+                    MenuItem::action("Background Tasks", ToggleTasks),
+                    MenuItem::action("Show or Hide Descriptions", ToggleDensity),
+                    MenuItem::separator(),
+                    MenuItem::action("Open in New Window", OpenInNewWindow),
                 ],
             },
         ]);
@@ -107,15 +135,27 @@ fn main() {
             gpui::KeyBinding::new("f", EnterKindFilter, Some("AgentDefsApp")),
             gpui::KeyBinding::new("p", EnterSourceFilter, Some("AgentDefsApp")),
             gpui::KeyBinding::new("i", Install, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("t", ToggleTasks, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("x", CancelTask, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("c", CopyBody, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("shift-y", CopyId, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("shift-d", ToggleDensity, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("n", OpenInNewWindow, Some("AgentDefsApp")),
             gpui::KeyBinding::new("/", EnterSearch, Some("AgentDefsApp")),
             gpui::KeyBinding::new("down", MoveDown, Some("AgentDefsApp")),
             gpui::KeyBinding::new("up", MoveUp, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("left", CollapseNode, Some("AgentDefsApp")),
+            gpui::KeyBinding::new("right", ExpandNode, Some("AgentDefsApp")),
             gpui::KeyBinding::new("escape", ExitSearch, Some("AgentDefsApp")),
             gpui::KeyBinding::new("enter", SelectItem, Some("AgentDefsApp")),
             gpui::KeyBinding::new("backspace", ClearFilters, Some("AgentDefsApp")),
             // Command palette - cmd+k on mac, ctrl+k elsewhere
             gpui::KeyBinding::new("cmd-k", ToggleCommandPalette, Some("AgentDefsApp")),
             gpui::KeyBinding::new("ctrl-k", ToggleCommandPalette, Some("AgentDefsApp")),
+            // The install form takes typing, so only its own keys are bound
+            gpui::KeyBinding::new("escape", ExitSearch, Some("InstallForm")),
+            gpui::KeyBinding::new("enter", SelectItem, Some("InstallForm")),
+            gpui::KeyBinding::new("backspace", ClearFilters, Some("InstallForm")),
             // Standard macOS shortcuts
             gpui::KeyBinding::new("cmd-q", Quit, None),
         ]);
@@ -124,34 +164,22 @@ fn main() {
             cx.quit();
         });
 
-        let bounds = Bounds::centered(None, size(px(1200.0), px(800.0)), cx);
-
-        cx.open_window(
-            WindowOptions {
-                window_bounds: Some(WindowBounds::Windowed(bounds)),
-                titlebar: Some(TitlebarOptions {
-                    title: Some("Agent Defs Browser".into()),
-                    appears_transparent: false,
-                    traffic_light_position: Some(point(px(9.0), px(9.0))),
-                }),
-                focus: true,
-                show: true,
-                ..Default::default()
-            },
-            |_window, cx| {
-                // Build composite source from all known labels
-                let source = match build_composite_source() {
-                    Ok(s) => s,
-                    Err(e) => {
-                        eprintln!("Failed to open stores: {e}");
-                        panic!("Failed to open stores: {e}");
-                    }
-                };
-
-                cx.new(|cx| AgentDefsApp::new(source, cx))
-            },
-        )
-        .expect("Failed to open window");
+        // Build composite source from all known labels; every window shares it
+        let demo = std::env::args().skip(1).any(|arg| arg == "--demo");
+        let source = if demo {
+            build_demo_source()
+        } else {
+            build_composite_source()
+        };
+        let source = match source {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to open stores: {e}");
+                panic!("Failed to open stores: {e}");
+            }
+        };
+
+        app::open_window(source, None, cx).expect("Failed to open window");
 
         // Bring app to foreground
         cx.activate(true);
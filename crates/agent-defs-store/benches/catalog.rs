@@ -0,0 +1,50 @@
+//! Syncing, listing, and searching a store holding the synthetic corpus.
+//!
+//! Run with `cargo bench -p agent-defs-store --bench catalog`.
+
+use agent_defs::Source;
+use agent_defs::fixtures::{self, BENCH_DEFINITIONS, RARE_WORD};
+use agent_defs::test_support::FakeSyncProvider;
+use agent_defs_store::DefinitionStore;
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+
+fn catalog(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let provider = FakeSyncProvider::new("bench").with_files(fixtures::corpus(BENCH_DEFINITIONS));
+
+    let mut group = c.benchmark_group("store");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(BENCH_DEFINITIONS as u64));
+    group.bench_function("sync", |b| {
+        b.iter_batched(
+            || DefinitionStore::open_in_memory("bench").unwrap(),
+            |store| runtime.block_on(store.sync(&provider)).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+
+    let store = DefinitionStore::open_in_memory("bench").unwrap();
+    runtime.block_on(store.sync(&provider)).unwrap();
+
+    group.bench_function("list", |b| {
+        b.iter(|| runtime.block_on(store.list()).unwrap())
+    });
+    group.bench_function("search (name)", |b| {
+        b.iter(|| runtime.block_on(store.search("security-helper")).unwrap())
+    });
+    group.bench_function("search (rare body word)", |b| {
+        b.iter(|| runtime.block_on(store.search(RARE_WORD)).unwrap())
+    });
+    group.bench_function("search (phrase and exclusion)", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(store.search("\"smaller follow-ups\" !mobile"))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, catalog);
+criterion_main!(benches);
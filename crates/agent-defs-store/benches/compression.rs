@@ -0,0 +1,47 @@
+//! Reading definitions back out of the compressed blob store, with the
+//! on-disk size of the synthetic corpus printed before the timings.
+//!
+//! Run with `cargo bench -p agent-defs-store --bench compression`.
+
+use agent_defs::fixtures::{self, BENCH_DEFINITIONS};
+use agent_defs::test_support::FakeSyncProvider;
+use agent_defs::{DefinitionId, Source};
+use agent_defs_store::DefinitionStore;
+use criterion::{Criterion, criterion_group, criterion_main};
+
+fn compression(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let files = fixtures::corpus(BENCH_DEFINITIONS);
+    // Fetch the largest agent. Skills are passed over because a skill's ID
+    // is its directory rather than the path of its file.
+    let largest = files
+        .iter()
+        .filter(|file| file.relative_path.starts_with("agents/"))
+        .max_by_key(|file| file.content.len())
+        .unwrap();
+    let id = DefinitionId::new(&largest.relative_path);
+    let provider = FakeSyncProvider::new("bench").with_files(files);
+    let store = DefinitionStore::open_in_memory("bench").unwrap();
+    runtime.block_on(store.sync(&provider)).unwrap();
+
+    let stats = store.blob_stats().unwrap();
+    println!(
+        "blobs: {} blobs, {} bytes stored / {} bytes content ({:.1}x)",
+        stats.blobs,
+        stats.stored_bytes,
+        stats.content_bytes,
+        stats.content_bytes as f64 / stats.stored_bytes.max(1) as f64
+    );
+
+    let mut group = c.benchmark_group("compression");
+    group.bench_function("fetch", |b| {
+        b.iter(|| runtime.block_on(store.fetch(&id)).unwrap())
+    });
+    group.bench_function("fetch_raw", |b| {
+        b.iter(|| runtime.block_on(store.fetch_raw(&id)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, compression);
+criterion_main!(benches);
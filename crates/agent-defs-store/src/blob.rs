@@ -0,0 +1,97 @@
+//! Content-addressed blob storage helpers.
+//!
+//! Blobs are keyed by the SHA-256 of their text and stored either as plain
+//! text or zstd-compressed bytes, whichever is smaller. Queries read them
+//! through the `blob_text(content, encoding)` SQL function so compression
+//! stays invisible to everything above the store.
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::{Value, ValueRef};
+
+use crate::store::StoreError;
+
+/// zstd level used for new blobs. Level 3 is zstd's own default and keeps
+/// sync fast while still shrinking markdown several-fold.
+const COMPRESSION_LEVEL: i32 = 3;
+
+const PLAIN: &str = "plain";
+const ZSTD: &str = "zstd";
+
+/// Register the `blob_text` SQL function on a connection.
+pub(crate) fn register_functions(conn: &rusqlite::Connection) -> Result<(), StoreError> {
+    conn.create_scalar_function(
+        "blob_text",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let encoding: String = ctx.get(1)?;
+            let bytes = match ctx.get_raw(0) {
+                ValueRef::Text(b) | ValueRef::Blob(b) => b,
+                _ => &[],
+            };
+            decode(bytes, &encoding).map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+        },
+    )
+    .map_err(|e| StoreError::Database(e.to_string()))
+}
+
+/// Store `content` in the blobs table if it isn't already there and return
+/// its SHA-256 hex digest.
+pub(crate) fn insert_blob(
+    conn: &rusqlite::Connection,
+    content: &str,
+) -> Result<String, StoreError> {
+    let hash = content_hash(content);
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM blobs WHERE hash = ?1)",
+            [&hash],
+            |row| row.get(0),
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+    if exists {
+        return Ok(hash);
+    }
+
+    let (stored, encoding) = encode(content)?;
+    conn.execute(
+        "INSERT INTO blobs (hash, content, encoding) VALUES (?1, ?2, ?3)",
+        rusqlite::params![hash, stored, encoding],
+    )
+    .map_err(|e| StoreError::Database(e.to_string()))?;
+    Ok(hash)
+}
+
+/// Pick the smaller of plain text and zstd for `content`.
+pub(crate) fn encode(content: &str) -> Result<(Value, &'static str), StoreError> {
+    let compressed = zstd::encode_all(content.as_bytes(), COMPRESSION_LEVEL)
+        .map_err(|e| StoreError::Database(format!("compression failed: {e}")))?;
+
+    if compressed.len() < content.len() {
+        Ok((Value::Blob(compressed), ZSTD))
+    } else {
+        Ok((Value::Text(content.to_owned()), PLAIN))
+    }
+}
+
+/// Turn stored blob bytes back into text.
+pub(crate) fn decode(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    let text = match encoding {
+        PLAIN => bytes.to_vec(),
+        ZSTD => zstd::decode_all(bytes).map_err(|e| format!("decompression failed: {e}"))?,
+        other => return Err(format!("unknown blob encoding: {other}")),
+    };
+    String::from_utf8(text).map_err(|e| format!("blob is not valid UTF-8: {e}"))
+}
+
+fn content_hash(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
@@ -1,4 +1,9 @@
+mod blob;
 pub mod schema;
 pub mod store;
 
-pub use store::{DefinitionStore, StoreError, SyncReport, SyncStatus};
+pub use schema::MigrationReport;
+pub use store::{
+    BlobStats, CountBy, DEFAULT_MAX_FILE_BYTES, DefinitionStore, ReparseReport, Reparsed,
+    SourceHealth, StoreError, SyncReport, SyncStatus,
+};
@@ -1,8 +1,27 @@
-use rusqlite_migration::{Migrations, M};
+use std::path::{Path, PathBuf};
+
+use rusqlite_migration::{Migrations, SchemaVersion, M};
+
+use crate::blob;
+use crate::store::StoreError;
+
+/// Key in `store_meta` holding the version of the binary that last migrated
+/// the database.
+const APP_VERSION_KEY: &str = "app_version";
 
 pub fn migrations() -> Migrations<'static> {
-    Migrations::new(vec![M::up(
-        "CREATE TABLE sources (
+    Migrations::new(migration_list())
+}
+
+/// Schema version produced by the newest migration this build knows about.
+pub fn latest_version() -> usize {
+    migration_list().len()
+}
+
+fn migration_list() -> Vec<M<'static>> {
+    vec![
+        M::up(
+            "CREATE TABLE sources (
             label           TEXT PRIMARY KEY,
             last_synced_at  TEXT
         );
@@ -25,5 +44,269 @@ pub fn migrations() -> Migrations<'static> {
 
         CREATE INDEX idx_definitions_kind ON definitions(kind);
         CREATE INDEX idx_definitions_name ON definitions(name);",
-    )])
+        ),
+        // Move body and raw content into a content-addressed `blobs` table so
+        // identical files across sources are stored once. The cache is rebuilt
+        // rather than rehashed: definitions are dropped and every source is
+        // marked unsynced so the next command repopulates it.
+        M::up(
+            "CREATE TABLE blobs (
+            hash            TEXT PRIMARY KEY,
+            content         TEXT NOT NULL
+        );
+
+        DROP TABLE definitions;
+
+        CREATE TABLE definitions (
+            id              TEXT NOT NULL,
+            source_label    TEXT NOT NULL,
+            name            TEXT NOT NULL,
+            description     TEXT,
+            kind            TEXT NOT NULL,
+            category        TEXT,
+            body_hash       TEXT NOT NULL,
+            tools_json      TEXT NOT NULL DEFAULT '[]',
+            model           TEXT,
+            metadata_json   TEXT NOT NULL DEFAULT '{}',
+            raw_hash        TEXT NOT NULL,
+            PRIMARY KEY (source_label, id),
+            FOREIGN KEY (source_label) REFERENCES sources(label),
+            FOREIGN KEY (body_hash) REFERENCES blobs(hash),
+            FOREIGN KEY (raw_hash) REFERENCES blobs(hash)
+        );
+
+        CREATE INDEX idx_definitions_kind ON definitions(kind);
+        CREATE INDEX idx_definitions_name ON definitions(name);
+
+        UPDATE sources SET last_synced_at = NULL;",
+        ),
+        // Blobs may now be zstd-compressed. Existing rows stay readable as
+        // `plain`; new writes pick whichever encoding is smaller.
+        M::up("ALTER TABLE blobs ADD COLUMN encoding TEXT NOT NULL DEFAULT 'plain';"),
+        M::up(
+            "CREATE TABLE store_meta (
+            key             TEXT PRIMARY KEY,
+            value           TEXT NOT NULL
+        );",
+        ),
+        // Per-sync changelog: what each sync added, removed, or changed
+        // relative to the one before it.
+        M::up(
+            "CREATE TABLE changes (
+            source_label    TEXT NOT NULL,
+            synced_at       INTEGER NOT NULL,
+            id              TEXT NOT NULL,
+            name            TEXT NOT NULL,
+            kind            TEXT NOT NULL,
+            change          TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_changes_source_time ON changes(source_label, synced_at);",
+        ),
+        // Local view and install counts behind `stats --personal`. Kept
+        // apart from `definitions` so they survive the wipe-and-refill sync.
+        M::up(
+            "CREATE TABLE usage (
+            source_label    TEXT NOT NULL,
+            id              TEXT NOT NULL,
+            views           INTEGER NOT NULL DEFAULT 0,
+            installs        INTEGER NOT NULL DEFAULT 0,
+            last_used       INTEGER NOT NULL,
+            PRIMARY KEY (source_label, id)
+        );",
+        ),
+        // Personal notes, written by the user and never touched by sync.
+        M::up(
+            "CREATE TABLE notes (
+            source_label    TEXT NOT NULL,
+            id              TEXT NOT NULL,
+            text            TEXT NOT NULL,
+            updated_at      INTEGER NOT NULL,
+            PRIMARY KEY (source_label, id)
+        );",
+        ),
+        // Files the latest sync of each source skipped, with the reason, so
+        // they can be reviewed after the sync output is gone.
+        M::up(
+            "CREATE TABLE skipped_files (
+            source_label    TEXT NOT NULL,
+            path            TEXT NOT NULL,
+            reason          TEXT NOT NULL,
+            recorded_at     INTEGER NOT NULL,
+            PRIMARY KEY (source_label, path)
+        );",
+        ),
+        // Bulk installs in progress. Rows are written before any file is
+        // and deleted once the install finishes, so whatever is left after
+        // a crash is what needs reconciling.
+        M::up(
+            "CREATE TABLE install_batches (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            label           TEXT NOT NULL,
+            target          TEXT NOT NULL,
+            started_at      INTEGER NOT NULL
+        );
+
+        CREATE TABLE install_journal (
+            batch           INTEGER NOT NULL,
+            position        INTEGER NOT NULL,
+            source_label    TEXT NOT NULL,
+            id              TEXT NOT NULL,
+            path            TEXT,
+            previous        TEXT,
+            state           TEXT NOT NULL,
+            error           TEXT,
+            PRIMARY KEY (batch, position)
+        );",
+        ),
+        // Community ratings, replaced wholesale each time a source's index
+        // is fetched. Like notes, rows aren't tied to definitions still being
+        // in the source.
+        M::up(
+            "CREATE TABLE ratings (
+            source_label    TEXT NOT NULL,
+            id              TEXT NOT NULL,
+            score           REAL,
+            votes           INTEGER NOT NULL,
+            verified        INTEGER NOT NULL,
+            PRIMARY KEY (source_label, id)
+        );",
+        ),
+        // When each definition first appeared and when its file last
+        // changed, in Unix seconds. Existing rows are dated from whatever
+        // the changelog still remembers and left NULL otherwise.
+        M::up(
+            "ALTER TABLE definitions ADD COLUMN first_seen_at INTEGER;
+        ALTER TABLE definitions ADD COLUMN last_updated_at INTEGER;
+
+        UPDATE definitions SET
+            first_seen_at = (
+                SELECT MIN(c.synced_at) FROM changes c
+                WHERE c.source_label = definitions.source_label AND c.id = definitions.id
+                  AND c.change = 'added'
+            ),
+            last_updated_at = (
+                SELECT MAX(c.synced_at) FROM changes c
+                WHERE c.source_label = definitions.source_label AND c.id = definitions.id
+                  AND c.change IN ('added', 'changed')
+            );",
+        ),
+        // The revision, such as a commit SHA, each source's latest sync got,
+        // for lockfiles to pin. Unknown until the next sync.
+        M::up("ALTER TABLE sources ADD COLUMN revision TEXT;"),
+        // Content kept for later three-way merges, such as a file as it was
+        // installed, which pruning must leave alone even after no
+        // definition references it.
+        M::up("CREATE TABLE kept_blobs (hash TEXT PRIMARY KEY NOT NULL);"),
+        // Sources whose syncs have been failing: how many times in a row,
+        // and the latest error. A successful sync deletes the row.
+        M::up(
+            "CREATE TABLE sync_failures (
+            source_label    TEXT PRIMARY KEY NOT NULL,
+            streak          INTEGER NOT NULL,
+            last_error      TEXT NOT NULL,
+            last_failed_at  INTEGER NOT NULL
+        );",
+        ),
+        // The signing policy each definition was synced under, so content
+        // cached before the policy changed isn't served as if it passed it.
+        // NULL when no policy was in force.
+        M::up("ALTER TABLE definitions ADD COLUMN signing_policy TEXT;"),
+    ]
+}
+
+/// Outcome of bringing a database up to date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Schema version before migrating (0 for a fresh database).
+    pub from: usize,
+    /// Schema version after migrating.
+    pub to: usize,
+    /// Where the pre-migration snapshot was written, if one was taken.
+    pub backup: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    /// Whether any migrations were applied.
+    pub fn migrated(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// Read a database's schema version without migrating it.
+pub fn current_version(conn: &rusqlite::Connection) -> Result<usize, StoreError> {
+    let version = migrations()
+        .current_version(conn)
+        .map_err(|e| StoreError::Migration(e.to_string()))?;
+    Ok(match version {
+        SchemaVersion::NoneSet => 0,
+        SchemaVersion::Inside(n) | SchemaVersion::Outside(n) => n.get(),
+    })
+}
+
+/// Bring a connection up to the latest schema and record which build did it.
+///
+/// Databases written by a newer build are rejected with
+/// [`StoreError::SchemaTooNew`] instead of being opened half-understood.
+pub fn apply(conn: &mut rusqlite::Connection) -> Result<MigrationReport, StoreError> {
+    let from = current_version(conn)?;
+    let supported = latest_version();
+    if from > supported {
+        return Err(StoreError::SchemaTooNew {
+            found: from,
+            supported,
+            written_by: written_by(conn),
+        });
+    }
+
+    migrations()
+        .to_latest(conn)
+        .map_err(|e| StoreError::Migration(e.to_string()))?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO store_meta (key, value) VALUES (?1, ?2)",
+        [APP_VERSION_KEY, env!("CARGO_PKG_VERSION")],
+    )
+    .map_err(|e| StoreError::Database(e.to_string()))?;
+
+    Ok(MigrationReport {
+        from,
+        to: supported,
+        backup: None,
+    })
+}
+
+/// Migrate the database file at `path` to the latest schema.
+///
+/// When `backup` is given and migrations are pending, the file is copied
+/// there first so the pre-migration cache can be restored by hand.
+pub fn migrate_file(path: &Path, backup: Option<&Path>) -> Result<MigrationReport, StoreError> {
+    let mut conn =
+        rusqlite::Connection::open(path).map_err(|e| StoreError::Database(e.to_string()))?;
+    blob::register_functions(&conn)?;
+
+    let from = current_version(&conn)?;
+    let mut backup_path = None;
+    if let Some(dest) = backup
+        && from < latest_version()
+    {
+        std::fs::copy(path, dest).map_err(|e| {
+            StoreError::Database(format!("failed to back up {}: {e}", path.display()))
+        })?;
+        backup_path = Some(dest.to_path_buf());
+    }
+
+    let mut report = apply(&mut conn)?;
+    report.backup = backup_path;
+    Ok(report)
+}
+
+/// The app version recorded by whichever build last migrated the database.
+fn written_by(conn: &rusqlite::Connection) -> Option<String> {
+    conn.query_row(
+        "SELECT value FROM store_meta WHERE key = ?1",
+        [APP_VERSION_KEY],
+        |row| row.get(0),
+    )
+    .ok()
 }
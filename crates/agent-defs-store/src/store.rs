@@ -1,12 +1,18 @@
-use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use agent_defs::{
-    Definition, DefinitionId, DefinitionKind, DefinitionSummary, Feedback, Source, SourceError,
-    SyncError, SyncProvider,
+    BatchId, CategoryMap, ChangeKind, Definition, DefinitionChange, DefinitionId, DefinitionKind,
+    DefinitionNote, DefinitionSummary, DefinitionUsage, Feedback, FileCheck, InstallBatch,
+    JournalEntry,
+    JournalState, RawDefinitionFile, Rating, SkippedFile, Source, SourceError, SyncError,
+    SyncProvider, TextQuery, UsageKind,
 };
 
+use rusqlite::OptionalExtension;
+
+use crate::blob::{self, insert_blob};
 use crate::schema;
 
 /// How fresh the local cache is for a given source.
@@ -20,10 +26,20 @@ pub enum SyncStatus {
 /// Threshold in days before cache is considered stale.
 const STALE_THRESHOLD_DAYS: u64 = 7;
 
+/// How long changelog entries are kept before sync prunes them.
+const CHANGELOG_RETENTION_DAYS: u64 = 90;
+
+/// Default ceiling on the size of a single definition file accepted by sync.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
 /// A SQLite-backed definition store that implements `Source`.
 pub struct DefinitionStore {
     conn: Mutex<rusqlite::Connection>,
     label: String,
+    max_file_bytes: u64,
+    categories: CategoryMap,
+    file_check: Option<Arc<dyn FileCheck>>,
+    signing_policy: Option<String>,
 }
 
 impl DefinitionStore {
@@ -31,33 +47,103 @@ impl DefinitionStore {
     pub fn open(path: &Path, label: impl Into<String>) -> Result<Self, StoreError> {
         let conn = rusqlite::Connection::open(path)
             .map_err(|e| StoreError::Database(e.to_string()))?;
+        blob::register_functions(&conn)?;
 
         let mut store = Self {
             conn: Mutex::new(conn),
             label: label.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            categories: CategoryMap::default(),
+            file_check: None,
+            signing_policy: None,
         };
         store.migrate()?;
         Ok(store)
     }
 
+    /// Labels of the sources with definitions cached in the database at
+    /// `path`, in alphabetical order, for opening a store per source
+    /// without the config that named them.
+    pub fn labels_in(path: &Path) -> Result<Vec<String>, StoreError> {
+        let mut conn = rusqlite::Connection::open(path)
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        blob::register_functions(&conn)?;
+        schema::apply(&mut conn)?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT label FROM sources
+                 WHERE EXISTS (SELECT 1 FROM definitions WHERE source_label = label)
+                 ORDER BY label",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        stmt.query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(|e| StoreError::Database(e.to_string()))
+    }
+
     /// Open an in-memory store (for testing).
     pub fn open_in_memory(label: impl Into<String>) -> Result<Self, StoreError> {
         let conn = rusqlite::Connection::open_in_memory()
             .map_err(|e| StoreError::Database(e.to_string()))?;
+        blob::register_functions(&conn)?;
 
         let mut store = Self {
             conn: Mutex::new(conn),
             label: label.into(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            categories: CategoryMap::default(),
+            file_check: None,
+            signing_policy: None,
         };
         store.migrate()?;
         Ok(store)
     }
 
+    /// `id` within this source. Lookups accept IDs qualified with this
+    /// store's label as well as bare ones.
+    fn local_id(&self, id: &DefinitionId) -> DefinitionId {
+        id.split_source([self.label.as_str()])
+            .map_or_else(|| id.clone(), |(_, id)| id)
+    }
+
+    /// Set how sync normalizes categories. Without this only the built-in
+    /// synonyms apply.
+    pub fn with_categories(mut self, categories: CategoryMap) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    /// Set the largest file (in bytes) that sync will store. Larger files
+    /// are skipped with a warning instead of bloating the cache.
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// The largest file (in bytes) that sync will store.
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_bytes
+    }
+
+    /// Have sync run `check` on each definition file and skip the ones it
+    /// rejects, such as files without a valid signature.
+    pub fn with_file_check(mut self, check: Arc<dyn FileCheck>) -> Self {
+        self.file_check = Some(check);
+        self
+    }
+
+    /// Have sync record that its definitions were checked under `policy`,
+    /// an identifier for the signing policy in force, and refuse to fetch
+    /// definitions synced under any other.
+    pub fn with_signing_policy(mut self, policy: impl Into<String>) -> Self {
+        self.signing_policy = Some(policy.into());
+        self
+    }
+
     fn migrate(&mut self) -> Result<(), StoreError> {
         let conn = self.conn.get_mut().unwrap();
-        schema::migrations()
-            .to_latest(conn)
-            .map_err(|e| StoreError::Migration(e.to_string()))?;
+        schema::apply(conn)?;
 
         // Ensure the source row exists (with NULL last_synced_at initially).
         conn.execute(
@@ -95,36 +181,150 @@ impl DefinitionStore {
         }
     }
 
-    /// Insert or replace a definition row. Used by sync.
+    /// Insert a definition row, or update the existing row with the same ID
+    /// in place.
+    ///
+    /// Body and raw content go into the shared `blobs` table keyed by
+    /// content hash, so byte-identical files are only stored once. Blobs are
+    /// zstd-compressed whenever that makes them smaller.
     pub fn upsert_definition(&self, def: &Definition) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
+        upsert(&conn, def)
+    }
 
-        let tools_json =
-            serde_json::to_string(&def.tools).map_err(|e| StoreError::Database(e.to_string()))?;
-        let metadata_json = serde_json::to_string(&def.metadata)
+    /// Make this source's definitions exactly `defs`, in one transaction:
+    /// existing rows are updated in place, new ones inserted, and rows whose
+    /// IDs are gone deleted. Readers never see the source half synced or
+    /// empty, and surviving rows keep their rowids.
+    fn replace_definitions(&self, defs: &[Definition]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
             .map_err(|e| StoreError::Database(e.to_string()))?;
 
+        for def in defs {
+            upsert(&tx, def)?;
+        }
+
+        let keep: HashSet<&str> = defs.iter().map(|d| d.id.as_str()).collect();
+        let existing: Vec<String> = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM definitions WHERE source_label = ?1")
+                .map_err(|e| StoreError::Database(e.to_string()))?;
+            stmt.query_map([&self.label], |row| row.get(0))
+                .map_err(|e| StoreError::Database(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect()
+        };
+        for id in existing.iter().filter(|id| !keep.contains(id.as_str())) {
+            tx.execute(
+                "DELETE FROM definitions WHERE source_label = ?1 AND id = ?2",
+                rusqlite::params![&self.label, id],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+        tx.execute(
+            "UPDATE definitions SET signing_policy = ?2 WHERE source_label = ?1",
+            rusqlite::params![&self.label, &self.signing_policy],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// Refuse `id` when a signing policy is in force and its row was synced
+    /// under another, or under none. Missing rows are left to the caller.
+    fn check_signing_policy(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &DefinitionId,
+    ) -> Result<(), SourceError> {
+        let Some(policy) = &self.signing_policy else {
+            return Ok(());
+        };
+        let synced_under: Option<Option<String>> = conn
+            .query_row(
+                "SELECT signing_policy FROM definitions WHERE source_label = ?1 AND id = ?2",
+                rusqlite::params![&self.label, id.as_str()],
+                |row| row.get(0),
+            )
+            .ok();
+        match synced_under {
+            Some(synced_under) if synced_under.as_deref() != Some(policy.as_str()) => {
+                Err(SourceError::Other(format!(
+                    "{id} was synced before the current [signing] policy; sync {} again to use it",
+                    self.label
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete blobs no longer referenced by any definition in any source
+    /// and not kept with [`Self::keep_content`]. Returns the number of
+    /// blobs removed.
+    pub fn prune_blobs(&self) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO definitions
-                (id, source_label, name, description, kind, category, body, tools_json, model, metadata_json, raw)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-            rusqlite::params![
-                def.id.as_str(),
-                def.source_label,
-                def.name,
-                def.description,
-                def.kind.to_string(),
-                def.category,
-                def.body,
-                tools_json,
-                def.model,
-                metadata_json,
-                def.raw,
-            ],
+            "DELETE FROM blobs
+             WHERE hash NOT IN (SELECT body_hash FROM definitions)
+               AND hash NOT IN (SELECT raw_hash FROM definitions)
+               AND hash NOT IN (SELECT hash FROM kept_blobs)",
+            [],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// Store `content` where pruning won't remove it and return its hash,
+    /// so [`Self::content`] can find it later.
+    pub fn keep_content(&self, content: &str) -> Result<String, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let hash = insert_blob(&conn, content)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO kept_blobs (hash) VALUES (?1)",
+            [&hash],
         )
         .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(hash)
+    }
 
-        Ok(())
+    /// The stored content with SHA-256 `hash`, if the blobs table has it.
+    pub fn content(&self, hash: &str) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let content = conn
+            .query_row(
+                "SELECT blob_text(content, encoding) FROM blobs WHERE hash = ?1",
+                [hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(content)
+    }
+
+    /// Number of distinct content blobs in the database (shared by all sources).
+    pub fn blob_count(&self) -> Result<u64, StoreError> {
+        Ok(self.blob_stats()?.blobs)
+    }
+
+    /// Size accounting for the blobs table, before and after compression.
+    pub fn blob_stats(&self) -> Result<BlobStats, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(length(CAST(content AS BLOB))), 0),
+                    COALESCE(SUM(length(CAST(blob_text(content, encoding) AS BLOB))), 0)
+             FROM blobs",
+            [],
+            |row| {
+                Ok(BlobStats {
+                    blobs: row.get::<_, i64>(0)? as u64,
+                    stored_bytes: row.get::<_, i64>(1)? as u64,
+                    content_bytes: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))
     }
 
     /// Clear all definitions for this source.
@@ -138,14 +338,46 @@ impl DefinitionStore {
         Ok(())
     }
 
+    /// When this source last synced, in seconds since the Unix epoch.
+    pub fn last_synced_at(&self) -> Result<Option<u64>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let timestamp: Option<Option<String>> = conn
+            .query_row(
+                "SELECT last_synced_at FROM sources WHERE label = ?1",
+                [&self.label],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(timestamp.flatten().and_then(|t| t.parse().ok()))
+    }
+
+    /// The revision, such as a commit SHA, the latest sync of this source
+    /// got, if its provider reports one.
+    pub fn revision(&self) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let revision: Option<Option<String>> = conn
+            .query_row(
+                "SELECT revision FROM sources WHERE label = ?1",
+                [&self.label],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(revision.flatten())
+    }
+
     /// Record the sync timestamp for this source.
     pub fn record_sync(&self) -> Result<(), StoreError> {
+        self.record_sync_at(None)
+    }
+
+    /// Record the sync timestamp for this source and the revision it got.
+    fn record_sync_at(&self, revision: Option<&str>) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
         let now = now_epoch_secs();
 
         conn.execute(
-            "INSERT OR REPLACE INTO sources (label, last_synced_at) VALUES (?1, ?2)",
-            rusqlite::params![&self.label, now],
+            "INSERT OR REPLACE INTO sources (label, last_synced_at, revision) VALUES (?1, ?2, ?3)",
+            rusqlite::params![&self.label, now, revision],
         )
         .map_err(|e| StoreError::Database(e.to_string()))?;
 
@@ -156,73 +388,713 @@ impl DefinitionStore {
     pub fn set_last_synced_at(&self, epoch_secs: u64) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO sources (label, last_synced_at) VALUES (?1, ?2)",
+            "INSERT INTO sources (label, last_synced_at) VALUES (?1, ?2)
+             ON CONFLICT (label) DO UPDATE SET last_synced_at = excluded.last_synced_at",
             rusqlite::params![&self.label, epoch_secs.to_string()],
         )
         .map_err(|e| StoreError::Database(e.to_string()))?;
         Ok(())
     }
 
+    /// Changelog entries for this source recorded at or after `since`
+    /// (seconds since the Unix epoch), newest sync first.
+    pub fn changes_since(&self, since: u64) -> Result<Vec<DefinitionChange>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, kind, change, synced_at, source_label
+                 FROM changes
+                 WHERE source_label = ?1 AND synced_at >= ?2
+                 ORDER BY synced_at DESC, change, name",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let changes = stmt
+            .query_map(rusqlite::params![&self.label, since as i64], |row| {
+                let change: String = row.get(3)?;
+                Ok(DefinitionChange {
+                    id: DefinitionId::new(row.get::<_, String>(0)?),
+                    name: row.get(1)?,
+                    kind: DefinitionKind::parse(&row.get::<_, String>(2)?),
+                    change: ChangeKind::parse(&change).unwrap_or(ChangeKind::Changed),
+                    synced_at: row.get::<_, i64>(4)? as u64,
+                    source_label: row.get(5)?,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(changes)
+    }
+
+    /// Count a view or install of `id`. Returns `false`, recording nothing,
+    /// when this source has no such definition.
+    pub fn record_usage(&self, id: &DefinitionId, kind: UsageKind) -> Result<bool, StoreError> {
+        let id = &self.local_id(id);
+        let (views, installs) = match kind {
+            UsageKind::View => (1, 0),
+            UsageKind::Install => (0, 1),
+        };
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn
+            .execute(
+                "INSERT INTO usage (source_label, id, views, installs, last_used)
+                 SELECT source_label, id, ?3, ?4, ?5
+                 FROM definitions WHERE source_label = ?1 AND id = ?2
+                 ON CONFLICT (source_label, id) DO UPDATE SET
+                     views = views + excluded.views,
+                     installs = installs + excluded.installs,
+                     last_used = excluded.last_used",
+                rusqlite::params![&self.label, id.as_str(), views, installs, now_secs() as i64],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(inserted > 0)
+    }
+
+    /// Usage counts for this source's definitions, in no particular order.
+    /// Definitions that have since left the source keep their counts and are
+    /// named by ID.
+    pub fn usage(&self) -> Result<Vec<DefinitionUsage>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT u.id, COALESCE(d.name, u.id), u.views, u.installs, u.last_used
+                 FROM usage u
+                 LEFT JOIN definitions d ON d.source_label = u.source_label AND d.id = u.id
+                 WHERE u.source_label = ?1",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let usage = stmt
+            .query_map([&self.label], |row| {
+                Ok(DefinitionUsage {
+                    id: DefinitionId::new(row.get::<_, String>(0)?),
+                    name: row.get(1)?,
+                    source_label: self.label.clone(),
+                    views: row.get::<_, i64>(2)? as u64,
+                    installs: row.get::<_, i64>(3)? as u64,
+                    last_used: row.get::<_, i64>(4)? as u64,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(usage)
+    }
+
+    /// Forget this source's usage counts. Returns how many definitions had
+    /// any.
+    pub fn clear_usage(&self) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM usage WHERE source_label = ?1", [&self.label])
+            .map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// Attach a note to `id`, replacing any it already has. Returns `false`,
+    /// saving nothing, when this source has no such definition.
+    pub fn set_note(&self, id: &DefinitionId, text: &str) -> Result<bool, StoreError> {
+        let id = &self.local_id(id);
+        let conn = self.conn.lock().unwrap();
+        let saved = conn
+            .execute(
+                "INSERT INTO notes (source_label, id, text, updated_at)
+                 SELECT source_label, id, ?3, ?4
+                 FROM definitions WHERE source_label = ?1 AND id = ?2
+                 ON CONFLICT (source_label, id) DO UPDATE SET
+                     text = excluded.text,
+                     updated_at = excluded.updated_at",
+                rusqlite::params![&self.label, id.as_str(), text, now_secs() as i64],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(saved > 0)
+    }
+
+    /// Delete the note on `id`. Returns whether there was one.
+    pub fn remove_note(&self, id: &DefinitionId) -> Result<bool, StoreError> {
+        let id = &self.local_id(id);
+        let conn = self.conn.lock().unwrap();
+        let removed = conn
+            .execute(
+                "DELETE FROM notes WHERE source_label = ?1 AND id = ?2",
+                rusqlite::params![&self.label, id.as_str()],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(removed > 0)
+    }
+
+    /// The note on `id`, if it has one.
+    pub fn note(&self, id: &DefinitionId) -> Result<Option<DefinitionNote>, StoreError> {
+        let id = &self.local_id(id);
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT text, updated_at FROM notes WHERE source_label = ?1 AND id = ?2",
+            rusqlite::params![&self.label, id.as_str()],
+            |row| {
+                Ok(DefinitionNote {
+                    id: id.clone(),
+                    source_label: self.label.clone(),
+                    text: row.get(0)?,
+                    updated_at: row.get::<_, i64>(1)? as u64,
+                })
+            },
+        );
+        match result {
+            Ok(note) => Ok(Some(note)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(StoreError::Database(e.to_string())),
+        }
+    }
+
+    /// Every note on this source's definitions, including definitions that
+    /// have since left the source, ordered by ID.
+    pub fn notes(&self) -> Result<Vec<DefinitionNote>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, text, updated_at FROM notes
+                 WHERE source_label = ?1
+                 ORDER BY id",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let notes = stmt
+            .query_map([&self.label], |row| {
+                Ok(DefinitionNote {
+                    id: DefinitionId::new(row.get::<_, String>(0)?),
+                    source_label: self.label.clone(),
+                    text: row.get(1)?,
+                    updated_at: row.get::<_, i64>(2)? as u64,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(notes)
+    }
+
+    /// Make `ratings` this source's community ratings, dropping any it had
+    /// before. Ratings labelled for other sources are ignored.
+    pub fn set_ratings(&self, ratings: &[Rating]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.execute("DELETE FROM ratings WHERE source_label = ?1", [&self.label])
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        for rating in ratings.iter().filter(|r| r.source_label == self.label) {
+            tx.execute(
+                "INSERT OR REPLACE INTO ratings (source_label, id, score, votes, verified)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    &self.label,
+                    rating.id.as_str(),
+                    rating.score.map(f64::from),
+                    rating.votes,
+                    rating.verified,
+                ],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// This source's community ratings, ordered by ID.
+    pub fn ratings(&self) -> Result<Vec<Rating>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, score, votes, verified FROM ratings
+                 WHERE source_label = ?1
+                 ORDER BY id",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let ratings = stmt
+            .query_map([&self.label], |row| {
+                Ok(Rating {
+                    id: DefinitionId::new(row.get::<_, String>(0)?),
+                    source_label: self.label.clone(),
+                    score: row.get::<_, Option<f64>>(1)?.map(|score| score as f32),
+                    votes: row.get(2)?,
+                    verified: row.get(3)?,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(ratings)
+    }
+
+    /// Files the latest sync of this source skipped, with the reason for each,
+    /// ordered by path.
+    pub fn skipped_files(&self) -> Result<Vec<SkippedFile>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT path, reason, recorded_at FROM skipped_files
+                 WHERE source_label = ?1
+                 ORDER BY path",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let skipped = stmt
+            .query_map([&self.label], |row| {
+                Ok(SkippedFile {
+                    source_label: self.label.clone(),
+                    path: row.get(0)?,
+                    reason: row.get(1)?,
+                    recorded_at: row.get::<_, i64>(2)? as u64,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(skipped)
+    }
+
+    /// How many of this source's definitions share each kind, category, or
+    /// source, most first. Definitions without a category count under `None`.
+    pub fn count_by(&self, by: CountBy) -> Result<Vec<(Option<String>, u64)>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let column = by.column();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {column}, COUNT(*) AS n FROM definitions
+                 WHERE source_label = ?1
+                 GROUP BY {column}
+                 ORDER BY n DESC, {column}"
+            ))
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let counts = stmt
+            .query_map([&self.label], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(counts)
+    }
+
+    /// Journal a bulk install of `items` (source label and ID pairs) into
+    /// `target` before anything is written, returning the batch to report
+    /// progress against. The journal is shared by every source in the
+    /// database, whichever store writes it.
+    pub fn begin_install_batch<'a>(
+        &self,
+        label: &str,
+        target: &Path,
+        items: impl IntoIterator<Item = (&'a str, &'a DefinitionId)>,
+    ) -> Result<BatchId, StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.execute(
+            "INSERT INTO install_batches (label, target, started_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![label, target.to_string_lossy(), now_secs() as i64],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        let batch = tx.last_insert_rowid();
+        for (position, (source_label, id)) in items.into_iter().enumerate() {
+            tx.execute(
+                "INSERT INTO install_journal (batch, position, source_label, id, state)
+                 VALUES (?1, ?2, ?3, ?4, 'planned')",
+                rusqlite::params![batch, position as i64, source_label, id.as_str()],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(batch)
+    }
+
+    /// Record that entry `position` of `batch` is about to be written to
+    /// `path`, which held `previous` (`None` if it didn't exist). Call this
+    /// before writing so a crash mid-write can still be undone.
+    pub fn journal_started(
+        &self,
+        batch: BatchId,
+        position: usize,
+        path: &Path,
+        previous: Option<&str>,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE install_journal SET state = 'started', path = ?3, previous = ?4
+             WHERE batch = ?1 AND position = ?2",
+            rusqlite::params![batch, position as i64, path.to_string_lossy(), previous],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Record that entry `position` of `batch` was installed, or failed
+    /// with `error`.
+    pub fn journal_finished(
+        &self,
+        batch: BatchId,
+        position: usize,
+        error: Option<&str>,
+    ) -> Result<(), StoreError> {
+        let state = if error.is_some() { "failed" } else { "done" };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE install_journal SET state = ?3, error = ?4
+             WHERE batch = ?1 AND position = ?2",
+            rusqlite::params![batch, position as i64, state, error],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Forget `batch`, once it has finished or been reconciled.
+    pub fn end_install_batch(&self, batch: BatchId) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.execute("DELETE FROM install_journal WHERE batch = ?1", [batch])
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.execute("DELETE FROM install_batches WHERE id = ?1", [batch])
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// Every journaled install that was never ended, oldest first. Outside
+    /// a running install these are the ones that were interrupted.
+    pub fn install_batches(&self) -> Result<Vec<InstallBatch>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, label, target, started_at FROM install_batches ORDER BY id")
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let mut batches: Vec<InstallBatch> = stmt
+            .query_map([], |row| {
+                Ok(InstallBatch {
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    target: PathBuf::from(row.get::<_, String>(2)?),
+                    started_at: row.get::<_, i64>(3)? as u64,
+                    entries: Vec::new(),
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT source_label, id, path, previous, state, error FROM install_journal
+                 WHERE batch = ?1
+                 ORDER BY position",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        for batch in &mut batches {
+            batch.entries = stmt
+                .query_map([batch.id], |row| {
+                    let state = match row.get::<_, String>(4)?.as_str() {
+                        "started" => JournalState::Started,
+                        "done" => JournalState::Done,
+                        "failed" => JournalState::Failed(
+                            row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                        ),
+                        _ => JournalState::Planned,
+                    };
+                    Ok(JournalEntry {
+                        source_label: row.get(0)?,
+                        id: DefinitionId::new(row.get::<_, String>(1)?),
+                        path: row.get::<_, Option<String>>(2)?.map(PathBuf::from),
+                        previous: row.get(3)?,
+                        state,
+                    })
+                })
+                .map_err(|e| StoreError::Database(e.to_string()))?
+                .filter_map(|r| r.ok())
+                .collect();
+        }
+        Ok(batches)
+    }
+
+    /// Call `visit` with each cached definition's ID and body, in ID order.
+    /// Rows are read one at a time, so the catalog is never all in memory
+    /// at once. `visit` returns false to stop early.
+    pub fn for_each_body(
+        &self,
+        mut visit: impl FnMut(&DefinitionId, &str) -> bool,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.id, blob_text(b.content, b.encoding)
+                 FROM definitions d
+                 JOIN blobs b ON b.hash = d.body_hash
+                 WHERE d.source_label = ?1
+                 ORDER BY d.id",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let mut rows = stmt
+            .query([&self.label])
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        while let Some(row) = rows.next().map_err(|e| StoreError::Database(e.to_string()))? {
+            let id: String = row.get(0).map_err(|e| StoreError::Database(e.to_string()))?;
+            let body: String = row.get(1).map_err(|e| StoreError::Database(e.to_string()))?;
+            if !visit(&DefinitionId::new(id), &body) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// This source's cached definitions as the files they were synced from,
+    /// so a sync can keep the ones it has no need to download again.
+    pub fn cached_files(&self) -> Result<Vec<RawDefinitionFile>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.id, blob_text(b.content, b.encoding)
+                 FROM definitions d
+                 JOIN blobs b ON b.hash = d.raw_hash
+                 WHERE d.source_label = ?1
+                 ORDER BY d.id",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let files = stmt
+            .query_map([&self.label], |row| {
+                let id: String = row.get(0)?;
+                Ok(RawDefinitionFile {
+                    relative_path: synced_path(&id),
+                    content: row.get(1)?,
+                })
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(files)
+    }
+
+    /// Swap in `skipped` (path and reason pairs) for the files the previous
+    /// sync skipped.
+    fn record_skipped(&self, skipped: &[(String, String)]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.execute("DELETE FROM skipped_files WHERE source_label = ?1", [&self.label])
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let now = now_secs() as i64;
+        for (path, reason) in skipped {
+            tx.execute(
+                "INSERT OR REPLACE INTO skipped_files (source_label, path, reason, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![&self.label, path, reason, now],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// Current definitions for this source, keyed by ID, with just enough to
+    /// tell whether they changed between syncs.
+    fn snapshot(&self) -> Result<HashMap<String, SnapshotEntry>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, name, kind, raw_hash FROM definitions WHERE source_label = ?1")
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let entries = stmt
+            .query_map([&self.label], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    SnapshotEntry {
+                        name: row.get(1)?,
+                        kind: row.get(2)?,
+                        raw_hash: row.get(3)?,
+                    },
+                ))
+            })
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(entries)
+    }
+
+    /// Store `changes` in the changelog and drop entries past the retention
+    /// window.
+    fn record_changes(&self, changes: &[DefinitionChange]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        for change in changes {
+            tx.execute(
+                "INSERT INTO changes (source_label, synced_at, id, name, kind, change)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    change.source_label,
+                    change.synced_at as i64,
+                    change.id.as_str(),
+                    change.name,
+                    change.kind.to_string(),
+                    change.change.as_str(),
+                ],
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+        let cutoff = now_secs().saturating_sub(CHANGELOG_RETENTION_DAYS * 86_400);
+        tx.execute(
+            "DELETE FROM changes WHERE source_label = ?1 AND synced_at < ?2",
+            rusqlite::params![&self.label, cutoff as i64],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        tx.commit().map_err(|e| StoreError::Database(e.to_string()))
+    }
+
+    /// How this source's recent syncs went.
+    pub fn health(&self) -> Result<SourceHealth, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let failures = conn
+            .query_row(
+                "SELECT streak, last_error, last_failed_at FROM sync_failures
+                 WHERE source_label = ?1",
+                [&self.label],
+                |row| {
+                    Ok(SourceHealth {
+                        failure_streak: row.get(0)?,
+                        last_error: Some(row.get(1)?),
+                        last_failed_at: Some(row.get::<_, i64>(2)? as u64),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(failures.unwrap_or_default())
+    }
+
+    /// Count a failed sync towards this source's failure streak.
+    fn record_failure(&self, error: &SyncError) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sync_failures (source_label, streak, last_error, last_failed_at)
+             VALUES (?1, 1, ?2, ?3)
+             ON CONFLICT (source_label) DO UPDATE SET
+                 streak = streak + 1,
+                 last_error = excluded.last_error,
+                 last_failed_at = excluded.last_failed_at",
+            rusqlite::params![&self.label, error.to_string(), now_secs() as i64],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// End this source's failure streak, if it has one.
+    fn clear_failures(&self) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM sync_failures WHERE source_label = ?1",
+            [&self.label],
+        )
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     /// Sync definitions from a provider into the store.
     ///
-    /// This clears existing definitions for the source, fetches all files
-    /// from the provider, parses them into definitions, and inserts them.
-    /// Records the sync timestamp on success.
+    /// This fetches all files from the provider, parses them into
+    /// definitions, and swaps them in for the source's current definitions in
+    /// a single transaction, updating rows that are still there rather than
+    /// recreating them. Records the sync timestamp on success.
+    ///
+    /// Every sync after the first also compares the result with what was
+    /// there before and records the added, removed, and changed definitions
+    /// in the changelog (see [`changes_since`](Self::changes_since)).
     ///
     /// Parse errors and skipped files are returned as feedback rather than
-    /// printed, allowing callers to decide how to present them.
+    /// printed, allowing callers to decide how to present them. Files larger
+    /// than [`max_file_bytes`](Self::max_file_bytes) are skipped the same way.
+    /// Both are also kept until the next sync; see
+    /// [`skipped_files`](Self::skipped_files).
+    ///
+    /// A failed sync adds to the source's failure streak, and a successful
+    /// one ends it; see [`health`](Self::health).
     pub async fn sync(&self, provider: &dyn SyncProvider) -> Result<SyncReport, SyncError> {
+        match self.sync_from(provider).await {
+            Ok(report) => {
+                self.clear_failures()
+                    .map_err(|e| SyncError::Storage(e.to_string()))?;
+                Ok(report)
+            }
+            Err(e) => {
+                // The sync's own error says more than a failure to record it.
+                let _ = self.record_failure(&e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn sync_from(&self, provider: &dyn SyncProvider) -> Result<SyncReport, SyncError> {
         let raw_files = provider.fetch_all().await?;
 
-        self.clear_definitions()
-            .map_err(|e| SyncError::Storage(e.to_string()))?;
+        let storage = |e: StoreError| SyncError::Storage(e.to_string());
+        let first_sync = self.sync_status().map_err(storage)? == SyncStatus::NeverSynced;
+        let previous = self.snapshot().map_err(storage)?;
 
+        let mut definitions = Vec::new();
         let mut synced = 0u64;
         let mut skipped = 0u64;
         let mut feedback = Vec::new();
+        let mut skipped_files = Vec::new();
+        let by_path: HashMap<&str, &str> = match self.file_check {
+            Some(_) => raw_files
+                .iter()
+                .map(|f| (f.relative_path.as_str(), f.content.as_str()))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let companion = |path: &str| by_path.get(path).copied();
 
         for file in &raw_files {
-            if !agent_defs::path::is_definition_file(&file.relative_path) {
+            // A multi-branch source prefixes each path with its branch; the
+            // layout rules apply to what follows.
+            let (branch, relative_path) = agent_defs::path::split_branch(&file.relative_path);
+            if !agent_defs::path::is_definition_file(relative_path) {
                 skipped += 1;
                 continue;
             }
 
-            if agent_defs::path::is_skill_reference(&file.relative_path) {
+            if agent_defs::path::is_skill_reference(relative_path) {
                 skipped += 1;
                 continue;
             }
 
-            let (id_str, path_name, kind, category) =
-                if agent_defs::path::is_skill_entry_point(&file.relative_path) {
-                    let (name, kind, category) =
-                        agent_defs::path::parse_skill_path(&file.relative_path);
-                    let dir_path = file
-                        .relative_path
-                        .strip_suffix("/SKILL.md")
-                        .unwrap_or(&file.relative_path);
-                    (dir_path.to_owned(), name, kind, category)
-                } else {
-                    let (name, kind, category) =
-                        agent_defs::path::parse_relative_path(&file.relative_path);
-                    (file.relative_path.clone(), name, kind, category)
-                };
-
-            let id = DefinitionId::new(&id_str);
-
-            let def_result = agent_defs::builder::build_definition(
-                &id,
-                &file.content,
-                &file.relative_path,
-                path_name,
-                kind,
-                category,
-                &self.label,
-            );
-
-            match def_result {
+            let size = file.content.len() as u64;
+            if size > self.max_file_bytes {
+                let reason = format!(
+                    "{} bytes exceeds the {} byte limit",
+                    size, self.max_file_bytes
+                );
+                feedback.push(Feedback::warning(format!(
+                    "skipping {}: {}",
+                    file.relative_path, reason
+                )));
+                skipped_files.push((file.relative_path.clone(), reason));
+                skipped += 1;
+                continue;
+            }
+
+            if let Some(check) = &self.file_check
+                && let Some(reason) = check.reject(file, &companion)
+            {
+                feedback.push(Feedback::warning(format!(
+                    "skipping {}: {}",
+                    file.relative_path, reason
+                )));
+                skipped_files.push((file.relative_path.clone(), reason));
+                skipped += 1;
+                continue;
+            }
+
+            match self.build(branch, relative_path, &file.content) {
                 Ok(def) => {
-                    self.upsert_definition(&def)
-                        .map_err(|e| SyncError::Storage(e.to_string()))?;
+                    definitions.push(def);
                     synced += 1;
                 }
                 Err(e) => {
@@ -230,21 +1102,128 @@ impl DefinitionStore {
                         "skipping {}: {}",
                         file.relative_path, e
                     )));
+                    skipped_files.push((file.relative_path.clone(), e.to_string()));
                     skipped += 1;
                 }
             }
         }
 
-        self.record_sync()
+        self.replace_definitions(&definitions).map_err(storage)?;
+
+        let changes = if first_sync {
+            Vec::new()
+        } else {
+            let current = self.snapshot().map_err(storage)?;
+            diff_snapshots(&self.label, &previous, &current, now_secs())
+        };
+        self.record_changes(&changes).map_err(storage)?;
+        self.record_skipped(&skipped_files).map_err(storage)?;
+
+        self.prune_blobs()
+            .map_err(|e| SyncError::Storage(e.to_string()))?;
+        self.record_sync_at(provider.revision().as_deref())
             .map_err(|e| SyncError::Storage(e.to_string()))?;
 
         Ok(SyncReport {
             synced,
             skipped,
             feedback,
+            changes,
         })
     }
 
+    /// Build the definition in `content`, synced from `relative_path` on
+    /// `branch` (for a multi-branch source), the way sync does.
+    fn build(
+        &self,
+        branch: Option<&str>,
+        relative_path: &str,
+        content: &str,
+    ) -> Result<Definition, SourceError> {
+        let (id_str, path_name, kind, category) =
+            if agent_defs::path::is_skill_entry_point(relative_path) {
+                let (name, kind, category) = agent_defs::path::parse_skill_path(relative_path);
+                let dir_path = relative_path
+                    .strip_suffix("/SKILL.md")
+                    .unwrap_or(relative_path);
+                (dir_path.to_owned(), name, kind, category)
+            } else {
+                let (name, kind, category) = agent_defs::path::parse_relative_path(relative_path);
+                (relative_path.to_owned(), name, kind, category)
+            };
+        let id_str = match branch {
+            Some(branch) => agent_defs::path::with_branch(branch, &id_str),
+            None => id_str,
+        };
+
+        let mut def = agent_defs::builder::build_definition(
+            &DefinitionId::new(&id_str),
+            content,
+            relative_path,
+            path_name,
+            kind,
+            category,
+            &self.label,
+        )?;
+        self.categories.apply(&mut def);
+        Ok(def)
+    }
+
+    /// Parse every cached definition's raw file again with the current
+    /// parser, and save the ones that come out different, unless
+    /// `dry_run`. Definitions the parser now rejects keep their old parse.
+    /// Upstream content isn't fetched, and the changelog is left alone.
+    pub fn reparse(&self, dry_run: bool) -> Result<ReparseReport, StoreError> {
+        let mut report = ReparseReport::default();
+        for old in self.definitions_with_raw()? {
+            report.checked += 1;
+            let path = synced_path(old.id.as_str());
+            let (branch, relative_path) = agent_defs::path::split_branch(&path);
+            let new = match self.build(branch, relative_path, &old.raw) {
+                Ok(new) => new,
+                Err(e) => {
+                    report.failed.push((old.id, e.to_string()));
+                    continue;
+                }
+            };
+            let fields = changed_fields(&old, &new);
+            if fields.is_empty() {
+                continue;
+            }
+            if !dry_run {
+                self.upsert_definition(&new)?;
+            }
+            report.changed.push(Reparsed { id: old.id, fields });
+        }
+        if !dry_run && !report.changed.is_empty() {
+            self.prune_blobs()?;
+        }
+        Ok(report)
+    }
+
+    /// Every cached definition with its raw file, in ID order.
+    fn definitions_with_raw(&self) -> Result<Vec<Definition>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.id, d.name, d.description, d.kind, d.category, d.source_label,
+                        blob_text(b.content, b.encoding), d.tools_json, d.model,
+                        d.metadata_json, blob_text(r.content, r.encoding)
+                 FROM definitions d
+                 JOIN blobs b ON b.hash = d.body_hash
+                 JOIN blobs r ON r.hash = d.raw_hash
+                 WHERE d.source_label = ?1
+                 ORDER BY d.id",
+            )
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        let definitions = stmt
+            .query_map([&self.label], Self::row_to_definition)
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(definitions)
+    }
+
     fn row_to_summary(row: &rusqlite::Row) -> rusqlite::Result<DefinitionSummary> {
         let id: String = row.get(0)?;
         let name: String = row.get(1)?;
@@ -252,6 +1231,8 @@ impl DefinitionStore {
         let kind_str: String = row.get(3)?;
         let category: Option<String> = row.get(4)?;
         let source_label: String = row.get(5)?;
+        let first_seen_at: Option<i64> = row.get(6)?;
+        let last_updated_at: Option<i64> = row.get(7)?;
 
         Ok(DefinitionSummary {
             id: DefinitionId::new(id),
@@ -260,6 +1241,8 @@ impl DefinitionStore {
             kind: DefinitionKind::parse(&kind_str),
             category,
             source_label,
+            first_seen_at: first_seen_at.map(|t| t as u64),
+            last_updated_at: last_updated_at.map(|t| t as u64),
         })
     }
 
@@ -307,7 +1290,8 @@ impl Source for DefinitionStore {
 
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, description, kind, category, source_label
+                "SELECT id, name, description, kind, category, source_label,
+                        first_seen_at, last_updated_at
                  FROM definitions
                  WHERE source_label = ?1
                  ORDER BY kind, name",
@@ -323,22 +1307,41 @@ impl Source for DefinitionStore {
         Ok(summaries)
     }
 
+    /// Every word of the [`TextQuery`] must appear in the name, description,
+    /// body, or note, and no excluded word may.
     async fn search(&self, query: &str) -> Result<Vec<DefinitionSummary>, SourceError> {
         let conn = self.conn.lock().unwrap();
-        let pattern = format!("%{query}%");
+        let text = TextQuery::parse(query);
+
+        let mut sql = String::from(
+            "SELECT d.id, d.name, d.description, d.kind, d.category, d.source_label,
+                    d.first_seen_at, d.last_updated_at
+             FROM definitions d
+             JOIN blobs b ON b.hash = d.body_hash
+             LEFT JOIN notes n ON n.source_label = d.source_label AND n.id = d.id
+             WHERE d.source_label = ?1",
+        );
+        let mut params = vec![self.label.clone()];
+        let terms = (text.include.iter().map(|term| (term, "")))
+            .chain(text.exclude.iter().map(|term| (term, "NOT ")));
+        for (term, not) in terms {
+            params.push(format!("%{}%", escape_like(term)));
+            let n = params.len();
+            sql.push_str(&format!(
+                "\n AND {not}(d.name LIKE ?{n} ESCAPE '\\' \
+                 OR COALESCE(d.description, '') LIKE ?{n} ESCAPE '\\' \
+                 OR blob_text(b.content, b.encoding) LIKE ?{n} ESCAPE '\\' \
+                 OR COALESCE(n.text, '') LIKE ?{n} ESCAPE '\\')"
+            ));
+        }
+        sql.push_str("\n ORDER BY d.kind, d.name");
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, name, description, kind, category, source_label
-                 FROM definitions
-                 WHERE source_label = ?1
-                   AND (name LIKE ?2 OR description LIKE ?2 OR body LIKE ?2)
-                 ORDER BY kind, name",
-            )
+            .prepare(&sql)
             .map_err(|e| SourceError::Other(e.to_string()))?;
 
         let summaries = stmt
-            .query_map(rusqlite::params![&self.label, pattern], Self::row_to_summary)
+            .query_map(rusqlite::params_from_iter(&params), Self::row_to_summary)
             .map_err(|e| SourceError::Other(e.to_string()))?
             .filter_map(|r| r.ok())
             .collect();
@@ -346,14 +1349,20 @@ impl Source for DefinitionStore {
         Ok(summaries)
     }
 
+    /// Fetch a definition without its `raw` content, which is left empty.
+    /// Use [`Source::fetch_raw`] when the original file is needed.
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
+        let id = &self.local_id(id);
         let conn = self.conn.lock().unwrap();
+        self.check_signing_policy(&conn, id)?;
 
         conn.query_row(
-            "SELECT id, name, description, kind, category, source_label,
-                    body, tools_json, model, metadata_json, raw
-             FROM definitions
-             WHERE source_label = ?1 AND id = ?2",
+            "SELECT d.id, d.name, d.description, d.kind, d.category, d.source_label,
+                    blob_text(b.content, b.encoding), d.tools_json, d.model,
+                    d.metadata_json, '' AS raw
+             FROM definitions d
+             JOIN blobs b ON b.hash = d.body_hash
+             WHERE d.source_label = ?1 AND d.id = ?2",
             rusqlite::params![&self.label, id.as_str()],
             Self::row_to_definition,
         )
@@ -362,6 +1371,74 @@ impl Source for DefinitionStore {
             other => SourceError::Other(other.to_string()),
         })
     }
+
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        let id = &self.local_id(id);
+        let conn = self.conn.lock().unwrap();
+        self.check_signing_policy(&conn, id)?;
+
+        conn.query_row(
+            "SELECT blob_text(b.content, b.encoding)
+             FROM definitions d
+             JOIN blobs b ON b.hash = d.raw_hash
+             WHERE d.source_label = ?1 AND d.id = ?2",
+            rusqlite::params![&self.label, id.as_str()],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => SourceError::NotFound(id.clone()),
+            other => SourceError::Other(other.to_string()),
+        })
+    }
+}
+
+/// The path, branch prefix and all, that sync read the definition `id`
+/// from. Skills are cached under their directory but read from the
+/// SKILL.md inside it.
+fn synced_path(id: &str) -> String {
+    let (_, path) = agent_defs::path::split_branch(id);
+    if agent_defs::path::is_skill_directory_id(path) {
+        format!("{id}/SKILL.md")
+    } else {
+        id.to_owned()
+    }
+}
+
+/// Names of the parsed fields that differ between `old` and `new`.
+fn changed_fields(old: &Definition, new: &Definition) -> Vec<&'static str> {
+    let checks = [
+        ("name", old.name != new.name),
+        ("description", old.description != new.description),
+        ("kind", old.kind != new.kind),
+        ("category", old.category != new.category),
+        ("body", old.body != new.body),
+        ("tools", old.tools != new.tools),
+        ("model", old.model != new.model),
+        ("metadata", old.metadata != new.metadata),
+    ];
+    checks
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then_some(field))
+        .collect()
+}
+
+/// What [`DefinitionStore::reparse`] found.
+#[derive(Debug, Clone, Default)]
+pub struct ReparseReport {
+    /// How many definitions were parsed again.
+    pub checked: u64,
+    /// The definitions that parse differently now.
+    pub changed: Vec<Reparsed>,
+    /// Definitions the parser now rejects, with the reason.
+    pub failed: Vec<(DefinitionId, String)>,
+}
+
+/// A definition that parses differently than when it was cached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reparsed {
+    pub id: DefinitionId,
+    /// The fields that changed, such as `description` or `tools`.
+    pub fields: Vec<&'static str>,
 }
 
 /// Summary of a sync operation.
@@ -370,6 +1447,104 @@ pub struct SyncReport {
     pub synced: u64,
     pub skipped: u64,
     pub feedback: Vec<Feedback>,
+    /// What this sync added, removed, or changed. Empty on a source's first
+    /// sync, when there is nothing to compare against.
+    pub changes: Vec<DefinitionChange>,
+}
+
+/// A definition as of one sync, for change detection.
+struct SnapshotEntry {
+    name: String,
+    kind: String,
+    raw_hash: String,
+}
+
+/// Compare two snapshots of a source. Changes are ordered by kind of change,
+/// then name.
+fn diff_snapshots(
+    label: &str,
+    previous: &HashMap<String, SnapshotEntry>,
+    current: &HashMap<String, SnapshotEntry>,
+    synced_at: u64,
+) -> Vec<DefinitionChange> {
+    let entry = |id: &str, snapshot: &SnapshotEntry, change| DefinitionChange {
+        id: DefinitionId::new(id),
+        name: snapshot.name.clone(),
+        kind: DefinitionKind::parse(&snapshot.kind),
+        source_label: label.to_owned(),
+        change,
+        synced_at,
+    };
+
+    let mut changes: Vec<DefinitionChange> = current
+        .iter()
+        .filter_map(|(id, now)| match previous.get(id) {
+            None => Some(entry(id, now, ChangeKind::Added)),
+            Some(before) if before.raw_hash != now.raw_hash => {
+                Some(entry(id, now, ChangeKind::Changed))
+            }
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(
+        previous
+            .iter()
+            .filter(|(id, _)| !current.contains_key(*id))
+            .map(|(id, before)| entry(id, before, ChangeKind::Removed)),
+    );
+    changes.sort_by(|a, b| {
+        a.change
+            .as_str()
+            .cmp(b.change.as_str())
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+    });
+    changes
+}
+
+/// What [`DefinitionStore::count_by`] groups definitions by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountBy {
+    Kind,
+    Category,
+    Source,
+}
+
+impl CountBy {
+    fn column(self) -> &'static str {
+        match self {
+            Self::Kind => "kind",
+            Self::Category => "category",
+            Self::Source => "source_label",
+        }
+    }
+}
+
+/// How a source's recent syncs went: how many in a row have failed, and
+/// the latest failure, if there is a streak.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceHealth {
+    pub failure_streak: u32,
+    pub last_error: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub last_failed_at: Option<u64>,
+}
+
+impl SourceHealth {
+    /// Whether at least `threshold` syncs in a row have failed. A
+    /// threshold of 0 never counts a source as degraded.
+    pub fn is_degraded(&self, threshold: u32) -> bool {
+        threshold > 0 && self.failure_streak >= threshold
+    }
+}
+
+/// Blob table size accounting. `stored_bytes` is what is on disk;
+/// `content_bytes` is the decompressed total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobStats {
+    pub blobs: u64,
+    pub stored_bytes: u64,
+    pub content_bytes: u64,
 }
 
 /// Errors specific to store operations.
@@ -380,14 +1555,88 @@ pub enum StoreError {
 
     #[error("migration error: {0}")]
     Migration(String),
+
+    #[error(
+        "cache database uses schema v{found}{}, but this build only understands up to v{supported}; \
+         upgrade agent-def-fetcher to open it (don't delete it: it also holds your notes, \
+         ratings, and install history)",
+        written_by.as_ref().map(|v| format!(" (written by {v})")).unwrap_or_default()
+    )]
+    SchemaTooNew {
+        found: usize,
+        supported: usize,
+        written_by: Option<String>,
+    },
 }
 
-fn now_epoch_secs() -> String {
-    let now = std::time::SystemTime::now()
+/// Write one definition row on `conn`, which may be a transaction.
+/// `ON CONFLICT ... DO UPDATE` rather than `INSERT OR REPLACE` so an existing
+/// row is updated in place instead of deleted and reinserted. A new row is
+/// first seen now; an existing one counts as updated only when its raw file
+/// changed, so re-parsing or re-syncing the same content keeps its dates.
+fn upsert(conn: &rusqlite::Connection, def: &Definition) -> Result<(), StoreError> {
+    let tools_json =
+        serde_json::to_string(&def.tools).map_err(|e| StoreError::Database(e.to_string()))?;
+    let metadata_json =
+        serde_json::to_string(&def.metadata).map_err(|e| StoreError::Database(e.to_string()))?;
+
+    let body_hash = insert_blob(conn, &def.body)?;
+    let raw_hash = insert_blob(conn, &def.raw)?;
+
+    conn.execute(
+        "INSERT INTO definitions
+            (id, source_label, name, description, kind, category, body_hash, tools_json, model, metadata_json, raw_hash,
+             first_seen_at, last_updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)
+         ON CONFLICT (source_label, id) DO UPDATE SET
+            name = excluded.name,
+            description = excluded.description,
+            kind = excluded.kind,
+            category = excluded.category,
+            body_hash = excluded.body_hash,
+            tools_json = excluded.tools_json,
+            model = excluded.model,
+            metadata_json = excluded.metadata_json,
+            last_updated_at = CASE
+                WHEN definitions.raw_hash = excluded.raw_hash THEN definitions.last_updated_at
+                ELSE excluded.last_updated_at
+            END,
+            raw_hash = excluded.raw_hash",
+        rusqlite::params![
+            def.id.as_str(),
+            def.source_label,
+            def.name,
+            def.description,
+            def.kind.to_string(),
+            def.category,
+            body_hash,
+            tools_json,
+            def.model,
+            metadata_json,
+            raw_hash,
+            now_secs() as i64,
+        ],
+    )
+    .map_err(|e| StoreError::Database(e.to_string()))?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
-        .as_secs();
-    now.to_string()
+        .as_secs()
+}
+
+fn now_epoch_secs() -> String {
+    now_secs().to_string()
+}
+
+/// `text` with `LIKE` wildcards escaped, for use with `ESCAPE '\'`.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 fn days_since(timestamp: &str) -> Option<u64> {
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use agent_defs_store::schema::{self, latest_version};
+use agent_defs_store::{DefinitionStore, StoreError};
+
+fn temp_db(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("agent-defs-store-schema-{name}.db"));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn opening_a_store_migrates_to_latest() {
+    let path = temp_db("latest");
+    DefinitionStore::open(&path, "test-source").unwrap();
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    assert_eq!(schema::current_version(&conn).unwrap(), latest_version());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn newer_schema_is_rejected_with_clear_error() {
+    let path = temp_db("too-new");
+    DefinitionStore::open(&path, "test-source").unwrap();
+
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let future = (latest_version() + 3) as i64;
+        conn.pragma_update(None, "user_version", future).unwrap();
+        conn.execute(
+            "UPDATE store_meta SET value = '9.9.9' WHERE key = 'app_version'",
+            [],
+        )
+        .unwrap();
+    }
+
+    let err = DefinitionStore::open(&path, "test-source")
+        .err()
+        .expect("newer schema should not open");
+    match &err {
+        StoreError::SchemaTooNew {
+            found,
+            supported,
+            written_by,
+        } => {
+            assert_eq!(*found, latest_version() + 3);
+            assert_eq!(*supported, latest_version());
+            assert_eq!(written_by.as_deref(), Some("9.9.9"));
+        }
+        other => panic!("expected SchemaTooNew, got {other:?}"),
+    }
+    let message = err.to_string();
+    assert!(message.contains("written by 9.9.9"));
+    assert!(message.contains("upgrade agent-def-fetcher"));
+    assert!(!message.contains("delete the cache"));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn migrate_file_snapshots_before_migrating() {
+    let path = temp_db("backup");
+    let backup = path.with_extension("db.v0.bak");
+    let _ = std::fs::remove_file(&backup);
+    rusqlite::Connection::open(&path).unwrap();
+
+    let report = schema::migrate_file(&path, Some(&backup)).unwrap();
+    assert_eq!(report.from, 0);
+    assert_eq!(report.to, latest_version());
+    assert!(report.migrated());
+    assert_eq!(report.backup.as_deref(), Some(backup.as_path()));
+    assert!(backup.exists());
+
+    let conn = rusqlite::Connection::open(&backup).unwrap();
+    assert_eq!(schema::current_version(&conn).unwrap(), 0);
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&backup);
+}
+
+#[test]
+fn migrate_file_skips_backup_when_up_to_date() {
+    let path = temp_db("current");
+    let backup = path.with_extension("db.bak");
+    let _ = std::fs::remove_file(&backup);
+    DefinitionStore::open(&path, "test-source").unwrap();
+
+    let report = schema::migrate_file(&path, Some(&backup)).unwrap();
+    assert!(!report.migrated());
+    assert!(report.backup.is_none());
+    assert!(!backup.exists());
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 
-use agent_defs::{Definition, DefinitionId, DefinitionKind, Source, SourceError};
-use agent_defs_store::{DefinitionStore, SyncStatus};
+use agent_defs::{
+    Definition, DefinitionId, DefinitionKind, JournalState, Rating, Source, SourceError,
+    UsageKind,
+};
+use agent_defs_store::{CountBy, DefinitionStore, SyncStatus};
 
 fn sample_definition(id: &str, name: &str, kind: DefinitionKind) -> Definition {
     Definition {
@@ -98,7 +101,32 @@ async fn fetch_returns_full_definition() {
     assert_eq!(def.tools, vec!["Read", "Write"]);
     assert_eq!(def.model.as_deref(), Some("opus"));
     assert_eq!(def.metadata.get("color").unwrap(), "blue");
-    assert!(def.raw.contains("---"));
+    assert!(def.raw.is_empty(), "raw is loaded lazily via fetch_raw");
+}
+
+#[tokio::test]
+async fn fetch_raw_returns_original_content() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition(
+            "agents/arch.md",
+            "Architect",
+            DefinitionKind::Agent,
+        ))
+        .unwrap();
+
+    let id = DefinitionId::new("agents/arch.md");
+    let raw = store.fetch_raw(&id).await.unwrap();
+
+    assert_eq!(raw, "---\nname: Architect\n---\nBody of Architect.");
+}
+
+#[tokio::test]
+async fn fetch_raw_returns_not_found_for_missing_id() {
+    let store = create_store();
+    let id = DefinitionId::new("nonexistent.md");
+    let result = store.fetch_raw(&id).await;
+    assert!(matches!(result, Err(SourceError::NotFound(_))));
 }
 
 #[tokio::test]
@@ -162,6 +190,31 @@ async fn search_matches_body() {
     assert_eq!(results.len(), 1);
 }
 
+#[tokio::test]
+async fn search_matches_words_independently_and_excludes() {
+    let store = create_store();
+    let mut reviewer =
+        sample_definition("agents/reviewer.md", "Code Reviewer", DefinitionKind::Agent);
+    reviewer.description = Some("Reviews pull requests".into());
+    let mut legacy =
+        sample_definition("agents/legacy.md", "Legacy Reviewer", DefinitionKind::Agent);
+    legacy.description = Some("Deprecated: reviews code 100% by hand".into());
+    store.upsert_definition(&reviewer).unwrap();
+    store.upsert_definition(&legacy).unwrap();
+
+    let names = |results: Vec<agent_defs::DefinitionSummary>| -> Vec<String> {
+        results.into_iter().map(|s| s.name).collect()
+    };
+    assert_eq!(
+        names(store.search("reviewer code").await.unwrap()),
+        ["Code Reviewer", "Legacy Reviewer"]
+    );
+    assert_eq!(names(store.search("\"reviews code\"").await.unwrap()), ["Legacy Reviewer"]);
+    assert_eq!(names(store.search("reviewer !deprecated").await.unwrap()), ["Code Reviewer"]);
+    assert_eq!(names(store.search("100%").await.unwrap()), ["Legacy Reviewer"]);
+    assert!(store.search("0%b").await.unwrap().is_empty());
+}
+
 #[tokio::test]
 async fn search_returns_empty_for_no_match() {
     let store = create_store();
@@ -257,3 +310,402 @@ fn label_returns_configured_label() {
     let store = create_store();
     assert_eq!(store.label(), "test-source");
 }
+
+#[test]
+fn labels_in_lists_sources_with_cached_definitions() {
+    let path = std::env::temp_dir().join("agent-defs-store-test-labels.db");
+    let _ = std::fs::remove_file(&path);
+
+    for label in ["beta", "alpha"] {
+        let store = DefinitionStore::open(&path, label).unwrap();
+        let mut def = sample_definition("agents/a.md", "A", DefinitionKind::Agent);
+        def.source_label = label.to_owned();
+        store.upsert_definition(&def).unwrap();
+    }
+    DefinitionStore::open(&path, "never-synced").unwrap();
+
+    assert_eq!(DefinitionStore::labels_in(&path).unwrap(), ["alpha", "beta"]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn identical_content_is_stored_once() {
+    let store = create_store();
+
+    let mut first = sample_definition("agents/a.md", "Shared", DefinitionKind::Agent);
+    let mut second = sample_definition("agents/b.md", "Shared", DefinitionKind::Agent);
+    first.body = "Same body.".to_owned();
+    second.body = "Same body.".to_owned();
+    store.upsert_definition(&first).unwrap();
+    store.upsert_definition(&second).unwrap();
+
+    // One blob for the shared body, one for the shared raw content.
+    assert_eq!(store.blob_count().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn blobs_are_shared_across_sources() {
+    let path = std::env::temp_dir().join("agent-defs-store-test-blob-dedup.db");
+    let _ = std::fs::remove_file(&path);
+
+    let store_a = DefinitionStore::open(&path, "source-a").unwrap();
+    let store_b = DefinitionStore::open(&path, "source-b").unwrap();
+
+    let mut def = sample_definition("agents/arch.md", "Architect", DefinitionKind::Agent);
+    def.source_label = "source-a".to_owned();
+    store_a.upsert_definition(&def).unwrap();
+    def.source_label = "source-b".to_owned();
+    store_b.upsert_definition(&def).unwrap();
+
+    assert_eq!(store_a.blob_count().unwrap(), 2);
+
+    let id = DefinitionId::new("agents/arch.md");
+    assert_eq!(store_a.fetch(&id).await.unwrap().body, "Body of Architect.");
+    assert_eq!(store_b.fetch(&id).await.unwrap().body, "Body of Architect.");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn prune_blobs_removes_unreferenced_content() {
+    let store = create_store();
+
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "Alpha", DefinitionKind::Agent))
+        .unwrap();
+    assert_eq!(store.blob_count().unwrap(), 2);
+
+    store.clear_definitions().unwrap();
+    assert_eq!(store.prune_blobs().unwrap(), 2);
+    assert_eq!(store.blob_count().unwrap(), 0);
+}
+
+#[test]
+fn kept_content_survives_pruning() {
+    let store = create_store();
+
+    let hash = store.keep_content("As installed.").unwrap();
+    assert_eq!(store.prune_blobs().unwrap(), 0);
+    assert_eq!(store.content(&hash).unwrap().as_deref(), Some("As installed."));
+    assert_eq!(store.content("0000").unwrap(), None);
+}
+
+#[tokio::test]
+async fn large_content_is_compressed_transparently() {
+    let store = create_store();
+
+    let mut def = sample_definition("agents/big.md", "Big", DefinitionKind::Agent);
+    def.body = "You are a meticulous reviewer. Check every line.\n".repeat(200);
+    def.raw = format!("---\nname: Big\n---\n{}", def.body);
+    store.upsert_definition(&def).unwrap();
+
+    let stats = store.blob_stats().unwrap();
+    assert_eq!(stats.blobs, 2);
+    assert!(
+        stats.stored_bytes * 4 < stats.content_bytes,
+        "expected compression, got {stats:?}"
+    );
+
+    let id = DefinitionId::new("agents/big.md");
+    assert_eq!(store.fetch(&id).await.unwrap().body, def.body);
+    assert_eq!(store.fetch_raw(&id).await.unwrap(), def.raw);
+
+    let results = store.search("meticulous reviewer").await.unwrap();
+    assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn small_content_is_stored_plain() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+
+    let stats = store.blob_stats().unwrap();
+    assert_eq!(stats.stored_bytes, stats.content_bytes);
+}
+
+#[test]
+fn usage_counts_views_and_installs() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+    let id = DefinitionId::new("agents/a.md");
+
+    assert!(store.record_usage(&id, UsageKind::View).unwrap());
+    assert!(store.record_usage(&id, UsageKind::View).unwrap());
+    assert!(store.record_usage(&id, UsageKind::Install).unwrap());
+
+    let usage = store.usage().unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].name, "A");
+    assert_eq!((usage[0].views, usage[0].installs), (2, 1));
+}
+
+#[test]
+fn usage_ignores_unknown_definitions() {
+    let store = create_store();
+    let recorded = store
+        .record_usage(&DefinitionId::new("agents/missing.md"), UsageKind::View)
+        .unwrap();
+    assert!(!recorded);
+    assert!(store.usage().unwrap().is_empty());
+}
+
+#[test]
+fn usage_survives_resync_and_can_be_wiped() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+    store
+        .record_usage(&DefinitionId::new("agents/a.md"), UsageKind::Install)
+        .unwrap();
+
+    store.clear_definitions().unwrap();
+    let usage = store.usage().unwrap();
+    assert_eq!(usage[0].name, "agents/a.md");
+
+    assert_eq!(store.clear_usage().unwrap(), 1);
+    assert!(store.usage().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn lookups_accept_ids_qualified_with_the_label() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+    let qualified = DefinitionId::new("test-source:agents/a.md");
+
+    assert_eq!(store.fetch(&qualified).await.unwrap().id.as_str(), "agents/a.md");
+    assert!(store.fetch_raw(&qualified).await.unwrap().contains("name: A"));
+    assert!(store.set_note(&qualified, "qualified").unwrap());
+    assert_eq!(store.note(&DefinitionId::new("agents/a.md")).unwrap().unwrap().text, "qualified");
+    assert!(store.record_usage(&qualified, UsageKind::View).unwrap());
+
+    let elsewhere = DefinitionId::new("other:agents/a.md");
+    assert!(matches!(store.fetch(&elsewhere).await, Err(SourceError::NotFound(_))));
+}
+
+#[test]
+fn notes_replace_and_remove() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+    let id = DefinitionId::new("agents/a.md");
+
+    assert!(store.set_note(&id, "first draft").unwrap());
+    assert!(store.set_note(&id, "use with the reviewer").unwrap());
+    assert_eq!(store.note(&id).unwrap().unwrap().text, "use with the reviewer");
+    assert_eq!(store.notes().unwrap().len(), 1);
+
+    assert!(!store.set_note(&DefinitionId::new("agents/missing.md"), "x").unwrap());
+
+    assert!(store.remove_note(&id).unwrap());
+    assert!(!store.remove_note(&id).unwrap());
+    assert!(store.note(&id).unwrap().is_none());
+}
+
+#[test]
+fn ratings_are_replaced_per_source() {
+    let store = create_store();
+    let rating = |id: &str, source: &str, score: Option<f32>| Rating {
+        id: DefinitionId::new(id),
+        source_label: source.to_owned(),
+        score,
+        votes: 3,
+        verified: score.is_none(),
+    };
+
+    store
+        .set_ratings(&[
+            rating("agents/b.md", "test-source", Some(4.5)),
+            rating("agents/a.md", "test-source", None),
+            rating("agents/c.md", "other", Some(1.0)),
+        ])
+        .unwrap();
+    let ratings = store.ratings().unwrap();
+    assert_eq!(
+        ratings,
+        [
+            rating("agents/a.md", "test-source", None),
+            rating("agents/b.md", "test-source", Some(4.5)),
+        ]
+    );
+
+    store
+        .set_ratings(&[rating("agents/b.md", "test-source", Some(2.0))])
+        .unwrap();
+    assert_eq!(store.ratings().unwrap(), [rating("agents/b.md", "test-source", Some(2.0))]);
+}
+
+#[test]
+fn install_journal_survives_until_the_batch_ends() {
+    let store = create_store();
+    let (a, b) = (DefinitionId::new("agents/a.md"), DefinitionId::new("agents/b.md"));
+    let target = std::path::Path::new("/project");
+    let batch = store
+        .begin_install_batch("backend", target, [("acme", &a), ("other", &b)])
+        .unwrap();
+
+    let path = target.join(".claude/agents/a.md");
+    store.journal_started(batch, 0, &path, Some("old a")).unwrap();
+    store.journal_finished(batch, 0, None).unwrap();
+    store.journal_started(batch, 1, &target.join("b.md"), None).unwrap();
+
+    let batches = store.install_batches().unwrap();
+    assert_eq!(batches.len(), 1);
+    let entries = &batches[0].entries;
+    assert_eq!(batches[0].label, "backend");
+    assert_eq!(batches[0].target, target);
+    assert_eq!(entries[0].state, JournalState::Done);
+    assert_eq!(entries[0].path.as_deref(), Some(path.as_path()));
+    assert_eq!(entries[0].previous.as_deref(), Some("old a"));
+    assert_eq!(entries[1].source_label, "other");
+    assert_eq!(entries[1].state, JournalState::Started);
+    assert_eq!(entries[1].previous, None);
+
+    store.journal_finished(batch, 1, Some("disk full")).unwrap();
+    assert_eq!(
+        store.install_batches().unwrap()[0].entries[1].state,
+        JournalState::Failed("disk full".into())
+    );
+    store.end_install_batch(batch).unwrap();
+    assert!(store.install_batches().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn search_matches_note_text() {
+    let store = create_store();
+    store
+        .upsert_definition(&sample_definition("agents/a.md", "A", DefinitionKind::Agent))
+        .unwrap();
+    store
+        .set_note(&DefinitionId::new("agents/a.md"), "Great for Terraform reviews")
+        .unwrap();
+
+    let results = store.search("terraform").await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id.as_str(), "agents/a.md");
+}
+
+#[tokio::test]
+async fn for_each_body_visits_bodies_in_id_order() {
+    let store = create_store();
+    let definitions = [("agents/b.md", "Beta"), ("agents/a.md", "Alpha"), ("agents/c.md", "Gamma")];
+    for (id, name) in definitions {
+        store
+            .upsert_definition(&sample_definition(id, name, DefinitionKind::Agent))
+            .unwrap();
+    }
+
+    let mut seen = Vec::new();
+    store
+        .for_each_body(|id, body| {
+            seen.push((id.as_str().to_owned(), body.to_owned()));
+            seen.len() < 2
+        })
+        .unwrap();
+    assert_eq!(
+        seen,
+        [
+            ("agents/a.md".to_owned(), "Body of Alpha.".to_owned()),
+            ("agents/b.md".to_owned(), "Body of Beta.".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn count_by_groups_definitions() {
+    let store = create_store();
+    let mut uncategorized = sample_definition("hooks/fmt.json", "fmt", DefinitionKind::Hook);
+    uncategorized.category = None;
+    for def in [
+        sample_definition("agents/a.md", "A", DefinitionKind::Agent),
+        sample_definition("agents/b.md", "B", DefinitionKind::Agent),
+        uncategorized,
+    ] {
+        store.upsert_definition(&def).unwrap();
+    }
+
+    assert_eq!(
+        store.count_by(CountBy::Kind).unwrap(),
+        [(Some("agent".to_owned()), 2), (Some("hook".to_owned()), 1)]
+    );
+    assert_eq!(
+        store.count_by(CountBy::Category).unwrap(),
+        [(Some("test-category".to_owned()), 2), (None, 1)]
+    );
+    assert_eq!(
+        store.count_by(CountBy::Source).unwrap(),
+        [(Some("test-source".to_owned()), 3)]
+    );
+}
+
+#[tokio::test]
+async fn reparse_rebuilds_definitions_from_their_raw_files() {
+    let store = create_store();
+    // Cached by an older parser that ignored the description.
+    let stale = Definition {
+        description: None,
+        category: None,
+        tools: Vec::new(),
+        model: None,
+        metadata: HashMap::new(),
+        body: "Review it.".to_owned(),
+        raw: "---\nname: reviewer\ndescription: Reviews code\n---\nReview it.".to_owned(),
+        ..sample_definition("agents/reviewer.md", "reviewer", DefinitionKind::Agent)
+    };
+    store.upsert_definition(&stale).unwrap();
+
+    let report = store.reparse(true).unwrap();
+    assert_eq!(report.checked, 1);
+    assert!(report.failed.is_empty());
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(report.changed[0].id.as_str(), "agents/reviewer.md");
+    assert!(report.changed[0].fields.contains(&"description"));
+    let id = DefinitionId::new("agents/reviewer.md");
+    assert_eq!(store.fetch(&id).await.unwrap().description, None);
+
+    assert_eq!(store.reparse(false).unwrap().changed.len(), 1);
+    assert_eq!(
+        store.fetch(&id).await.unwrap().description.as_deref(),
+        Some("Reviews code")
+    );
+    assert!(store.reparse(false).unwrap().changed.is_empty());
+}
+
+#[tokio::test]
+async fn sync_dates_are_kept_until_the_raw_file_changes() {
+    let path = std::env::temp_dir().join("agent-defs-store-dates.db");
+    let _ = std::fs::remove_file(&path);
+    let store = DefinitionStore::open(&path, "test-source").unwrap();
+    let mut def = sample_definition("agents/a.md", "A", DefinitionKind::Agent);
+    store.upsert_definition(&def).unwrap();
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute(
+            "UPDATE definitions SET first_seen_at = 100, last_updated_at = 200",
+            [],
+        )
+        .unwrap();
+    let dates = |summaries: Vec<agent_defs::DefinitionSummary>| {
+        (summaries[0].first_seen_at, summaries[0].last_updated_at)
+    };
+
+    def.description = Some("Reparsed".to_owned());
+    store.upsert_definition(&def).unwrap();
+    assert_eq!(dates(store.list().await.unwrap()), (Some(100), Some(200)));
+
+    def.raw.push_str("\nMore.");
+    store.upsert_definition(&def).unwrap();
+    let (first_seen, updated) = dates(store.list().await.unwrap());
+    assert_eq!(first_seen, Some(100));
+    assert!(updated.unwrap() > 200);
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -1,29 +1,13 @@
-use agent_defs::{DefinitionId, DefinitionKind, RawDefinitionFile, Source, SyncError, SyncProvider};
-use agent_defs_store::{DefinitionStore, SyncStatus};
+use agent_defs::test_support::FakeSyncProvider;
+use std::sync::Arc;
 
-struct FakeSyncProvider {
-    label: String,
-    files: Vec<RawDefinitionFile>,
-}
-
-impl FakeSyncProvider {
-    fn new(files: Vec<RawDefinitionFile>) -> Self {
-        Self {
-            label: "fake-source".to_owned(),
-            files,
-        }
-    }
-}
+use agent_defs::{
+    ChangeKind, DefinitionId, DefinitionKind, FileCheck, RawDefinitionFile, Source, SyncError,
+};
+use agent_defs_store::{DefinitionStore, SourceHealth, SyncStatus};
 
-#[async_trait::async_trait]
-impl SyncProvider for FakeSyncProvider {
-    fn label(&self) -> &str {
-        &self.label
-    }
-
-    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
-        Ok(self.files.clone())
-    }
+fn fake_provider(files: Vec<RawDefinitionFile>) -> FakeSyncProvider {
+    FakeSyncProvider::new("fake-source").with_files(files)
 }
 
 fn markdown_file(path: &str, name: &str, description: &str) -> RawDefinitionFile {
@@ -67,7 +51,7 @@ fn create_store() -> DefinitionStore {
 #[tokio::test]
 async fn sync_populates_store_with_definitions() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![
+    let provider = fake_provider(vec![
         markdown_file(
             "agents/development-team/code-architect.md",
             "Code Architect",
@@ -95,7 +79,7 @@ async fn sync_populates_store_with_definitions() {
 #[tokio::test]
 async fn sync_parses_descriptions_and_tools() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![markdown_file(
+    let provider = fake_provider(vec![markdown_file(
         "agents/team/architect.md",
         "Architect",
         "Designs features",
@@ -117,7 +101,7 @@ async fn sync_parses_descriptions_and_tools() {
 #[tokio::test]
 async fn sync_handles_json_definitions() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![json_file(
+    let provider = fake_provider(vec![json_file(
         "agents/team/data.json",
         "test-agent",
         "A test agent",
@@ -136,7 +120,7 @@ async fn sync_handles_json_definitions() {
 #[tokio::test]
 async fn sync_groups_skills_by_directory_id() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![
+    let provider = fake_provider(vec![
         skill_file("ai-research", "agents-crewai", "Set up CrewAI"),
         skill_reference("ai-research", "agents-crewai", "crew-setup"),
     ]);
@@ -155,12 +139,40 @@ async fn sync_groups_skills_by_directory_id() {
     assert_eq!(skill.id.as_str(), "skills/ai-research/agents-crewai");
 }
 
+#[tokio::test]
+async fn sync_keeps_branch_prefixes_in_ids() {
+    let store = create_store();
+    let mut skill = skill_file("ai", "crewai", "Set up CrewAI");
+    skill.relative_path = format!("next:{}", skill.relative_path);
+    let provider = fake_provider(vec![
+        markdown_file("main:agents/team/lead.md", "lead", "Leads"),
+        markdown_file("next:agents/team/lead.md", "lead", "Leads, but newer"),
+        skill,
+    ]);
+
+    let report = store.sync(&provider).await.unwrap();
+    assert_eq!(report.synced, 3);
+
+    let mut summaries = store.list().await.unwrap();
+    summaries.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+    let ids: Vec<&str> = summaries.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        ["main:agents/team/lead.md", "next:agents/team/lead.md", "next:skills/ai/crewai"]
+    );
+    // Kind and category come from the path within the branch.
+    assert_eq!(summaries[1].kind, DefinitionKind::Agent);
+    assert_eq!(summaries[1].category.as_deref(), Some("team"));
+    assert_eq!(summaries[2].kind, DefinitionKind::Skill);
+    assert_eq!(summaries[2].category.as_deref(), Some("ai"));
+}
+
 #[tokio::test]
 async fn sync_replaces_old_data() {
     let store = create_store();
 
     // First sync
-    let provider1 = FakeSyncProvider::new(vec![
+    let provider1 = fake_provider(vec![
         markdown_file("agents/team/old.md", "Old Agent", "Will be replaced"),
     ]);
     store.sync(&provider1).await.unwrap();
@@ -170,7 +182,7 @@ async fn sync_replaces_old_data() {
     assert_eq!(summaries[0].name, "Old Agent");
 
     // Second sync with different data
-    let provider2 = FakeSyncProvider::new(vec![
+    let provider2 = fake_provider(vec![
         markdown_file("agents/team/new.md", "New Agent", "Fresh data"),
     ]);
     store.sync(&provider2).await.unwrap();
@@ -184,10 +196,49 @@ async fn sync_replaces_old_data() {
     assert!(old_result.is_err());
 }
 
+#[tokio::test]
+async fn resync_updates_surviving_rows_in_place() {
+    let path = std::env::temp_dir().join("agent-defs-store-test-resync-in-place.db");
+    let _ = std::fs::remove_file(&path);
+    let store = DefinitionStore::open(&path, "fake-source").unwrap();
+    let rowid = |id: &str| -> i64 {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.query_row(
+            "SELECT rowid FROM definitions WHERE source_label = 'fake-source' AND id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    };
+
+    let first = fake_provider(vec![
+        markdown_file("agents/kept.md", "Kept", "Before"),
+        markdown_file("agents/gone.md", "Gone", "Leaving"),
+    ]);
+    store.sync(&first).await.unwrap();
+    let kept = rowid("agents/kept.md");
+
+    let second = fake_provider(vec![
+        markdown_file("agents/new.md", "New", "Arrived"),
+        markdown_file("agents/kept.md", "Kept", "After"),
+    ]);
+    store.sync(&second).await.unwrap();
+
+    assert_eq!(rowid("agents/kept.md"), kept);
+    let summaries = store.list().await.unwrap();
+    let ids: Vec<_> = summaries.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&"agents/kept.md") && ids.contains(&"agents/new.md"));
+    let kept = store.fetch(&DefinitionId::new("agents/kept.md")).await.unwrap();
+    assert_eq!(kept.description.as_deref(), Some("After"));
+
+    let _ = std::fs::remove_file(&path);
+}
+
 #[tokio::test]
 async fn sync_status_is_fresh_after_sync() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![]);
+    let provider = fake_provider(vec![]);
 
     assert_eq!(store.sync_status().unwrap(), SyncStatus::NeverSynced);
 
@@ -199,10 +250,46 @@ async fn sync_status_is_fresh_after_sync() {
     ));
 }
 
+#[tokio::test]
+async fn failed_syncs_build_a_streak_until_one_succeeds() {
+    let store = create_store();
+    let provider = fake_provider(vec![])
+        .failing_next(SyncError::Network("connection reset".into()))
+        .failing_next(SyncError::NotFound("acme/agents".into()));
+    assert_eq!(store.health().unwrap(), SourceHealth::default());
+
+    store.sync(&provider).await.unwrap_err();
+    store.sync(&provider).await.unwrap_err();
+    let health = store.health().unwrap();
+    assert_eq!(health.failure_streak, 2);
+    assert!(health.last_error.unwrap().contains("acme/agents"));
+    assert!(health.last_failed_at.is_some());
+    assert!(store.health().unwrap().is_degraded(2));
+    assert!(!store.health().unwrap().is_degraded(3));
+    assert!(!store.health().unwrap().is_degraded(0));
+
+    store.sync(&provider).await.unwrap();
+    assert_eq!(store.health().unwrap(), SourceHealth::default());
+}
+
+#[tokio::test]
+async fn sync_records_the_revision_it_got() {
+    let store = create_store();
+    store.sync(&fake_provider(vec![])).await.unwrap();
+    assert_eq!(store.revision().unwrap(), None);
+
+    let provider = fake_provider(vec![]).with_revision("1a2b3c4");
+    store.sync(&provider).await.unwrap();
+    assert_eq!(store.revision().unwrap().as_deref(), Some("1a2b3c4"));
+
+    store.set_last_synced_at(0).unwrap();
+    assert_eq!(store.revision().unwrap().as_deref(), Some("1a2b3c4"));
+}
+
 #[tokio::test]
 async fn sync_skips_non_definition_files() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![
+    let provider = fake_provider(vec![
         markdown_file("agents/team/valid.md", "Valid", "A valid agent"),
         RawDefinitionFile {
             relative_path: "README.txt".to_owned(),
@@ -219,10 +306,131 @@ async fn sync_skips_non_definition_files() {
     assert_eq!(report.skipped, 2);
 }
 
+#[tokio::test]
+async fn sync_skips_files_over_size_limit() {
+    let store = create_store().with_max_file_bytes(256);
+    let mut oversized = markdown_file("agents/team/huge.md", "Huge", "Far too large");
+    oversized.content.push_str(&"x".repeat(512));
+    let provider = fake_provider(vec![
+        markdown_file("agents/team/small.md", "Small", "Fits comfortably"),
+        oversized,
+    ]);
+
+    let report = store.sync(&provider).await.unwrap();
+    assert_eq!(report.synced, 1);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.feedback.len(), 1);
+    assert!(report.feedback[0].is_warning());
+    assert!(report.feedback[0].message().contains("agents/team/huge.md"));
+    assert!(report.feedback[0].message().contains("256 byte limit"));
+
+    let summaries = store.list().await.unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].name, "Small");
+}
+
+/// Accepts a file only when an `.ok` file sits beside it.
+struct NeedsCompanion;
+
+impl FileCheck for NeedsCompanion {
+    fn reject<'a>(
+        &self,
+        file: &RawDefinitionFile,
+        companion: &dyn Fn(&str) -> Option<&'a str>,
+    ) -> Option<String> {
+        let marker = format!("{}.ok", file.relative_path);
+        companion(&marker).is_none().then(|| "no marker".to_owned())
+    }
+}
+
+#[tokio::test]
+async fn sync_skips_files_the_check_rejects() {
+    let store = create_store().with_file_check(Arc::new(NeedsCompanion));
+    let provider = fake_provider(vec![
+        markdown_file("agents/team/vetted.md", "Vetted", "Has a marker"),
+        RawDefinitionFile {
+            relative_path: "agents/team/vetted.md.ok".to_owned(),
+            content: String::new(),
+        },
+        markdown_file("agents/team/stray.md", "Stray", "Has none"),
+    ]);
+
+    let report = store.sync(&provider).await.unwrap();
+    assert_eq!(report.synced, 1);
+    assert_eq!(report.feedback.len(), 1);
+    assert_eq!(
+        report.feedback[0].message(),
+        "skipping agents/team/stray.md: no marker"
+    );
+    let skipped = store.skipped_files().unwrap();
+    assert_eq!(skipped[0].path, "agents/team/stray.md");
+    assert_eq!(store.list().await.unwrap()[0].name, "Vetted");
+}
+
+#[tokio::test]
+async fn definitions_synced_under_another_signing_policy_are_refused() {
+    let id = DefinitionId::new("agents/team/vetted.md");
+    let provider = fake_provider(vec![markdown_file("agents/team/vetted.md", "Vetted", "Ok")]);
+    let store = create_store();
+    store.sync(&provider).await.unwrap();
+    assert!(store.fetch(&id).await.is_ok());
+
+    // Cached before the policy was turned on.
+    let store = store.with_signing_policy("key-a");
+    let err = store.fetch(&id).await.unwrap_err();
+    assert!(err.to_string().contains("synced before the current [signing] policy"), "{err}");
+    assert!(store.fetch_raw(&id).await.is_err());
+
+    store.sync(&provider).await.unwrap();
+    assert!(store.fetch(&id).await.is_ok());
+
+    // And after its keys changed.
+    let store = store.with_signing_policy("key-b");
+    assert!(store.fetch(&id).await.is_err());
+}
+
+#[tokio::test]
+async fn skipped_files_are_kept_until_the_next_sync() {
+    let store = create_store().with_max_file_bytes(256);
+    let mut oversized = markdown_file("agents/team/huge.md", "Huge", "Far too large");
+    oversized.content.push_str(&"x".repeat(512));
+    let broken = RawDefinitionFile {
+        relative_path: "agents/team/broken.json".to_owned(),
+        content: "{not json".to_owned(),
+    };
+    let small = markdown_file("agents/team/small.md", "Small", "Fits comfortably");
+    let provider = fake_provider(vec![small.clone(), oversized, broken]);
+    store.sync(&provider).await.unwrap();
+
+    let skipped = store.skipped_files().unwrap();
+    let paths: Vec<_> = skipped.iter().map(|s| s.path.as_str()).collect();
+    assert_eq!(paths, ["agents/team/broken.json", "agents/team/huge.md"]);
+    assert!(skipped[1].reason.ends_with("exceeds the 256 byte limit"));
+    assert!(skipped.iter().all(|s| s.source_label == "fake-source"));
+
+    store.sync(&fake_provider(vec![small])).await.unwrap();
+    assert!(store.skipped_files().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sync_stores_raw_for_lazy_loading() {
+    let store = create_store();
+    let file = markdown_file("agents/team/lazy.md", "Lazy", "Loaded on demand");
+    let expected_raw = file.content.clone();
+    let provider = fake_provider(vec![file]);
+
+    store.sync(&provider).await.unwrap();
+
+    let id = DefinitionId::new("agents/team/lazy.md");
+    let def = store.fetch(&id).await.unwrap();
+    assert!(def.raw.is_empty());
+    assert_eq!(store.fetch_raw(&id).await.unwrap(), expected_raw);
+}
+
 #[tokio::test]
 async fn search_works_after_sync() {
     let store = create_store();
-    let provider = FakeSyncProvider::new(vec![
+    let provider = fake_provider(vec![
         markdown_file(
             "agents/team/architect.md",
             "Code Architect",
@@ -246,3 +454,110 @@ async fn search_works_after_sync() {
     assert_eq!(body_results.len(), 1);
     assert_eq!(body_results[0].name, "Test Runner");
 }
+
+#[tokio::test]
+async fn resync_prunes_content_that_disappeared() {
+    let store = create_store();
+    let first = fake_provider(vec![
+        markdown_file("agents/team/old.md", "Old", "Going away"),
+        markdown_file("agents/team/kept.md", "Kept", "Sticking around"),
+    ]);
+    store.sync(&first).await.unwrap();
+    assert_eq!(store.blob_count().unwrap(), 4);
+
+    let second = fake_provider(vec![markdown_file(
+        "agents/team/kept.md",
+        "Kept",
+        "Sticking around",
+    )]);
+    store.sync(&second).await.unwrap();
+    assert_eq!(store.blob_count().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn first_sync_records_no_changes() {
+    let store = create_store();
+    let provider = fake_provider(vec![markdown_file("agents/a.md", "A", "First")]);
+    let report = store.sync(&provider).await.unwrap();
+
+    assert!(report.changes.is_empty());
+    assert!(store.changes_since(0).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn resync_records_added_removed_and_changed() {
+    let store = create_store();
+    let first = fake_provider(vec![
+        markdown_file("agents/kept.md", "Kept", "Same"),
+        markdown_file("agents/edited.md", "Edited", "Before"),
+        markdown_file("agents/gone.md", "Gone", "Leaving"),
+    ]);
+    store.sync(&first).await.unwrap();
+
+    let second = fake_provider(vec![
+        markdown_file("agents/kept.md", "Kept", "Same"),
+        markdown_file("agents/edited.md", "Edited", "After"),
+        markdown_file("agents/new.md", "New", "Arrived"),
+    ]);
+    let report = store.sync(&second).await.unwrap();
+
+    let summary: Vec<_> = report
+        .changes
+        .iter()
+        .map(|c| (c.change, c.id.as_str(), c.name.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (ChangeKind::Added, "agents/new.md", "New"),
+            (ChangeKind::Changed, "agents/edited.md", "Edited"),
+            (ChangeKind::Removed, "agents/gone.md", "Gone"),
+        ]
+    );
+    assert!(report.changes.iter().all(|c| c.source_label == "fake-source"));
+    assert_eq!(store.changes_since(0).unwrap(), report.changes);
+
+    let later = report.changes[0].synced_at + 1;
+    assert!(store.changes_since(later).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sync_normalizes_categories_and_keeps_the_original() {
+    let overrides = [("Reviews".to_owned(), "code-quality".to_owned())];
+    let store = create_store().with_categories(agent_defs::CategoryMap::new(overrides));
+    let provider = fake_provider(vec![
+        markdown_file("agents/testing-qa/runner.md", "Runner", "Runs tests"),
+        markdown_file("agents/reviews/linter.md", "Linter", "Lints"),
+        markdown_file("agents/backend/api.md", "API", "Builds APIs"),
+    ]);
+    store.sync(&provider).await.unwrap();
+
+    let runner = store.fetch(&DefinitionId::new("agents/testing-qa/runner.md")).await.unwrap();
+    assert_eq!(runner.category.as_deref(), Some("testing"));
+    assert_eq!(runner.metadata["original_category"], "testing-qa");
+
+    let linter = store.fetch(&DefinitionId::new("agents/reviews/linter.md")).await.unwrap();
+    assert_eq!(linter.category.as_deref(), Some("code-quality"));
+
+    let api = store.fetch(&DefinitionId::new("agents/backend/api.md")).await.unwrap();
+    assert_eq!(api.category.as_deref(), Some("backend"));
+    assert!(!api.metadata.contains_key("original_category"));
+}
+
+#[tokio::test]
+async fn cached_files_round_trip_through_sync() {
+    let store = create_store();
+    let files = vec![
+        markdown_file("agents/reviewer.md", "Reviewer", "Reviews code"),
+        skill_file("dev", "lint", "Lints code"),
+    ];
+    store.sync(&fake_provider(files.clone())).await.unwrap();
+
+    let cached = store.cached_files().unwrap();
+    let paths: Vec<&str> = cached.iter().map(|f| f.relative_path.as_str()).collect();
+    assert_eq!(paths, ["agents/reviewer.md", "skills/dev/lint/SKILL.md"]);
+    assert_eq!(cached[1].content, files[1].content);
+
+    let report = store.sync(&fake_provider(cached)).await.unwrap();
+    assert!(report.changes.is_empty());
+}
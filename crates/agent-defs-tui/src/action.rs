@@ -1,8 +1,20 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use agent_defs::{Definition, DefinitionId};
+use agent_defs::template::TemplateVar;
+use agent_defs::{Definition, DefinitionId, DefinitionNote, HideRule, UsageKind};
 
-use crate::SyncResult;
+use crate::clipboard::ClipboardPath;
+use crate::{Freshness, SyncEvent, SyncTarget};
+
+/// A program outside the TUI that a definition can be handed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalTool {
+    /// `$VISUAL` or `$EDITOR`.
+    Editor,
+    /// `$PAGER`.
+    Pager,
+}
 
 /// Commands returned by the app to the event loop for side-effect execution.
 #[derive(Debug)]
@@ -13,16 +25,38 @@ pub enum AppCommand {
     Quit,
     /// Fetch a full definition by ID.
     FetchDefinition(DefinitionId),
-    /// Trigger a sync operation.
-    Sync,
+    /// Sync every source, or retry just one.
+    Sync(SyncTarget),
     /// Copy the given text to the system clipboard.
     CopyBody(String),
     /// Reload the definition list from the source.
     ReloadList,
-    /// Install a definition's raw content to the given path.
-    Install { raw: String, install_path: PathBuf },
+    /// Load a definition's raw content and install it to the given path.
+    /// `vars` is `None` until the user has been asked for template values.
+    Install {
+        id: DefinitionId,
+        install_path: PathBuf,
+        vars: Option<HashMap<String, String>>,
+    },
+    /// Install each of `ids` under `target`. `total` is the size of the
+    /// collection they came from, which may include unresolved items.
+    InstallMany {
+        label: String,
+        ids: Vec<DefinitionId>,
+        total: usize,
+        target: PathBuf,
+    },
     /// Dismiss the sync overlay (user acknowledged).
     DismissSyncOverlay,
+    /// Count a view or install of a definition in the host's usage stats.
+    RecordUsage(DefinitionId, UsageKind),
+    /// Store a note in the host's cache; an empty note removes it.
+    SaveNote(DefinitionNote),
+    /// Persist a hide rule the user just added.
+    Hide(HideRule),
+    /// Suspend the TUI and open a definition's raw content in an editor or
+    /// pager, resuming when it exits.
+    OpenExternal(DefinitionId, ExternalTool),
 }
 
 /// Actions dispatched back into the app from async tasks.
@@ -32,10 +66,22 @@ pub enum Action {
     DefinitionLoaded(DefinitionId, Box<Result<Definition, String>>),
     /// The definition list was reloaded.
     ListReloaded(Result<Vec<agent_defs::DefinitionSummary>, String>),
-    /// A sync operation completed.
-    SyncCompleted(Result<SyncResult, String>),
+    /// A source started or finished syncing.
+    SyncProgress(SyncEvent),
+    /// Every source in the sync is done; carries each source's freshness.
+    SyncFinished(Vec<(String, Freshness)>),
     /// Clipboard copy completed.
-    CopyCompleted(Result<(), String>),
+    CopyCompleted(Result<ClipboardPath, String>),
     /// Install operation completed.
     InstallCompleted(Result<String, String>),
+    /// A hide rule was persisted (or failed to be).
+    HideSaved(Result<(), String>),
+    /// The external editor or pager exited.
+    ExternalClosed(Result<String, String>),
+    /// The definition declares template variables that need values first.
+    InstallNeedsVars {
+        id: DefinitionId,
+        install_path: PathBuf,
+        vars: Vec<TemplateVar>,
+    },
 }
@@ -1,7 +1,15 @@
-use std::path::PathBuf;
-use std::time::Instant;
-
-use agent_defs::{Definition, DefinitionId, DefinitionKind, DefinitionSummary};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use agent_defs::grouping::{self, Group, ItemOrder, ListRow};
+use agent_defs::merge::KeyChange;
+use agent_defs::template::TemplateVar;
+use agent_defs::{
+    Collection, Definition, DefinitionChange, DefinitionId, DefinitionKind, DefinitionNote,
+    DefinitionSummary, DefinitionUsage, DisplayNames, GlyphSet, HideRule, HideRules,
+    InstallLayout, ListDensity, Rating, SkippedFile, SyncError, TaskQueue, TextQuery, UsageKind,
+};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::layout::{Position, Rect};
 use ratatui_explorer::{FileExplorer, Input, Theme};
@@ -9,9 +17,22 @@ use ratatui_explorer::{FileExplorer, Input, Theme};
 /// Duration threshold for detecting double-clicks (in milliseconds).
 const DOUBLE_CLICK_THRESHOLD_MS: u128 = 400;
 
-use crate::action::{Action, AppCommand};
-use crate::grouping::{self, Group, ListRow};
-use crate::SyncResult;
+/// Default share of the content width given to the list pane (percent).
+pub const DEFAULT_LIST_PERCENT: u16 = 35;
+/// Narrowest and widest the list pane may be resized to (percent).
+const MIN_LIST_PERCENT: u16 = 15;
+const MAX_LIST_PERCENT: u16 = 85;
+/// How far `[` and `]` move the divider (percent).
+const SPLIT_STEP: u16 = 5;
+
+/// Upper bound on a typed count prefix such as `5j`.
+const MAX_COUNT: usize = 9999;
+
+/// Rows the category filter overlay shows before it scrolls.
+pub const CATEGORY_FILTER_ROWS: usize = 15;
+
+use crate::action::{Action, AppCommand, ExternalTool};
+use crate::{Freshness, SourceReport, SyncEvent, SyncResult, SyncTarget};
 
 /// Tracks clickable regions for mouse hit testing.
 #[derive(Debug, Clone, Default)]
@@ -24,6 +45,30 @@ pub struct LayoutGeometry {
     pub overlay: Option<Rect>,
     /// Inner area of the file explorer list (for click-to-select in InstallPrompt mode).
     pub explorer_list_inner: Option<Rect>,
+    /// Main content area holding both panes (used to map drags to a split).
+    pub content: Rect,
+    /// Grab area for the divider: the adjoining borders of the two panes.
+    pub divider: Rect,
+    /// Each filter chip's area, in the order they are shown.
+    pub chips: Vec<(Rect, FilterChip)>,
+    /// Scroll position of each section of the detail body, in order.
+    pub detail_sections: Vec<u16>,
+    /// Scroll position of each match of the detail search, in order.
+    pub detail_matches: Vec<u16>,
+    /// Scroll position of each line of the detail body, in order.
+    pub detail_lines: Vec<u16>,
+}
+
+/// One active filter, shown as a chip under the title bar that clears it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterChip {
+    Kind,
+    Source,
+    Branch,
+    Category,
+    Collection,
+    MostUsed,
+    Search,
 }
 
 /// UI mode the app is currently in.
@@ -33,9 +78,203 @@ pub enum Mode {
     Search,
     KindFilter,
     SourceFilter,
+    CategoryFilter,
     InstallPrompt,
     InstallConfirm,
+    InstallVars,
     SyncProgress,
+    WhatsNew,
+    NoteEditor,
+    Warnings,
+    Tasks,
+    /// Typing a query to find in the full-screen detail body.
+    DetailSearch,
+    /// Typing the number of a body line to scroll the detail pane to.
+    GoToLine,
+    /// Reading the diff of the marked definition and the selected one.
+    Compare,
+}
+
+/// An entry in the source filter overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceOption {
+    All,
+    Source(String),
+    /// One branch of a source that syncs several side by side.
+    Branch(String),
+    /// A collection, browsed as if it were a source.
+    Collection(String),
+    /// Definitions the user has viewed or installed before.
+    MostUsed,
+}
+
+/// Values for a parameterized definition's template variables, collected
+/// before it is installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallForm {
+    pub id: DefinitionId,
+    pub install_path: PathBuf,
+    /// Variable names and the values typed so far, in declaration order.
+    pub fields: Vec<(String, String)>,
+    /// Index of the field being edited.
+    pub cursor: usize,
+}
+
+/// What installing a settings definition would do to the settings file
+/// it merges into, shown in place of its raw JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsPreview {
+    pub path: PathBuf,
+    /// Keys the install would add or change, or why the file can't be read.
+    pub changes: Result<Vec<KeyChange>, String>,
+}
+
+impl SettingsPreview {
+    /// The preview for installing `def` under `target`, if it's a settings
+    /// definition that merges into a settings file.
+    pub fn new(target: &Path, def: &Definition) -> Option<Self> {
+        if def.kind != DefinitionKind::Setting {
+            return None;
+        }
+        let path = agent_defs::install::merge_target(target, def)?;
+        let changes = agent_defs::merge::plan(&path, &def.raw)
+            .map(|plan| plan.changes)
+            .map_err(|e| e.to_string());
+        Some(Self { path, changes })
+    }
+}
+
+/// One line of a comparison: in both definitions, or only one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareLine {
+    Same(String),
+    /// Only in the marked definition.
+    Removed(String),
+    /// Only in the one compared with it.
+    Added(String),
+}
+
+/// The marked definition's file diffed against another's, frontmatter and
+/// body both, as `=` shows them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    /// Names of the marked definition and the other one, or their
+    /// qualified IDs when the names alone don't tell them apart.
+    pub left: String,
+    pub right: String,
+    pub lines: Vec<CompareLine>,
+    /// Whether the two are shown in columns rather than as one diff.
+    pub side_by_side: bool,
+    /// First row shown.
+    pub scroll: usize,
+}
+
+impl Comparison {
+    pub fn new(left: &Definition, right: &Definition) -> Self {
+        // Definitions built without their file have only a body to show.
+        let text = |def: &Definition| {
+            if def.raw.is_empty() {
+                def.body.clone()
+            } else {
+                def.raw.clone()
+            }
+        };
+        let (old, new) = (text(left), text(right));
+        let lines = agent_defs::diff::lines(&old, &new)
+            .into_iter()
+            .map(|line| match line {
+                agent_defs::diff::Line::Same(text) => CompareLine::Same(text.to_owned()),
+                agent_defs::diff::Line::Removed(text) => CompareLine::Removed(text.to_owned()),
+                agent_defs::diff::Line::Added(text) => CompareLine::Added(text.to_owned()),
+            })
+            .collect();
+        let (left, right) = if left.name == right.name {
+            (left.qualified_id().to_string(), right.qualified_id().to_string())
+        } else {
+            (left.name.clone(), right.name.clone())
+        };
+        Self {
+            left,
+            right,
+            lines,
+            side_by_side: false,
+            scroll: 0,
+        }
+    }
+
+    /// Rows of the side-by-side view, left then right: each line removed
+    /// beside the one added in its place, if any, and kept lines beside
+    /// themselves.
+    pub fn pairs(&self) -> Vec<(Option<&CompareLine>, Option<&CompareLine>)> {
+        let mut pairs = Vec::new();
+        let mut i = 0;
+        while i < self.lines.len() {
+            if let CompareLine::Same(_) = self.lines[i] {
+                pairs.push((Some(&self.lines[i]), Some(&self.lines[i])));
+                i += 1;
+                continue;
+            }
+            let removed = self.lines[i..]
+                .iter()
+                .take_while(|line| matches!(line, CompareLine::Removed(_)))
+                .count();
+            let added = self.lines[i + removed..]
+                .iter()
+                .take_while(|line| matches!(line, CompareLine::Added(_)))
+                .count();
+            for row in 0..removed.max(added) {
+                pairs.push((
+                    (row < removed).then(|| &self.lines[i + row]),
+                    (row < added).then(|| &self.lines[i + removed + row]),
+                ));
+            }
+            i += removed + added;
+        }
+        pairs
+    }
+
+    /// Whether each row of the current view is a change.
+    fn changed_rows(&self) -> Vec<bool> {
+        if self.side_by_side {
+            self.pairs()
+                .into_iter()
+                .map(|(left, _)| !matches!(left, Some(CompareLine::Same(_))))
+                .collect()
+        } else {
+            self.lines
+                .iter()
+                .map(|line| !matches!(line, CompareLine::Same(_)))
+                .collect()
+        }
+    }
+
+    /// Rows in the current view.
+    pub fn rows(&self) -> usize {
+        self.changed_rows().len()
+    }
+
+    /// Rows where a run of changes starts, in order.
+    fn change_starts(&self) -> Vec<usize> {
+        let changed = self.changed_rows();
+        (0..changed.len())
+            .filter(|&row| changed[row] && (row == 0 || !changed[row - 1]))
+            .collect()
+    }
+
+    /// How many lines only one side or the other has.
+    pub fn counts(&self) -> (usize, usize) {
+        let removed = self
+            .lines
+            .iter()
+            .filter(|line| matches!(line, CompareLine::Removed(_)))
+            .count();
+        let added = self
+            .lines
+            .iter()
+            .filter(|line| matches!(line, CompareLine::Added(_)))
+            .count();
+        (removed, added)
+    }
 }
 
 /// State of background loading operations.
@@ -46,15 +285,39 @@ pub enum LoadingState {
     Syncing,
 }
 
+/// Where one source stands in the current sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceSyncState {
+    /// Not part of a sync since the session started.
+    Idle,
+    /// Waiting for its turn.
+    Pending,
+    Syncing,
+    /// Synced this many definitions.
+    Synced(u64),
+    Failed,
+}
+
+/// A source the host can sync, and how it's doing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSync {
+    pub label: String,
+    pub freshness: Freshness,
+    pub state: SourceSyncState,
+}
+
 /// Transient status message shown in the status bar.
 #[derive(Debug, Clone)]
 pub struct StatusMessage {
     pub text: String,
     pub is_error: bool,
     /// Remaining ticks before the message expires.
-    pub ticks_remaining: u8,
+    pub ticks_remaining: u16,
 }
 
+/// How long a status message stays up.
+const STATUS_DURATION: Duration = Duration::from_secs(3);
+
 /// The TUI application state. This is a pure state machine:
 /// inputs produce commands (side effects), actions update state.
 pub struct App {
@@ -62,6 +325,8 @@ pub struct App {
     pub summaries: Vec<DefinitionSummary>,
     /// Current view summaries (filtered by search or same as summaries).
     pub view_summaries: Vec<DefinitionSummary>,
+    /// Names for the view, qualified where two of them would read the same.
+    pub display_names: DisplayNames,
     /// Source label for display.
     pub source_label: String,
     /// Computed groups from current view.
@@ -72,6 +337,10 @@ pub struct App {
     pub cursor: usize,
     /// Viewport scroll offset for list pane.
     pub list_scroll_offset: usize,
+    /// Count typed before a motion (e.g. the `5` in `5j`).
+    pub pending_count: Option<usize>,
+    /// Whether the first `g` of `gg` has been typed.
+    pending_g: bool,
 
     /// Full definition for the currently selected item.
     pub selected_definition: Option<Definition>,
@@ -87,6 +356,8 @@ pub struct App {
 
     /// Transient feedback message.
     pub status_message: Option<StatusMessage>,
+    /// Time between ticks, which status messages count down in.
+    pub tick_rate: Duration,
     /// Background loading state.
     pub loading: LoadingState,
 
@@ -94,26 +365,102 @@ pub struct App {
     pub kind_filter: Option<DefinitionKind>,
     /// Cursor position in the kind filter overlay list.
     pub kind_filter_cursor: usize,
+    /// Order of the definitions within each kind.
+    pub item_order: ItemOrder,
+    /// Active category filter (None = show all).
+    pub category_filter: Option<String>,
+    /// Cursor position in the category filter overlay list.
+    pub category_filter_cursor: usize,
 
     /// Active source filter (None = show all).
     pub source_filter: Option<String>,
     /// Cursor position in the source filter overlay list.
     pub source_filter_cursor: usize,
+    /// Active branch filter, for sources that sync several branches.
+    pub branch_filter: Option<String>,
+    /// Collections offered in the source filter.
+    pub collections: Vec<Collection>,
+    /// Active collection filter, by name (None = show all).
+    pub collection_filter: Option<String>,
+    /// Recorded views and installs, most used first.
+    pub usage: Vec<DefinitionUsage>,
+    /// Whether the list is narrowed to definitions in `usage`.
+    pub most_used_filter: bool,
+    /// The user's own notes on definitions.
+    pub notes: Vec<DefinitionNote>,
+    /// Text in the note editor while it is open.
+    pub note_draft: String,
+    /// Community ratings, shown as badges beside the definitions they rate.
+    pub ratings: Vec<Rating>,
+    /// Definitions left out of the list entirely.
+    pub hidden: HideRules,
 
     /// Target directory for installing definitions.
     pub install_target: Option<PathBuf>,
+    /// The configured target `I` installs to without opening the explorer.
+    pub quick_install_target: Option<PathBuf>,
+    /// Whether `I` asks before installing. Hooks are always confirmed.
+    pub quick_install_confirm: bool,
+    /// How installed files are laid out under the target.
+    pub install_layout: InstallLayout,
     /// File explorer for selecting install directory.
     pub file_explorer: Option<FileExplorer>,
     /// Pending install path for confirmation dialog.
     pub pending_install_path: Option<PathBuf>,
-
+    /// Whether the install in progress is the whole active collection.
+    pub installing_collection: bool,
+    /// Whether the install being confirmed came from `I`, so cancelling it
+    /// returns to the list rather than an explorer.
+    quick_installing: bool,
+    /// Template variable form for the install in progress.
+    pub install_form: Option<InstallForm>,
+    /// Values entered in earlier forms, offered again for same-named variables.
+    install_var_memory: HashMap<String, String>,
+    /// Changes the selected settings definition would make on install.
+    pub settings_preview: Option<SettingsPreview>,
+
+    /// Syncs and installs, run one at a time and listed in the task list.
+    pub tasks: TaskQueue<AppCommand>,
+    /// Selected row in the task list.
+    pub task_cursor: usize,
     /// Result of last sync operation (for display in overlay).
     pub sync_result: Option<SyncResult>,
-    /// Scroll offset in sync result warnings list.
+    /// The host's sources, with freshness and progress in the current sync.
+    pub sync_sources: Vec<SourceSync>,
+    /// Selected line among the sync result's failures and warnings.
     pub sync_result_scroll: usize,
+    /// Recent changelog entries, newest first.
+    pub changes: Vec<DefinitionChange>,
+    /// Scroll offset in the "What's new" overlay.
+    pub changes_scroll: usize,
+    /// Files the latest sync of each source skipped, by source then path.
+    pub skipped: Vec<SkippedFile>,
+    /// Scroll offset in the warnings overlay, in rows.
+    pub skipped_scroll: usize,
 
     /// Layout geometry for mouse hit testing.
     pub layout_geometry: LayoutGeometry,
+    /// Width of the list pane as a percentage of the content area.
+    pub list_percent: u16,
+    /// Whether the user is currently dragging the pane divider.
+    dragging_divider: bool,
+    /// Whether the detail pane is expanded to full screen (list hidden).
+    pub detail_zoomed: bool,
+    /// Text found and highlighted in the full-screen detail body; `n`/`N`
+    /// scroll between its matches.
+    pub detail_query: String,
+    /// Whether the detail body's lines are numbered.
+    pub detail_line_numbers: bool,
+    /// The definition marked with `m`, to compare others with.
+    pub compare_mark: Option<Definition>,
+    /// The comparison `=` opened.
+    pub comparison: Option<Comparison>,
+    /// The line number typed after `:`.
+    pub go_to_line_input: String,
+    /// Glyphs prefixed to kinds so they are distinguishable without color.
+    pub glyphs: GlyphSet,
+    /// How much each list row shows; `D` switches between them.
+    pub list_density: ListDensity,
 
     /// Timestamp of last mouse click for double-click detection.
     last_click_time: Option<Instant>,
@@ -134,34 +481,76 @@ impl App {
         install_target: Option<PathBuf>,
     ) -> Self {
         let view_summaries = summaries.clone();
+        let display_names = DisplayNames::new(&view_summaries);
         let (groups, flat_items) = grouping::build_groups(&view_summaries);
         let cursor = grouping::first_item_index(&flat_items).unwrap_or(0);
 
         let mut app = Self {
             summaries,
             view_summaries,
+            display_names,
             source_label,
             groups,
             flat_items,
             cursor,
             list_scroll_offset: 0,
+            pending_count: None,
+            pending_g: false,
             selected_definition: None,
             detail_scroll: 0,
             pending_fetch: None,
             mode: Mode::Normal,
             search_query: String::new(),
             status_message: None,
+            tick_rate: crate::DEFAULT_TICK_RATE,
             loading: LoadingState::Idle,
             kind_filter: None,
             kind_filter_cursor: 0,
+            item_order: ItemOrder::default(),
+            category_filter: None,
+            category_filter_cursor: 0,
             source_filter: None,
             source_filter_cursor: 0,
+            branch_filter: None,
+            collections: Vec::new(),
+            collection_filter: None,
+            usage: Vec::new(),
+            most_used_filter: false,
+            notes: Vec::new(),
+            note_draft: String::new(),
+            ratings: Vec::new(),
+            hidden: HideRules::default(),
+            quick_install_target: install_target.clone(),
+            quick_install_confirm: true,
+            install_layout: InstallLayout::default(),
             install_target,
             file_explorer: None,
             pending_install_path: None,
+            installing_collection: false,
+            quick_installing: false,
+            install_form: None,
+            install_var_memory: HashMap::new(),
+            settings_preview: None,
+            tasks: TaskQueue::new(),
+            task_cursor: 0,
             sync_result: None,
+            sync_sources: Vec::new(),
             sync_result_scroll: 0,
+            changes: Vec::new(),
+            changes_scroll: 0,
+            skipped: Vec::new(),
+            skipped_scroll: 0,
             layout_geometry: LayoutGeometry::default(),
+            list_percent: DEFAULT_LIST_PERCENT,
+            dragging_divider: false,
+            detail_zoomed: false,
+            detail_query: String::new(),
+            detail_line_numbers: false,
+            compare_mark: None,
+            comparison: None,
+            go_to_line_input: String::new(),
+            glyphs: GlyphSet::default(),
+            list_density: ListDensity::Compact,
             last_click_time: None,
             last_click_pos: None,
         };
@@ -173,11 +562,13 @@ impl App {
 
     /// Handle a terminal event, returning a command for the event loop.
     pub fn handle_event(&mut self, event: Event) -> AppCommand {
-        match event {
+        let command = match event {
             Event::Key(key) => self.handle_key(key),
             Event::Mouse(mouse) => self.handle_mouse(mouse),
             _ => AppCommand::None,
-        }
+        };
+        self.ensure_cursor_visible();
+        command
     }
 
     /// Handle an async action (result from a background task).
@@ -195,9 +586,11 @@ impl App {
                     Ok(def) => {
                         self.selected_definition = Some(def);
                         self.detail_scroll = 0;
+                        self.refresh_settings_preview();
                     }
                     Err(msg) => {
                         self.selected_definition = None;
+                        self.settings_preview = None;
                         self.set_status(msg, true);
                     }
                 }
@@ -213,51 +606,191 @@ impl App {
                         self.set_status(format!("Reload failed: {msg}"), true);
                     }
                 }
+                // The reload is the last step of a sync.
+                let outcome = match &self.sync_result {
+                    Some(result) if !result.failures.is_empty() => Err(result.message.clone()),
+                    Some(result) => Ok(result.message.clone()),
+                    None => Ok("Synced".to_owned()),
+                };
+                self.finish_task(outcome)
+            }
+            Action::SyncProgress(SyncEvent::Started(label)) => {
+                self.set_source_state(&label, SourceSyncState::Syncing);
                 AppCommand::None
             }
-            Action::SyncCompleted(result) => {
+            Action::SyncProgress(SyncEvent::Finished(label, result)) => {
+                self.source_synced(&label, result);
+                AppCommand::None
+            }
+            Action::SyncFinished(freshness) => {
                 self.loading = LoadingState::Idle;
-                match result {
-                    Ok(sync_result) => {
-                        self.sync_result = Some(sync_result);
-                        // Stay in SyncProgress mode to show results
-                        return AppCommand::ReloadList;
-                    }
-                    Err(msg) => {
-                        self.mode = Mode::Normal;
-                        self.set_status(format!("Sync failed: {msg}"), true);
+                for (label, freshness) in freshness {
+                    if let Some(source) = self.sync_sources.iter_mut().find(|s| s.label == label) {
+                        source.freshness = freshness;
                     }
                 }
-                AppCommand::None
+                // Stay in SyncProgress mode to show results
+                AppCommand::ReloadList
             }
             Action::CopyCompleted(result) => {
                 match result {
-                    Ok(()) => self.set_status("Copied to clipboard".into(), false),
+                    Ok(path) => {
+                        self.set_status(format!("Copied to clipboard via {}", path.label()), false)
+                    }
                     Err(msg) => self.set_status(format!("Copy failed: {msg}"), true),
                 }
                 AppCommand::None
             }
             Action::InstallCompleted(result) => {
+                match &result {
+                    Ok(msg) => {
+                        self.set_status(msg.clone(), false);
+                        self.refresh_settings_preview();
+                    }
+                    Err(msg) => self.set_status(format!("Install failed: {msg}"), true),
+                }
+                self.finish_task(result)
+            }
+            Action::ExternalClosed(result) => {
                 match result {
                     Ok(msg) => self.set_status(msg, false),
-                    Err(msg) => self.set_status(format!("Install failed: {msg}"), true),
+                    Err(msg) => self.set_status(msg, true),
+                }
+                AppCommand::None
+            }
+            Action::HideSaved(result) => {
+                if let Err(msg) = result {
+                    self.set_status(format!("Could not save hide rule: {msg}"), true);
                 }
+                self.maybe_fetch_current()
+            }
+            Action::InstallNeedsVars {
+                id,
+                install_path,
+                vars,
+            } => {
+                self.open_install_form(id, install_path, vars);
+                self.finish_task(Ok("Asked for template values".to_owned()))
+            }
+        }
+    }
+
+    /// Run `command` now if no other task is running, or queue it behind
+    /// the ones that are.
+    fn queue_task(&mut self, label: String, command: AppCommand) -> AppCommand {
+        match self.tasks.push(label.clone(), command) {
+            Some(command) => command,
+            None => {
+                let ahead = self.tasks.pending();
+                self.set_status(format!("Queued {label} ({ahead} waiting; t for tasks)"), false);
                 AppCommand::None
             }
         }
     }
 
-    /// Tick the app forward (called on interval). Used for expiring status messages.
-    pub fn tick(&mut self) {
+    /// Record how the running task went and start the next one.
+    fn finish_task(&mut self, result: Result<String, String>) -> AppCommand {
+        self.tasks.finish(result).unwrap_or(AppCommand::None)
+    }
+
+    /// Tick the app forward (called on interval). Used for expiring status
+    /// messages. Returns whether the screen needs drawing again.
+    pub fn tick(&mut self) -> bool {
         if let Some(msg) = &mut self.status_message {
             if msg.ticks_remaining == 0 {
                 self.status_message = None;
-            } else {
-                msg.ticks_remaining -= 1;
+                return true;
             }
+            msg.ticks_remaining -= 1;
+        }
+        // The guidance for a rate-limited source counts down the wait.
+        self.mode == Mode::SyncProgress && self.selected_sync_failure().is_some()
+    }
+
+    /// Whether anything is waiting on ticks: a status message to expire or
+    /// a countdown to redraw. While nothing is, the loop needn't wake up.
+    pub fn needs_ticks(&self) -> bool {
+        self.status_message.is_some()
+            || (self.mode == Mode::SyncProgress && self.selected_sync_failure().is_some())
+    }
+
+    /// Replace the hide rules, refreshing the list and fetching whatever
+    /// the cursor lands on.
+    pub fn set_hidden(&mut self, hidden: HideRules) {
+        self.hidden = hidden;
+        self.recompute_view();
+        self.pending_fetch = None;
+        self.maybe_fetch_current();
+    }
+
+    /// Replace the list of skipped files, grouping them by source.
+    pub fn set_skipped(&mut self, mut skipped: Vec<SkippedFile>) {
+        skipped.sort_by(|a, b| (&a.source_label, &a.path).cmp(&(&b.source_label, &b.path)));
+        self.skipped = skipped;
+        self.skipped_scroll = 0;
+    }
+
+    /// Rows in the warnings overlay: a heading per source plus a path and a
+    /// reason line per skipped file.
+    pub fn skipped_rows(&self) -> usize {
+        let sources = self
+            .skipped
+            .iter()
+            .enumerate()
+            .filter(|(i, s)| *i == 0 || self.skipped[i - 1].source_label != s.source_label)
+            .count();
+        sources + 2 * self.skipped.len()
+    }
+
+    /// Replace the host's sources and their freshness.
+    pub fn set_sync_sources(&mut self, sources: Vec<(String, Freshness)>) {
+        self.sync_sources = sources
+            .into_iter()
+            .map(|(label, freshness)| SourceSync {
+                label,
+                freshness,
+                state: SourceSyncState::Idle,
+            })
+            .collect();
+    }
+
+    /// The freshness of the source with this label, if the host syncs it.
+    pub fn source_freshness(&self, label: &str) -> Option<Freshness> {
+        self.sync_sources
+            .iter()
+            .find(|s| s.label == label)
+            .map(|s| s.freshness)
+    }
+
+    fn set_source_state(&mut self, label: &str, state: SourceSyncState) {
+        if let Some(source) = self.sync_sources.iter_mut().find(|s| s.label == label) {
+            source.state = state;
         }
     }
 
+    /// Fold a finished source into the sync result. Its changes join the
+    /// "What's new" list and its skipped files replace the ones it had.
+    fn source_synced(&mut self, label: &str, result: Result<SourceReport, SyncError>) {
+        let state = match &result {
+            Ok(report) => {
+                self.changes.splice(0..0, report.changes.iter().cloned());
+                let mut skipped: Vec<_> = self
+                    .skipped
+                    .drain(..)
+                    .filter(|s| s.source_label != label)
+                    .collect();
+                skipped.extend(report.skipped_files.iter().cloned());
+                self.set_skipped(skipped);
+                SourceSyncState::Synced(report.synced)
+            }
+            Err(_) => SourceSyncState::Failed,
+        };
+        self.set_source_state(label, state);
+        self.sync_result
+            .get_or_insert_with(SyncResult::default)
+            .add(label, result);
+    }
+
     /// Reload the summaries list (e.g., after sync). Preserves search filter if active.
     pub fn reload(&mut self, summaries: Vec<DefinitionSummary>) {
         self.summaries = summaries;
@@ -289,9 +822,18 @@ impl App {
             Mode::Search => self.handle_search_key(key),
             Mode::KindFilter => self.handle_kind_filter_key(key),
             Mode::SourceFilter => self.handle_source_filter_key(key),
+            Mode::CategoryFilter => self.handle_category_filter_key(key),
             Mode::InstallPrompt => self.handle_install_prompt_key(key),
             Mode::InstallConfirm => self.handle_install_confirm_key(key),
+            Mode::InstallVars => self.handle_install_vars_key(key),
             Mode::SyncProgress => self.handle_sync_progress_key(key),
+            Mode::WhatsNew => self.handle_whats_new_key(key),
+            Mode::NoteEditor => self.handle_note_editor_key(key),
+            Mode::Warnings => self.handle_warnings_key(key),
+            Mode::Tasks => self.handle_tasks_key(key),
+            Mode::DetailSearch => self.handle_detail_search_key(key),
+            Mode::GoToLine => self.handle_go_to_line_key(key),
+            Mode::Compare => self.handle_compare_key(key),
         }
     }
 
@@ -300,21 +842,81 @@ impl App {
             Mode::Normal | Mode::Search => self.handle_normal_mouse(mouse),
             Mode::KindFilter => self.handle_kind_filter_mouse(mouse),
             Mode::SourceFilter => self.handle_source_filter_mouse(mouse),
+            Mode::CategoryFilter => self.handle_category_filter_mouse(mouse),
             Mode::SyncProgress => self.handle_sync_progress_mouse(mouse),
             Mode::InstallPrompt => self.handle_install_prompt_mouse(mouse),
             Mode::InstallConfirm => self.handle_install_confirm_mouse(mouse),
+            Mode::WhatsNew => self.handle_whats_new_mouse(mouse),
+            Mode::Warnings => self.handle_warnings_mouse(mouse),
+            Mode::Tasks => self.handle_tasks_mouse(mouse),
+            Mode::Compare => self.handle_compare_mouse(mouse),
+            Mode::InstallVars | Mode::NoteEditor | Mode::DetailSearch | Mode::GoToLine => {
+                AppCommand::None
+            }
         }
     }
 
     fn handle_normal_key(&mut self, key: KeyEvent) -> AppCommand {
+        if self.detail_zoomed {
+            return self.handle_zoomed_key(key);
+        }
+
+        // Alt+1..9 clears the matching filter chip.
+        if key.modifiers.contains(KeyModifiers::ALT)
+            && let KeyCode::Char(c @ '1'..='9') = key.code
+        {
+            let index = c as usize - '1' as usize;
+            return match self.filter_chips().get(index) {
+                Some(&(chip, _)) => self.clear_chip(chip),
+                None => AppCommand::None,
+            };
+        }
+
+        // Vim-style count prefix: digits accumulate until a motion consumes them.
+        // A leading `0` is not a count.
+        if let KeyCode::Char(c @ '0'..='9') = key.code
+            && (c != '0' || self.pending_count.is_some())
+        {
+            let digit = c as usize - '0' as usize;
+            let count = self.pending_count.unwrap_or(0) * 10 + digit;
+            self.pending_count = Some(count.min(MAX_COUNT));
+            return AppCommand::None;
+        }
+        let count = self.pending_count.take();
+        let pending_g = std::mem::take(&mut self.pending_g);
+
         match key.code {
             KeyCode::Char('q') => AppCommand::Quit,
             KeyCode::Char('j') | KeyCode::Down => {
-                self.move_cursor_down();
+                self.move_cursor_down_n(count.unwrap_or(1));
                 self.maybe_fetch_current()
             }
             KeyCode::Char('k') | KeyCode::Up => {
-                self.move_cursor_up();
+                self.move_cursor_up_n(count.unwrap_or(1));
+                self.maybe_fetch_current()
+            }
+            KeyCode::Char('g') if pending_g => {
+                self.jump_to_item(count);
+                self.maybe_fetch_current()
+            }
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+                self.pending_count = count;
+                AppCommand::None
+            }
+            KeyCode::Char('G') => {
+                match count {
+                    Some(_) => self.jump_to_item(count),
+                    None => self.jump_to_last_item(),
+                }
+                self.maybe_fetch_current()
+            }
+            KeyCode::Home => {
+                self.jump_to_item(None);
+                self.maybe_fetch_current()
+            }
+            KeyCode::End => {
+                self.jump_to_last_item();
                 self.maybe_fetch_current()
             }
             KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -325,6 +927,14 @@ impl App {
                 self.scroll_detail_up();
                 AppCommand::None
             }
+            KeyCode::PageDown if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.page_list(count.unwrap_or(1) as isize);
+                self.maybe_fetch_current()
+            }
+            KeyCode::PageUp if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.page_list(-(count.unwrap_or(1) as isize));
+                self.maybe_fetch_current()
+            }
             KeyCode::PageDown => {
                 self.scroll_detail_down();
                 AppCommand::None
@@ -348,6 +958,19 @@ impl App {
                 self.source_filter_cursor = 0;
                 AppCommand::None
             }
+            KeyCode::Char('C') => {
+                self.mode = Mode::CategoryFilter;
+                self.category_filter_cursor = 0;
+                AppCommand::None
+            }
+            KeyCode::Char('o') => {
+                self.item_order = self.item_order.next();
+                self.recompute_view();
+                self.set_status(format!("Sorted {}", self.item_order.label()), false);
+                self.maybe_fetch_current()
+            }
+            KeyCode::Char('A') => self.start_collection_install(),
+            KeyCode::Char('I') => self.start_quick_install(),
             KeyCode::Enter | KeyCode::Char('i') => {
                 // Enter on a header row sets kind filter to that group's kind.
                 // Enter on an item row starts the installer.
@@ -360,9 +983,13 @@ impl App {
                 }
             }
             KeyCode::Esc => {
-                if self.kind_filter.is_some() || self.source_filter.is_some() {
+                if self.has_filters() {
                     self.kind_filter = None;
                     self.source_filter = None;
+                    self.category_filter = None;
+                    self.branch_filter = None;
+                    self.collection_filter = None;
+                    self.most_used_filter = false;
                     self.recompute_view();
                     self.maybe_fetch_current()
                 } else {
@@ -375,11 +1002,27 @@ impl App {
                     self.mode = Mode::SyncProgress;
                     self.sync_result = None;
                     self.sync_result_scroll = 0;
-                    AppCommand::Sync
+                    for source in &mut self.sync_sources {
+                        source.state = SourceSyncState::Pending;
+                    }
+                    self.queue_task("Sync all sources".into(), AppCommand::Sync(SyncTarget::All))
                 } else {
                     AppCommand::None
                 }
             }
+            KeyCode::Char('w') => {
+                self.open_whats_new();
+                AppCommand::None
+            }
+            KeyCode::Char('W') => {
+                self.open_warnings();
+                AppCommand::None
+            }
+            KeyCode::Char('t') => {
+                self.mode = Mode::Tasks;
+                self.task_cursor = self.tasks.tasks().len().saturating_sub(1);
+                AppCommand::None
+            }
             KeyCode::Char('c') => {
                 if let Some(def) = &self.selected_definition {
                     AppCommand::CopyBody(def.body.clone())
@@ -387,55 +1030,449 @@ impl App {
                     AppCommand::None
                 }
             }
+            KeyCode::Char('Y') => match self.selected_summary() {
+                Some(summary) => AppCommand::CopyBody(summary.qualified_id().to_string()),
+                None => AppCommand::None,
+            },
+            KeyCode::Char('z') => self.zoom_detail(),
+            KeyCode::Char('m') => {
+                self.toggle_compare_mark();
+                AppCommand::None
+            }
+            KeyCode::Char('=') => {
+                self.open_comparison();
+                AppCommand::None
+            }
+            KeyCode::Char('D') => {
+                self.list_density = self.list_density.toggled();
+                self.ensure_cursor_visible();
+                let text = match self.list_density {
+                    ListDensity::Compact => "Showing names only",
+                    ListDensity::Comfortable => "Showing descriptions",
+                };
+                self.set_status(text.into(), false);
+                AppCommand::None
+            }
+            KeyCode::Char('#') => {
+                self.toggle_line_numbers();
+                AppCommand::None
+            }
+            KeyCode::Char(':') => {
+                self.start_go_to_line();
+                AppCommand::None
+            }
+            KeyCode::Char('?') => {
+                let command = self.zoom_detail();
+                self.start_detail_search();
+                command
+            }
+            KeyCode::Char('e') => self.open_external(ExternalTool::Editor),
+            KeyCode::Char('v') => self.open_external(ExternalTool::Pager),
+            KeyCode::Char('n') => {
+                self.open_note_editor();
+                AppCommand::None
+            }
+            KeyCode::Char('x') => self.hide_selected(),
+            KeyCode::Char('[') => {
+                self.set_list_percent(self.list_percent.saturating_sub(SPLIT_STEP));
+                AppCommand::None
+            }
+            KeyCode::Char(']') => {
+                self.set_list_percent(self.list_percent + SPLIT_STEP);
+                AppCommand::None
+            }
+            KeyCode::Char('}') => {
+                self.next_section(count.unwrap_or(1));
+                AppCommand::None
+            }
+            KeyCode::Char('{') => {
+                self.prev_section(count.unwrap_or(1));
+                AppCommand::None
+            }
             _ => AppCommand::None,
         }
     }
 
-    fn handle_search_key(&mut self, key: KeyEvent) -> AppCommand {
+    /// Show the detail pane full screen. Reading a definition this way counts
+    /// as a view in the usage stats.
+    fn zoom_detail(&mut self) -> AppCommand {
+        self.detail_zoomed = true;
+        match &self.selected_definition {
+            Some(def) => AppCommand::RecordUsage(def.id.clone(), UsageKind::View),
+            None => AppCommand::None,
+        }
+    }
+
+    /// Keys while the detail pane is full screen: the list is hidden, so the
+    /// arrow keys scroll the body instead of moving the cursor.
+    fn handle_zoomed_key(&mut self, key: KeyEvent) -> AppCommand {
         match key.code {
-            KeyCode::Esc => {
-                self.mode = Mode::Normal;
-                self.search_query.clear();
-                self.recompute_view();
+            KeyCode::Char('q') => AppCommand::Quit,
+            KeyCode::Char('z') | KeyCode::Esc | KeyCode::Enter => {
+                self.unzoom_detail();
                 AppCommand::None
             }
-            KeyCode::Enter => {
-                self.mode = Mode::Normal;
-                // Keep the current filter active.
-                self.maybe_fetch_current()
+            KeyCode::Char('/') => {
+                self.start_detail_search();
+                AppCommand::None
             }
-            KeyCode::Down => {
-                self.move_cursor_down();
-                self.maybe_fetch_current()
+            KeyCode::Char('#') => {
+                self.toggle_line_numbers();
+                AppCommand::None
             }
-            KeyCode::Up => {
-                self.move_cursor_up();
-                self.maybe_fetch_current()
+            KeyCode::Char(':') => {
+                self.start_go_to_line();
+                AppCommand::None
             }
-            KeyCode::Backspace => {
-                self.search_query.pop();
-                self.recompute_view();
-                self.maybe_fetch_current()
+            KeyCode::Char('n') => {
+                self.next_detail_match();
+                AppCommand::None
             }
-            KeyCode::Char(c) => {
-                self.search_query.push(c);
-                self.recompute_view();
-                self.maybe_fetch_current()
+            KeyCode::Char('N') => {
+                self.prev_detail_match();
+                AppCommand::None
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.scroll_detail_down_n(1);
+                AppCommand::None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll_detail_up_n(1);
+                AppCommand::None
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_detail_down();
+                AppCommand::None
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_detail_up();
+                AppCommand::None
+            }
+            KeyCode::PageDown | KeyCode::Char(' ') => {
+                self.scroll_detail_down();
+                AppCommand::None
+            }
+            KeyCode::PageUp => {
+                self.scroll_detail_up();
+                AppCommand::None
+            }
+            KeyCode::Char('}') => {
+                self.next_section(1);
+                AppCommand::None
+            }
+            KeyCode::Char('{') => {
+                self.prev_section(1);
+                AppCommand::None
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                self.jump_to_section(c as usize - '0' as usize);
+                AppCommand::None
+            }
+            KeyCode::Char('c') => {
+                if let Some(def) = &self.selected_definition {
+                    AppCommand::CopyBody(def.body.clone())
+                } else {
+                    AppCommand::None
+                }
             }
+            KeyCode::Char('e') => self.open_external(ExternalTool::Editor),
+            KeyCode::Char('v') => self.open_external(ExternalTool::Pager),
             _ => AppCommand::None,
         }
     }
 
-    fn move_cursor_down(&mut self) {
-        self.cursor = grouping::next_item_index(&self.flat_items, self.cursor);
+    /// Leave full-screen reading, dropping any search of the body.
+    fn unzoom_detail(&mut self) {
+        self.detail_zoomed = false;
+        self.detail_query.clear();
     }
 
-    fn move_cursor_up(&mut self) {
-        self.cursor = grouping::prev_item_index(&self.flat_items, self.cursor);
+    fn start_detail_search(&mut self) {
+        self.mode = Mode::DetailSearch;
+        self.detail_query.clear();
     }
 
-    fn move_cursor_down_n(&mut self, n: usize) {
-        for _ in 0..n {
+    /// Keys while typing a search of the detail body. Enter scrolls to the
+    /// first match from where the body is scrolled to; Esc drops the search.
+    fn handle_detail_search_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.detail_query.clear();
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                if self.detail_query.is_empty() {
+                    return AppCommand::None;
+                }
+                let matches = &self.layout_geometry.detail_matches;
+                let first = matches.iter().find(|&&row| row >= self.detail_scroll);
+                match first.or(matches.first()) {
+                    Some(&row) => self.detail_scroll = row,
+                    None => {
+                        let text = format!("No matches for \"{}\"", self.detail_query);
+                        self.set_status(text, true);
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                self.detail_query.pop();
+            }
+            KeyCode::Char(c) => self.detail_query.push(c),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    /// Mark the selected definition to compare others with, or clear the
+    /// mark if it's already the one marked.
+    fn toggle_compare_mark(&mut self) {
+        let Some(def) = &self.selected_definition else {
+            self.set_status("Wait for the definition to load before marking it".into(), true);
+            return;
+        };
+        let id = def.qualified_id();
+        if self.compare_mark.as_ref().is_some_and(|mark| mark.qualified_id() == id) {
+            self.compare_mark = None;
+            self.set_status("Cleared the mark".into(), false);
+        } else {
+            let text = format!("Marked {}; select another and press = to compare", def.name);
+            self.compare_mark = Some(def.clone());
+            self.set_status(text, false);
+        }
+    }
+
+    /// Diff the marked definition against the selected one.
+    fn open_comparison(&mut self) {
+        let Some(mark) = &self.compare_mark else {
+            self.set_status("Mark a definition with m to compare it with another".into(), true);
+            return;
+        };
+        match &self.selected_definition {
+            Some(def) if def.qualified_id() == mark.qualified_id() => {
+                self.set_status("Select a different definition to compare with".into(), true);
+            }
+            Some(def) => {
+                self.comparison = Some(Comparison::new(mark, def));
+                self.mode = Mode::Compare;
+                self.status_message = None;
+            }
+            None => self.set_status("Wait for the definition to load".into(), true),
+        }
+    }
+
+    /// Rows of the comparison overlay, falling back to 1 before it's laid out.
+    fn compare_page_size(&self) -> usize {
+        self.layout_geometry
+            .overlay
+            .map_or(1, |area| usize::from(area.height.saturating_sub(2)).max(1))
+    }
+
+    /// Scroll the comparison to `row`, kept within the diff.
+    fn scroll_comparison_to(&mut self, row: usize) {
+        if let Some(comparison) = &mut self.comparison {
+            comparison.scroll = row.min(comparison.rows().saturating_sub(1));
+        }
+    }
+
+    fn scroll_comparison(&mut self, rows: isize) {
+        if let Some(comparison) = &self.comparison {
+            let row = comparison.scroll.saturating_add_signed(rows);
+            self.scroll_comparison_to(row);
+        }
+    }
+
+    /// Scroll to the next run of changes, or with `forward` false the
+    /// previous one.
+    fn step_comparison(&mut self, forward: bool) {
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+        let starts = comparison.change_starts();
+        let scroll = comparison.scroll;
+        let target = if forward {
+            starts.into_iter().find(|&row| row > scroll)
+        } else {
+            starts.into_iter().rev().find(|&row| row < scroll)
+        };
+        match target {
+            Some(row) => self.scroll_comparison_to(row),
+            None => self.set_status("No more changes that way".into(), false),
+        }
+    }
+
+    fn handle_compare_key(&mut self, key: KeyEvent) -> AppCommand {
+        let page = self.compare_page_size() as isize;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('=') => {
+                self.mode = Mode::Normal;
+                self.comparison = None;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_comparison(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_comparison(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_comparison(page),
+            KeyCode::PageUp => self.scroll_comparison(-page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll_comparison_to(0),
+            KeyCode::Char('G') | KeyCode::End => self.scroll_comparison_to(usize::MAX),
+            KeyCode::Char('n') => self.step_comparison(true),
+            KeyCode::Char('N') => self.step_comparison(false),
+            KeyCode::Tab | KeyCode::Char('s') => {
+                if let Some(comparison) = &mut self.comparison {
+                    comparison.side_by_side = !comparison.side_by_side;
+                    comparison.scroll = 0;
+                }
+            }
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn handle_compare_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay
+                    && !overlay.contains(pos)
+                {
+                    self.mode = Mode::Normal;
+                    self.comparison = None;
+                }
+            }
+            MouseEventKind::ScrollDown => self.scroll_comparison(3),
+            MouseEventKind::ScrollUp => self.scroll_comparison(-3),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn toggle_line_numbers(&mut self) {
+        self.detail_line_numbers = !self.detail_line_numbers;
+    }
+
+    fn start_go_to_line(&mut self) {
+        self.mode = Mode::GoToLine;
+        self.go_to_line_input.clear();
+    }
+
+    /// Keys while typing a line number after `:`. Enter scrolls the detail
+    /// pane to that line of the body, or its last line when it's shorter.
+    fn handle_go_to_line_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Normal,
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                let lines = &self.layout_geometry.detail_lines;
+                let target = self.go_to_line_input.parse::<usize>().ok().filter(|&n| n > 0);
+                if let (Some(n), Some(&last)) = (target, lines.last()) {
+                    self.detail_scroll = lines.get(n - 1).copied().unwrap_or(last);
+                }
+            }
+            KeyCode::Backspace => {
+                self.go_to_line_input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() && self.go_to_line_input.len() < 6 => {
+                self.go_to_line_input.push(c);
+            }
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    /// Scroll to the next match in the detail body, wrapping to the first
+    /// after the last.
+    fn next_detail_match(&mut self) {
+        let matches = &self.layout_geometry.detail_matches;
+        let next = matches.iter().find(|&&row| row > self.detail_scroll);
+        if let Some(&row) = next.or(matches.first()) {
+            if next.is_none() {
+                self.set_status("Search wrapped to the top".into(), false);
+            }
+            self.detail_scroll = row;
+        }
+    }
+
+    /// Scroll to the previous match in the detail body, wrapping to the last
+    /// before the first.
+    fn prev_detail_match(&mut self) {
+        let matches = &self.layout_geometry.detail_matches;
+        let prev = matches.iter().rev().find(|&&row| row < self.detail_scroll);
+        if let Some(&row) = prev.or(matches.last()) {
+            if prev.is_none() {
+                self.set_status("Search wrapped to the bottom".into(), false);
+            }
+            self.detail_scroll = row;
+        }
+    }
+
+    /// Hand the loaded definition to an editor or pager.
+    fn open_external(&self, tool: ExternalTool) -> AppCommand {
+        match &self.selected_definition {
+            Some(def) => AppCommand::OpenExternal(def.qualified_id(), tool),
+            None => AppCommand::None,
+        }
+    }
+
+    /// Set the list pane width, clamped so neither pane collapses.
+    pub fn set_list_percent(&mut self, percent: u16) {
+        self.list_percent = percent.clamp(MIN_LIST_PERCENT, MAX_LIST_PERCENT);
+    }
+
+    /// Move the divider so the list pane ends at the given terminal column.
+    fn drag_divider_to(&mut self, column: u16) {
+        let content = self.layout_geometry.content;
+        if content.width == 0 {
+            return;
+        }
+        let list_width = u32::from(column.saturating_sub(content.x)) + 1;
+        let percent = (list_width * 100 / u32::from(content.width)) as u16;
+        self.set_list_percent(percent);
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.search_query.clear();
+                self.recompute_view();
+                AppCommand::None
+            }
+            KeyCode::Enter => {
+                self.mode = Mode::Normal;
+                // Keep the current filter active.
+                self.maybe_fetch_current()
+            }
+            KeyCode::Down => {
+                self.move_cursor_down();
+                self.maybe_fetch_current()
+            }
+            KeyCode::Up => {
+                self.move_cursor_up();
+                self.maybe_fetch_current()
+            }
+            KeyCode::Backspace => {
+                self.search_query.pop();
+                self.recompute_view();
+                self.maybe_fetch_current()
+            }
+            KeyCode::Char(c) => {
+                self.search_query.push(c);
+                self.recompute_view();
+                self.maybe_fetch_current()
+            }
+            _ => AppCommand::None,
+        }
+    }
+
+    fn move_cursor_down(&mut self) {
+        self.cursor = grouping::next_item_index(&self.flat_items, self.cursor);
+    }
+
+    fn move_cursor_up(&mut self) {
+        self.cursor = grouping::prev_item_index(&self.flat_items, self.cursor);
+    }
+
+    fn move_cursor_down_n(&mut self, n: usize) {
+        for _ in 0..n {
             self.cursor = grouping::next_item_index(&self.flat_items, self.cursor);
         }
     }
@@ -446,6 +1483,66 @@ impl App {
         }
     }
 
+    /// Move to the `n`th item (1-based), or the first item when `n` is `None`.
+    /// Counts past the end land on the last item.
+    fn jump_to_item(&mut self, n: Option<usize>) {
+        let n = n.unwrap_or(1).max(1);
+        let target = self
+            .flat_items
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, ListRow::Item { .. }))
+            .map(|(i, _)| i)
+            .take(n)
+            .last();
+        if let Some(index) = target {
+            self.cursor = index;
+        }
+    }
+
+    fn jump_to_last_item(&mut self) {
+        if let Some(index) = grouping::last_item_index(&self.flat_items) {
+            self.cursor = index;
+        }
+    }
+
+    /// Move the cursor by whole pages of the list viewport; negative pages move up.
+    fn page_list(&mut self, pages: isize) {
+        let page = self.list_page_size() as isize;
+        let target = (self.cursor as isize + page * pages).max(0) as usize;
+        if let Some(index) = grouping::item_index_near(&self.flat_items, target) {
+            self.cursor = index;
+        }
+    }
+
+    /// Rows visible in the list pane, falling back to 1 before the first layout.
+    fn list_page_size(&self) -> usize {
+        self.list_rows_visible(self.layout_geometry.list_inner.height).max(1)
+    }
+
+    /// Terminal lines each list row takes: two when descriptions are shown
+    /// under the names, with headers given a blank line to match.
+    pub(crate) fn list_row_height(&self) -> usize {
+        match self.list_density {
+            ListDensity::Compact => 1,
+            ListDensity::Comfortable => 2,
+        }
+    }
+
+    /// List rows that fit in `height` terminal lines.
+    pub(crate) fn list_rows_visible(&self, height: u16) -> usize {
+        usize::from(height) / self.list_row_height()
+    }
+
+    /// Scroll the list viewport just enough to keep the cursor on screen.
+    fn ensure_cursor_visible(&mut self) {
+        let height = self.list_rows_visible(self.layout_geometry.list_inner.height);
+        if height > 0 {
+            self.list_scroll_offset =
+                compute_scroll_offset(self.cursor, self.list_scroll_offset, height);
+        }
+    }
+
     fn scroll_detail_down(&mut self) {
         self.detail_scroll = self.detail_scroll.saturating_add(5);
     }
@@ -462,6 +1559,35 @@ impl App {
         self.detail_scroll = self.detail_scroll.saturating_sub(n);
     }
 
+    /// Scroll the detail body to the `n`th section after the one it's at.
+    fn next_section(&mut self, n: usize) {
+        let sections = &self.layout_geometry.detail_sections;
+        if let Some(&row) = sections
+            .iter()
+            .filter(|&&row| row > self.detail_scroll)
+            .take(n)
+            .last()
+        {
+            self.detail_scroll = row;
+        }
+    }
+
+    /// Scroll the detail body back `n` sections, to the top before the
+    /// first one.
+    fn prev_section(&mut self, n: usize) {
+        let sections = &self.layout_geometry.detail_sections;
+        let earlier = sections.iter().filter(|&&row| row < self.detail_scroll);
+        self.detail_scroll = earlier.rev().nth(n.saturating_sub(1)).copied().unwrap_or(0);
+    }
+
+    /// Scroll the detail body to its `n`th section (1-based), as numbered in
+    /// its contents.
+    fn jump_to_section(&mut self, n: usize) {
+        if let Some(&row) = self.layout_geometry.detail_sections.get(n.wrapping_sub(1)) {
+            self.detail_scroll = row;
+        }
+    }
+
     /// Check if a click at (col, row) is a double-click based on timing and position.
     /// Updates the last click state and returns true if this is a double-click.
     fn is_double_click(&mut self, col: u16, row: u16) -> bool {
@@ -492,7 +1618,24 @@ impl App {
         let pos = Position::new(mouse.column, mouse.row);
 
         match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if self.layout_geometry.divider.contains(pos) => {
+                self.dragging_divider = true;
+                AppCommand::None
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_divider => {
+                self.drag_divider_to(mouse.column);
+                AppCommand::None
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.dragging_divider = false;
+                AppCommand::None
+            }
             MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(&(_, chip)) =
+                    self.layout_geometry.chips.iter().find(|(area, _)| area.contains(pos))
+                {
+                    return self.clear_chip(chip);
+                }
                 let is_double = self.is_double_click(mouse.column, mouse.row);
 
                 if self.layout_geometry.list_inner.contains(pos) {
@@ -506,6 +1649,14 @@ impl App {
                     } else {
                         self.handle_list_click(mouse.row)
                     }
+                } else if is_double && self.layout_geometry.detail_inner.contains(pos) {
+                    // Double-click on the detail pane toggles full-screen reading.
+                    if self.detail_zoomed {
+                        self.unzoom_detail();
+                        AppCommand::None
+                    } else {
+                        self.zoom_detail()
+                    }
                 } else {
                     AppCommand::None
                 }
@@ -539,7 +1690,7 @@ impl App {
     fn handle_list_click(&mut self, row: u16) -> AppCommand {
         let inner = self.layout_geometry.list_inner;
         let relative_row = (row.saturating_sub(inner.y)) as usize;
-        let list_index = self.list_scroll_offset + relative_row;
+        let list_index = self.list_scroll_offset + relative_row / self.list_row_height();
 
         if list_index >= self.flat_items.len() {
             return AppCommand::None;
@@ -607,12 +1758,15 @@ impl App {
                     self.install_target = Some(target.clone());
 
                     // Compute the install path for preview/confirmation
-                    if let Some(def) = &self.selected_definition {
-                        let install_path = agent_defs::install::install_path(&target, def);
+                    if !self.installing_collection
+                        && let Some(def) = &self.selected_definition
+                    {
+                        let install_path = self.install_layout.path(&target, def);
                         self.pending_install_path = Some(install_path);
+                        self.refresh_settings_preview();
                     }
 
-                    if has_shift {
+                    if has_shift && !self.install_runs_commands() {
                         // Install immediately without confirmation
                         self.file_explorer = None;
                         self.pending_install_path = None;
@@ -627,6 +1781,7 @@ impl App {
             }
             KeyCode::Esc => {
                 self.file_explorer = None;
+                self.installing_collection = false;
                 self.mode = Mode::Normal;
                 AppCommand::None
             }
@@ -643,7 +1798,11 @@ impl App {
 
     fn handle_install_confirm_key(&mut self, key: KeyEvent) -> AppCommand {
         match key.code {
-            KeyCode::Enter | KeyCode::Char('y') => {
+            KeyCode::Enter | KeyCode::Char('y') if self.install_runs_commands() => {
+                self.set_status("Hooks run shell commands: press Y to install".into(), true);
+                AppCommand::None
+            }
+            KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
                 // Confirm installation
                 self.file_explorer = None;
                 self.pending_install_path = None;
@@ -651,20 +1810,47 @@ impl App {
                 self.emit_install()
             }
             KeyCode::Esc | KeyCode::Char('n') => {
-                // Cancel - go back to explorer
-                self.pending_install_path = None;
-                self.install_target = None;
-                self.mode = Mode::InstallPrompt;
+                self.cancel_install_confirm();
                 AppCommand::None
             }
             _ => AppCommand::None,
         }
     }
 
+    fn handle_install_vars_key(&mut self, key: KeyEvent) -> AppCommand {
+        let Some(form) = &mut self.install_form else {
+            self.mode = Mode::Normal;
+            return AppCommand::None;
+        };
+        let last = form.fields.len().saturating_sub(1);
+
+        match key.code {
+            KeyCode::Esc => {
+                self.install_form = None;
+                self.mode = Mode::Normal;
+                self.set_status("Install cancelled".into(), false);
+            }
+            KeyCode::Tab | KeyCode::Down => form.cursor = (form.cursor + 1).min(last),
+            KeyCode::BackTab | KeyCode::Up => form.cursor = form.cursor.saturating_sub(1),
+            KeyCode::Enter if form.cursor < last => form.cursor += 1,
+            KeyCode::Enter => return self.submit_install_form(),
+            KeyCode::Backspace => {
+                if let Some((_, value)) = form.fields.get_mut(form.cursor) {
+                    value.pop();
+                }
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some((_, value)) = form.fields.get_mut(form.cursor) {
+                    value.push(c);
+                }
+            }
+            _ => {}
+        }
+        AppCommand::None
+    }
+
     fn handle_source_filter_key(&mut self, key: KeyEvent) -> AppCommand {
-        let sources = self.available_sources();
-        // Option count: "All" + each source
-        let option_count = 1 + sources.len();
+        let option_count = self.source_filter_options().len();
 
         match key.code {
             KeyCode::Char('j') | KeyCode::Down => {
@@ -677,17 +1863,55 @@ impl App {
                 self.source_filter_cursor = self.source_filter_cursor.saturating_sub(1);
                 AppCommand::None
             }
-            KeyCode::Enter => {
-                if self.source_filter_cursor == 0 {
-                    // "All" selected
-                    self.source_filter = None;
-                } else if let Some(source) = sources.get(self.source_filter_cursor - 1) {
-                    self.source_filter = Some(source.clone());
-                }
+            KeyCode::Enter => self.apply_source_option(self.source_filter_cursor),
+            KeyCode::Esc => {
                 self.mode = Mode::Normal;
-                self.recompute_view();
-                self.maybe_fetch_current()
+                AppCommand::None
+            }
+            _ => AppCommand::None,
+        }
+    }
+
+    /// Apply the source filter overlay entry at `index` and close the overlay.
+    /// Each entry replaces whichever source, branch, or collection filter
+    /// was set before.
+    fn apply_source_option(&mut self, index: usize) -> AppCommand {
+        let Some(option) = self.source_filter_options().into_iter().nth(index) else {
+            self.mode = Mode::Normal;
+            return AppCommand::None;
+        };
+        self.source_filter = None;
+        self.branch_filter = None;
+        self.collection_filter = None;
+        self.most_used_filter = false;
+        match option {
+            SourceOption::All => {}
+            SourceOption::Source(source) => self.source_filter = Some(source),
+            SourceOption::Branch(branch) => self.branch_filter = Some(branch),
+            SourceOption::Collection(name) => self.collection_filter = Some(name),
+            SourceOption::MostUsed => self.most_used_filter = true,
+        }
+        self.mode = Mode::Normal;
+        self.recompute_view();
+        self.maybe_fetch_current()
+    }
+
+    fn handle_category_filter_key(&mut self, key: KeyEvent) -> AppCommand {
+        // Option count: "All" + each category
+        let option_count = 1 + self.available_categories().len();
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if self.category_filter_cursor + 1 < option_count {
+                    self.category_filter_cursor += 1;
+                }
+                AppCommand::None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.category_filter_cursor = self.category_filter_cursor.saturating_sub(1);
+                AppCommand::None
             }
+            KeyCode::Enter => self.apply_category_option(self.category_filter_cursor),
             KeyCode::Esc => {
                 self.mode = Mode::Normal;
                 AppCommand::None
@@ -696,6 +1920,26 @@ impl App {
         }
     }
 
+    /// Apply the category filter overlay entry at `index` ("All" first)
+    /// and close the overlay.
+    fn apply_category_option(&mut self, index: usize) -> AppCommand {
+        let categories = self.available_categories();
+        if index == 0 {
+            self.category_filter = None;
+        } else if let Some((category, _)) = categories.into_iter().nth(index - 1) {
+            self.category_filter = Some(category);
+        }
+        self.mode = Mode::Normal;
+        self.recompute_view();
+        self.maybe_fetch_current()
+    }
+
+    /// First category filter entry the overlay shows, so the cursor stays
+    /// in its window.
+    pub fn category_filter_scroll(&self) -> usize {
+        (self.category_filter_cursor + 1).saturating_sub(CATEGORY_FILTER_ROWS)
+    }
+
     fn handle_sync_progress_key(&mut self, key: KeyEvent) -> AppCommand {
         match key.code {
             KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => {
@@ -706,23 +1950,314 @@ impl App {
                 AppCommand::None
             }
             KeyCode::Char('j') | KeyCode::Down => {
-                self.sync_result_scroll = self.sync_result_scroll.saturating_add(1);
+                self.select_sync_issue(true);
                 AppCommand::None
             }
             KeyCode::Char('k') | KeyCode::Up => {
-                self.sync_result_scroll = self.sync_result_scroll.saturating_sub(1);
+                self.select_sync_issue(false);
+                AppCommand::None
+            }
+            KeyCode::Char('r') if self.loading != LoadingState::Syncing => {
+                self.retry_selected_source()
+            }
+            KeyCode::Char('w') if self.loading != LoadingState::Syncing => {
+                self.open_whats_new();
+                AppCommand::None
+            }
+            KeyCode::Char('W') if self.loading != LoadingState::Syncing => {
+                self.open_warnings();
                 AppCommand::None
             }
             _ => AppCommand::None,
         }
     }
 
-    fn handle_kind_filter_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
-        let pos = Position::new(mouse.column, mouse.row);
+    fn select_sync_issue(&mut self, down: bool) {
+        let count = self.sync_result.as_ref().map_or(0, SyncResult::issue_count);
+        self.sync_result_scroll = if down {
+            (self.sync_result_scroll + 1).min(count.saturating_sub(1))
+        } else {
+            self.sync_result_scroll.saturating_sub(1)
+        };
+    }
 
-        match mouse.kind {
-            MouseEventKind::Down(MouseButton::Left) => {
-                if let Some(overlay) = self.layout_geometry.overlay {
+    /// The failed source on the selected line of the sync result, if that
+    /// line is a failure rather than a warning.
+    pub fn selected_sync_failure(&self) -> Option<&crate::SyncFailure> {
+        self.sync_result
+            .as_ref()
+            .and_then(|result| result.failures.get(self.sync_result_scroll))
+    }
+
+    /// Sync the selected failed source again, leaving the others alone.
+    /// The rest of the result stays, so the summary covers the whole sync.
+    fn retry_selected_source(&mut self) -> AppCommand {
+        let Some(failure) = self.selected_sync_failure() else {
+            return AppCommand::None;
+        };
+        let label = failure.source_label.clone();
+        if let Some(result) = &mut self.sync_result {
+            result.failures.retain(|f| f.source_label != label);
+        }
+        self.set_source_state(&label, SourceSyncState::Pending);
+        self.loading = LoadingState::Syncing;
+        self.sync_result_scroll = 0;
+        let task = format!("Sync {label}");
+        self.queue_task(task, AppCommand::Sync(SyncTarget::Source(label)))
+    }
+
+    /// Hide the definition under the cursor, leaving the cursor where it was
+    /// so the next one moves up into its place.
+    fn hide_selected(&mut self) -> AppCommand {
+        let Some(summary) = self.selected_summary() else {
+            return AppCommand::None;
+        };
+        let rule = HideRule::Id(summary.id.clone());
+        let message = format!("Hid {}; `unhide {}` brings it back", summary.name, summary.id);
+        let cursor = self.cursor;
+        self.hidden.add(rule.clone());
+        self.recompute_view();
+        if let Some(index) = grouping::item_index_near(&self.flat_items, cursor) {
+            self.cursor = index;
+        }
+        self.set_status(message, false);
+        AppCommand::Hide(rule)
+    }
+
+    /// Start editing the note on the selected definition, beginning from
+    /// the note it already has.
+    fn open_note_editor(&mut self) {
+        if self.selected_definition.is_none() {
+            return;
+        }
+        self.note_draft = self
+            .selected_note()
+            .map(|note| note.text.clone())
+            .unwrap_or_default();
+        self.mode = Mode::NoteEditor;
+    }
+
+    fn handle_note_editor_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Esc => {
+                self.note_draft.clear();
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Enter => return self.save_note(),
+            KeyCode::Backspace => {
+                self.note_draft.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.note_draft.clear();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.note_draft.push(c);
+            }
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    /// Keep the draft as the selected definition's note. An empty draft
+    /// removes the note.
+    fn save_note(&mut self) -> AppCommand {
+        self.mode = Mode::Normal;
+        let text = std::mem::take(&mut self.note_draft).trim().to_owned();
+        let Some(def) = &self.selected_definition else {
+            return AppCommand::None;
+        };
+        let note = DefinitionNote {
+            id: def.id.clone(),
+            source_label: def.source_label.clone(),
+            text,
+            updated_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        self.notes
+            .retain(|n| n.id != note.id || n.source_label != note.source_label);
+        if note.text.is_empty() {
+            self.set_status("Note removed".into(), false);
+        } else {
+            self.notes.push(note.clone());
+            self.set_status("Note saved".into(), false);
+        }
+        AppCommand::SaveNote(note)
+    }
+
+    /// The note on the definition shown in the detail pane, if any.
+    pub fn selected_note(&self) -> Option<&DefinitionNote> {
+        let def = self.selected_definition.as_ref()?;
+        self.notes
+            .iter()
+            .find(|n| n.id == def.id && n.source_label == def.source_label)
+    }
+
+    /// The community rating of `summary`, if the index has one.
+    pub fn rating(&self, summary: &DefinitionSummary) -> Option<&Rating> {
+        self.ratings.iter().find(|r| r.matches(summary))
+    }
+
+    /// The community rating of the definition shown in the detail pane.
+    pub fn selected_rating(&self) -> Option<&Rating> {
+        let def = self.selected_definition.as_ref()?;
+        self.ratings
+            .iter()
+            .find(|r| r.id == def.id && r.source_label == def.source_label)
+    }
+
+    fn open_whats_new(&mut self) {
+        self.mode = Mode::WhatsNew;
+        self.changes_scroll = 0;
+    }
+
+    fn scroll_changes(&mut self, down: bool) {
+        self.changes_scroll = if down {
+            (self.changes_scroll + 1).min(self.changes.len().saturating_sub(1))
+        } else {
+            self.changes_scroll.saturating_sub(1)
+        };
+    }
+
+    fn handle_whats_new_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_changes(true),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_changes(false),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn open_warnings(&mut self) {
+        self.mode = Mode::Warnings;
+        self.skipped_scroll = 0;
+    }
+
+    fn scroll_skipped(&mut self, down: bool) {
+        self.skipped_scroll = if down {
+            (self.skipped_scroll + 1).min(self.skipped_rows().saturating_sub(1))
+        } else {
+            self.skipped_scroll.saturating_sub(1)
+        };
+    }
+
+    fn handle_warnings_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W') => {
+                self.mode = Mode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_skipped(true),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_skipped(false),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn handle_warnings_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay
+                    && !overlay.contains(pos)
+                {
+                    self.mode = Mode::Normal;
+                }
+            }
+            MouseEventKind::ScrollDown => self.scroll_skipped(true),
+            MouseEventKind::ScrollUp => self.scroll_skipped(false),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn move_task_cursor(&mut self, down: bool) {
+        let last = self.tasks.tasks().len().saturating_sub(1);
+        self.task_cursor = if down {
+            (self.task_cursor + 1).min(last)
+        } else {
+            self.task_cursor.saturating_sub(1)
+        };
+    }
+
+    fn handle_tasks_key(&mut self, key: KeyEvent) -> AppCommand {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('t') => self.mode = Mode::Normal,
+            KeyCode::Char('j') | KeyCode::Down => self.move_task_cursor(true),
+            KeyCode::Char('k') | KeyCode::Up => self.move_task_cursor(false),
+            KeyCode::Char('x') | KeyCode::Delete => self.cancel_selected_task(),
+            KeyCode::Char('c') => {
+                self.tasks.clear_finished();
+                self.task_cursor = 0;
+            }
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    /// Cancel the selected task if it is still waiting to start.
+    fn cancel_selected_task(&mut self) {
+        let Some(task) = self.tasks.tasks().get(self.task_cursor) else {
+            return;
+        };
+        let (id, label) = (task.id, task.label.clone());
+        let Some(command) = self.tasks.cancel(id) else {
+            self.set_status("Only tasks that haven't started can be cancelled".into(), true);
+            return;
+        };
+        if let AppCommand::Sync(_) = command {
+            self.loading = LoadingState::Idle;
+            for source in &mut self.sync_sources {
+                if source.state == SourceSyncState::Pending {
+                    source.state = SourceSyncState::Idle;
+                }
+            }
+        }
+        self.set_status(format!("Cancelled {label}"), false);
+    }
+
+    fn handle_tasks_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay
+                    && !overlay.contains(pos)
+                {
+                    self.mode = Mode::Normal;
+                }
+            }
+            MouseEventKind::ScrollDown => self.move_task_cursor(true),
+            MouseEventKind::ScrollUp => self.move_task_cursor(false),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn handle_whats_new_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay
+                    && !overlay.contains(pos)
+                {
+                    self.mode = Mode::Normal;
+                }
+            }
+            MouseEventKind::ScrollDown => self.scroll_changes(true),
+            MouseEventKind::ScrollUp => self.scroll_changes(false),
+            _ => {}
+        }
+        AppCommand::None
+    }
+
+    fn handle_kind_filter_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay {
                     if overlay.contains(pos) {
                         // Map click to option index (accounting for border).
                         let relative_row = mouse.row.saturating_sub(overlay.y + 1) as usize;
@@ -772,21 +2307,9 @@ impl App {
                     if overlay.contains(pos) {
                         // Map click to option index (accounting for border).
                         let relative_row = mouse.row.saturating_sub(overlay.y + 1) as usize;
-                        let sources = self.available_sources();
-                        let option_count = 1 + sources.len();
-
-                        if relative_row < option_count {
+                        if relative_row < self.source_filter_options().len() {
                             self.source_filter_cursor = relative_row;
-                            // Apply selection (same as Enter key).
-                            if self.source_filter_cursor == 0 {
-                                self.source_filter = None;
-                            } else if let Some(source) = sources.get(self.source_filter_cursor - 1)
-                            {
-                                self.source_filter = Some(source.clone());
-                            }
-                            self.mode = Mode::Normal;
-                            self.recompute_view();
-                            return self.maybe_fetch_current();
+                            return self.apply_source_option(relative_row);
                         }
                     } else {
                         // Click outside: close overlay.
@@ -796,7 +2319,7 @@ impl App {
                 AppCommand::None
             }
             MouseEventKind::ScrollDown => {
-                let option_count = 1 + self.available_sources().len();
+                let option_count = self.source_filter_options().len();
                 if self.source_filter_cursor + 1 < option_count {
                     self.source_filter_cursor += 1;
                 }
@@ -810,6 +2333,41 @@ impl App {
         }
     }
 
+    fn handle_category_filter_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
+        let pos = Position::new(mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(overlay) = self.layout_geometry.overlay {
+                    if overlay.contains(pos) {
+                        // Map click to option index (accounting for border and scroll).
+                        let relative_row = mouse.row.saturating_sub(overlay.y + 1) as usize;
+                        let index = self.category_filter_scroll() + relative_row;
+                        if index <= self.available_categories().len() {
+                            self.category_filter_cursor = index;
+                            return self.apply_category_option(index);
+                        }
+                    } else {
+                        // Click outside: close overlay.
+                        self.mode = Mode::Normal;
+                    }
+                }
+                AppCommand::None
+            }
+            MouseEventKind::ScrollDown => {
+                if self.category_filter_cursor < self.available_categories().len() {
+                    self.category_filter_cursor += 1;
+                }
+                AppCommand::None
+            }
+            MouseEventKind::ScrollUp => {
+                self.category_filter_cursor = self.category_filter_cursor.saturating_sub(1);
+                AppCommand::None
+            }
+            _ => AppCommand::None,
+        }
+    }
+
     fn handle_sync_progress_mouse(&mut self, mouse: MouseEvent) -> AppCommand {
         let pos = Position::new(mouse.column, mouse.row);
 
@@ -827,11 +2385,11 @@ impl App {
                 AppCommand::None
             }
             MouseEventKind::ScrollDown => {
-                self.sync_result_scroll = self.sync_result_scroll.saturating_add(1);
+                self.select_sync_issue(true);
                 AppCommand::None
             }
             MouseEventKind::ScrollUp => {
-                self.sync_result_scroll = self.sync_result_scroll.saturating_sub(1);
+                self.select_sync_issue(false);
                 AppCommand::None
             }
             _ => AppCommand::None,
@@ -847,9 +2405,7 @@ impl App {
                     && !overlay.contains(pos)
                 {
                     // Click outside: cancel and go back to explorer.
-                    self.pending_install_path = None;
-                    self.install_target = None;
-                    self.mode = Mode::InstallPrompt;
+                    self.cancel_install_confirm();
                 }
                 AppCommand::None
             }
@@ -953,6 +2509,65 @@ impl App {
         if self.selected_definition.is_none() {
             return AppCommand::None;
         }
+        self.installing_collection = false;
+        self.open_install_explorer()
+    }
+
+    /// Install the selected definition straight to the configured target,
+    /// skipping the explorer, and the confirmation too if so configured.
+    fn start_quick_install(&mut self) -> AppCommand {
+        if self.selected_definition.is_none() || self.header_kind_at_cursor().is_some() {
+            return AppCommand::None;
+        }
+        let Some(target) = self.quick_install_target.clone() else {
+            self.set_status(
+                "No install target configured: set install_target or pass --target".into(),
+                true,
+            );
+            return AppCommand::None;
+        };
+        self.installing_collection = false;
+        self.quick_installing = true;
+        self.file_explorer = None;
+        if let Some(def) = &self.selected_definition {
+            self.pending_install_path = Some(self.install_layout.path(&target, def));
+        }
+        self.install_target = Some(target);
+        self.refresh_settings_preview();
+
+        if self.quick_install_confirm || self.install_runs_commands() {
+            self.mode = Mode::InstallConfirm;
+            return AppCommand::None;
+        }
+        self.pending_install_path = None;
+        self.emit_install()
+    }
+
+    /// Back out of the install confirmation to the explorer, or to the list
+    /// for a quick install, which never opened one.
+    fn cancel_install_confirm(&mut self) {
+        self.pending_install_path = None;
+        self.install_target = None;
+        self.refresh_settings_preview();
+        self.mode = if std::mem::take(&mut self.quick_installing) {
+            Mode::Normal
+        } else {
+            Mode::InstallPrompt
+        };
+    }
+
+    /// Install everything in the active collection, choosing the target
+    /// directory the same way as a single install.
+    fn start_collection_install(&mut self) -> AppCommand {
+        if self.active_collection().is_none() {
+            self.set_status("Choose a collection with p first".into(), true);
+            return AppCommand::None;
+        }
+        self.installing_collection = true;
+        self.open_install_explorer()
+    }
+
+    fn open_install_explorer(&mut self) -> AppCommand {
 
         // Always show the file explorer for directory selection
         let theme = Theme::default().add_default_title();
@@ -972,31 +2587,122 @@ impl App {
         AppCommand::None
     }
 
+    /// Whether the install being confirmed includes a hook, which runs
+    /// shell commands and so takes a deliberate `Y` rather than Enter.
+    pub fn install_runs_commands(&self) -> bool {
+        if self.installing_collection {
+            return self.active_collection().is_some_and(|collection| {
+                let (found, _missing) = collection.resolve(&self.summaries);
+                found.iter().any(|summary| summary.kind == DefinitionKind::Hook)
+            });
+        }
+        self.selected_definition
+            .as_ref()
+            .is_some_and(|def| def.kind == DefinitionKind::Hook)
+    }
+
+    /// Work out what installing the selected definition would change, in
+    /// the install target or else the working directory.
+    fn refresh_settings_preview(&mut self) {
+        let target = self
+            .install_target
+            .clone()
+            .or_else(|| std::env::current_dir().ok());
+        self.settings_preview = match (&target, &self.selected_definition) {
+            (Some(target), Some(def)) => SettingsPreview::new(target, def),
+            _ => None,
+        };
+    }
+
     fn emit_install(&mut self) -> AppCommand {
+        self.quick_installing = false;
+        if std::mem::take(&mut self.installing_collection) {
+            return self.emit_collection_install();
+        }
         let Some(def) = &self.selected_definition else {
             return AppCommand::None;
         };
         let Some(target) = &self.install_target else {
             return AppCommand::None;
         };
-        let install_path = agent_defs::install::install_path(target, def);
-        AppCommand::Install {
-            raw: def.raw.clone(),
+        let install_path = self.install_layout.path(target, def);
+        let command = AppCommand::Install {
+            id: def.qualified_id(),
+            install_path,
+            vars: None,
+        };
+        self.queue_task(format!("Install {}", def.name), command)
+    }
+
+    fn emit_collection_install(&mut self) -> AppCommand {
+        let (Some(collection), Some(target)) = (self.active_collection(), &self.install_target)
+        else {
+            return AppCommand::None;
+        };
+        let (found, _missing) = collection.resolve(&self.summaries);
+        let command = AppCommand::InstallMany {
+            label: collection.name.clone(),
+            ids: found.into_iter().map(|summary| summary.id.clone()).collect(),
+            total: collection.items.len(),
+            target: target.clone(),
+        };
+        self.queue_task(format!("Install collection {}", collection.name), command)
+    }
+
+    /// Ask for template variable values, pre-filling each from what was
+    /// typed for the same name before, or else its declared default.
+    fn open_install_form(&mut self, id: DefinitionId, install_path: PathBuf, vars: Vec<TemplateVar>) {
+        let fields = vars
+            .into_iter()
+            .map(|var| {
+                let value = self
+                    .install_var_memory
+                    .get(&var.name)
+                    .cloned()
+                    .or(var.default)
+                    .unwrap_or_default();
+                (var.name, value)
+            })
+            .collect();
+        self.install_form = Some(InstallForm {
+            id,
             install_path,
+            fields,
+            cursor: 0,
+        });
+        self.mode = Mode::InstallVars;
+    }
+
+    fn submit_install_form(&mut self) -> AppCommand {
+        let Some(mut form) = self.install_form.take() else {
+            return AppCommand::None;
+        };
+        if let Some(empty) = form.fields.iter().position(|(_, value)| value.trim().is_empty()) {
+            form.cursor = empty;
+            let message = format!("{} needs a value", form.fields[empty].0);
+            self.install_form = Some(form);
+            self.set_status(message, true);
+            return AppCommand::None;
         }
+
+        let vars: HashMap<String, String> = form.fields.into_iter().collect();
+        self.install_var_memory
+            .extend(vars.iter().map(|(name, value)| (name.clone(), value.clone())));
+        self.mode = Mode::Normal;
+        let task = format!("Install {}", form.id);
+        let command = AppCommand::Install {
+            id: form.id,
+            install_path: form.install_path,
+            vars: Some(vars),
+        };
+        self.queue_task(task, command)
     }
 
     /// Get the DefinitionKind if the cursor is on a header row.
     fn header_kind_at_cursor(&self) -> Option<DefinitionKind> {
-        let row = self.flat_items.get(self.cursor)?;
-        if let ListRow::Header { label, .. } = row {
-            // Find the group matching this label.
-            self.groups
-                .iter()
-                .find(|g| &g.label == label)
-                .map(|g| g.kind.clone())
-        } else {
-            None
+        match self.flat_items.get(self.cursor)? {
+            ListRow::Header { kind, .. } => Some(kind.clone()),
+            ListRow::Item { .. } => None,
         }
     }
 
@@ -1012,6 +2718,24 @@ impl App {
         kinds
     }
 
+    /// The distinct categories among the definitions every other filter and
+    /// the search let through, with how many each has, sorted by name.
+    pub fn available_categories(&self) -> Vec<(String, usize)> {
+        let search = TextQuery::parse(&self.search_query);
+        let mut counts: std::collections::BTreeMap<&str, usize> = Default::default();
+        for s in &self.summaries {
+            if let Some(category) = &s.category
+                && self.passes_filters(s, &search)
+            {
+                *counts.entry(category).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|(category, count)| (category.to_owned(), count))
+            .collect()
+    }
+
     /// Get the distinct source labels present in the full (unfiltered) summaries.
     pub fn available_sources(&self) -> Vec<String> {
         let mut sources: Vec<String> = Vec::new();
@@ -1024,16 +2748,118 @@ impl App {
         sources
     }
 
+    /// Branches that definitions come from, for sources that sync several,
+    /// sorted and deduplicated.
+    pub fn available_branches(&self) -> Vec<String> {
+        let mut branches: Vec<String> = self
+            .summaries
+            .iter()
+            .filter_map(|s| agent_defs::path::split_branch(s.id.as_str()).0)
+            .map(str::to_owned)
+            .collect();
+        branches.sort();
+        branches.dedup();
+        branches
+    }
+
+    /// Entries for the source filter overlay: "All", "Most used" once
+    /// anything has been used, each source, each branch of multi-branch
+    /// sources, then each collection.
+    pub fn source_filter_options(&self) -> Vec<SourceOption> {
+        let mut options = vec![SourceOption::All];
+        if !self.usage.is_empty() {
+            options.push(SourceOption::MostUsed);
+        }
+        options.extend(self.available_sources().into_iter().map(SourceOption::Source));
+        options.extend(self.available_branches().into_iter().map(SourceOption::Branch));
+        options.extend(
+            self.collections
+                .iter()
+                .map(|c| SourceOption::Collection(c.name.clone())),
+        );
+        options
+    }
+
+    /// The collection selected in the source filter, if any.
+    pub fn active_collection(&self) -> Option<&Collection> {
+        let name = self.collection_filter.as_ref()?;
+        self.collections.iter().find(|c| &c.name == name)
+    }
+
+    /// True if any kind, source, category, collection, or "Most used"
+    /// filter is narrowing the list.
+    pub fn has_filters(&self) -> bool {
+        self.kind_filter.is_some()
+            || self.source_filter.is_some()
+            || self.category_filter.is_some()
+            || self.branch_filter.is_some()
+            || self.collection_filter.is_some()
+            || self.most_used_filter
+    }
+
+    /// The active filters and search, each with the label its chip shows.
+    pub fn filter_chips(&self) -> Vec<(FilterChip, String)> {
+        let mut chips = Vec::new();
+        if let Some(kind) = &self.kind_filter {
+            let label = self.glyphs.prefix(kind, kind.display_label());
+            chips.push((FilterChip::Kind, format!("kind:{label}")));
+        }
+        if let Some(source) = &self.source_filter {
+            chips.push((FilterChip::Source, format!("source:{source}")));
+        }
+        if let Some(branch) = &self.branch_filter {
+            chips.push((FilterChip::Branch, format!("branch:{branch}")));
+        }
+        if let Some(category) = &self.category_filter {
+            chips.push((FilterChip::Category, format!("category:{category}")));
+        }
+        if let Some(collection) = &self.collection_filter {
+            chips.push((FilterChip::Collection, format!("collection:{collection}")));
+        }
+        if self.most_used_filter {
+            chips.push((FilterChip::MostUsed, "most used".to_owned()));
+        }
+        if !self.search_query.is_empty() {
+            chips.push((FilterChip::Search, format!("search:{}", self.search_query)));
+        }
+        chips
+    }
+
+    /// Drop the filter behind `chip`, keeping the others.
+    pub fn clear_chip(&mut self, chip: FilterChip) -> AppCommand {
+        match chip {
+            FilterChip::Kind => self.kind_filter = None,
+            FilterChip::Source => self.source_filter = None,
+            FilterChip::Branch => self.branch_filter = None,
+            FilterChip::Category => self.category_filter = None,
+            FilterChip::Collection => self.collection_filter = None,
+            FilterChip::MostUsed => self.most_used_filter = false,
+            FilterChip::Search => self.search_query.clear(),
+        }
+        self.recompute_view();
+        self.maybe_fetch_current()
+    }
+
+    /// How many loaded definitions have recorded usage.
+    pub fn most_used_count(&self) -> usize {
+        self.summaries
+            .iter()
+            .filter(|s| self.usage.iter().any(|u| u.matches(s)))
+            .count()
+    }
+
     /// If the cursor is on a selectable item, return a fetch command.
     fn maybe_fetch_current(&mut self) -> AppCommand {
         if let Some(summary) = self.selected_summary() {
-            let id = summary.id.clone();
+            // Qualified, so a path more than one source has still fetches
+            // the copy under the cursor.
+            let id = summary.qualified_id();
             // Don't re-fetch if we already have this one or it's already pending.
             if self.pending_fetch.as_ref() == Some(&id) {
                 return AppCommand::None;
             }
             if let Some(def) = &self.selected_definition
-                && def.id == id
+                && def.qualified_id() == id
             {
                 return AppCommand::None;
             }
@@ -1045,38 +2871,67 @@ impl App {
         }
     }
 
+    /// Whether `s` gets past the hide rules, `search`, and every filter
+    /// except the category one.
+    fn passes_filters(&self, s: &DefinitionSummary, search: &TextQuery) -> bool {
+        if self.hidden.hides(s) {
+            return false;
+        }
+        if let Some(ref kind) = self.kind_filter
+            && &s.kind != kind
+        {
+            return false;
+        }
+        if let Some(ref source) = self.source_filter
+            && &s.source_label != source
+        {
+            return false;
+        }
+        if let Some(ref branch) = self.branch_filter
+            && agent_defs::path::split_branch(s.id.as_str()).0 != Some(branch.as_str())
+        {
+            return false;
+        }
+        if let Some(collection) = self.active_collection()
+            && !collection.contains(s)
+        {
+            return false;
+        }
+        if self.most_used_filter && !self.usage.iter().any(|u| u.matches(s)) {
+            return false;
+        }
+        if !search.is_empty() {
+            let name = s.name.to_lowercase();
+            let description = s.description.as_deref().unwrap_or("").to_lowercase();
+            let note = self.notes.iter().find(|n| n.matches(s));
+            return search.matches(|term| {
+                name.contains(term)
+                    || description.contains(term)
+                    || note.is_some_and(|n| n.mentions(term))
+            });
+        }
+        true
+    }
+
     fn recompute_view(&mut self) {
+        let search = TextQuery::parse(&self.search_query);
         let view: Vec<DefinitionSummary> = self
             .summaries
             .iter()
             .filter(|s| {
-                if let Some(ref kind) = self.kind_filter
-                    && &s.kind != kind
-                {
-                    return false;
-                }
-                if let Some(ref source) = self.source_filter
-                    && &s.source_label != source
+                if let Some(ref category) = self.category_filter
+                    && s.category.as_ref() != Some(category)
                 {
                     return false;
                 }
-                if !self.search_query.is_empty() {
-                    let q = self.search_query.to_lowercase();
-                    if !s.name.to_lowercase().contains(&q)
-                        && !s
-                            .description
-                            .as_ref()
-                            .is_some_and(|d| d.to_lowercase().contains(&q))
-                    {
-                        return false;
-                    }
-                }
-                true
+                self.passes_filters(s, &search)
             })
             .cloned()
             .collect();
 
         self.view_summaries = view;
+        self.item_order.sort(&mut self.view_summaries);
+        self.display_names = DisplayNames::new(&self.view_summaries);
         let (groups, flat_items) = grouping::build_groups(&self.view_summaries);
         self.groups = groups;
         self.flat_items = flat_items;
@@ -1088,14 +2943,31 @@ impl App {
         self.status_message = Some(StatusMessage {
             text,
             is_error,
-            ticks_remaining: 12, // ~3 seconds at 250ms tick
+            ticks_remaining: (STATUS_DURATION.as_millis() / self.tick_rate.as_millis().max(1))
+                .clamp(1, u16::MAX.into()) as u16,
         });
     }
 }
 
+/// Scroll offset that keeps `cursor` within a viewport of `visible_height` rows,
+/// moving the viewport as little as possible.
+pub(crate) fn compute_scroll_offset(
+    cursor: usize,
+    current_offset: usize,
+    visible_height: usize,
+) -> usize {
+    if cursor < current_offset {
+        cursor
+    } else if cursor >= current_offset + visible_height {
+        cursor.saturating_sub(visible_height - 1)
+    } else {
+        current_offset
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use agent_defs::{DefinitionId, DefinitionKind};
+    use agent_defs::{ChangeKind, DefinitionId, DefinitionKind, TaskState};
     use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
 
     use super::*;
@@ -1109,6 +2981,8 @@ mod tests {
             kind,
             category: None,
             source_label: "test".into(),
+            first_seen_at: None,
+            last_updated_at: None,
         }
     }
 
@@ -1120,6 +2994,8 @@ mod tests {
             kind,
             category: None,
             source_label: "test".into(),
+            first_seen_at: None,
+            last_updated_at: None,
         }
     }
 
@@ -1309,6 +3185,31 @@ mod tests {
         assert_eq!(app.detail_scroll, 0);
     }
 
+    #[test]
+    fn loaded_settings_preview_the_keys_they_change() {
+        let target = std::env::temp_dir().join("agent-defs-tui-test-settings-preview");
+        let _ = std::fs::remove_dir_all(&target);
+        std::fs::create_dir_all(target.join(".claude")).unwrap();
+        std::fs::write(target.join(".claude/settings.json"), r#"{"cleanupPeriodDays": 30}"#)
+            .unwrap();
+
+        let summaries = vec![summary("settings/strict.json", DefinitionKind::Setting)];
+        let mut app = App::with_install_target(summaries, "test".into(), Some(target.clone()));
+        app.pending_fetch = Some(DefinitionId::new("settings/strict.json"));
+        let mut def = sample_definition_with_raw(
+            "settings/strict.json",
+            r#"{"name": "strict", "cleanupPeriodDays": 7, "env": {"CI": "1"}}"#,
+        );
+        def.kind = DefinitionKind::Setting;
+        app.handle_action(Action::DefinitionLoaded(def.id.clone(), Box::new(Ok(def))));
+        let _ = std::fs::remove_dir_all(&target);
+
+        let preview = app.settings_preview.expect("settings preview");
+        assert_eq!(preview.path, target.join(".claude/settings.json"));
+        let lines: Vec<String> = preview.changes.unwrap().iter().map(ToString::to_string).collect();
+        assert_eq!(lines, ["~ cleanupPeriodDays = 7 (was 30)", "+ env.CI = \"1\""]);
+    }
+
     #[test]
     fn stale_fetch_is_silently_dropped() {
         let summaries = vec![
@@ -1439,7 +3340,7 @@ mod tests {
     fn s_triggers_sync() {
         let mut app = App::new(vec![], "test".into());
         let cmd = app.handle_event(key_event(KeyCode::Char('s')));
-        assert!(matches!(cmd, AppCommand::Sync));
+        assert!(matches!(cmd, AppCommand::Sync(SyncTarget::All)));
         assert_eq!(app.loading, LoadingState::Syncing);
     }
 
@@ -1451,21 +3352,233 @@ mod tests {
         assert!(matches!(cmd, AppCommand::None));
     }
 
+    fn synced(app: &mut App, label: &str, report: SourceReport) {
+        let event = SyncEvent::Finished(label.into(), Ok(report));
+        app.handle_action(Action::SyncProgress(event));
+    }
+
     #[test]
-    fn sync_completed_triggers_reload() {
+    fn sync_tracks_each_source_then_reloads() {
         let mut app = App::new(vec![], "test".into());
-        app.loading = LoadingState::Syncing;
-        app.mode = Mode::SyncProgress;
-
-        let result = SyncResult {
-            message: "Synced 5".into(),
-            warnings: vec![],
+        app.set_sync_sources(vec![
+            ("one".into(), Freshness::NeverSynced),
+            ("two".into(), Freshness::Stale { days_old: 9 }),
+        ]);
+        app.handle_event(key_event(KeyCode::Char('s')));
+        assert!(app.sync_sources.iter().all(|s| s.state == SourceSyncState::Pending));
+
+        app.handle_action(Action::SyncProgress(SyncEvent::Started("one".into())));
+        assert_eq!(app.sync_sources[0].state, SourceSyncState::Syncing);
+        let report = SourceReport {
+            synced: 5,
+            ..SourceReport::default()
         };
-        let cmd = app.handle_action(Action::SyncCompleted(Ok(result)));
+        synced(&mut app, "one", report);
+        let failed = SyncEvent::Finished("two".into(), Err(SyncError::Timeout("no answer".into())));
+        app.handle_action(Action::SyncProgress(failed));
+        assert_eq!(app.sync_sources[0].state, SourceSyncState::Synced(5));
+        assert_eq!(app.sync_sources[1].state, SourceSyncState::Failed);
+
+        let fresh = Freshness::Fresh { days_old: 0 };
+        let cmd = app.handle_action(Action::SyncFinished(vec![("one".into(), fresh)]));
         assert!(matches!(cmd, AppCommand::ReloadList));
         assert_eq!(app.loading, LoadingState::Idle);
+        assert_eq!(app.source_freshness("one"), Some(fresh));
         // Should stay in SyncProgress mode to show results
         assert_eq!(app.mode, Mode::SyncProgress);
+        let result = app.sync_result.as_ref().unwrap();
+        assert_eq!(result.synced, 5);
+        assert_eq!(result.failures[0].source_label, "two");
+    }
+
+    #[test]
+    fn r_retries_the_selected_failed_source() {
+        let mut app = App::new(vec![], "test".into());
+        app.mode = Mode::SyncProgress;
+        app.sync_result = Some(SyncResult {
+            message: "Synced 5".into(),
+            failures: vec![crate::SyncFailure {
+                source_label: "acme".into(),
+                error: SyncError::Timeout("no answer".into()),
+            }],
+            warnings: vec!["skipping agents/a.md: bad frontmatter".into()],
+            ..SyncResult::default()
+        });
+
+        // A warning line has nothing to retry.
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.sync_result_scroll, 1);
+        assert!(matches!(app.handle_event(key_event(KeyCode::Char('r'))), AppCommand::None));
+
+        app.handle_event(key_event(KeyCode::Char('k')));
+        let cmd = app.handle_event(key_event(KeyCode::Char('r')));
+        assert!(matches!(cmd, AppCommand::Sync(SyncTarget::Source(label)) if label == "acme"));
+        assert_eq!(app.loading, LoadingState::Syncing);
+        assert_eq!(app.mode, Mode::SyncProgress);
+        // The rest of the result stays; only the retried failure goes.
+        let result = app.sync_result.as_ref().unwrap();
+        assert!(result.failures.is_empty());
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    // --- Tasks ---
+
+    /// An app syncing, with an install of `a` confirmed behind it.
+    fn install_queued_behind_sync() -> App {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
+        let mut app = App::new(summaries, "test".into());
+        app.selected_definition = Some(sample_definition_with_raw("a", "content"));
+        app.loading = LoadingState::Idle;
+        let cmd = app.handle_event(key_event(KeyCode::Char('s')));
+        assert!(matches!(cmd, AppCommand::Sync(SyncTarget::All)));
+
+        app.mode = Mode::InstallConfirm;
+        app.install_target = Some(PathBuf::from("/tmp"));
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.tasks.pending(), 1);
+        app
+    }
+
+    #[test]
+    fn queued_install_starts_when_the_sync_is_done() {
+        let mut app = install_queued_behind_sync();
+
+        let cmd = app.handle_action(Action::SyncFinished(vec![]));
+        assert!(matches!(cmd, AppCommand::ReloadList));
+        let cmd = app.handle_action(Action::ListReloaded(Ok(vec![summary(
+            "a",
+            DefinitionKind::Agent,
+        )])));
+        assert!(matches!(cmd, AppCommand::Install { .. }));
+        assert_eq!(app.tasks.running().unwrap().label, "Install a");
+
+        let cmd = app.handle_action(Action::InstallCompleted(Err("disk full".into())));
+        assert!(matches!(cmd, AppCommand::None));
+        let states: Vec<&TaskState> = app.tasks.tasks().iter().map(|t| &t.state).collect();
+        assert!(matches!(states[..], [TaskState::Done(_), TaskState::Failed(_)]));
+    }
+
+    #[test]
+    fn task_list_cancels_only_waiting_tasks() {
+        let mut app = install_queued_behind_sync();
+        app.handle_event(key_event(KeyCode::Char('t')));
+        assert_eq!(app.mode, Mode::Tasks);
+        assert_eq!(app.task_cursor, 1);
+
+        app.handle_event(key_event(KeyCode::Char('k')));
+        app.handle_event(key_event(KeyCode::Char('x')));
+        assert!(app.status_message.as_ref().unwrap().is_error);
+        assert_eq!(app.tasks.pending(), 1);
+
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Char('x')));
+        assert_eq!(app.tasks.pending(), 0);
+        assert_eq!(app.tasks.tasks()[1].state, TaskState::Cancelled);
+
+        app.handle_action(Action::SyncFinished(vec![]));
+        let cmd = app.handle_action(Action::ListReloaded(Ok(vec![])));
+        assert!(matches!(cmd, AppCommand::None));
+    }
+
+    // --- What's new ---
+
+    fn change(id: &str, kind: ChangeKind) -> DefinitionChange {
+        DefinitionChange {
+            id: DefinitionId::new(id),
+            name: id.to_owned(),
+            kind: DefinitionKind::Agent,
+            source_label: "test".into(),
+            change: kind,
+            synced_at: 0,
+        }
+    }
+
+    #[test]
+    fn w_opens_and_closes_whats_new() {
+        let mut app = App::new(vec![], "test".into());
+        app.changes = vec![change("a", ChangeKind::Added), change("b", ChangeKind::Removed)];
+
+        app.handle_event(key_event(KeyCode::Char('w')));
+        assert_eq!(app.mode, Mode::WhatsNew);
+
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.changes_scroll, 1);
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn sync_prepends_new_changes() {
+        let mut app = App::new(vec![], "test".into());
+        app.changes = vec![change("old", ChangeKind::Changed)];
+        app.loading = LoadingState::Syncing;
+        app.mode = Mode::SyncProgress;
+
+        let report = SourceReport {
+            changes: vec![change("new", ChangeKind::Added)],
+            ..SourceReport::default()
+        };
+        synced(&mut app, "test", report);
+        app.handle_action(Action::SyncFinished(vec![]));
+        let ids: Vec<_> = app.changes.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["new", "old"]);
+
+        app.handle_event(key_event(KeyCode::Char('w')));
+        assert_eq!(app.mode, Mode::WhatsNew);
+    }
+
+    // --- Warnings ---
+
+    fn skipped_file(source: &str, path: &str) -> SkippedFile {
+        SkippedFile {
+            source_label: source.into(),
+            path: path.into(),
+            reason: "missing frontmatter".into(),
+            recorded_at: 0,
+        }
+    }
+
+    #[test]
+    fn shift_w_browses_skipped_files_by_source() {
+        let mut app = App::new(vec![], "test".into());
+        app.set_skipped(vec![
+            skipped_file("b", "agents/x.md"),
+            skipped_file("a", "agents/z.md"),
+            skipped_file("a", "agents/y.md"),
+        ]);
+        let order: Vec<_> = app.skipped.iter().map(|s| (&*s.source_label, &*s.path)).collect();
+        assert_eq!(order, [("a", "agents/y.md"), ("a", "agents/z.md"), ("b", "agents/x.md")]);
+        assert_eq!(app.skipped_rows(), 8);
+
+        app.handle_event(key_event(KeyCode::Char('W')));
+        assert_eq!(app.mode, Mode::Warnings);
+        for _ in 0..10 {
+            app.handle_event(key_event(KeyCode::Char('j')));
+        }
+        assert_eq!(app.skipped_scroll, 7);
+
+        app.handle_event(key_event(KeyCode::Char('W')));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn sync_replaces_the_source_skipped_files() {
+        let mut app = App::new(vec![], "test".into());
+        app.set_skipped(vec![skipped_file("a", "agents/old.md"), skipped_file("b", "agents/b.md")]);
+        app.loading = LoadingState::Syncing;
+        app.mode = Mode::SyncProgress;
+
+        let report = SourceReport {
+            skipped_files: vec![skipped_file("a", "agents/new.md")],
+            ..SourceReport::default()
+        };
+        synced(&mut app, "a", report);
+        let paths: Vec<_> = app.skipped.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, ["agents/new.md", "agents/b.md"]);
     }
 
     // --- Copy ---
@@ -1479,6 +3592,56 @@ mod tests {
         assert!(matches!(cmd, AppCommand::CopyBody(_)));
     }
 
+    #[test]
+    fn shift_y_copies_the_qualified_id() {
+        let mut app = App::new(vec![summary("agents/a.md", DefinitionKind::Agent)], "test".into());
+
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('Y')));
+        assert!(matches!(cmd, AppCommand::CopyBody(id) if id == "test:agents/a.md"));
+    }
+
+    #[test]
+    fn o_cycles_the_order_within_each_kind() {
+        let dated = |name, added, updated| DefinitionSummary {
+            first_seen_at: Some(added),
+            last_updated_at: Some(updated),
+            ..summary(name, DefinitionKind::Agent)
+        };
+        let mut app = App::new(vec![dated("a", 1, 3), dated("b", 2, 2)], "test".into());
+        let names = |app: &App| -> Vec<String> {
+            app.view_summaries.iter().map(|s| s.name.clone()).collect()
+        };
+
+        app.handle_event(key_event(KeyCode::Char('o')));
+        assert_eq!(names(&app), ["b", "a"]);
+        app.handle_event(key_event(KeyCode::Char('o')));
+        assert_eq!(names(&app), ["a", "b"]);
+        assert_eq!(app.item_order, ItemOrder::Updated);
+        app.handle_event(key_event(KeyCode::Char('o')));
+        assert_eq!(app.item_order, ItemOrder::Name);
+    }
+
+    #[test]
+    fn e_and_v_open_the_selection_externally() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        assert!(matches!(
+            app.handle_event(key_event(KeyCode::Char('e'))),
+            AppCommand::None
+        ));
+
+        app.selected_definition = Some(sample_definition("a"));
+        let cmd = app.handle_event(key_event(KeyCode::Char('e')));
+        let expected = |id: &DefinitionId| id.as_str() == "test:a";
+        assert!(matches!(cmd, AppCommand::OpenExternal(id, ExternalTool::Editor) if expected(&id)));
+        app.handle_event(key_event(KeyCode::Char('z')));
+        let cmd = app.handle_event(key_event(KeyCode::Char('v')));
+        assert!(matches!(cmd, AppCommand::OpenExternal(_, ExternalTool::Pager)));
+
+        app.handle_action(Action::ExternalClosed(Err("less exited with 2".into())));
+        let status = app.status_message.as_ref().map(|s| s.text.as_str());
+        assert_eq!(status, Some("less exited with 2"));
+    }
+
     #[test]
     fn c_without_selection_is_noop() {
         let mut app = App::new(vec![], "test".into());
@@ -1542,11 +3705,24 @@ mod tests {
             ticks_remaining: 1,
         });
 
-        app.tick(); // ticks_remaining -> 0
+        assert!(app.needs_ticks());
+        assert!(!app.tick()); // ticks_remaining -> 0
         assert!(app.status_message.is_some());
 
-        app.tick(); // expires
+        assert!(app.tick()); // expires
         assert!(app.status_message.is_none());
+        assert!(!app.needs_ticks());
+    }
+
+    #[test]
+    fn status_messages_last_as_long_at_any_tick_rate() {
+        let mut app = App::new(vec![], "test".into());
+        app.set_status("hello".into(), false);
+        assert_eq!(app.status_message.as_ref().unwrap().ticks_remaining, 12);
+
+        app.tick_rate = Duration::from_millis(1000);
+        app.set_status("hello".into(), false);
+        assert_eq!(app.status_message.as_ref().unwrap().ticks_remaining, 3);
     }
 
     // --- Kind filter ---
@@ -1744,6 +3920,79 @@ mod tests {
         assert_eq!(item_count, 1); // Only alpha-agent matches both filters
     }
 
+    #[test]
+    fn category_filter_offers_the_categories_other_filters_leave() {
+        let categorized = |name, kind, category: &str| DefinitionSummary {
+            category: Some(category.to_owned()),
+            ..summary(name, kind)
+        };
+        let summaries = vec![
+            categorized("a", DefinitionKind::Agent, "review"),
+            categorized("b", DefinitionKind::Agent, "planning"),
+            categorized("c", DefinitionKind::Agent, "review"),
+            categorized("d", DefinitionKind::Hook, "ops"),
+            summary("e", DefinitionKind::Agent),
+        ];
+        let mut app = App::new(summaries, "test".into());
+        app.kind_filter = Some(DefinitionKind::Agent);
+        app.recompute_view();
+
+        assert_eq!(
+            app.available_categories(),
+            [("planning".to_owned(), 1), ("review".to_owned(), 2)]
+        );
+
+        app.handle_event(shift_key_event(KeyCode::Char('C')));
+        assert_eq!(app.mode, Mode::CategoryFilter);
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.category_filter.as_deref(), Some("review"));
+        let names: Vec<&str> = app.view_summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+        // The other categories stay on offer while one is picked.
+        assert_eq!(app.available_categories().len(), 2);
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.category_filter, None);
+        assert_eq!(app.kind_filter, None);
+    }
+
+    #[test]
+    fn filter_chips_clear_one_filter_each() {
+        let summaries = vec![
+            summary("alpha", DefinitionKind::Agent),
+            summary("beta", DefinitionKind::Hook),
+        ];
+        let mut app = App::new(summaries, "test".into());
+        app.kind_filter = Some(DefinitionKind::Agent);
+        app.source_filter = Some("test".into());
+        app.search_query = "al".into();
+        app.recompute_view();
+        let chips: Vec<FilterChip> = app.filter_chips().into_iter().map(|(c, _)| c).collect();
+        assert_eq!(chips, [FilterChip::Kind, FilterChip::Source, FilterChip::Search]);
+
+        app.handle_event(Event::Key(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT)));
+        assert_eq!(app.source_filter, None);
+        assert_eq!(app.kind_filter, Some(DefinitionKind::Agent));
+        assert_eq!(app.pending_count, None);
+
+        app.layout_geometry.chips = vec![
+            (Rect::new(1, 1, 10, 1), FilterChip::Kind),
+            (Rect::new(12, 1, 10, 1), FilterChip::Search),
+        ];
+        app.handle_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 14,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        }));
+        assert_eq!(app.search_query, "");
+        assert_eq!(app.kind_filter, Some(DefinitionKind::Agent));
+        assert_eq!(app.view_summaries.len(), 1);
+    }
+
     #[test]
     fn reload_preserves_kind_filter() {
         let summaries = vec![
@@ -1796,85 +4045,490 @@ mod tests {
         );
     }
 
-    // --- Install ---
+    // --- Motions ---
 
-    fn sample_definition_with_raw(name: &str, raw: &str) -> Definition {
-        Definition {
-            id: DefinitionId::new(name),
-            name: name.to_owned(),
-            description: None,
-            kind: DefinitionKind::Agent,
-            category: None,
-            source_label: "test".to_owned(),
-            body: format!("Body of {name}"),
-            tools: vec![],
-            model: None,
-            metadata: std::collections::HashMap::new(),
-            raw: raw.to_owned(),
-        }
+    /// Ten agents: flat rows are Header(0), Item(1)..Item(10).
+    fn ten_agents() -> App {
+        let summaries = (0..10)
+            .map(|i| summary(&format!("a{i}"), DefinitionKind::Agent))
+            .collect();
+        App::new(summaries, "test".into())
     }
 
-    fn shift_key_event(code: KeyCode) -> Event {
-        Event::Key(KeyEvent {
-            code,
-            modifiers: KeyModifiers::SHIFT,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
+    #[test]
+    fn count_prefix_repeats_motion() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::Char('5')));
+        assert_eq!(app.pending_count, Some(5));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.cursor, 6);
+        assert_eq!(app.pending_count, None);
+
+        app.handle_event(key_event(KeyCode::Char('3')));
+        app.handle_event(key_event(KeyCode::Char('k')));
+        assert_eq!(app.cursor, 3);
     }
 
     #[test]
-    fn i_with_target_opens_explorer_at_target() {
-        let summaries = vec![summary("a", DefinitionKind::Agent)];
-        let mut app =
-            App::with_install_target(summaries, "test".into(), Some(PathBuf::from("/tmp")));
-        app.selected_definition = Some(sample_definition_with_raw("a", "raw content"));
+    fn multi_digit_count() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::Char('1')));
+        app.handle_event(key_event(KeyCode::Char('0')));
+        assert_eq!(app.pending_count, Some(10));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.cursor, 10);
+    }
 
-        // Pressing 'i' now always opens the explorer (even with existing target)
-        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
-        assert!(matches!(cmd, AppCommand::None));
-        assert_eq!(app.mode, Mode::InstallPrompt);
-        assert!(app.file_explorer.is_some());
+    #[test]
+    fn count_is_dropped_by_unrelated_key() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::Char('4')));
+        app.handle_event(key_event(KeyCode::Char('z')));
+        app.handle_event(key_event(KeyCode::Char('z')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.cursor, 2);
     }
 
     #[test]
-    fn i_without_target_enters_install_prompt() {
-        let summaries = vec![summary("a", DefinitionKind::Agent)];
-        let mut app = App::new(summaries, "test".into());
-        app.selected_definition = Some(sample_definition_with_raw("a", "raw content"));
+    fn gg_and_shift_g_jump_to_ends() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::Char('G')));
+        assert_eq!(app.cursor, 10);
+
+        app.handle_event(key_event(KeyCode::Char('g')));
+        assert_eq!(app.cursor, 10);
+        app.handle_event(key_event(KeyCode::Char('g')));
+        assert_eq!(app.cursor, 1);
+    }
 
-        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
-        assert!(matches!(cmd, AppCommand::None));
-        assert_eq!(app.mode, Mode::InstallPrompt);
-        assert!(app.file_explorer.is_some());
+    #[test]
+    fn counted_g_jumps_to_nth_item() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::Char('4')));
+        app.handle_event(key_event(KeyCode::Char('G')));
+        assert_eq!(app.cursor, 4);
+
+        app.handle_event(key_event(KeyCode::Char('7')));
+        app.handle_event(key_event(KeyCode::Char('g')));
+        app.handle_event(key_event(KeyCode::Char('g')));
+        assert_eq!(app.cursor, 7);
     }
 
     #[test]
-    fn i_without_selection_is_noop() {
-        let mut app = App::new(vec![], "test".into());
-        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
-        assert!(matches!(cmd, AppCommand::None));
-        assert_eq!(app.mode, Mode::Normal);
+    fn home_and_end_jump_to_ends() {
+        let mut app = ten_agents();
+        app.handle_event(key_event(KeyCode::End));
+        assert_eq!(app.cursor, 10);
+        app.handle_event(key_event(KeyCode::Home));
+        assert_eq!(app.cursor, 1);
     }
 
     #[test]
-    fn install_prompt_esc_cancels() {
-        let summaries = vec![summary("a", DefinitionKind::Agent)];
-        let mut app = App::new(summaries, "test".into());
-        app.selected_definition = Some(sample_definition_with_raw("a", "content"));
+    fn shift_page_keys_move_list_by_viewport() {
+        let mut app = ten_agents();
+        app.layout_geometry.list_inner = Rect::new(1, 2, 20, 4);
 
-        app.handle_event(key_event(KeyCode::Char('i')));
-        assert_eq!(app.mode, Mode::InstallPrompt);
-        assert!(app.file_explorer.is_some());
+        app.handle_event(shift_key_event(KeyCode::PageDown));
+        assert_eq!(app.cursor, 5);
+        assert_eq!(app.detail_scroll, 0);
 
-        app.handle_event(key_event(KeyCode::Esc));
-        assert_eq!(app.mode, Mode::Normal);
-        assert!(app.file_explorer.is_none());
+        app.handle_event(shift_key_event(KeyCode::PageDown));
+        app.handle_event(shift_key_event(KeyCode::PageDown));
+        assert_eq!(app.cursor, 10);
+
+        app.handle_event(shift_key_event(KeyCode::PageUp));
+        assert_eq!(app.cursor, 6);
     }
 
     #[test]
-    fn install_prompt_i_shows_confirmation() {
-        let summaries = vec![summary("a", DefinitionKind::Agent)];
+    fn viewport_follows_cursor() {
+        let mut app = ten_agents();
+        app.layout_geometry.list_inner = Rect::new(1, 2, 20, 4);
+
+        app.handle_event(key_event(KeyCode::End));
+        assert_eq!(app.list_scroll_offset, 7);
+
+        app.handle_event(key_event(KeyCode::Home));
+        assert_eq!(app.list_scroll_offset, 1);
+    }
+
+    // --- Pane split ---
+
+    fn mouse_event(kind: MouseEventKind, column: u16, row: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn brackets_resize_split() {
+        let mut app = App::new(vec![], "test".into());
+        assert_eq!(app.list_percent, DEFAULT_LIST_PERCENT);
+
+        app.handle_event(key_event(KeyCode::Char(']')));
+        assert_eq!(app.list_percent, DEFAULT_LIST_PERCENT + SPLIT_STEP);
+
+        app.handle_event(key_event(KeyCode::Char('[')));
+        app.handle_event(key_event(KeyCode::Char('[')));
+        assert_eq!(app.list_percent, DEFAULT_LIST_PERCENT - SPLIT_STEP);
+    }
+
+    #[test]
+    fn split_is_clamped() {
+        let mut app = App::new(vec![], "test".into());
+        for _ in 0..30 {
+            app.handle_event(key_event(KeyCode::Char('[')));
+        }
+        assert_eq!(app.list_percent, MIN_LIST_PERCENT);
+
+        for _ in 0..30 {
+            app.handle_event(key_event(KeyCode::Char(']')));
+        }
+        assert_eq!(app.list_percent, MAX_LIST_PERCENT);
+    }
+
+    #[test]
+    fn dragging_divider_resizes_split() {
+        let mut app = App::new(vec![], "test".into());
+        app.layout_geometry.content = Rect::new(0, 1, 100, 20);
+        app.layout_geometry.divider = Rect::new(34, 1, 2, 20);
+
+        app.handle_event(mouse_event(MouseEventKind::Down(MouseButton::Left), 34, 5));
+        app.handle_event(mouse_event(MouseEventKind::Drag(MouseButton::Left), 49, 5));
+        assert_eq!(app.list_percent, 50);
+
+        app.handle_event(mouse_event(MouseEventKind::Up(MouseButton::Left), 49, 5));
+        app.handle_event(mouse_event(MouseEventKind::Drag(MouseButton::Left), 69, 5));
+        assert_eq!(app.list_percent, 50);
+    }
+
+    #[test]
+    fn drag_outside_divider_does_not_resize() {
+        let mut app = App::new(vec![], "test".into());
+        app.layout_geometry.content = Rect::new(0, 1, 100, 20);
+        app.layout_geometry.divider = Rect::new(34, 1, 2, 20);
+
+        app.handle_event(mouse_event(MouseEventKind::Down(MouseButton::Left), 60, 5));
+        app.handle_event(mouse_event(MouseEventKind::Drag(MouseButton::Left), 20, 5));
+        assert_eq!(app.list_percent, DEFAULT_LIST_PERCENT);
+    }
+
+    #[test]
+    fn split_survives_reload() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.set_list_percent(60);
+        app.reload(vec![summary("b", DefinitionKind::Hook)]);
+        assert_eq!(app.list_percent, 60);
+    }
+
+    #[test]
+    fn comfortable_rows_take_two_lines() {
+        let mut app = ten_agents();
+        app.layout_geometry.list_inner = Rect::new(1, 2, 20, 6);
+        app.handle_event(key_event(KeyCode::Char('D')));
+        assert_eq!(app.list_density, ListDensity::Comfortable);
+
+        // Six lines fit three rows, so a page is three items.
+        app.handle_event(shift_key_event(KeyCode::PageDown));
+        assert_eq!(app.cursor, 4);
+        assert_eq!(app.list_scroll_offset, 2);
+
+        // A click on an item's description line selects that item.
+        app.handle_event(mouse_event(MouseEventKind::Down(MouseButton::Left), 5, 5));
+        assert_eq!(app.cursor, 3);
+
+        app.handle_event(key_event(KeyCode::Char('D')));
+        assert_eq!(app.list_density, ListDensity::Compact);
+    }
+
+    // --- Full-screen detail ---
+
+    #[test]
+    fn z_toggles_full_screen_detail() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.handle_event(key_event(KeyCode::Char('z')));
+        assert!(app.detail_zoomed);
+
+        app.handle_event(key_event(KeyCode::Char('z')));
+        assert!(!app.detail_zoomed);
+    }
+
+    #[test]
+    fn esc_and_enter_leave_full_screen_detail() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.handle_event(key_event(KeyCode::Char('z')));
+        app.handle_event(key_event(KeyCode::Esc));
+        assert!(!app.detail_zoomed);
+
+        app.handle_event(key_event(KeyCode::Char('z')));
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(!app.detail_zoomed);
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn arrows_scroll_detail_when_zoomed() {
+        let summaries = vec![
+            summary("a", DefinitionKind::Agent),
+            summary("b", DefinitionKind::Agent),
+        ];
+        let mut app = App::new(summaries, "test".into());
+        let cursor = app.cursor;
+        app.handle_event(key_event(KeyCode::Char('z')));
+
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Down));
+        assert_eq!(app.detail_scroll, 2);
+        assert_eq!(app.cursor, cursor);
+
+        app.handle_event(key_event(KeyCode::Char('k')));
+        assert_eq!(app.detail_scroll, 1);
+
+        app.handle_event(ctrl_key_event('d'));
+        assert_eq!(app.detail_scroll, 6);
+    }
+
+    #[test]
+    fn detail_search_steps_through_matches() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.handle_event(key_event(KeyCode::Char('?')));
+        assert!(app.detail_zoomed);
+        assert_eq!(app.mode, Mode::DetailSearch);
+        for c in "todo".chars() {
+            app.handle_event(key_event(KeyCode::Char(c)));
+        }
+        assert_eq!(app.detail_query, "todo");
+
+        app.layout_geometry.detail_matches = vec![4, 9, 9, 20];
+        app.detail_scroll = 5;
+        app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.detail_scroll, 9);
+
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.detail_scroll, 20);
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.detail_scroll, 4);
+        app.handle_event(key_event(KeyCode::Char('N')));
+        assert_eq!(app.detail_scroll, 20);
+        app.handle_event(key_event(KeyCode::Char('N')));
+        assert_eq!(app.detail_scroll, 9);
+
+        // `/` starts over; Esc drops the search, and so does leaving.
+        app.handle_event(key_event(KeyCode::Char('/')));
+        assert!(app.detail_query.is_empty());
+        app.handle_event(key_event(KeyCode::Char('x')));
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.detail_query.is_empty());
+        assert!(app.detail_zoomed);
+
+        app.handle_event(key_event(KeyCode::Char('/')));
+        app.handle_event(key_event(KeyCode::Char('x')));
+        app.handle_event(key_event(KeyCode::Enter));
+        app.handle_event(key_event(KeyCode::Char('z')));
+        assert!(!app.detail_zoomed);
+        assert!(app.detail_query.is_empty());
+    }
+
+    #[test]
+    fn colon_goes_to_a_body_line() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.layout_geometry.detail_lines = vec![8, 9, 11, 12];
+
+        for key in [':', '3', 'x'] {
+            app.handle_event(key_event(KeyCode::Char(key)));
+        }
+        assert_eq!(app.mode, Mode::GoToLine);
+        assert_eq!(app.go_to_line_input, "3");
+        app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.detail_scroll, 11);
+
+        // Past the end goes to the last line; Esc goes nowhere.
+        app.handle_event(key_event(KeyCode::Char('z')));
+        for key in [':', '9', '9'] {
+            app.handle_event(key_event(KeyCode::Char(key)));
+        }
+        app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.detail_scroll, 12);
+        for key in [':', '1'] {
+            app.handle_event(key_event(KeyCode::Char(key)));
+        }
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.detail_scroll, 12);
+        assert!(app.detail_zoomed);
+
+        app.handle_event(key_event(KeyCode::Char('#')));
+        assert!(app.detail_line_numbers);
+    }
+
+    #[test]
+    fn mark_then_equals_compares_two_definitions() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.handle_event(key_event(KeyCode::Char('=')));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.as_ref().unwrap().is_error);
+
+        app.selected_definition = Some(sample_definition_with_raw("a", "one\ntwo\nthree\nfour\n"));
+        app.handle_event(key_event(KeyCode::Char('m')));
+        assert_eq!(app.compare_mark.as_ref().unwrap().name, "a");
+        app.handle_event(key_event(KeyCode::Char('=')));
+        assert_eq!(app.mode, Mode::Normal, "a definition isn't compared with itself");
+
+        app.selected_definition = Some(sample_definition_with_raw("b", "one\n2\nthree\nfour\n5\n"));
+        app.handle_event(key_event(KeyCode::Char('=')));
+        assert_eq!(app.mode, Mode::Compare);
+        let comparison = app.comparison.as_ref().unwrap();
+        assert_eq!((comparison.left.as_str(), comparison.right.as_str()), ("a", "b"));
+        assert_eq!(comparison.counts(), (1, 2));
+        assert_eq!(comparison.rows(), 6);
+
+        // n and N step between runs of changes.
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.comparison.as_ref().unwrap().scroll, 1);
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.comparison.as_ref().unwrap().scroll, 5);
+        app.handle_event(key_event(KeyCode::Char('N')));
+        assert_eq!(app.comparison.as_ref().unwrap().scroll, 1);
+
+        // Side by side pairs the replaced line with its replacement.
+        app.handle_event(key_event(KeyCode::Tab));
+        let comparison = app.comparison.as_ref().unwrap();
+        assert!(comparison.side_by_side);
+        assert_eq!(comparison.scroll, 0);
+        assert_eq!(comparison.rows(), 5);
+        assert_eq!(
+            comparison.pairs()[1],
+            (
+                Some(&CompareLine::Removed("two".into())),
+                Some(&CompareLine::Added("2".into()))
+            )
+        );
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.comparison.is_none());
+        assert!(app.compare_mark.is_some(), "the mark stays for the next comparison");
+
+        // m on another definition moves the mark; on the marked one clears it.
+        app.handle_event(key_event(KeyCode::Char('m')));
+        assert_eq!(app.compare_mark.as_ref().unwrap().name, "b");
+        app.handle_event(key_event(KeyCode::Char('m')));
+        assert!(app.compare_mark.is_none());
+    }
+
+    #[test]
+    fn braces_and_digits_jump_between_sections() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.layout_geometry.detail_sections = vec![10, 14, 30];
+
+        app.handle_event(key_event(KeyCode::Char('}')));
+        assert_eq!(app.detail_scroll, 10);
+        app.handle_event(key_event(KeyCode::Char('2')));
+        app.handle_event(key_event(KeyCode::Char('}')));
+        assert_eq!(app.detail_scroll, 30);
+        app.handle_event(key_event(KeyCode::Char('}')));
+        assert_eq!(app.detail_scroll, 30);
+        app.handle_event(key_event(KeyCode::Char('{')));
+        assert_eq!(app.detail_scroll, 14);
+        app.handle_event(key_event(KeyCode::Char('{')));
+        app.handle_event(key_event(KeyCode::Char('{')));
+        assert_eq!(app.detail_scroll, 0);
+
+        app.handle_event(key_event(KeyCode::Char('z')));
+        app.handle_event(key_event(KeyCode::Char('3')));
+        assert_eq!(app.detail_scroll, 30);
+        app.handle_event(key_event(KeyCode::Char('1')));
+        assert_eq!(app.detail_scroll, 10);
+        app.handle_event(key_event(KeyCode::Char('9')));
+        assert_eq!(app.detail_scroll, 10);
+        assert!(app.detail_zoomed);
+    }
+
+    // --- Install ---
+
+    fn sample_definition_with_raw(name: &str, raw: &str) -> Definition {
+        Definition {
+            id: DefinitionId::new(name),
+            name: name.to_owned(),
+            description: None,
+            kind: DefinitionKind::Agent,
+            category: None,
+            source_label: "test".to_owned(),
+            body: format!("Body of {name}"),
+            tools: vec![],
+            model: None,
+            metadata: std::collections::HashMap::new(),
+            raw: raw.to_owned(),
+        }
+    }
+
+    fn shift_key_event(code: KeyCode) -> Event {
+        Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::SHIFT,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    #[test]
+    fn i_with_target_opens_explorer_at_target() {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
+        let mut app =
+            App::with_install_target(summaries, "test".into(), Some(PathBuf::from("/tmp")));
+        app.selected_definition = Some(sample_definition_with_raw("a", "raw content"));
+
+        // Pressing 'i' now always opens the explorer (even with existing target)
+        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallPrompt);
+        assert!(app.file_explorer.is_some());
+    }
+
+    #[test]
+    fn i_without_target_enters_install_prompt() {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
+        let mut app = App::new(summaries, "test".into());
+        app.selected_definition = Some(sample_definition_with_raw("a", "raw content"));
+
+        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallPrompt);
+        assert!(app.file_explorer.is_some());
+    }
+
+    #[test]
+    fn i_without_selection_is_noop() {
+        let mut app = App::new(vec![], "test".into());
+        let cmd = app.handle_event(key_event(KeyCode::Char('i')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn install_prompt_esc_cancels() {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
+        let mut app = App::new(summaries, "test".into());
+        app.selected_definition = Some(sample_definition_with_raw("a", "content"));
+
+        app.handle_event(key_event(KeyCode::Char('i')));
+        assert_eq!(app.mode, Mode::InstallPrompt);
+        assert!(app.file_explorer.is_some());
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.file_explorer.is_none());
+    }
+
+    #[test]
+    fn install_prompt_i_shows_confirmation() {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
         let mut app = App::new(summaries, "test".into());
         app.selected_definition = Some(sample_definition_with_raw("a", "content"));
 
@@ -1905,6 +4559,61 @@ mod tests {
         assert!(app.file_explorer.is_none());
     }
 
+    fn quick_install_app(kind: DefinitionKind, target: Option<PathBuf>) -> App {
+        let summaries = vec![summary("a", kind.clone())];
+        let mut app = App::with_install_target(summaries, "test".into(), target);
+        let mut def = sample_definition_with_raw("a", "content");
+        def.kind = kind;
+        app.selected_definition = Some(def);
+        app
+    }
+
+    #[test]
+    fn quick_install_confirms_then_cancels_back_to_the_list() {
+        let mut app = quick_install_app(DefinitionKind::Agent, Some(PathBuf::from("/work")));
+
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('I')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallConfirm);
+        assert!(app.file_explorer.is_none());
+        assert!(app.pending_install_path.as_ref().unwrap().starts_with("/work"));
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.install_target.is_none());
+        assert_eq!(app.quick_install_target, Some(PathBuf::from("/work")));
+    }
+
+    #[test]
+    fn quick_install_without_confirm_installs_at_once() {
+        let mut app = quick_install_app(DefinitionKind::Agent, Some(PathBuf::from("/work")));
+        app.quick_install_confirm = false;
+
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('I')));
+        let AppCommand::Install { install_path, .. } = cmd else {
+            panic!("expected an install, got {cmd:?}");
+        };
+        assert!(install_path.starts_with("/work"));
+        assert_eq!(app.mode, Mode::Normal);
+
+        // Hooks are confirmed regardless.
+        let mut app = quick_install_app(DefinitionKind::Hook, Some(PathBuf::from("/work")));
+        app.quick_install_confirm = false;
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('I')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallConfirm);
+    }
+
+    #[test]
+    fn quick_install_needs_a_configured_target() {
+        let mut app = quick_install_app(DefinitionKind::Agent, None);
+
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('I')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.as_ref().unwrap().is_error);
+    }
+
     #[test]
     fn install_confirm_enter_confirms() {
         let summaries = vec![summary("a", DefinitionKind::Agent)];
@@ -1918,6 +4627,26 @@ mod tests {
         assert_eq!(app.mode, Mode::Normal);
     }
 
+    #[test]
+    fn hooks_need_shift_y_to_install() {
+        let summaries = vec![summary("a", DefinitionKind::Hook)];
+        let mut app = App::new(summaries, "test".into());
+        let mut def = sample_definition_with_raw("a", "content");
+        def.kind = DefinitionKind::Hook;
+        app.selected_definition = Some(def);
+        app.install_target = Some(PathBuf::from("/tmp"));
+        app.mode = Mode::InstallConfirm;
+
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallConfirm);
+        assert!(app.status_message.as_ref().unwrap().text.contains("press Y"));
+
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('Y')));
+        assert!(matches!(cmd, AppCommand::Install { .. }));
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
     #[test]
     fn install_confirm_esc_returns_to_explorer() {
         let summaries = vec![summary("a", DefinitionKind::Agent)];
@@ -1946,4 +4675,428 @@ mod tests {
         assert!(app.status_message.is_some());
         assert!(app.status_message.as_ref().unwrap().is_error);
     }
+
+    // --- Template variables ---
+
+    fn needs_vars(app: &mut App, vars: &[(&str, Option<&str>)]) {
+        app.handle_action(Action::InstallNeedsVars {
+            id: DefinitionId::new("agents/templated.md"),
+            install_path: PathBuf::from("/tmp/.claude/agents/templated.md"),
+            vars: vars
+                .iter()
+                .map(|(name, default)| TemplateVar {
+                    name: (*name).to_owned(),
+                    default: default.map(str::to_owned),
+                })
+                .collect(),
+        });
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_event(key_event(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn emit_install_leaves_vars_unasked() {
+        let summaries = vec![summary("a", DefinitionKind::Agent)];
+        let mut app = App::new(summaries, "test".into());
+        app.selected_definition = Some(sample_definition_with_raw("a", "content"));
+        app.install_target = Some(PathBuf::from("/tmp"));
+        app.mode = Mode::InstallConfirm;
+
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(matches!(cmd, AppCommand::Install { vars: None, .. }));
+    }
+
+    #[test]
+    fn needs_vars_opens_form_with_defaults() {
+        let mut app = App::new(vec![], "test".into());
+        needs_vars(&mut app, &[("PROJECT", None), ("LANGUAGE", Some("Rust"))]);
+
+        assert_eq!(app.mode, Mode::InstallVars);
+        let form = app.install_form.as_ref().unwrap();
+        assert_eq!(
+            form.fields,
+            [("PROJECT".into(), String::new()), ("LANGUAGE".into(), "Rust".into())]
+        );
+        assert_eq!(form.cursor, 0);
+    }
+
+    #[test]
+    fn filling_form_emits_install_with_vars() {
+        let mut app = App::new(vec![], "test".into());
+        needs_vars(&mut app, &[("PROJECT", None), ("LANGUAGE", Some("Rust"))]);
+
+        type_text(&mut app, "crate");
+        assert!(matches!(app.handle_event(key_event(KeyCode::Enter)), AppCommand::None));
+        app.handle_event(key_event(KeyCode::Backspace));
+        app.handle_event(key_event(KeyCode::Backspace));
+        type_text(&mut app, "by");
+
+        let AppCommand::Install { id, vars, .. } = app.handle_event(key_event(KeyCode::Enter))
+        else {
+            panic!("expected install");
+        };
+        assert_eq!(id.as_str(), "agents/templated.md");
+        let vars = vars.unwrap();
+        assert_eq!(vars["PROJECT"], "crate");
+        assert_eq!(vars["LANGUAGE"], "Ruby");
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.install_form.is_none());
+    }
+
+    #[test]
+    fn empty_field_blocks_submit() {
+        let mut app = App::new(vec![], "test".into());
+        needs_vars(&mut app, &[("PROJECT", None), ("LANGUAGE", Some("Rust"))]);
+        app.handle_event(key_event(KeyCode::Tab));
+
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::InstallVars);
+        assert_eq!(app.install_form.as_ref().unwrap().cursor, 0);
+        assert!(app.status_message.as_ref().unwrap().is_error);
+    }
+
+    #[test]
+    fn later_forms_remember_values() {
+        let mut app = App::new(vec![], "test".into());
+        needs_vars(&mut app, &[("PROJECT", None)]);
+        type_text(&mut app, "crate");
+        app.handle_event(key_event(KeyCode::Enter));
+
+        needs_vars(&mut app, &[("PROJECT", None), ("OWNER", None)]);
+        let form = app.install_form.as_ref().unwrap();
+        assert_eq!(form.fields[0].1, "crate");
+        assert_eq!(form.fields[1].1, "");
+    }
+
+    #[test]
+    fn esc_cancels_form() {
+        let mut app = App::new(vec![], "test".into());
+        needs_vars(&mut app, &[("PROJECT", None)]);
+
+        let cmd = app.handle_event(key_event(KeyCode::Esc));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.install_form.is_none());
+    }
+
+    // --- Collections ---
+
+    fn app_with_collection() -> App {
+        let summaries = vec![
+            summary("api", DefinitionKind::Agent),
+            summary("db", DefinitionKind::Agent),
+            summary("fmt", DefinitionKind::Hook),
+        ];
+        let mut app = App::new(summaries, "test".into());
+        app.collections = vec![Collection {
+            name: "starter".into(),
+            description: None,
+            items: vec![
+                agent_defs::CollectionItem::new("api"),
+                agent_defs::CollectionItem::new("fmt"),
+                agent_defs::CollectionItem::new("gone"),
+            ],
+        }];
+        app
+    }
+
+    #[test]
+    fn collections_follow_sources_in_source_filter() {
+        let app = app_with_collection();
+        assert_eq!(
+            app.source_filter_options(),
+            [
+                SourceOption::All,
+                SourceOption::Source("test".into()),
+                SourceOption::Collection("starter".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn choosing_collection_filters_to_its_items() {
+        let mut app = app_with_collection();
+        app.handle_event(key_event(KeyCode::Char('p')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Enter));
+
+        assert_eq!(app.collection_filter.as_deref(), Some("starter"));
+        let ids: Vec<_> = app.view_summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["api", "fmt"]);
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert!(app.collection_filter.is_none());
+        assert_eq!(app.view_summaries.len(), 3);
+    }
+
+    #[test]
+    fn choosing_source_clears_collection() {
+        let mut app = app_with_collection();
+        app.collection_filter = Some("starter".into());
+        app.mode = Mode::SourceFilter;
+        app.source_filter_cursor = 1;
+        app.handle_event(key_event(KeyCode::Enter));
+
+        assert_eq!(app.source_filter.as_deref(), Some("test"));
+        assert!(app.collection_filter.is_none());
+    }
+
+    #[test]
+    fn branches_are_a_source_filter_facet() {
+        let mut summaries = vec![summary("plain", DefinitionKind::Agent)];
+        for id in ["main:agents/a.md", "next:agents/a.md", "next:agents/b.md"] {
+            let mut s = summary(id, DefinitionKind::Agent);
+            s.id = DefinitionId::new(id);
+            summaries.push(s);
+        }
+        let mut app = App::new(summaries, "test".into());
+        assert_eq!(
+            app.source_filter_options(),
+            [
+                SourceOption::All,
+                SourceOption::Source("test".into()),
+                SourceOption::Branch("main".into()),
+                SourceOption::Branch("next".into()),
+            ]
+        );
+
+        app.mode = Mode::SourceFilter;
+        app.source_filter_cursor = 3;
+        app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.branch_filter.as_deref(), Some("next"));
+        let ids: Vec<_> = app.view_summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["next:agents/a.md", "next:agents/b.md"]);
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert!(app.branch_filter.is_none());
+        assert_eq!(app.view_summaries.len(), 4);
+    }
+
+    #[test]
+    fn install_collection_needs_active_collection() {
+        let mut app = app_with_collection();
+        let cmd = app.handle_event(shift_key_event(KeyCode::Char('A')));
+        assert!(matches!(cmd, AppCommand::None));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(app.status_message.as_ref().unwrap().is_error);
+    }
+
+    #[test]
+    fn confirming_collection_install_emits_resolved_ids() {
+        let mut app = app_with_collection();
+        app.collection_filter = Some("starter".into());
+        app.handle_event(shift_key_event(KeyCode::Char('A')));
+        assert_eq!(app.mode, Mode::InstallPrompt);
+        assert!(app.installing_collection);
+
+        app.handle_event(key_event(KeyCode::Char('i')));
+        assert_eq!(app.mode, Mode::InstallConfirm);
+        assert!(app.pending_install_path.is_none());
+
+        // `fmt` is a hook, so Enter alone doesn't install the collection.
+        assert!(app.install_runs_commands());
+        assert!(matches!(app.handle_event(key_event(KeyCode::Enter)), AppCommand::None));
+        let AppCommand::InstallMany {
+            label, ids, total, ..
+        } = app.handle_event(shift_key_event(KeyCode::Char('Y')))
+        else {
+            panic!("expected a collection install");
+        };
+        assert_eq!(label, "starter");
+        let ids: Vec<_> = ids.iter().map(DefinitionId::as_str).collect();
+        assert_eq!(ids, ["api", "fmt"]);
+        assert_eq!(total, 3);
+        assert!(!app.installing_collection);
+    }
+
+    #[test]
+    fn shared_names_are_qualified_only_while_both_are_visible() {
+        let mut other = summary("reviewer", DefinitionKind::Agent);
+        other.source_label = "other".into();
+        let mut app = App::new(vec![summary("reviewer", DefinitionKind::Agent), other], "all".into());
+        assert_eq!(app.display_names.get(&app.view_summaries[1]), "reviewer (other)");
+
+        app.source_filter = Some("other".into());
+        app.recompute_view();
+        assert_eq!(app.display_names.get(&app.view_summaries[0]), "reviewer");
+    }
+
+    // --- Most used ---
+
+    fn used(id: &str, views: u64) -> DefinitionUsage {
+        DefinitionUsage {
+            id: DefinitionId::new(id),
+            name: id.to_owned(),
+            source_label: "test".into(),
+            views,
+            installs: 0,
+            last_used: 0,
+        }
+    }
+
+    #[test]
+    fn most_used_appears_only_with_usage() {
+        let mut app = app_with_collection();
+        assert!(!app.source_filter_options().contains(&SourceOption::MostUsed));
+
+        app.usage = vec![used("db", 3)];
+        assert_eq!(app.source_filter_options()[1], SourceOption::MostUsed);
+    }
+
+    #[test]
+    fn choosing_most_used_filters_to_used_definitions() {
+        let mut app = app_with_collection();
+        app.usage = vec![used("db", 3), used("removed", 1)];
+        app.collection_filter = Some("starter".into());
+        app.handle_event(key_event(KeyCode::Char('p')));
+        app.handle_event(key_event(KeyCode::Char('j')));
+        app.handle_event(key_event(KeyCode::Enter));
+
+        assert!(app.most_used_filter);
+        assert!(app.collection_filter.is_none());
+        assert_eq!(app.most_used_count(), 1);
+        let ids: Vec<_> = app.view_summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["db"]);
+
+        app.handle_event(key_event(KeyCode::Esc));
+        assert!(!app.most_used_filter);
+        assert_eq!(app.view_summaries.len(), 3);
+    }
+
+    #[test]
+    fn zooming_records_a_view() {
+        let mut app = App::new(vec![summary("a", DefinitionKind::Agent)], "test".into());
+        app.handle_action(Action::DefinitionLoaded(
+            DefinitionId::new("test:a"),
+            Box::new(Ok(sample_definition("a"))),
+        ));
+
+        let cmd = app.handle_event(key_event(KeyCode::Char('z')));
+        assert!(matches!(cmd, AppCommand::RecordUsage(id, UsageKind::View) if id.as_str() == "a"));
+    }
+
+    // --- Notes ---
+
+    fn app_with_loaded(name: &str) -> App {
+        let mut app = App::new(vec![summary(name, DefinitionKind::Agent)], "test".into());
+        let def = sample_definition(name);
+        app.handle_action(Action::DefinitionLoaded(
+            def.qualified_id(),
+            Box::new(Ok(def)),
+        ));
+        app
+    }
+
+    #[test]
+    fn note_editor_saves_and_removes_notes() {
+        let mut app = app_with_loaded("a");
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.mode, Mode::NoteEditor);
+        type_text(&mut app, "handy ");
+
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert_eq!(app.mode, Mode::Normal);
+        assert!(matches!(&cmd, AppCommand::SaveNote(note) if note.text == "handy"));
+        assert_eq!(app.selected_note().unwrap().text, "handy");
+
+        // Reopening starts from the saved note; clearing it removes the note.
+        app.handle_event(key_event(KeyCode::Char('n')));
+        assert_eq!(app.note_draft, "handy");
+        app.handle_event(ctrl_key_event('u'));
+        let cmd = app.handle_event(key_event(KeyCode::Enter));
+        assert!(matches!(&cmd, AppCommand::SaveNote(note) if note.text.is_empty()));
+        assert!(app.selected_note().is_none());
+    }
+
+    #[test]
+    fn escape_discards_the_note_draft() {
+        let mut app = app_with_loaded("a");
+        app.handle_event(key_event(KeyCode::Char('n')));
+        type_text(&mut app, "nope");
+        let cmd = app.handle_event(key_event(KeyCode::Esc));
+        assert!(matches!(cmd, AppCommand::None));
+        assert!(app.notes.is_empty());
+    }
+
+    #[test]
+    fn search_matches_note_text() {
+        let mut app = App::new(
+            vec![summary("alpha", DefinitionKind::Agent), summary("beta", DefinitionKind::Agent)],
+            "test".into(),
+        );
+        app.notes = vec![DefinitionNote {
+            id: DefinitionId::new("beta"),
+            source_label: "test".into(),
+            text: "Use for Terraform".into(),
+            updated_at: 0,
+        }];
+        app.handle_event(key_event(KeyCode::Char('/')));
+        type_text(&mut app, "terraform");
+
+        let ids: Vec<_> = app.view_summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["beta"]);
+    }
+
+    #[test]
+    fn search_words_match_independently() {
+        let mut reviewer = summary("code-reviewer", DefinitionKind::Agent);
+        reviewer.description = Some("Reviews pull requests".into());
+        let mut legacy = summary("legacy-reviewer", DefinitionKind::Agent);
+        legacy.description = Some("Deprecated code review".into());
+        let mut app = App::new(vec![reviewer, legacy], "test".into());
+        let search = |app: &mut App, query: &str| -> Vec<String> {
+            app.handle_event(key_event(KeyCode::Esc));
+            app.handle_event(key_event(KeyCode::Char('/')));
+            type_text(app, query);
+            app.view_summaries.iter().map(|s| s.name.clone()).collect()
+        };
+
+        assert_eq!(search(&mut app, "review code"), ["code-reviewer", "legacy-reviewer"]);
+        assert_eq!(search(&mut app, "\"code review\""), ["legacy-reviewer"]);
+        assert_eq!(search(&mut app, "reviewer !deprecated"), ["code-reviewer"]);
+    }
+
+    // --- Hiding ---
+
+    #[test]
+    fn hiding_removes_the_item_and_keeps_the_cursor_in_place() {
+        let mut app = App::new(
+            vec![
+                summary("alpha", DefinitionKind::Agent),
+                summary("beta", DefinitionKind::Agent),
+                summary("gamma", DefinitionKind::Agent),
+            ],
+            "test".into(),
+        );
+        app.handle_event(key_event(KeyCode::Char('j')));
+        assert_eq!(app.selected_summary().unwrap().name, "beta");
+
+        let cmd = app.handle_event(key_event(KeyCode::Char('x')));
+        assert!(matches!(cmd, AppCommand::Hide(HideRule::Id(id)) if id.as_str() == "beta"));
+        let names: Vec<_> = app.view_summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "gamma"]);
+        assert_eq!(app.selected_summary().unwrap().name, "gamma");
+
+        let cmd = app.handle_action(Action::HideSaved(Ok(())));
+        assert!(matches!(cmd, AppCommand::FetchDefinition(id) if id.as_str() == "test:gamma"));
+    }
+
+    #[test]
+    fn hide_rules_filter_the_initial_list() {
+        let mut app = App::new(
+            vec![summary("alpha", DefinitionKind::Agent), summary("beta", DefinitionKind::Agent)],
+            "test".into(),
+        );
+        assert_eq!(app.pending_fetch.as_ref().unwrap().as_str(), "test:alpha");
+
+        app.set_hidden(HideRules::parse("glob:al*"));
+        assert_eq!(app.view_summaries.len(), 1);
+        assert_eq!(app.pending_fetch.as_ref().unwrap().as_str(), "test:beta");
+    }
 }
@@ -0,0 +1,164 @@
+//! Copying text out of the TUI.
+//!
+//! The system clipboard is tried first. When there isn't one to reach (a
+//! headless box, an SSH session, a missing display server) the text goes out
+//! as an OSC 52 escape sequence instead, but only to terminals that are
+//! likely to act on it: OSC 52 has no acknowledgement, so sending it to a
+//! terminal that ignores it would report a copy that never happened.
+
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, OnceLock};
+
+/// How a copy reached the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardPath {
+    /// The platform clipboard, through arboard.
+    Native,
+    /// An OSC 52 escape sequence written to the terminal.
+    Osc52,
+}
+
+impl ClipboardPath {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Native => "system clipboard",
+            Self::Osc52 => "terminal (OSC 52)",
+        }
+    }
+}
+
+/// Copy `text`, reporting which path carried it.
+pub fn copy(text: &str) -> Result<ClipboardPath, String> {
+    let native_error = match copy_native(text) {
+        Ok(()) => return Ok(ClipboardPath::Native),
+        Err(e) => e,
+    };
+    if !std::io::stdout().is_terminal() {
+        return Err(format!("{native_error}, and stdout is not a terminal"));
+    }
+    if let Some(terminal) = osc52_unsupported(|name| std::env::var(name).ok()) {
+        return Err(format!("{native_error}, and {terminal} doesn't support OSC 52"));
+    }
+    copy_via_osc52(text)?;
+    Ok(ClipboardPath::Osc52)
+}
+
+/// On X11 the copied text is served by the process that owns the clipboard,
+/// so the handle is kept for the life of the app rather than dropped after
+/// each copy.
+fn copy_native(text: &str) -> Result<(), String> {
+    static CLIPBOARD: OnceLock<Mutex<Option<arboard::Clipboard>>> = OnceLock::new();
+    let mut guard = CLIPBOARD
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|_| "clipboard lock poisoned".to_owned())?;
+    if guard.is_none() {
+        let clipboard =
+            arboard::Clipboard::new().map_err(|e| format!("No system clipboard ({e})"))?;
+        *guard = Some(clipboard);
+    }
+    let clipboard = guard.as_mut().expect("clipboard was just set");
+    clipboard
+        .set_text(text)
+        .map_err(|e| format!("System clipboard refused the copy ({e})"))
+}
+
+/// The terminal's name when it's known to ignore OSC 52, read through `env`
+/// so tests can supply their own environment.
+fn osc52_unsupported(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    const PROGRAMS: &[&str] = &["Apple_Terminal"];
+    const TERMS: &[&str] = &["dumb", "linux", "cons25", "emacs"];
+
+    if let Some(program) = env("TERM_PROGRAM")
+        && PROGRAMS.contains(&program.as_str())
+    {
+        return Some(program);
+    }
+    match env("TERM") {
+        Some(term) if TERMS.contains(&term.as_str()) => Some(format!("TERM={term}")),
+        None if env("WT_SESSION").is_none() => Some("a terminal without TERM".into()),
+        _ => None,
+    }
+}
+
+/// OSC 52 escape sequence, which works in most modern terminals and over SSH.
+fn copy_via_osc52(text: &str) -> Result<(), String> {
+    let encoded = base64_encode(text.as_bytes());
+    let sequence = format!("\x1b]52;c;{encoded}\x07");
+    let mut stdout = std::io::stdout();
+    stdout
+        .write_all(sequence.as_bytes())
+        .map_err(|e| format!("Failed to write clipboard escape: {e}"))?;
+    stdout.flush().map_err(|e| format!("Failed to flush: {e}"))
+}
+
+/// Minimal base64 encoding (no external dep needed for this).
+fn base64_encode(input: &[u8]) -> String {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::new();
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        output.push(CHARS[((triple >> 18) & 0x3F) as usize] as char);
+        output.push(CHARS[((triple >> 12) & 0x3F) as usize] as char);
+
+        if chunk.len() > 1 {
+            output.push(CHARS[((triple >> 6) & 0x3F) as usize] as char);
+        } else {
+            output.push('=');
+        }
+
+        if chunk.len() > 2 {
+            output.push(CHARS[(triple & 0x3F) as usize] as char);
+        } else {
+            output.push('=');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn osc52_is_skipped_for_terminals_that_ignore_it() {
+        assert_eq!(
+            osc52_unsupported(env(&[("TERM_PROGRAM", "Apple_Terminal")])),
+            Some("Apple_Terminal".into())
+        );
+        assert_eq!(
+            osc52_unsupported(env(&[("TERM", "linux")])),
+            Some("TERM=linux".into())
+        );
+        assert!(osc52_unsupported(env(&[])).is_some());
+
+        assert_eq!(osc52_unsupported(env(&[("TERM", "xterm-256color")])), None);
+        assert_eq!(
+            osc52_unsupported(env(&[("TERM_PROGRAM", "WezTerm"), ("TERM", "xterm")])),
+            None
+        );
+        assert_eq!(osc52_unsupported(env(&[("WT_SESSION", "1")])), None);
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"hello, world"), "aGVsbG8sIHdvcmxk");
+    }
+}
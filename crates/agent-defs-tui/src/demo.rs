@@ -0,0 +1,345 @@
+//! Scripted sessions: a demo script plays keys into the TUI on a timer, for
+//! recording the same session every time or smoke-testing the whole stack.
+//!
+//! A script has one step per line; blank lines and lines starting with `#`
+//! are skipped:
+//!
+//! ```text
+//! delay 80ms         # time between keys from here on (default 150ms)
+//! pause 1s           # wait before the next step
+//! type /review       # press each character in turn
+//! press down 2       # press a key, optionally more than once
+//! press ctrl+u
+//! expect Installed   # wait up to 10s for text on screen, or fail
+//! ```
+//!
+//! The session quits once the last step has run.
+
+use std::path::Path;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use tokio::time::{Duration, Instant};
+
+/// Time between keys until a `delay` step says otherwise.
+const DEFAULT_DELAY: Duration = Duration::from_millis(150);
+
+/// How long `expect` waits for its text.
+const EXPECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One thing a script does, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DemoStep {
+    /// Send a key, then wait the current delay.
+    Key(KeyEvent),
+    /// Wait this long before the next step.
+    Pause(Duration),
+    /// Change the wait after each key.
+    Delay(Duration),
+    /// Wait for this text to be on screen.
+    Expect(String),
+}
+
+/// A parsed demo script: its steps, each with the line it came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DemoScript {
+    pub steps: Vec<(usize, DemoStep)>,
+}
+
+impl DemoScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut steps = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let fail = |message: String| anyhow::anyhow!("line {number}: {message}");
+            match command {
+                "type" if !rest.is_empty() => {
+                    // The rest of the line as written, so a script can type
+                    // spaces or a `#`.
+                    let text = line[command.len()..].trim_start();
+                    for c in text.chars() {
+                        steps.push((
+                            number,
+                            DemoStep::Key(key(KeyCode::Char(c), KeyModifiers::NONE)),
+                        ));
+                    }
+                }
+                "press" => {
+                    let mut words = rest.split_whitespace();
+                    let name = words
+                        .next()
+                        .ok_or_else(|| fail("press needs a key".into()))?;
+                    let key =
+                        parse_key(name).ok_or_else(|| fail(format!("unknown key {name:?}")))?;
+                    let times = match words.next() {
+                        Some(count) => count
+                            .parse::<usize>()
+                            .map_err(|_| fail(format!("{count:?} isn't a number of presses")))?,
+                        None => 1,
+                    };
+                    steps.extend(std::iter::repeat_n((number, DemoStep::Key(key)), times));
+                }
+                "pause" | "delay" => {
+                    let duration = parse_duration(rest).ok_or_else(|| {
+                        fail(format!("{rest:?} isn't a duration like 500ms or 2s"))
+                    })?;
+                    let step = if command == "pause" {
+                        DemoStep::Pause(duration)
+                    } else {
+                        DemoStep::Delay(duration)
+                    };
+                    steps.push((number, step));
+                }
+                "expect" if !rest.is_empty() => {
+                    steps.push((number, DemoStep::Expect(rest.to_owned())))
+                }
+                "type" | "expect" => return Err(fail(format!("{command} needs some text"))),
+                _ => return Err(fail(format!("unknown step {command:?}"))),
+            }
+        }
+        Ok(Self { steps })
+    }
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent {
+        code,
+        modifiers,
+        kind: KeyEventKind::Press,
+        state: KeyEventState::NONE,
+    }
+}
+
+/// A key like `j`, `enter`, `pagedown`, `f5` or `ctrl+u`.
+fn parse_key(name: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        rest = key;
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            other => KeyCode::F(other.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some(key(code, modifiers))
+}
+
+/// `500ms`, `2s` or `1.5s`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    if let Some(ms) = text.strip_suffix("ms") {
+        return ms.parse().ok().map(Duration::from_millis);
+    }
+    let secs: f64 = text.strip_suffix('s')?.parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Plays a script into a running session: the event loop asks when the
+/// next step is due, runs it, and shows it each frame it draws so an
+/// `expect` can see the screen.
+pub(crate) struct DemoPlayer {
+    steps: std::vec::IntoIter<(usize, DemoStep)>,
+    /// The `expect` being waited on, and its line.
+    waiting: Option<(usize, String)>,
+    delay: Duration,
+    due: Instant,
+}
+
+/// What the event loop should do when a step comes due.
+pub(crate) enum DemoCue {
+    Event(Event),
+    /// Nothing to send; ask again at the new due time.
+    Wait,
+    /// The script has finished.
+    Done,
+}
+
+impl DemoPlayer {
+    pub(crate) fn new(script: DemoScript) -> Self {
+        Self {
+            steps: script.steps.into_iter(),
+            waiting: None,
+            delay: DEFAULT_DELAY,
+            due: Instant::now() + DEFAULT_DELAY,
+        }
+    }
+
+    /// When the next step runs, or an `expect` gives up.
+    pub(crate) fn due(&self) -> Instant {
+        self.due
+    }
+
+    /// Whether a step is waiting on the screen, so the frame should be shown.
+    pub(crate) fn is_waiting(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    /// Show the player the screen just drawn.
+    pub(crate) fn observe(&mut self, screen: &str) {
+        if self
+            .waiting
+            .as_ref()
+            .is_some_and(|(_, text)| screen.contains(text.as_str()))
+        {
+            self.waiting = None;
+            self.due = Instant::now();
+        }
+    }
+
+    /// Run the step that's due.
+    pub(crate) fn next(&mut self) -> anyhow::Result<DemoCue> {
+        if let Some((line, text)) = self.waiting.take() {
+            anyhow::bail!(
+                "demo script line {line}: {text:?} didn't appear within {EXPECT_TIMEOUT:?}"
+            );
+        }
+        let now = Instant::now();
+        let Some((line, step)) = self.steps.next() else {
+            return Ok(DemoCue::Done);
+        };
+        Ok(match step {
+            DemoStep::Key(key) => {
+                self.due = now + self.delay;
+                DemoCue::Event(Event::Key(key))
+            }
+            DemoStep::Pause(duration) => {
+                self.due = now + duration;
+                DemoCue::Wait
+            }
+            DemoStep::Delay(duration) => {
+                self.delay = duration;
+                self.due = now;
+                DemoCue::Wait
+            }
+            DemoStep::Expect(text) => {
+                self.waiting = Some((line, text));
+                self.due = now + EXPECT_TIMEOUT;
+                DemoCue::Wait
+            }
+        })
+    }
+}
+
+/// The text of a drawn frame, row by row.
+pub(crate) fn screen_text(buffer: &ratatui::buffer::Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            text.push_str(buffer[(x, y)].symbol());
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_kind_of_step() {
+        let script = DemoScript::parse(
+            "# Install the reviewer.\n\ndelay 80ms\ntype /re v\npress ctrl+u\npress j 2\n\
+             pause 1.5s\nexpect Installed\n",
+        )
+        .unwrap();
+        let plain = |c| DemoStep::Key(key(KeyCode::Char(c), KeyModifiers::NONE));
+        let steps: Vec<DemoStep> = script.steps.iter().map(|(_, step)| step.clone()).collect();
+        assert_eq!(
+            steps,
+            [
+                DemoStep::Delay(Duration::from_millis(80)),
+                plain('/'),
+                plain('r'),
+                plain('e'),
+                plain(' '),
+                plain('v'),
+                DemoStep::Key(key(KeyCode::Char('u'), KeyModifiers::CONTROL)),
+                plain('j'),
+                plain('j'),
+                DemoStep::Pause(Duration::from_millis(1500)),
+                DemoStep::Expect("Installed".into()),
+            ]
+        );
+        assert_eq!(script.steps[1].0, 4);
+    }
+
+    #[test]
+    fn names_keys() {
+        assert_eq!(parse_key("pagedown").unwrap().code, KeyCode::PageDown);
+        assert_eq!(parse_key("F5").unwrap().code, KeyCode::F(5));
+        assert_eq!(parse_key("+").unwrap().code, KeyCode::Char('+'));
+        let shifted = parse_key("shift+tab").unwrap();
+        assert_eq!(
+            (shifted.code, shifted.modifiers),
+            (KeyCode::Tab, KeyModifiers::SHIFT)
+        );
+        assert_eq!(parse_key("hyper+x"), None);
+        assert_eq!(parse_key("fn"), None);
+    }
+
+    #[test]
+    fn expect_waits_for_its_text() {
+        let mut player = DemoPlayer::new(DemoScript::parse("expect Installed\npress q").unwrap());
+        assert!(matches!(player.next().unwrap(), DemoCue::Wait));
+        player.observe("Installing reviewer");
+        assert!(player.is_waiting());
+        player.observe("Installed reviewer to /tmp/demo");
+        assert!(!player.is_waiting());
+        assert!(matches!(player.next().unwrap(), DemoCue::Event(_)));
+        assert!(matches!(player.next().unwrap(), DemoCue::Done));
+
+        // Coming due while still waiting means the text never showed.
+        let mut player = DemoPlayer::new(DemoScript::parse("\nexpect Saved").unwrap());
+        player.next().unwrap();
+        let error = player.next().err().unwrap().to_string();
+        assert!(error.starts_with("demo script line 2: \"Saved\" didn't appear"), "{error}");
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = DemoScript::parse("press down\npress sideways\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown key \"sideways\"");
+        let error = DemoScript::parse("pause soon").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: \"soon\" isn't a duration like 500ms or 2s"
+        );
+        assert!(DemoScript::parse("dance").is_err());
+    }
+}
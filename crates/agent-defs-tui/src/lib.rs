@@ -1,123 +1,363 @@
 pub mod action;
 pub mod app;
-pub mod grouping;
+pub mod clipboard;
+pub mod demo;
 mod render;
+pub mod sync;
 
-use std::future::Future;
-use std::pin::Pin;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use agent_defs::Source;
+use agent_defs::{
+    Collection, DefinitionChange, DefinitionId, DefinitionNote, DefinitionUsage, GlyphSet, HideRule,
+    HideRules, InstallRules, ListDensity, Policy, Rating, SkippedFile, Source, UsageKind, template,
+};
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyEventKind};
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
 use crossterm::ExecutableCommand;
-use ratatui::backend::CrosstermBackend;
+use ratatui::backend::{Backend, CrosstermBackend};
 use ratatui::Terminal;
 use tokio::sync::mpsc;
-use tokio::time::{Duration, interval};
+use tokio::task::AbortHandle;
+use tokio::time::{Duration, Instant, MissedTickBehavior, interval, sleep_until};
 
-use crate::action::{Action, AppCommand};
+use crate::action::{Action, AppCommand, ExternalTool};
 use crate::app::App;
+use crate::demo::{DemoCue, DemoPlayer, DemoScript};
+pub use crate::sync::{
+    Freshness, SourceReport, SyncController, SyncEvent, SyncFailure, SyncResult, SyncSelection,
+    SyncTarget,
+};
+
+/// Callback the host provides to count views and installs toward the
+/// user's local usage stats.
+pub type UsageFn = Arc<dyn Fn(&DefinitionId, UsageKind) + Send + Sync>;
+
+/// Callback the host provides to save notes written in the TUI. A note
+/// with empty text should be removed.
+pub type NoteFn = Arc<dyn Fn(&DefinitionNote) + Send + Sync>;
+
+/// Callback the host provides to persist a hide rule added in the TUI.
+pub type HideFn = Arc<dyn Fn(&HideRule) -> anyhow::Result<()> + Send + Sync>;
 
-/// Result of a sync operation.
-#[derive(Debug, Clone)]
-pub struct SyncResult {
-    /// Summary message (e.g., "Synced 50 definitions (5 skipped)").
-    pub message: String,
-    /// Warnings/errors encountered during sync.
-    pub warnings: Vec<String>,
+/// Host-provided settings for a TUI session.
+#[derive(Clone, Default)]
+pub struct TuiOptions {
+    /// Directory the installer opens at (defaults to the working directory),
+    /// and where `I` installs without asking for a directory.
+    pub install_target: Option<std::path::PathBuf>,
+    /// Install with `I` without a confirmation, except for hooks.
+    pub skip_quick_install_confirm: bool,
+    /// Glyphs shown next to kinds.
+    pub glyphs: GlyphSet,
+    /// How much each list row shows, when not the compact default.
+    pub list_density: Option<ListDensity>,
+    /// Collections offered alongside sources in the source filter.
+    pub collections: Vec<Collection>,
+    /// Recent changelog entries, newest first, for the "What's new" overlay.
+    pub changes: Vec<DefinitionChange>,
+    /// Recorded usage, most used first, for the "Most used" filter.
+    pub usage: Vec<DefinitionUsage>,
+    /// Where to record views and installs; `None` records nothing.
+    pub on_usage: Option<UsageFn>,
+    /// The user's notes, shown in the detail pane and matched by search.
+    pub notes: Vec<DefinitionNote>,
+    /// Where to save notes; `None` keeps them for this session only.
+    pub on_note: Option<NoteFn>,
+    /// Community ratings, shown in the list and the detail pane.
+    pub ratings: Vec<Rating>,
+    /// Definitions to leave out of the list.
+    pub hidden: HideRules,
+    /// Where to save hide rules; `None` hides for this session only.
+    pub on_hide: Option<HideFn>,
+    /// Files the latest sync of each source skipped, for the warnings
+    /// overlay.
+    pub skipped: Vec<SkippedFile>,
+    /// The install layout, and the org policy installs are checked against.
+    pub rules: InstallRules,
+    /// Keys to play into the session instead of waiting on the user; the
+    /// session quits when the script ends.
+    pub demo_script: Option<DemoScript>,
+    /// Time between ticks while a status message or countdown is showing;
+    /// `None` for [`DEFAULT_TICK_RATE`].
+    pub tick_rate: Option<Duration>,
 }
 
-/// Callback the host provides to trigger a sync.
-pub type SyncFn = Box<
-    dyn Fn() -> Pin<Box<dyn Future<Output = anyhow::Result<SyncResult>> + Send>> + Send + Sync,
->;
+/// Input for a session: the terminal's own events, or events a test
+/// sends through a channel.
+pub trait EventSource: futures::Stream<Item = std::io::Result<Event>> + Unpin {
+    /// Stop reading input while another program has the terminal.
+    fn release(&mut self) {}
+}
+
+impl EventSource for EventStream {
+    fn release(&mut self) {
+        // Dropping the stream stops it reading keys meant for the child.
+        *self = EventStream::new();
+    }
+}
+
+impl EventSource for futures::channel::mpsc::UnboundedReceiver<std::io::Result<Event>> {}
 
 /// Launch the interactive TUI. Returns when the user quits.
 pub async fn run(
     source: Arc<dyn Source>,
-    on_sync: SyncFn,
-    install_target: Option<std::path::PathBuf>,
+    sync: Arc<dyn SyncController>,
+    options: TuiOptions,
 ) -> anyhow::Result<()> {
     // Load initial data.
     let label = source.label().to_owned();
-    let summaries = source
-        .list()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to load definitions: {e}"))?;
+    let summaries = load_summaries(source.as_ref()).await?;
 
-    // Terminal setup.
-    enable_raw_mode()?;
-    let mut stdout = std::io::stdout();
-    stdout.execute(EnterAlternateScreen)?;
-    stdout.execute(EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // Terminal setup. A panic in the session itself restores the terminal
+    // before the message prints, so it isn't lost to raw mode. Panics in
+    // background tasks run on other threads and are reported as failed
+    // tasks while the session carries on, so those leave the screen alone.
+    let session_thread = std::thread::current().id();
+    let previous_hook: Arc<dyn Fn(&std::panic::PanicHookInfo<'_>) + Send + Sync> =
+        Arc::from(std::panic::take_hook());
+    let hook = Arc::clone(&previous_hook);
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().id() == session_thread {
+            restore_terminal();
+        }
+        hook(info);
+    }));
 
-    let result =
-        run_event_loop(&mut terminal, source, on_sync, summaries, label, install_target).await;
+    let result = async {
+        enable_raw_mode()?;
+        let mut stdout = std::io::stdout();
+        stdout.execute(EnterAlternateScreen)?;
+        stdout.execute(EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        let events = EventStream::new();
+        run_event_loop(&mut terminal, events, source, sync, summaries, label, options).await
+    }
+    .await;
 
     // Terminal teardown (always runs).
-    disable_raw_mode()?;
-    std::io::stdout().execute(DisableMouseCapture)?;
-    std::io::stdout().execute(LeaveAlternateScreen)?;
+    restore_terminal();
+    let _ = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| previous_hook(info)));
 
     result
 }
 
-async fn run_event_loop(
-    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+/// Run a session on `terminal`, reading input from `events`, without
+/// touching the real terminal. With a [`ratatui::backend::TestBackend`]
+/// this drives whole sessions headlessly, for tests. Returns when the
+/// session quits.
+pub async fn run_with<B: Backend>(
+    terminal: &mut Terminal<B>,
+    events: impl EventSource,
+    source: Arc<dyn Source>,
+    sync: Arc<dyn SyncController>,
+    options: TuiOptions,
+) -> anyhow::Result<()> {
+    let label = source.label().to_owned();
+    let summaries = load_summaries(source.as_ref()).await?;
+    run_event_loop(terminal, events, source, sync, summaries, label, options).await
+}
+
+async fn load_summaries(source: &dyn Source) -> anyhow::Result<Vec<agent_defs::DefinitionSummary>> {
+    source
+        .list()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to load definitions: {e}"))
+}
+
+/// Put the terminal back the way the shell expects it. Each step runs even
+/// if an earlier one fails, since this is also the last thing a panic does.
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let mut stdout = std::io::stdout();
+    let _ = stdout.execute(DisableMouseCapture);
+    let _ = stdout.execute(LeaveAlternateScreen);
+    let _ = stdout.execute(crossterm::cursor::Show);
+}
+
+/// Resolves when the process is asked to stop: SIGINT (which raw mode
+/// only delivers when sent from outside, e.g. `kill -INT`) or, on Unix,
+/// SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Time between ticks unless the host sets one.
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(250);
+
+/// The fastest the loop will tick, however low the host sets the rate.
+const MIN_TICK_RATE: Duration = Duration::from_millis(10);
+
+/// How long the cursor rests on a definition before it's fetched, so
+/// holding `j` doesn't fetch every row it passes.
+const FETCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// How many frames in a row may fail to draw before the session gives up.
+/// A single failure (a resize racing the draw, a briefly unwritable tty)
+/// shouldn't end the session.
+const MAX_DRAW_FAILURES: u32 = 5;
+
+async fn run_event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut events: impl EventSource,
     source: Arc<dyn Source>,
-    on_sync: SyncFn,
+    sync: Arc<dyn SyncController>,
     summaries: Vec<agent_defs::DefinitionSummary>,
     label: String,
-    install_target: Option<std::path::PathBuf>,
+    options: TuiOptions,
 ) -> anyhow::Result<()> {
     use futures::StreamExt;
 
-    let mut app = App::with_install_target(summaries, label, install_target);
+    let mut app = App::with_install_target(summaries, label, options.install_target);
+    app.quick_install_confirm = !options.skip_quick_install_confirm;
+    app.glyphs = options.glyphs;
+    app.list_density = options.list_density.unwrap_or(ListDensity::Compact);
+    app.collections = options.collections;
+    app.changes = options.changes;
+    app.usage = options.usage;
+    app.notes = options.notes;
+    app.ratings = options.ratings;
+    app.set_skipped(options.skipped);
+    app.set_sync_sources(source_freshness(sync.as_ref()));
+    app.set_hidden(options.hidden);
+    let on_usage = options.on_usage;
+    let on_note = options.on_note;
+    let on_hide = options.on_hide;
+    app.install_layout = options.rules.layout;
+    let rules = Arc::new(options.rules);
+    let mut demo = options.demo_script.map(DemoPlayer::new);
+    app.tick_rate = options.tick_rate.unwrap_or(DEFAULT_TICK_RATE).max(MIN_TICK_RATE);
 
     let (action_tx, mut action_rx) = mpsc::channel::<Action>(32);
-    let mut event_stream = EventStream::new();
-    let mut tick_interval = interval(Duration::from_millis(250));
+    let mut tick_interval = interval(app.tick_rate);
+    tick_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-    // Handle initial fetch if app requested one.
-    process_initial_fetch(&app, &source, &action_tx);
+    // Handle initial fetch if app requested one. Only the latest detail
+    // fetch is kept running: moving the cursor drops the one before, so a
+    // slow request for a definition nobody is looking at can't linger.
+    let mut detail_fetch = process_initial_fetch(&app, &source, &action_tx);
+    // The fetch waiting for the cursor to rest, and when it may start.
+    let mut queued_fetch: Option<(DefinitionId, Instant)> = None;
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut draw_failures = 0;
+    // Frames are drawn only after something that can change the screen, so
+    // an idle session doesn't redraw.
+    let mut redraw = true;
+    let mut ticking = false;
 
     loop {
-        // Compute layout geometry for mouse hit testing before render.
-        let size = terminal.size()?;
-        let frame_rect = ratatui::layout::Rect::new(0, 0, size.width, size.height);
-        app.layout_geometry = render::compute_layout(frame_rect, &app);
+        // Compute layout geometry for mouse hit testing before render, then
+        // render. A failed frame is retried on the next tick with a full
+        // redraw rather than ending the session.
+        if redraw {
+            let drawn = terminal.size().and_then(|size| {
+                let frame_rect = ratatui::layout::Rect::new(0, 0, size.width, size.height);
+                app.layout_geometry = render::compute_layout(frame_rect, &app);
+                let frame = terminal.draw(|frame| render::render(frame, &app))?;
+                if let Some(demo) = demo.as_mut().filter(|demo| demo.is_waiting()) {
+                    demo.observe(&demo::screen_text(frame.buffer));
+                }
+                Ok(())
+            });
+            match drawn {
+                Ok(()) => {
+                    draw_failures = 0;
+                    redraw = false;
+                }
+                Err(e) => {
+                    draw_failures += 1;
+                    if draw_failures >= MAX_DRAW_FAILURES {
+                        return Err(anyhow::anyhow!("failed to draw the screen: {e}"));
+                    }
+                    let _ = terminal.clear();
+                }
+            }
+        }
 
-        // Render.
-        terminal.draw(|frame| render::render(frame, &app))?;
+        // Tick only while something counts down. Starting again waits a
+        // whole tick, so a new status message gets its full time.
+        let needs_ticks = app.needs_ticks() || redraw;
+        if needs_ticks && !ticking {
+            tick_interval.reset();
+        }
+        ticking = needs_ticks;
 
         // Wait for next event.
         let command = tokio::select! {
-            Some(event_result) = event_stream.next() => {
+            Some(event_result) = events.next() => {
                 match event_result {
                     Ok(event) => {
                         // Filter events: only key press (not release/repeat) and mouse events.
                         match &event {
                             Event::Key(key) if key.kind != KeyEventKind::Press => continue,
                             Event::Key(_) | Event::Mouse(_) => {}
+                            Event::Resize(..) => {
+                                redraw = true;
+                                continue;
+                            }
                             _ => continue,
                         }
+                        redraw = true;
                         app.handle_event(event)
                     }
                     Err(_) => continue,
                 }
             }
             Some(action) = action_rx.recv() => {
+                redraw = true;
                 app.handle_action(action)
             }
-            _ = tick_interval.tick() => {
-                app.tick();
+            _ = tick_interval.tick(), if ticking => {
+                redraw |= app.tick();
+                AppCommand::None
+            }
+            _ = sleep_until(
+                queued_fetch.as_ref().map_or_else(Instant::now, |(_, at)| *at)
+            ), if queued_fetch.is_some() => {
+                if let Some((id, _)) = queued_fetch.take() {
+                    detail_fetch = Some(spawn_fetch(&source, id, &action_tx));
+                }
                 AppCommand::None
             }
+            cue = next_cue(&mut demo) => match cue? {
+                DemoCue::Event(event) => {
+                    redraw = true;
+                    app.handle_event(event)
+                }
+                DemoCue::Wait => {
+                    // An `expect` only sees frames drawn after it starts,
+                    // and the screen may already show what it wants.
+                    redraw = true;
+                    AppCommand::None
+                }
+                DemoCue::Done => AppCommand::Quit,
+            },
+            _ = &mut shutdown => AppCommand::Quit,
         };
 
         // Execute side effects.
@@ -125,31 +365,55 @@ async fn run_event_loop(
             AppCommand::None => {}
             AppCommand::Quit => break,
             AppCommand::FetchDefinition(id) => {
-                let source = Arc::clone(&source);
+                // Whatever was loading is for a row the cursor has left.
+                if let Some(previous) = detail_fetch.take() {
+                    previous.abort();
+                }
+                queued_fetch = Some((id, Instant::now() + FETCH_DEBOUNCE));
+            }
+            AppCommand::Sync(target) => {
+                tokio::spawn(run_sync(Arc::clone(&sync), target, action_tx.clone()));
+            }
+            AppCommand::DismissSyncOverlay => {
+                // Handled by app state, no external side effect needed.
+            }
+            AppCommand::RecordUsage(id, kind) => {
+                if let Some(on_usage) = on_usage.clone() {
+                    tokio::task::spawn_blocking(move || on_usage(&id, kind));
+                }
+            }
+            AppCommand::SaveNote(note) => {
+                if let Some(on_note) = on_note.clone() {
+                    tokio::task::spawn_blocking(move || on_note(&note));
+                }
+            }
+            AppCommand::Hide(rule) => {
                 let tx = action_tx.clone();
+                let on_hide = on_hide.clone();
                 tokio::spawn(async move {
-                    let result = source
-                        .fetch(&id)
-                        .await
-                        .map_err(|e| format!("{e}"));
-                    let _ = tx.send(Action::DefinitionLoaded(id, Box::new(result))).await;
+                    let result = match on_hide {
+                        Some(on_hide) => tokio::task::spawn_blocking(move || on_hide(&rule))
+                            .await
+                            .unwrap_or_else(|e| Err(anyhow::anyhow!("task panicked: {e}")))
+                            .map_err(|e| e.to_string()),
+                        None => Ok(()),
+                    };
+                    let _ = tx.send(Action::HideSaved(result)).await;
                 });
             }
-            AppCommand::Sync => {
+            AppCommand::OpenExternal(id, tool) => {
+                // The child owns the terminal until it exits.
+                events.release();
+                let result = open_external(terminal, source.as_ref(), &id, tool).await;
                 let tx = action_tx.clone();
-                let future = on_sync();
                 tokio::spawn(async move {
-                    let result = future.await.map_err(|e| e.to_string());
-                    let _ = tx.send(Action::SyncCompleted(result)).await;
+                    let _ = tx.send(Action::ExternalClosed(result)).await;
                 });
             }
-            AppCommand::DismissSyncOverlay => {
-                // Handled by app state, no external side effect needed.
-            }
             AppCommand::CopyBody(body) => {
                 let tx = action_tx.clone();
                 tokio::spawn(async move {
-                    let result = copy_to_clipboard(&body);
+                    let result = clipboard::copy(&body);
                     let _ = tx.send(Action::CopyCompleted(result)).await;
                 });
             }
@@ -161,81 +425,397 @@ async fn run_event_loop(
                     let _ = tx.send(Action::ListReloaded(result)).await;
                 });
             }
-            AppCommand::Install { raw, install_path } => {
+            AppCommand::InstallMany {
+                label,
+                ids,
+                total,
+                target,
+            } => {
+                let source = Arc::clone(&source);
                 let tx = action_tx.clone();
+                let on_usage = on_usage.clone();
+                let rules = Arc::clone(&rules);
                 tokio::spawn(async move {
-                    let result = tokio::task::spawn_blocking(move || {
-                        if let Some(parent) = install_path.parent() {
-                            std::fs::create_dir_all(parent)
-                                .map_err(|e| format!("Failed to create directory: {e}"))?;
-                        }
-                        std::fs::write(&install_path, &raw)
-                            .map_err(|e| format!("Failed to write file: {e}"))?;
-                        Ok(format!("Installed to {}", install_path.display()))
-                    })
-                    .await
-                    .unwrap_or_else(|e| Err(format!("Task panicked: {e}")));
+                    let source = source.as_ref();
+                    let result =
+                        install_many(source, &label, ids, total, &target, &rules, on_usage).await;
                     let _ = tx.send(Action::InstallCompleted(result)).await;
                 });
             }
+            AppCommand::Install {
+                id,
+                install_path,
+                vars,
+            } => {
+                let source = Arc::clone(&source);
+                let tx = action_tx.clone();
+                let on_usage = on_usage.clone();
+                let rules = Arc::clone(&rules);
+                tokio::spawn(async move {
+                    let action =
+                        install(source.as_ref(), id.clone(), install_path, vars, &rules.policy)
+                            .await;
+                    if let (Action::InstallCompleted(Ok(_)), Some(on_usage)) = (&action, on_usage) {
+                        let _ = tokio::task::spawn_blocking(move || {
+                            on_usage(&id, UsageKind::Install)
+                        })
+                        .await;
+                    }
+                    let _ = tx.send(action).await;
+                });
+            }
         }
     }
 
     Ok(())
 }
 
-/// If the app constructor requested a fetch (cursor placed on an item), kick it off.
-fn process_initial_fetch(app: &App, source: &Arc<dyn Source>, tx: &mpsc::Sender<Action>) {
-    if let Some(id) = &app.pending_fetch {
-        let id = id.clone();
-        let source = Arc::clone(source);
-        let tx = tx.clone();
-        tokio::spawn(async move {
-            let result = source.fetch(&id).await.map_err(|e| format!("{e}"));
-            let _ = tx.send(Action::DefinitionLoaded(id, Box::new(result))).await;
-        });
+/// Wait for the demo script's next step; without a script, never.
+async fn next_cue(demo: &mut Option<DemoPlayer>) -> anyhow::Result<DemoCue> {
+    match demo {
+        Some(player) => {
+            sleep_until(player.due()).await;
+            player.next()
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Write `id`'s raw content to a temporary file and open it in the user's
+/// editor or pager, leaving the alternate screen while it runs. The copy is
+/// deleted afterwards; edits to it aren't saved anywhere.
+async fn open_external<B: Backend>(
+    terminal: &mut Terminal<B>,
+    source: &dyn Source,
+    id: &DefinitionId,
+    tool: ExternalTool,
+) -> Result<String, String> {
+    let raw = source
+        .fetch_raw(id)
+        .await
+        .map_err(|e| format!("Failed to load content: {e}"))?;
+    // A fresh, unpredictable name that only this user can open, so nobody
+    // else can plant or read the copy. Dropping `file` deletes it.
+    let file = tempfile::Builder::new()
+        .prefix("agent-defs-")
+        .suffix(&temp_file_suffix(id))
+        .tempfile()
+        .and_then(|mut file| file.write_all(raw.as_bytes()).map(|()| file))
+        .map_err(|e| format!("Failed to write temporary file: {e}"))?;
+    let path = file.path().to_path_buf();
+
+    let (program, args) = external_command(tool, |name| std::env::var(name).ok());
+    let _ = disable_raw_mode();
+    let _ = std::io::stdout().execute(DisableMouseCapture);
+    let _ = std::io::stdout().execute(LeaveAlternateScreen);
+
+    let status = {
+        let program = program.clone();
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            std::process::Command::new(program).args(args).arg(path).status()
+        })
+        .await
+    };
+
+    let _ = enable_raw_mode();
+    let _ = std::io::stdout().execute(EnterAlternateScreen);
+    let _ = std::io::stdout().execute(EnableMouseCapture);
+    let _ = terminal.clear();
+    drop(file);
+
+    match status {
+        Ok(Ok(status)) if status.success() => Ok(match tool {
+            ExternalTool::Editor => format!("Closed {program}; edits to the copy aren't kept"),
+            ExternalTool::Pager => format!("Closed {program}"),
+        }),
+        Ok(Ok(status)) => Err(format!("{program} exited with {status}")),
+        Ok(Err(e)) => Err(format!("Could not run {program}: {e}")),
+        Err(e) => Err(format!("Task panicked: {e}")),
+    }
+}
+
+/// The program and leading arguments for `tool`: `$VISUAL` or `$EDITOR`
+/// (falling back to `vi`) and `$PAGER` (falling back to `less`). Values
+/// like `code --wait` are split on whitespace.
+fn external_command(
+    tool: ExternalTool,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> (String, Vec<String>) {
+    let (vars, fallback): (&[&str], &str) = match (tool, cfg!(windows)) {
+        (ExternalTool::Editor, true) => (&["VISUAL", "EDITOR"], "notepad"),
+        (ExternalTool::Editor, false) => (&["VISUAL", "EDITOR"], "vi"),
+        (ExternalTool::Pager, true) => (&["PAGER"], "more"),
+        (ExternalTool::Pager, false) => (&["PAGER"], "less"),
+    };
+    let command = vars
+        .iter()
+        .filter_map(|var| lookup(var))
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_owned());
+    let mut words = command.split_whitespace().map(str::to_owned);
+    let program = words.next().unwrap_or_else(|| fallback.to_owned());
+    (program, words.collect())
+}
+
+/// The end of the name for `id`'s temporary copy, keeping its extension so
+/// the editor can pick a syntax.
+fn temp_file_suffix(id: &DefinitionId) -> String {
+    let flat: String = id
+        .as_str()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    let flat = if flat.contains('.') { flat } else { format!("{flat}.md") };
+    format!("-{flat}")
+}
+
+/// Load a definition, check it against `policy`, fill in its template
+/// variables, and write it. Comes back asking for values when the definition
+/// declares variables and the user hasn't been asked yet, or left one
+/// without a value.
+async fn install(
+    source: &dyn Source,
+    id: DefinitionId,
+    install_path: PathBuf,
+    vars: Option<HashMap<String, String>>,
+    policy: &Policy,
+) -> Action {
+    let loaded = async {
+        let mut def = source.fetch(&id).await?;
+        if def.raw.is_empty() {
+            def.raw = source.fetch_raw(&id).await?;
+        }
+        Ok::<_, agent_defs::SourceError>(def)
+    };
+    let def = match loaded.await {
+        Ok(def) => def,
+        Err(e) => return Action::InstallCompleted(Err(format!("Failed to load content: {e}"))),
+    };
+    if let Err(e) = policy.check_install(&def) {
+        return Action::InstallCompleted(Err(format!("Not installed: {e}")));
+    }
+    let raw = def.raw;
+    let declared = template::declared_vars(&raw);
+    if vars.is_none() && !declared.is_empty() {
+        return Action::InstallNeedsVars {
+            id,
+            install_path,
+            vars: declared,
+        };
+    }
+    let Ok(content) = template::render(&raw, &vars.unwrap_or_default()) else {
+        return Action::InstallNeedsVars {
+            id,
+            install_path,
+            vars: declared,
+        };
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        agent_defs::install::install_content(&install_path, &content)
+            .map_err(|e| format!("Failed to write file: {e}"))?;
+        Ok(format!("Installed to {}", install_path.display()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task panicked: {e}")));
+    Action::InstallCompleted(result)
+}
+
+/// Install several definitions under `target`, filling template variables
+/// from their defaults. Definitions that fail or that `policy` refuses are
+/// named in the result rather than stopping the batch; `total` counts any
+/// the app couldn't resolve too.
+async fn install_many(
+    source: &dyn Source,
+    label: &str,
+    ids: Vec<DefinitionId>,
+    total: usize,
+    target: &Path,
+    rules: &InstallRules,
+    on_usage: Option<UsageFn>,
+) -> Result<String, String> {
+    let mut skipped = Vec::new();
+    for id in &ids {
+        let installed = async {
+            let mut def = source.fetch(id).await.map_err(|e| e.to_string())?;
+            if def.raw.is_empty() {
+                def.raw = source.fetch_raw(id).await.map_err(|e| e.to_string())?;
+            }
+            rules.policy.check_install(&def).map_err(|e| e.to_string())?;
+            let (target, layout) = (target.to_owned(), rules.layout);
+            tokio::task::spawn_blocking(move || {
+                layout
+                    .install(&target, &def, &HashMap::new())
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| format!("Task panicked: {e}"))?
+        };
+        if installed.await.is_err() {
+            skipped.push(id.as_str());
+        } else if let Some(on_usage) = on_usage.clone() {
+            let id = id.clone();
+            let _ = tokio::task::spawn_blocking(move || on_usage(&id, UsageKind::Install)).await;
+        }
+    }
+
+    let installed = ids.len() - skipped.len();
+    if installed == 0 {
+        return Err(format!("nothing from {label} could be installed"));
     }
+    let mut message = format!("Installed {installed} of {total} definitions from {label}");
+    if !skipped.is_empty() {
+        message.push_str(&format!("; skipped {}", skipped.join(", ")));
+    }
+    Ok(message)
+}
+
+/// If the app constructor requested a fetch (cursor placed on an item), kick it off.
+fn process_initial_fetch(
+    app: &App,
+    source: &Arc<dyn Source>,
+    tx: &mpsc::Sender<Action>,
+) -> Option<AbortHandle> {
+    let id = app.pending_fetch.clone()?;
+    Some(spawn_fetch(source, id, tx))
+}
+
+/// Every source with how fresh its cache is.
+fn source_freshness(sync: &dyn SyncController) -> Vec<(String, Freshness)> {
+    sync.sources()
+        .into_iter()
+        .map(|label| {
+            let freshness = sync.status(&label);
+            (label, freshness)
+        })
+        .collect()
 }
 
-fn copy_to_clipboard(text: &str) -> Result<(), String> {
-    // Clipboard access runs on a blocking thread via spawn_blocking in the caller,
-    // but arboard is not async anyway. We'll use a simple approach here.
-    // For now, use OSC 52 escape sequence which works in most modern terminals.
-    use std::io::Write;
-    let encoded = base64_encode(text.as_bytes());
-    let sequence = format!("\x1b]52;c;{encoded}\x07");
-    std::io::stdout()
-        .write_all(sequence.as_bytes())
-        .map_err(|e| format!("Failed to write clipboard escape: {e}"))?;
-    std::io::stdout()
-        .flush()
-        .map_err(|e| format!("Failed to flush: {e}"))
-}
-
-/// Minimal base64 encoding (no external dep needed for this).
-fn base64_encode(input: &[u8]) -> String {
-    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut output = String::new();
-    for chunk in input.chunks(3) {
-        let b0 = chunk[0] as u32;
-        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
-        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
-        let triple = (b0 << 16) | (b1 << 8) | b2;
-
-        output.push(CHARS[((triple >> 18) & 0x3F) as usize] as char);
-        output.push(CHARS[((triple >> 12) & 0x3F) as usize] as char);
-
-        if chunk.len() > 1 {
-            output.push(CHARS[((triple >> 6) & 0x3F) as usize] as char);
-        } else {
-            output.push('=');
+/// Sync `target`, reporting each source's progress back through `tx` and
+/// finishing with every source's new freshness.
+async fn run_sync(sync: Arc<dyn SyncController>, target: SyncTarget, tx: mpsc::Sender<Action>) {
+    match target {
+        SyncTarget::All => {
+            // Progress callbacks can't wait on a full channel, so they go
+            // through an unbounded one and are forwarded from here.
+            let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+            let syncing = async {
+                sync.sync_all(&move |event| {
+                    let _ = progress_tx.send(event);
+                })
+                .await;
+            };
+            let forwarding = async {
+                while let Some(event) = progress_rx.recv().await {
+                    let _ = tx.send(Action::SyncProgress(event)).await;
+                }
+            };
+            tokio::join!(syncing, forwarding);
+        }
+        SyncTarget::Source(label) => {
+            let _ = tx.send(Action::SyncProgress(SyncEvent::Started(label.clone()))).await;
+            let result = sync.sync_source(&label).await.map_err(crate::sync::sync_error);
+            let _ = tx.send(Action::SyncProgress(SyncEvent::Finished(label, result))).await;
         }
+    }
+    let _ = tx.send(Action::SyncFinished(source_freshness(sync.as_ref()))).await;
+}
+
+/// Load `id` for the detail pane in the background. Aborting the returned
+/// handle drops the request.
+fn spawn_fetch(
+    source: &Arc<dyn Source>,
+    id: DefinitionId,
+    tx: &mpsc::Sender<Action>,
+) -> AbortHandle {
+    let source = Arc::clone(source);
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        let result = source.fetch(&id).await.map_err(|e| format!("{e}"));
+        let _ = tx.send(Action::DefinitionLoaded(id, Box::new(result))).await;
+    })
+    .abort_handle()
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::InMemorySource;
+
+    use super::*;
 
-        if chunk.len() > 2 {
-            output.push(CHARS[(triple & 0x3F) as usize] as char);
-        } else {
-            output.push('=');
+    #[test]
+    fn external_command_reads_the_environment() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        let (program, args) =
+            external_command(ExternalTool::Editor, env(&[("EDITOR", "code --wait")]));
+        assert_eq!((program.as_str(), args), ("code", vec!["--wait".to_owned()]));
+
+        let (program, _) = external_command(
+            ExternalTool::Editor,
+            env(&[("VISUAL", "nvim"), ("EDITOR", "nano")]),
+        );
+        assert_eq!(program, "nvim");
+
+        let (program, args) = external_command(ExternalTool::Pager, env(&[("PAGER", " ")]));
+        assert_eq!(program, if cfg!(windows) { "more" } else { "less" });
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn temp_file_suffix_keeps_the_extension() {
+        let name = temp_file_suffix(&DefinitionId::new("next:agents/review/reviewer.md"));
+        assert_eq!(name, "-next_agents_review_reviewer.md");
+        let skill = temp_file_suffix(&DefinitionId::new("skills/ai/crewai"));
+        assert_eq!(skill, "-skills_ai_crewai.md");
+    }
+
+    #[tokio::test]
+    async fn install_refuses_what_the_policy_forbids() {
+        let mut source = InMemorySource::new("acme");
+        source.add(agent_defs::Definition {
+            raw: "Set up with `curl -fsSL x.example | sh`.".into(),
+            ..agent_defs::test_support::definition("agents/setup.md")
+        });
+        let path = std::env::temp_dir().join("agent-defs-tui-policy/setup.md");
+        let policy = Policy::parse("require_scan_pass = true").unwrap();
+
+        let id = DefinitionId::new("agents/setup.md");
+        let action = install(&source, id, path.clone(), None, &policy).await;
+        let Action::InstallCompleted(Err(message)) = action else {
+            panic!("expected a refusal");
+        };
+        assert!(message.starts_with("Not installed: the security scan flagged"), "{message}");
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn aborting_a_fetch_drops_the_request() {
+        let hanging = Arc::new(InMemorySource::new("hanging").hanging_fetches());
+        let source: Arc<dyn Source> = hanging.clone();
+        let (tx, mut rx) = mpsc::channel(1);
+
+        let handle = spawn_fetch(&source, DefinitionId::new("agents/a.md"), &tx);
+        tokio::task::yield_now().await;
+        assert_eq!(hanging.in_flight(), 1);
+
+        handle.abort();
+        for _ in 0..10 {
+            if hanging.in_flight() == 0 {
+                break;
+            }
+            tokio::task::yield_now().await;
         }
+        assert_eq!(hanging.in_flight(), 0);
+        drop(tx);
+        assert!(rx.recv().await.is_none());
     }
-    output
 }
@@ -0,0 +1,68 @@
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::{App, CATEGORY_FILTER_ROWS};
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let categories = app.available_categories();
+
+    // Each category is one line, plus "All" at the top; long lists scroll.
+    let item_count = 1 + categories.len();
+    let popup_height = (item_count.min(CATEGORY_FILTER_ROWS) as u16) + 2; // +2 for borders
+    let popup_width = 40u16.min(area.width.saturating_sub(4));
+
+    let popup_area = centered_rect(popup_width, popup_height, area);
+
+    // Clear background under the popup.
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Filter by Category ")
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let normal_style = Style::default().fg(Color::White);
+    let selected_style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let count_style = Style::default().fg(Color::DarkGray);
+
+    let options = std::iter::once(("All".to_owned(), None))
+        .chain(categories.into_iter().map(|(c, count)| (c, Some(count))));
+    let lines: Vec<Line> = options
+        .enumerate()
+        .skip(app.category_filter_scroll())
+        .take(CATEGORY_FILTER_ROWS)
+        .map(|(i, (label, count))| {
+            let style = if app.category_filter_cursor == i {
+                selected_style
+            } else {
+                normal_style
+            };
+            let mut spans = vec![Span::styled(format!("  {label}"), style)];
+            if let Some(count) = count {
+                spans.push(Span::styled(format!(" ({count})"), count_style));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines);
+    frame.render_widget(paragraph, inner);
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center);
+
+    let [vertical_area] = vertical.areas(area);
+    let [centered] = horizontal.areas(vertical_area);
+    centered
+}
@@ -0,0 +1,80 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::{App, CompareLine};
+
+pub fn popup(area: Rect) -> Rect {
+    super::centered_rect_percent(90, 85, area)
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let Some(comparison) = &app.comparison else {
+        return;
+    };
+    let popup_area = popup(area);
+    frame.render_widget(Clear, popup_area);
+
+    let (removed, added) = comparison.counts();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " Compare: {} \u{2194} {} (-{removed} +{added}) ",
+            comparison.left, comparison.right
+        ))
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if removed == 0 && added == 0 {
+        let same = Paragraph::new(Span::styled(
+            "  The two definitions are identical",
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(same, inner);
+        return;
+    }
+
+    let rows = usize::from(inner.height);
+    if !comparison.side_by_side {
+        let lines: Vec<Line> = comparison
+            .lines
+            .iter()
+            .skip(comparison.scroll)
+            .take(rows)
+            .map(|line| Line::from(marked(line)))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+        return;
+    }
+
+    let [left, divider, right] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+    let (mut left_lines, mut right_lines) = (Vec::new(), Vec::new());
+    for (old, new) in comparison.pairs().into_iter().skip(comparison.scroll).take(rows) {
+        left_lines.push(old.map_or_else(Line::default, |line| Line::from(marked(line))));
+        right_lines.push(new.map_or_else(Line::default, |line| Line::from(marked(line))));
+    }
+    frame.render_widget(Paragraph::new(left_lines), left);
+    frame.render_widget(Paragraph::new(right_lines), right);
+    let rule: Vec<Line> = (0..inner.height)
+        .map(|_| Line::styled("\u{2502}", Style::default().fg(Color::DarkGray)))
+        .collect();
+    frame.render_widget(Paragraph::new(rule), divider);
+}
+
+/// A diff line with its `-`/`+` marker, coloured by what happened to it.
+fn marked(line: &CompareLine) -> Vec<Span<'_>> {
+    let (marker, text, style) = match line {
+        CompareLine::Same(text) => ("  ", text, Style::default()),
+        CompareLine::Removed(text) => ("- ", text, Style::default().fg(Color::Red)),
+        CompareLine::Added(text) => ("+ ", text, Style::default().fg(Color::Green)),
+    };
+    vec![Span::styled(marker, style), Span::styled(text.as_str(), style)]
+}
@@ -1,20 +1,32 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap};
 use ratatui::Frame;
 
-use crate::app::{App, LoadingState};
+use agent_defs::merge::KeyChange;
+use agent_defs::{Definition, DefinitionKind};
 
-pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Detail ");
+use crate::app::{App, LoadingState, Mode, SettingsPreview};
 
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let title = if !app.detail_zoomed {
+        " Detail ".to_owned()
+    } else if app.detail_query.is_empty() || app.mode == Mode::DetailSearch {
+        " Detail (z to return) ".to_owned()
+    } else {
+        let count = app.layout_geometry.detail_matches.len();
+        let noun = if count == 1 { "match" } else { "matches" };
+        format!(" Detail (z to return, /{}: {count} {noun}) ", app.detail_query)
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
     let inner = block.inner(area);
-    frame.render_widget(block, area);
 
     if app.loading == LoadingState::Fetching && app.selected_definition.is_none() {
+        frame.render_widget(block, area);
         let loading = Paragraph::new("Loading...")
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(loading, inner);
@@ -22,12 +34,124 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     }
 
     let Some(def) = &app.selected_definition else {
+        frame.render_widget(block, area);
         let hint = Paragraph::new("Select a definition to view details")
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(hint, inner);
         return;
     };
 
+    let lines = content(app, def, inner.width).lines;
+    let content_length = lines.len();
+    let visible_height = inner.height as usize;
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((app.detail_scroll, 0))
+        .wrap(Wrap { trim: false });
+
+    // Where the pane is scrolled to, once there's more than fits.
+    let rows = paragraph.line_count(inner.width);
+    let block = match position(app, rows, visible_height) {
+        Some(position) => block.title_bottom(
+            Line::from(Span::styled(position, Style::default().fg(Color::DarkGray)))
+                .right_aligned(),
+        ),
+        None => block,
+    };
+    frame.render_widget(block, area);
+    frame.render_widget(paragraph, inner);
+
+    // Render scrollbar if content exceeds visible height.
+    if content_length > visible_height {
+        let mut scrollbar_state = ScrollbarState::new(content_length)
+            .position(app.detail_scroll as usize)
+            .viewport_content_length(visible_height);
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some("│"))
+            .thumb_symbol("█");
+
+        frame.render_stateful_widget(scrollbar, inner, &mut scrollbar_state);
+    }
+}
+
+/// The footer saying how far down the pane is scrolled, and which line of
+/// the body is at the top, when `rows` of content don't fit in `height`.
+fn position(app: &App, rows: usize, height: usize) -> Option<String> {
+    if rows <= height {
+        return None;
+    }
+    let scroll = usize::from(app.detail_scroll);
+    let percent = (scroll * 100 / (rows - height)).min(100);
+    let body = &app.layout_geometry.detail_lines;
+    let line = body.iter().take_while(|&&row| row <= app.detail_scroll).count();
+    Some(if line == 0 {
+        format!(" {percent}% ")
+    } else {
+        format!(" line {line} of {}, {percent}% ", body.len())
+    })
+}
+
+/// The rows, once wrapped to `width`, that jumping around the selected
+/// definition's body scrolls to.
+#[derive(Debug, Default)]
+pub(super) struct ScrollTargets {
+    /// Where each section starts.
+    pub sections: Vec<u16>,
+    /// Where each match of the detail search is.
+    pub matches: Vec<u16>,
+    /// Where each line of the body starts.
+    pub lines: Vec<u16>,
+}
+
+pub(super) fn scroll_targets(app: &App, width: u16) -> ScrollTargets {
+    let Some(def) = &app.selected_definition else {
+        return ScrollTargets::default();
+    };
+    let content = content(app, def, width);
+    let mut starts = Vec::with_capacity(content.lines.len());
+    let mut row = 0;
+    for line in &content.lines {
+        starts.push(row);
+        row += wrapped_rows(line.clone(), width);
+    }
+    let row = |line: usize, within: usize| {
+        u16::try_from(starts[line] + within).unwrap_or(u16::MAX)
+    };
+    ScrollTargets {
+        sections: content.sections.iter().map(|&line| row(line, 0)).collect(),
+        matches: content
+            .matches
+            .iter()
+            .map(|&(line, within)| row(line, within))
+            .collect(),
+        lines: content.body.map(|line| row(line, 0)).collect(),
+    }
+}
+
+fn wrapped_rows<'a>(line: impl Into<Line<'a>>, width: u16) -> usize {
+    Paragraph::new(line.into())
+        .wrap(Wrap { trim: false })
+        .line_count(width)
+}
+
+/// The detail pane's lines, and where in them the keys that jump around
+/// the body land.
+struct Content {
+    lines: Vec<Line<'static>>,
+    /// Index of each line that starts a section of the body.
+    sections: Vec<usize>,
+    /// Each match of the detail search, as the index of its line and the
+    /// row within that line, once wrapped, it's on.
+    matches: Vec<(usize, usize)>,
+    /// The lines of the body, which a settings preview has none of.
+    body: Range<usize>,
+}
+
+/// The detail pane's lines for `def`, `width` columns wide.
+fn content(app: &App, def: &Definition, width: u16) -> Content {
     let label_style = Style::default()
         .fg(Color::Yellow)
         .add_modifier(Modifier::BOLD);
@@ -45,7 +169,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     // Kind
     lines.push(Line::from(vec![
         Span::styled("Kind:     ", label_style),
-        Span::styled(def.kind.to_string(), value_style),
+        Span::styled(app.glyphs.prefix(&def.kind, &def.kind.to_string()), value_style),
     ]));
 
     // Category
@@ -72,53 +196,230 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         ]));
     }
 
+    // Frontmatter fields without a line of their own above.
+    if !def.metadata.is_empty() {
+        lines.push(Line::from(Span::styled("Metadata:", label_style)));
+        lines.extend(metadata_lines(&def.metadata, value_style));
+    }
+
     // Source
     lines.push(Line::from(vec![
         Span::styled("Source:   ", label_style),
         Span::styled(def.source_label.clone(), value_style),
     ]));
 
+    // Rating, from the community index
+    if let Some(rating) = app.selected_rating() {
+        lines.push(Line::from(vec![
+            Span::styled("Rating:   ", label_style),
+            Span::styled(rating.describe(), value_style),
+        ]));
+    }
+
     // ID
     lines.push(Line::from(vec![
         Span::styled("ID:       ", label_style),
         Span::styled(def.id.to_string(), dim_style),
     ]));
 
+    // The user's own note, set apart from the definition's metadata.
+    if let Some(note) = app.selected_note() {
+        let note_style = Style::default().fg(Color::Cyan);
+        lines.push(Line::from(""));
+        for (i, text) in note.text.lines().enumerate() {
+            let label = if i == 0 { "Note:     " } else { "          " };
+            lines.push(Line::from(vec![
+                Span::styled(label, label_style),
+                Span::styled(text.to_owned(), note_style),
+            ]));
+        }
+    }
+
+    // Hooks run shell commands; show them before anything else in the body.
+    if def.kind == DefinitionKind::Hook {
+        lines.push(Line::from(""));
+        lines.extend(hook_warning_lines(&def.hook_commands(), false));
+    }
+
+    // Contents, numbered for jumping to a section, when there's more than
+    // one to jump between. A settings preview doesn't show the body.
+    let sections = if app.settings_preview.is_none() {
+        def.sections()
+    } else {
+        Vec::new()
+    };
+    if sections.len() > 1 {
+        let top = sections.iter().map(|s| s.level).min().unwrap_or(1);
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Contents:", label_style)));
+        for (n, section) in sections.iter().enumerate() {
+            let indent = "  ".repeat(usize::from(section.level - top) + 1);
+            lines.push(Line::from(vec![
+                Span::styled(format!("{indent}{:>2} ", n + 1), dim_style),
+                Span::styled(section.title.clone(), value_style),
+            ]));
+        }
+    }
+
     // Separator
     lines.push(Line::from(""));
-    let separator_width = inner.width as usize;
+    let separator_width = width as usize;
     lines.push(Line::from(Span::styled(
         "\u{2500}".repeat(separator_width),
         dim_style,
     )));
     lines.push(Line::from(""));
 
-    // Body
-    for line in def.body.lines() {
-        lines.push(Line::from(line.to_owned()));
+    // Body: a settings definition shows what it would change instead.
+    let body_start = lines.len();
+    let mut matches = Vec::new();
+    if let Some(preview) = &app.settings_preview {
+        lines.push(Line::from(vec![
+            Span::styled("Changes to ", label_style),
+            Span::styled(preview.path.display().to_string(), value_style),
+        ]));
+        lines.push(Line::from(""));
+        lines.extend(settings_change_lines(preview));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("v to view the raw JSON", dim_style)));
+    } else {
+        let found_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let query = &app.detail_query;
+        let digits = def.body.lines().count().to_string().len();
+        for (i, text) in def.body.lines().enumerate() {
+            let mut spans = Vec::new();
+            if app.detail_line_numbers {
+                spans.push(Span::styled(format!("{:>digits$} ", i + 1), dim_style));
+            }
+            let mut end = 0;
+            for start in find_all(text, query) {
+                spans.push(Span::raw(text[end..start].to_owned()));
+                end = start + query.len();
+                spans.push(Span::styled(text[start..end].to_owned(), found_style));
+                // Wrapping up to the match's end finds its row even when
+                // the match is what moves to the next one.
+                let within = wrapped_rows(spans.clone(), width).saturating_sub(1);
+                matches.push((lines.len(), within));
+            }
+            spans.push(Span::raw(text[end..].to_owned()));
+            lines.push(Line::from(spans));
+        }
     }
 
-    let content_length = lines.len();
-    let visible_height = inner.height as usize;
+    Content {
+        sections: sections.iter().map(|s| body_start + s.line).collect(),
+        matches,
+        body: if app.settings_preview.is_some() {
+            body_start..body_start
+        } else {
+            body_start..lines.len()
+        },
+        lines,
+    }
+}
 
-    let paragraph = Paragraph::new(lines)
-        .scroll((app.detail_scroll, 0))
-        .wrap(Wrap { trim: false });
+/// Where `query` starts in `text`, ignoring ASCII case, in order and
+/// without overlapping.
+fn find_all(text: &str, query: &str) -> Vec<usize> {
+    let (haystack, needle) = (text.as_bytes(), query.as_bytes());
+    let mut found = Vec::new();
+    let mut start = 0;
+    while !needle.is_empty() && start + needle.len() <= haystack.len() {
+        if text.is_char_boundary(start)
+            && haystack[start..start + needle.len()].eq_ignore_ascii_case(needle)
+        {
+            found.push(start);
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    found
+}
 
-    frame.render_widget(paragraph, inner);
+/// Widest the key column of the metadata table gets; longer keys push
+/// their own value along rather than the whole column.
+const MAX_METADATA_KEY_WIDTH: usize = 20;
 
-    // Render scrollbar if content exceeds visible height.
-    if content_length > visible_height {
-        let mut scrollbar_state = ScrollbarState::new(content_length)
-            .position(app.detail_scroll as usize)
-            .viewport_content_length(visible_height);
+/// A row per metadata key, sorted by key, with the values lined up.
+/// Values spanning several lines continue under the value column.
+fn metadata_lines(metadata: &HashMap<String, String>, value_style: Style) -> Vec<Line<'static>> {
+    let key_style = Style::default().fg(Color::Yellow);
+    let mut entries: Vec<(&String, &String)> = metadata.iter().collect();
+    entries.sort();
+    let width = entries
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_METADATA_KEY_WIDTH);
 
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(None)
-            .end_symbol(None)
-            .track_symbol(Some("│"))
-            .thumb_symbol("█");
+    let mut lines = Vec::new();
+    for (key, value) in entries {
+        let mut value_lines = value.lines();
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {key:<width$}  "), key_style),
+            Span::styled(value_lines.next().unwrap_or("").to_owned(), value_style),
+        ]));
+        for more in value_lines {
+            lines.push(Line::from(vec![
+                Span::raw(" ".repeat(width + 4)),
+                Span::styled(more.to_owned(), value_style),
+            ]));
+        }
+    }
+    lines
+}
 
-        frame.render_stateful_widget(scrollbar, inner, &mut scrollbar_state);
+/// One line per key a settings install would add (green) or change
+/// (yellow), or a note that it changes nothing.
+pub(super) fn settings_change_lines(preview: &SettingsPreview) -> Vec<Line<'static>> {
+    let changes = match &preview.changes {
+        Ok(changes) => changes,
+        Err(e) => {
+            return vec![Line::from(Span::styled(
+                format!("Can't compare with the current settings: {e}"),
+                Style::default().fg(Color::Red),
+            ))];
+        }
+    };
+    if changes.is_empty() {
+        return vec![Line::from(Span::styled(
+            "Already applied; nothing would change.",
+            Style::default().fg(Color::DarkGray),
+        ))];
     }
+    changes
+        .iter()
+        .map(|change| {
+            let color = match change {
+                KeyChange::Added { .. } => Color::Green,
+                KeyChange::Changed { .. } => Color::Yellow,
+            };
+            Line::from(Span::styled(change.to_string(), Style::default().fg(color)))
+        })
+        .collect()
+}
+
+/// A warning that a hook runs shell commands, with each command it runs
+/// set apart. `collection` words it for a collection holding hooks.
+pub(super) fn hook_warning_lines(commands: &[String], collection: bool) -> Vec<Line<'static>> {
+    let warning = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+    let command_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let heading = if collection {
+        "! This collection includes hooks, which run shell commands"
+    } else if commands.is_empty() {
+        "! Hooks run shell commands; read the body before installing"
+    } else {
+        "! Runs these shell commands:"
+    };
+    let mut lines = vec![Line::from(Span::styled(heading, warning))];
+    lines.extend(
+        commands
+            .iter()
+            .map(|command| Line::from(Span::styled(format!("  $ {command}"), command_style))),
+    );
+    lines
 }
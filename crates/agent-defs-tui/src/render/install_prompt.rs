@@ -4,7 +4,7 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, SettingsPreview};
 
 /// Render the file explorer overlay for selecting install directory.
 pub fn render_explorer(frame: &mut Frame, area: Rect, app: &App) {
@@ -42,9 +42,18 @@ pub fn render_explorer(frame: &mut Frame, area: Rect, app: &App) {
     let preview_style = Style::default().fg(Color::DarkGray);
     let path_style = Style::default().fg(Color::Yellow);
 
-    let preview_text = if let Some(def) = &app.selected_definition {
+    let preview_text = if let Some(collection) = app.active_collection().filter(|_| app.installing_collection) {
+        Line::from(vec![
+            Span::styled(" Will install to: ", preview_style),
+            Span::styled(explorer.cwd().join(".claude").display().to_string(), path_style),
+            Span::styled(
+                format!(" ({} from {})", collection.items.len(), collection.name),
+                preview_style,
+            ),
+        ])
+    } else if let Some(def) = &app.selected_definition {
         let target = explorer.cwd();
-        let install_path = agent_defs::install::install_path(target, def);
+        let install_path = app.install_layout.path(target, def);
         Line::from(vec![
             Span::styled(" Will install to: ", preview_style),
             Span::styled(install_path.display().to_string(), path_style),
@@ -77,18 +86,44 @@ pub fn render_explorer(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(Paragraph::new(hints), chunks[2]);
 }
 
+/// Settings changes to list in the confirmation dialog, if any.
+fn confirm_preview(app: &App) -> Option<&SettingsPreview> {
+    app.settings_preview.as_ref().filter(|_| !app.installing_collection)
+}
+
+/// Commands a hook being installed would run, listed in the dialog.
+fn confirm_commands(app: &App) -> Vec<String> {
+    match &app.selected_definition {
+        Some(def) if !app.installing_collection => def.hook_commands(),
+        _ => Vec::new(),
+    }
+}
+
+/// Area of the install confirmation dialog: small, unless it lists settings
+/// changes or hook commands.
+pub fn confirm_popup(area: Rect, app: &App) -> Rect {
+    let detailed = confirm_preview(app).is_some() || app.install_runs_commands();
+    centered_rect(50, if detailed { 60 } else { 30 }, area)
+}
+
 /// Render the install confirmation dialog.
 pub fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
-    // Smaller centered dialog
-    let popup_area = centered_rect(50, 30, area);
+    let preview = confirm_preview(app);
+    let runs_commands = app.install_runs_commands();
+    let popup_area = confirm_popup(area, app);
 
     // Clear the area behind the popup
     frame.render_widget(Clear, popup_area);
 
+    let (border, title) = if runs_commands {
+        (Color::Red, " Confirm Installation: runs shell commands ")
+    } else {
+        (Color::Yellow, " Confirm Installation ")
+    };
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow))
-        .title(" Confirm Installation ");
+        .border_style(Style::default().fg(border))
+        .title(title);
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
@@ -110,21 +145,41 @@ pub fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(question, chunks[0]);
 
     // Path
-    let path_text = if let Some(path) = &app.pending_install_path {
+    let path_text = if let (true, Some(collection), Some(target)) = (
+        app.installing_collection,
+        app.active_collection(),
+        &app.install_target,
+    ) {
+        format!(
+            "{} definitions from {} into {}",
+            collection.items.len(),
+            collection.name,
+            target.join(".claude").display()
+        )
+    } else if let Some(path) = &app.pending_install_path {
         path.display().to_string()
     } else if let (Some(target), Some(def)) = (&app.install_target, &app.selected_definition) {
-        agent_defs::install::install_path(target, def)
-            .display()
-            .to_string()
+        app.install_layout.path(target, def).display().to_string()
     } else {
         "(unknown)".to_string()
     };
 
-    let path_para = Paragraph::new(Line::from(Span::styled(
+    let mut path_lines = vec![Line::from(Span::styled(
         path_text,
         Style::default().fg(Color::Yellow),
-    )))
-    .wrap(ratatui::widgets::Wrap { trim: false });
+    ))];
+    if let Some(preview) = preview {
+        path_lines.push(Line::from(""));
+        path_lines.extend(super::detail_pane::settings_change_lines(preview));
+    }
+    if runs_commands {
+        path_lines.push(Line::from(""));
+        path_lines.extend(super::detail_pane::hook_warning_lines(
+            &confirm_commands(app),
+            app.installing_collection,
+        ));
+    }
+    let path_para = Paragraph::new(path_lines).wrap(ratatui::widgets::Wrap { trim: false });
     frame.render_widget(path_para, chunks[1]);
 
     // Hint bar
@@ -133,8 +188,9 @@ pub fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
         .fg(Color::Green)
         .add_modifier(Modifier::BOLD);
 
+    let confirm_key = if runs_commands { " Y" } else { " Enter/y" };
     let hints = Line::from(vec![
-        Span::styled(" Enter/y", key_style),
+        Span::styled(confirm_key, key_style),
         Span::styled(" confirm  ", hint_style),
         Span::styled("Esc/n", key_style),
         Span::styled(" cancel", hint_style),
@@ -142,6 +198,86 @@ pub fn render_confirm(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(Paragraph::new(hints), chunks[2]);
 }
 
+/// Area of the template variable form: one row per variable plus the
+/// install path, hints, and borders.
+pub fn vars_popup(area: Rect, app: &App) -> Rect {
+    let field_count = app.install_form.as_ref().map_or(0, |form| form.fields.len());
+    let height = (field_count as u16 + 6).min(area.height);
+    let width = 60u16.min(area.width.saturating_sub(4));
+    super::centered_rect_fixed(width, height, area)
+}
+
+/// Render the form asking for a definition's template variables.
+pub fn render_vars(frame: &mut Frame, area: Rect, app: &App) {
+    let Some(form) = &app.install_form else {
+        return;
+    };
+
+    let popup_area = vars_popup(area, app);
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(" Template Variables ");
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .margin(1)
+        .split(inner);
+
+    let path = Line::from(Span::styled(
+        form.install_path.display().to_string(),
+        Style::default().fg(Color::DarkGray),
+    ));
+    frame.render_widget(Paragraph::new(path), chunks[0]);
+
+    let name_width = form.fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let selected = i == form.cursor;
+            let name_style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            let mut spans = vec![
+                Span::styled(format!("{name:>name_width$}: "), name_style),
+                Span::styled(value.clone(), Style::default().fg(Color::White)),
+            ];
+            if selected {
+                spans.push(Span::styled("\u{2588}", Style::default().fg(Color::Yellow)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let hint_style = Style::default().fg(Color::DarkGray);
+    let key_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+    let hints = Line::from(vec![
+        Span::styled("Tab", key_style),
+        Span::styled(" next  ", hint_style),
+        Span::styled("Enter", key_style),
+        Span::styled(" next/install  ", hint_style),
+        Span::styled("Esc", key_style),
+        Span::styled(" cancel", hint_style),
+    ]);
+    frame.render_widget(Paragraph::new(hints), chunks[2]);
+}
+
 /// Helper to create a centered rectangle.
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -60,7 +60,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             .map(|(_, c)| *c)
             .unwrap_or(0);
 
-        let label = format!("  {}", kind.display_label());
+        let label = format!("  {}", app.glyphs.prefix(kind, kind.display_label()));
         let count_text = format!(" ({count})");
 
         lines.push(Line::from(vec![
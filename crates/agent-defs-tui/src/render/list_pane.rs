@@ -1,22 +1,26 @@
+use agent_defs::ListDensity;
+use agent_defs::grouping::{ItemOrder, ListRow};
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState};
 use ratatui::Frame;
 
-use crate::app::App;
-use crate::grouping::ListRow;
+use crate::app::{App, compute_scroll_offset};
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(" Definitions ");
+    let title = match app.item_order {
+        ItemOrder::Name => " Definitions ".to_owned(),
+        order => format!(" Definitions ({}) ", order.label()),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let visible_height = inner.height as usize;
-    if visible_height == 0 || app.flat_items.is_empty() {
+    // Rows rather than lines: a row takes two lines when descriptions show.
+    let visible_height = app.list_rows_visible(inner.height).max(1);
+    if inner.height == 0 || app.flat_items.is_empty() {
         return;
     }
 
@@ -29,7 +33,7 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .enumerate()
         .skip(scroll_offset)
         .take(visible_height)
-        .map(|(idx, row)| render_row(row, idx == app.cursor, app))
+        .flat_map(|(idx, row)| render_row(row, idx == app.cursor, app))
         .collect();
 
     let paragraph = Paragraph::new(lines);
@@ -51,20 +55,22 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     }
 }
 
-fn render_row<'a>(row: &ListRow, is_selected: bool, app: &App) -> Line<'a> {
-    match row {
-        ListRow::Header { label, count } => {
+/// The lines for one row: the name, and in comfortable density the
+/// description under it (or a blank line under a header).
+fn render_row<'a>(row: &ListRow, is_selected: bool, app: &App) -> Vec<Line<'a>> {
+    let mut lines = match row {
+        ListRow::Header { kind, label, count } => {
             let style = Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD);
-            Line::from(Span::styled(format!("{label} ({count})"), style))
+            let label = app.glyphs.prefix(kind, label);
+            vec![Line::from(Span::styled(format!("{label} ({count})"), style))]
         }
         ListRow::Item { summary_index } => {
-            let name = app
-                .view_summaries
-                .get(*summary_index)
-                .map(|s| s.name.as_str())
-                .unwrap_or("???");
+            let summary = app.view_summaries.get(*summary_index);
+            let name = summary
+                .map(|s| app.glyphs.prefix(&s.kind, app.display_names.get(s)))
+                .unwrap_or_else(|| "???".into());
 
             let style = if is_selected {
                 Style::default()
@@ -75,17 +81,37 @@ fn render_row<'a>(row: &ListRow, is_selected: bool, app: &App) -> Line<'a> {
                 Style::default()
             };
 
-            Line::from(Span::styled(format!("  {name}"), style))
+            let mut spans = vec![Span::styled(format!("  {name}"), style)];
+            if let Some(rating) = summary.and_then(|s| app.rating(s)) {
+                let badge_style = if is_selected {
+                    style
+                } else {
+                    Style::default().fg(Color::Yellow)
+                };
+                spans.push(Span::styled(format!(" {}", rating.badge()), badge_style));
+            }
+            let marked = app.compare_mark.as_ref().map(|def| def.qualified_id());
+            if marked.is_some() && marked == summary.map(|s| s.qualified_id()) {
+                let mark_style = if is_selected {
+                    style
+                } else {
+                    Style::default().fg(Color::Magenta)
+                };
+                spans.push(Span::styled(" (marked)", mark_style));
+            }
+            let mut lines = vec![Line::from(spans)];
+            if app.list_density == ListDensity::Comfortable {
+                let description = summary.and_then(|s| s.description.as_deref()).unwrap_or("");
+                let style = if is_selected {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                lines.push(Line::from(Span::styled(format!("    {description}"), style)));
+            }
+            lines
         }
-    }
-}
-
-fn compute_scroll_offset(cursor: usize, current_offset: usize, visible_height: usize) -> usize {
-    if cursor < current_offset {
-        cursor
-    } else if cursor >= current_offset + visible_height {
-        cursor.saturating_sub(visible_height - 1)
-    } else {
-        current_offset
-    }
+    };
+    lines.resize(app.list_row_height(), Line::default());
+    lines
 }
@@ -1,11 +1,17 @@
+mod category_filter_overlay;
+mod compare_overlay;
 mod detail_pane;
 mod install_prompt;
 mod kind_filter_overlay;
 mod list_pane;
+mod note_editor;
 mod search_bar;
 mod source_filter_overlay;
 mod status_bar;
 mod sync_overlay;
+mod task_list_overlay;
+mod warnings_overlay;
+mod whats_new_overlay;
 
 use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
@@ -13,51 +19,57 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 use ratatui::Frame;
 
-use crate::app::{App, LayoutGeometry, LoadingState, Mode};
+use crate::app::{App, FilterChip, LayoutGeometry, Mode};
 
 pub fn render(frame: &mut Frame, app: &App) {
     let size = frame.area();
+    let [title, chips, content, bottom] = outer_layout(size, app);
 
-    // Outer layout: title bar (1), main content, bottom bar (1).
-    let outer = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Min(3),
-            Constraint::Length(1),
-        ])
-        .split(size);
-
-    // Title bar.
-    render_title_bar(frame, outer[0], app);
+    render_title_bar(frame, title, app);
+    render_chips(frame, chips, app);
 
     // Main content: two horizontal panes.
-    let panes = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
-        .split(outer[1]);
+    let panes = split_panes(content, app);
 
-    list_pane::render(frame, panes[0], app);
+    if !app.detail_zoomed {
+        list_pane::render(frame, panes[0], app);
+    }
     detail_pane::render(frame, panes[1], app);
 
     // Bottom bar: depends on mode.
     match app.mode {
-        Mode::Search => search_bar::render(frame, outer[2], app),
+        Mode::Search | Mode::DetailSearch | Mode::GoToLine => {
+            search_bar::render(frame, bottom, app)
+        }
         Mode::Normal
         | Mode::KindFilter
         | Mode::SourceFilter
+        | Mode::CategoryFilter
         | Mode::SyncProgress
         | Mode::InstallPrompt
-        | Mode::InstallConfirm => status_bar::render(frame, outer[2], app),
+        | Mode::InstallConfirm
+        | Mode::InstallVars
+        | Mode::WhatsNew
+        | Mode::NoteEditor
+        | Mode::Warnings
+        | Mode::Tasks
+        | Mode::Compare => status_bar::render(frame, bottom, app),
     }
 
     // Overlays (rendered on top).
     match app.mode {
         Mode::KindFilter => kind_filter_overlay::render(frame, size, app),
         Mode::SourceFilter => source_filter_overlay::render(frame, size, app),
+        Mode::CategoryFilter => category_filter_overlay::render(frame, size, app),
         Mode::SyncProgress => sync_overlay::render(frame, size, app),
         Mode::InstallPrompt => install_prompt::render_explorer(frame, size, app),
         Mode::InstallConfirm => install_prompt::render_confirm(frame, size, app),
+        Mode::InstallVars => install_prompt::render_vars(frame, size, app),
+        Mode::WhatsNew => whats_new_overlay::render(frame, size, app),
+        Mode::NoteEditor => note_editor::render(frame, size, app),
+        Mode::Warnings => warnings_overlay::render(frame, size, app),
+        Mode::Tasks => task_list_overlay::render(frame, size, app),
+        Mode::Compare => compare_overlay::render(frame, size, app),
         _ => {}
     }
 }
@@ -67,9 +79,6 @@ fn render_title_bar(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
         .fg(Color::Cyan)
         .add_modifier(Modifier::BOLD);
     let label_style = Style::default().fg(Color::DarkGray);
-    let filter_style = Style::default()
-        .fg(Color::Magenta)
-        .add_modifier(Modifier::BOLD);
 
     let mut spans = vec![
         Span::styled(" Agent Definitions", title_style),
@@ -77,17 +86,22 @@ fn render_title_bar(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
         Span::styled(format!("[{}]", app.source_label), label_style),
     ];
 
-    if let Some(ref kind) = app.kind_filter {
-        spans.push(Span::raw(" "));
+    if !app.skipped.is_empty() {
+        let count = app.skipped.len();
+        spans.push(Span::raw("  "));
         spans.push(Span::styled(
-            format!("{{kind:{}}}", kind.display_label()),
-            filter_style,
+            format!("{count} {}", if count == 1 { "warning" } else { "warnings" }),
+            Style::default().fg(Color::Yellow),
         ));
     }
 
-    if let Some(ref source) = app.source_filter {
-        spans.push(Span::raw(" "));
-        spans.push(Span::styled(format!("{{source:{}}}", source), filter_style));
+    if let Some(task) = app.tasks.running() {
+        let mut text = format!("\u{25b6} {}", task.label);
+        if app.tasks.pending() > 0 {
+            text.push_str(&format!(" (+{})", app.tasks.pending()));
+        }
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(text, Style::default().fg(Color::Yellow)));
     }
 
     let line = Line::from(spans);
@@ -95,24 +109,56 @@ fn render_title_bar(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
     frame.render_widget(paragraph, area);
 }
 
-/// Compute layout geometry for mouse hit testing.
-/// This mirrors the layout calculations in render() but returns Rect values.
-pub fn compute_layout(frame_size: Rect, app: &App) -> LayoutGeometry {
-    // Outer layout: title bar (1), main content, bottom bar (1).
-    let outer = Layout::default()
+/// Outer layout: title bar (1), filter chips (1, only while any filter is
+/// active), main content, bottom bar (1).
+fn outer_layout(size: Rect, app: &App) -> [Rect; 4] {
+    let chip_rows = if app.filter_chips().is_empty() { 0 } else { 1 };
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),
+            Constraint::Length(chip_rows),
             Constraint::Min(3),
             Constraint::Length(1),
         ])
-        .split(frame_size);
+        .areas(size)
+}
+
+/// Lay the filter chips out left to right in `area`, numbered for the
+/// Alt+number keys. Chips that don't fit are left off.
+fn chip_areas(area: Rect, app: &App) -> Vec<(Rect, FilterChip, String)> {
+    let mut x = area.x + 1;
+    let right = area.x + area.width;
+    let mut chips = Vec::new();
+    for (n, (chip, label)) in app.filter_chips().into_iter().enumerate() {
+        let text = format!(" {} {label} \u{2715} ", n + 1); // ✕ clear marker
+        let width = text.chars().count() as u16;
+        if area.height == 0 || x + width > right {
+            break;
+        }
+        chips.push((Rect::new(x, area.y, width, 1), chip, text));
+        x += width + 1;
+    }
+    chips
+}
+
+fn render_chips(frame: &mut Frame, area: Rect, app: &App) {
+    let style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Magenta)
+        .add_modifier(Modifier::BOLD);
+    for (chip_area, _, text) in chip_areas(area, app) {
+        frame.render_widget(Paragraph::new(Span::styled(text, style)), chip_area);
+    }
+}
+
+/// Compute layout geometry for mouse hit testing.
+/// This mirrors the layout calculations in render() but returns Rect values.
+pub fn compute_layout(frame_size: Rect, app: &App) -> LayoutGeometry {
+    let [_, chips, content, _] = outer_layout(frame_size, app);
 
     // Main content: two horizontal panes.
-    let panes = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
-        .split(outer[1]);
+    let panes = split_panes(content, app);
 
     // Compute inner areas (excluding borders).
     let list_block = Block::default().borders(Borders::ALL);
@@ -121,6 +167,9 @@ pub fn compute_layout(frame_size: Rect, app: &App) -> LayoutGeometry {
     let detail_block = Block::default().borders(Borders::ALL);
     let detail_inner = detail_block.inner(panes[1]);
 
+    // Where jumping to a section, search match, or line scrolls the detail pane.
+    let targets = detail_pane::scroll_targets(app, detail_inner.width);
+
     // Compute overlay area if one is displayed.
     let overlay = compute_overlay_rect(frame_size, app);
 
@@ -131,14 +180,46 @@ pub fn compute_layout(frame_size: Rect, app: &App) -> LayoutGeometry {
         None
     };
 
+    // The divider is the list pane's right border plus the detail pane's left
+    // border, so either column can be grabbed.
+    let divider = if app.detail_zoomed {
+        Rect::default()
+    } else {
+        Rect::new(panes[1].x.saturating_sub(1), panes[0].y, 2, panes[0].height)
+    };
+
     LayoutGeometry {
         list_inner,
         detail_inner,
         overlay,
         explorer_list_inner,
+        content,
+        divider,
+        chips: chip_areas(chips, app)
+            .into_iter()
+            .map(|(area, chip, _)| (area, chip))
+            .collect(),
+        detail_sections: targets.sections,
+        detail_matches: targets.matches,
+        detail_lines: targets.lines,
     }
 }
 
+/// Split the content area into list and detail panes at the app's divider.
+/// In full-screen reading mode the list gets an empty rect.
+fn split_panes(area: Rect, app: &App) -> [Rect; 2] {
+    if app.detail_zoomed {
+        return [Rect::new(area.x, area.y, 0, area.height), area];
+    }
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(app.list_percent),
+            Constraint::Percentage(100 - app.list_percent),
+        ])
+        .areas(area)
+}
+
 /// Compute the overlay Rect based on current mode.
 fn compute_overlay_rect(area: Rect, app: &App) -> Option<Rect> {
     match app.mode {
@@ -150,38 +231,30 @@ fn compute_overlay_rect(area: Rect, app: &App) -> Option<Rect> {
             Some(centered_rect_fixed(popup_width, popup_height, area))
         }
         Mode::SourceFilter => {
-            let sources = app.available_sources();
-            let item_count = 1 + sources.len();
+            let item_count = app.source_filter_options().len();
             let popup_height = (item_count as u16).min(15) + 2;
             let popup_width = 40u16.min(area.width.saturating_sub(4));
             Some(centered_rect_fixed(popup_width, popup_height, area))
         }
-        Mode::SyncProgress => {
-            let is_syncing = app.loading == LoadingState::Syncing;
-            let (popup_height, popup_width) = if is_syncing {
-                (5u16, 30u16)
-            } else if let Some(result) = &app.sync_result {
-                let warning_count = result.warnings.len();
-                let content_height = if warning_count == 0 {
-                    3
-                } else {
-                    4 + warning_count.min(10) as u16
-                };
-                (content_height + 2, 60u16.min(area.width.saturating_sub(4)))
-            } else {
-                (5u16, 30u16)
-            };
+        Mode::CategoryFilter => {
+            let item_count = 1 + app.available_categories().len();
+            let popup_height = (item_count.min(crate::app::CATEGORY_FILTER_ROWS) as u16) + 2;
+            let popup_width = 40u16.min(area.width.saturating_sub(4));
             Some(centered_rect_fixed(popup_width, popup_height, area))
         }
+        Mode::SyncProgress => Some(sync_overlay::popup(area, app)),
         Mode::InstallPrompt => {
             // 60% width, 70% height
             Some(centered_rect_percent(60, 70, area))
         }
-        Mode::InstallConfirm => {
-            // 50% width, 30% height
-            Some(centered_rect_percent(50, 30, area))
-        }
-        Mode::Normal | Mode::Search => None,
+        Mode::InstallConfirm => Some(install_prompt::confirm_popup(area, app)),
+        Mode::InstallVars => Some(install_prompt::vars_popup(area, app)),
+        Mode::WhatsNew => Some(whats_new_overlay::popup(area, app)),
+        Mode::NoteEditor => Some(note_editor::popup(area)),
+        Mode::Warnings => Some(warnings_overlay::popup(area, app)),
+        Mode::Tasks => Some(task_list_overlay::popup(area, app)),
+        Mode::Compare => Some(compare_overlay::popup(area)),
+        Mode::Normal | Mode::Search | Mode::DetailSearch | Mode::GoToLine => None,
     }
 }
 
@@ -241,3 +314,383 @@ fn centered_rect_percent(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::test_support::definition;
+    use agent_defs::{ChangeKind, Definition, DefinitionChange, Rating, SkippedFile, SyncError};
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+    use crate::action::{Action, AppCommand};
+    use crate::app::InstallForm;
+    use crate::{Freshness, SyncFailure, SyncResult};
+
+    fn press(app: &mut App, c: char) {
+        app.handle_event(Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        }));
+    }
+
+    fn definitions() -> Vec<Definition> {
+        let described = |id: &str, description: &str| Definition {
+            description: Some(description.to_owned()),
+            ..definition(id)
+        };
+        vec![
+            described("agents/review/reviewer.md", "Reviews pull requests"),
+            described("agents/planning/planner.md", "Breaks work into steps"),
+            described("commands/ops/deploy.md", "Deploys the current branch"),
+            definition("hooks/lint-on-save.md"),
+        ]
+    }
+
+    /// An app browsing a small catalog, with the initial fetch answered.
+    fn browsing() -> App {
+        let definitions = definitions();
+        let summaries = definitions.iter().map(Definition::summary).collect();
+        let mut app = App::new(summaries, "test".into());
+        app.set_sync_sources(vec![("test".into(), Freshness::Fresh { days_old: 2 })]);
+        let id = app.pending_fetch.clone().unwrap();
+        let def = definitions.into_iter().find(|def| def.qualified_id() == id).unwrap();
+        app.handle_action(Action::DefinitionLoaded(id, Box::new(Ok(def))));
+        app
+    }
+
+    fn draw(app: &App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render(frame, app)).unwrap();
+        terminal.backend().to_string()
+    }
+
+    #[test]
+    fn empty_list() {
+        let app = App::new(vec![], "test".into());
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn browsing_a_definition() {
+        insta::assert_snapshot!(draw(&browsing(), 80, 24));
+    }
+
+    #[test]
+    fn rated_definitions() {
+        let mut app = browsing();
+        let rating = |id: &str, score, verified| Rating {
+            id: agent_defs::DefinitionId::new(id),
+            source_label: "test".into(),
+            score,
+            votes: 12,
+            verified,
+        };
+        app.ratings = vec![
+            rating("agents/planning/planner.md", Some(4.5), true),
+            rating("agents/review/reviewer.md", Some(3.0), false),
+            rating("commands/ops/deploy.md", None, true),
+        ];
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn filters_active() {
+        let mut app = browsing();
+        app.kind_filter = Some(agent_defs::DefinitionKind::Agent);
+        app.source_filter = Some("test".into());
+        press(&mut app, '/');
+        for c in "review".chars() {
+            press(&mut app, c);
+        }
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn chips_sit_where_they_are_drawn() {
+        let mut app = browsing();
+        app.kind_filter = Some(agent_defs::DefinitionKind::Agent);
+        app.category_filter = Some("review".into());
+
+        let screen = draw(&app, 80, 24);
+        let chips_row = screen.lines().nth(1).unwrap();
+        assert!(chips_row.contains(" 2 category:review \u{2715} "), "{chips_row}");
+        let layout = compute_layout(Rect::new(0, 0, 80, 24), &app);
+        let (area, chip) = layout.chips[1];
+        assert_eq!(chip, FilterChip::Category);
+        assert_eq!(area.y, 1);
+        assert_eq!(layout.list_inner.y, 3);
+    }
+
+    #[test]
+    fn metadata_table() {
+        let mut app = browsing();
+        let def = app.selected_definition.as_mut().unwrap();
+        def.tools = vec!["Read".into(), "Grep".into()];
+        def.metadata = [
+            ("color", "blue"),
+            ("priority", "high"),
+            ("examples", "review my PR\nfind the bug"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn body_sections() {
+        let mut app = browsing();
+        let def = app.selected_definition.as_mut().unwrap();
+        def.body = "# Reviewer\n\nReads every change closely before it merges, line by line.\n\
+                    ## Checklist\n- Tests\n## Tone\nBe kind.\n"
+            .into();
+        insta::assert_snapshot!(draw(&app, 80, 24));
+
+        // Each section's row is where its heading is drawn, body lines
+        // wrapping included. Screen lines are quoted, hence the extra column.
+        let layout = compute_layout(Rect::new(0, 0, 80, 40), &app);
+        let pane = layout.detail_inner;
+        let screen = draw(&app, 80, 40);
+        let rows: Vec<&str> = screen.lines().skip(usize::from(pane.y)).collect();
+        let titles = ["# Reviewer", "## Checklist", "## Tone"];
+        assert_eq!(layout.detail_sections.len(), titles.len());
+        for (&row, title) in layout.detail_sections.iter().zip(titles) {
+            let line: String = rows[usize::from(row)]
+                .chars()
+                .skip(usize::from(pane.x) + 1)
+                .collect();
+            assert!(line.starts_with(title), "{line}");
+        }
+    }
+
+    #[test]
+    fn searching_the_body() {
+        let mut app = browsing();
+        let def = app.selected_definition.as_mut().unwrap();
+        def.body = "Check the TODO list.\n\nThen look again for anything left to do, in every file \
+                    the change touches, todo or not.\n"
+            .into();
+        press(&mut app, 'z');
+        press(&mut app, '/');
+        for c in "todo".chars() {
+            press(&mut app, c);
+        }
+        app.layout_geometry = compute_layout(Rect::new(0, 0, 80, 24), &app);
+        let screen = draw(&app, 80, 24);
+        assert!(screen.contains("/  todo\u{2588}  2 matches"), "{screen}");
+
+        // The second match is on the third line's second row.
+        let pane = app.layout_geometry.detail_inner;
+        let [first, second] = app.layout_geometry.detail_matches[..] else {
+            panic!("{:?}", app.layout_geometry.detail_matches);
+        };
+        assert_eq!(second, first + 3);
+        let rows: Vec<&str> = screen.lines().skip(usize::from(pane.y)).collect();
+        assert!(rows[usize::from(second)].contains("todo or not"), "{screen}");
+
+        app.handle_event(Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+        assert_eq!(app.detail_scroll, first);
+        let screen = draw(&app, 80, 24);
+        assert!(screen.contains("Detail (z to return, /todo: 2 matches)"), "{screen}");
+    }
+
+    #[test]
+    fn numbered_lines_and_position() {
+        let mut app = browsing();
+        let def = app.selected_definition.as_mut().unwrap();
+        def.body = (1..=30).map(|n| format!("Step {n}.\n")).collect();
+        press(&mut app, '#');
+        app.layout_geometry = compute_layout(Rect::new(0, 0, 80, 24), &app);
+        app.detail_scroll = app.layout_geometry.detail_lines[9];
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    /// Marks the selected definition and compares it with an edited copy.
+    fn comparing() -> App {
+        let mut app = browsing();
+        press(&mut app, 'm');
+        let mut other = definition("agents/review/strict-reviewer.md");
+        other.raw = "---\nname: strict-reviewer\nmodel: opus\n---\n".to_owned()
+            + "You are reviewer.\nBe thorough.\n";
+        app.selected_definition = Some(other);
+        press(&mut app, '=');
+        app
+    }
+
+    #[test]
+    fn comparison_unified() {
+        insta::assert_snapshot!(draw(&comparing(), 80, 24));
+    }
+
+    #[test]
+    fn comparison_side_by_side() {
+        let mut app = comparing();
+        press(&mut app, 's');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn comfortable_rows() {
+        let mut app = browsing();
+        press(&mut app, 'D');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn tiny_terminal() {
+        insta::assert_snapshot!(draw(&browsing(), 30, 8));
+    }
+
+    #[test]
+    fn kind_filter_overlay() {
+        let mut app = browsing();
+        press(&mut app, 'f');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn source_filter_overlay() {
+        let mut app = browsing();
+        press(&mut app, 'p');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn category_filter_overlay() {
+        let mut app = browsing();
+        press(&mut app, 'C');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn sync_overlay_in_progress() {
+        let mut app = browsing();
+        press(&mut app, 's');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn sync_overlay_with_failures() {
+        let mut app = browsing();
+        app.mode = Mode::SyncProgress;
+        app.sync_result = Some(SyncResult {
+            message: "Synced 4 definitions (1 skipped)".into(),
+            failures: vec![SyncFailure {
+                source_label: "upstream".into(),
+                error: SyncError::Network("connection refused".into()),
+            }],
+            warnings: vec!["hooks/broken.md: missing frontmatter".into()],
+            synced: 4,
+            skipped: 1,
+            ..SyncResult::default()
+        });
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn sync_overlay_with_a_refused_token() {
+        let mut app = browsing();
+        app.mode = Mode::SyncProgress;
+        app.sync_result = Some(SyncResult {
+            message: "Synced 0 definitions (0 skipped)".into(),
+            failures: vec![SyncFailure {
+                source_label: "private".into(),
+                error: SyncError::Unauthorized("Bad credentials".into()),
+            }],
+            ..SyncResult::default()
+        });
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn install_explorer() {
+        let mut app = browsing();
+        app.install_target = Some("tests/fixtures/project".into());
+        press(&mut app, 'i');
+        assert_eq!(app.mode, Mode::InstallPrompt);
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn install_confirm() {
+        let mut app = browsing();
+        app.install_target = Some("tests/fixtures/project".into());
+        press(&mut app, 'i');
+        press(&mut app, 'i');
+        assert_eq!(app.mode, Mode::InstallConfirm);
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn install_vars() {
+        let mut app = browsing();
+        app.mode = Mode::InstallVars;
+        app.install_form = Some(InstallForm {
+            id: app.selected_definition.as_ref().unwrap().id.clone(),
+            install_path: "project/.claude/agents/planner.md".into(),
+            fields: vec![
+                ("team".into(), "platform".into()),
+                ("language".into(), String::new()),
+            ],
+            cursor: 1,
+        });
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn whats_new_overlay() {
+        let mut app = browsing();
+        app.changes = definitions()
+            .iter()
+            .zip([ChangeKind::Added, ChangeKind::Changed, ChangeKind::Removed])
+            .map(|(def, change)| DefinitionChange {
+                id: def.id.clone(),
+                name: def.name.clone(),
+                kind: def.kind.clone(),
+                source_label: def.source_label.clone(),
+                change,
+                synced_at: 1_760_000_000,
+            })
+            .collect();
+        press(&mut app, 'w');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn note_editor() {
+        let mut app = browsing();
+        press(&mut app, 'n');
+        for c in "Use for large PRs".chars() {
+            press(&mut app, c);
+        }
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn warnings_overlay() {
+        let mut app = browsing();
+        app.set_skipped(vec![SkippedFile {
+            source_label: "test".into(),
+            path: "agents/broken.md".into(),
+            reason: "frontmatter is not valid YAML".into(),
+            recorded_at: 1_760_000_000,
+        }]);
+        press(&mut app, 'W');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+
+    #[test]
+    fn task_list_overlay() {
+        let mut app = browsing();
+        app.tasks.push("Sync all sources", AppCommand::None);
+        app.tasks.finish(Ok("Synced 4 definitions".into()));
+        app.tasks.push("Install planner", AppCommand::None);
+        app.tasks.push("Install reviewer", AppCommand::None);
+        press(&mut app, 't');
+        insta::assert_snapshot!(draw(&app, 80, 24));
+    }
+}
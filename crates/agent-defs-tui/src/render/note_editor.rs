@@ -0,0 +1,58 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+use crate::app::App;
+
+pub fn popup(area: Rect) -> Rect {
+    let width = 60u16.min(area.width.saturating_sub(4));
+    let height = 9u16.min(area.height);
+    super::centered_rect_fixed(width, height, area)
+}
+
+/// Render the editor for the selected definition's note.
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let popup_area = popup(area);
+    frame.render_widget(Clear, popup_area);
+
+    let title = match &app.selected_definition {
+        Some(def) => format!(" Note: {} ", def.name),
+        None => " Note ".to_owned(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(title);
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let text = Line::from(vec![
+        Span::styled(app.note_draft.clone(), Style::default().fg(Color::White)),
+        Span::styled("\u{2588}", Style::default().fg(Color::Yellow)),
+    ]);
+    frame.render_widget(
+        Paragraph::new(text).wrap(Wrap { trim: false }),
+        chunks[0],
+    );
+
+    let hint_style = Style::default().fg(Color::DarkGray);
+    let key_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+    let hints = Line::from(vec![
+        Span::styled("Enter", key_style),
+        Span::styled(" save (empty removes)  ", hint_style),
+        Span::styled("Ctrl+U", key_style),
+        Span::styled(" clear  ", hint_style),
+        Span::styled("Esc", key_style),
+        Span::styled(" cancel", hint_style),
+    ]);
+    frame.render_widget(Paragraph::new(hints), chunks[1]);
+}
@@ -4,22 +4,43 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, Mode};
 
+/// What's being typed: the list's search, a search of the detail body
+/// with its match count, or a line number to go to.
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let line = Line::from(vec![
+    let (prompt, input) = match app.mode {
+        Mode::DetailSearch => (" / ", &app.detail_query),
+        Mode::GoToLine => (" : ", &app.go_to_line_input),
+        _ => (" / ", &app.search_query),
+    };
+    let mut spans = vec![
         Span::styled(
-            " / ",
+            prompt,
             Style::default()
                 .fg(Color::Black)
                 .bg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" "),
-        Span::styled(&app.search_query, Style::default().fg(Color::White)),
+        Span::styled(input.as_str(), Style::default().fg(Color::White)),
         Span::styled("\u{2588}", Style::default().fg(Color::Cyan)), // block cursor
-    ]);
+    ];
+    let note = match app.mode {
+        Mode::DetailSearch if !input.is_empty() => {
+            let count = app.layout_geometry.detail_matches.len();
+            Some(format!("{count} {}", if count == 1 { "match" } else { "matches" }))
+        }
+        Mode::GoToLine => Some(format!("of {}", app.layout_geometry.detail_lines.len())),
+        _ => None,
+    };
+    if let Some(note) = note {
+        spans.push(Span::styled(
+            format!("  {note}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
 
-    let paragraph = Paragraph::new(line);
+    let paragraph = Paragraph::new(Line::from(spans));
     frame.render_widget(paragraph, area);
 }
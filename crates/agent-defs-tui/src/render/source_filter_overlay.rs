@@ -4,13 +4,14 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, SourceOption};
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let sources = app.available_sources();
+    let options = app.source_filter_options();
 
-    // Each source is one line, plus "All" at the top.
-    let item_count = 1 + sources.len();
+    // One line per option: "All", "Most used", sources, branches, then
+    // collections.
+    let item_count = options.len();
     let popup_height = (item_count as u16).min(15) + 2; // +2 for borders, max 15 items visible
     let popup_width = 40u16.min(area.width.saturating_sub(4));
 
@@ -34,40 +35,72 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .add_modifier(Modifier::BOLD);
     let count_style = Style::default().fg(Color::DarkGray);
 
-    let mut lines: Vec<Line> = Vec::with_capacity(item_count);
-
-    // "All" option.
-    let all_style = if app.source_filter_cursor == 0 {
-        selected_style
-    } else {
-        normal_style
-    };
-    lines.push(Line::from(Span::styled("  All", all_style)));
-
-    // Source options with counts.
     let source_counts = compute_source_counts(app);
-    for (i, source) in sources.iter().enumerate() {
-        let cursor_idx = i + 1;
-        let style = if app.source_filter_cursor == cursor_idx {
-            selected_style
-        } else {
-            normal_style
-        };
-
-        let count = source_counts
-            .iter()
-            .find(|(s, _)| s == source)
-            .map(|(_, c)| *c)
-            .unwrap_or(0);
-
-        let label = format!("  {}", source);
-        let count_text = format!(" ({count})");
-
-        lines.push(Line::from(vec![
-            Span::styled(label, style),
-            Span::styled(count_text, count_style),
-        ]));
-    }
+    let lines: Vec<Line> = options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let style = if app.source_filter_cursor == i {
+                selected_style
+            } else {
+                normal_style
+            };
+            match option {
+                SourceOption::All => Line::from(Span::styled("  All", style)),
+                SourceOption::MostUsed => Line::from(vec![
+                    Span::styled("  \u{2605} Most used", style), // ★ usage marker
+                    Span::styled(format!(" ({})", app.most_used_count()), count_style),
+                ]),
+                SourceOption::Source(source) => {
+                    let count = source_counts
+                        .iter()
+                        .find(|(s, _)| s == source)
+                        .map(|(_, c)| *c)
+                        .unwrap_or(0);
+                    let mut spans = vec![
+                        Span::styled(format!("  {}", source), style),
+                        Span::styled(format!(" ({count})"), count_style),
+                    ];
+                    if let Some(freshness) = app.source_freshness(source) {
+                        let color = if freshness.is_stale() {
+                            Color::Yellow
+                        } else {
+                            Color::DarkGray
+                        };
+                        spans.push(Span::styled(
+                            format!(" {}", freshness.describe()),
+                            Style::default().fg(color),
+                        ));
+                    }
+                    Line::from(spans)
+                }
+                SourceOption::Branch(branch) => {
+                    let count = app
+                        .summaries
+                        .iter()
+                        .filter(|s| {
+                            agent_defs::path::split_branch(s.id.as_str()).0 == Some(branch.as_str())
+                        })
+                        .count();
+                    Line::from(vec![
+                        Span::styled(format!("  \u{2387} {branch}"), style), // ⎇ branch marker
+                        Span::styled(format!(" ({count})"), count_style),
+                    ])
+                }
+                SourceOption::Collection(name) => {
+                    let count = app
+                        .collections
+                        .iter()
+                        .find(|c| &c.name == name)
+                        .map_or(0, |c| c.items.len());
+                    Line::from(vec![
+                        Span::styled(format!("  \u{25c6} {name}"), style), // ◆ collection marker
+                        Span::styled(format!(" ({count})"), count_style),
+                    ])
+                }
+            }
+        })
+        .collect();
 
     let paragraph = Paragraph::new(lines);
     frame.render_widget(paragraph, inner);
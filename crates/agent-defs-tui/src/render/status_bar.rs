@@ -4,7 +4,10 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, LoadingState, Mode};
+
+/// A key and what it does, e.g. `("/", "search")`.
+type Hint = (&'static str, &'static str);
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let line = if let Some(msg) = &app.status_message {
@@ -15,27 +18,205 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         };
         Line::from(Span::styled(format!(" {}", msg.text), style))
     } else {
-        let hint_style = Style::default().fg(Color::DarkGray);
-        Line::from(vec![
-            Span::styled(" \u{2191}\u{2193}", hint_style),
-            Span::styled(" navigate  ", hint_style),
-            Span::styled("/", hint_style),
-            Span::styled(" search  ", hint_style),
-            Span::styled("f", hint_style),
-            Span::styled(" kind  ", hint_style),
-            Span::styled("p", hint_style),
-            Span::styled(" source  ", hint_style),
-            Span::styled("\u{23ce}", hint_style), // ⏎ Enter symbol
-            Span::styled(" install  ", hint_style),
-            Span::styled("s", hint_style),
-            Span::styled(" sync  ", hint_style),
-            Span::styled("c", hint_style),
-            Span::styled(" copy  ", hint_style),
-            Span::styled("q", hint_style),
-            Span::styled(" quit", hint_style),
-        ])
+        hint_line(&hints(app), area.width)
     };
 
     let paragraph = Paragraph::new(line);
     frame.render_widget(paragraph, area);
 }
+
+/// Key hints for the current mode, most useful first so truncation drops
+/// the least important ones.
+fn hints(app: &App) -> Vec<Hint> {
+    match app.mode {
+        Mode::Normal if app.detail_zoomed => {
+            let mut hints = vec![("\u{2191}\u{2193}", "scroll"), ("PgUp/PgDn", "page")];
+            if !app.detail_query.is_empty() {
+                hints.push(("n/N", "next/prev match"));
+            }
+            hints.extend([("/", "find"), (":", "go to line"), ("#", "line numbers")]);
+            if app.layout_geometry.detail_sections.len() > 1 {
+                hints.push(("{/}/1-9", "section"));
+            }
+            hints.extend([
+                ("z/Esc", "back"),
+                ("c", "copy"),
+                ("e/v", "editor/pager"),
+                ("q", "quit"),
+            ]);
+            hints
+        }
+        Mode::Search => vec![
+            ("type", "filter"),
+            ("\u{2191}\u{2193}", "navigate"),
+            ("\u{23ce}", "keep"),
+            ("Esc", "clear"),
+        ],
+        Mode::Normal => {
+            let mut hints = vec![("\u{2191}\u{2193}", "navigate"), ("/", "search")];
+            if app.collection_filter.is_some() {
+                hints.push(("A", "install collection"));
+            }
+            if app.has_filters() {
+                hints.push(("Esc", "clear filters"));
+            }
+            if !app.filter_chips().is_empty() {
+                hints.push(("Alt+1-9", "clear one"));
+            }
+            hints.extend([
+                ("f", "kind"),
+                ("p", "source"),
+                ("C", "category"),
+                ("\u{23ce}", "install"), // ⏎ Enter symbol
+            ]);
+            if app.quick_install_target.is_some() {
+                hints.push(("I", "quick install"));
+            }
+            hints.extend([
+                ("s", "sync"),
+                ("w", "what's new"),
+                ("W", "warnings"),
+                ("t", "tasks"),
+                ("n", "note"),
+                ("x", "hide"),
+                ("o", "order"),
+                ("c", "copy"),
+                ("Y", "copy ID"),
+                ("e/v", "editor/pager"),
+                ("z", "zoom"),
+                ("m", "mark"),
+                ("=", "compare"),
+                ("gg/G", "top/bottom"),
+                ("D", "density"),
+                ("[]", "resize"),
+                ("q", "quit"),
+            ]);
+            hints
+        }
+        Mode::KindFilter | Mode::SourceFilter | Mode::CategoryFilter => vec![
+            ("\u{2191}\u{2193}", "choose"),
+            ("\u{23ce}", "apply"),
+            ("Esc", "cancel"),
+        ],
+        Mode::SyncProgress if app.loading == LoadingState::Syncing => {
+            vec![("\u{23ce}/Esc", "hide")]
+        }
+        Mode::SyncProgress if app.selected_sync_failure().is_some() => vec![
+            ("\u{2191}\u{2193}", "select"),
+            ("r", "retry source"),
+            ("\u{23ce}/Esc", "close"),
+        ],
+        Mode::SyncProgress => vec![
+            ("\u{2191}\u{2193}", "scroll warnings"),
+            ("w", "what's new"),
+            ("W", "warnings"),
+            ("\u{23ce}/Esc", "close"),
+        ],
+        Mode::WhatsNew | Mode::Warnings => {
+            vec![("\u{2191}\u{2193}", "scroll"), ("\u{23ce}/Esc", "close")]
+        }
+        Mode::InstallPrompt => vec![
+            ("\u{2191}\u{2193}", "navigate"),
+            ("\u{23ce}", "open"),
+            ("i", "install here"),
+            ("I", "install now"),
+            ("Esc", "cancel"),
+        ],
+        Mode::InstallConfirm if app.install_runs_commands() => {
+            vec![("Y", "install hook"), ("Esc/n", "cancel")]
+        }
+        Mode::InstallConfirm => vec![("\u{23ce}/y", "confirm"), ("Esc/n", "cancel")],
+        Mode::InstallVars => vec![
+            ("Tab/\u{2191}\u{2193}", "field"),
+            ("\u{23ce}", "next/install"),
+            ("Esc", "cancel"),
+        ],
+        Mode::NoteEditor => vec![("\u{23ce}", "save"), ("Ctrl+U", "clear"), ("Esc", "cancel")],
+        Mode::DetailSearch => vec![("\u{23ce}", "find"), ("Esc", "cancel")],
+        Mode::GoToLine => vec![("\u{23ce}", "go to line"), ("Esc", "cancel")],
+        Mode::Compare => vec![
+            ("\u{2191}\u{2193}", "scroll"),
+            ("n/N", "next/prev change"),
+            ("Tab", "side by side/unified"),
+            ("Esc", "close"),
+        ],
+        Mode::Tasks => vec![
+            ("\u{2191}\u{2193}", "select"),
+            ("x", "cancel waiting"),
+            ("c", "clear finished"),
+            ("Esc", "close"),
+        ],
+    }
+}
+
+/// Lay hints out left to right, dropping whole hints that don't fit in
+/// `width` and marking the cut with an ellipsis.
+fn hint_line(hints: &[Hint], width: u16) -> Line<'static> {
+    let key_style = Style::default().fg(Color::Gray);
+    let hint_style = Style::default().fg(Color::DarkGray);
+    let width = usize::from(width);
+
+    let mut spans = Vec::new();
+    let mut used = 1; // leading space
+    for (i, (key, action)) in hints.iter().enumerate() {
+        let separator = if i == 0 { 0 } else { 2 };
+        let needed = separator + Span::raw(*key).width() + 1 + Span::raw(*action).width();
+        if used + needed > width {
+            if used + 3 <= width {
+                spans.push(Span::styled("  \u{2026}", hint_style));
+            }
+            break;
+        }
+        spans.push(Span::styled(if i == 0 { " " } else { "  " }, hint_style));
+        spans.push(Span::styled(*key, key_style));
+        spans.push(Span::styled(format!(" {action}"), hint_style));
+        used += needed;
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use agent_defs::DefinitionKind;
+
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn wide_terminal_shows_every_hint() {
+        let line = hint_line(&[("/", "search"), ("q", "quit")], 80);
+        assert_eq!(text(&line), " / search  q quit");
+    }
+
+    #[test]
+    fn narrow_terminal_drops_trailing_hints() {
+        let line = hint_line(&[("/", "search"), ("f", "kind"), ("q", "quit")], 20);
+        assert_eq!(text(&line), " / search  f kind  \u{2026}");
+        assert!(line.width() <= 20);
+    }
+
+    #[test]
+    fn hints_follow_mode() {
+        let mut app = App::new(vec![], "test".into());
+        assert!(hints(&app).contains(&("/", "search")));
+        assert!(!hints(&app).contains(&("Esc", "clear filters")));
+
+        app.kind_filter = Some(DefinitionKind::Agent);
+        assert!(hints(&app).contains(&("Esc", "clear filters")));
+
+        app.mode = Mode::Search;
+        assert_eq!(hints(&app)[0], ("type", "filter"));
+        assert!(hints(&app).contains(&("Esc", "clear")));
+        assert!(!hints(&app).contains(&("q", "quit")));
+
+        app.mode = Mode::InstallConfirm;
+        assert_eq!(hints(&app)[0], ("\u{23ce}/y", "confirm"));
+
+        app.mode = Mode::Normal;
+        app.detail_zoomed = true;
+        assert!(hints(&app).contains(&("z/Esc", "back")));
+    }
+}
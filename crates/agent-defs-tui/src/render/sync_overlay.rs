@@ -4,27 +4,44 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use ratatui::Frame;
 
-use crate::app::{App, LoadingState};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+use agent_defs::ErrorKind;
+
+use crate::app::{App, LoadingState, SourceSync, SourceSyncState};
+
+/// Most sources listed while a sync runs.
+const VISIBLE_SOURCES: usize = 10;
+
+pub fn popup(area: Rect, app: &App) -> Rect {
     let is_syncing = app.loading == LoadingState::Syncing;
 
     // Determine popup size based on content
-    let (popup_height, popup_width) = if is_syncing {
+    let (popup_height, popup_width) = if is_syncing && !app.sync_sources.is_empty() {
+        let rows = app.sync_sources.len().min(VISIBLE_SOURCES) as u16;
+        (rows + 2, 50u16.min(area.width.saturating_sub(4)))
+    } else if is_syncing {
         (5u16, 30u16)
     } else if let Some(result) = &app.sync_result {
-        let warning_count = result.warnings.len();
+        let warning_count = result.issue_count();
         let content_height = if warning_count == 0 {
             3 // Just the message
         } else {
             4 + warning_count.min(10) as u16 // Message + header + warnings (max 10 visible)
         };
+        let guidance = guidance(result, app.sync_result_scroll).is_some();
+        let content_height = content_height + u16::from(guidance);
         (content_height + 2, 60u16.min(area.width.saturating_sub(4)))
     } else {
         (5u16, 30u16)
     };
 
-    let popup_area = centered_rect(popup_width, popup_height, area);
+    centered_rect(popup_width, popup_height, area)
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let is_syncing = app.loading == LoadingState::Syncing;
+    let popup_area = popup(area, app);
 
     // Clear background under the popup.
     frame.render_widget(Clear, popup_area);
@@ -40,7 +57,9 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
 
-    if is_syncing {
+    if is_syncing && !app.sync_sources.is_empty() {
+        render_sources(frame, inner, &app.sync_sources);
+    } else if is_syncing {
         render_syncing(frame, inner);
     } else if let Some(result) = &app.sync_result {
         render_result(frame, inner, result, app.sync_result_scroll);
@@ -56,37 +75,86 @@ fn render_syncing(frame: &mut Frame, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
-fn render_result(frame: &mut Frame, area: Rect, result: &crate::SyncResult, scroll: usize) {
+/// Render the summary and the failures and warnings under it. `selected`
+/// indexes failures then warnings; the list scrolls to keep it in view.
+/// One line per source: where it is in the sync, and how old its cache was
+/// or how many definitions it brought in.
+fn render_sources(frame: &mut Frame, area: Rect, sources: &[SourceSync]) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = sources
+        .iter()
+        .take(VISIBLE_SOURCES)
+        .map(|source| {
+            let (marker, color, detail) = match &source.state {
+                SourceSyncState::Syncing => ("\u{21bb}", Color::Yellow, "syncing...".to_owned()),
+                SourceSyncState::Synced(count) => {
+                    ("\u{2713}", Color::Green, format!("{count} synced"))
+                }
+                SourceSyncState::Failed => ("\u{2717}", Color::Red, "failed".to_owned()),
+                SourceSyncState::Idle | SourceSyncState::Pending => {
+                    ("\u{00b7}", Color::DarkGray, source.freshness.describe())
+                }
+            };
+            Line::from(vec![
+                Span::styled(format!(" {marker} "), Style::default().fg(color)),
+                Span::raw(source.label.clone()),
+                Span::styled(format!("  {detail}"), dim),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+fn render_result(frame: &mut Frame, area: Rect, result: &crate::SyncResult, selected: usize) {
     let mut lines: Vec<Line> = Vec::new();
 
     // Summary message
     let msg_style = Style::default().fg(Color::Green);
     lines.push(Line::from(Span::styled(&result.message, msg_style)));
 
-    if !result.warnings.is_empty() {
+    let issue_count = result.issue_count();
+    if issue_count > 0 {
         lines.push(Line::from("")); // blank line
 
         let header_style = Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD);
         lines.push(Line::from(Span::styled(
-            format!("Warnings ({}):", result.warnings.len()),
+            format!("Warnings ({issue_count}):"),
             header_style,
         )));
 
-        let warning_style = Style::default().fg(Color::DarkGray);
-        for warning in result.warnings.iter().skip(scroll).take(10) {
+        let failures = result.failures.iter().map(|failure| {
+            let text = format!("[{}] failed: {}", failure.source_label, failure.error);
+            // Refusals the user can fix stand apart from outright failures.
+            let color = match failure.error.kind() {
+                ErrorKind::Unauthorized | ErrorKind::RateLimited => Color::Yellow,
+                _ => Color::Red,
+            };
+            (text, Style::default().fg(color))
+        });
+        let warnings = result
+            .warnings
+            .iter()
+            .map(|warning| (warning.clone(), Style::default().fg(Color::DarkGray)));
+        let first = selected.saturating_sub(9);
+        for (i, (text, style)) in failures.chain(warnings).enumerate().skip(first).take(10) {
             // Truncate long warnings
-            let display = if warning.len() > 55 {
-                format!("  {}...", &warning[..52])
+            let text = if text.chars().count() > 55 {
+                format!("{}...", text.chars().take(52).collect::<String>())
+            } else {
+                text
+            };
+            let line = if i == selected {
+                Span::styled(format!("> {text}"), style.add_modifier(Modifier::REVERSED))
             } else {
-                format!("  {}", warning)
+                Span::styled(format!("  {text}"), style)
             };
-            lines.push(Line::from(Span::styled(display, warning_style)));
+            lines.push(Line::from(line));
         }
 
-        if result.warnings.len() > 10 {
-            let more = result.warnings.len().saturating_sub(scroll + 10);
+        if issue_count > 10 {
+            let more = issue_count.saturating_sub(first + 10);
             if more > 0 {
                 lines.push(Line::from(Span::styled(
                     format!("  ... and {} more (j/k to scroll)", more),
@@ -97,13 +165,35 @@ fn render_result(frame: &mut Frame, area: Rect, result: &crate::SyncResult, scro
     }
 
     lines.push(Line::from("")); // blank line
+    if let Some(guidance) = guidance(result, selected) {
+        lines.push(Line::from(Span::styled(
+            format!("hint: {guidance}"),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
     let hint_style = Style::default().fg(Color::DarkGray);
-    lines.push(Line::from(Span::styled("Press Enter to dismiss", hint_style)));
+    let hint = if result.failures.get(selected).is_some() {
+        "Press r to retry this source, Enter to dismiss"
+    } else {
+        "Press Enter to dismiss"
+    };
+    lines.push(Line::from(Span::styled(hint, hint_style)));
 
     let paragraph = Paragraph::new(lines);
     frame.render_widget(paragraph, area);
 }
 
+/// What to do about the selected failure, when it's a refusal such as a
+/// missing token or a rate limit. Worked out as it's drawn, so the time
+/// left on a rate limit counts down.
+fn guidance(result: &crate::SyncResult, selected: usize) -> Option<String> {
+    let failure = result.failures.get(selected)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    agent_defs::guidance(&failure.error, now)
+}
+
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center);
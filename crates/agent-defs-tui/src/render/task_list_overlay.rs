@@ -0,0 +1,71 @@
+use agent_defs::TaskState;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+
+/// Most tasks shown at once; the list scrolls to keep the selection in view.
+const VISIBLE_ROWS: usize = 15;
+
+pub fn popup(area: Rect, app: &App) -> Rect {
+    let rows = app.tasks.tasks().len().clamp(1, VISIBLE_ROWS) as u16;
+    let height = (rows + 2).min(area.height);
+    let width = 70u16.min(area.width.saturating_sub(4));
+    super::centered_rect_fixed(width, height, area)
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let popup_area = popup(area, app);
+    frame.render_widget(Clear, popup_area);
+
+    let tasks = app.tasks.tasks();
+    let title = match app.tasks.pending() {
+        0 => " Tasks ".to_owned(),
+        waiting => format!(" Tasks ({waiting} waiting) "),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if tasks.is_empty() {
+        let empty = Paragraph::new(Span::styled(
+            "  No syncs or installs yet this session",
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let skip = (app.task_cursor + 1).saturating_sub(VISIBLE_ROWS);
+    let lines: Vec<Line> = tasks
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .take(VISIBLE_ROWS)
+        .map(|(i, task)| {
+            let (marker, color, detail) = match &task.state {
+                TaskState::Pending => ("\u{2026}", Color::DarkGray, "waiting"),
+                TaskState::Running => ("\u{25b6}", Color::Yellow, "running"),
+                TaskState::Done(summary) => ("\u{2713}", Color::Green, summary.as_str()),
+                TaskState::Failed(error) => ("\u{2717}", Color::Red, error.as_str()),
+                TaskState::Cancelled => ("-", Color::DarkGray, "cancelled"),
+            };
+            let mut label_style = Style::default();
+            if i == app.task_cursor {
+                label_style = label_style.add_modifier(Modifier::REVERSED);
+            }
+            Line::from(vec![
+                Span::styled(format!(" {marker} "), Style::default().fg(color)),
+                Span::styled(task.label.clone(), label_style),
+                Span::styled(format!("  {detail}"), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
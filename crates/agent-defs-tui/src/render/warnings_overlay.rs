@@ -0,0 +1,68 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+
+/// Most rows shown at once; the rest are reached by scrolling.
+const VISIBLE_ROWS: usize = 18;
+
+pub fn popup(area: Rect, app: &App) -> Rect {
+    let rows = app.skipped_rows().clamp(1, VISIBLE_ROWS) as u16;
+    let height = (rows + 2).min(area.height);
+    let width = 76u16.min(area.width.saturating_sub(4));
+    super::centered_rect_fixed(width, height, area)
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let popup_area = popup(area, app);
+    frame.render_widget(Clear, popup_area);
+
+    let title = format!(" Skipped files ({}) ", app.skipped.len());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if app.skipped.is_empty() {
+        let empty = Paragraph::new(Span::styled(
+            "  The last sync of every source kept all its files",
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let heading_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let reason_style = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::with_capacity(app.skipped_rows());
+    for (i, file) in app.skipped.iter().enumerate() {
+        if i == 0 || app.skipped[i - 1].source_label != file.source_label {
+            let count = app
+                .skipped
+                .iter()
+                .filter(|s| s.source_label == file.source_label)
+                .count();
+            lines.push(Line::from(Span::styled(
+                format!(" [{}] {count} skipped", file.source_label),
+                heading_style,
+            )));
+        }
+        lines.push(Line::from(Span::styled(
+            format!("   {}", file.path),
+            Style::default().fg(Color::Yellow),
+        )));
+        lines.push(Line::from(Span::styled(format!("     {}", file.reason), reason_style)));
+    }
+
+    let visible: Vec<Line> = lines
+        .into_iter()
+        .skip(app.skipped_scroll)
+        .take(VISIBLE_ROWS)
+        .collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+}
@@ -0,0 +1,78 @@
+use agent_defs::ChangeKind;
+use agent_defs::changelog::format_date;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+
+/// Most changes shown at once; the rest are reached by scrolling.
+const VISIBLE_CHANGES: usize = 15;
+
+pub fn popup(area: Rect, app: &App) -> Rect {
+    let rows = app.changes.len().clamp(1, VISIBLE_CHANGES) as u16;
+    let height = (rows + 2).min(area.height);
+    let width = 70u16.min(area.width.saturating_sub(4));
+    super::centered_rect_fixed(width, height, area)
+}
+
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    let popup_area = popup(area, app);
+    frame.render_widget(Clear, popup_area);
+
+    let title = if app.changes.len() > VISIBLE_CHANGES {
+        format!(
+            " What's new ({}-{} of {}) ",
+            app.changes_scroll + 1,
+            (app.changes_scroll + VISIBLE_CHANGES).min(app.changes.len()),
+            app.changes.len()
+        )
+    } else {
+        " What's new ".to_owned()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if app.changes.is_empty() {
+        let empty = Paragraph::new(Span::styled(
+            "  No changes recorded in the last week",
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let date_style = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = app
+        .changes
+        .iter()
+        .skip(app.changes_scroll)
+        .take(VISIBLE_CHANGES)
+        .map(|change| {
+            let color = match change.change {
+                ChangeKind::Added => Color::Green,
+                ChangeKind::Removed => Color::Red,
+                ChangeKind::Changed => Color::Yellow,
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", format_date(change.synced_at)), date_style),
+                Span::styled(
+                    format!("{} ", change.change.marker()),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    app.glyphs.prefix(&change.kind, &change.name),
+                    Style::default().fg(color),
+                ),
+                Span::styled(format!("  [{}]", change.source_label), date_style),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+}
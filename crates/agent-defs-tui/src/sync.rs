@@ -0,0 +1,255 @@
+//! How the TUI drives syncs. The host hands over a [`SyncController`] that
+//! knows its sources one by one, so the TUI can show each source's progress
+//! and freshness and retry a single source that failed.
+
+use agent_defs::{DefinitionChange, SkippedFile, SyncError};
+
+/// Which sources a sync covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncTarget {
+    /// Every configured source.
+    All,
+    /// One source, by label, such as a retry of one that failed.
+    Source(String),
+}
+
+/// How fresh a source's local cache is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    NeverSynced,
+    Fresh { days_old: u64 },
+    Stale { days_old: u64 },
+}
+
+impl Freshness {
+    /// A short description such as "synced today" or "9 days old".
+    pub fn describe(&self) -> String {
+        match self {
+            Self::NeverSynced => "never synced".to_owned(),
+            Self::Fresh { days_old: 0 } => "synced today".to_owned(),
+            Self::Fresh { days_old: 1 } | Self::Stale { days_old: 1 } => "1 day old".to_owned(),
+            Self::Fresh { days_old } | Self::Stale { days_old } => format!("{days_old} days old"),
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        !matches!(self, Self::Fresh { .. })
+    }
+}
+
+/// Which sources to sync, out of the ones a host has.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncSelection {
+    /// Labels to sync; every source when empty.
+    pub labels: Vec<String>,
+    /// Skip sources whose caches are still fresh.
+    pub only_stale: bool,
+}
+
+impl SyncSelection {
+    /// Whether a source labelled `label` that is `freshness` fresh gets synced.
+    pub fn includes(&self, label: &str, freshness: Freshness) -> bool {
+        (self.labels.is_empty() || self.labels.iter().any(|l| l == label))
+            && (!self.only_stale || freshness.is_stale())
+    }
+
+    /// The first label asked for that none of `known` has.
+    pub fn unknown<'a>(&'a self, known: &[&str]) -> Option<&'a str> {
+        self.labels
+            .iter()
+            .map(String::as_str)
+            .find(|label| !known.contains(label))
+    }
+}
+
+/// What syncing one source did.
+#[derive(Debug, Clone, Default)]
+pub struct SourceReport {
+    pub synced: u64,
+    pub skipped: u64,
+    /// Warnings encountered during the sync.
+    pub warnings: Vec<String>,
+    /// Definitions the sync added, removed, or changed.
+    pub changes: Vec<DefinitionChange>,
+    /// Every file the source now skips, replacing its earlier list.
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// Progress of a [`SyncController::sync_all`] run.
+#[derive(Debug)]
+pub enum SyncEvent {
+    /// A source started syncing.
+    Started(String),
+    /// A source finished, with its report or the reason it failed.
+    Finished(String, Result<SourceReport, SyncError>),
+}
+
+/// The host's sources, as far as syncing them goes.
+#[async_trait::async_trait]
+pub trait SyncController: Send + Sync {
+    /// Labels of the sources that can be synced, in the order they sync.
+    fn sources(&self) -> Vec<String>;
+
+    /// How fresh a source's cache is.
+    fn status(&self, label: &str) -> Freshness;
+
+    /// Sync one source by label.
+    async fn sync_source(&self, label: &str) -> anyhow::Result<SourceReport>;
+
+    /// Labels of the sources `selection` covers, in the order they sync.
+    fn selected(&self, selection: &SyncSelection) -> Vec<String> {
+        self.sources()
+            .into_iter()
+            .filter(|label| selection.includes(label, self.status(label)))
+            .collect()
+    }
+
+    /// Sync every source, one after another, reporting each as it starts
+    /// and finishes. One source failing doesn't stop the rest.
+    async fn sync_all(&self, progress: &(dyn Fn(SyncEvent) + Send + Sync)) {
+        for label in self.sources() {
+            progress(SyncEvent::Started(label.clone()));
+            let result = self.sync_source(&label).await.map_err(sync_error);
+            progress(SyncEvent::Finished(label, result));
+        }
+    }
+}
+
+/// The [`SyncError`] behind a failed [`SyncController::sync_source`], or
+/// the failure's message when it didn't come from a provider.
+pub fn sync_error(error: anyhow::Error) -> SyncError {
+    match error.downcast::<SyncError>() {
+        Ok(error) => error,
+        Err(error) => SyncError::Other(error.to_string()),
+    }
+}
+
+/// A source whose sync failed outright.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncFailure {
+    pub source_label: String,
+    pub error: SyncError,
+}
+
+/// Result of a sync operation.
+#[derive(Debug, Clone, Default)]
+pub struct SyncResult {
+    /// Summary message (e.g., "Synced 50 definitions (5 skipped)").
+    pub message: String,
+    /// Sources that failed to sync; the overlay offers to retry each one.
+    pub failures: Vec<SyncFailure>,
+    /// Warnings encountered during sync.
+    pub warnings: Vec<String>,
+    /// Definitions the sync added, removed, or changed.
+    pub changes: Vec<DefinitionChange>,
+    /// Definitions synced and files skipped, across every source.
+    pub synced: u64,
+    pub skipped: u64,
+}
+
+impl SyncResult {
+    /// Lines listed under the summary: failures first, then warnings.
+    pub fn issue_count(&self) -> usize {
+        self.failures.len() + self.warnings.len()
+    }
+
+    /// Fold one source's outcome into the totals.
+    pub fn add(&mut self, label: &str, result: Result<SourceReport, SyncError>) {
+        match result {
+            Ok(report) => {
+                self.synced += report.synced;
+                self.skipped += report.skipped;
+                self.warnings.extend(report.warnings);
+                self.changes.extend(report.changes);
+            }
+            Err(error) => self.failures.push(SyncFailure {
+                source_label: label.to_owned(),
+                error,
+            }),
+        }
+        self.message = self.summary();
+    }
+
+    fn summary(&self) -> String {
+        let mut msg = format!("Synced {} definitions ({} skipped)", self.synced, self.skipped);
+        if !self.changes.is_empty() {
+            msg.push_str(&format!(", {} change(s)", self.changes.len()));
+        }
+        if !self.warnings.is_empty() {
+            msg.push_str(&format!(", {} warning(s)", self.warnings.len()));
+        }
+        if !self.failures.is_empty() {
+            msg.push_str(&format!(", {} source(s) failed", self.failures.len()));
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_error_keeps_provider_errors() {
+        let refused = anyhow::Error::new(SyncError::Unauthorized("bad credentials".into()));
+        assert_eq!(sync_error(refused), SyncError::Unauthorized("bad credentials".into()));
+        assert_eq!(
+            sync_error(anyhow::anyhow!("disk full")),
+            SyncError::Other("disk full".into())
+        );
+    }
+
+    #[test]
+    fn selections_filter_by_label_and_freshness() {
+        let fresh = Freshness::Fresh { days_old: 1 };
+        let stale = Freshness::Stale { days_old: 9 };
+        let all = SyncSelection::default();
+        assert!(all.includes("acme", fresh));
+
+        let stale_only = SyncSelection {
+            only_stale: true,
+            ..SyncSelection::default()
+        };
+        assert!(!stale_only.includes("acme", fresh));
+        assert!(stale_only.includes("acme", stale));
+        assert!(stale_only.includes("acme", Freshness::NeverSynced));
+
+        let named = SyncSelection {
+            labels: vec!["acme".into(), "typo".into()],
+            only_stale: false,
+        };
+        assert!(named.includes("acme", fresh));
+        assert!(!named.includes("other", stale));
+        assert_eq!(named.unknown(&["acme", "other"]), Some("typo"));
+        assert_eq!(all.unknown(&["acme"]), None);
+    }
+
+    #[test]
+    fn results_add_up_across_sources() {
+        let mut result = SyncResult::default();
+        let report = SourceReport {
+            synced: 5,
+            skipped: 1,
+            warnings: vec!["skipping agents/a.md: bad frontmatter".into()],
+            ..SourceReport::default()
+        };
+        result.add("one", Ok(report));
+        result.add("two", Err(SyncError::Timeout("no answer".into())));
+
+        assert_eq!(
+            result.message,
+            "Synced 5 definitions (1 skipped), 1 warning(s), 1 source(s) failed"
+        );
+        assert_eq!(result.failures[0].source_label, "two");
+        assert_eq!(result.issue_count(), 2);
+    }
+
+    #[test]
+    fn freshness_reads_naturally() {
+        assert_eq!(Freshness::NeverSynced.describe(), "never synced");
+        assert_eq!(Freshness::Fresh { days_old: 0 }.describe(), "synced today");
+        assert_eq!(Freshness::Stale { days_old: 9 }.describe(), "9 days old");
+        assert!(Freshness::Stale { days_old: 9 }.is_stale());
+        assert!(!Freshness::Fresh { days_old: 1 }.is_stale());
+    }
+}
@@ -0,0 +1,148 @@
+//! Whole sessions run headlessly: an in-memory source, a test terminal,
+//! and keys from a demo script or an injected event stream.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use agent_defs::test_support::{InMemorySource, definition};
+use agent_defs::{CompositeSource, Source};
+use agent_defs_tui::demo::DemoScript;
+use agent_defs_tui::{Freshness, SourceReport, SyncController, TuiOptions};
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+/// Sources that are always fresh and can't be synced.
+struct NoSync;
+
+#[async_trait::async_trait]
+impl SyncController for NoSync {
+    fn sources(&self) -> Vec<String> {
+        vec!["test".into()]
+    }
+
+    fn status(&self, _label: &str) -> Freshness {
+        Freshness::Fresh { days_old: 0 }
+    }
+
+    async fn sync_source(&self, label: &str) -> anyhow::Result<SourceReport> {
+        anyhow::bail!("{label} can't sync in tests")
+    }
+}
+
+/// One source behind a composite, as the CLI serves them, so the session
+/// fetches by qualified ID.
+fn source() -> Arc<dyn Source> {
+    let source = InMemorySource::new("test")
+        .with(definition("agents/review/reviewer.md"))
+        .with(definition("commands/git/review-pr.md"))
+        .with(definition("commands/ops/deploy.md"));
+    Arc::new(CompositeSource::from_sources([source]))
+}
+
+/// Run a session to the end with `keys` sent in, returning how it ended
+/// and its last screen.
+async fn session(options: TuiOptions, keys: &[KeyCode]) -> (anyhow::Result<()>, String) {
+    let (tx, events) = futures::channel::mpsc::unbounded();
+    for &code in keys {
+        tx.unbounded_send(Ok(Event::Key(KeyEvent::from(code)))).unwrap();
+    }
+    drop(tx);
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    let result =
+        agent_defs_tui::run_with(&mut terminal, events, source(), Arc::new(NoSync), options).await;
+    (result, terminal.backend().to_string())
+}
+
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(files_under(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[tokio::test]
+async fn search_filter_and_install() {
+    let target = tempfile::tempdir().unwrap();
+    let script = DemoScript::parse(
+        "delay 0ms\n\
+         type /review\n\
+         press enter\n\
+         press f\n\
+         press down 2\n\
+         press enter\n\
+         expect You are review-pr.\n\
+         press I\n\
+         expect Installed to\n",
+    )
+    .unwrap();
+    let options = TuiOptions {
+        install_target: Some(target.path().to_owned()),
+        skip_quick_install_confirm: true,
+        demo_script: Some(script),
+        ..TuiOptions::default()
+    };
+
+    let (result, screen) = session(options, &[]).await;
+    result.unwrap();
+    assert!(screen.contains("review-pr"), "{screen}");
+    assert!(!screen.contains("deploy"), "{screen}");
+
+    let installed = files_under(target.path());
+    assert_eq!(installed.len(), 1, "{installed:?}");
+    assert!(installed[0].ends_with("review-pr.md"), "{installed:?}");
+    let content = std::fs::read_to_string(&installed[0]).unwrap();
+    assert!(content.contains("You are review-pr."), "{content}");
+}
+
+#[tokio::test]
+async fn holding_a_key_fetches_only_where_the_cursor_rests() {
+    let ids = (1..=8).map(|n| format!("agents/a{n}.md")).collect::<Vec<_>>();
+    let memory = Arc::new(InMemorySource::new("test").with_ids(ids.iter().map(String::as_str)));
+    let source: Arc<dyn Source> = Arc::new(CompositeSource::new(vec![memory.clone()]));
+    // Keys at a typical repeat rate, then a pause well past the debounce so
+    // the last row has loaded by the end.
+    let script = DemoScript::parse("delay 40ms\npress j 6\npause 1s\n").unwrap();
+    let options = TuiOptions {
+        demo_script: Some(script),
+        ..TuiOptions::default()
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    let events = futures::channel::mpsc::unbounded().1;
+    agent_defs_tui::run_with(&mut terminal, events, source, Arc::new(NoSync), options)
+        .await
+        .unwrap();
+    // The first row on opening, then the one the cursor stopped on.
+    assert_eq!(memory.fetches(), 2);
+    let screen = terminal.backend().to_string();
+    assert!(screen.contains("You are a"), "{screen}");
+}
+
+#[tokio::test]
+async fn injected_keys_drive_the_session() {
+    let keys = [KeyCode::Char('G'), KeyCode::Char('q')];
+    let (result, screen) = session(TuiOptions::default(), &keys).await;
+    result.unwrap();
+    assert!(screen.contains("Commands (2)"), "{screen}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_missed_expectation_fails_the_session() {
+    let script = DemoScript::parse("expect Nothing like this\n").unwrap();
+    let options = TuiOptions {
+        demo_script: Some(script),
+        ..TuiOptions::default()
+    };
+
+    let (result, _) = session(options, &[]).await;
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("\"Nothing like this\" didn't appear"), "{error}");
+}
@@ -0,0 +1,53 @@
+//! Frontmatter parsing and definition building over the synthetic corpus.
+//!
+//! Run with `cargo bench -p agent-defs --bench parse`.
+
+use agent_defs::fixtures::{self, BENCH_DEFINITIONS};
+use agent_defs::{DefinitionId, builder, path};
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+
+fn parse(c: &mut Criterion) {
+    let files = fixtures::corpus(BENCH_DEFINITIONS);
+    let markdown: Vec<_> = files
+        .iter()
+        .filter(|file| file.relative_path.ends_with(".md"))
+        .collect();
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(markdown.len() as u64));
+    group.bench_function("frontmatter", |b| {
+        b.iter(|| {
+            for file in &markdown {
+                black_box(agent_defs::parse_frontmatter(&file.content).unwrap());
+            }
+        })
+    });
+
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.bench_function("build_definition", |b| {
+        b.iter(|| {
+            for file in &files {
+                let (name, kind, category) = path::parse_relative_path(&file.relative_path);
+                let id = DefinitionId::new(&file.relative_path);
+                black_box(
+                    builder::build_definition(
+                        &id,
+                        &file.content,
+                        &file.relative_path,
+                        name,
+                        kind,
+                        category,
+                        "bench",
+                    )
+                    .unwrap(),
+                );
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -1,8 +1,22 @@
 use std::collections::HashMap;
 
 use serde::Deserialize;
+use serde_json::{Map, Value};
 
-use crate::{Definition, DefinitionId, DefinitionKind, SourceError};
+use crate::{Definition, DefinitionId, DefinitionKind, Frontmatter, SourceError};
+
+/// Frontmatter fields only slash commands use.
+const COMMAND_FIELDS: [&str; 2] = ["allowed-tools", "argument-hint"];
+
+/// Top-level keys of a Claude Code `settings.json`.
+const SETTINGS_KEYS: [&str; 6] = [
+    "permissions",
+    "env",
+    "statusLine",
+    "apiKeyHelper",
+    "outputStyle",
+    "cleanupPeriodDays",
+];
 
 /// Schema for JSON-based definition files.
 #[derive(Debug, Deserialize)]
@@ -16,6 +30,10 @@ pub struct JsonDefinition {
 }
 
 /// Builds a `Definition` from raw markdown content with optional frontmatter.
+///
+/// `kind` is the guess from the file's path. A kind the frontmatter declares
+/// (`type: agent`) or implies (`tools` for an agent, `allowed-tools` or
+/// `argument-hint` for a command) is preferred over it.
 pub fn build_markdown_definition(
     id: &DefinitionId,
     raw_content: &str,
@@ -24,8 +42,16 @@ pub fn build_markdown_definition(
     category: Option<String>,
     source_label: &str,
 ) -> Result<Definition, SourceError> {
-    let parsed =
-        crate::parse_frontmatter(raw_content).map_err(|e| SourceError::Parse(e.to_string()))?;
+    let parsed = crate::parse_frontmatter(raw_content).map_err(|e| match e {
+        crate::frontmatter::FrontmatterError::InvalidYaml { message, location } => {
+            SourceError::Parse {
+                message: format!("invalid YAML in frontmatter: {message}"),
+                location,
+            }
+        }
+    })?;
+
+    let kind = prefer_inferred(kind, parsed.frontmatter.as_ref().and_then(infer_markdown_kind));
 
     let (name, description, tools, model, metadata) = match &parsed.frontmatter {
         Some(fm) => {
@@ -58,6 +84,11 @@ pub fn build_markdown_definition(
 }
 
 /// Builds a `Definition` from raw JSON content.
+///
+/// A leading UTF-8 BOM is ignored, since `serde_json` rejects it. As with
+/// markdown, a kind the JSON declares or implies beats the path's guess:
+/// `mcpServers` makes an MCP server, `hooks` a hook, and settings keys such
+/// as `permissions` a setting.
 pub fn build_json_definition(
     id: &DefinitionId,
     raw_content: &str,
@@ -66,17 +97,29 @@ pub fn build_json_definition(
     category: Option<String>,
     source_label: &str,
 ) -> Result<Definition, SourceError> {
-    let json_def: JsonDefinition = serde_json::from_str(raw_content)
-        .map_err(|e| SourceError::Parse(format!("JSON parse failed: {e}")))?;
+    let json_text = raw_content.strip_prefix('\u{feff}').unwrap_or(raw_content);
+    let value: Value = serde_json::from_str(json_text).map_err(|e| SourceError::Parse {
+        message: format!(
+            "JSON parse failed: {}",
+            crate::source::strip_location(&e.to_string())
+        ),
+        location: (e.line() > 0).then(|| crate::Location {
+            line: e.line(),
+            column: e.column(),
+        }),
+    })?;
+    let json_def = JsonDefinition::deserialize(&value)
+        .map_err(|e| SourceError::parse(format!("JSON parse failed: {e}")))?;
+    let inferred = match json_def.kind {
+        Some(declared) => Some(DefinitionKind::parse(&declared)),
+        None => value.as_object().and_then(infer_json_kind),
+    };
 
     Ok(Definition {
         id: id.clone(),
         name: json_def.name.unwrap_or(path_name),
         description: json_def.description,
-        kind: json_def
-            .kind
-            .map(|k| DefinitionKind::parse(&k))
-            .unwrap_or(kind),
+        kind: prefer_inferred(kind, inferred),
         category,
         source_label: source_label.to_owned(),
         body: raw_content.to_owned(),
@@ -87,6 +130,54 @@ pub fn build_json_definition(
     })
 }
 
+/// The kind markdown frontmatter declares with `type` or `kind`, or else
+/// implies by the fields it sets.
+fn infer_markdown_kind(frontmatter: &Frontmatter) -> Option<DefinitionKind> {
+    let declared = ["type", "kind"]
+        .iter()
+        .find_map(|key| frontmatter.extras.get(*key)?.as_str())
+        .map(DefinitionKind::parse);
+    if let Some(kind) = declared.filter(|kind| !matches!(kind, DefinitionKind::Other(_))) {
+        return Some(kind);
+    }
+    if COMMAND_FIELDS.iter().any(|field| frontmatter.extras.contains_key(*field)) {
+        return Some(DefinitionKind::Command);
+    }
+    frontmatter.tools.is_some().then_some(DefinitionKind::Agent)
+}
+
+/// The kind a JSON definition's top-level keys imply.
+fn infer_json_kind(object: &Map<String, Value>) -> Option<DefinitionKind> {
+    if let Some(declared) = object.get("type").and_then(Value::as_str) {
+        let kind = DefinitionKind::parse(declared);
+        if !matches!(kind, DefinitionKind::Other(_)) {
+            return Some(kind);
+        }
+    }
+    if object.contains_key("mcpServers") {
+        Some(DefinitionKind::Mcp)
+    } else if object.contains_key("hooks") {
+        Some(DefinitionKind::Hook)
+    } else if SETTINGS_KEYS.iter().any(|key| object.contains_key(*key)) {
+        Some(DefinitionKind::Setting)
+    } else if object.contains_key("tools") {
+        Some(DefinitionKind::Agent)
+    } else {
+        None
+    }
+}
+
+/// The inferred kind if there is one, otherwise the path's guess. A skill's
+/// `SKILL.md` stays a skill whatever its frontmatter says, since skills are
+/// known by their directory layout.
+fn prefer_inferred(path_kind: DefinitionKind, inferred: Option<DefinitionKind>) -> DefinitionKind {
+    match (path_kind, inferred) {
+        (DefinitionKind::Skill, _) => DefinitionKind::Skill,
+        (_, Some(inferred)) => inferred,
+        (path_kind, None) => path_kind,
+    }
+}
+
 /// Builds a `Definition` from raw content, choosing markdown or JSON based on file extension.
 pub fn build_definition(
     id: &DefinitionId,
@@ -217,6 +308,29 @@ You are a senior software architect.
         assert_eq!(def.name, "unnamed");
     }
 
+    #[test]
+    fn json_parse_errors_carry_a_location() {
+        let id = DefinitionId::new("agents/broken.json");
+        let raw = "{\n  \"name\": \"broken\",\n  \"tools\": [\n}";
+        let err = build_json_definition(
+            &id,
+            raw,
+            "broken".into(),
+            DefinitionKind::Agent,
+            None,
+            "test-source",
+        )
+        .unwrap_err();
+
+        let SourceError::Parse { message, location } = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert!(!message.contains(" at line "), "{message}");
+        assert_eq!(location.map(|l| l.line), Some(4));
+        assert_eq!(err.kind(), crate::ErrorKind::Parse);
+        assert!(err.to_string().starts_with("parse error at line 4, column "));
+    }
+
     #[test]
     fn build_definition_routes_json_by_extension() {
         let raw = r#"{"name":"test","tools":[]}"#;
@@ -276,4 +390,136 @@ Body.
 
         assert_eq!(def.metadata.get("custom_field").unwrap(), "custom_value");
     }
+
+    #[test]
+    fn builds_markdown_from_crlf_fixture() {
+        let raw = include_str!("../tests/fixtures/crlf_agent.md");
+        let id = DefinitionId::new("agents/review/crlf_agent.md");
+        let def = build_markdown_definition(
+            &id,
+            raw,
+            "crlf_agent".into(),
+            DefinitionKind::Agent,
+            Some("review".into()),
+            "test-source",
+        )
+        .unwrap();
+
+        assert_eq!(def.name, "Windows Reviewer");
+        assert_eq!(def.description.as_deref(), Some("Reviews pull requests"));
+        assert_eq!(def.tools, vec!["Read", "Grep"]);
+        assert_eq!(def.model.as_deref(), Some("sonnet"));
+        assert_eq!(def.body, "\nYou review code.\nBe thorough.\n");
+        assert_eq!(def.raw, raw, "raw content is preserved byte-for-byte");
+    }
+
+    #[test]
+    fn builds_markdown_from_bom_fixture() {
+        let raw = include_str!("../tests/fixtures/bom_crlf_agent.md");
+        let id = DefinitionId::new("agents/bom_crlf_agent.md");
+        let def = build_markdown_definition(
+            &id,
+            raw,
+            "bom_crlf_agent".into(),
+            DefinitionKind::Agent,
+            None,
+            "test-source",
+        )
+        .unwrap();
+
+        assert_eq!(def.name, "BOM Agent");
+        assert_eq!(def.description.as_deref(), Some("Saved by Notepad"));
+        assert_eq!(def.body, "Hello from Windows.\n");
+    }
+
+    #[test]
+    fn builds_markdown_from_tab_indented_fixture() {
+        let raw = include_str!("../tests/fixtures/tab_indented_agent.md");
+        let id = DefinitionId::new("agents/tab_indented_agent.md");
+        let def = build_markdown_definition(
+            &id,
+            raw,
+            "tab_indented_agent".into(),
+            DefinitionKind::Agent,
+            None,
+            "test-source",
+        )
+        .unwrap();
+
+        assert_eq!(def.name, "Tabbed Agent");
+        assert_eq!(def.tools, vec!["Read"]);
+        assert_eq!(def.body, "Body.\n");
+    }
+
+    /// Build `raw` the way a sync does, guessing the kind from `path` first.
+    fn kind_of(path: &str, raw: &str) -> DefinitionKind {
+        let (name, kind, category) = if crate::path::is_skill_entry_point(path) {
+            crate::path::parse_skill_path(path)
+        } else {
+            crate::path::parse_relative_path(path)
+        };
+        build_definition(&DefinitionId::new(path), raw, path, name, kind, category, "test")
+            .unwrap()
+            .kind
+    }
+
+    #[test]
+    fn curated_layouts_keep_their_kinds() {
+        // claude-code-templates
+        let agent = "---\nname: reviewer\ntools: Read, Grep\nmodel: sonnet\n---\nReview.\n";
+        assert_eq!(kind_of("agents/quality/reviewer.md", agent), DefinitionKind::Agent);
+        let command = "---\nallowed-tools: Bash(git:*)\nargument-hint: [message]\n---\nCommit.\n";
+        assert_eq!(kind_of("commands/git/commit.md", command), DefinitionKind::Command);
+        let hook = r#"{"description": "Lint", "hooks": {"PostToolUse": []}}"#;
+        assert_eq!(kind_of("hooks/quality/lint.json", hook), DefinitionKind::Hook);
+        let mcp = r#"{"mcpServers": {"github": {"command": "npx"}}}"#;
+        assert_eq!(kind_of("mcps/devtools/github.json", mcp), DefinitionKind::Mcp);
+        let setting = r#"{"description": "Strict", "permissions": {"deny": ["WebFetch"]}}"#;
+        assert_eq!(kind_of("settings/security/strict.json", setting), DefinitionKind::Setting);
+        let skill = "---\nname: pdf\nallowed-tools: Read\n---\nPDFs.\n";
+        assert_eq!(kind_of("skills/documents/pdf/SKILL.md", skill), DefinitionKind::Skill);
+
+        // awesome-claude-code-subagents, after its paths are rewritten
+        let subagent = "---\nname: api-designer\ntools: Read, Write\n---\nDesign APIs.\n";
+        assert_eq!(
+            kind_of("agents/core-development/api-designer.md", subagent),
+            DefinitionKind::Agent
+        );
+    }
+
+    #[test]
+    fn content_beats_an_unfamiliar_layout() {
+        let declared = "---\nname: reviewer\ntype: agent\n---\nReview.\n";
+        assert_eq!(kind_of("prompts/reviewer.md", declared), DefinitionKind::Agent);
+        let tools = "---\ntools: Read\n---\nHelp.\n";
+        assert_eq!(kind_of("helper.md", tools), DefinitionKind::Agent);
+        let command = "---\nargument-hint: <file>\n---\nExplain $1.\n";
+        assert_eq!(kind_of("agents/explain.md", command), DefinitionKind::Command);
+        let mcp = r#"{"mcpServers": {}}"#;
+        assert_eq!(kind_of("servers.json", mcp), DefinitionKind::Mcp);
+
+        // Nothing to go on, or a type we don't know: the path's guess stands.
+        assert_eq!(kind_of("notes/todo.md", "Just text.\n"), DefinitionKind::Other("notes".into()));
+        let unknown = "---\ntype: workflow\n---\nSteps.\n";
+        assert_eq!(kind_of("hooks/steps.md", unknown), DefinitionKind::Hook);
+    }
+
+    #[test]
+    fn builds_json_from_bom_fixture() {
+        let raw = include_str!("../tests/fixtures/bom_definition.json");
+        let id = DefinitionId::new("agents/bom_definition.json");
+        let def = build_definition(
+            &id,
+            raw,
+            "agents/bom_definition.json",
+            "bom_definition".into(),
+            DefinitionKind::Agent,
+            None,
+            "test-source",
+        )
+        .unwrap();
+
+        assert_eq!(def.name, "bom-json");
+        assert_eq!(def.tools, vec!["Read"]);
+    }
 }
@@ -0,0 +1,145 @@
+//! Category names, which vary from source to source for the same idea:
+//! one repo files tests under `testing-qa`, another under `tests`. A
+//! [`CategoryMap`] folds them into one name so filtering by category works
+//! across sources, and keeps the name the source used in the definition's
+//! metadata.
+
+use std::collections::HashMap;
+
+use crate::Definition;
+
+/// Metadata key holding the category a source gave before it was
+/// normalized. Only set when normalizing changed it.
+pub const ORIGINAL_CATEGORY_KEY: &str = "original_category";
+
+/// Built-in synonyms: each canonical category and the names that fold into it.
+const SYNONYMS: &[(&str, &[&str])] = &[
+    (
+        "testing",
+        &["test", "tests", "testing-qa", "qa", "quality-assurance"],
+    ),
+    ("code-quality", &["quality", "code-review", "review", "linting"]),
+    ("documentation", &["docs", "doc", "documentation-writing"]),
+    (
+        "devops",
+        &["dev-ops", "devops-infrastructure", "infrastructure", "infra", "deployment"],
+    ),
+    ("security", &["sec", "security-audit", "appsec"]),
+    ("database", &["databases", "db", "data-storage"]),
+    ("frontend", &["front-end", "ui", "web-frontend"]),
+    ("backend", &["back-end"]),
+    ("development", &["dev", "development-team", "core-development"]),
+];
+
+/// Maps the categories sources use onto one name each: first the user's
+/// overrides, then the built-in synonyms. Names are compared as slugs, so
+/// `Testing QA`, `testing_qa`, and `testing-qa` are the same category.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryMap {
+    overrides: HashMap<String, String>,
+}
+
+impl CategoryMap {
+    /// A map with `overrides` (source category to category) taking
+    /// precedence over the built-in synonyms.
+    pub fn new(overrides: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            overrides: overrides
+                .into_iter()
+                .map(|(from, to)| (slug(&from), to))
+                .collect(),
+        }
+    }
+
+    /// The category `category` folds into.
+    pub fn normalize(&self, category: &str) -> String {
+        let slug = slug(category);
+        if let Some(to) = self.overrides.get(&slug) {
+            return to.clone();
+        }
+        SYNONYMS
+            .iter()
+            .find(|(_, synonyms)| synonyms.contains(&slug.as_str()))
+            .map(|(canonical, _)| (*canonical).to_owned())
+            .unwrap_or(slug)
+    }
+
+    /// Normalize `def`'s category, recording the original under
+    /// [`ORIGINAL_CATEGORY_KEY`] if it changed.
+    pub fn apply(&self, def: &mut Definition) {
+        let Some(category) = &def.category else {
+            return;
+        };
+        let normalized = self.normalize(category);
+        if normalized != *category {
+            def.metadata
+                .insert(ORIGINAL_CATEGORY_KEY.to_owned(), category.clone());
+            def.category = Some(normalized);
+        }
+    }
+}
+
+/// `category` lowercased, with spaces and underscores as hyphens.
+fn slug(category: &str) -> String {
+    let mut slug = String::with_capacity(category.len());
+    for c in category.trim().chars() {
+        match c {
+            ' ' | '_' | '-' if slug.is_empty() || slug.ends_with('-') => {}
+            ' ' | '_' => slug.push('-'),
+            c => slug.extend(c.to_lowercase()),
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefinitionId, DefinitionKind};
+
+    #[test]
+    fn synonyms_fold_into_one_category() {
+        let map = CategoryMap::default();
+        assert_eq!(map.normalize("testing-qa"), "testing");
+        assert_eq!(map.normalize("Tests"), "testing");
+        assert_eq!(map.normalize("Code_Review"), "code-quality");
+        assert_eq!(map.normalize("Data  Science"), "data-science");
+        assert_eq!(map.normalize("testing"), "testing");
+    }
+
+    #[test]
+    fn overrides_win_over_synonyms() {
+        let map = CategoryMap::new(HashMap::from([
+            ("Quality".to_owned(), "testing".to_owned()),
+            ("ml-ops".to_owned(), "machine-learning".to_owned()),
+        ]));
+        assert_eq!(map.normalize("quality"), "testing");
+        assert_eq!(map.normalize("ML Ops"), "machine-learning");
+        assert_eq!(map.normalize("qa"), "testing");
+    }
+
+    #[test]
+    fn apply_keeps_the_original_in_metadata() {
+        let mut def = Definition {
+            id: DefinitionId::new("agents/tests/runner.md"),
+            name: "runner".into(),
+            description: None,
+            kind: DefinitionKind::Agent,
+            category: Some("tests".into()),
+            source_label: "test".into(),
+            body: String::new(),
+            tools: vec![],
+            model: None,
+            metadata: HashMap::new(),
+            raw: String::new(),
+        };
+        CategoryMap::default().apply(&mut def);
+        assert_eq!(def.category.as_deref(), Some("testing"));
+        assert_eq!(def.metadata[ORIGINAL_CATEGORY_KEY], "tests");
+
+        let mut unchanged = def.clone();
+        unchanged.metadata.clear();
+        CategoryMap::default().apply(&mut unchanged);
+        assert!(unchanged.metadata.is_empty());
+    }
+}
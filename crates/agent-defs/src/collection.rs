@@ -0,0 +1,230 @@
+//! Named, shareable lists of definitions drawn from any number of sources.
+//!
+//! A collection only records IDs (optionally pinned to a source label), so it
+//! stays valid as sources re-sync and can be exported as a small JSON file.
+
+use crate::{DefinitionId, DefinitionSummary};
+
+/// A named set of definitions, e.g. `backend-starter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collection {
+    pub name: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub items: Vec<CollectionItem>,
+}
+
+/// One member of a collection. Without a `source`, the ID matches in any
+/// source that has it.
+///
+/// With the `serde` feature an item reads either as a bare ID string or as
+/// `{ id, source }`, and is written in the shortest of those forms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(from = "serde_repr::ItemRepr")
+)]
+pub struct CollectionItem {
+    pub id: DefinitionId,
+    pub source: Option<String>,
+}
+
+impl CollectionItem {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: DefinitionId::new(id),
+            source: None,
+        }
+    }
+
+    pub fn from_source(id: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            id: DefinitionId::new(id),
+            source: Some(source.into()),
+        }
+    }
+
+    /// True if `summary` is this item.
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        summary.id == self.id
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| *source == summary.source_label)
+    }
+}
+
+impl Collection {
+    /// True if `summary` is one of this collection's items.
+    pub fn contains(&self, summary: &DefinitionSummary) -> bool {
+        self.items.iter().any(|item| item.matches(summary))
+    }
+
+    /// Match items against `summaries`, in item order. An unpinned item found
+    /// in several sources resolves to the first. Items with no match are
+    /// returned separately.
+    pub fn resolve<'a>(
+        &'a self,
+        summaries: &'a [DefinitionSummary],
+    ) -> (Vec<&'a DefinitionSummary>, Vec<&'a CollectionItem>) {
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for item in &self.items {
+            match summaries.iter().find(|summary| item.matches(summary)) {
+                Some(summary) => found.push(summary),
+                None => missing.push(item),
+            }
+        }
+        (found, missing)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CollectionItem {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        match &self.source {
+            None => serializer.serialize_str(self.id.as_str()),
+            Some(source) => {
+                let mut item = serializer.serialize_struct("CollectionItem", 2)?;
+                item.serialize_field("id", &self.id)?;
+                item.serialize_field("source", source)?;
+                item.end()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_repr {
+    use super::CollectionItem;
+    use crate::DefinitionId;
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    pub enum ItemRepr {
+        Id(String),
+        Pinned { id: String, source: Option<String> },
+    }
+
+    impl From<ItemRepr> for CollectionItem {
+        fn from(repr: ItemRepr) -> Self {
+            match repr {
+                ItemRepr::Id(id) => Self::new(id),
+                ItemRepr::Pinned { id, source } => Self {
+                    id: DefinitionId::new(id),
+                    source,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefinitionKind;
+
+    fn summary(id: &str, source: &str) -> DefinitionSummary {
+        DefinitionSummary {
+            id: DefinitionId::new(id),
+            name: id.to_owned(),
+            description: None,
+            kind: DefinitionKind::Agent,
+            category: None,
+            source_label: source.to_owned(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    fn starter() -> Collection {
+        Collection {
+            name: "backend-starter".into(),
+            description: None,
+            items: vec![
+                CollectionItem::new("agents/api.md"),
+                CollectionItem::from_source("agents/db.md", "acme"),
+                CollectionItem::new("agents/gone.md"),
+            ],
+        }
+    }
+
+    #[test]
+    fn unpinned_item_matches_any_source() {
+        let collection = starter();
+        assert!(collection.contains(&summary("agents/api.md", "acme")));
+        assert!(collection.contains(&summary("agents/api.md", "other")));
+    }
+
+    #[test]
+    fn pinned_item_matches_only_its_source() {
+        let collection = starter();
+        assert!(collection.contains(&summary("agents/db.md", "acme")));
+        assert!(!collection.contains(&summary("agents/db.md", "other")));
+    }
+
+    #[test]
+    fn resolve_splits_found_and_missing_in_item_order() {
+        let summaries = vec![
+            summary("agents/db.md", "acme"),
+            summary("agents/api.md", "other"),
+            summary("agents/api.md", "acme"),
+        ];
+        let collection = starter();
+        let (found, missing) = collection.resolve(&summaries);
+
+        let found: Vec<_> = found
+            .iter()
+            .map(|s| (s.id.as_str(), s.source_label.as_str()))
+            .collect();
+        assert_eq!(found, [("agents/api.md", "other"), ("agents/db.md", "acme")]);
+        assert_eq!(missing, [&CollectionItem::new("agents/gone.md")]);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn items_read_as_strings_or_tables() {
+        let collection: Collection = serde_json::from_str(
+            r#"{"name":"starter","items":["agents/api.md",{"id":"agents/db.md","source":"acme"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            collection.items,
+            [
+                CollectionItem::new("agents/api.md"),
+                CollectionItem::from_source("agents/db.md", "acme"),
+            ]
+        );
+        assert_eq!(collection.description, None);
+    }
+
+    #[test]
+    fn round_trips_through_json_in_short_form() {
+        let collection = Collection {
+            name: "starter".into(),
+            description: Some("Backend basics".into()),
+            items: vec![
+                CollectionItem::new("agents/api.md"),
+                CollectionItem::from_source("agents/db.md", "acme"),
+            ],
+        };
+        let json = serde_json::to_value(&collection).unwrap();
+        assert_eq!(json["items"][0], "agents/api.md");
+        assert_eq!(json["items"][1]["source"], "acme");
+
+        let back: Collection = serde_json::from_value(json).unwrap();
+        assert_eq!(back, collection);
+    }
+}
@@ -12,6 +12,23 @@ impl CompositeSource {
     pub fn new(sources: Vec<Arc<dyn Source>>) -> Self {
         Self { sources }
     }
+
+    /// A composite of `sources`, which may be anything implementing
+    /// [`Source`]: stores, `Arc`s of them, `Box<dyn Source>`s, and so on.
+    pub fn from_sources<S: Source + 'static>(sources: impl IntoIterator<Item = S>) -> Self {
+        Self::new(
+            sources
+                .into_iter()
+                .map(|source| Arc::new(source) as Arc<dyn Source>)
+                .collect(),
+        )
+    }
+}
+
+impl<S: Source + 'static> FromIterator<S> for CompositeSource {
+    fn from_iter<I: IntoIterator<Item = S>>(sources: I) -> Self {
+        Self::from_sources(sources)
+    }
 }
 
 #[async_trait::async_trait]
@@ -36,15 +53,63 @@ impl Source for CompositeSource {
         Ok(all)
     }
 
+    /// Fetch from the source a qualified `label:path` ID names, or else from
+    /// the only source that has the ID. An ID in several sources is an
+    /// [`SourceError::Ambiguous`] error.
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
+        if let Some((source, id)) = self.qualified(id) {
+            return source.fetch(&id).await;
+        }
+        let mut found = Vec::new();
         for source in &self.sources {
             match source.fetch(id).await {
-                Ok(def) => return Ok(def),
+                Ok(def) => found.push(def),
                 Err(SourceError::NotFound(_)) => continue,
                 Err(e) => return Err(e),
             }
         }
-        Err(SourceError::NotFound(id.clone()))
+        if found.len() > 1 {
+            let sources = found.into_iter().map(|def| def.source_label).collect();
+            return Err(SourceError::Ambiguous {
+                id: id.clone(),
+                sources,
+            });
+        }
+        found.pop().ok_or_else(|| SourceError::NotFound(id.clone()))
+    }
+
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        if let Some((source, id)) = self.qualified(id) {
+            return source.fetch_raw(&id).await;
+        }
+        let mut found = Vec::new();
+        for source in &self.sources {
+            match source.fetch_raw(id).await {
+                Ok(raw) => found.push((source.label().to_owned(), raw)),
+                Err(SourceError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        if found.len() > 1 {
+            let sources = found.into_iter().map(|(label, _)| label).collect();
+            return Err(SourceError::Ambiguous {
+                id: id.clone(),
+                sources,
+            });
+        }
+        found
+            .pop()
+            .map(|(_, raw)| raw)
+            .ok_or_else(|| SourceError::NotFound(id.clone()))
+    }
+}
+
+impl CompositeSource {
+    /// The inner source a qualified `id` names, with the ID within it.
+    fn qualified(&self, id: &DefinitionId) -> Option<(&Arc<dyn Source>, DefinitionId)> {
+        let (label, id) = id.split_source(self.sources.iter().map(|s| s.label()))?;
+        let source = self.sources.iter().find(|s| s.label() == label)?;
+        Some((source, id))
     }
 }
 
@@ -82,7 +147,7 @@ mod tests {
         let mut src2 = InMemorySource::new("source-2");
         src2.add(make_def("beta", "source-2"));
 
-        let composite = CompositeSource::new(vec![Arc::new(src1), Arc::new(src2)]);
+        let composite = CompositeSource::from_sources([src1, src2]);
         let summaries = composite.list().await.unwrap();
         assert_eq!(summaries.len(), 2);
     }
@@ -95,7 +160,7 @@ mod tests {
         let mut src2 = InMemorySource::new("source-2");
         src2.add(make_def("beta", "source-2"));
 
-        let composite = CompositeSource::new(vec![Arc::new(src1), Arc::new(src2)]);
+        let composite = CompositeSource::from_sources([src1, src2]);
         let results = composite.search("alpha").await.unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].name, "alpha");
@@ -108,7 +173,7 @@ mod tests {
         let mut src2 = InMemorySource::new("source-2");
         src2.add(make_def("beta", "source-2"));
 
-        let composite = CompositeSource::new(vec![Arc::new(src1), Arc::new(src2)]);
+        let composite = CompositeSource::from_sources([src1, src2]);
         let def = composite.fetch(&DefinitionId::new("beta")).await.unwrap();
         assert_eq!(def.name, "beta");
     }
@@ -118,11 +183,72 @@ mod tests {
         let src1 = InMemorySource::new("source-1");
         let src2 = InMemorySource::new("source-2");
 
-        let composite = CompositeSource::new(vec![Arc::new(src1), Arc::new(src2)]);
+        let composite = CompositeSource::from_sources([src1, src2]);
         let result = composite.fetch(&DefinitionId::new("missing")).await;
         assert!(matches!(result, Err(SourceError::NotFound(_))));
     }
 
+    #[tokio::test]
+    async fn fetch_raw_finds_in_second_source() {
+        let src1 = InMemorySource::new("source-1");
+
+        let mut src2 = InMemorySource::new("source-2");
+        let mut def = make_def("beta", "source-2");
+        def.raw = "---\nname: beta\n---\nYou are beta.".to_owned();
+        src2.add(def);
+
+        let composite = CompositeSource::from_sources([src1, src2]);
+        let raw = composite.fetch_raw(&DefinitionId::new("beta")).await.unwrap();
+        assert!(raw.starts_with("---"));
+
+        let result = composite.fetch_raw(&DefinitionId::new("missing")).await;
+        assert!(matches!(result, Err(SourceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn qualified_ids_pick_between_sources() {
+        let mut src1 = InMemorySource::new("source-1");
+        let mut first = make_def("alpha", "source-1");
+        first.raw = "first".to_owned();
+        src1.add(first);
+        let mut src2 = InMemorySource::new("source-2");
+        let mut second = make_def("alpha", "source-2");
+        second.raw = "second".to_owned();
+        src2.add(second);
+        let composite = CompositeSource::from_sources([src1, src2]);
+
+        let qualified = DefinitionId::new("source-2:alpha");
+        assert_eq!(composite.fetch(&qualified).await.unwrap().source_label, "source-2");
+        assert_eq!(composite.fetch_raw(&qualified).await.unwrap(), "second");
+
+        let err = composite.fetch(&DefinitionId::new("alpha")).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "alpha is in 2 sources (source-1, source-2); qualify it, as in `source-1:alpha`"
+        );
+        let err = composite.fetch_raw(&DefinitionId::new("alpha")).await.unwrap_err();
+        assert!(matches!(err, SourceError::Ambiguous { .. }));
+
+        let missing = composite.fetch(&DefinitionId::new("source-1:beta")).await;
+        assert!(matches!(missing, Err(SourceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn takes_shared_boxed_and_borrowed_sources() {
+        async fn count(source: impl Source) -> usize {
+            source.list().await.unwrap().len()
+        }
+
+        let shared = Arc::new(InMemorySource::new("shared").with(make_def("alpha", "shared")));
+        let boxed: Vec<Box<dyn Source>> = vec![
+            Box::new(Arc::clone(&shared)),
+            Box::new(InMemorySource::new("boxed").with(make_def("beta", "boxed"))),
+        ];
+        let composite: CompositeSource = boxed.into_iter().collect();
+        assert_eq!(count(&composite).await, 2);
+        assert_eq!(count(CompositeSource::from_sources([shared])).await, 1);
+    }
+
     #[tokio::test]
     async fn empty_composite_returns_empty() {
         let composite = CompositeSource::new(vec![]);
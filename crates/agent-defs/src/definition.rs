@@ -1,9 +1,16 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use crate::section::Section;
+
 /// Source-opaque identifier for a definition.
 /// Each source determines its own ID scheme (e.g., GitHub uses file paths).
+///
+/// IDs are only unique within a source. Prefixed with a source label, as
+/// `label:path`, an ID is fully qualified and names one source's definition;
+/// see [`DefinitionId::qualified`] and [`DefinitionId::split_source`].
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct DefinitionId(String);
 
 impl DefinitionId {
@@ -14,6 +21,27 @@ impl DefinitionId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// This ID qualified with the label of the source it belongs to.
+    pub fn qualified(&self, source_label: &str) -> Self {
+        Self(format!("{source_label}:{}", self.0))
+    }
+
+    /// If this ID is qualified with one of `labels`, that label and the ID
+    /// within its source. The longest label wins when several match, and
+    /// an unqualified ID (including a `branch:path` one) gives `None`.
+    pub fn split_source<'a>(
+        &self,
+        labels: impl IntoIterator<Item = &'a str>,
+    ) -> Option<(&'a str, DefinitionId)> {
+        labels
+            .into_iter()
+            .filter_map(|label| {
+                let rest = self.0.strip_prefix(label)?.strip_prefix(':')?;
+                (!rest.is_empty()).then(|| (label, Self::new(rest)))
+            })
+            .max_by_key(|(label, _)| label.len())
+    }
 }
 
 impl fmt::Display for DefinitionId {
@@ -87,9 +115,27 @@ impl DefinitionKind {
     }
 }
 
+/// Kinds serialize as their lowercase name (`"agent"`, or the raw string for
+/// `Other`), matching how they appear in paths and the CLI.
+#[cfg(feature = "serde")]
+impl serde::Serialize for DefinitionKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DefinitionKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::parse(&s))
+    }
+}
+
 /// Lightweight summary returned from `list()` and `search()`.
 /// Does not include the full body content.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefinitionSummary {
     pub id: DefinitionId,
     pub name: String,
@@ -97,10 +143,26 @@ pub struct DefinitionSummary {
     pub kind: DefinitionKind,
     pub category: Option<String>,
     pub source_label: String,
+    /// When a sync first found this definition, in Unix seconds. `None`
+    /// when the source doesn't keep dates or it predates them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub first_seen_at: Option<u64>,
+    /// When a sync last found its file changed (or first found it), in
+    /// Unix seconds.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub last_updated_at: Option<u64>,
+}
+
+impl DefinitionSummary {
+    /// The ID qualified with this definition's source label.
+    pub fn qualified_id(&self) -> DefinitionId {
+        self.id.qualified(&self.source_label)
+    }
 }
 
 /// Full definition with body content and metadata.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Definition {
     pub id: DefinitionId,
     pub name: String,
@@ -112,10 +174,16 @@ pub struct Definition {
     pub tools: Vec<String>,
     pub model: Option<String>,
     pub metadata: HashMap<String, String>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub raw: String,
 }
 
 impl Definition {
+    /// The ID qualified with this definition's source label.
+    pub fn qualified_id(&self) -> DefinitionId {
+        self.id.qualified(&self.source_label)
+    }
+
     pub fn summary(&self) -> DefinitionSummary {
         DefinitionSummary {
             id: self.id.clone(),
@@ -124,6 +192,271 @@ impl Definition {
             kind: self.kind.clone(),
             category: self.category.clone(),
             source_label: self.source_label.clone(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    /// The body's sections, by markdown heading.
+    pub fn sections(&self) -> Vec<Section> {
+        crate::section::sections(&self.body)
+    }
+
+    /// Shell commands a hook definition runs, so they can be shown before
+    /// it's installed: every `command` string in a JSON hook, or the lines
+    /// of `sh`/`bash` code blocks in a markdown one. Empty for other kinds.
+    pub fn hook_commands(&self) -> Vec<String> {
+        if self.kind != DefinitionKind::Hook {
+            return Vec::new();
+        }
+        let text = self.body.strip_prefix('\u{feff}').unwrap_or(&self.body);
+        let mut commands = Vec::new();
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(text) {
+            collect_commands(&json, &mut commands);
+            return commands;
+        }
+
+        let mut in_shell = false;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if let Some(lang) = trimmed.strip_prefix("```") {
+                in_shell = !in_shell && matches!(lang.trim(), "sh" | "bash" | "shell" | "zsh");
+            } else if in_shell && !trimmed.is_empty() && !trimmed.starts_with('#') {
+                commands.push(trimmed.to_owned());
+            }
+        }
+        commands
+    }
+}
+
+fn collect_commands(value: &serde_json::Value, commands: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    serde_json::Value::String(command) if key == "command" => {
+                        commands.push(command.clone());
+                    }
+                    _ => collect_commands(value, commands),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_commands(item, commands);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Display names that tell apart definitions sharing a name.
+///
+/// Built from the summaries on screen, so a name only needs qualifying
+/// when its twin is actually visible. A shared name (compared ignoring case)
+/// gets the first of these that is unique among its twins, in parentheses:
+/// the source label, the category, then the ID.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayNames {
+    qualified: HashMap<(String, DefinitionId), String>,
+}
+
+impl DisplayNames {
+    pub fn new(summaries: &[DefinitionSummary]) -> Self {
+        let mut by_name: HashMap<String, Vec<&DefinitionSummary>> = HashMap::new();
+        for summary in summaries {
+            by_name
+                .entry(summary.name.to_lowercase())
+                .or_default()
+                .push(summary);
+        }
+
+        let qualifiers: [fn(&DefinitionSummary) -> Option<&str>; 3] = [
+            |s| Some(s.source_label.as_str()),
+            |s| s.category.as_deref(),
+            |s| Some(s.id.as_str()),
+        ];
+        let mut qualified = HashMap::new();
+        for twins in by_name.values().filter(|twins| twins.len() > 1) {
+            for summary in twins {
+                let qualifier = qualifiers
+                    .iter()
+                    .filter_map(|qualifier| qualifier(summary).map(|q| (qualifier, q)))
+                    .find(|(qualifier, q)| {
+                        twins.iter().filter(|t| qualifier(t) == Some(*q)).count() == 1
+                    })
+                    .map_or(summary.id.as_str(), |(_, q)| q);
+                qualified.insert(
+                    (summary.source_label.clone(), summary.id.clone()),
+                    format!("{} ({qualifier})", summary.name),
+                );
+            }
         }
+        Self { qualified }
+    }
+
+    /// The name to show for `summary`: its own name unless it shares one.
+    pub fn get<'a>(&'a self, summary: &'a DefinitionSummary) -> &'a str {
+        self.qualified
+            .get(&(summary.source_label.clone(), summary.id.clone()))
+            .map_or(summary.name.as_str(), String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(id: &str, name: &str, source: &str, category: Option<&str>) -> DefinitionSummary {
+        DefinitionSummary {
+            id: DefinitionId::new(id),
+            name: name.into(),
+            description: None,
+            kind: DefinitionKind::Agent,
+            category: category.map(str::to_owned),
+            source_label: source.into(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn qualified_ids_split_on_known_labels_only() {
+        let labels = ["acme", "acme:beta", "other"];
+        let split = |id: &str| {
+            DefinitionId::new(id)
+                .split_source(labels)
+                .map(|(label, id)| (label, id.as_str().to_owned()))
+        };
+
+        assert_eq!(
+            summary("agents/a.md", "a", "acme", None).qualified_id().as_str(),
+            "acme:agents/a.md"
+        );
+        assert_eq!(split("acme:agents/a.md"), Some(("acme", "agents/a.md".into())));
+        assert_eq!(split("acme:beta:agents/a.md"), Some(("acme:beta", "agents/a.md".into())));
+        assert_eq!(split("main:agents/a.md"), None);
+        assert_eq!(split("agents/a.md"), None);
+        assert_eq!(split("acme:"), None);
+    }
+
+    fn hook(body: &str) -> Definition {
+        Definition {
+            id: DefinitionId::new("hooks/format.json"),
+            name: "format".into(),
+            description: None,
+            kind: DefinitionKind::Hook,
+            category: None,
+            source_label: "acme".into(),
+            body: body.into(),
+            tools: Vec::new(),
+            model: None,
+            metadata: HashMap::new(),
+            raw: body.into(),
+        }
+    }
+
+    #[test]
+    fn hook_commands_come_from_json_and_shell_blocks() {
+        let json = hook(
+            r#"{"hooks": {"PostToolUse": [{"matcher": "Edit", "hooks": [
+                {"type": "command", "command": "npx prettier --write ."},
+                {"type": "command", "command": "cargo fmt"}
+            ]}]}}"#,
+        );
+        assert_eq!(json.hook_commands(), ["npx prettier --write .", "cargo fmt"]);
+
+        let markdown =
+            hook("Formats code.\n\n```bash\n# tidy up\ncargo fmt\n```\n\n```\nnot run\n```\n");
+        assert_eq!(markdown.hook_commands(), ["cargo fmt"]);
+
+        let mut agent = hook(&json.body);
+        agent.kind = DefinitionKind::Agent;
+        assert!(agent.hook_commands().is_empty());
+    }
+
+    fn names(summaries: &[DefinitionSummary]) -> Vec<String> {
+        let display = DisplayNames::new(summaries);
+        summaries.iter().map(|s| display.get(s).to_owned()).collect()
+    }
+
+    #[test]
+    fn unique_names_are_left_alone() {
+        let summaries = [
+            summary("a.md", "reviewer", "acme", None),
+            summary("b.md", "linter", "other", None),
+        ];
+        assert_eq!(names(&summaries), ["reviewer", "linter"]);
+    }
+
+    #[test]
+    fn shared_names_across_sources_get_the_source() {
+        let summaries = [
+            summary("a.md", "reviewer", "acme", None),
+            summary("b.md", "Reviewer", "other", None),
+        ];
+        assert_eq!(names(&summaries), ["reviewer (acme)", "Reviewer (other)"]);
+    }
+
+    #[test]
+    fn shared_names_in_one_source_fall_back_to_category_then_id() {
+        let summaries = [
+            summary("qa/reviewer.md", "reviewer", "acme", Some("qa")),
+            summary("sec/reviewer.md", "reviewer", "acme", Some("security")),
+            summary("reviewer.md", "reviewer", "acme", None),
+            summary("x.md", "reviewer", "other", Some("qa")),
+        ];
+        assert_eq!(
+            names(&summaries),
+            [
+                "reviewer (qa/reviewer.md)",
+                "reviewer (security)",
+                "reviewer (reviewer.md)",
+                "reviewer (other)",
+            ]
+        );
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn summary_round_trips_through_json() {
+        let summary = DefinitionSummary {
+            id: DefinitionId::new("agents/reviewer.md"),
+            name: "reviewer".into(),
+            description: Some("Reviews code".into()),
+            kind: DefinitionKind::Agent,
+            category: None,
+            source_label: "acme".into(),
+            first_seen_at: None,
+            last_updated_at: None,
+        };
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["id"], "agents/reviewer.md");
+        assert_eq!(json["kind"], "agent");
+
+        let back: DefinitionSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(back.id, summary.id);
+        assert_eq!(back.kind, DefinitionKind::Agent);
+    }
+
+    #[test]
+    fn unknown_kind_deserializes_as_other() {
+        let kind: DefinitionKind = serde_json::from_str("\"workflow\"").unwrap();
+        assert_eq!(kind, DefinitionKind::Other("workflow".into()));
+    }
+
+    #[test]
+    fn definition_without_raw_defaults_to_empty() {
+        let def: Definition = serde_json::from_str(
+            r#"{"id":"a","name":"a","description":null,"kind":"hook","category":null,
+                "source_label":"s","body":"","tools":[],"model":null,"metadata":{}}"#,
+        )
+        .unwrap();
+        assert!(def.raw.is_empty());
     }
 }
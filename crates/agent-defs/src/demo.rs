@@ -0,0 +1,204 @@
+//! A built-in source of sample definitions, for trying the browsers without
+//! configuring a repository and for UI tests that need the same catalog
+//! every run. [`DemoProvider`] serves the files bundled from this crate's
+//! `demo/` directory, taking a moment over each sync the way a real
+//! download does.
+
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+use std::time::Duration;
+
+use crate::{RawDefinitionFile, SyncError, SyncProvider};
+
+/// The label the demo source goes by unless configured otherwise.
+pub const LABEL: &str = "demo";
+
+/// How long a demo sync takes by default: long enough to see the progress
+/// overlay, short enough not to be in the way.
+pub const LATENCY: Duration = Duration::from_millis(600);
+
+/// The revision every demo sync reports; the files never change.
+const REVISION: &str = "demo";
+
+const FILES: &[(&str, &str)] = &[
+    (
+        "agents/devops/release-manager.md",
+        include_str!("../demo/agents/devops/release-manager.md"),
+    ),
+    (
+        "agents/review/code-reviewer.md",
+        include_str!("../demo/agents/review/code-reviewer.md"),
+    ),
+    (
+        "agents/review/security-auditor.md",
+        include_str!("../demo/agents/review/security-auditor.md"),
+    ),
+    (
+        "agents/testing/flaky-test-hunter.md",
+        include_str!("../demo/agents/testing/flaky-test-hunter.md"),
+    ),
+    (
+        "agents/testing/test-writer.md",
+        include_str!("../demo/agents/testing/test-writer.md"),
+    ),
+    (
+        "commands/changelog.md",
+        include_str!("../demo/commands/changelog.md"),
+    ),
+    (
+        "commands/explain-error.md",
+        include_str!("../demo/commands/explain-error.md"),
+    ),
+    (
+        "hooks/format-on-save.json",
+        include_str!("../demo/hooks/format-on-save.json"),
+    ),
+    (
+        "mcps/postgres.json",
+        include_str!("../demo/mcps/postgres.json"),
+    ),
+    (
+        "skills/documents/pdf-forms/SKILL.md",
+        include_str!("../demo/skills/documents/pdf-forms/SKILL.md"),
+    ),
+];
+
+/// The sample definition files, with paths relative to the definition root.
+pub fn files() -> Vec<RawDefinitionFile> {
+    FILES
+        .iter()
+        .map(|(relative_path, content)| RawDefinitionFile {
+            relative_path: (*relative_path).to_owned(),
+            content: (*content).to_owned(),
+        })
+        .collect()
+}
+
+/// Syncs the sample definitions, after waiting [`LATENCY`]. Nothing goes
+/// over the network.
+pub struct DemoProvider {
+    label: String,
+    latency: Duration,
+}
+
+impl DemoProvider {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            latency: LATENCY,
+        }
+    }
+
+    /// Wait `latency` before each sync instead; tests pass
+    /// [`Duration::ZERO`].
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+}
+
+impl Default for DemoProvider {
+    fn default() -> Self {
+        Self::new(LABEL)
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for DemoProvider {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        if !self.latency.is_zero() {
+            sleep(self.latency).await;
+        }
+        Ok(files())
+    }
+
+    fn revision(&self) -> Option<String> {
+        Some(REVISION.to_owned())
+    }
+}
+
+/// Finish after `duration`, under any async runtime: a thread does the
+/// waiting and wakes the task.
+pub(crate) async fn sleep(duration: Duration) {
+    let done = Arc::new(AtomicBool::new(false));
+    let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+    {
+        let (done, waker) = (Arc::clone(&done), Arc::clone(&waker));
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            done.store(true, Ordering::SeqCst);
+            if let Some(waker) = waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        });
+    }
+    poll_fn(|cx| {
+        *waker.lock().unwrap() = Some(cx.waker().clone());
+        if done.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefinitionKind;
+    use crate::builder::build_definition;
+    use crate::path;
+
+    #[test]
+    fn every_sample_builds() {
+        let files = files();
+        for file in &files {
+            let relative_path = file.relative_path.as_str();
+            assert!(path::is_definition_file(relative_path), "{relative_path}");
+            let (name, kind, category) = if path::is_skill_entry_point(relative_path) {
+                path::parse_skill_path(relative_path)
+            } else {
+                path::parse_relative_path(relative_path)
+            };
+            let id = crate::DefinitionId::new(relative_path);
+            let def = build_definition(
+                &id,
+                &file.content,
+                relative_path,
+                name,
+                kind,
+                category,
+                LABEL,
+            )
+            .unwrap_or_else(|e| panic!("{relative_path}: {e}"));
+            assert!(
+                def.description.is_some(),
+                "{relative_path} has no description"
+            );
+        }
+
+        let kinds = |kind: DefinitionKind| {
+            files
+                .iter()
+                .filter(|f| path::parse_relative_path(&f.relative_path).1 == kind)
+                .count()
+        };
+        assert!(kinds(DefinitionKind::Agent) >= 3);
+        assert!(kinds(DefinitionKind::Command) >= 1);
+    }
+
+    #[tokio::test]
+    async fn syncs_the_samples_at_a_fixed_revision() {
+        let provider = DemoProvider::default().with_latency(Duration::ZERO);
+        assert_eq!(provider.label(), "demo");
+        assert_eq!(provider.fetch_all().await.unwrap().len(), FILES.len());
+        assert_eq!(provider.revision().as_deref(), Some("demo"));
+    }
+}
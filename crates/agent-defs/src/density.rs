@@ -0,0 +1,60 @@
+//! How much of each definition a list row shows, so lists can trade
+//! detail for how many rows fit.
+
+use std::fmt;
+
+/// How much of each definition a list row shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum ListDensity {
+    /// The name alone, one line per definition.
+    Compact,
+    /// The name with the description under it.
+    Comfortable,
+}
+
+impl ListDensity {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "compact" => Some(Self::Compact),
+            "comfortable" => Some(Self::Comfortable),
+            _ => None,
+        }
+    }
+
+    /// The other density, for a key that switches between them.
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Compact => Self::Comfortable,
+            Self::Comfortable => Self::Compact,
+        }
+    }
+}
+
+impl fmt::Display for ListDensity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Compact => "compact",
+            Self::Comfortable => "comfortable",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trips_display() {
+        for density in [ListDensity::Compact, ListDensity::Comfortable] {
+            assert_eq!(ListDensity::parse(&density.to_string()), Some(density));
+            assert_eq!(density.toggled().toggled(), density);
+        }
+        assert_eq!(ListDensity::parse("Compact"), Some(ListDensity::Compact));
+        assert_eq!(ListDensity::parse("cozy"), None);
+    }
+}
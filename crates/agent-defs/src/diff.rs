@@ -0,0 +1,247 @@
+//! Line diffs between two versions of a file, for showing what an update
+//! would change, and three-way merges of two edits of the same file.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Past this many cells in the comparison table, the changed middle of two
+/// files is treated as having nothing in common rather than diffed line by
+/// line.
+const MAX_TABLE: usize = 4_000_000;
+
+/// One line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The lines of `old` and `new` in order, each kept, removed, or added,
+/// keeping as many as possible.
+pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    for (kept_old, kept_new) in common(&old, &new) {
+        diff.extend(old[i..kept_old].iter().map(|l| Line::Removed(l)));
+        diff.extend(new[j..kept_new].iter().map(|l| Line::Added(l)));
+        diff.push(Line::Same(old[kept_old]));
+        (i, j) = (kept_old + 1, kept_new + 1);
+    }
+    diff.extend(old[i..].iter().map(|l| Line::Removed(l)));
+    diff.extend(new[j..].iter().map(|l| Line::Added(l)));
+    diff
+}
+
+/// Index pairs of the lines `old` and `new` have in common, in order.
+fn common(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    if (old_mid.len() + 1) * (new_mid.len() + 1) <= MAX_TABLE {
+        let middle = subsequence(old_mid, new_mid);
+        pairs.extend(middle.into_iter().map(|(i, j)| (prefix + i, prefix + j)));
+    }
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    pairs.extend((0..suffix).map(|k| (old_end + k, new_end + k)));
+    pairs
+}
+
+/// Index pairs of a longest common subsequence of `old` and `new`.
+fn subsequence(old: &[&str], new: &[&str]) -> Vec<(usize, usize)> {
+    let width = new.len() + 1;
+    // longest[i * width + j]: length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut longest = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            longest[i * width + j] = if old[i] == new[j] {
+                longest[(i + 1) * width + j + 1] + 1
+            } else {
+                longest[(i + 1) * width + j].max(longest[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if longest[(i + 1) * width + j] >= longest[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// The result of merging two edits of the same file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    /// How many places both sides changed differently, each written out
+    /// between conflict markers.
+    pub conflicts: usize,
+}
+
+impl Merged {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts == 0
+    }
+}
+
+/// Merge `local` and `upstream`, two edits of `base`: a region only one
+/// side changed takes that side's lines, and one both changed differently
+/// is a conflict, written with both versions between
+/// `<<<<<<< local` / `=======` / `>>>>>>> upstream` markers.
+pub fn merge(base: &str, local: &str, upstream: &str) -> Merged {
+    let base_lines: Vec<&str> = base.lines().collect();
+    let local_lines: Vec<&str> = local.lines().collect();
+    let upstream_lines: Vec<&str> = upstream.lines().collect();
+
+    // Base lines both sides kept, with where each side has them.
+    let in_local: HashMap<usize, usize> = common(&base_lines, &local_lines).into_iter().collect();
+    let stable: Vec<(usize, usize, usize)> = common(&base_lines, &upstream_lines)
+        .into_iter()
+        .filter_map(|(b, u)| in_local.get(&b).map(|&l| (b, l, u)))
+        .collect();
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut conflicts = 0;
+    let (mut b, mut l, mut u) = (0, 0, 0);
+    let ends = (base_lines.len(), local_lines.len(), upstream_lines.len());
+    for next in stable.into_iter().map(Some).chain([None]) {
+        let (end_b, end_l, end_u) = next.unwrap_or(ends);
+        let (old, ours, theirs) = (
+            &base_lines[b..end_b],
+            &local_lines[l..end_l],
+            &upstream_lines[u..end_u],
+        );
+        if ours == old || ours == theirs {
+            out.extend(theirs);
+        } else if theirs == old {
+            out.extend(ours);
+        } else {
+            conflicts += 1;
+            out.push("<<<<<<< local");
+            out.extend(ours);
+            out.push("=======");
+            out.extend(theirs);
+            out.push(">>>>>>> upstream");
+        }
+        if next.is_some() {
+            out.push(base_lines[end_b]);
+            (b, l, u) = (end_b + 1, end_l + 1, end_u + 1);
+        }
+    }
+
+    let mut text = out.join("\n");
+    if !text.is_empty() && (upstream.ends_with('\n') || local.ends_with('\n')) {
+        text.push('\n');
+    }
+    Merged { text, conflicts }
+}
+
+/// How many lines a change adds and removes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStat {
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl DiffStat {
+    pub fn between(old: &str, new: &str) -> Self {
+        let mut stat = Self::default();
+        for line in lines(old, new) {
+            match line {
+                Line::Added(_) => stat.added += 1,
+                Line::Removed(_) => stat.removed += 1,
+                Line::Same(_) => {}
+            }
+        }
+        stat
+    }
+}
+
+impl fmt::Display for DiffStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} -{}", self.added, self.removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_longest_common_run() {
+        let diff = lines("a\nb\nc\nd\n", "a\nc\nx\nd\n");
+        assert_eq!(
+            diff,
+            [
+                Line::Same("a"),
+                Line::Removed("b"),
+                Line::Same("c"),
+                Line::Added("x"),
+                Line::Same("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_added_and_removed_lines() {
+        let stat = DiffStat::between("one\ntwo\n", "one\n2\nthree\n");
+        assert_eq!(
+            stat,
+            DiffStat {
+                added: 2,
+                removed: 1
+            }
+        );
+        assert_eq!(stat.to_string(), "+2 -1");
+        assert_eq!(DiffStat::between("same", "same"), DiffStat::default());
+        assert_eq!(DiffStat::between("", "new\n").added, 1);
+    }
+
+    #[test]
+    fn merges_edits_to_different_lines() {
+        let base = "# Reviewer\n\nReview the diff.\nBe kind.\n";
+        let local = "# Reviewer\n\nReview the diff closely.\nBe kind.\n";
+        let upstream = "# Reviewer\n\nReview the diff.\nBe kind.\nCite line numbers.\n";
+        let merged = merge(base, local, upstream);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.text,
+            "# Reviewer\n\nReview the diff closely.\nBe kind.\nCite line numbers.\n"
+        );
+        assert_eq!(merge(base, base, upstream).text, upstream);
+        assert_eq!(merge(base, local, base).text, local);
+    }
+
+    #[test]
+    fn marks_lines_both_sides_changed() {
+        let merged = merge("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< local\nmine\n=======\ntheirs\n>>>>>>> upstream\nc\n"
+        );
+        assert!(merge("a\nb\n", "a\nsame\n", "a\nsame\n").is_clean());
+    }
+}
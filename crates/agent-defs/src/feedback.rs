@@ -59,6 +59,18 @@ impl std::fmt::Display for Feedback {
     }
 }
 
+/// A file a sync left out of the cache, and why. Stores keep these until
+/// the source's next sync, so the reasons outlive the sync's own output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub source_label: String,
+    /// Path of the file within the source.
+    pub path: String,
+    pub reason: String,
+    /// When the sync that skipped it ran, in seconds since the Unix epoch.
+    pub recorded_at: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
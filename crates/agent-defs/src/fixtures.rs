@@ -0,0 +1,109 @@
+//! A synthetic catalog for benchmarks and load testing. [`corpus`] lays
+//! out as many definitions as asked for the way a large source does:
+//! mostly agents and commands spread over twenty categories, some
+//! skills and MCP servers, and bodies from a line to a few pages long. The
+//! same count always gives the same files, so timings compare across runs.
+
+use crate::RawDefinitionFile;
+
+/// How many definitions the benchmarks use: several times the size of the
+/// largest source we know of.
+pub const BENCH_DEFINITIONS: usize = 50_000;
+
+/// A word that only every thousandth body contains, for timing a search
+/// that matches little of the catalog.
+pub const RARE_WORD: &str = "quasicrystal";
+
+const CATEGORIES: &[&str] = &[
+    "development",
+    "testing",
+    "security",
+    "devops",
+    "documentation",
+    "database",
+    "frontend",
+    "backend",
+    "data-science",
+    "machine-learning",
+    "mobile",
+    "infrastructure",
+    "code-quality",
+    "performance",
+    "accessibility",
+    "api-design",
+    "observability",
+    "cloud",
+    "networking",
+    "compliance",
+];
+
+const TOOLS: &[&str] = &["Read", "Write", "Edit", "Grep", "Glob", "Bash", "WebFetch"];
+
+const PARAGRAPH: &str = "Reviews changes for correctness, readability, and test coverage. \
+    Flags risky migrations and suggests smaller follow-ups that are easier to land.\n\n";
+
+/// `count` definition files, with paths relative to the definition root.
+pub fn corpus(count: usize) -> Vec<RawDefinitionFile> {
+    (0..count).map(file).collect()
+}
+
+fn file(i: usize) -> RawDefinitionFile {
+    let category = CATEGORIES[i % CATEGORIES.len()];
+    let name = format!("{category}-helper-{i}");
+    let description = format!("Synthetic definition {i} for {category} work");
+    if i % 50 == 49 {
+        let content = format!(
+            "{{\n  \"mcpServers\": {{\n    \"{name}\": {{\n      \"command\": \"npx\",\n      \
+             \"args\": [\"-y\", \"@example/{name}\"]\n    }}\n  }},\n  \
+             \"description\": \"{description}\"\n}}\n"
+        );
+        return RawDefinitionFile {
+            relative_path: format!("mcps/{category}/{name}.json"),
+            content,
+        };
+    }
+
+    let relative_path = match i % 10 {
+        0..=5 => format!("agents/{category}/{name}.md"),
+        6..=8 => format!("commands/{category}/{name}.md"),
+        _ => format!("skills/{category}/{name}/SKILL.md"),
+    };
+    let tools = TOOLS[..1 + i % TOOLS.len()].join(", ");
+    let mut body = PARAGRAPH.repeat(1 + i % 30);
+    if i.is_multiple_of(1000) {
+        body.push_str(&format!("Knows a little about {RARE_WORD} lattices.\n"));
+    }
+    RawDefinitionFile {
+        relative_path,
+        content: format!(
+            "---\nname: {name}\ndescription: {description}\ntools: {tools}\nmodel: sonnet\n---\n\n{body}"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path;
+
+    #[test]
+    fn corpus_is_stable_and_uses_every_layout() {
+        let files = corpus(1000);
+        assert_eq!(files.len(), 1000);
+        assert_eq!(files[7].content, corpus(8)[7].content);
+
+        let mut paths: Vec<&str> = files.iter().map(|f| f.relative_path.as_str()).collect();
+        assert!(paths.iter().all(|p| path::is_definition_file(p)));
+        paths.sort_unstable();
+        paths.dedup();
+        assert_eq!(paths.len(), 1000);
+        for prefix in ["agents/", "commands/", "skills/", "mcps/"] {
+            assert!(paths.iter().any(|p| p.starts_with(prefix)), "{prefix}");
+        }
+        let rare = files
+            .iter()
+            .filter(|f| f.content.contains(RARE_WORD))
+            .count();
+        assert_eq!(rare, 1);
+    }
+}
@@ -1,24 +1,33 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde_yaml_ng::Value;
+
+use crate::source::{Location, at, strip_location};
 
 /// Raw frontmatter fields parsed from YAML between `---` delimiters.
+///
+/// Fields are read leniently, since repos write them every which way: a
+/// number or boolean is kept as text, and a list or map where text belongs
+/// is dropped rather than failing the whole document.
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct Frontmatter {
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar")]
     pub name: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar")]
     pub description: Option<String>,
-    /// Comma-separated list of tools.
-    #[serde(default)]
+    /// Comma-separated list of tools. A YAML list of tools is joined into
+    /// one.
+    #[serde(default, deserialize_with = "tools")]
     pub tools: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar")]
     pub model: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "scalar")]
     pub color: Option<String>,
     /// Any additional fields not explicitly modeled.
     #[serde(flatten)]
-    pub extras: HashMap<String, serde_yaml_ng::Value>,
+    pub extras: HashMap<String, Value>,
 }
 
 impl Frontmatter {
@@ -34,19 +43,36 @@ impl Frontmatter {
     pub fn extras_as_strings(&self) -> HashMap<String, String> {
         self.extras
             .iter()
-            .filter_map(|(k, v)| {
-                let s = match v {
-                    serde_yaml_ng::Value::String(s) => s.clone(),
-                    serde_yaml_ng::Value::Bool(b) => b.to_string(),
-                    serde_yaml_ng::Value::Number(n) => n.to_string(),
-                    _ => return None,
-                };
-                Some((k.clone(), s))
-            })
+            .filter_map(|(k, v)| Some((k.clone(), scalar_text(v)?)))
             .collect()
     }
 }
 
+/// A string, number, or boolean as text.
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Tagged(tagged) => scalar_text(&tagged.value),
+        _ => None,
+    }
+}
+
+fn scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(scalar_text(&Value::deserialize(deserializer)?))
+}
+
+fn tools<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Sequence(items) => {
+            let tools: Vec<String> = items.iter().filter_map(scalar_text).collect();
+            Ok((!tools.is_empty()).then(|| tools.join(", ")))
+        }
+        value => Ok(scalar_text(&value)),
+    }
+}
+
 /// Result of parsing a markdown document with optional frontmatter.
 #[derive(Debug, Clone)]
 pub struct ParsedDocument {
@@ -54,17 +80,33 @@ pub struct ParsedDocument {
     pub body: String,
 }
 
+/// Strip a leading UTF-8 byte order mark and normalize `\r\n` / `\r` line
+/// endings to `\n`.
+///
+/// Borrows the input unchanged when there is nothing to fix up.
+pub fn normalize(content: &str) -> Cow<'_, str> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    if content.contains('\r') {
+        Cow::Owned(content.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
 /// Parse a markdown document, extracting YAML frontmatter if present.
 ///
 /// Frontmatter must be delimited by `---` on its own line at the very
-/// start of the document.
+/// start of the document. The document is [`normalize`]d first, so files
+/// saved with a BOM or Windows line endings parse the same as their Unix
+/// counterparts; the returned body always uses `\n` line endings.
 pub fn parse(content: &str) -> Result<ParsedDocument, FrontmatterError> {
+    let content = normalize(content);
     let trimmed = content.trim_start();
 
     if !trimmed.starts_with("---") {
         return Ok(ParsedDocument {
             frontmatter: None,
-            body: content.to_owned(),
+            body: content.into_owned(),
         });
     }
 
@@ -74,18 +116,40 @@ pub fn parse(content: &str) -> Result<ParsedDocument, FrontmatterError> {
         // No closing delimiter — treat entire content as body with no frontmatter.
         return Ok(ParsedDocument {
             frontmatter: None,
-            body: content.to_owned(),
+            body: content.into_owned(),
         });
     };
 
-    let yaml_str = &after_opening[..end_pos];
+    let yaml_str = expand_leading_tabs(&after_opening[..end_pos]);
     let rest_start = end_pos + 4; // skip past "\n---"
     let body = after_opening[rest_start..]
         .strip_prefix('\n')
         .unwrap_or(&after_opening[rest_start..]);
 
-    let frontmatter: Frontmatter =
-        serde_yaml_ng::from_str(yaml_str).map_err(|e| FrontmatterError::InvalidYaml(e.to_string()))?;
+    // The YAML starts on the opening `---` line.
+    let first_line = content[..content.len() - trimmed.len()].matches('\n').count();
+    let value: Value =
+        serde_yaml_ng::from_str(&yaml_str).map_err(|e| FrontmatterError::yaml(&e, first_line))?;
+    let frontmatter = match value {
+        Value::Null => Frontmatter::default(),
+        Value::Mapping(mapping) => {
+            // YAML allows keys like `1:` or `[a]:` that no field can have;
+            // read the first as text and drop the rest.
+            let mapping = mapping
+                .into_iter()
+                .filter_map(|(key, value)| Some((Value::String(scalar_text(&key)?), value)))
+                .collect();
+            Frontmatter::deserialize(Value::Mapping(mapping))
+                .map_err(|e| FrontmatterError::yaml(&e, first_line))?
+        }
+        // Prose between two `---` rules, not frontmatter.
+        _ => {
+            return Ok(ParsedDocument {
+                frontmatter: None,
+                body: content.into_owned(),
+            });
+        }
+    };
 
     Ok(ParsedDocument {
         frontmatter: Some(frontmatter),
@@ -93,10 +157,47 @@ pub fn parse(content: &str) -> Result<ParsedDocument, FrontmatterError> {
     })
 }
 
+/// YAML forbids tabs in indentation, but hand-edited frontmatter often
+/// uses them anyway. Replace each leading tab with two spaces so nested
+/// values still line up.
+fn expand_leading_tabs(yaml: &str) -> Cow<'_, str> {
+    if !yaml.lines().any(|line| line.starts_with('\t')) {
+        return Cow::Borrowed(yaml);
+    }
+
+    let expanded = yaml
+        .lines()
+        .map(|line| {
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let (lead, rest) = line.split_at(indent);
+            format!("{}{rest}", lead.replace('\t', "  "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Cow::Owned(expanded)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FrontmatterError {
-    #[error("invalid YAML in frontmatter: {0}")]
-    InvalidYaml(String),
+    /// `location` is in the whole document, not just the frontmatter.
+    #[error("invalid YAML in frontmatter{}: {message}", at(location))]
+    InvalidYaml {
+        message: String,
+        location: Option<Location>,
+    },
+}
+
+impl FrontmatterError {
+    /// `e`, from YAML that starts `first_line` lines into the document.
+    fn yaml(e: &serde_yaml_ng::Error, first_line: usize) -> Self {
+        Self::InvalidYaml {
+            message: strip_location(&e.to_string()),
+            location: e.location().map(|l| Location {
+                line: first_line + l.line(),
+                column: l.column(),
+            }),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -211,4 +312,93 @@ Body.
 
         assert!(fm.tool_list().is_empty());
     }
+
+    #[test]
+    fn reads_mistyped_fields_leniently() {
+        let content = "\
+---
+name: 2024
+description: [not, text]
+tools: [Read, Grep, {nested: map}]
+model: true
+color: !custom red
+1: numeric key
+? [complex, key]
+: dropped
+---
+Body.
+";
+        let fm = parse(content).unwrap().frontmatter.unwrap();
+
+        assert_eq!(fm.name.as_deref(), Some("2024"));
+        assert_eq!(fm.description, None);
+        assert_eq!(fm.tool_list(), vec!["Read", "Grep"]);
+        assert_eq!(fm.model.as_deref(), Some("true"));
+        assert_eq!(fm.color.as_deref(), Some("red"));
+        assert_eq!(fm.extras_as_strings()["1"], "numeric key");
+    }
+
+    #[test]
+    fn prose_between_rules_is_body() {
+        let content = "---\nJust a horizontal rule above and below.\n---\nMore text.\n";
+        let doc = parse(content).unwrap();
+
+        assert!(doc.frontmatter.is_none());
+        assert_eq!(doc.body, content);
+    }
+
+    #[test]
+    fn strips_byte_order_mark() {
+        let content = "\u{feff}---\nname: With BOM\n---\nBody.\n";
+        let doc = parse(content).unwrap();
+        let fm = doc.frontmatter.expect("should have frontmatter");
+
+        assert_eq!(fm.name.as_deref(), Some("With BOM"));
+        assert_eq!(doc.body, "Body.\n");
+    }
+
+    #[test]
+    fn normalizes_crlf_line_endings() {
+        let content = "---\r\nname: Windows Agent\r\ntools: Read, Write\r\n---\r\nLine one.\r\nLine two.\r\n";
+        let doc = parse(content).unwrap();
+        let fm = doc.frontmatter.expect("should have frontmatter");
+
+        assert_eq!(fm.name.as_deref(), Some("Windows Agent"));
+        assert_eq!(fm.tool_list(), vec!["Read", "Write"]);
+        assert_eq!(doc.body, "Line one.\nLine two.\n");
+    }
+
+    #[test]
+    fn normalizes_bare_carriage_returns() {
+        let content = "---\rname: Classic Mac\r---\rBody.\r";
+        let doc = parse(content).unwrap();
+        let fm = doc.frontmatter.expect("should have frontmatter");
+
+        assert_eq!(fm.name.as_deref(), Some("Classic Mac"));
+        assert_eq!(doc.body, "Body.\n");
+    }
+
+    #[test]
+    fn accepts_tab_indented_nested_values() {
+        let content = "---\nname: Tabbed\nsettings:\n\tmode: strict\n\tlevel: 2\n---\nBody.\n";
+        let doc = parse(content).unwrap();
+        let fm = doc.frontmatter.expect("should have frontmatter");
+
+        assert_eq!(fm.name.as_deref(), Some("Tabbed"));
+        assert!(fm.extras["settings"].is_mapping());
+    }
+
+    #[test]
+    fn yaml_errors_point_into_the_document() {
+        let content = "\n---\nname: Broken\ntools: [Read\n---\nBody.\n";
+        let FrontmatterError::InvalidYaml { message, location } = parse(content).unwrap_err();
+        assert!(!message.contains(" at line "), "{message}");
+        let location = location.expect("YAML syntax errors have a location");
+        assert!(location.line >= 4, "{location}");
+    }
+
+    #[test]
+    fn normalize_borrows_clean_input() {
+        assert!(matches!(normalize("---\nname: x\n---\n"), Cow::Borrowed(_)));
+    }
 }
@@ -0,0 +1,100 @@
+//! Path globs in the style of `.gitignore`, used for hide rules and for the
+//! `include`/`exclude` patterns on sources.
+//!
+//! `*` matches any run of characters within one path segment, `?` matches a
+//! single character other than `/`, and `**` matches across segments, so
+//! `agents/**` is everything under `agents/` and `**/deprecated/**` is any
+//! `deprecated` directory, including one at the top.
+
+/// Whether `path` matches `pattern` as a whole.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    match_from(&pattern, &path)
+}
+
+fn match_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` may also stand for no directories at all.
+            if let ['/', after @ ..] = rest
+                && match_from(after, path)
+            {
+                return true;
+            }
+            (0..=path.len()).any(|skip| match_from(rest, &path[skip..]))
+        }
+        ['*', rest @ ..] => {
+            let segment = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment).any(|skip| match_from(rest, &path[skip..]))
+        }
+        ['?', rest @ ..] => {
+            matches!(path.first(), Some(&c) if c != '/') && match_from(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && match_from(rest, &path[1..]),
+    }
+}
+
+/// Which paths a source keeps: those matching any `include` pattern (or all
+/// of them when there are none) and no `exclude` pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PathFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// True when the filter keeps everything.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn allows(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| matches(p, path)))
+            && !self.exclude.iter().any(|p| matches(p, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_star_stays_within_a_segment() {
+        assert!(matches("agents/*.md", "agents/a.md"));
+        assert!(!matches("agents/*.md", "agents/team/a.md"));
+        assert!(matches("*/SKILL.md", "crypto/SKILL.md"));
+        assert!(matches("agents/?.md", "agents/a.md"));
+        assert!(!matches("agents/?.md", "agents/ab.md"));
+        assert!(!matches("agents?a.md", "agents/a.md"));
+    }
+
+    #[test]
+    fn double_star_crosses_segments() {
+        assert!(matches("agents/**", "agents/team/lead.md"));
+        assert!(!matches("agents/**", "skills/agents/x.md"));
+        assert!(matches("**/deprecated/**", "agents/deprecated/old.md"));
+        assert!(matches("**/deprecated/**", "deprecated/old.md"));
+        assert!(matches("skills/**/SKILL.md", "skills/SKILL.md"));
+        assert!(matches("skills/**/SKILL.md", "skills/a/b/SKILL.md"));
+        assert!(matches("**", "anything/at/all"));
+    }
+
+    #[test]
+    fn filter_needs_an_include_and_no_exclude() {
+        let filter = PathFilter::new(vec!["agents/**".into()], vec!["**/deprecated/**".into()]);
+        assert!(filter.allows("agents/team/lead.md"));
+        assert!(!filter.allows("agents/deprecated/old.md"));
+        assert!(!filter.allows("skills/a/SKILL.md"));
+
+        let exclude_only = PathFilter::new(vec![], vec!["*.json".into()]);
+        assert!(exclude_only.allows("agents/a.md"));
+        assert!(!exclude_only.allows("settings.json"));
+        assert!(PathFilter::default().allows("anything"));
+    }
+}
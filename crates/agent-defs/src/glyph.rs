@@ -0,0 +1,129 @@
+//! Per-kind glyphs so kinds can be told apart without relying on color.
+
+use std::fmt;
+
+use crate::DefinitionKind;
+
+/// Which set of glyphs to prefix kinds with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum GlyphSet {
+    /// Shapes from common Unicode blocks; render in almost any font.
+    #[default]
+    Unicode,
+    /// Icons from a Nerd Font patched font.
+    NerdFont,
+    /// Bracketed letters for terminals without Unicode support.
+    Ascii,
+    /// No glyphs at all.
+    None,
+}
+
+impl GlyphSet {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "unicode" => Some(Self::Unicode),
+            "nerd-font" | "nerdfont" | "nerd" => Some(Self::NerdFont),
+            "ascii" => Some(Self::Ascii),
+            "none" | "off" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Glyph for `kind`, or an empty string for [`GlyphSet::None`].
+    pub fn glyph(self, kind: &DefinitionKind) -> &'static str {
+        match self {
+            Self::Unicode => match kind {
+                DefinitionKind::Agent => "\u{25c9}",   // ◉
+                DefinitionKind::Command => "\u{276f}", // ❯
+                DefinitionKind::Hook => "\u{21aa}",    // ↪
+                DefinitionKind::Mcp => "\u{21c4}",     // ⇄
+                DefinitionKind::Setting => "\u{2261}", // ≡
+                DefinitionKind::Skill => "\u{2605}",   // ★
+                DefinitionKind::Other(_) => "\u{2022}", // •
+            },
+            Self::NerdFont => match kind {
+                DefinitionKind::Agent => "\u{f06a9}",   // nf-md-robot
+                DefinitionKind::Command => "\u{f120}",  // nf-fa-terminal
+                DefinitionKind::Hook => "\u{f06e2}",    // nf-md-hook
+                DefinitionKind::Mcp => "\u{f048b}",     // nf-md-server
+                DefinitionKind::Setting => "\u{f013}",  // nf-fa-cog
+                DefinitionKind::Skill => "\u{f0335}",   // nf-md-lightbulb
+                DefinitionKind::Other(_) => "\u{f15b}", // nf-fa-file
+            },
+            Self::Ascii => match kind {
+                DefinitionKind::Agent => "[A]",
+                DefinitionKind::Command => "[C]",
+                DefinitionKind::Hook => "[H]",
+                DefinitionKind::Mcp => "[M]",
+                DefinitionKind::Setting => "[S]",
+                DefinitionKind::Skill => "[K]",
+                DefinitionKind::Other(_) => "[?]",
+            },
+            Self::None => "",
+        }
+    }
+
+    /// `text` prefixed with the glyph for `kind` and a space, or `text`
+    /// unchanged when glyphs are off.
+    pub fn prefix(self, kind: &DefinitionKind, text: &str) -> String {
+        match self.glyph(kind) {
+            "" => text.to_owned(),
+            glyph => format!("{glyph} {text}"),
+        }
+    }
+}
+
+impl fmt::Display for GlyphSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unicode => "unicode",
+            Self::NerdFont => "nerd-font",
+            Self::Ascii => "ascii",
+            Self::None => "none",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_set_gives_known_kinds_distinct_glyphs() {
+        for set in [GlyphSet::Unicode, GlyphSet::NerdFont, GlyphSet::Ascii] {
+            let mut glyphs: Vec<&str> = DefinitionKind::all_known()
+                .iter()
+                .map(|k| set.glyph(k))
+                .collect();
+            glyphs.sort();
+            glyphs.dedup();
+            assert_eq!(glyphs.len(), DefinitionKind::all_known().len(), "{set}");
+        }
+    }
+
+    #[test]
+    fn ascii_glyphs_are_ascii() {
+        for kind in DefinitionKind::all_known() {
+            assert!(GlyphSet::Ascii.glyph(&kind).is_ascii());
+        }
+    }
+
+    #[test]
+    fn prefix_skips_separator_when_off() {
+        assert_eq!(GlyphSet::None.prefix(&DefinitionKind::Agent, "Agents"), "Agents");
+        assert_eq!(GlyphSet::Ascii.prefix(&DefinitionKind::Agent, "Agents"), "[A] Agents");
+    }
+
+    #[test]
+    fn parse_round_trips_display() {
+        for set in [GlyphSet::Unicode, GlyphSet::NerdFont, GlyphSet::Ascii, GlyphSet::None] {
+            assert_eq!(GlyphSet::parse(&set.to_string()), Some(set));
+        }
+        assert_eq!(GlyphSet::parse("emoji"), None);
+    }
+}
@@ -1,4 +1,6 @@
-use agent_defs::{DefinitionKind, DefinitionSummary};
+//! Grouping of definition summaries by kind, shared by every front end.
+
+use crate::{DefinitionKind, DefinitionSummary};
 
 /// A group of definitions sharing the same kind.
 #[derive(Debug, Clone)]
@@ -13,21 +15,17 @@ pub struct Group {
 /// A row in the flattened list: either a section header or a selectable item.
 #[derive(Debug, Clone)]
 pub enum ListRow {
-    Header { label: String, count: usize },
+    Header {
+        kind: DefinitionKind,
+        label: String,
+        count: usize,
+    },
     Item { summary_index: usize },
 }
 
 /// Human-readable plural label for a definition kind.
 pub fn kind_label(kind: &DefinitionKind) -> &str {
-    match kind {
-        DefinitionKind::Agent => "Agents",
-        DefinitionKind::Command => "Commands",
-        DefinitionKind::Hook => "Hooks",
-        DefinitionKind::Mcp => "MCP Servers",
-        DefinitionKind::Setting => "Settings",
-        DefinitionKind::Skill => "Skills",
-        DefinitionKind::Other(s) => s.as_str(),
-    }
+    kind.display_label()
 }
 
 /// Sort key for consistent kind ordering.
@@ -43,6 +41,47 @@ pub fn kind_sort_key(kind: &DefinitionKind) -> u8 {
     }
 }
 
+/// How definitions are ordered within their group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ItemOrder {
+    /// As they came, which for the cache is by name.
+    #[default]
+    Name,
+    /// The ones a sync found most recently first.
+    Added,
+    /// The ones whose files changed most recently first.
+    Updated,
+}
+
+impl ItemOrder {
+    /// The order after this one, cycling back to [`ItemOrder::Name`].
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Added,
+            Self::Added => Self::Updated,
+            Self::Updated => Self::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Name => "by name",
+            Self::Added => "newest first",
+            Self::Updated => "recently updated",
+        }
+    }
+
+    /// Reorder `summaries`. Undated definitions go last, and ties keep the
+    /// order they had.
+    pub fn sort(self, summaries: &mut [DefinitionSummary]) {
+        match self {
+            Self::Name => {}
+            Self::Added => summaries.sort_by_key(|s| std::cmp::Reverse(s.first_seen_at)),
+            Self::Updated => summaries.sort_by_key(|s| std::cmp::Reverse(s.last_updated_at)),
+        }
+    }
+}
+
 /// Build sorted groups from summaries, returning both the groups and a
 /// flattened list of rows for cursor navigation.
 pub fn build_groups(summaries: &[DefinitionSummary]) -> (Vec<Group>, Vec<ListRow>) {
@@ -66,6 +105,7 @@ pub fn build_groups(summaries: &[DefinitionSummary]) -> (Vec<Group>, Vec<ListRow
         let count = indices.len();
 
         flat_items.push(ListRow::Header {
+            kind: kind.clone(),
             label: label.clone(),
             count,
         });
@@ -92,6 +132,25 @@ pub fn first_item_index(flat_items: &[ListRow]) -> Option<usize> {
         .position(|row| matches!(row, ListRow::Item { .. }))
 }
 
+/// Find the last selectable (Item) row index, or None if empty.
+pub fn last_item_index(flat_items: &[ListRow]) -> Option<usize> {
+    flat_items
+        .iter()
+        .rposition(|row| matches!(row, ListRow::Item { .. }))
+}
+
+/// Find the selectable row nearest to `row`: the first item at or after it,
+/// falling back to the last item before it.
+pub fn item_index_near(flat_items: &[ListRow], row: usize) -> Option<usize> {
+    flat_items
+        .iter()
+        .enumerate()
+        .skip(row)
+        .find(|(_, r)| matches!(r, ListRow::Item { .. }))
+        .map(|(i, _)| i)
+        .or_else(|| last_item_index(&flat_items[..row.min(flat_items.len())]))
+}
+
 /// Find the next selectable row after `current`, or stay put.
 pub fn next_item_index(flat_items: &[ListRow], current: usize) -> usize {
     flat_items
@@ -117,9 +176,8 @@ pub fn prev_item_index(flat_items: &[ListRow], current: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use agent_defs::DefinitionId;
-
     use super::*;
+    use crate::DefinitionId;
 
     fn summary(name: &str, kind: DefinitionKind) -> DefinitionSummary {
         DefinitionSummary {
@@ -129,6 +187,8 @@ mod tests {
             kind,
             category: None,
             source_label: "test".into(),
+            first_seen_at: None,
+            last_updated_at: None,
         }
     }
 
@@ -171,6 +231,29 @@ mod tests {
         assert!(matches!(flat[4], ListRow::Item { .. }));
     }
 
+    #[test]
+    fn dated_orders_put_newest_first_and_undated_last() {
+        let dated = |name, added, updated| DefinitionSummary {
+            first_seen_at: added,
+            last_updated_at: updated,
+            ..summary(name, DefinitionKind::Agent)
+        };
+        let mut summaries = vec![
+            dated("a", None, None),
+            dated("b", Some(10), Some(30)),
+            dated("c", Some(20), Some(20)),
+        ];
+        let names = |summaries: &[DefinitionSummary]| -> Vec<String> {
+            summaries.iter().map(|s| s.name.clone()).collect()
+        };
+
+        ItemOrder::Added.sort(&mut summaries);
+        assert_eq!(names(&summaries), ["c", "b", "a"]);
+        ItemOrder::Updated.sort(&mut summaries);
+        assert_eq!(names(&summaries), ["b", "c", "a"]);
+        assert_eq!(ItemOrder::Updated.next(), ItemOrder::Name);
+    }
+
     #[test]
     fn first_item_index_skips_header() {
         let summaries = vec![summary("a", DefinitionKind::Agent)];
@@ -184,6 +267,33 @@ mod tests {
         assert_eq!(first_item_index(&[]), None);
     }
 
+    #[test]
+    fn last_item_index_finds_final_item() {
+        let summaries = vec![
+            summary("a", DefinitionKind::Agent),
+            summary("b", DefinitionKind::Hook),
+        ];
+        let (_, flat) = build_groups(&summaries);
+
+        assert_eq!(last_item_index(&flat), Some(3));
+        assert_eq!(last_item_index(&[]), None);
+    }
+
+    #[test]
+    fn item_index_near_snaps_off_headers() {
+        let summaries = vec![
+            summary("a", DefinitionKind::Agent),
+            summary("b", DefinitionKind::Hook),
+        ];
+        let (_, flat) = build_groups(&summaries);
+
+        // flat: Header(0), Item(1), Header(2), Item(3)
+        assert_eq!(item_index_near(&flat, 0), Some(1));
+        assert_eq!(item_index_near(&flat, 2), Some(3));
+        assert_eq!(item_index_near(&flat, 10), Some(3));
+        assert_eq!(item_index_near(&[], 3), None);
+    }
+
     #[test]
     fn next_item_skips_headers() {
         let summaries = vec![
@@ -245,4 +355,37 @@ mod tests {
         assert_eq!(kind_label(&DefinitionKind::Skill), "Skills");
         assert_eq!(kind_label(&DefinitionKind::Mcp), "MCP Servers");
     }
+
+    #[test]
+    fn single_kind_rows_carry_labels_and_indices() {
+        let summaries = vec![
+            summary("agent1", DefinitionKind::Agent),
+            summary("agent2", DefinitionKind::Agent),
+        ];
+
+        let (groups, flat) = build_groups(&summaries);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kind, DefinitionKind::Agent);
+        assert!(
+            matches!(&flat[0], ListRow::Header { label, count, .. } if label == "Agents" && *count == 2)
+        );
+        assert!(matches!(&flat[1], ListRow::Item { summary_index: 0 }));
+        assert!(matches!(&flat[2], ListRow::Item { summary_index: 1 }));
+    }
+
+    #[test]
+    fn items_keep_original_summary_indices_after_sorting() {
+        let summaries = vec![
+            summary("skill1", DefinitionKind::Skill),
+            summary("agent1", DefinitionKind::Agent),
+            summary("command1", DefinitionKind::Command),
+        ];
+
+        let (_, flat) = build_groups(&summaries);
+
+        assert!(matches!(&flat[1], ListRow::Item { summary_index: 1 }));
+        assert!(matches!(&flat[3], ListRow::Item { summary_index: 2 }));
+        assert!(matches!(&flat[5], ListRow::Item { summary_index: 0 }));
+    }
 }
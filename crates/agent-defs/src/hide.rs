@@ -0,0 +1,179 @@
+//! Rules for hiding definitions the user never wants to see, by ID, by
+//! category, or by a [glob](crate::glob) over IDs such as `agents/blockchain/**`.
+//!
+//! Rules are written one per line as `id:…`, `category:…` or `glob:…`, the
+//! same form [`HideRule::parse`] reads back.
+
+use crate::{DefinitionId, DefinitionSummary, glob};
+
+/// One way of hiding definitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HideRule {
+    /// A single definition: in whichever source has it, or only in one
+    /// source when the ID is qualified as `label:path`.
+    Id(DefinitionId),
+    /// Every definition in a category, ignoring case.
+    Category(String),
+    /// Definitions whose ID matches the glob pattern.
+    Glob(String),
+}
+
+impl HideRule {
+    /// Read a rule in its `kind:value` form. A bare value is taken as an ID.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let rule = match s.split_once(':') {
+            Some(("id", id)) => Self::Id(DefinitionId::new(id.trim())),
+            Some(("category", category)) => Self::Category(category.trim().to_owned()),
+            Some(("glob", pattern)) => Self::Glob(pattern.trim().to_owned()),
+            _ => Self::Id(DefinitionId::new(s)),
+        };
+        (!rule.value().is_empty()).then_some(rule)
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Self::Id(id) => id.as_str(),
+            Self::Category(category) => category,
+            Self::Glob(pattern) => pattern,
+        }
+    }
+
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        match self {
+            Self::Id(id) => {
+                summary.id == *id
+                    || id
+                        .as_str()
+                        .strip_prefix(summary.source_label.as_str())
+                        .and_then(|rest| rest.strip_prefix(':'))
+                        == Some(summary.id.as_str())
+            }
+            Self::Category(category) => summary
+                .category
+                .as_ref()
+                .is_some_and(|c| c.eq_ignore_ascii_case(category)),
+            Self::Glob(pattern) => glob::matches(pattern, summary.id.as_str()),
+        }
+    }
+}
+
+impl std::fmt::Display for HideRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "id:{id}"),
+            Self::Category(category) => write!(f, "category:{category}"),
+            Self::Glob(pattern) => write!(f, "glob:{pattern}"),
+        }
+    }
+}
+
+/// The user's hide rules. A definition is hidden if any rule matches it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HideRules {
+    rules: Vec<HideRule>,
+}
+
+impl HideRules {
+    /// Read rules one per line, skipping blank lines and `#` comments.
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(HideRule::parse)
+            .collect();
+        Self { rules }
+    }
+
+    pub fn rules(&self) -> &[HideRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Add a rule, returning `false` if it was already there.
+    pub fn add(&mut self, rule: HideRule) -> bool {
+        if self.rules.contains(&rule) {
+            return false;
+        }
+        self.rules.push(rule);
+        true
+    }
+
+    /// Remove a rule, returning whether it was there.
+    pub fn remove(&mut self, rule: &HideRule) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|r| r != rule);
+        self.rules.len() != before
+    }
+
+    pub fn hides(&self, summary: &DefinitionSummary) -> bool {
+        self.rules.iter().any(|rule| rule.matches(summary))
+    }
+}
+
+impl std::fmt::Display for HideRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rule in &self.rules {
+            writeln!(f, "{rule}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefinitionKind;
+
+    fn summary(id: &str, category: Option<&str>) -> DefinitionSummary {
+        DefinitionSummary {
+            id: DefinitionId::new(id),
+            name: id.to_owned(),
+            description: None,
+            kind: DefinitionKind::Agent,
+            category: category.map(str::to_owned),
+            source_label: "acme".into(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn rules_round_trip_through_text() {
+        let text = "# hidden\nid:agents/a.md\n\ncategory:Blockchain\nglob:skills/*\nagents/b.md\n";
+        let rules = HideRules::parse(text);
+        assert_eq!(rules.rules().len(), 4);
+        assert_eq!(HideRules::parse(&rules.to_string()), rules);
+        assert_eq!(rules.rules()[3], HideRule::Id(DefinitionId::new("agents/b.md")));
+    }
+
+    #[test]
+    fn each_rule_kind_matches() {
+        let rules = HideRules::parse("id:agents/a.md\ncategory:blockchain\nglob:skills/*/SKILL.md");
+        assert!(rules.hides(&summary("agents/a.md", None)));
+        assert!(rules.hides(&summary("agents/web3.md", Some("Blockchain"))));
+        assert!(rules.hides(&summary("skills/crypto/SKILL.md", None)));
+        assert!(!rules.hides(&summary("agents/b.md", Some("testing"))));
+
+        let qualified = HideRules::parse("acme:agents/b.md
+other:agents/c.md");
+        assert!(qualified.hides(&summary("agents/b.md", None)));
+        assert!(!qualified.hides(&summary("agents/c.md", None)));
+        assert!(!rules.hides(&summary("skills/crypto/README.md", None)));
+    }
+
+    #[test]
+    fn add_and_remove_report_changes() {
+        let mut rules = HideRules::default();
+        let rule = HideRule::Category("blockchain".into());
+        assert!(rules.add(rule.clone()));
+        assert!(!rules.add(rule.clone()));
+        assert!(rules.remove(&rule));
+        assert!(!rules.remove(&rule));
+        assert!(rules.is_empty());
+    }
+}
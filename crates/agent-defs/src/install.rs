@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use crate::definition::{Definition, DefinitionKind};
+use crate::policy::Policy;
+use crate::template::{self, TemplateError};
 
 /// Errors that can occur during install operations.
 #[derive(Debug, thiserror::Error)]
@@ -9,6 +12,36 @@ pub enum InstallError {
     Io(#[from] std::io::Error),
     #[error("no raw content available")]
     NoContent,
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+    #[error("invalid JSON in {0}")]
+    InvalidJson(String),
+    #[error("{} already sets {} differently", path.display(), keys.join(", "))]
+    Conflict { path: PathBuf, keys: Vec<String> },
+}
+
+/// How definitions are arranged under a target's `.claude` directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum InstallLayout {
+    /// Categories become directories, as [`install_path`] describes.
+    #[default]
+    Nested,
+    /// Categories are left out: `.claude/agents/name.md` and
+    /// `.claude/skills/name/SKILL.md`.
+    Flat,
+}
+
+/// What an install follows besides its target: the layout to write in, and
+/// the org policy a definition has to pass first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallRules {
+    pub layout: InstallLayout,
+    pub policy: Policy,
 }
 
 /// Compute where a definition should be installed within a target directory.
@@ -17,57 +50,192 @@ pub enum InstallError {
 /// - `target/.claude/agents/cat/name.md`
 /// - `target/.claude/hooks/name.md`
 /// - `target/.claude/skills/cat/name/SKILL.md`
+/// - `target/.mcp.json` and `target/.claude/settings.json` for JSON MCP and
+///   setting definitions, which are merged in (see [`merge_target`])
+///
+/// Categories may contain `/` or `\` to nest directories. Every component
+/// is sanitized, so neither `..` nor a drive letter or root can move the
+/// result outside `target/.claude`.
 pub fn install_path(target: &Path, def: &Definition) -> PathBuf {
-    let kind_dir = kind_directory(&def.kind);
-    let base = target.join(".claude").join(kind_dir);
-
-    match &def.kind {
-        DefinitionKind::Skill => {
-            let cat = def.category.as_deref().unwrap_or("general");
-            let name = sanitize_filename(&def.name);
-            base.join(cat).join(name).join("SKILL.md")
+    InstallLayout::Nested.path(target, def)
+}
+
+impl InstallLayout {
+    /// Where `def` goes under `target` in this layout.
+    pub fn path(self, target: &Path, def: &Definition) -> PathBuf {
+        if let Some(path) = merge_target(target, def) {
+            return path;
+        }
+        let mut path = target.join(".claude").join(kind_directory(&def.kind));
+        let categories = match self {
+            Self::Nested => def.category.as_deref().map(category_components),
+            Self::Flat => None,
         }
-        _ => {
-            let name = format!("{}.md", sanitize_filename(&def.name));
-            if let Some(cat) = &def.category {
-                base.join(cat).join(name)
-            } else {
-                base.join(name)
+        .unwrap_or_default();
+
+        match &def.kind {
+            DefinitionKind::Skill => {
+                if categories.is_empty() && self == Self::Nested {
+                    path.push("general");
+                }
+                path.extend(categories);
+                path.push(file_stem(&def.name));
+                path.push("SKILL.md");
+            }
+            _ => {
+                path.extend(categories);
+                path.push(format!("{}.md", file_stem(&def.name)));
             }
         }
+        path
     }
+
+    /// The `install_layout` a user's `sources.toml` sets, read from its
+    /// text without the rest of the config, for front ends that don't sync.
+    /// `None` when it isn't set or the file doesn't parse.
+    pub fn from_config(text: &str) -> Option<Self> {
+        let config: toml::Table = toml::from_str(text).ok()?;
+        match config.get("install_layout")?.as_str()? {
+            "nested" => Some(Self::Nested),
+            "flat" => Some(Self::Flat),
+            _ => None,
+        }
+    }
+
+    /// [`install_definition_with_vars`] in this layout.
+    pub fn install(
+        self,
+        target: &Path,
+        def: &Definition,
+        values: &HashMap<String, String>,
+    ) -> Result<PathBuf, InstallError> {
+        if def.raw.is_empty() {
+            return Err(InstallError::NoContent);
+        }
+        let content = template::render(&def.raw, values)?;
+        let path = self.path(target, def);
+        install_content(&path, &content)?;
+        Ok(path)
+    }
+}
+
+/// The shared config file a JSON MCP server or settings definition is
+/// merged into, or `None` for definitions installed as files of their own.
+pub fn merge_target(target: &Path, def: &Definition) -> Option<PathBuf> {
+    if !def.id.as_str().ends_with(".json") {
+        return None;
+    }
+    match def.kind {
+        DefinitionKind::Mcp => Some(target.join(".mcp.json")),
+        DefinitionKind::Setting => Some(target.join(".claude").join("settings.json")),
+        _ => None,
+    }
+}
+
+/// Write content to a path from [`install_path`]. JSON config files are
+/// merged into rather than replaced, and a conflicting key stops the
+/// install with [`InstallError::Conflict`].
+pub fn install_content(path: &Path, content: &str) -> Result<(), InstallError> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return crate::merge::merge_file(path, content).map(drop);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
 }
 
 /// Write a definition's raw content to its install path. Creates directories as needed.
 /// Returns the path written on success.
+///
+/// The content is written verbatim, template placeholders included; use
+/// [`install_definition_with_vars`] to fill them in.
 pub fn install_definition(target: &Path, def: &Definition) -> Result<PathBuf, InstallError> {
     if def.raw.is_empty() {
         return Err(InstallError::NoContent);
     }
     let path = install_path(target, def);
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    std::fs::write(&path, &def.raw)?;
+    install_content(&path, &def.raw)?;
     Ok(path)
 }
 
-fn kind_directory(kind: &DefinitionKind) -> &str {
-    match kind {
+/// Like [`install_definition`], but first substitutes the definition's
+/// template variables (see [`crate::template`]) from `values`. Nothing is
+/// written if a declared variable has neither a value nor a default.
+pub fn install_definition_with_vars(
+    target: &Path,
+    def: &Definition,
+    values: &HashMap<String, String>,
+) -> Result<PathBuf, InstallError> {
+    InstallLayout::Nested.install(target, def, values)
+}
+
+fn kind_directory(kind: &DefinitionKind) -> String {
+    let dir = match kind {
         DefinitionKind::Agent => "agents",
         DefinitionKind::Command => "commands",
         DefinitionKind::Hook => "hooks",
         DefinitionKind::Mcp => "mcp",
         DefinitionKind::Setting => "settings",
         DefinitionKind::Skill => "skills",
-        DefinitionKind::Other(s) => s.as_str(),
-    }
+        DefinitionKind::Other(s) => return non_empty(sanitize_filename(s), "other"),
+    };
+    dir.to_owned()
+}
+
+/// Split a category on either separator, dropping a leading drive letter and
+/// anything that sanitizes away (empty segments, `.`, `..`).
+fn category_components(category: &str) -> Vec<String> {
+    category
+        .split(['/', '\\'])
+        .enumerate()
+        .filter(|(i, part)| !(*i == 0 && is_drive_prefix(part)))
+        .map(|(_, part)| sanitize_filename(part))
+        .filter(|part| !part.is_empty())
+        .collect()
 }
 
+fn is_drive_prefix(part: &str) -> bool {
+    let bytes = part.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn file_stem(name: &str) -> String {
+    non_empty(sanitize_filename(name), "unnamed")
+}
+
+fn non_empty(value: String, fallback: &str) -> String {
+    if value.is_empty() { fallback.to_owned() } else { value }
+}
+
+/// Replace anything that isn't safe in a file name on every platform. Windows
+/// silently drops trailing dots and refuses device names like `con` or `nul`
+/// (with or without an extension), so those are adjusted too.
 fn sanitize_filename(name: &str) -> String {
-    name.chars()
+    let cleaned: String = name
+        .chars()
         .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '-' })
-        .collect()
+        .collect();
+    let cleaned = cleaned.trim_end_matches('.');
+    if is_reserved_on_windows(cleaned) {
+        let (stem, extension) = cleaned.split_at(cleaned.find('.').unwrap_or(cleaned.len()));
+        format!("{stem}_{extension}")
+    } else {
+        cleaned.to_owned()
+    }
+}
+
+fn is_reserved_on_windows(name: &str) -> bool {
+    const DEVICES: [&str; 4] = ["con", "prn", "aux", "nul"];
+    let stem = name.split('.').next().unwrap_or(name).to_ascii_lowercase();
+    if DEVICES.contains(&stem.as_str()) {
+        return true;
+    }
+    match stem.strip_prefix("com").or_else(|| stem.strip_prefix("lpt")) {
+        Some(digit) => matches!(digit.as_bytes(), [b'1'..=b'9']),
+        None => false,
+    }
 }
 
 #[cfg(test)]
@@ -134,6 +302,131 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flat_layout_leaves_categories_out() {
+        let target = Path::new("/target");
+        let agent = make_def("reviewer", DefinitionKind::Agent, Some("dev/team"), "");
+        let skill = make_def("rust-analyzer", DefinitionKind::Skill, None, "");
+        let mcp = make_def("github.json", DefinitionKind::Mcp, Some("tools"), "");
+        assert_eq!(
+            InstallLayout::Flat.path(target, &agent),
+            PathBuf::from("/target/.claude/agents/reviewer.md")
+        );
+        assert_eq!(
+            InstallLayout::Flat.path(target, &skill),
+            PathBuf::from("/target/.claude/skills/rust-analyzer/SKILL.md")
+        );
+        assert_eq!(InstallLayout::Flat.path(target, &mcp), PathBuf::from("/target/.mcp.json"));
+    }
+
+    #[test]
+    fn install_path_nests_categories_with_either_separator() {
+        let forward = make_def("x", DefinitionKind::Agent, Some("dev/tools"), "");
+        let backward = make_def("x", DefinitionKind::Agent, Some("dev\\tools"), "");
+        let expected = Path::new("/target")
+            .join(".claude")
+            .join("agents")
+            .join("dev")
+            .join("tools")
+            .join("x.md");
+        assert_eq!(install_path(Path::new("/target"), &forward), expected);
+        assert_eq!(install_path(Path::new("/target"), &backward), expected);
+    }
+
+    #[test]
+    fn install_path_ignores_parent_and_drive_components() {
+        let target = Path::new("/target");
+        let agents = target.join(".claude").join("agents");
+        for category in ["../../etc", "..\\..\\etc", "C:\\etc", "c:/etc", "/etc", "\\etc", "./etc/."] {
+            let def = make_def("x", DefinitionKind::Agent, Some(category), "");
+            assert_eq!(
+                install_path(target, &def),
+                agents.join("etc").join("x.md"),
+                "category {category:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn install_path_skill_falls_back_when_category_sanitizes_away() {
+        let def = make_def("s", DefinitionKind::Skill, Some(".."), "");
+        assert_eq!(
+            install_path(Path::new("/target"), &def),
+            PathBuf::from("/target/.claude/skills/general/s/SKILL.md")
+        );
+    }
+
+    #[test]
+    fn install_path_sanitizes_name_and_other_kind() {
+        let def = make_def("..", DefinitionKind::Other("..\\evil".into()), None, "");
+        assert_eq!(
+            install_path(Path::new("/target"), &def),
+            PathBuf::from("/target/.claude/..-evil/unnamed.md")
+        );
+    }
+
+    #[test]
+    fn sanitize_filename_avoids_windows_device_names() {
+        assert_eq!(sanitize_filename("con"), "con_");
+        assert_eq!(sanitize_filename("NUL.txt"), "NUL_.txt");
+        assert_eq!(sanitize_filename("aux.tar.gz"), "aux_.tar.gz");
+        assert_eq!(sanitize_filename("com1"), "com1_");
+        assert_eq!(sanitize_filename("com10"), "com10");
+        assert_eq!(sanitize_filename("console"), "console");
+        assert_eq!(sanitize_filename("notes."), "notes");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn install_path_under_drive_letter_target() {
+        let def = make_def("reviewer", DefinitionKind::Agent, Some("dev/team"), "");
+        assert_eq!(
+            install_path(Path::new(r"C:\Users\me\project"), &def),
+            PathBuf::from(r"C:\Users\me\project\.claude\agents\dev\team\reviewer.md")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn install_path_under_unc_target() {
+        let def = make_def("fmt", DefinitionKind::Hook, None, "");
+        assert_eq!(
+            install_path(Path::new(r"\\server\share\repo"), &def),
+            PathBuf::from(r"\\server\share\repo\.claude\hooks\fmt.md")
+        );
+    }
+
+    #[test]
+    fn json_mcp_and_settings_install_into_shared_config() {
+        let mcp = make_def("github.json", DefinitionKind::Mcp, None, "");
+        let setting = make_def("statusline.json", DefinitionKind::Setting, Some("ui"), "");
+        let markdown = make_def("github", DefinitionKind::Mcp, None, "");
+        let target = Path::new("/target");
+        assert_eq!(install_path(target, &mcp), PathBuf::from("/target/.mcp.json"));
+        assert_eq!(
+            install_path(target, &setting),
+            PathBuf::from("/target/.claude/settings.json")
+        );
+        assert_eq!(install_path(target, &markdown), PathBuf::from("/target/.claude/mcp/github.md"));
+    }
+
+    #[test]
+    fn install_definition_merges_json_config() {
+        let dir = std::env::temp_dir().join("agent-defs-test-install-merge");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".mcp.json"), r#"{"mcpServers": {"local": {}}}"#).unwrap();
+
+        let raw = r#"{"name": "github", "mcpServers": {"github": {"command": "npx"}}}"#;
+        let def = make_def("github.json", DefinitionKind::Mcp, None, raw);
+        let path = install_definition(&dir, &def).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\"local\""), "{written}");
+        assert!(written.contains("\"github\""), "{written}");
+        assert!(!written.contains("\"name\""), "{written}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn install_definition_creates_dirs_and_writes() {
         let dir = std::env::temp_dir().join("agent-defs-test-install");
@@ -163,6 +456,42 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn install_definition_with_vars_substitutes_placeholders() {
+        let dir = std::env::temp_dir().join("agent-defs-test-template");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let raw = "---\nvars: [PROJECT]\n---\nWork on {{ PROJECT }}.";
+        let def = make_def("templated", DefinitionKind::Agent, None, raw);
+        let values = HashMap::from([("PROJECT".to_owned(), "crate".to_owned())]);
+        let path = install_definition_with_vars(&dir, &def, &values).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "---\nvars: [PROJECT]\n---\nWork on crate."
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn install_definition_with_missing_vars_writes_nothing() {
+        let dir = std::env::temp_dir().join("agent-defs-test-template-missing");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let def = make_def("templated", DefinitionKind::Agent, None, "---\nvars: [A]\n---\n{{A}}");
+        let result = install_definition_with_vars(&dir, &def, &HashMap::new());
+        assert!(matches!(result, Err(InstallError::Template(TemplateError::Missing(_)))));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn layout_is_read_from_config_text() {
+        let config = "install_layout = \"flat\"\n\n[[sources]]\nlabel = \"x\"\n";
+        assert_eq!(InstallLayout::from_config(config), Some(InstallLayout::Flat));
+        assert_eq!(InstallLayout::from_config("[[sources]]\nlabel = \"x\"\n"), None);
+        assert_eq!(InstallLayout::from_config("install_layout = \"sideways\""), None);
+    }
+
     #[test]
     fn install_definition_errors_on_empty_raw() {
         let dir = std::env::temp_dir().join("agent-defs-test-empty");
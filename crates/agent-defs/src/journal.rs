@@ -0,0 +1,115 @@
+//! A record of a bulk install kept while it runs. Every definition is
+//! planned before anything is written, and each write notes what the file
+//! held before it, so an install that dies part way can be reported,
+//! finished, or undone the next time the app starts.
+
+use std::path::PathBuf;
+
+use crate::DefinitionId;
+
+/// Identifies one journaled install.
+pub type BatchId = i64;
+
+/// How far one definition in a batch got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalState {
+    /// Nothing written yet.
+    Planned,
+    /// The write began but was never confirmed, so the file may or may not
+    /// have changed.
+    Started,
+    Done,
+    /// The install failed; the error is kept for the report.
+    Failed(String),
+}
+
+/// One definition in a journaled install.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub id: DefinitionId,
+    pub source_label: String,
+    /// Where the definition was written, once the write has started.
+    pub path: Option<PathBuf>,
+    /// What the file held before the write, or `None` if it didn't exist.
+    pub previous: Option<String>,
+    pub state: JournalState,
+}
+
+/// A bulk install that was journaled and never marked finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallBatch {
+    pub id: BatchId,
+    /// What was being installed, such as a collection name.
+    pub label: String,
+    /// The project directory installed into.
+    pub target: PathBuf,
+    /// When the install began, in seconds since the Unix epoch.
+    pub started_at: u64,
+    /// In the order they were planned.
+    pub entries: Vec<JournalEntry>,
+}
+
+impl InstallBatch {
+    /// How many definitions were installed before it stopped.
+    pub fn done(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.state == JournalState::Done)
+            .count()
+    }
+
+    /// Definitions never confirmed installed or failed, which resuming
+    /// installs again.
+    pub fn unfinished(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.state, JournalState::Planned | JournalState::Started))
+    }
+
+    /// Definitions whose files may have changed, newest first, which is the
+    /// order rolling back undoes them in.
+    pub fn written(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().rev().filter(|entry| {
+            entry.path.is_some()
+                && matches!(entry.state, JournalState::Started | JournalState::Done)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, state: JournalState) -> JournalEntry {
+        JournalEntry {
+            id: DefinitionId::new(id),
+            source_label: "acme".into(),
+            path: (state != JournalState::Planned).then(|| PathBuf::from(id)),
+            previous: None,
+            state,
+        }
+    }
+
+    #[test]
+    fn entries_split_by_how_far_they_got() {
+        let batch = InstallBatch {
+            id: 1,
+            label: "backend".into(),
+            target: PathBuf::from("/project"),
+            started_at: 0,
+            entries: vec![
+                entry("a", JournalState::Done),
+                entry("b", JournalState::Failed("not found".into())),
+                entry("c", JournalState::Started),
+                entry("d", JournalState::Planned),
+            ],
+        };
+        let ids = |entries: Vec<&JournalEntry>| -> Vec<String> {
+            entries.iter().map(|e| e.id.as_str().to_owned()).collect()
+        };
+
+        assert_eq!(batch.done(), 1);
+        assert_eq!(ids(batch.unfinished().collect()), ["c", "d"]);
+        assert_eq!(ids(batch.written().collect()), ["c", "a"]);
+    }
+}
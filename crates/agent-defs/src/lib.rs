@@ -1,20 +1,86 @@
+//! Core definition model and parsing for agent definitions.
+//!
+//! With `default-features = false` this crate only contains the model,
+//! frontmatter and path parsing, and grouping, and builds for
+//! `wasm32-unknown-unknown`. The `install` and `source` features add
+//! filesystem installs and the async source traits.
+
 pub mod builder;
+pub mod category;
+pub mod changelog;
+pub mod collection;
+#[cfg(feature = "source")]
 pub mod composite;
 pub mod definition;
+#[cfg(feature = "source")]
+pub mod demo;
+pub mod density;
+pub mod diff;
 pub mod feedback;
+#[cfg(feature = "source")]
+pub mod fixtures;
 pub mod frontmatter;
+pub mod glob;
+pub mod glyph;
+pub mod grouping;
+pub mod hide;
+#[cfg(feature = "install")]
 pub mod install;
+pub mod journal;
+#[cfg(feature = "install")]
+pub mod lockfile;
+#[cfg(feature = "install")]
+pub mod merge;
+pub mod note;
 pub mod path;
+#[cfg(feature = "install")]
+pub mod policy;
+pub mod query;
+pub mod rating;
+pub mod scan;
+pub mod section;
 pub mod source;
+#[cfg(feature = "source")]
 pub mod sync;
+pub mod task;
+pub mod template;
+pub mod tree;
+pub mod usage;
 
+pub use category::CategoryMap;
+pub use changelog::{ChangeKind, DefinitionChange};
+pub use collection::{Collection, CollectionItem};
+#[cfg(feature = "source")]
 pub use composite::CompositeSource;
-pub use definition::{Definition, DefinitionId, DefinitionKind, DefinitionSummary};
-pub use feedback::Feedback;
+pub use definition::{Definition, DefinitionId, DefinitionKind, DefinitionSummary, DisplayNames};
+pub use density::ListDensity;
+pub use feedback::{Feedback, SkippedFile};
 pub use frontmatter::{parse as parse_frontmatter, Frontmatter, ParsedDocument};
-pub use install::{InstallError, install_definition, install_path};
-pub use source::{Source, SourceError};
-pub use sync::{RawDefinitionFile, SyncError, SyncProvider};
+pub use glob::PathFilter;
+pub use glyph::GlyphSet;
+pub use hide::{HideRule, HideRules};
+#[cfg(feature = "install")]
+pub use install::{
+    InstallError, InstallLayout, InstallRules, install_definition, install_definition_with_vars,
+    install_path,
+};
+pub use journal::{BatchId, InstallBatch, JournalEntry, JournalState};
+#[cfg(feature = "install")]
+pub use lockfile::{LockError, LockedDefinition, Lockfile};
+pub use note::DefinitionNote;
+#[cfg(feature = "install")]
+pub use policy::{Policy, PolicyError, PolicyViolation};
+pub use query::{Query, TextQuery};
+pub use rating::Rating;
+pub use section::Section;
+pub use source::{ErrorKind, Location, SourceError, guidance};
+#[cfg(feature = "source")]
+pub use source::Source;
+#[cfg(feature = "source")]
+pub use sync::{FileCheck, FilteredProvider, RawDefinitionFile, SyncError, SyncProvider};
+pub use task::{Task, TaskId, TaskQueue, TaskState};
+pub use template::{TemplateError, TemplateVar};
+pub use usage::{DefinitionUsage, UsageKind};
 
-#[cfg(any(test, feature = "test-support"))]
+#[cfg(all(feature = "source", any(test, feature = "test-support")))]
 pub mod test_support;
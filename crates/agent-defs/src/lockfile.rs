@@ -0,0 +1,360 @@
+//! A project's `agent-defs.lock`, pinning each definition installed into it
+//! to the source revision and exact content it came from, so the same
+//! install can be repeated elsewhere:
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[definitions]]
+//! source = "acme"
+//! id = "agents/reviewer.md"
+//! path = ".claude/agents/reviewer.md"
+//! revision = "1a2b3c4"
+//! sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//! installed_sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//!
+//! [definitions.vars]
+//! PROJECT = "crate"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Definition, DefinitionId};
+
+/// File name of the lockfile, kept in the directory definitions are
+/// installed into.
+pub const LOCKFILE_NAME: &str = "agent-defs.lock";
+
+/// Format version written into every lockfile. Readers reject newer ones.
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Errors reading or writing a lockfile.
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    #[error("failed to read {}: {error}", path.display())]
+    Unreadable {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error("invalid lockfile at {}: {message}", path.display())]
+    Invalid { path: PathBuf, message: String },
+    #[error("failed to write {}: {error}", path.display())]
+    Unwritable {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+/// Every definition pinned for one install target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    /// Sorted by source, then ID, so the file diffs cleanly.
+    #[serde(default)]
+    pub definitions: Vec<LockedDefinition>,
+}
+
+/// One installed definition, as it was when installed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedDefinition {
+    pub source: String,
+    pub id: String,
+    /// Where it was installed, relative to the lockfile's directory.
+    pub path: String,
+    /// The source's revision, such as a commit SHA, when it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// SHA-256 of the definition's file as the source has it, before any
+    /// template variables are filled in.
+    pub sha256: String,
+    /// SHA-256 of the file as it was written, to tell when it's been edited
+    /// since. Not kept for JSON config files other installs merge into.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_sha256: Option<String>,
+    /// Whether the installed file was customized on purpose and `update`
+    /// should leave it alone.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keep_local: bool,
+    /// Template variable values it was installed with, so an update fills
+    /// in the new version the same way. Defaults aren't recorded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            definitions: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// The lockfile for installs into `dir`.
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(LOCKFILE_NAME)
+    }
+
+    /// Read the lockfile in `dir`. A missing one is empty.
+    pub fn load(dir: &Path) -> Result<Self, LockError> {
+        let path = Self::path(dir);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(LockError::Unreadable { path, error }),
+        };
+        Self::parse(&text).map_err(|message| LockError::Invalid { path, message })
+    }
+
+    /// Parse lockfile TOML, refusing one written by a newer version or one
+    /// pinning a path installs would never write to. A lockfile is
+    /// committed with a project, so a path outside `.claude/` or
+    /// `.mcp.json` could point `install --locked` anywhere.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lockfile: Self = toml::from_str(text).map_err(|e| e.message().to_owned())?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(format!(
+                "lockfile format {} is newer than this build reads ({LOCKFILE_VERSION}); \
+                 upgrade agent-def-fetcher",
+                lockfile.version
+            ));
+        }
+        for locked in &lockfile.definitions {
+            if !is_install_path(&locked.path) {
+                return Err(format!(
+                    "{} is pinned to `{}`, which isn't under .claude/ or .mcp.json",
+                    locked.qualified_id(),
+                    locked.path
+                ));
+            }
+        }
+        Ok(lockfile)
+    }
+
+    /// Write the lockfile into `dir`, returning its path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, LockError> {
+        let path = Self::path(dir);
+        let text = toml::to_string(self).map_err(|e| LockError::Invalid {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+        let written = std::fs::create_dir_all(dir).and_then(|()| {
+            std::fs::write(
+                &path,
+                format!("# Written by agent-def-fetcher; commit it with the project.\n{text}"),
+            )
+        });
+        match written {
+            Ok(()) => Ok(path),
+            Err(error) => Err(LockError::Unwritable { path, error }),
+        }
+    }
+
+    /// The pin for `id` from `source`, if there is one.
+    pub fn get(&self, source: &str, id: &DefinitionId) -> Option<&LockedDefinition> {
+        self.definitions
+            .iter()
+            .find(|locked| locked.source == source && locked.id == id.as_str())
+    }
+
+    /// Add `locked`, replacing any pin for the same definition.
+    pub fn pin(&mut self, locked: LockedDefinition) {
+        self.definitions
+            .retain(|other| (&other.source, &other.id) != (&locked.source, &locked.id));
+        let at = self
+            .definitions
+            .partition_point(|other| (&other.source, &other.id) < (&locked.source, &locked.id));
+        self.definitions.insert(at, locked);
+    }
+}
+
+impl LockedDefinition {
+    /// Pin `def`, installed at `path` under `dir`, at `revision`.
+    pub fn new(def: &Definition, dir: &Path, path: &Path, revision: Option<String>) -> Self {
+        let relative = path.strip_prefix(dir).unwrap_or(path);
+        let path: Vec<String> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        Self {
+            source: def.source_label.clone(),
+            id: def.id.as_str().to_owned(),
+            path: path.join("/"),
+            revision,
+            sha256: content_hash(&def.raw),
+            installed_sha256: None,
+            keep_local: false,
+            vars: BTreeMap::new(),
+        }
+    }
+
+    /// Record the template variable values it was installed with.
+    pub fn with_vars(mut self, vars: BTreeMap<String, String>) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// The pinned template variable values, as rendering takes them.
+    pub fn values(&self) -> HashMap<String, String> {
+        self.vars.clone().into_iter().collect()
+    }
+
+    /// Record `content` as what was written to the pinned path.
+    pub fn with_installed(mut self, content: &str) -> Self {
+        if !self.path.ends_with(".json") {
+            self.installed_sha256 = Some(content_hash(content));
+        }
+        self
+    }
+
+    /// `source:id`, as commands take it.
+    pub fn qualified_id(&self) -> DefinitionId {
+        DefinitionId::new(&self.id).qualified(&self.source)
+    }
+
+    /// Whether `def` has exactly the pinned content.
+    pub fn matches(&self, def: &Definition) -> bool {
+        content_hash(&def.raw) == self.sha256
+    }
+
+    /// Whether `installed`, the file at the pinned path now, differs from
+    /// what was written there. Unknown counts as unedited.
+    pub fn edited(&self, installed: &str) -> bool {
+        self.installed_sha256
+            .as_ref()
+            .is_some_and(|hash| *hash != content_hash(installed))
+    }
+}
+
+/// Whether `path`, relative to the lockfile's directory, is somewhere an
+/// install writes: inside `.claude/`, or the `.mcp.json` MCP servers are
+/// merged into. Both separators are split on and no component may be
+/// empty, `.`, `..`, or carry a drive prefix, whatever platform reads it.
+fn is_install_path(path: &str) -> bool {
+    let parts: Vec<&str> = path.split(['/', '\\']).collect();
+    let plain = parts
+        .iter()
+        .all(|part| !matches!(*part, "" | "." | "..") && !part.contains(':'));
+    plain && ((parts[0] == ".claude" && parts.len() > 1) || path == ".mcp.json")
+}
+
+/// Hex SHA-256 of `text`.
+pub fn content_hash(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::definition;
+
+    fn locked(source: &str, id: &str) -> LockedDefinition {
+        LockedDefinition {
+            source: source.into(),
+            id: id.into(),
+            path: format!(".claude/{id}"),
+            revision: None,
+            sha256: content_hash(""),
+            installed_sha256: None,
+            keep_local: false,
+            vars: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn pins_stay_sorted_and_unique() {
+        let mut lockfile = Lockfile::default();
+        lockfile.pin(locked("b", "agents/x.md"));
+        lockfile.pin(locked("a", "agents/y.md"));
+        lockfile.pin(locked("a", "agents/x.md"));
+        lockfile.pin(LockedDefinition {
+            revision: Some("1a2b3c4".into()),
+            ..locked("b", "agents/x.md")
+        });
+
+        let ids: Vec<String> = lockfile
+            .definitions
+            .iter()
+            .map(|locked| locked.qualified_id().as_str().to_owned())
+            .collect();
+        assert_eq!(ids, ["a:agents/x.md", "a:agents/y.md", "b:agents/x.md"]);
+        let pinned = lockfile.get("b", &DefinitionId::new("agents/x.md")).unwrap();
+        assert_eq!(pinned.revision.as_deref(), Some("1a2b3c4"));
+    }
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let dir = std::env::temp_dir().join("agent-defs-test-lockfile");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(Lockfile::load(&dir).unwrap(), Lockfile::default());
+
+        let def = definition("agents/helper.md");
+        let path = dir.join(".claude").join("agents").join("helper.md");
+        let pin = LockedDefinition::new(&def, &dir, &path, Some("1a2b3c4".into()))
+            .with_installed("Helps.");
+        assert_eq!(pin.path, ".claude/agents/helper.md");
+        assert!(!pin.edited("Helps."));
+        assert!(pin.edited("Helps, my way."));
+        assert!(pin.matches(&def));
+        assert!(!pin.matches(&crate::Definition {
+            raw: "changed".into(),
+            ..def.clone()
+        }));
+
+        let mut lockfile = Lockfile::default();
+        lockfile.pin(LockedDefinition {
+            keep_local: true,
+            ..pin.with_vars(BTreeMap::from([("PROJECT".into(), "crate".into())]))
+        });
+        lockfile.save(&dir).unwrap();
+        assert_eq!(Lockfile::load(&dir).unwrap(), lockfile);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_pins_outside_the_install_directories() {
+        let pin = |path: &str| {
+            format!(
+                "version = 1\n[[definitions]]\nsource = \"acme\"\nid = \"agents/x.md\"\n\
+                 path = '{path}'\nsha256 = \"0\"\n"
+            )
+        };
+        for hostile in [
+            "../../.bashrc",
+            "/etc/passwd",
+            ".claude/../../.bashrc",
+            ".claude\\..\\..\\.bashrc",
+            "C:\\Users\\me\\.bashrc",
+            ".claude",
+            ".claude//agents/x.md",
+            "src/main.rs",
+        ] {
+            let err = Lockfile::parse(&pin(hostile)).unwrap_err();
+            assert!(err.contains("isn't under .claude/"), "{hostile}: {err}");
+        }
+        assert!(Lockfile::parse(&pin(".claude/agents/x.md")).is_ok());
+        assert!(Lockfile::parse(&pin(".claude/settings.json")).is_ok());
+        assert!(Lockfile::parse(&pin(".mcp.json")).is_ok());
+    }
+
+    #[test]
+    fn refuses_newer_formats() {
+        let err = Lockfile::parse("version = 99").unwrap_err();
+        assert!(err.contains("newer"), "{err}");
+    }
+}
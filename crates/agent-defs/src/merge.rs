@@ -0,0 +1,435 @@
+//! Installing JSON definitions into a config file that other tools also
+//! write, such as `.mcp.json` or `.claude/settings.json`.
+//!
+//! The definition is deep-merged into what the file already holds: objects
+//! merge key by key, and anything else (strings, numbers, arrays) is a single
+//! value. A key both sides set to different values is a [`MergeConflict`],
+//! left to the caller to settle before anything is written. The file as it
+//! was is copied to a backup next to it first. Keys keep the order the file
+//! and the definition wrote them in.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::install::InstallError;
+
+/// Top-level keys that describe a JSON definition in the catalog rather
+/// than configure anything, so they aren't merged.
+const CATALOG_KEYS: [&str; 5] = ["name", "description", "kind", "tools", "model"];
+
+/// A key the config file and the definition both set, to different values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// Keys from the top of the file down, such as
+    /// `["mcpServers", "github", "command"]`. Kept apart rather than
+    /// dotted, since keys like server names may contain dots themselves.
+    pub path: Vec<String>,
+    pub existing: Value,
+    pub incoming: Value,
+}
+
+impl MergeConflict {
+    /// The path joined with dots, for showing.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// One key a merge would set, for previewing an install. Objects are
+/// walked down to the values inside them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyChange {
+    /// The file doesn't set this key yet.
+    Added { path: Vec<String>, value: Value },
+    /// The file sets this key to something else; a [`MergeConflict`].
+    Changed {
+        path: Vec<String>,
+        existing: Value,
+        incoming: Value,
+    },
+}
+
+impl std::fmt::Display for KeyChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added { path, value } => write!(f, "+ {} = {value}", path.join(".")),
+            Self::Changed {
+                path,
+                existing,
+                incoming,
+            } => write!(f, "~ {} = {incoming} (was {existing})", path.join(".")),
+        }
+    }
+}
+
+/// A merge worked out but not yet written.
+#[derive(Debug, Clone)]
+pub struct MergePlan {
+    /// The config file to write.
+    pub path: PathBuf,
+    /// Every key the merge would add or change, sorted by key. Keys already
+    /// set to the same value are left out.
+    pub changes: Vec<KeyChange>,
+    pub conflicts: Vec<MergeConflict>,
+    existing: Option<Map<String, Value>>,
+    incoming: Map<String, Value>,
+}
+
+/// Where a merge was written, and where the file it replaced was kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeOutcome {
+    pub path: PathBuf,
+    /// `None` when there was no file before.
+    pub backup: Option<PathBuf>,
+}
+
+/// Work out how `content` (a JSON object) merges into the config file at
+/// `path`, which need not exist yet.
+pub fn plan(path: &Path, content: &str) -> Result<MergePlan, InstallError> {
+    let mut incoming = parse_object(content.strip_prefix('\u{feff}').unwrap_or(content))
+        .map_err(|e| InstallError::InvalidJson(format!("definition: {e}")))?;
+    for key in CATALOG_KEYS {
+        incoming.shift_remove(key);
+    }
+
+    let existing = match std::fs::read_to_string(path) {
+        Ok(text) => Some(
+            parse_object(&text)
+                .map_err(|e| InstallError::InvalidJson(format!("{}: {e}", path.display())))?,
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut changes = Vec::new();
+    diff(
+        existing.as_ref().unwrap_or(&Map::new()),
+        &incoming,
+        &[],
+        &mut changes,
+    );
+    changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+    let conflicts = changes
+        .iter()
+        .filter_map(|change| match change {
+            KeyChange::Changed {
+                path,
+                existing,
+                incoming,
+            } => Some(MergeConflict {
+                path: path.clone(),
+                existing: existing.clone(),
+                incoming: incoming.clone(),
+            }),
+            KeyChange::Added { .. } => None,
+        })
+        .collect();
+    Ok(MergePlan {
+        path: path.to_path_buf(),
+        changes,
+        conflicts,
+        existing,
+        incoming,
+    })
+}
+
+impl MergePlan {
+    /// Write the merged config. Conflicting keys for which `keep_existing`
+    /// returns true keep the file's value; the rest take the definition's.
+    pub fn apply(
+        &self,
+        keep_existing: impl Fn(&MergeConflict) -> bool,
+    ) -> Result<MergeOutcome, InstallError> {
+        let kept: Vec<&[String]> = self
+            .conflicts
+            .iter()
+            .filter(|c| keep_existing(c))
+            .map(|c| c.path.as_slice())
+            .collect();
+        let mut merged = self.existing.clone().unwrap_or_default();
+        merge_into(&mut merged, &self.incoming, &mut Vec::new(), &kept);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let backup = match self.existing {
+            Some(_) => Some(write_backup(&self.path)?),
+            None => None,
+        };
+        let mut text = serde_json::to_string_pretty(&Value::Object(merged))
+            .map_err(|e| InstallError::InvalidJson(e.to_string()))?;
+        text.push('\n');
+        std::fs::write(&self.path, text)?;
+        Ok(MergeOutcome {
+            path: self.path.clone(),
+            backup,
+        })
+    }
+}
+
+/// Merge `content` into the config file at `path`, refusing if any key
+/// conflicts. Use [`plan`] to settle conflicts first.
+pub fn merge_file(path: &Path, content: &str) -> Result<MergeOutcome, InstallError> {
+    let plan = plan(path, content)?;
+    if !plan.conflicts.is_empty() {
+        return Err(InstallError::Conflict {
+            path: plan.path,
+            keys: plan.conflicts.iter().map(MergeConflict::key).collect(),
+        });
+    }
+    plan.apply(|_| false)
+}
+
+fn parse_object(text: &str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(text) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err("expected a JSON object".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn change_path(change: &KeyChange) -> &[String] {
+    match change {
+        KeyChange::Added { path, .. } | KeyChange::Changed { path, .. } => path,
+    }
+}
+
+fn diff(
+    existing: &Map<String, Value>,
+    incoming: &Map<String, Value>,
+    prefix: &[String],
+    changes: &mut Vec<KeyChange>,
+) {
+    for (key, new) in incoming {
+        let path = [prefix, std::slice::from_ref(key)].concat();
+        match (existing.get(key), new) {
+            (None, Value::Object(new)) if !new.is_empty() => {
+                diff(&Map::new(), new, &path, changes);
+            }
+            (Some(Value::Object(old)), Value::Object(new)) => diff(old, new, &path, changes),
+            (None, _) => changes.push(KeyChange::Added {
+                path,
+                value: new.clone(),
+            }),
+            (Some(old), _) if old != new => changes.push(KeyChange::Changed {
+                path,
+                existing: old.clone(),
+                incoming: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn merge_into(
+    target: &mut Map<String, Value>,
+    incoming: &Map<String, Value>,
+    path: &mut Vec<String>,
+    kept: &[&[String]],
+) {
+    for (key, new) in incoming {
+        path.push(key.clone());
+        match (target.get_mut(key), new) {
+            (Some(Value::Object(old)), Value::Object(new)) => merge_into(old, new, path, kept),
+            (Some(_), _) if kept.contains(&path.as_slice()) => {}
+            _ => {
+                target.insert(key.clone(), new.clone());
+            }
+        }
+        path.pop();
+    }
+}
+
+/// Copy `path` to `settings.json.<epoch-secs>.bak` beside it, or, when an
+/// earlier merge this second already took that name,
+/// `settings.json.<epoch-secs>.<n>.bak`. Existing backups are never
+/// overwritten.
+fn write_backup(path: &Path) -> std::io::Result<PathBuf> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut original = std::fs::File::open(path)?;
+    for n in 0u32.. {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        match n {
+            0 => name.push(format!(".{stamp}.bak")),
+            n => name.push(format!(".{stamp}.{n}.bak")),
+        }
+        let backup = path.with_file_name(name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(mut file) => {
+                std::io::copy(&mut original, &mut file)?;
+                return Ok(backup);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("ran out of backup names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("agent-defs-test-merge-{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const GITHUB: &str = r#"{
+        "name": "github",
+        "description": "GitHub MCP server",
+        "mcpServers": {"github": {"command": "npx", "args": ["github-mcp"]}}
+    }"#;
+
+    #[test]
+    fn new_file_gets_the_definition_without_catalog_keys() {
+        let dir = temp_dir("new");
+        let path = dir.join(".mcp.json");
+        let outcome = merge_file(&path, GITHUB).unwrap();
+        assert_eq!(outcome.backup, None);
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"mcpServers": {"github": {"command": "npx", "args": ["github-mcp"]}}})
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merges_alongside_existing_keys_and_backs_up() {
+        let dir = temp_dir("existing");
+        let path = dir.join(".mcp.json");
+        let before = r#"{"mcpServers": {"local": {"command": "serve"}}, "other": 1}"#;
+        std::fs::write(&path, before).unwrap();
+
+        let outcome = merge_file(&path, GITHUB).unwrap();
+        let backup = outcome.backup.unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), before);
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["other"], 1);
+        assert_eq!(written["mcpServers"]["local"]["command"], "serve");
+        assert_eq!(written["mcpServers"]["github"]["command"], "npx");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn back_to_back_merges_keep_every_backup() {
+        let dir = temp_dir("twice");
+        let path = dir.join(".mcp.json");
+        let before = r#"{"other": 1}"#;
+        std::fs::write(&path, before).unwrap();
+
+        let first = merge_file(&path, GITHUB).unwrap().backup.unwrap();
+        let merged = std::fs::read_to_string(&path).unwrap();
+        let second = merge_file(&path, GITHUB).unwrap().backup.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), before);
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), merged);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn conflicts_are_reported_and_settled_per_key() {
+        let dir = temp_dir("conflict");
+        let path = dir.join("settings.json");
+        std::fs::write(
+            &path,
+            r#"{"mcpServers": {"github": {"command": "docker"}}}"#,
+        )
+        .unwrap();
+
+        let err = merge_file(&path, GITHUB).unwrap_err();
+        assert!(
+            matches!(&err, InstallError::Conflict { keys, .. } if keys == &["mcpServers.github.command"])
+        );
+
+        let plan = plan(&path, GITHUB).unwrap();
+        assert_eq!(plan.conflicts[0].existing, "docker");
+        plan.apply(|c| c.path == ["mcpServers", "github", "command"])
+            .unwrap();
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["mcpServers"]["github"]["command"], "docker");
+        assert_eq!(written["mcpServers"]["github"]["args"][0], "github-mcp");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keys_with_dots_are_told_apart_from_nesting() {
+        let dir = temp_dir("dotted");
+        let path = dir.join(".mcp.json");
+        std::fs::write(&path, r#"{"a.b": 1, "a": {"b": 2}}"#).unwrap();
+
+        let plan = plan(&path, r#"{"a.b": 3, "a": {"b": 4}}"#).unwrap();
+        let paths: Vec<&[String]> = plan.conflicts.iter().map(|c| c.path.as_slice()).collect();
+        assert_eq!(paths, [&["a", "b"][..], &["a.b"][..]]);
+        plan.apply(|c| c.path == ["a.b"]).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&written).unwrap(),
+            serde_json::json!({"a.b": 1, "a": {"b": 4}})
+        );
+        assert!(written.find("\"a.b\"") < written.find("\"a\":"), "{written}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn changes_list_added_and_changed_keys() {
+        let dir = temp_dir("changes");
+        let path = dir.join("settings.json");
+        std::fs::write(
+            &path,
+            r#"{"cleanupPeriodDays": 30, "permissions": {"allow": ["Bash"]}}"#,
+        )
+        .unwrap();
+        let settings = r#"{
+            "name": "strict",
+            "permissions": {"allow": ["Bash"], "deny": ["WebFetch"]},
+            "env": {"CI": "1"},
+            "cleanupPeriodDays": 7
+        }"#;
+
+        let plan = plan(&path, settings).unwrap();
+        let lines: Vec<String> = plan.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "~ cleanupPeriodDays = 7 (was 30)",
+                "+ env.CI = \"1\"",
+                "+ permissions.deny = [\"WebFetch\"]",
+            ]
+        );
+        assert_eq!(plan.conflicts.len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn non_object_json_is_rejected() {
+        let dir = temp_dir("invalid");
+        let path = dir.join(".mcp.json");
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(matches!(
+            plan(&path, GITHUB),
+            Err(InstallError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            plan(&dir.join("none.json"), "nope"),
+            Err(InstallError::InvalidJson(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,26 @@
+//! Personal notes attached to definitions. Like usage counts they are kept
+//! only in the local cache, and a sync never touches them.
+
+use crate::{DefinitionId, DefinitionSummary};
+
+/// A note the user wrote about one definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionNote {
+    pub id: DefinitionId,
+    pub source_label: String,
+    pub text: String,
+    /// When the note was last saved, in seconds since the Unix epoch.
+    pub updated_at: u64,
+}
+
+impl DefinitionNote {
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        self.id == summary.id && self.source_label == summary.source_label
+    }
+
+    /// Whether the note mentions `query`, ignoring case. `query` is expected
+    /// to be lowercase already, as search terms are.
+    pub fn mentions(&self, query: &str) -> bool {
+        self.text.to_lowercase().contains(query)
+    }
+}
@@ -1,8 +1,91 @@
+use std::borrow::Cow;
+
 use crate::DefinitionKind;
 
+/// Rewrite `\\` separators as `/`. Sources always report forward slashes, but
+/// paths typed into config on Windows (a `base_path`, say) often don't.
+pub fn normalize_separators(path: &str) -> Cow<'_, str> {
+    if path.contains('\\') {
+        Cow::Owned(path.replace('\\', "/"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Split an ID or path from a multi-branch source, written `branch:path`,
+/// into its branch and the path within that branch. Paths without a branch
+/// come back whole.
+pub fn split_branch(id: &str) -> (Option<&str>, &str) {
+    match id.split_once(':') {
+        Some((branch, path)) if !branch.is_empty() && !branch.contains('/') => {
+            (Some(branch), path)
+        }
+        _ => (None, id),
+    }
+}
+
+/// Prefix `path` with `branch` in the form [`split_branch`] reads.
+pub fn with_branch(branch: &str, path: &str) -> String {
+    format!("{branch}:{path}")
+}
+
+/// Find the root `path` sits under and its path relative to that root.
+/// Returns the root's index in `roots`, or `0` with the whole path when
+/// there are no roots. When roots nest, the deepest one that matches wins,
+/// so `tools/agents` takes `tools/agents/a.md` ahead of `tools`.
+pub fn strip_root<'a>(path: &'a str, roots: &[String]) -> Option<(usize, &'a str)> {
+    if roots.is_empty() {
+        return Some((0, path));
+    }
+    roots
+        .iter()
+        .enumerate()
+        .filter_map(|(index, root)| {
+            let root = normalize_separators(root);
+            let root = root.trim_end_matches('/');
+            if root.is_empty() {
+                return Some((0, index, path));
+            }
+            let relative = path.strip_prefix(root)?.strip_prefix('/')?;
+            Some((root.len(), index, relative))
+        })
+        // Deepest root first; on a tie (the same root listed twice) the
+        // earlier entry.
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+        .map(|(_, index, relative)| (index, relative))
+}
+
+/// Join a root from config and a path relative to it.
+pub fn join_root(root: &str, relative: &str) -> String {
+    let root = normalize_separators(root);
+    match root.trim_end_matches('/') {
+        "" => relative.to_owned(),
+        root => format!("{root}/{relative}"),
+    }
+}
+
+/// Given entries tagged with the index of the root they came from, keep
+/// only the entry from the earliest root when several roots hold the same
+/// relative path. Order is otherwise preserved.
+pub fn first_root_wins<T>(entries: Vec<(usize, T)>, relative: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut winners: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for (index, entry) in &entries {
+        winners
+            .entry(relative(entry).to_owned())
+            .and_modify(|winner| *winner = (*winner).min(*index))
+            .or_insert(*index);
+    }
+    entries
+        .into_iter()
+        .filter(|(index, entry)| winners.get(relative(entry)) == Some(index))
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
 /// True if a relative path represents a definition file we care about.
 /// Excludes hidden directories (segments starting with `.`).
 pub fn is_definition_file(relative_path: &str) -> bool {
+    let relative_path = normalize_separators(relative_path);
     if relative_path
         .split('/')
         .any(|segment| segment.starts_with('.'))
@@ -15,17 +98,20 @@ pub fn is_definition_file(relative_path: &str) -> bool {
 
 /// True if this relative path is a skill entry point: `skills/<category>/<name>/SKILL.md`.
 pub fn is_skill_entry_point(relative_path: &str) -> bool {
+    let relative_path = normalize_separators(relative_path);
     relative_path.starts_with("skills/") && relative_path.ends_with("/SKILL.md")
 }
 
 /// True if this relative path is under `skills/` but is NOT a SKILL.md entry point.
 /// These are reference files that should be excluded from listing.
 pub fn is_skill_reference(relative_path: &str) -> bool {
+    let relative_path = normalize_separators(relative_path);
     relative_path.starts_with("skills/") && !relative_path.ends_with("/SKILL.md")
 }
 
 /// True if this ID represents a skill directory (no file extension).
 pub fn is_skill_directory_id(relative_id: &str) -> bool {
+    let relative_id = normalize_separators(relative_id);
     relative_id.starts_with("skills/")
         && !relative_id.ends_with(".md")
         && !relative_id.ends_with(".json")
@@ -35,7 +121,8 @@ pub fn is_skill_directory_id(relative_id: &str) -> bool {
 /// Input: `skills/<category>/<name>/SKILL.md`
 /// Output: (name, Skill, Some(category))
 pub fn parse_skill_path(relative_path: &str) -> (String, DefinitionKind, Option<String>) {
-    let parts: Vec<&str> = relative_path.split('/').collect();
+    let relative_path = normalize_separators(relative_path);
+    let parts = segments(&relative_path);
 
     match parts.as_slice() {
         // skills/<category>/<name>/SKILL.md
@@ -48,8 +135,8 @@ pub fn parse_skill_path(relative_path: &str) -> (String, DefinitionKind, Option<
             // Fallback: strip trailing /SKILL.md, use last segment as name
             let dir = relative_path
                 .strip_suffix("/SKILL.md")
-                .unwrap_or(relative_path);
-            let name = dir.rsplit('/').next().unwrap_or("unknown").to_owned();
+                .unwrap_or(&relative_path);
+            let name = segments(dir).pop().unwrap_or("unknown").to_owned();
             (name, DefinitionKind::Skill, None)
         }
     }
@@ -62,15 +149,19 @@ pub fn parse_skill_path(relative_path: &str) -> (String, DefinitionKind, Option<
 /// - `hooks/<name>.md` -> kind=Hook, category=None
 /// - `commands/<name>.md` -> kind=Command, category=None
 /// - `<name>.md` -> kind=Other("unknown"), category=None
+///
+/// Empty segments, as in `agents//a.md`, are ignored, and a file called just
+/// `.md` is named `unknown`.
 pub fn parse_relative_path(relative_path: &str) -> (String, DefinitionKind, Option<String>) {
-    let parts: Vec<&str> = relative_path.split('/').collect();
+    let relative_path = normalize_separators(relative_path);
+    let parts = segments(&relative_path);
 
-    let file_name = parts.last().unwrap_or(&"unknown");
+    let file_name = parts.last().copied().unwrap_or("unknown");
     let name = file_name
         .strip_suffix(".md")
         .or_else(|| file_name.strip_suffix(".json"))
-        .unwrap_or(file_name)
-        .to_owned();
+        .unwrap_or(file_name);
+    let name = if name.is_empty() { "unknown" } else { name }.to_owned();
 
     match parts.as_slice() {
         // e.g., agents/development-team/code-architect.md
@@ -94,10 +185,60 @@ pub fn parse_relative_path(relative_path: &str) -> (String, DefinitionKind, Opti
     }
 }
 
+/// The non-empty `/`-separated segments of `path`.
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // -- split_branch --
+
+    #[test]
+    fn branch_prefix_splits_off() {
+        assert_eq!(split_branch("next:agents/a.md"), (Some("next"), "agents/a.md"));
+        assert_eq!(split_branch(&with_branch("main", "hooks/h.md")), (Some("main"), "hooks/h.md"));
+    }
+
+    #[test]
+    fn plain_paths_have_no_branch() {
+        assert_eq!(split_branch("agents/a.md"), (None, "agents/a.md"));
+        assert_eq!(split_branch("agents/a:b.md"), (None, "agents/a:b.md"));
+        assert_eq!(split_branch(":agents/a.md"), (None, ":agents/a.md"));
+    }
+
+    // -- roots --
+
+    fn roots(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| (*p).to_owned()).collect()
+    }
+
+    #[test]
+    fn strip_root_picks_the_deepest_match() {
+        let roots = roots(&["claude/", "tools", "tools\\agents"]);
+        assert_eq!(strip_root("claude/agents/a.md", &roots), Some((0, "agents/a.md")));
+        assert_eq!(strip_root("tools/agents/x/a.md", &roots), Some((2, "x/a.md")));
+        assert_eq!(strip_root("tools/hooks/h.md", &roots), Some((1, "hooks/h.md")));
+        assert_eq!(strip_root("toolsmith/a.md", &roots), None);
+        assert_eq!(strip_root("README.md", &roots), None);
+        assert_eq!(strip_root("README.md", &[]), Some((0, "README.md")));
+    }
+
+    #[test]
+    fn join_root_handles_trailing_slashes() {
+        assert_eq!(join_root("claude/", "agents/a.md"), "claude/agents/a.md");
+        assert_eq!(join_root("tools\\agents", "a.md"), "tools/agents/a.md");
+        assert_eq!(join_root("", "a.md"), "a.md");
+    }
+
+    #[test]
+    fn earliest_root_wins_a_collision() {
+        let entries = vec![(1, "agents/a.md"), (0, "agents/a.md"), (1, "agents/b.md")];
+        assert_eq!(first_root_wins(entries, |e| *e), ["agents/a.md", "agents/b.md"]);
+    }
+
     // -- is_definition_file --
 
     #[test]
@@ -251,4 +392,38 @@ mod tests {
         assert_eq!(kind, DefinitionKind::Agent);
         assert_eq!(category.as_deref(), Some("team"));
     }
+
+    #[test]
+    fn empty_segments_and_names_fall_back() {
+        assert_eq!(
+            parse_relative_path("/agents//team/reviewer.md"),
+            parse_relative_path("agents/team/reviewer.md")
+        );
+        assert_eq!(parse_relative_path("agents/.md").0, "unknown");
+        assert_eq!(parse_relative_path("").0, "unknown");
+    }
+
+    // -- backslash separators --
+
+    #[test]
+    fn parses_backslash_separated_path() {
+        assert_eq!(
+            parse_relative_path("agents\\development-team\\code-architect.md"),
+            parse_relative_path("agents/development-team/code-architect.md")
+        );
+    }
+
+    #[test]
+    fn backslash_skill_paths_are_recognized() {
+        let path = "skills\\ai-research\\agents-crewai\\SKILL.md";
+        assert!(is_skill_entry_point(path));
+        assert!(!is_skill_reference(path));
+        assert_eq!(parse_skill_path(path).0, "agents-crewai");
+        assert!(!is_skill_reference("skills/ai-research/agents-crewai\\SKILL.md"));
+    }
+
+    #[test]
+    fn backslash_hidden_directory_excluded() {
+        assert!(!is_definition_file("agents\\.hidden\\secret.md"));
+    }
 }
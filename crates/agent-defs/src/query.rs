@@ -0,0 +1,213 @@
+//! Saved queries such as `kind:skill category:code-quality review`, matched
+//! against definition summaries.
+//!
+//! `kind:`, `category:`, `source:` and `branch:` terms must all match; any
+//! other words must each appear in the name or description, in any order.
+//! `"quoted phrases"` are matched whole, and a leading `!` turns a word or
+//! phrase into one that must not appear. Matching ignores case.
+
+use crate::{DefinitionKind, DefinitionSummary};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Kind(DefinitionKind),
+    Category(String),
+    Source(String),
+    Branch(String),
+    Text(String),
+    Without(String),
+}
+
+/// One word of a query, or a quoted phrase.
+struct Word {
+    text: String,
+    negated: bool,
+    quoted: bool,
+}
+
+/// Split `query` on whitespace, keeping `"quoted phrases"` together. A
+/// phrase missing its closing quote runs to the end.
+fn words(query: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut chars = query.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+        let negated = first == '!' && chars.next().is_some();
+        let quoted = chars.next_if_eq(&'"').is_some();
+        let mut text = String::new();
+        if quoted {
+            text.extend(chars.by_ref().take_while(|&c| c != '"'));
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                text.push(c);
+            }
+        }
+        if !text.trim().is_empty() {
+            words.push(Word {
+                text: text.trim().to_lowercase(),
+                negated,
+                quoted,
+            });
+        }
+    }
+    words
+}
+
+/// Free text to look for: words that must each appear, in any order, and
+/// words that must not, such as `code review !deprecated` or
+/// `"code review"`. Terms are lowercased.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TextQuery {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TextQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut text = Self::default();
+        for word in words(query) {
+            if word.negated {
+                text.exclude.push(word.text);
+            } else {
+                text.include.push(word.text);
+            }
+        }
+        text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether `contains` finds every included term and none of the
+    /// excluded ones.
+    pub fn matches(&self, contains: impl Fn(&str) -> bool) -> bool {
+        self.include.iter().all(|term| contains(term))
+            && !self.exclude.iter().any(|term| contains(term))
+    }
+}
+
+/// A parsed query. The empty query matches everything.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let terms = words(query)
+            .into_iter()
+            .map(|word| match word.text.split_once(':') {
+                _ if word.negated => Term::Without(word.text),
+                _ if word.quoted => Term::Text(word.text),
+                Some(("kind", kind)) => Term::Kind(DefinitionKind::parse(kind)),
+                Some(("category", category)) => Term::Category(category.to_owned()),
+                Some(("source", source)) => Term::Source(source.to_owned()),
+                Some(("branch", branch)) => Term::Branch(branch.to_owned()),
+                _ => Term::Text(word.text),
+            })
+            .collect();
+        Self { terms }
+    }
+
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Kind(kind) => summary.kind == *kind,
+            Term::Category(category) => summary
+                .category
+                .as_ref()
+                .is_some_and(|c| c.to_lowercase() == *category),
+            Term::Source(source) => summary.source_label.to_lowercase() == *source,
+            Term::Branch(branch) => crate::path::split_branch(summary.id.as_str())
+                .0
+                .is_some_and(|b| b.to_lowercase() == *branch),
+            Term::Text(text) => mentions(summary, text),
+            Term::Without(text) => !mentions(summary, text),
+        })
+    }
+}
+
+/// Whether `summary`'s name or description contains `text`, which is
+/// lowercase.
+fn mentions(summary: &DefinitionSummary, text: &str) -> bool {
+    summary.name.to_lowercase().contains(text)
+        || summary
+            .description
+            .as_ref()
+            .is_some_and(|d| d.to_lowercase().contains(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefinitionId;
+
+    fn summary(name: &str, kind: DefinitionKind, category: Option<&str>) -> DefinitionSummary {
+        DefinitionSummary {
+            id: DefinitionId::new(name),
+            name: name.to_owned(),
+            description: Some("Finds lint problems".into()),
+            kind,
+            category: category.map(str::to_owned),
+            source_label: "acme".into(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    #[test]
+    fn field_terms_must_all_match() {
+        let query = Query::parse("kind:skill category:Code-Quality");
+        let category = Some("code-quality");
+        assert!(query.matches(&summary("linter", DefinitionKind::Skill, category)));
+        assert!(!query.matches(&summary("linter", DefinitionKind::Agent, category)));
+        assert!(!query.matches(&summary("linter", DefinitionKind::Skill, None)));
+    }
+
+    #[test]
+    fn text_terms_search_name_and_description() {
+        let linter = summary("linter", DefinitionKind::Skill, None);
+        assert!(Query::parse("LINT").matches(&linter));
+        assert!(Query::parse("problems source:acme").matches(&linter));
+        assert!(!Query::parse("lint source:other").matches(&linter));
+        assert!(!Query::parse("deploy").matches(&linter));
+    }
+
+    #[test]
+    fn branch_term_matches_the_id_prefix() {
+        let mut next = summary("linter", DefinitionKind::Skill, None);
+        next.id = DefinitionId::new("next:skills/lint/linter");
+        assert!(Query::parse("branch:NEXT lint").matches(&next));
+        assert!(!Query::parse("branch:main").matches(&next));
+        assert!(!Query::parse("branch:next").matches(&summary("x", DefinitionKind::Agent, None)));
+    }
+
+    #[test]
+    fn words_match_independently_and_phrases_whole() {
+        let mut reviewer = summary("code-reviewer", DefinitionKind::Agent, None);
+        reviewer.description = Some("Reviews code for style".into());
+        assert!(Query::parse("review code").matches(&reviewer));
+        assert!(Query::parse("\"reviews code\"").matches(&reviewer));
+        assert!(!Query::parse("\"code reviews\"").matches(&reviewer));
+        assert!(!Query::parse("review !style").matches(&reviewer));
+        assert!(Query::parse("review !\"bad style\"").matches(&reviewer));
+    }
+
+    #[test]
+    fn text_query_splits_included_and_excluded_terms() {
+        let text = TextQuery::parse("  Code \"Pull Request\" !deprecated !\"old api ! \"open");
+        assert_eq!(text.include, ["code", "pull request", "open"]);
+        assert_eq!(text.exclude, ["deprecated", "old api !"]);
+        assert!(TextQuery::parse(" ! \"\" ").is_empty());
+        assert!(text.matches(|term| ["code", "pull request", "open"].contains(&term)));
+        assert!(!text.matches(|term| term != "nothing"));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(Query::parse("  ").matches(&summary("x", DefinitionKind::Hook, None)));
+    }
+}
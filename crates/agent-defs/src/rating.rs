@@ -0,0 +1,153 @@
+//! Ratings from a community index: a JSON file per source, published
+//! alongside it, that scores its definitions and marks the ones someone has
+//! checked. Syncing fetches the file and keeps the ratings in the local
+//! cache, next to the definitions they describe.
+//!
+//! The file maps definition IDs to their ratings:
+//!
+//! ```json
+//! {
+//!   "definitions": {
+//!     "agents/reviewer.md": { "rating": 4.6, "ratings": 212, "verified": true }
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::{DefinitionId, DefinitionSummary};
+
+/// The highest score a definition can have.
+pub const MAX_SCORE: f32 = 5.0;
+
+/// How the community rates one definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub id: DefinitionId,
+    pub source_label: String,
+    /// Average score, from 0 to [`MAX_SCORE`], or `None` while unrated.
+    pub score: Option<f32>,
+    /// How many ratings the score averages.
+    pub votes: u32,
+    /// Whether the index maintainers have reviewed the definition.
+    pub verified: bool,
+}
+
+impl Rating {
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        self.id == summary.id && self.source_label == summary.source_label
+    }
+
+    /// A short label for lists, such as `★4.6 ✓`.
+    pub fn badge(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(score) = self.score {
+            parts.push(format!("\u{2605}{score:.1}"));
+        }
+        if self.verified {
+            parts.push("\u{2713}".to_owned());
+        }
+        parts.join(" ")
+    }
+
+    /// A sentence for detail views, such as `★4.6 from 212 ratings, verified`.
+    pub fn describe(&self) -> String {
+        let Some(score) = self.score else {
+            return "verified, not yet rated".to_owned();
+        };
+        let plural = if self.votes == 1 { "" } else { "s" };
+        let mut text = format!("\u{2605}{score:.1} from {} rating{plural}", self.votes);
+        if self.verified {
+            text.push_str(", verified");
+        }
+        text
+    }
+}
+
+#[derive(Deserialize)]
+struct IndexFile {
+    #[serde(default)]
+    definitions: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    rating: Option<f32>,
+    #[serde(default)]
+    ratings: u32,
+    #[serde(default)]
+    verified: bool,
+}
+
+/// Read a community index file for the source labelled `source_label`.
+///
+/// Entries without a score count as unrated unless they are verified, and
+/// scores outside 0–5 are clamped, so one bad entry doesn't cost the rest.
+pub fn parse_index(source_label: &str, json: &str) -> Result<Vec<Rating>, serde_json::Error> {
+    let file: IndexFile = serde_json::from_str(json)?;
+    Ok(file
+        .definitions
+        .into_iter()
+        .filter_map(|(id, entry)| {
+            let score = entry
+                .rating
+                .filter(|score| score.is_finite())
+                .map(|score| score.clamp(0.0, MAX_SCORE));
+            if score.is_none() && !entry.verified {
+                return None;
+            }
+            Some(Rating {
+                id: DefinitionId::new(id),
+                source_label: source_label.to_owned(),
+                score,
+                votes: if score.is_some() { entry.ratings } else { 0 },
+                verified: entry.verified,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_entries_become_ratings() {
+        let ratings = parse_index(
+            "acme",
+            r#"{"definitions": {
+                "agents/reviewer.md": {"rating": 4.62, "ratings": 212, "verified": true},
+                "agents/noisy.md": {"rating": 9, "ratings": 1},
+                "agents/checked.md": {"verified": true},
+                "agents/unknown.md": {}
+            }}"#,
+        )
+        .unwrap();
+
+        let badges: Vec<(&str, String)> =
+            ratings.iter().map(|r| (r.id.as_str(), r.badge())).collect();
+        assert_eq!(
+            badges,
+            [
+                ("agents/checked.md", "\u{2713}".to_owned()),
+                ("agents/noisy.md", "\u{2605}5.0".to_owned()),
+                ("agents/reviewer.md", "\u{2605}4.6 \u{2713}".to_owned()),
+            ]
+        );
+        assert!(ratings.iter().all(|r| r.source_label == "acme"));
+        assert_eq!(ratings[0].describe(), "verified, not yet rated");
+        assert_eq!(ratings[1].describe(), "\u{2605}5.0 from 1 rating");
+        assert_eq!(
+            ratings[2].describe(),
+            "\u{2605}4.6 from 212 ratings, verified"
+        );
+    }
+
+    #[test]
+    fn an_index_must_be_json() {
+        assert!(parse_index("acme", "<html>").is_err());
+        assert!(parse_index("acme", "{}").unwrap().is_empty());
+    }
+}
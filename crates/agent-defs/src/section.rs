@@ -0,0 +1,114 @@
+//! A definition body's sections, by markdown heading, so a detail view can
+//! offer a table of contents and a search can say which part of a body it
+//! matched.
+
+/// A markdown heading and where it sits in the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// 1 for `#`, up to 6 for `######`.
+    pub level: u8,
+    pub title: String,
+    /// The heading's line in the body, counting from 0.
+    pub line: usize,
+}
+
+/// The ATX headings of `body` (`# Title`, `## Title`, ...) in order.
+/// Lines inside fenced code blocks aren't headings, so a shell comment in
+/// an example doesn't start a section.
+pub fn sections(body: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (line, text) in body.lines().enumerate() {
+        let trimmed = text.trim_start();
+        if text.len() - trimmed.len() > 3 {
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = match fence {
+                Some(open) if open == marker => None,
+                Some(open) => Some(open),
+                None => Some(marker),
+            };
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+        if let Some((level, title)) = heading(trimmed) {
+            sections.push(Section {
+                level,
+                title,
+                line,
+            });
+        }
+    }
+    sections
+}
+
+/// The level and title of a heading line.
+fn heading(line: &str) -> Option<(u8, String)> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // A closing run of `#`s is decoration, as in `## Usage ##`.
+    let rest = rest.trim();
+    let title = match rest.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim_end(),
+        _ => rest,
+    };
+    (!title.is_empty()).then(|| (level as u8, title.to_owned()))
+}
+
+/// The section `line` of the body falls in: the last heading at or before
+/// it. Lines before the first heading are in none.
+pub fn section_at(sections: &[Section], line: usize) -> Option<&Section> {
+    sections.iter().take_while(|section| section.line <= line).last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(body: &str) -> Vec<(u8, String, usize)> {
+        sections(body)
+            .into_iter()
+            .map(|s| (s.level, s.title, s.line))
+            .collect()
+    }
+
+    #[test]
+    fn headings_start_sections() {
+        let body = "Intro.\n\n# Reviewer\n\n## Checklist ##\nLook.\n### C# tips\n#hashtag\n####### Too deep\n";
+        assert_eq!(
+            titles(body),
+            [
+                (1, "Reviewer".into(), 2),
+                (2, "Checklist".into(), 4),
+                (3, "C# tips".into(), 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_blocks_have_no_headings() {
+        let body = "## Usage\n```sh\n# install it\n~~~\n# still code\n```\n## After\n~~~\n# code\n~~~\n";
+        assert_eq!(
+            titles(body),
+            [(2, "Usage".into(), 0), (2, "After".into(), 6)]
+        );
+    }
+
+    #[test]
+    fn lines_fall_in_the_heading_before_them() {
+        let found = sections("Intro\n# One\ntext\n# Two\ntext\n");
+        assert_eq!(section_at(&found, 0), None);
+        assert_eq!(section_at(&found, 1).unwrap().title, "One");
+        assert_eq!(section_at(&found, 2).unwrap().title, "One");
+        assert_eq!(section_at(&found, 4).unwrap().title, "Two");
+    }
+}
@@ -1,27 +1,234 @@
+use std::fmt;
+#[cfg(feature = "source")]
 use std::sync::Arc;
 
-use crate::definition::{Definition, DefinitionId, DefinitionSummary};
+#[cfg(feature = "source")]
+use crate::definition::{Definition, DefinitionSummary};
+use crate::definition::DefinitionId;
 
 /// Errors that can occur when interacting with a definition source.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum SourceError {
     #[error("definition not found: {0}")]
     NotFound(DefinitionId),
 
+    /// More than one source has the ID; qualifying it picks one.
+    #[error(
+        "{id} is in {} sources ({}); qualify it, as in `{}:{id}`",
+        .sources.len(),
+        .sources.join(", "),
+        .sources[0]
+    )]
+    Ambiguous {
+        id: DefinitionId,
+        sources: Vec<String>,
+    },
+
     #[error("network error: {0}")]
     Network(String),
 
-    #[error("parse error: {0}")]
-    Parse(String),
+    /// The remote end didn't answer in time; trying again may work.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// The remote end refused the credentials, or needs some.
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The remote end is limiting requests. `reset_at` is when it stops,
+    /// in seconds since the Unix epoch, if it said.
+    #[error("rate limited: {message}")]
+    RateLimited {
+        message: String,
+        reset_at: Option<u64>,
+    },
+
+    #[error("parse error{}: {message}", at(location))]
+    Parse {
+        message: String,
+        location: Option<Location>,
+    },
 
     #[error("{0}")]
     Other(String),
 }
 
+impl SourceError {
+    /// A parse error with no known location.
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::Parse {
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::NotFound(_) => ErrorKind::NotFound,
+            Self::Ambiguous { .. } | Self::Other(_) => ErrorKind::Other,
+            Self::Network(_) => ErrorKind::Network,
+            Self::Timeout(_) => ErrorKind::Timeout,
+            Self::Unauthorized(_) => ErrorKind::Unauthorized,
+            Self::RateLimited { .. } => ErrorKind::RateLimited,
+            Self::Parse { .. } => ErrorKind::Parse,
+        }
+    }
+
+    /// When a rate limit stops, in seconds since the Unix epoch.
+    pub fn reset_at(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited { reset_at, .. } => *reset_at,
+            _ => None,
+        }
+    }
+}
+
+/// A line and column in a file, counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// ` at <location>`, or nothing when there isn't one.
+pub(crate) fn at(location: &Option<Location>) -> String {
+    location.map(|l| format!(" at {l}")).unwrap_or_default()
+}
+
+/// `message` without the ` at line N column M`s serde puts in it, for
+/// errors that carry their [`Location`] separately. JSON errors end with
+/// one; YAML ones can have two, as in `did not find expected ',' at line 4
+/// column 1, while parsing a flow sequence at line 3 column 8`.
+pub(crate) fn strip_location(message: &str) -> String {
+    let digits = |s: &str| s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let mut stripped = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find(" at line ") {
+        let after = &rest[start + " at line ".len()..];
+        let line_end = after.len() - digits(after);
+        let column = after[line_end..].strip_prefix(" column ");
+        match column {
+            Some(column) if line_end > 0 && digits(column) < column.len() => {
+                stripped.push_str(&rest[..start]);
+                rest = &column[column.len() - digits(column)..];
+            }
+            _ => {
+                stripped.push_str(&rest[..start + 1]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// What went wrong, broadly, for callers that handle some failures
+/// differently from the rest, such as asking for a token when a remote
+/// refuses the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    Unauthorized,
+    RateLimited,
+    Timeout,
+    Network,
+    Parse,
+    Other,
+}
+
+impl ErrorKind {
+    /// The kind of the first [`SourceError`] or [`SyncError`] in `error`'s
+    /// chain of causes, or [`ErrorKind::Other`].
+    ///
+    /// [`SyncError`]: crate::SyncError
+    pub fn of(error: &(dyn std::error::Error + 'static)) -> Self {
+        classify(error).map_or(Self::Other, |(kind, _)| kind)
+    }
+
+    /// A stable name for the kind, for output other programs read.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::Unauthorized => "unauthorized",
+            Self::RateLimited => "rate_limited",
+            Self::Timeout => "timeout",
+            Self::Network => "network",
+            Self::Parse => "parse",
+            Self::Other => "other",
+        }
+    }
+
+    /// A short name for the kind, to tag a message with.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::NotFound => "not found",
+            Self::Unauthorized => "unauthorized",
+            Self::RateLimited => "rate limited",
+            Self::Timeout => "timed out",
+            Self::Network => "network",
+            Self::Parse => "parse error",
+            Self::Other => "error",
+        }
+    }
+}
+
+/// The kind and rate limit reset of the first [`SourceError`] or
+/// `SyncError` in `error`'s chain of causes.
+fn classify(error: &(dyn std::error::Error + 'static)) -> Option<(ErrorKind, Option<u64>)> {
+    let mut next = Some(error);
+    while let Some(error) = next {
+        if let Some(e) = error.downcast_ref::<SourceError>() {
+            return Some((e.kind(), e.reset_at()));
+        }
+        #[cfg(feature = "source")]
+        if let Some(e) = error.downcast_ref::<crate::SyncError>() {
+            return Some((e.kind(), e.reset_at()));
+        }
+        next = error.source();
+    }
+    None
+}
+
+/// What to do about `error` when a remote refused it for a reason the user
+/// can fix: a missing or rejected token, or a rate limit to wait out. `now`
+/// is seconds since the Unix epoch, for saying when the limit resets.
+pub fn guidance(error: &(dyn std::error::Error + 'static), now: u64) -> Option<String> {
+    let (kind, reset_at) = classify(error)?;
+    match kind {
+        ErrorKind::Unauthorized => Some("set GITHUB_TOKEN to a token with access".to_owned()),
+        ErrorKind::RateLimited => Some(match reset_at {
+            Some(reset_at) if reset_at > now => format!(
+                "rate limit resets in {}; GITHUB_TOKEN raises it",
+                wait(reset_at - now)
+            ),
+            Some(_) => "rate limit has reset; try again".to_owned(),
+            None => "wait out the rate limit, or set GITHUB_TOKEN".to_owned(),
+        }),
+        _ => None,
+    }
+}
+
+/// `45` -> `1m`, `840` -> `14m`, `3900` -> `1h 5m`, rounding up.
+fn wait(secs: u64) -> String {
+    let minutes = secs.div_ceil(60);
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
 /// A source of agent definitions.
 ///
 /// Sources know how to list, search, and fetch definitions from
 /// a particular backing store (e.g., a GitHub repository).
+#[cfg(feature = "source")]
 #[async_trait::async_trait]
 pub trait Source: Send + Sync {
     /// Human-readable label identifying this source.
@@ -30,28 +237,40 @@ pub trait Source: Send + Sync {
     /// List all available definition summaries.
     async fn list(&self) -> Result<Vec<DefinitionSummary>, SourceError>;
 
-    /// Search definitions by query string.
+    /// Search definitions by query string, read as a [`TextQuery`](crate::TextQuery).
     /// Default implementation filters `list()` results by name and description.
     async fn search(&self, query: &str) -> Result<Vec<DefinitionSummary>, SourceError> {
-        let query_lower = query.to_lowercase();
+        let text = crate::TextQuery::parse(query);
         let all = self.list().await?;
 
         Ok(all
             .into_iter()
             .filter(|summary| {
-                summary.name.to_lowercase().contains(&query_lower)
-                    || summary
-                        .description
-                        .as_ref()
-                        .is_some_and(|d| d.to_lowercase().contains(&query_lower))
+                let name = summary.name.to_lowercase();
+                let description = summary.description.as_deref().unwrap_or("").to_lowercase();
+                text.matches(|term| name.contains(term) || description.contains(term))
             })
             .collect())
     }
 
     /// Fetch the full definition by ID.
+    ///
+    /// Sources may leave `raw` empty to keep detail views cheap; callers
+    /// that need the original file should use [`Source::fetch_raw`].
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError>;
+
+    /// Fetch the original, unparsed file content for a definition.
+    /// Default implementation returns the `raw` field from `fetch()`.
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        self.fetch(id).await.map(|def| def.raw)
+    }
 }
 
+// Shared, boxed, and borrowed sources are sources too, so code taking
+// `impl Source` accepts a store, an `Arc` of one, a `Box<dyn Source>`, or a
+// reference to any of them alike.
+
+#[cfg(feature = "source")]
 #[async_trait::async_trait]
 impl<T: Source + ?Sized> Source for Arc<T> {
     fn label(&self) -> &str {
@@ -69,4 +288,56 @@ impl<T: Source + ?Sized> Source for Arc<T> {
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
         (**self).fetch(id).await
     }
+
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        (**self).fetch_raw(id).await
+    }
+}
+
+#[cfg(feature = "source")]
+#[async_trait::async_trait]
+impl<T: Source + ?Sized> Source for Box<T> {
+    fn label(&self) -> &str {
+        (**self).label()
+    }
+
+    async fn list(&self) -> Result<Vec<DefinitionSummary>, SourceError> {
+        (**self).list().await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<DefinitionSummary>, SourceError> {
+        (**self).search(query).await
+    }
+
+    async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
+        (**self).fetch(id).await
+    }
+
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        (**self).fetch_raw(id).await
+    }
+}
+
+#[cfg(feature = "source")]
+#[async_trait::async_trait]
+impl<T: Source + ?Sized> Source for &T {
+    fn label(&self) -> &str {
+        (**self).label()
+    }
+
+    async fn list(&self) -> Result<Vec<DefinitionSummary>, SourceError> {
+        (**self).list().await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<DefinitionSummary>, SourceError> {
+        (**self).search(query).await
+    }
+
+    async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
+        (**self).fetch(id).await
+    }
+
+    async fn fetch_raw(&self, id: &DefinitionId) -> Result<String, SourceError> {
+        (**self).fetch_raw(id).await
+    }
 }
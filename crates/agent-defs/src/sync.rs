@@ -1,3 +1,6 @@
+use crate::glob::PathFilter;
+use crate::source::ErrorKind;
+
 /// A raw file extracted from a sync source (e.g., a tarball).
 /// Paths are already relative to the definition root (base_path stripped).
 #[derive(Debug, Clone)]
@@ -7,11 +10,15 @@ pub struct RawDefinitionFile {
 }
 
 /// Errors that can occur during sync operations.
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum SyncError {
     #[error("network error: {0}")]
     Network(String),
 
+    /// The remote end didn't answer in time; trying again may work.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
     #[error("extraction error: {0}")]
     Extraction(String),
 
@@ -21,10 +28,48 @@ pub enum SyncError {
     #[error("storage error: {0}")]
     Storage(String),
 
+    /// The repository, branch, or snapshot isn't there.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// The remote end refused the credentials, or needs some.
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The remote end is limiting requests. `reset_at` is when it stops,
+    /// in seconds since the Unix epoch, if it said.
+    #[error("rate limited: {message}")]
+    RateLimited {
+        message: String,
+        reset_at: Option<u64>,
+    },
+
     #[error("{0}")]
     Other(String),
 }
 
+impl SyncError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Network(_) => ErrorKind::Network,
+            Self::Timeout(_) => ErrorKind::Timeout,
+            Self::Extraction(_) => ErrorKind::Parse,
+            Self::NotFound(_) => ErrorKind::NotFound,
+            Self::Unauthorized(_) => ErrorKind::Unauthorized,
+            Self::RateLimited { .. } => ErrorKind::RateLimited,
+            Self::Io(_) | Self::Storage(_) | Self::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    /// When a rate limit stops, in seconds since the Unix epoch.
+    pub fn reset_at(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited { reset_at, .. } => *reset_at,
+            _ => None,
+        }
+    }
+}
+
 /// Provides raw definition files from a remote source for bulk sync.
 #[async_trait::async_trait]
 pub trait SyncProvider: Send + Sync {
@@ -34,4 +79,163 @@ pub trait SyncProvider: Send + Sync {
     /// Fetch all definition files from the source.
     /// Returns files with paths relative to the definition root.
     async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError>;
+
+    /// The revision the latest [`fetch_all`](Self::fetch_all) got, such as
+    /// a commit SHA, for sources that have one.
+    fn revision(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A check every synced file must pass to be cached, such as a signature
+/// check. Files that fail are skipped with the reason the check gives.
+pub trait FileCheck: Send + Sync {
+    /// Why `file` should be kept out of the cache, or `None` to keep it.
+    /// `companion` finds another file fetched by the same sync by its
+    /// path, such as a detached signature stored beside `file`.
+    fn reject<'a>(
+        &self,
+        file: &RawDefinitionFile,
+        companion: &dyn Fn(&str) -> Option<&'a str>,
+    ) -> Option<String>;
+}
+
+/// A provider that passes on only the files a [`PathFilter`] allows, for
+/// sources configured with `include` or `exclude` patterns. Files from a
+/// multi-branch source are matched on their path within the branch.
+pub struct FilteredProvider {
+    inner: Box<dyn SyncProvider>,
+    filter: PathFilter,
+    companion_suffix: Option<&'static str>,
+}
+
+impl FilteredProvider {
+    pub fn new(inner: Box<dyn SyncProvider>, filter: PathFilter) -> Self {
+        Self {
+            inner,
+            filter,
+            companion_suffix: None,
+        }
+    }
+
+    /// Also pass on `<file><suffix>` for every file the filter allows, so
+    /// a [`FileCheck`] still finds companions such as detached signatures.
+    pub fn with_companions(mut self, suffix: &'static str) -> Self {
+        self.companion_suffix = Some(suffix);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for FilteredProvider {
+    fn label(&self) -> &str {
+        self.inner.label()
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        let mut files = self.inner.fetch_all().await?;
+        files.retain(|file| {
+            let (_, path) = crate::path::split_branch(&file.relative_path);
+            let companion_of = self
+                .companion_suffix
+                .and_then(|suffix| path.strip_suffix(suffix));
+            self.filter.allows(path) || companion_of.is_some_and(|of| self.filter.allows(of))
+        });
+        Ok(files)
+    }
+
+    fn revision(&self) -> Option<String> {
+        self.inner.revision()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceError;
+    use crate::test_support::FakeSyncProvider;
+
+    #[tokio::test]
+    async fn filtered_provider_drops_disallowed_paths() {
+        let inner = FakeSyncProvider::new("fixed")
+            .with_file("agents/a.md", "")
+            .with_file("agents/deprecated/old.md", "")
+            .with_file("commands/c.md", "");
+        let filter = PathFilter::new(vec!["agents/**".into()], vec!["**/deprecated/**".into()]);
+        let provider = FilteredProvider::new(Box::new(inner), filter);
+
+        let paths: Vec<_> = provider
+            .fetch_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        assert_eq!(paths, ["agents/a.md"]);
+        assert_eq!(provider.label(), "fixed");
+    }
+
+    #[tokio::test]
+    async fn filtered_provider_passes_on_companions_of_allowed_files() {
+        let inner = FakeSyncProvider::new("fixed")
+            .with_file("agents/a.md", "")
+            .with_file("agents/a.md.minisig", "")
+            .with_file("commands/c.md", "")
+            .with_file("commands/c.md.minisig", "");
+        let filter = PathFilter::new(vec!["agents/**/*.md".into()], Vec::new());
+        let provider = FilteredProvider::new(Box::new(inner), filter).with_companions(".minisig");
+
+        let paths: Vec<_> = provider
+            .fetch_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        assert_eq!(paths, ["agents/a.md", "agents/a.md.minisig"]);
+    }
+
+    #[test]
+    fn error_kind_follows_the_cause_chain() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("syncing failed")]
+        struct Wrapper(#[source] SyncError);
+
+        let limited = SyncError::RateLimited {
+            message: "slow down".into(),
+            reset_at: Some(1_700_000_000),
+        };
+        assert_eq!(ErrorKind::of(&Wrapper(limited)), ErrorKind::RateLimited);
+        assert_eq!(
+            ErrorKind::of(&SourceError::Unauthorized("bad token".into())),
+            ErrorKind::Unauthorized
+        );
+        assert_eq!(SyncError::Extraction("bad tar".into()).kind(), ErrorKind::Parse);
+        assert_eq!(ErrorKind::of(&std::fmt::Error), ErrorKind::Other);
+        assert_eq!(ErrorKind::RateLimited.code(), "rate_limited");
+    }
+
+    #[test]
+    fn guidance_says_when_the_rate_limit_resets() {
+        let now = 1_700_000_000;
+        let limited = |reset_at| SyncError::RateLimited {
+            message: "slow down".into(),
+            reset_at,
+        };
+        assert_eq!(
+            crate::guidance(&limited(Some(now + 840)), now).as_deref(),
+            Some("rate limit resets in 14m; GITHUB_TOKEN raises it")
+        );
+        assert_eq!(
+            crate::guidance(&limited(Some(now + 3_900)), now).as_deref(),
+            Some("rate limit resets in 1h 5m; GITHUB_TOKEN raises it")
+        );
+        assert_eq!(
+            crate::guidance(&limited(Some(now - 5)), now).as_deref(),
+            Some("rate limit has reset; try again")
+        );
+        let refused = SourceError::Unauthorized("Bad credentials".into());
+        assert!(crate::guidance(&refused, now).unwrap().contains("GITHUB_TOKEN"));
+        assert_eq!(crate::guidance(&SyncError::Timeout("slow".into()), now), None);
+    }
 }
@@ -0,0 +1,231 @@
+//! Bookkeeping for the slow operations a browser starts on the user's
+//! behalf, such as syncs and installs. A [`TaskQueue`] runs them one at a
+//! time in the order they were asked for and remembers how each went, so a
+//! task list can show what is running, what is waiting, and what finished.
+//!
+//! The queue never runs anything itself: [`TaskQueue::push`] and
+//! [`TaskQueue::finish`] hand back the job to start next, and the caller
+//! reports back when it is done.
+
+use std::collections::VecDeque;
+
+/// Identifies a task for as long as the queue remembers it.
+pub type TaskId = u64;
+
+/// How many finished tasks are kept for the task list.
+const HISTORY: usize = 50;
+
+/// Where a task is in its life.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskState {
+    /// Waiting for the tasks ahead of it.
+    Pending,
+    Running,
+    /// Finished, with a summary of what it did.
+    Done(String),
+    /// Finished with an error.
+    Failed(String),
+    /// Cancelled before it started.
+    Cancelled,
+}
+
+impl TaskState {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Pending | Self::Running)
+    }
+}
+
+/// One operation in the task list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub id: TaskId,
+    /// What the task does, such as "Install reviewer".
+    pub label: String,
+    pub state: TaskState,
+}
+
+/// Jobs of type `J` waiting to run, one at a time, and the tasks that track
+/// them.
+#[derive(Debug)]
+pub struct TaskQueue<J> {
+    tasks: Vec<Task>,
+    waiting: VecDeque<(TaskId, J)>,
+    next_id: TaskId,
+}
+
+impl<J> Default for TaskQueue<J> {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::new(),
+            waiting: VecDeque::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl<J> TaskQueue<J> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `job` under `label`. When nothing else is running the job is
+    /// handed straight back to start now; otherwise it waits its turn.
+    pub fn push(&mut self, label: impl Into<String>, job: J) -> Option<J> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let idle = self.running().is_none();
+        self.tasks.push(Task {
+            id,
+            label: label.into(),
+            state: if idle {
+                TaskState::Running
+            } else {
+                TaskState::Pending
+            },
+        });
+        if idle {
+            return Some(job);
+        }
+        self.waiting.push_back((id, job));
+        None
+    }
+
+    /// Record how the running task went and start the next one, handing
+    /// back its job. Does nothing if no task is running.
+    pub fn finish(&mut self, result: Result<String, String>) -> Option<J> {
+        let task = self
+            .tasks
+            .iter_mut()
+            .find(|task| task.state == TaskState::Running)?;
+        task.state = match result {
+            Ok(summary) => TaskState::Done(summary),
+            Err(error) => TaskState::Failed(error),
+        };
+        let next = self.start_next();
+        self.trim_history();
+        next
+    }
+
+    /// Cancel a task that hasn't started yet, handing back its job. Returns
+    /// `None` when it has started, finished, or isn't known.
+    pub fn cancel(&mut self, id: TaskId) -> Option<J> {
+        let index = self.waiting.iter().position(|(waiting, _)| *waiting == id)?;
+        let (_, job) = self.waiting.remove(index)?;
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.state = TaskState::Cancelled;
+        }
+        self.trim_history();
+        Some(job)
+    }
+
+    /// Forget every finished task.
+    pub fn clear_finished(&mut self) {
+        self.tasks.retain(|task| !task.state.is_finished());
+    }
+
+    /// Every task remembered, oldest first.
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn running(&self) -> Option<&Task> {
+        self.tasks
+            .iter()
+            .find(|task| task.state == TaskState::Running)
+    }
+
+    /// How many tasks are waiting to start.
+    pub fn pending(&self) -> usize {
+        self.waiting.len()
+    }
+
+    fn start_next(&mut self) -> Option<J> {
+        let (id, job) = self.waiting.pop_front()?;
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.state = TaskState::Running;
+        }
+        Some(job)
+    }
+
+    fn trim_history(&mut self) {
+        let finished = self
+            .tasks
+            .iter()
+            .filter(|task| task.state.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(HISTORY);
+        self.tasks.retain(|task| {
+            if excess > 0 && task.state.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states<'a>(queue: &'a TaskQueue<&str>) -> Vec<(&'a str, TaskState)> {
+        queue
+            .tasks()
+            .iter()
+            .map(|task| (task.label.as_str(), task.state.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn jobs_run_one_at_a_time_in_order() {
+        let mut queue = TaskQueue::new();
+        assert_eq!(queue.push("Sync", "sync"), Some("sync"));
+        assert_eq!(queue.push("Install a", "a"), None);
+        assert_eq!(queue.push("Install b", "b"), None);
+        assert_eq!(queue.pending(), 2);
+
+        assert_eq!(queue.finish(Ok("Synced 3".into())), Some("a"));
+        assert_eq!(queue.finish(Err("disk full".into())), Some("b"));
+        assert_eq!(queue.finish(Ok("Installed".into())), None);
+        assert_eq!(queue.finish(Ok("nothing running".into())), None);
+        assert_eq!(
+            states(&queue),
+            [
+                ("Sync", TaskState::Done("Synced 3".into())),
+                ("Install a", TaskState::Failed("disk full".into())),
+                ("Install b", TaskState::Done("Installed".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_waiting_tasks_can_be_cancelled() {
+        let mut queue = TaskQueue::new();
+        queue.push("Sync", "sync");
+        queue.push("Install a", "a");
+        queue.push("Install b", "b");
+        let ids: Vec<TaskId> = queue.tasks().iter().map(|task| task.id).collect();
+
+        assert_eq!(queue.cancel(ids[0]), None);
+        assert_eq!(queue.cancel(ids[1]), Some("a"));
+        assert_eq!(queue.cancel(ids[1]), None);
+        assert_eq!(queue.finish(Ok(String::new())), Some("b"));
+        assert_eq!(queue.tasks()[1].state, TaskState::Cancelled);
+
+        queue.clear_finished();
+        assert_eq!(states(&queue), [("Install b", TaskState::Running)]);
+    }
+
+    #[test]
+    fn old_finished_tasks_are_forgotten() {
+        let mut queue = TaskQueue::new();
+        for _ in 0..HISTORY + 5 {
+            queue.push("Install", "job");
+            queue.finish(Ok(String::new()));
+        }
+        queue.push("Sync", "sync");
+        assert_eq!(queue.tasks().len(), HISTORY + 1);
+        assert_eq!(queue.tasks()[0].id, 6);
+        assert_eq!(queue.running().unwrap().label, "Sync");
+    }
+}
@@ -0,0 +1,211 @@
+//! Template variables that a definition declares in its frontmatter and that
+//! get substituted on install.
+//!
+//! ```markdown
+//! ---
+//! name: Project Reviewer
+//! vars: [PROJECT_NAME, LANGUAGE]
+//! ---
+//! Review changes to {{ PROJECT_NAME }}, written in {{LANGUAGE}}.
+//! ```
+//!
+//! `vars` may also be a mapping from name to default value. Placeholders
+//! that don't name a declared variable are left alone, so unrelated `{{ }}`
+//! syntax in a definition survives installation.
+
+use std::collections::HashMap;
+
+use serde_yaml_ng::Value;
+
+/// A variable declared under `vars:` in a definition's frontmatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVar {
+    pub name: String,
+    /// Value used when none is supplied.
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TemplateError {
+    #[error("missing values for template variables: {}", .0.join(", "))]
+    Missing(Vec<String>),
+}
+
+/// Variables declared in the frontmatter of `raw`, in declaration order.
+/// Returns an empty list when there is no frontmatter or no `vars` key.
+pub fn declared_vars(raw: &str) -> Vec<TemplateVar> {
+    let Ok(parsed) = crate::parse_frontmatter(raw) else {
+        return Vec::new();
+    };
+    let Some(vars) = parsed.frontmatter.as_ref().and_then(|fm| fm.extras.get("vars")) else {
+        return Vec::new();
+    };
+
+    match vars {
+        Value::Sequence(items) => items
+            .iter()
+            .filter_map(scalar)
+            .map(|name| TemplateVar { name, default: None })
+            .collect(),
+        Value::Mapping(entries) => entries
+            .iter()
+            .filter_map(|(name, default)| {
+                Some(TemplateVar {
+                    name: scalar(name)?,
+                    default: scalar(default),
+                })
+            })
+            .collect(),
+        other => scalar(other)
+            .map(|name| vec![TemplateVar { name, default: None }])
+            .unwrap_or_default(),
+    }
+    .into_iter()
+    .filter(|var| !var.name.is_empty())
+    .collect()
+}
+
+/// Substitute every declared variable in `raw`, taking values from `values`
+/// and falling back to each variable's default.
+///
+/// Fails with the names of all variables that have neither. Definitions that
+/// declare no variables come back unchanged.
+pub fn render(raw: &str, values: &HashMap<String, String>) -> Result<String, TemplateError> {
+    let declared = declared_vars(raw);
+    if declared.is_empty() {
+        return Ok(raw.to_owned());
+    }
+
+    let mut resolved = HashMap::new();
+    let mut missing = Vec::new();
+    for var in &declared {
+        match values.get(&var.name).or(var.default.as_ref()) {
+            Some(value) => {
+                resolved.insert(var.name.as_str(), value.as_str());
+            }
+            None => missing.push(var.name.clone()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(TemplateError::Missing(missing));
+    }
+
+    Ok(substitute(raw, &resolved))
+}
+
+/// Replace `{{ NAME }}` placeholders (inner whitespace optional) whose name
+/// is in `values`, leaving everything else as written.
+fn substitute(text: &str, values: &HashMap<&str, &str>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find("{{") {
+        let after_open = &rest[open + 2..];
+        let Some(close) = after_open.find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        match values.get(after_open[..close].trim()) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[open..open + 2 + close + 2]),
+        }
+        rest = &after_open[close + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_owned()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect()
+    }
+
+    const TEMPLATE: &str = "\
+---
+name: Reviewer
+vars: [PROJECT_NAME, LANGUAGE]
+---
+Review {{ PROJECT_NAME }} ({{LANGUAGE}}).
+";
+
+    #[test]
+    fn declares_vars_from_a_list() {
+        let names: Vec<_> = declared_vars(TEMPLATE).into_iter().map(|v| v.name).collect();
+        assert_eq!(names, ["PROJECT_NAME", "LANGUAGE"]);
+    }
+
+    #[test]
+    fn declares_vars_with_defaults_from_a_mapping() {
+        let raw = "---\nvars:\n  PROJECT_NAME:\n  LANGUAGE: Rust\n---\nbody";
+        assert_eq!(
+            declared_vars(raw),
+            [
+                TemplateVar { name: "PROJECT_NAME".into(), default: None },
+                TemplateVar { name: "LANGUAGE".into(), default: Some("Rust".into()) },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_frontmatter_declares_nothing() {
+        assert!(declared_vars("Just {{ TEXT }}").is_empty());
+        assert!(declared_vars("---\nname: x\n---\nbody").is_empty());
+    }
+
+    #[test]
+    fn render_substitutes_declared_vars() {
+        let out = render(TEMPLATE, &values(&[("PROJECT_NAME", "crate"), ("LANGUAGE", "Rust")]))
+            .unwrap();
+        assert!(out.ends_with("Review crate (Rust).\n"));
+        assert!(out.contains("vars: [PROJECT_NAME, LANGUAGE]"));
+    }
+
+    #[test]
+    fn render_reports_every_missing_var() {
+        assert_eq!(
+            render(TEMPLATE, &HashMap::new()),
+            Err(TemplateError::Missing(vec!["PROJECT_NAME".into(), "LANGUAGE".into()]))
+        );
+    }
+
+    #[test]
+    fn render_falls_back_to_defaults() {
+        let raw = "---\nvars:\n  LANGUAGE: Rust\n---\nIn {{LANGUAGE}}.";
+        assert_eq!(render(raw, &HashMap::new()).unwrap(), raw.replace("{{LANGUAGE}}", "Rust"));
+        assert_eq!(
+            render(raw, &values(&[("LANGUAGE", "Go")])).unwrap(),
+            raw.replace("{{LANGUAGE}}", "Go")
+        );
+    }
+
+    #[test]
+    fn render_leaves_undeclared_placeholders_alone() {
+        let raw = "---\nvars: [A]\n---\n{{A}} {{ B }} {{ unclosed";
+        assert_eq!(
+            render(raw, &values(&[("A", "a")])).unwrap(),
+            "---\nvars: [A]\n---\na {{ B }} {{ unclosed"
+        );
+    }
+
+    #[test]
+    fn render_without_vars_is_verbatim() {
+        let raw = "---\nname: x\n---\n{{ A }}";
+        assert_eq!(render(raw, &HashMap::new()).unwrap(), raw);
+    }
+}
@@ -1,11 +1,59 @@
-use std::collections::HashMap;
+//! Fakes for tests in this crate and the ones built on it: an in-memory
+//! [`Source`] and a [`SyncProvider`] that serve canned definitions, and can
+//! be scripted to answer slowly, fail, or never answer at all. Enabled by
+//! the `test-support` feature.
 
-use crate::{Definition, DefinitionId, DefinitionSummary, Source, SourceError};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::demo::sleep;
+use crate::{
+    Definition, DefinitionId, DefinitionSummary, RawDefinitionFile, Source, SourceError,
+    SyncError, SyncProvider,
+};
+
+/// A definition for `id`, with the name, kind, and category its path
+/// implies, a one-line body, and raw markdown to install. Change what a test
+/// cares about with struct update syntax.
+pub fn definition(id: &str) -> Definition {
+    let (name, kind, category) = crate::path::parse_relative_path(id);
+    let body = format!("You are {name}.\n");
+    Definition {
+        id: DefinitionId::new(id),
+        raw: format!("---\nname: {name}\n---\n{body}"),
+        name,
+        description: None,
+        kind,
+        category,
+        source_label: "test".to_owned(),
+        body,
+        tools: vec![],
+        model: None,
+        metadata: HashMap::new(),
+    }
+}
+
+/// A markdown definition file at `relative_path` with a name and description
+/// in its frontmatter.
+pub fn markdown_file(relative_path: &str, name: &str, description: &str) -> RawDefinitionFile {
+    RawDefinitionFile {
+        relative_path: relative_path.to_owned(),
+        content: format!("---\nname: {name}\ndescription: {description}\n---\nYou are {name}.\n"),
+    }
+}
 
 /// In-memory source for testing. Stores full definitions and derives summaries.
 pub struct InMemorySource {
     label: String,
     definitions: HashMap<DefinitionId, Definition>,
+    latency: Option<Duration>,
+    list_error: Option<SourceError>,
+    fetch_errors: HashMap<DefinitionId, SourceError>,
+    hang_fetches: bool,
+    fetches: AtomicUsize,
+    in_flight: Arc<AtomicUsize>,
 }
 
 impl InMemorySource {
@@ -13,12 +61,73 @@ impl InMemorySource {
         Self {
             label: label.into(),
             definitions: HashMap::new(),
+            latency: None,
+            list_error: None,
+            fetch_errors: HashMap::new(),
+            hang_fetches: false,
+            fetches: AtomicUsize::new(0),
+            in_flight: Arc::new(AtomicUsize::new(0)),
         }
     }
 
     pub fn add(&mut self, definition: Definition) {
         self.definitions.insert(definition.id.clone(), definition);
     }
+
+    /// Serve `definition` too, taking this source's label.
+    pub fn with(mut self, definition: Definition) -> Self {
+        self.add(Definition {
+            source_label: self.label.clone(),
+            ..definition
+        });
+        self
+    }
+
+    /// Serve a [`definition`] for each of `ids`.
+    pub fn with_ids<'a>(self, ids: impl IntoIterator<Item = &'a str>) -> Self {
+        ids.into_iter().fold(self, |source, id| source.with(definition(id)))
+    }
+
+    /// Wait `latency` before answering each call.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Fail every `list` (and so every default `search`) with `error`.
+    pub fn failing_list(mut self, error: SourceError) -> Self {
+        self.list_error = Some(error);
+        self
+    }
+
+    /// Fail every fetch of `id` with `error`.
+    pub fn failing_fetch(mut self, id: &str, error: SourceError) -> Self {
+        self.fetch_errors.insert(DefinitionId::new(id), error);
+        self
+    }
+
+    /// Never finish a fetch, as a source that stopped answering would.
+    /// [`in_flight`](Self::in_flight) shows when a caller gives up on one.
+    pub fn hanging_fetches(mut self) -> Self {
+        self.hang_fetches = true;
+        self
+    }
+
+    /// How many fetches have been started.
+    pub fn fetches(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+
+    /// How many fetches have started and neither finished nor been dropped.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    async fn wait(&self) {
+        if let Some(latency) = self.latency {
+            sleep(latency).await;
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -28,10 +137,23 @@ impl Source for InMemorySource {
     }
 
     async fn list(&self) -> Result<Vec<DefinitionSummary>, SourceError> {
+        self.wait().await;
+        if let Some(error) = &self.list_error {
+            return Err(error.clone());
+        }
         Ok(self.definitions.values().map(|d| d.summary()).collect())
     }
 
     async fn fetch(&self, id: &DefinitionId) -> Result<Definition, SourceError> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        let _in_flight = InFlight::start(&self.in_flight);
+        self.wait().await;
+        if self.hang_fetches {
+            std::future::pending::<()>().await;
+        }
+        if let Some(error) = self.fetch_errors.get(id) {
+            return Err(error.clone());
+        }
         self.definitions
             .get(id)
             .cloned()
@@ -39,6 +161,106 @@ impl Source for InMemorySource {
     }
 }
 
+/// A sync provider for testing that hands out a fixed set of files.
+pub struct FakeSyncProvider {
+    label: String,
+    files: Vec<RawDefinitionFile>,
+    latency: Option<Duration>,
+    failures: Mutex<VecDeque<SyncError>>,
+    calls: AtomicUsize,
+    revision: Option<String>,
+}
+
+impl FakeSyncProvider {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            files: Vec::new(),
+            latency: None,
+            failures: Mutex::new(VecDeque::new()),
+            calls: AtomicUsize::new(0),
+            revision: None,
+        }
+    }
+
+    /// Hand out a file at `relative_path` holding `content`.
+    pub fn with_file(mut self, relative_path: &str, content: &str) -> Self {
+        self.files.push(RawDefinitionFile {
+            relative_path: relative_path.to_owned(),
+            content: content.to_owned(),
+        });
+        self
+    }
+
+    pub fn with_files(mut self, files: impl IntoIterator<Item = RawDefinitionFile>) -> Self {
+        self.files.extend(files);
+        self
+    }
+
+    /// Report `revision` as the revision each sync got.
+    pub fn with_revision(mut self, revision: &str) -> Self {
+        self.revision = Some(revision.to_owned());
+        self
+    }
+
+    /// Wait `latency` before answering each sync.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Fail the next sync that hasn't already been scripted to fail with
+    /// `error`. Syncs after the scripted failures succeed.
+    pub fn failing_next(self, error: SyncError) -> Self {
+        self.failures.lock().unwrap().push_back(error);
+        self
+    }
+
+    /// How many times `fetch_all` has been called.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl SyncProvider for FakeSyncProvider {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    async fn fetch_all(&self) -> Result<Vec<RawDefinitionFile>, SyncError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(latency) = self.latency {
+            sleep(latency).await;
+        }
+        let failure = self.failures.lock().unwrap().pop_front();
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(self.files.clone()),
+        }
+    }
+
+    fn revision(&self) -> Option<String> {
+        self.revision.clone()
+    }
+}
+
+/// Counts a fetch as in flight until it finishes or is dropped.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn start(count: &Arc<AtomicUsize>) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(count))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::DefinitionKind;
@@ -119,6 +341,53 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[tokio::test]
+    async fn scripted_latency_and_failures() {
+        let source = InMemorySource::new("test")
+            .with_ids(["agents/a.md", "agents/b.md"])
+            .with_latency(Duration::from_millis(20))
+            .failing_fetch("agents/b.md", SourceError::Timeout("slow".into()));
+
+        let started = std::time::Instant::now();
+        let def = source.fetch(&DefinitionId::new("agents/a.md")).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!((def.name.as_str(), def.source_label.as_str()), ("a", "test"));
+        assert!(matches!(
+            source.fetch(&DefinitionId::new("agents/b.md")).await,
+            Err(SourceError::Timeout(_))
+        ));
+        assert_eq!(source.fetches(), 2);
+
+        let failing = InMemorySource::new("test").failing_list(SourceError::Network("down".into()));
+        assert!(failing.search("a").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn hanging_fetches_stay_in_flight_until_dropped() {
+        let source = Arc::new(InMemorySource::new("test").hanging_fetches());
+        let task = {
+            let source = Arc::clone(&source);
+            tokio::spawn(async move { source.fetch(&DefinitionId::new("agents/a.md")).await })
+        };
+        while source.in_flight() == 0 {
+            tokio::task::yield_now().await;
+        }
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert_eq!(source.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn sync_provider_fails_as_scripted() {
+        let provider = FakeSyncProvider::new("test")
+            .with_files([markdown_file("agents/a.md", "A", "First")])
+            .failing_next(SyncError::Timeout("first try".into()));
+
+        assert!(matches!(provider.fetch_all().await, Err(SyncError::Timeout(_))));
+        assert_eq!(provider.fetch_all().await.unwrap().len(), 1);
+        assert_eq!(provider.calls(), 2);
+    }
+
     #[tokio::test]
     async fn default_search_returns_empty_for_no_match() {
         let mut source = InMemorySource::new("test");
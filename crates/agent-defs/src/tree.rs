@@ -0,0 +1,295 @@
+//! A collapsible kind → category → definition tree over summaries, for
+//! front ends whose sources have too many categories for a flat list.
+
+use std::collections::BTreeSet;
+
+use crate::grouping::{build_groups, kind_label};
+use crate::{DefinitionKind, DefinitionSummary};
+
+/// A collapsible node in the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeNode {
+    Kind(DefinitionKind),
+    Category(DefinitionKind, String),
+}
+
+impl TreeNode {
+    /// Stable key for remembering this node's expansion: the kind, or
+    /// `kind/category`.
+    pub fn key(&self) -> String {
+        match self {
+            Self::Kind(kind) => kind.to_string(),
+            Self::Category(kind, category) => format!("{kind}/{category}"),
+        }
+    }
+}
+
+/// A row in the flattened tree.
+#[derive(Debug, Clone)]
+pub enum TreeRow {
+    Node {
+        node: TreeNode,
+        label: String,
+        /// Definitions anywhere under the node.
+        count: usize,
+        expanded: bool,
+        depth: usize,
+    },
+    Item {
+        summary_index: usize,
+        depth: usize,
+    },
+}
+
+impl TreeRow {
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Node { depth, .. } | Self::Item { depth, .. } => *depth,
+        }
+    }
+}
+
+/// Which nodes are open. Kinds start expanded and categories collapsed, so
+/// a source with hundreds of categories opens as one line per category.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeExpansion {
+    collapsed_kinds: BTreeSet<String>,
+    expanded_categories: BTreeSet<String>,
+}
+
+impl TreeExpansion {
+    pub fn is_expanded(&self, node: &TreeNode) -> bool {
+        match node {
+            TreeNode::Kind(_) => !self.collapsed_kinds.contains(&node.key()),
+            TreeNode::Category(..) => self.expanded_categories.contains(&node.key()),
+        }
+    }
+
+    pub fn set_expanded(&mut self, node: &TreeNode, expanded: bool) {
+        let key = node.key();
+        match node {
+            TreeNode::Kind(_) if expanded => self.collapsed_kinds.remove(&key),
+            TreeNode::Kind(_) => self.collapsed_kinds.insert(key),
+            TreeNode::Category(..) if expanded => self.expanded_categories.insert(key),
+            TreeNode::Category(..) => self.expanded_categories.remove(&key),
+        };
+    }
+
+    pub fn toggle(&mut self, node: &TreeNode) {
+        self.set_expanded(node, !self.is_expanded(node));
+    }
+
+    /// One line per node that differs from its default: `-kind` for a
+    /// collapsed kind, `+kind/category` for an expanded category.
+    pub fn to_lines(&self) -> String {
+        let collapsed = self.collapsed_kinds.iter().map(|key| format!("-{key}\n"));
+        let expanded = self
+            .expanded_categories
+            .iter()
+            .map(|key| format!("+{key}\n"));
+        collapsed.chain(expanded).collect()
+    }
+
+    /// Read what [`TreeExpansion::to_lines`] wrote, skipping lines it
+    /// doesn't recognise.
+    pub fn from_lines(text: &str) -> Self {
+        let mut expansion = Self::default();
+        for line in text.lines().map(str::trim) {
+            if let Some(key) = line.strip_prefix('-') {
+                expansion.collapsed_kinds.insert(key.to_owned());
+            } else if let Some(key) = line.strip_prefix('+') {
+                expansion.expanded_categories.insert(key.to_owned());
+            }
+        }
+        expansion
+    }
+}
+
+/// Flatten `summaries` into tree rows, leaving out whatever sits under a
+/// collapsed node. Definitions without a category sit directly under their
+/// kind, after its categories. With `expand_all` every node is open, as
+/// when a search has narrowed the list and every match should show.
+pub fn build_tree(
+    summaries: &[DefinitionSummary],
+    expansion: &TreeExpansion,
+    expand_all: bool,
+) -> Vec<TreeRow> {
+    let (groups, _) = build_groups(summaries);
+    let is_expanded = |node: &TreeNode| expand_all || expansion.is_expanded(node);
+    let mut rows = Vec::new();
+
+    for group in groups {
+        let node = TreeNode::Kind(group.kind.clone());
+        let expanded = is_expanded(&node);
+        rows.push(TreeRow::Node {
+            node,
+            label: kind_label(&group.kind).to_owned(),
+            count: group.count,
+            expanded,
+            depth: 0,
+        });
+        if !expanded {
+            continue;
+        }
+
+        let mut categories: Vec<(&str, Vec<usize>)> = Vec::new();
+        let mut uncategorized = Vec::new();
+        for &idx in &group.summary_indices {
+            match summaries[idx].category.as_deref() {
+                Some(category) => match categories.iter_mut().find(|(c, _)| *c == category) {
+                    Some((_, indices)) => indices.push(idx),
+                    None => categories.push((category, vec![idx])),
+                },
+                None => uncategorized.push(idx),
+            }
+        }
+        categories.sort_by_key(|(category, _)| category.to_lowercase());
+
+        for (category, indices) in categories {
+            let node = TreeNode::Category(group.kind.clone(), category.to_owned());
+            let expanded = is_expanded(&node);
+            rows.push(TreeRow::Node {
+                node,
+                label: category.to_owned(),
+                count: indices.len(),
+                expanded,
+                depth: 1,
+            });
+            if expanded {
+                rows.extend(indices.into_iter().map(|summary_index| TreeRow::Item {
+                    summary_index,
+                    depth: 2,
+                }));
+            }
+        }
+        rows.extend(
+            uncategorized
+                .into_iter()
+                .map(|summary_index| TreeRow::Item {
+                    summary_index,
+                    depth: 1,
+                }),
+        );
+    }
+
+    rows
+}
+
+/// The row of the node that contains `row`, if it isn't a top-level node.
+pub fn parent_index(rows: &[TreeRow], row: usize) -> Option<usize> {
+    let depth = rows.get(row)?.depth();
+    rows[..row].iter().rposition(|r| r.depth() < depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefinitionId;
+
+    fn summary(name: &str, kind: DefinitionKind, category: Option<&str>) -> DefinitionSummary {
+        DefinitionSummary {
+            id: DefinitionId::new(name),
+            name: name.to_owned(),
+            description: None,
+            kind,
+            category: category.map(str::to_owned),
+            source_label: "test".into(),
+            first_seen_at: None,
+            last_updated_at: None,
+        }
+    }
+
+    fn outline(rows: &[TreeRow], summaries: &[DefinitionSummary]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row {
+                TreeRow::Node {
+                    label,
+                    count,
+                    expanded,
+                    depth,
+                    ..
+                } => {
+                    let marker = if *expanded { "v" } else { ">" };
+                    format!("{}{marker} {label} ({count})", "  ".repeat(*depth))
+                }
+                TreeRow::Item {
+                    summary_index,
+                    depth,
+                } => format!("{}{}", "  ".repeat(*depth), summaries[*summary_index].name),
+            })
+            .collect()
+    }
+
+    fn summaries() -> Vec<DefinitionSummary> {
+        vec![
+            summary("reviewer", DefinitionKind::Agent, Some("review")),
+            summary("linter", DefinitionKind::Agent, Some("Quality")),
+            summary("auditor", DefinitionKind::Agent, Some("review")),
+            summary("helper", DefinitionKind::Agent, None),
+            summary("deploy", DefinitionKind::Command, None),
+        ]
+    }
+
+    #[test]
+    fn categories_start_collapsed_under_expanded_kinds() {
+        let summaries = summaries();
+        let rows = build_tree(&summaries, &TreeExpansion::default(), false);
+        assert_eq!(
+            outline(&rows, &summaries),
+            [
+                "v Agents (4)",
+                "  > Quality (1)",
+                "  > review (2)",
+                "  helper",
+                "v Commands (1)",
+                "  deploy",
+            ]
+        );
+    }
+
+    #[test]
+    fn toggled_nodes_open_and_close() {
+        let summaries = summaries();
+        let mut expansion = TreeExpansion::default();
+        expansion.toggle(&TreeNode::Category(DefinitionKind::Agent, "review".into()));
+        expansion.toggle(&TreeNode::Kind(DefinitionKind::Command));
+
+        let rows = build_tree(&summaries, &expansion, false);
+        assert_eq!(
+            outline(&rows, &summaries),
+            [
+                "v Agents (4)",
+                "  > Quality (1)",
+                "  v review (2)",
+                "    reviewer",
+                "    auditor",
+                "  helper",
+                "> Commands (1)",
+            ]
+        );
+        assert_eq!(parent_index(&rows, 3), Some(2));
+        assert_eq!(parent_index(&rows, 2), Some(0));
+        assert_eq!(parent_index(&rows, 0), None);
+
+        let rows = build_tree(&summaries, &expansion, true);
+        assert_eq!(rows.len(), 9);
+    }
+
+    #[test]
+    fn expansion_round_trips_through_lines() {
+        let mut expansion = TreeExpansion::default();
+        expansion.set_expanded(&TreeNode::Kind(DefinitionKind::Hook), false);
+        expansion.set_expanded(
+            &TreeNode::Category(DefinitionKind::Agent, "review".into()),
+            true,
+        );
+
+        let lines = expansion.to_lines();
+        assert_eq!(lines, "-hook\n+agent/review\n");
+        assert_eq!(TreeExpansion::from_lines(&lines), expansion);
+        assert_eq!(
+            TreeExpansion::from_lines("junk\n"),
+            TreeExpansion::default()
+        );
+    }
+}
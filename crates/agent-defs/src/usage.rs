@@ -0,0 +1,75 @@
+//! Local, per-definition counts of how often definitions are viewed and
+//! installed. Nothing here leaves the machine; it only feeds `stats
+//! --personal` and the TUI's "Most used" list.
+
+use crate::{DefinitionId, DefinitionSummary};
+
+/// Something the user did with a definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsageKind {
+    View,
+    Install,
+}
+
+/// How much one definition has been used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionUsage {
+    pub id: DefinitionId,
+    pub name: String,
+    pub source_label: String,
+    pub views: u64,
+    pub installs: u64,
+    /// When it was last viewed or installed, in seconds since the Unix epoch.
+    pub last_used: u64,
+}
+
+impl DefinitionUsage {
+    /// Views and installs together. Installs are the stronger signal, so
+    /// each counts as much as several views.
+    pub fn score(&self) -> u64 {
+        self.views + self.installs * 3
+    }
+
+    pub fn matches(&self, summary: &DefinitionSummary) -> bool {
+        self.id == summary.id && self.source_label == summary.source_label
+    }
+}
+
+/// Sort most used first, breaking ties by the most recently used.
+pub fn sort_most_used(usage: &mut [DefinitionUsage]) {
+    usage.sort_by(|a, b| {
+        b.score()
+            .cmp(&a.score())
+            .then_with(|| b.last_used.cmp(&a.last_used))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(id: &str, views: u64, installs: u64, last_used: u64) -> DefinitionUsage {
+        DefinitionUsage {
+            id: DefinitionId::new(id),
+            name: id.to_owned(),
+            source_label: "acme".into(),
+            views,
+            installs,
+            last_used,
+        }
+    }
+
+    #[test]
+    fn installs_outweigh_views() {
+        let mut all = vec![usage("viewed", 2, 0, 10), usage("installed", 0, 1, 5)];
+        sort_most_used(&mut all);
+        assert_eq!(all[0].id.as_str(), "installed");
+    }
+
+    #[test]
+    fn ties_go_to_most_recent() {
+        let mut all = vec![usage("old", 1, 0, 10), usage("new", 1, 0, 20)];
+        sort_most_used(&mut all);
+        assert_eq!(all[0].id.as_str(), "new");
+    }
+}
@@ -0,0 +1,133 @@
+//! Property tests for the parsers that see whatever a synced repo holds:
+//! frontmatter, definition paths, and the definition builder. Whatever the
+//! input, they must not panic, and should fall back to something usable.
+
+use agent_defs::frontmatter::{self, normalize};
+use agent_defs::{DefinitionId, builder, path};
+use proptest::prelude::*;
+
+/// Text that looks enough like a definition file to reach the interesting
+/// branches: frontmatter delimiters, YAML punctuation, and odd line endings.
+fn document() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        Just("---".to_owned()),
+        Just("\n".to_owned()),
+        Just("\r\n".to_owned()),
+        Just("\t".to_owned()),
+        Just("\u{feff}".to_owned()),
+        Just("name: ".to_owned()),
+        Just("tools: [Read, ".to_owned()),
+        Just("- ".to_owned()),
+        Just(": ".to_owned()),
+        Just("? ".to_owned()),
+        Just("1: ".to_owned()),
+        Just("!tag ".to_owned()),
+        Just("&a *a ".to_owned()),
+        "[a-z{}\\[\\]\"'|>&*!%@#, ]{0,12}",
+        any::<String>(),
+    ];
+    prop::collection::vec(piece, 0..16).prop_map(|pieces| pieces.concat())
+}
+
+/// A single-line value that YAML must quote or escape correctly.
+fn field() -> impl Strategy<Value = String> {
+    "[^\r\n\u{85}\u{2028}\u{2029}\u{feff}]{1,30}"
+        .prop_filter("YAML trims surrounding whitespace", |s| s.trim() == s)
+}
+
+fn definition_path() -> impl Strategy<Value = String> {
+    let segment = prop_oneof![
+        Just("agents".to_owned()),
+        Just("skills".to_owned()),
+        Just("SKILL.md".to_owned()),
+        Just(".md".to_owned()),
+        Just(String::new()),
+        "[a-zA-Z0-9 ._\\\\-]{1,10}",
+        any::<String>(),
+    ];
+    prop::collection::vec(segment, 0..6).prop_map(|segments| segments.join("/"))
+}
+
+proptest! {
+    #[test]
+    fn frontmatter_parse_never_panics(content in document()) {
+        let normalized = normalize(&content).into_owned();
+        if let Ok(doc) = frontmatter::parse(&content) {
+            prop_assert!(!doc.body.contains('\r'));
+            if doc.frontmatter.is_none() {
+                prop_assert_eq!(doc.body, normalized);
+            }
+        }
+    }
+
+    #[test]
+    fn written_frontmatter_reads_back(
+        name in field(),
+        description in field(),
+        tools in prop::collection::vec("[A-Za-z][A-Za-z0-9_]{0,10}", 0..5),
+        body in "[^\r]*",
+    ) {
+        let mut yaml = serde_yaml_ng::Mapping::new();
+        yaml.insert("name".into(), name.clone().into());
+        yaml.insert("description".into(), description.clone().into());
+        if !tools.is_empty() {
+            let list = tools.iter().cloned().map(serde_yaml_ng::Value::from).collect();
+            yaml.insert("tools".into(), serde_yaml_ng::Value::Sequence(list));
+        }
+        let yaml = serde_yaml_ng::to_string(&yaml).unwrap();
+        let content = format!("---\n{yaml}---\n{body}");
+
+        let doc = frontmatter::parse(&content).unwrap();
+        let fm = doc.frontmatter.unwrap();
+        prop_assert_eq!(fm.tool_list(), tools);
+        prop_assert_eq!(fm.name, Some(name));
+        prop_assert_eq!(fm.description, Some(description));
+        prop_assert_eq!(doc.body, body);
+    }
+
+    #[test]
+    fn paths_always_give_a_usable_name(relative_path in definition_path()) {
+        let (name, _, category) = path::parse_relative_path(&relative_path);
+        prop_assert!(!name.is_empty());
+        prop_assert!(!name.contains('/'));
+        if let Some(category) = category {
+            prop_assert!(!category.is_empty() && !category.contains('/'));
+        }
+
+        let (name, _, category) = path::parse_skill_path(&relative_path);
+        prop_assert!(!name.is_empty());
+        prop_assert!(category.is_none_or(|c| !c.is_empty()));
+
+        let backslashed = relative_path.replace('/', "\\");
+        prop_assert_eq!(
+            path::parse_relative_path(&backslashed),
+            path::parse_relative_path(&relative_path)
+        );
+    }
+
+    #[test]
+    fn build_definition_never_panics(
+        relative_path in definition_path(),
+        content in document(),
+        json in any::<bool>(),
+    ) {
+        let relative_path = if json {
+            format!("{relative_path}.json")
+        } else {
+            format!("{relative_path}.md")
+        };
+        let (name, kind, category) = path::parse_relative_path(&relative_path);
+        let id = DefinitionId::new(&relative_path);
+        let result = builder::build_definition(
+            &id, &content, &relative_path, name.clone(), kind, category, "prop",
+        );
+
+        // Markdown without frontmatter always builds, named for its path.
+        let has_frontmatter = normalize(&content).trim_start().starts_with("---");
+        if !json && !has_frontmatter {
+            let def = result.unwrap();
+            prop_assert_eq!(def.name, name);
+            prop_assert_eq!(def.body, normalize(&content).into_owned());
+        }
+    }
+}
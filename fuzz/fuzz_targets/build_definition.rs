@@ -0,0 +1,12 @@
+#![no_main]
+
+use agent_defs::{DefinitionId, builder, path};
+use libfuzzer_sys::fuzz_target;
+
+// The first line is the path, the rest the file's content.
+fuzz_target!(|input: &str| {
+    let (relative_path, content) = input.split_once('\n').unwrap_or((input, ""));
+    let (name, kind, category) = path::parse_relative_path(relative_path);
+    let id = DefinitionId::new(relative_path);
+    let _ = builder::build_definition(&id, content, relative_path, name, kind, category, "fuzz");
+});
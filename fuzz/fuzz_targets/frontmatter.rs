@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|content: &str| {
+    if let Ok(doc) = agent_defs::parse_frontmatter(content) {
+        assert!(!doc.body.contains('\r'));
+        if let Some(frontmatter) = doc.frontmatter {
+            let _ = frontmatter.tool_list();
+            let _ = frontmatter.extras_as_strings();
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use agent_defs::path;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|relative_path: &str| {
+    let (name, _, category) = path::parse_relative_path(relative_path);
+    assert!(!name.is_empty() && !name.contains('/'));
+    assert!(category.is_none_or(|c| !c.is_empty() && !c.contains('/')));
+
+    let (name, _, _) = path::parse_skill_path(relative_path);
+    assert!(!name.is_empty());
+    let _ = path::is_definition_file(relative_path);
+    let _ = path::split_branch(relative_path);
+});